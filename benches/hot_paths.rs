@@ -0,0 +1,143 @@
+//! Benchmarks of the hot paths of the interpreter core, to evaluate changes
+//! aimed at performance against the baseline in the README.
+//!
+//! Requires the `testing` feature: `cargo bench --features testing`
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use keet_8::{Emulator, OpCode};
+
+// --- constants --------------------------------------------------------------
+
+/// The ROM stepped through by the throughput benchmark
+const STEP_ROM: &str = "tests/3-corax+.ch8";
+/// The number of instructions executed per iteration of the throughput
+/// benchmark
+const NUM_STEPS: usize = 1000;
+
+/// The ROM of the benchmarks which execute instructions directly, jumping
+/// to itself
+const IDLE_ROM: &[u8] = &[0x12, 0x00];
+
+/// The address of the font sprite of `0`
+const FONT_ADDR: u16 = 0x0050;
+
+/// The color of the pixels which are on, as RGBA
+const FOREGROUND: u32 = 0x00FF_55FF;
+/// The color of the pixels which are off, as RGBA
+const BACKGROUND: u32 = 0x0000_00FF;
+
+// --- utility functions ------------------------------------------------------
+
+/// Creates an emulator from the bytes of a ROM
+///
+/// # Params
+///
+/// - `rom` - The bytes of the ROM
+fn emulator(rom: &[u8]) -> Emulator {
+    Emulator::from_rom(rom).unwrap_or_else(|e| panic!("{e}"))
+}
+
+// --- benchmarks -------------------------------------------------------------
+
+/// Decodes every possible raw opcode and the raw opcodes of a test ROM,
+/// both with `OpCode::from` and by looking them up in a table of every raw
+/// opcode decoded beforehand, as a decode cache would
+fn decode(c: &mut Criterion) {
+    let table: Vec<OpCode> = (0..=u16::MAX).map(OpCode::from).collect();
+    let rom = std::fs::read(STEP_ROM).unwrap_or_else(|e| panic!("{STEP_ROM}: {e}"));
+    let raws: Vec<u16> =
+        rom.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+
+    c.bench_function("decode all opcodes", |b| {
+        b.iter(|| {
+            for raw in 0..=u16::MAX {
+                black_box(OpCode::from(black_box(raw)));
+            }
+        })
+    });
+
+    c.bench_function("decode all opcodes (table)", |b| {
+        b.iter(|| {
+            for raw in 0..=u16::MAX {
+                black_box(table[black_box(raw) as usize]);
+            }
+        })
+    });
+
+    c.bench_function("decode rom opcodes", |b| {
+        b.iter(|| {
+            for &raw in &raws {
+                black_box(OpCode::from(black_box(raw)));
+            }
+        })
+    });
+
+    c.bench_function("decode rom opcodes (table)", |b| {
+        b.iter(|| {
+            for &raw in &raws {
+                black_box(table[black_box(raw) as usize]);
+            }
+        })
+    });
+}
+
+/// Draws regular and SCHIP sprites
+fn drw(c: &mut Criterion) {
+    let mut emu = emulator(IDLE_ROM);
+    emu.set_index(FONT_ADDR);
+
+    c.bench_function("drw 8x5", |b| {
+        b.iter(|| emu.execute(black_box(OpCode::from(0xD015))).is_ok())
+    });
+
+    let mut emu = emulator(IDLE_ROM);
+    emu.set_index(FONT_ADDR);
+    let _ = emu.execute(OpCode::from(0x00FF));
+
+    c.bench_function("drw 16x16 (hires)", |b| {
+        b.iter(|| emu.execute(black_box(OpCode::from(0xD010))).is_ok())
+    });
+}
+
+/// Steps through a test ROM from its start
+fn step(c: &mut Criterion) {
+    let rom = std::fs::read(STEP_ROM).unwrap_or_else(|e| panic!("{STEP_ROM}: {e}"));
+
+    c.bench_function("step 1000 instructions", |b| {
+        b.iter_batched_ref(
+            || emulator(&rom),
+            |emu| {
+                for _ in 0..NUM_STEPS {
+                    let _ = emu.step();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// Converts the framebuffer to RGBA pixels, as uploading it to a texture
+/// would
+fn framebuffer(c: &mut Criterion) {
+    let mut emu = emulator(IDLE_ROM);
+    emu.set_index(FONT_ADDR);
+    let _ = emu.execute(OpCode::from(0x00FF));
+    let _ = emu.execute(OpCode::from(0xD015));
+
+    let mut pixels = Vec::with_capacity(emu.framebuffer().len());
+
+    c.bench_function("framebuffer to rgba (hires)", |b| {
+        b.iter(|| {
+            pixels.clear();
+            pixels.extend(
+                emu.framebuffer()
+                    .iter()
+                    .map(|&pixel| if pixel > 0 { FOREGROUND } else { BACKGROUND }),
+            );
+            black_box(&pixels);
+        })
+    });
+}
+
+criterion_group!(benches, decode, drw, step, framebuffer);
+criterion_main!(benches);
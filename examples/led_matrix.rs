@@ -0,0 +1,80 @@
+//! A backend driving a 64×32 LED matrix, such as a HUB75 panel behind a
+//! microcontroller, through a `Display` writing the frames to a byte stream.
+//!
+//! On real hardware the stream would be the SPI bus (or a serial port to the
+//! microcontroller). Here it is stdout, so the frames can be piped into a
+//! device or inspected with `xxd`:
+//!
+//! `cargo run --example led_matrix --no-default-features -- path/to/rom | xxd`
+
+use keet_8::{Display, Emulator, FRAME_ROWS};
+
+use std::io::Write;
+
+// --- constants --------------------------------------------------------------
+
+/// The byte starting every frame, letting the receiver resynchronize
+const FRAME_SYNC: u8 = 0xC8;
+
+/// The number of instructions executed per frame
+const STEPS_PER_FRAME: usize = 10;
+/// The number of frames run before exiting
+const NUM_FRAMES: usize = 600;
+
+// --- led matrix definition --------------------------------------------------
+
+/// A 64×32 LED matrix receiving its frames over a byte stream
+///
+/// Every frame is the sync byte followed by the 32 rows from top to bottom,
+/// each as 8 bytes in big-endian order, so the leftmost pixel is the first
+/// bit shifted out.
+struct LedMatrix<W: Write> {
+    /// The byte stream the matrix is connected to
+    bus: W,
+}
+
+impl<W: Write + Send> Display for LedMatrix<W> {
+    /// Writes the frame to the bus
+    fn on_frame(&mut self, rows: &[u64; FRAME_ROWS]) {
+        let mut frame = Vec::with_capacity(1 + FRAME_ROWS * 8);
+        frame.push(FRAME_SYNC);
+        rows.iter().for_each(|row| frame.extend(row.to_be_bytes()));
+
+        // A dropped frame is corrected by the next one, so errors are ignored
+        let _ = self.bus.write_all(&frame).and_then(|_| self.bus.flush());
+    }
+}
+
+// --- main routine -----------------------------------------------------------
+
+/// Runs a ROM for a fixed number of frames, pushing them to the matrix
+fn main() {
+    let Some(rom_file) = std::env::args().nth(1) else {
+        eprintln!("usage: led_matrix <rom>");
+        return;
+    };
+
+    let mut emulator = match Emulator::new(&rom_file) {
+        Ok(emulator) => emulator,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+
+    emulator.set_display(LedMatrix {
+        bus: std::io::stdout(),
+    });
+
+    for _ in 0..NUM_FRAMES {
+        for _ in 0..STEPS_PER_FRAME {
+            if let Err(e) = emulator.step() {
+                eprintln!("{e}");
+                return;
+            }
+        }
+
+        // Pushes the frame to the matrix if the screen changed
+        emulator.tick_timers();
+    }
+}
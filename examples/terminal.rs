@@ -0,0 +1,89 @@
+//! A backend drawing the screen in a terminal through a `Renderer`, with two
+//! rows of pixels per line of text using half block characters.
+//!
+//! `cargo run --example terminal --no-default-features -- path/to/rom`
+
+use keet_8::{Emulator, FrameBuffer, Renderer};
+
+use std::io::Write;
+use std::time::Duration;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of instructions executed per frame
+const STEPS_PER_FRAME: usize = 10;
+/// The number of frames run before exiting
+const NUM_FRAMES: usize = 600;
+/// The time between two frames, as the timers run at 60Hz
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+// --- terminal definition ----------------------------------------------------
+
+/// A terminal understanding ANSI escape codes, drawn over from its top-left
+/// corner every frame
+struct Terminal<W: Write> {
+    /// The output of the terminal
+    out: W,
+}
+
+impl<W: Write> Renderer for Terminal<W> {
+    /// Draws the screen, a line of text covering two rows of pixels
+    fn present(&mut self, fb: &FrameBuffer) {
+        // Moves the cursor to the top-left corner rather than clearing, which
+        // would flicker
+        let mut text = String::from("\x1b[H");
+
+        for y in (0..fb.height()).step_by(2) {
+            for x in 0..fb.width() {
+                text.push(match (fb.is_on(x, y), fb.is_on(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+
+            // Clears what is left of a wider screen from the other resolution
+            text.push_str("\x1b[K\n");
+        }
+
+        // A dropped frame is corrected by the next one, so errors are ignored
+        let _ = self.out.write_all(text.as_bytes()).and_then(|_| self.out.flush());
+    }
+}
+
+// --- main routine -----------------------------------------------------------
+
+/// Runs a ROM for a fixed number of frames, drawing them in the terminal
+fn main() {
+    let Some(rom_file) = std::env::args().nth(1) else {
+        eprintln!("usage: terminal <rom>");
+        return;
+    };
+
+    let mut emulator = match Emulator::new(&rom_file) {
+        Ok(emulator) => emulator,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+
+    let mut terminal = Terminal {
+        out: std::io::stdout(),
+    };
+    print!("\x1b[2J");
+
+    for _ in 0..NUM_FRAMES {
+        for _ in 0..STEPS_PER_FRAME {
+            if let Err(e) = emulator.step() {
+                eprintln!("{e}");
+                return;
+            }
+        }
+
+        emulator.tick_timers();
+        terminal.present(&emulator.frame());
+        std::thread::sleep(FRAME_TIME);
+    }
+}
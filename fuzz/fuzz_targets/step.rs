@@ -0,0 +1,45 @@
+//! Runs arbitrary bytes as a ROM, under every combination of quirks and
+//! keys, checking that the interpreter core errors rather than panics.
+//!
+//! Requires cargo-fuzz and a nightly toolchain: `cargo +nightly fuzz run step`
+
+#![no_main]
+
+use keet_8::{Emulator, Quirks};
+use libfuzzer_sys::fuzz_target;
+
+// --- constants --------------------------------------------------------------
+
+/// The most instructions executed per input, bounding ROMs which never exit
+const MAX_STEPS: usize = 10_000;
+
+// --- fuzz target ------------------------------------------------------------
+
+fuzz_target!(|data: &[u8]| {
+    // The first two bytes pick the quirks and the next two the keys held
+    // down, the rest being the ROM
+    let Some((flags, data)) = data.split_first_chunk::<2>() else {
+        return;
+    };
+    let flags = u16::from_le_bytes(*flags);
+    let Some((keys, rom)) = data.split_first_chunk::<2>() else {
+        return;
+    };
+
+    let mut emulator = Emulator::from_bytes(rom);
+    emulator.set_quirks(Quirks {
+        shift_vy: flags & 0x01 != 0,
+        load_store_increment: flags & 0x02 != 0,
+        vf_reset: flags & 0x04 != 0,
+        jump_vx: flags & 0x08 != 0,
+        wrap_sprites: flags & 0x10 != 0,
+        display_wait: flags & 0x20 != 0,
+        index_overflow: flags & 0x40 != 0,
+        strict_memory: flags & 0x80 != 0,
+        clamp_memory: flags & 0x100 != 0,
+    });
+
+    emulator.set_all(u16::from_le_bytes(*keys));
+
+    let _ = emulator.run_steps(MAX_STEPS);
+});
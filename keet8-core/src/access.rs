@@ -0,0 +1,104 @@
+use crate::prelude::*;
+
+// --- access counts definition -----------------------------------------------
+
+/// The number of times the ROM read, wrote and executed each address of
+/// memory, showing where its code, sprites and variables live
+///
+/// The counts are only kept once enabled with
+/// [Emulator::set_access_counting](crate::Emulator::set_access_counting), and
+/// start over whenever the ROM restarts. Reads include the sprite data read
+/// by `DRW`, and both bytes of an instruction count as executed.
+pub struct AccessCounts {
+    /// The number of reads of each address
+    reads: Vec<u32>,
+    /// The number of writes of each address
+    writes: Vec<u32>,
+    /// The number of instructions executed from each address
+    executes: Vec<u32>,
+}
+
+impl AccessCounts {
+    /// Creates the counts with no access counted yet
+    ///
+    /// # Params
+    ///
+    /// - `size` - The number of bytes of memory, being a power of two
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            reads: vec![0; size],
+            writes: vec![0; size],
+            executes: vec![0; size],
+        }
+    }
+
+    /// Gets the number of times the ROM read an address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address
+    pub fn reads(&self, addr: u16) -> u32 {
+        self.reads[self.wrap(addr)]
+    }
+
+    /// Gets the number of times the ROM wrote an address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address
+    pub fn writes(&self, addr: u16) -> u32 {
+        self.writes[self.wrap(addr)]
+    }
+
+    /// Gets the number of instructions executed from an address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address
+    pub fn executes(&self, addr: u16) -> u32 {
+        self.executes[self.wrap(addr)]
+    }
+
+    /// Counts a read of an address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address, within memory
+    pub(crate) fn count_read(&mut self, addr: u16) {
+        let count = &mut self.reads[addr as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Counts a write of an address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address, within memory
+    pub(crate) fn count_write(&mut self, addr: u16) {
+        let count = &mut self.writes[addr as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Counts an instruction executed from an address, being both of its
+    /// bytes
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the instruction
+    pub(crate) fn count_execute(&mut self, addr: u16) {
+        for addr in [addr, addr.wrapping_add(1)] {
+            let addr = self.wrap(addr);
+            let count = &mut self.executes[addr];
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// Wraps an address around to within memory
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address
+    fn wrap(&self, addr: u16) -> usize {
+        addr as usize & (self.executes.len() - 1)
+    }
+}
@@ -0,0 +1,32 @@
+use super::AUDIO_PATTERN_SIZE;
+
+// --- audio sink definition --------------------------------------------------
+
+/// A sink the `Emulator` drives the buzzer of the ROM through, such as an
+/// audio stream or a piezo buzzer on a GPIO pin
+///
+/// The sink is attached with
+/// [Emulator::set_audio_sink](crate::Emulator::set_audio_sink) and is told
+/// about the transitions of the buzzer as they happen: it starts when the
+/// sound timer is set to a non-zero value, stops once it reaches zero, and
+/// the audio pattern (XO-CHIP) or its pitch is passed along whenever `F002`,
+/// `FX3A`, a reset or a restored save state changes it. Nothing is repeated
+/// to the sink when the state of the buzzer doesn't change.
+pub trait AudioSink: Send {
+    /// Starts sounding the buzzer, with the audio pattern last given if any
+    fn beep_start(&mut self);
+
+    /// Silences the buzzer
+    fn beep_stop(&mut self);
+
+    /// Sets the audio pattern (XO-CHIP) to play instead of the tone of the
+    /// buzzer
+    ///
+    /// # Params
+    ///
+    /// - `pattern` - The 128 bits of the audio pattern, played from the most
+    ///   significant bit of the first byte, or `None` to play the tone
+    /// - `pitch` - The pitch the audio pattern is played at, giving a
+    ///   playback rate of `4000 * 2^((pitch - 64) / 48)` bits per second
+    fn pattern(&mut self, pattern: Option<&[u8; AUDIO_PATTERN_SIZE]>, pitch: u8);
+}
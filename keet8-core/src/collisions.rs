@@ -0,0 +1,43 @@
+use crate::prelude::*;
+
+// --- collisions definition --------------------------------------------------
+
+/// The collisions of the `DRW` instructions during a frame, being the sprites
+/// which erased pixels that were already on and so set `VF`
+///
+/// Games detect hits with these collisions, so they show how tight the
+/// hitboxes of a game are.
+#[derive(Clone, Default)]
+pub struct Collisions {
+    /// The number of `DRW` instructions which set `VF`
+    count: u32,
+    /// The indices into the framebuffer of the pixels erased by a collision
+    pixels: Vec<usize>,
+}
+
+impl Collisions {
+    /// Gets the number of `DRW` instructions which set `VF`
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Gets the pixels erased by a collision, as indices into
+    /// [Emulator::framebuffer](crate::Emulator::framebuffer)
+    pub fn pixels(&self) -> &[usize] {
+        &self.pixels
+    }
+
+    /// Notes a pixel erased by the sprite being drawn
+    ///
+    /// # Params
+    ///
+    /// - `pixel` - The index into the framebuffer of the erased pixel
+    pub(crate) fn add_pixel(&mut self, pixel: usize) {
+        self.pixels.push(pixel);
+    }
+
+    /// Notes a `DRW` instruction which set `VF`
+    pub(crate) fn add_draw(&mut self) {
+        self.count += 1;
+    }
+}
@@ -0,0 +1,61 @@
+use super::Pixels;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of rows of a packed frame, one per row of the low resolution
+/// screen
+pub const FRAME_ROWS: usize = 32;
+/// The number of columns of a packed frame, one per bit of a row
+const FRAME_COLUMNS: usize = 64;
+
+// --- display definition -----------------------------------------------------
+
+/// A display the `Emulator` pushes its frames to, such as a physical 64×32
+/// LED matrix
+///
+/// The display is attached with
+/// [Emulator::set_display](crate::Emulator::set_display) and receives a frame
+/// from `tick_timers` whenever the screen changed since the last one. Every
+/// row is packed into a `u64` with the leftmost pixel in the most significant
+/// bit, which is the order in which the shift registers of most LED matrices
+/// are clocked. In the high resolution mode, each 2×2 block of pixels is
+/// folded into a single pixel which is on if any of the block is, as is each
+/// pair of rows in the two-page mode.
+///
+/// # Examples
+///
+/// A backend streaming the frames over an SPI-like byte stream, with a sync
+/// byte and the rows in big-endian order, is found in
+/// `examples/led_matrix.rs`.
+pub trait Display: Send {
+    /// Presents a frame
+    ///
+    /// # Params
+    ///
+    /// - `rows` - The rows of the frame from top to bottom, the leftmost pixel
+    ///   of each being its most significant bit
+    fn on_frame(&mut self, rows: &[u64; FRAME_ROWS]);
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Packs the pixels of the screen into the rows of a frame
+///
+/// # Params
+///
+/// - `pixels` - The pixels of the screen, row by row
+/// - `width` - The width of the screen, being a multiple of 64
+/// - `height` - The height of the screen, being a multiple of 32
+pub(crate) fn pack_rows(pixels: &Pixels, width: usize, height: usize) -> [u64; FRAME_ROWS] {
+    let scale_x = width / FRAME_COLUMNS;
+    let scale_y = height / FRAME_ROWS;
+    let mut rows = [0; FRAME_ROWS];
+
+    for y in 0..height {
+        for x in (0..width).filter(|&x| pixels.get(x, y)) {
+            rows[y / scale_y] |= 0x8000_0000_0000_0000 >> (x / scale_x);
+        }
+    }
+
+    rows
+}
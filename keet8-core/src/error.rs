@@ -0,0 +1,445 @@
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+use crate::memory::{MAX_MEMORY_SIZE, MEMORY_SIZE};
+use crate::opcode::AddressMode;
+use crate::state::STATE_VERSION;
+use crate::prelude::{Box, String, ToString, Vec};
+
+// --- error definition -------------------------------------------------------
+
+#[derive(Debug)]
+pub enum Keet8Error {
+    /// The ROM file was not specified in the command-line arguments
+    NoROMFile,
+    /// The ROM could not be loaded into memory
+    /// 
+    /// Also contains the filepath to the specified ROM
+    #[deprecated(since = "1.1.0", note = "no longer raised, see `RomUnreadable`")]
+    FailedToLoadROM(String),
+    /// The ROM file could not be read, such as when it doesn't exist or
+    /// isn't readable
+    ///
+    /// Also contains the filepath to the ROM and the error of the file
+    /// system, which is the source of this error. Only raised with the `std`
+    /// feature, as ROMs are otherwise never read from a file
+    #[cfg(feature = "std")]
+    RomUnreadable(String, std::io::Error),
+    /// An instruction raised an error whilst executed by `Emulator::step`
+    ///
+    /// Also contains the address and raw opcode of the instruction, and the
+    /// error it raised, which is the source of this error
+    InstructionFailed(u16, u16, Box<Keet8Error>),
+    /// There was an attempt to pop from the call stack, but the stack was empty
+    CallStackEmpty,
+    /// There was an attempt to push onto the call stack, but the stack was full
+    CallStackFull,
+    /// An invalid address mode was encounted for a instruction
+    InvalidAddressMode(AddressMode),
+    /// An unknown argument was specified in the command-line arguments
+    ///
+    /// Also contains the unknown argument
+    UnknownArgument(String),
+    /// A command-line argument requiring a value was specified without one
+    ///
+    /// Also contains the argument missing its value
+    MissingArgumentValue(String),
+    /// The symbol file could not be loaded
+    ///
+    /// Also contains the filepath to the specified symbol file
+    FailedToLoadSymbols(String),
+    /// The per-ROM sidecar could not be written
+    ///
+    /// Also contains the directory of the sidecar
+    FailedToSaveSidecar(String),
+    /// The list of recently opened ROMs could not be written
+    ///
+    /// Also contains the filepath to the list
+    FailedToSaveRecentRoms(String),
+    /// The configuration file could not be read
+    ///
+    /// Also contains the filepath to the configuration file
+    FailedToLoadConfig(String),
+    /// The settings changed in the application could not be written back to
+    /// the configuration file
+    ///
+    /// Also contains the filepath to the configuration file
+    FailedToSaveConfig(String),
+    /// The configuration file is invalid
+    ///
+    /// Also contains the reason it is invalid
+    InvalidConfig(String),
+    /// A command-line argument was provided with a value it doesn't accept
+    ///
+    /// Also contains the argument
+    InvalidArgumentValue(String),
+    /// The font of the overlay could not be loaded
+    ///
+    /// Also contains the filepath to the font
+    FailedToLoadFont(String),
+    /// The scripted sequence of random bytes could not be read
+    ///
+    /// Also contains the filepath to the sequence
+    FailedToLoadRandomScript(String),
+    /// A tracepoint could not be parsed
+    ///
+    /// Also contains the textual form of the tracepoint
+    InvalidTracepoint(String),
+    /// A break condition could not be parsed
+    ///
+    /// Also contains the textual form of the break condition
+    InvalidBreakCondition(String),
+    /// A save state could not be restored, as it is truncated or corrupted
+    InvalidSaveState,
+    /// A save state was written by a version of the emulator whose format
+    /// differs
+    ///
+    /// Also contains the version of the format of the save state
+    UnsupportedSaveStateVersion(u16),
+    /// A save state was captured with another ROM than the one loaded
+    ///
+    /// Also contains the hash of the ROM of the save state
+    SaveStateRomMismatch(u64),
+    /// The save state could not be written
+    ///
+    /// Also contains the filepath to the save state
+    FailedToSaveState(String),
+    /// The save state could not be read
+    ///
+    /// Also contains the filepath to the save state
+    FailedToLoadState(String),
+    /// The ROM doesn't fit in the memory of the emulator
+    ///
+    /// Also contains the size of the ROM in bytes and the size of the largest
+    /// ROM which fits from the address it is loaded at
+    ROMTooLarge { size: usize, max: usize },
+    /// The ROM holds no bytes, so there is nothing to run
+    EmptyROM,
+    /// The memory can't have the size which was asked for, not being a power
+    /// of two from `MEMORY_SIZE` to `MAX_MEMORY_SIZE`
+    ///
+    /// Also contains the size in bytes
+    InvalidMemorySize(usize),
+    /// There is no built-in ROM by the name given with `--builtin`
+    ///
+    /// Also contains the name which was given
+    UnknownBuiltinRom(String),
+    /// The ROM reported a failed test through the host-call extension
+    ///
+    /// Also contains the code reported by the ROM
+    TestFailed(u8),
+    /// The ROM accessed memory through `I` past the end of memory, with the
+    /// strict memory quirk enabled
+    ///
+    /// Also contains the address which was accessed
+    AddressOutOfRange(usize),
+    /// The ROM wrote into the interpreter area below `0x200` with `FX33` or
+    /// `FX55`, with the strict memory quirk enabled
+    ///
+    /// Also contains the address which was written to
+    ReservedAreaWrite(u16),
+    /// The ROM executed an opcode which doesn't decode to any instruction,
+    /// with the emulator being strict
+    ///
+    /// Also contains the opcode and its address
+    UnknownOpcode(u16, u16),
+    /// The texture the emulator display is rendered to could not be created
+    ///
+    /// Also contains the reason given by raylib
+    FailedToCreateTexture(String),
+    /// The timing test ROM didn't count the expected number of instructions
+    ///
+    /// Also contains the refresh rate in Hz at which it was off
+    TimingCheckFailed(u32),
+    /// Some of the test ROMs run by `--verify` failed
+    ///
+    /// Also contains the number of test ROMs which failed
+    VerificationFailed(u32),
+    /// A screenshot could not be written
+    ///
+    /// Also contains the filepath of the screenshot
+    FailedToSaveScreenshot(String),
+    /// A recording could not be written
+    ///
+    /// Also contains the filepath of the recording
+    FailedToSaveRecording(String),
+    /// The sound of the emulator could not be written to a WAV file
+    ///
+    /// Also contains the filepath of the WAV file
+    FailedToSaveAudio(String),
+    /// A dump of the machine state could not be written
+    ///
+    /// Also contains the filepath of the dump
+    FailedToSaveStateDump(String),
+    /// A profile of the instructions executed could not be written
+    ///
+    /// Also contains the filepath of the profile
+    FailedToSaveProfile(String),
+    /// An instruction could not be assembled
+    ///
+    /// Also contains the instruction
+    InvalidAssembly(String),
+    /// A command typed into the debugger console could not be parsed
+    ///
+    /// Also contains the command
+    InvalidCommand(String),
+    /// The two save states to compare were not specified in the command-line
+    /// arguments
+    NoStateFiles,
+    /// A bug report could not be written
+    ///
+    /// Also contains the filepath of the bug report
+    FailedToSaveBugReport(String),
+    /// The instruction trace could not be written
+    ///
+    /// Also contains the filepath of the trace
+    FailedToWriteTrace(String),
+    /// An instruction trace to compare could not be read
+    ///
+    /// Also contains the filepath of the trace
+    FailedToReadTrace(String),
+    /// The two instruction traces to compare were not specified in the
+    /// command-line arguments
+    NoTraceFiles,
+    /// The two instruction traces compared by `diff-traces` diverge
+    ///
+    /// Also contains the step at which they first diverge, counted from 1
+    TracesDiverged(usize),
+    /// An input log could not be written
+    ///
+    /// Also contains the filepath of the input log
+    FailedToSaveInputLog(String),
+    /// An input log could not be read or is invalid
+    ///
+    /// Also contains the filepath of the input log
+    FailedToLoadInputLog(String),
+    /// The connection to the other player of a netplay session failed
+    ///
+    /// Also contains the reason
+    NetplayFailed(String),
+    /// The GDB stub could not listen on its port
+    ///
+    /// Also contains the port
+    FailedToOpenGdbStub(u16),
+    /// The remote control server could not listen on its port
+    ///
+    /// Also contains the port
+    FailedToOpenRemote(u16),
+    /// The cheats file could not be read
+    ///
+    /// Also contains the filepath to the cheats file
+    FailedToLoadCheats(String),
+    /// A cheat could not be parsed
+    ///
+    /// Also contains the textual form of the cheat
+    InvalidCheat(String),
+    /// A script could not be loaded or failed whilst running
+    ///
+    /// Also contains the reason given by the script engine
+    ScriptFailed(String),
+    /// The ROM database could not be read
+    ///
+    /// Also contains the filepath to the ROM database
+    FailedToLoadRomDatabase(String),
+    /// The ROM database is invalid
+    ///
+    /// Also contains the reason it is invalid
+    InvalidRomDatabase(String),
+    /// A ZIP archive of ROMs is invalid, or holds no ROM
+    ///
+    /// Also contains the filepath to the archive and the reason it is invalid
+    InvalidArchive(String, String),
+    /// A ZIP archive holds several ROMs, without one being picked
+    ///
+    /// Also contains the filepath to the archive and the names of the ROMs
+    SeveralRomsInArchive(String, Vec<String>),
+    /// A crash report could not be written
+    ///
+    /// Also contains the filepath of the crash report
+    FailedToSaveCrashReport(String),
+    /// An input script could not be read or holds an invalid statement
+    ///
+    /// Also contains the filepath to the input script and the invalid
+    /// statement, which is empty if the script could not be read
+    InvalidInputScript(String, String),
+}
+
+impl Keet8Error {
+    /// Gets the message of the error without the `[ERROR]:` prefix, for
+    /// showing it in the window or in colors
+    pub fn message(&self) -> String {
+        Message(self).to_string()
+    }
+
+    /// Gets the error at the end of the chain of sources, being the error
+    /// itself if it has no source of the same type, such as the error an
+    /// instruction raised within `InstructionFailed`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use keet8_core::{Emulator, Keet8Error};
+    ///
+    /// // `RET` with an empty call stack
+    /// let mut emulator = Emulator::from_rom(&[0x00, 0xEE]).unwrap();
+    /// let error = emulator.step().unwrap_err();
+    ///
+    /// assert!(matches!(error, Keet8Error::InstructionFailed(0x200, 0x00EE, _)));
+    /// assert!(matches!(error.root_cause(), Keet8Error::CallStackEmpty));
+    /// ```
+    pub fn root_cause(&self) -> &Keet8Error {
+        match self {
+            Keet8Error::InstructionFailed(_, _, cause) => cause.root_cause(),
+            _ => self,
+        }
+    }
+
+    /// Adds the address and raw opcode of the instruction which raised the
+    /// error, unless the error already holds them
+    ///
+    /// # Params
+    ///
+    /// - `pc` - The address of the instruction
+    /// - `opcode` - The raw opcode of the instruction
+    pub(crate) fn at_instruction(self, pc: u16, opcode: u16) -> Self {
+        match self {
+            Keet8Error::UnknownOpcode(..) | Keet8Error::InstructionFailed(..) => self,
+            _ => Keet8Error::InstructionFailed(pc, opcode, Box::new(self)),
+        }
+    }
+}
+
+impl Error for Keet8Error {
+    /// Gets the error this error was caused by, if it keeps it
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Keet8Error::RomUnreadable(_, e) => Some(e),
+            Keet8Error::InstructionFailed(_, _, cause) => Some(cause.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Keet8Error {
+    /// Writes the error to the output stream
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[ERROR]: {}", Message(self))
+    }
+}
+
+// --- message definition -----------------------------------------------------
+
+/// The message of an error, without the `[ERROR]:` prefix, so errors can be
+/// nested within each other
+struct Message<'a>(&'a Keet8Error);
+
+impl Display for Message<'_> {
+    /// Writes the message to the output stream
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Keet8Error::NoROMFile => write!(f, "No ROM file specified"),
+            #[allow(deprecated)]
+            Keet8Error::FailedToLoadROM(rom) => write!(f, "Failed to load ROM: {rom}"),
+            #[cfg(feature = "std")]
+            Keet8Error::RomUnreadable(rom, e) => write!(f, "Failed to load ROM: {rom} ({e})"),
+            Keet8Error::InstructionFailed(pc, opcode, cause) => {
+                write!(f, "{} at {pc:#05X} ({opcode:04X})", Message(cause))
+            }
+            Keet8Error::CallStackEmpty => write!(f, "Call stack is empty"),
+            Keet8Error::CallStackFull => write!(f, "Call stack limit reached"),
+            Keet8Error::InvalidAddressMode(addr_mode) => write!(f, "Invalid address mode: {addr_mode}"),
+            Keet8Error::UnknownArgument(arg) => write!(f, "Unknown argument: {arg}"),
+            Keet8Error::MissingArgumentValue(arg) => write!(f, "Missing value for argument: {arg}"),
+            Keet8Error::FailedToLoadSymbols(file) => write!(f, "Failed to load symbols: {file}"),
+            Keet8Error::FailedToSaveSidecar(dir) => write!(f, "Failed to save sidecar: {dir}"),
+            Keet8Error::FailedToSaveRecentRoms(file) => {
+                write!(f, "Failed to save the recent ROMs: {file}")
+            }
+            Keet8Error::FailedToLoadConfig(file) => write!(f, "Failed to load config: {file}"),
+            Keet8Error::FailedToSaveConfig(file) => write!(f, "Failed to save config: {file}"),
+            Keet8Error::InvalidConfig(reason) => write!(f, "Invalid config: {reason}"),
+            Keet8Error::InvalidArgumentValue(arg) => write!(f, "Invalid value for argument: {arg}"),
+            Keet8Error::FailedToLoadFont(file) => write!(f, "Failed to load font: {file}"),
+            Keet8Error::FailedToLoadRandomScript(file) => write!(f, "Failed to load random script: {file}"),
+            Keet8Error::InvalidTracepoint(text) => write!(f, "Invalid tracepoint: {text}"),
+            Keet8Error::InvalidBreakCondition(text) => {
+                write!(f, "Invalid break condition: {text}")
+            }
+            Keet8Error::InvalidSaveState => write!(f, "Invalid save state"),
+            Keet8Error::UnsupportedSaveStateVersion(version) => write!(
+                f,
+                "Unsupported save state: format version {version}, but only version \
+                 {STATE_VERSION} can be loaded"
+            ),
+            Keet8Error::SaveStateRomMismatch(hash) => {
+                write!(f, "Save state is for another ROM (hash {hash:016x})")
+            }
+            Keet8Error::FailedToSaveState(file) => write!(f, "Failed to save state: {file}"),
+            Keet8Error::FailedToLoadState(file) => write!(f, "Failed to load state: {file}"),
+            Keet8Error::ROMTooLarge { size, max } => {
+                write!(f, "ROM too large: {size} bytes, but at most {max} bytes fit")
+            }
+            Keet8Error::EmptyROM => write!(f, "ROM is empty"),
+            Keet8Error::InvalidMemorySize(size) => write!(
+                f,
+                "Invalid memory size: {size} bytes, expected a power of two from {MEMORY_SIZE} to \
+                 {MAX_MEMORY_SIZE} bytes"
+            ),
+            Keet8Error::UnknownBuiltinRom(name) => write!(f, "Unknown built-in ROM: {name}"),
+            Keet8Error::TestFailed(code) => write!(f, "ROM reported a failed test with code {code}"),
+            Keet8Error::AddressOutOfRange(addr) => write!(f, "Address out of range: {addr:#05X}"),
+            Keet8Error::ReservedAreaWrite(addr) => write!(f, "Write into the interpreter area: {addr:#05X}"),
+            Keet8Error::UnknownOpcode(opcode, pc) => write!(f, "Unknown opcode {opcode:04X} at {pc:#05X}"),
+            Keet8Error::FailedToCreateTexture(reason) => write!(f, "Failed to create texture: {reason}"),
+            Keet8Error::TimingCheckFailed(rate) => write!(f, "Timing check failed at {rate}Hz"),
+            Keet8Error::VerificationFailed(failed) => write!(f, "{failed} test ROM(s) failed"),
+            Keet8Error::FailedToSaveScreenshot(file) => write!(f, "Failed to save screenshot: {file}"),
+            Keet8Error::FailedToSaveRecording(file) => write!(f, "Failed to save recording: {file}"),
+            Keet8Error::FailedToSaveAudio(file) => write!(f, "Failed to save audio: {file}"),
+            Keet8Error::FailedToSaveStateDump(file) => {
+                write!(f, "Failed to save state dump: {file}")
+            }
+            Keet8Error::FailedToSaveProfile(file) => write!(f, "Failed to save profile: {file}"),
+            Keet8Error::InvalidAssembly(text) => write!(f, "Invalid instruction: {text}"),
+            Keet8Error::InvalidCommand(text) => write!(f, "Invalid command: {text}"),
+            Keet8Error::NoStateFiles => write!(f, "Two save state files have to be specified"),
+            Keet8Error::FailedToSaveBugReport(file) => write!(f, "Failed to save bug report: {file}"),
+            Keet8Error::FailedToWriteTrace(file) => write!(f, "Failed to write trace: {file}"),
+            Keet8Error::FailedToReadTrace(file) => write!(f, "Failed to read trace: {file}"),
+            Keet8Error::NoTraceFiles => write!(f, "Two trace files have to be specified"),
+            Keet8Error::TracesDiverged(step) => write!(f, "The traces diverge at step {step}"),
+            Keet8Error::FailedToSaveInputLog(file) => write!(f, "Failed to save input log: {file}"),
+            Keet8Error::FailedToLoadInputLog(file) => write!(f, "Failed to load input log: {file}"),
+            Keet8Error::NetplayFailed(reason) => write!(f, "Netplay failed: {reason}"),
+            Keet8Error::FailedToOpenGdbStub(port) => write!(f, "Failed to open GDB stub on port {port}"),
+            Keet8Error::FailedToOpenRemote(port) => {
+                write!(f, "Failed to open remote control on port {port}")
+            }
+            Keet8Error::FailedToLoadCheats(file) => write!(f, "Failed to load cheats: {file}"),
+            Keet8Error::InvalidCheat(text) => write!(f, "Invalid cheat: {text}"),
+            Keet8Error::ScriptFailed(reason) => write!(f, "Script failed: {reason}"),
+            Keet8Error::FailedToLoadRomDatabase(file) => {
+                write!(f, "Failed to load ROM database: {file}")
+            }
+            Keet8Error::InvalidRomDatabase(reason) => write!(f, "Invalid ROM database: {reason}"),
+            Keet8Error::InvalidArchive(file, reason) => {
+                write!(f, "Invalid archive: {file} ({reason})")
+            }
+            Keet8Error::SeveralRomsInArchive(file, names) => write!(
+                f,
+                "Several ROMs in {file}, pick one as {file}/<name>: {}",
+                names.join(", ")
+            ),
+            Keet8Error::FailedToSaveCrashReport(file) => {
+                write!(f, "Failed to save crash report: {file}")
+            }
+            Keet8Error::InvalidInputScript(file, statement) if statement.is_empty() => {
+                write!(f, "Failed to load input script: {file}")
+            }
+            Keet8Error::InvalidInputScript(file, statement) => {
+                write!(f, "Invalid input script: {file} ({statement})")
+            }
+        }
+    }
+}
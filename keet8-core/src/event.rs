@@ -0,0 +1,35 @@
+// --- event definition -------------------------------------------------------
+
+/// Something which happened whilst the `Emulator` ran, passed to the
+/// callbacks registered with [Emulator::on_event](crate::Emulator::on_event)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The screen was cleared with `CLS`
+    ScreenCleared,
+    /// The sound timer was set whilst the buzzer was silent
+    SoundStarted,
+    /// The sound timer ran out or was cleared whilst the buzzer sounded
+    SoundStopped,
+    /// An opcode no instruction or handler knows was executed, and skipped
+    /// unless the emulator is strict
+    UnknownOpcode {
+        /// The raw opcode
+        opcode: u16,
+        /// The address of the opcode
+        addr: u16,
+    },
+    /// The timers ticked, ending the frame, which is the vertical blank the
+    /// screen and the buzzer are presented at
+    FrameCompleted {
+        /// Flag indicating whether the screen was drawn to, cleared or
+        /// scrolled during the frame, so that it only has to be presented
+        /// again when it was
+        drawn: bool,
+    },
+    /// The ROM jumped to the jump itself, which is how most ROMs end as
+    /// Chip-8 has no way to exit, and will do nothing else from now on
+    Finished {
+        /// The address of the jump
+        addr: u16,
+    },
+}
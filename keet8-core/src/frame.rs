@@ -0,0 +1,27 @@
+use super::StepOutcome;
+
+use crate::prelude::*;
+
+// --- frame summary definition -----------------------------------------------
+
+/// What happened during a frame run by
+/// [Emulator::step_frame](crate::Emulator::step_frame)
+///
+/// The error of an instruction is kept in the summary rather than returned,
+/// as the instructions executed before it may still have drawn to the screen
+/// or started the buzzer.
+#[derive(Debug)]
+pub struct FrameSummary {
+    /// The number of instructions executed
+    pub executed: u32,
+    /// Flag indicating whether the screen changed, so that frontends only
+    /// present it when needed
+    pub drawn: bool,
+    /// Flag indicating whether the buzzer sounds after the frame
+    pub sound: bool,
+    /// The outcome of the step which ended the frame early, being
+    /// `StepOutcome::Executed` when every instruction of the frame ran
+    pub outcome: StepOutcome,
+    /// The error raised by an instruction, which ended the frame early
+    pub error: Option<Keet8Error>,
+}
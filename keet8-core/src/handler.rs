@@ -0,0 +1,48 @@
+use super::Emulator;
+
+use crate::prelude::*;
+
+// --- type definitions -------------------------------------------------------
+
+/// This type represents a user-provided handler for raw opcodes, receiving
+/// the emulator and the raw opcode that matched
+pub(crate) type OpcodeHandler = Box<dyn FnMut(&mut Emulator, u16) -> Result<()> + Send>;
+
+// --- opcode override definition ---------------------------------------------
+
+/// An opcode handler registered for all raw opcodes matching a pattern
+pub(crate) struct OpcodeOverride {
+    /// The bits of the raw opcode that are compared against `pattern`
+    mask: u16,
+    /// The value the masked raw opcode has to equal
+    pattern: u16,
+    /// The handler to execute instead of the decoded instruction
+    pub handler: OpcodeHandler,
+}
+
+impl OpcodeOverride {
+    /// Creates a new override
+    ///
+    /// # Params
+    ///
+    /// - `mask` - The bits of the raw opcode to compare
+    /// - `pattern` - The value the masked raw opcode has to equal
+    /// - `handler` - The handler to execute on a match
+    pub fn new(mask: u16, pattern: u16, handler: OpcodeHandler) -> Self {
+        Self {
+            mask,
+            pattern: pattern & mask,
+            handler,
+        }
+    }
+
+    /// Checks whether this override applies to the raw opcode
+    ///
+    /// # Params
+    ///
+    /// - `raw` - The raw opcode fetched from memory
+    #[inline(always)]
+    pub const fn matches(&self, raw: u16) -> bool {
+        raw & self.mask == self.pattern
+    }
+}
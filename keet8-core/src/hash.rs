@@ -0,0 +1,43 @@
+// --- constants --------------------------------------------------------------
+
+/// The offset basis of the 64-bit FNV-1a hash
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+/// The prime of the 64-bit FNV-1a hash
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+/// The reversed polynomial of the CRC-32 used by ZIP and PNG
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+// --- hashing functions ------------------------------------------------------
+
+/// Hashes the bytes with the 64-bit FNV-1a hash
+///
+/// This is not a cryptographic hash, but it is stable across platforms and
+/// versions, which makes it suitable for identifying ROMs
+///
+/// # Params
+///
+/// - `bytes` - The bytes to hash
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Computes the CRC-32 of the bytes, for detecting corrupted data
+///
+/// # Params
+///
+/// - `bytes` - The bytes to compute the checksum of
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(0xFFFF_FFFF, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            }
+        })
+    });
+
+    !crc
+}
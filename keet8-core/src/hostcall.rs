@@ -0,0 +1,94 @@
+use super::{Emulator, NUM_REGISTERS};
+
+use crate::prelude::*;
+
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// --- constants --------------------------------------------------------------
+
+/// The mask selecting the bits of a raw opcode identifying a host call
+pub(crate) const HOST_CALL_MASK: u16 = 0xFF00;
+/// The pattern of a host call (`01NN`, where `NN` is the service)
+pub(crate) const HOST_CALL_PATTERN: u16 = 0x0100;
+
+/// `0100` - Prints the NUL-terminated string at `I`
+const SERVICE_PRINT: u8 = 0x00;
+/// `0101` - Dumps all the `V` registers
+const SERVICE_REGISTERS: u8 = 0x01;
+/// `0102` - Stores the host's UNIX time in seconds into `V0`-`V3` (big endian)
+const SERVICE_TIME: u8 = 0x02;
+/// `0110` - Signals that a test has passed and exits
+const SERVICE_PASS: u8 = 0x10;
+/// `0111` - Signals that a test has failed with the code in `V0` and exits
+const SERVICE_FAIL: u8 = 0x11;
+
+/// The maximum length of a string printed with `SERVICE_PRINT`
+const MAX_PRINT_LEN: u16 = 256;
+
+// --- host call definition ---------------------------------------------------
+
+/// A request made by a ROM to the host through the keet-8 host-call
+/// extension
+pub enum HostCall {
+    /// The ROM printed a line of debug text
+    Print(String),
+    /// The ROM dumped the values of its `V` registers
+    Registers([u8; NUM_REGISTERS]),
+    /// The ROM signalled the result of a test and exited
+    TestResult {
+        /// Whether the test has passed
+        passed: bool,
+        /// The code reported by the ROM (`0` when passed)
+        code: u8,
+    },
+}
+
+// --- host call execution ----------------------------------------------------
+
+/// Executes a host call and returns the request to pass on to the host, if
+/// any
+///
+/// Unknown services are ignored, just like the `SYS` instruction
+///
+/// # Params
+///
+/// - `emu` - The emulator executing the host call
+/// - `raw` - The raw opcode of the host call
+pub(crate) fn execute(emu: &mut Emulator, raw: u16) -> Option<HostCall> {
+    match (raw & 0x00FF) as u8 {
+        SERVICE_PRINT => {
+            let bytes = (0..MAX_PRINT_LEN)
+                .map(|i| emu.memory[emu.idx.wrapping_add(i)])
+                .take_while(|&b| b != 0x00)
+                .collect::<Vec<u8>>();
+
+            Some(HostCall::Print(String::from_utf8_lossy(&bytes).into_owned()))
+        }
+        SERVICE_REGISTERS => Some(HostCall::Registers(emu.registers)),
+        SERVICE_TIME => {
+            // Without `std` there is no clock, so the time reads as zero
+            #[cfg(feature = "std")]
+            let secs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_secs() as u32);
+            #[cfg(not(feature = "std"))]
+            let secs = 0u32;
+
+            emu.registers[0x00..0x04].copy_from_slice(&secs.to_be_bytes());
+            None
+        }
+        SERVICE_PASS => {
+            emu.halted = true;
+            Some(HostCall::TestResult { passed: true, code: 0 })
+        }
+        SERVICE_FAIL => {
+            emu.halted = true;
+            Some(HostCall::TestResult {
+                passed: false,
+                code: emu.registers[0x00],
+            })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,46 @@
+use super::random::{Lcg, RandomSource};
+
+// --- constants --------------------------------------------------------------
+
+/// The stream the memory is filled from, see `InitPolicy::fill`
+pub(crate) const MEMORY_STREAM: u32 = 0;
+/// The stream the registers are filled from, see `InitPolicy::fill`
+pub(crate) const REGISTER_STREAM: u32 = 0x5EED_0001;
+
+// --- init policy definition -------------------------------------------------
+
+/// How the memory and registers are filled when a ROM is loaded or reset
+///
+/// Interpreters usually clear everything, whilst real hardware powered up with
+/// whatever its RAM happened to hold. ROMs which read memory or registers
+/// before writing them may only work by accident on the former, which random
+/// garbage exposes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InitPolicy {
+    /// The memory and registers are cleared to zero
+    #[default]
+    Zeroed,
+    /// The memory outside the fonts and the ROM, the `V` registers and `I`
+    /// are filled with garbage generated from the seed, the same seed giving
+    /// the same garbage
+    Random(u32),
+}
+
+impl InitPolicy {
+    /// Fills a buffer as the policy dictates
+    ///
+    /// # Params
+    ///
+    /// - `buffer` - The buffer to fill
+    /// - `stream` - The stream of garbage to fill it from, so that the
+    ///   buffers filled from the same seed don't hold the same garbage
+    pub(crate) fn fill(self, buffer: &mut [u8], stream: u32) {
+        match self {
+            Self::Zeroed => buffer.fill(0),
+            Self::Random(seed) => {
+                let mut rng = Lcg::new(seed ^ stream);
+                buffer.iter_mut().for_each(|byte| *byte = rng.next_u8());
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use super::NUM_KEYS;
+
+// --- key definition ---------------------------------------------------------
+
+/// A key of the hexadecimal keypad, set on the `Emulator` with
+/// [Emulator::set_key](crate::Emulator::set_key)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// The key `0`
+    K0,
+    /// The key `1`
+    K1,
+    /// The key `2`
+    K2,
+    /// The key `3`
+    K3,
+    /// The key `4`
+    K4,
+    /// The key `5`
+    K5,
+    /// The key `6`
+    K6,
+    /// The key `7`
+    K7,
+    /// The key `8`
+    K8,
+    /// The key `9`
+    K9,
+    /// The key `A`
+    KA,
+    /// The key `B`
+    KB,
+    /// The key `C`
+    KC,
+    /// The key `D`
+    KD,
+    /// The key `E`
+    KE,
+    /// The key `F`
+    KF,
+}
+
+impl Key {
+    /// Every key of the keypad, in the order of their values
+    pub const ALL: [Key; NUM_KEYS] = [
+        Key::K0, Key::K1, Key::K2, Key::K3, Key::K4, Key::K5, Key::K6, Key::K7,
+        Key::K8, Key::K9, Key::KA, Key::KB, Key::KC, Key::KD, Key::KE, Key::KF,
+    ];
+
+    /// Gets the key with the value, if there is one on the keypad
+    ///
+    /// # Params
+    ///
+    /// - `index` - The value of the key (`0x0` to `0xF`)
+    pub fn from_index(index: usize) -> Option<Key> {
+        Key::ALL.get(index).copied()
+    }
+
+    /// Gets the value of the key, from `0x0` to `0xF`
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
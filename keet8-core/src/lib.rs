@@ -0,0 +1,2334 @@
+//! This crate, `keet8-core`, is the actual implementation of the Chip-8
+//! emulator, without any frontend.
+//! 
+//! This includes things like the memory, call stack, opcode, instruction
+//! and address mode implementations aswell
+//! 
+//! This crate only exposes one submodule, being the `opcode` module for
+//! errors and disassembly. This also exposes the `Emulator` struct for a
+//! frontend to interact with during runtime, such as the raylib
+//! `Application` of `keet_8`.
+//! 
+//! It has no native dependencies. The `rand` feature seeds the random number
+//! generator from the OS, and the `serde` feature lets `Quirks` be read from
+//! configuration files.
+//! 
+//! The `testing` submodule, only compiled with the `testing` feature, exposes
+//! single instructions and the rest of the state to the instruction tests.
+//! 
+//! The core has no knowledge of any window, it only exposes its video buffer
+//! and keypad as plain data for a frontend to present and fill in, through a
+//! `Renderer` of its own. Hardware frontends can instead have packed frames
+//! pushed to a `Display`, and the buzzer driven through an `AudioSink`.
+//! 
+//! Without the default `std` feature the crate is `no_std`, only needing an
+//! allocator, for running on microcontrollers. ROMs are then loaded from
+//! bytes with `Emulator::from_rom`, as there is no filesystem to read them
+//! from, and the random number generator starts from a fixed seed unless a
+//! `RandomSource` of the board is set.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod access;
+mod audio;
+mod collisions;
+mod display;
+mod error;
+mod event;
+mod frame;
+mod hash;
+mod handler;
+mod hostcall;
+mod init;
+mod key;
+mod memory;
+pub mod opcode;
+mod pixels;
+mod platform;
+mod prelude;
+mod quirks;
+mod random;
+mod renderer;
+mod resolution;
+mod stack;
+mod state;
+#[cfg(feature = "testing")]
+mod testing;
+
+pub use access::AccessCounts;
+pub use audio::AudioSink;
+pub use collisions::Collisions;
+pub use display::{Display, FRAME_ROWS};
+pub use event::Event;
+pub use frame::FrameSummary;
+use handler::OpcodeOverride;
+pub use hostcall::HostCall;
+use init::REGISTER_STREAM;
+pub use init::InitPolicy;
+pub use key::Key;
+#[cfg(feature = "std")]
+pub use memory::read_rom;
+pub use memory::{ETI660_PROG_ADDR, FONT_ADDR, FONT_END, MAX_MEMORY_SIZE, MEMORY_SIZE, PROG_ADDR};
+use memory::Memory;
+use opcode::{AddressMode, OpCode};
+pub use pixels::Pixels;
+pub use platform::Platform;
+pub use quirks::Quirks;
+#[cfg(feature = "rand")]
+pub use random::OsRandom;
+pub use random::{Lcg, RandomSource, ScriptedRandom};
+pub use renderer::{FrameBuffer, Renderer};
+pub use resolution::{is_two_page_rom, Resolution, TWO_PAGE_ENTRY, TWO_PAGE_HEIGHT};
+use renderer::ALL_ROWS;
+use resolution::TWO_PAGE_CLS;
+use stack::CallStack;
+pub use stack::STACK_SIZE;
+
+pub use hash::{crc32, fnv1a};
+pub use prelude::{Keet8Error, Result};
+
+use crate::prelude::*;
+
+use alloc::collections::{BTreeSet, VecDeque};
+use core::ops::RangeInclusive;
+
+// --- constants --------------------------------------------------------------
+
+/// Represents the number of available registers to Chip-8
+const NUM_REGISTERS: usize = 16;
+/// Represents the number keys on the keypad available to Chip-8
+const NUM_KEYS: usize = 16;
+
+/// Represents the number of RPL user flags available to SCHIP
+const NUM_RPL_FLAGS: usize = 8;
+
+/// Represents the number of bytes in the audio pattern of XO-CHIP
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+/// Represents the pitch the audio pattern plays at before `FX3A` sets one,
+/// being a playback rate of 4000 bits per second
+const DEFAULT_PITCH: u8 = 64;
+
+/// Represents the width of the screen buffer in low resolution mode
+pub const LORES_WIDTH: usize = 64;
+/// Represents the height of the screen buffer in low resolution mode
+pub const LORES_HEIGHT: usize = 32;
+/// Represents the width of the screen buffer in high resolution mode (SCHIP)
+pub const HIRES_WIDTH: usize = 128;
+/// Represents the height of the screen buffer in high resolution mode (SCHIP)
+pub const HIRES_HEIGHT: usize = 64;
+
+/// Represents the number of instructions `run_steps` executes per timer
+/// tick, being 600 instructions per second
+const STEPS_PER_TICK: usize = 10;
+/// Represents the number of instructions last executed kept in the history
+const HISTORY_LENGTH: usize = 32;
+
+// --- type definitions -------------------------------------------------------
+
+/// This type represents the functions to call to execute any of the
+/// instructions of the Chip-8 emulator
+type Executor = fn(&mut Emulator, opcode: OpCode) -> Result<()>;
+
+// --- step outcome definition -----------------------------------------------
+
+/// What happened when the emulator was asked to step an instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The instruction at the program counter was executed
+    Executed,
+    /// The program counter reached a breakpoint, so the instruction at it
+    /// wasn't executed. The next step executes it.
+    Breakpoint(u16),
+    /// The instruction at the program counter was executed and accessed a
+    /// watched location
+    Watchpoint(WatchHit),
+    /// Nothing was executed, as the ROM has exited the interpreter
+    Halted,
+}
+
+/// An access by the ROM of a location being watched
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchHit {
+    /// The ROM read the byte at the memory address
+    Read(u16),
+    /// The ROM wrote the byte at the memory address
+    Write(u16),
+    /// The ROM changed the value of the `V` register
+    Register(usize),
+}
+
+// --- emulator definition ----------------------------------------------------
+
+/// The Chip-8 (and SCHIP) interpreter core
+///
+/// The emulator has no window or clock of its own. A frontend embeds it by
+/// setting the keypad with `set_key`, calling `step` for every instruction it
+/// wants to execute, calling `tick_timers` 60 times per second and presenting
+/// `framebuffer` along with the timers.
+///
+/// # Examples
+///
+/// ```rust
+/// // CLS, DRW V0, V0, 5 (the font sprite of 0), JP 0x0202
+/// let rom = [0x00, 0xE0, 0xD0, 0x05, 0x12, 0x02];
+///
+/// if let Ok(mut emulator) = keet8_core::Emulator::from_rom(&rom) {
+///     emulator.press(keet8_core::Key::K5);
+///     for _ in 0..10 {
+///         let _ = emulator.step();
+///     }
+///     emulator.tick_timers();
+///
+///     let width = emulator.screen_width();
+///     let lit = emulator.framebuffer().iter().filter(|&&pixel| pixel > 0).count();
+///     println!("{lit} pixels lit on a {width}-wide screen");
+///     println!("delay timer {}, sound timer {}", emulator.delay_timer(), emulator.sound_timer());
+/// }
+/// ```
+pub struct Emulator {
+    /// These are the `V` registers
+    registers: [u8; NUM_REGISTERS],
+    /// This is the index register
+    idx: u16,
+    /// This is the program counter
+    program_counter: u16,
+    /// This is the delay timer
+    delay_timer: u8,
+    /// This is the sound timer
+    sound_timer: u8,
+    /// This is the call stack
+    stack: CallStack,
+    /// This is the available memory to Chip-8
+    memory: Memory,
+    /// This is the screen buffer, of which only the pixels within the
+    /// current resolution mode are in use
+    video_buffer: Pixels,
+    /// This is the resolution mode of the screen
+    resolution: Resolution,
+    /// Flag indicating whether the ROM runs in the two-page mode (hi-res
+    /// Chip-8), being the resolution mode it starts in and returns to
+    two_page: bool,
+    /// Flag indicating whether the ROM has exited the interpreter (SCHIP)
+    halted: bool,
+    /// These are the RPL user flags (SCHIP)
+    rpl: [u8; NUM_RPL_FLAGS],
+    /// This is the audio pattern loaded by `F002` (XO-CHIP), played instead
+    /// of the tone of the buzzer once there is one
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    /// This is the pitch the audio pattern is played at (XO-CHIP)
+    pitch: u8,
+    /// These are the quirks the instructions are executed with
+    quirks: Quirks,
+    /// Flag indicating whether unknown opcodes raise an error instead of
+    /// being skipped
+    strict: bool,
+    /// The platform the opcodes are restricted to, if any, the opcodes of
+    /// later platforms being treated as unknown opcodes
+    platform: Option<Platform>,
+    /// Flag indicating whether a new frame has started since the last sprite
+    /// was drawn, used by the display wait quirk
+    vblank: bool,
+    /// Flag indicating whether the screen was drawn to, cleared or scrolled
+    /// since the last frame, reported by `Event::FrameCompleted`
+    screen_updated: bool,
+    /// The rows of the screen changed since `clear_dirty_rows`, with bit `Y`
+    /// set for row `Y`
+    dirty_rows: u64,
+    /// This is a small array containing the state of the keys
+    keypad: [u8; NUM_KEYS],
+    /// This is the source of the random bytes used by the `RND` instruction
+    rng: Box<dyn RandomSource>,
+    /// This is the display the frames are pushed to, if any
+    display: Option<Box<dyn Display>>,
+    /// This is the last frame pushed to the display, if any
+    presented: Option<[u64; FRAME_ROWS]>,
+    /// This is the sink the buzzer is driven through, if any
+    audio_sink: Option<Box<dyn AudioSink>>,
+    /// Flag indicating whether the audio sink was last told to sound
+    sink_sounding: bool,
+    /// This is the audio pattern and pitch the audio sink was last given
+    sink_pattern: Option<([u8; AUDIO_PATTERN_SIZE], u8)>,
+    /// These are the collisions of the sprites drawn in the last frame, being
+    /// the time between the last two timer ticks
+    collisions: Collisions,
+    /// These are the collisions of the sprites drawn since the last timer
+    /// tick
+    pending_collisions: Collisions,
+    /// These are all the executor functions available to our Chip-8
+    /// implementation
+    instructions: [Executor; 27],
+    /// These are the user-registered handlers taking precedence over the
+    /// decoded instructions
+    overrides: Vec<OpcodeOverride>,
+    /// These are the addresses of the instructions executed after the ROM
+    /// wrote to them
+    self_modified: BTreeSet<u16>,
+    /// These are the addresses of the `FX33`/`FX55` instructions which wrote
+    /// into the interpreter area below `PROG_ADDR`
+    reserved_writes: BTreeSet<u16>,
+    /// These are the addresses at which stepping stops before executing the
+    /// instruction
+    breakpoints: BTreeSet<u16>,
+    /// This is the breakpoint reported by the last step, which the next step
+    /// executes rather than reporting again
+    breakpoint_hit: Option<u16>,
+    /// These are the `V` registers whose changes by the ROM are reported
+    watched_registers: BTreeSet<usize>,
+    /// These are the callbacks notified of the events of the emulator
+    listeners: Vec<Box<dyn FnMut(Event) + Send>>,
+    /// Flag indicating whether the ROM jumped to itself since it started,
+    /// so that `Event::Finished` is only reported once
+    finished: bool,
+    /// These are the address and raw opcode of the instructions last
+    /// executed, oldest first, including one which raised an error
+    history: VecDeque<(u16, u16)>,
+}
+
+impl Emulator {
+    /// Creates a new instance of the Chip-8 emulator and initializes all the
+    /// systems required for emulation.
+    ///
+    /// # Params
+    ///
+    /// - `rom_file` - The filepath to the ROM file
+    ///
+    /// # Errors
+    ///
+    /// - If there was an error when loading the ROM file
+    /// - If the ROM doesn't fit in memory
+    #[cfg(feature = "std")]
+    pub fn new(rom_file: &str) -> Result<Self> {
+        Self::from_rom(&memory::read_rom(rom_file)?)
+    }
+
+    /// Creates a new instance of the Chip-8 emulator from the bytes of a ROM,
+    /// for frontends which don't load ROMs from the filesystem
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    ///
+    /// # Errors
+    ///
+    /// If the ROM is empty or doesn't fit in memory
+    pub fn from_rom(rom: &[u8]) -> Result<Self> {
+        Self::with_init(rom, InitPolicy::Zeroed)
+    }
+
+    /// Creates a new instance of the Chip-8 emulator from the bytes of a ROM,
+    /// with the memory and registers filled as the policy dictates whenever
+    /// the ROM is loaded or reset
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    /// - `init` - How the memory and registers are filled
+    ///
+    /// # Errors
+    ///
+    /// If the ROM is empty or doesn't fit in memory
+    pub fn with_init(rom: &[u8], init: InitPolicy) -> Result<Self> {
+        let mut emulator = Self::without_rom(init);
+        emulator.memory.load_rom(rom)?;
+
+        Ok(emulator)
+    }
+
+    /// Creates a new instance of the Chip-8 emulator with nothing loaded at
+    /// the load address, such as for a frontend waiting for a ROM to be
+    /// picked, which is loaded afterwards with `load_rom`
+    ///
+    /// # Params
+    ///
+    /// - `init` - How the memory and registers are filled
+    pub fn without_rom(init: InitPolicy) -> Self {
+        let mut emulator = Self {
+            registers: [0; NUM_REGISTERS],
+            idx: 0,
+            program_counter: memory::PROG_ADDR,
+            delay_timer: 0,
+            sound_timer: 0,
+            stack: CallStack::new(),
+            memory: Memory::without_rom(init, memory::PROG_ADDR),
+            video_buffer: Pixels::new(),
+            resolution: Resolution::Low,
+            two_page: false,
+            halted: false,
+            rpl: [0; NUM_RPL_FLAGS],
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            quirks: Quirks::default(),
+            strict: false,
+            platform: None,
+            vblank: true,
+            screen_updated: true,
+            dirty_rows: ALL_ROWS,
+            keypad: [0; NUM_KEYS],
+            rng: Box::new(Lcg::default()),
+            display: None,
+            presented: None,
+            audio_sink: None,
+            sink_sounding: false,
+            sink_pattern: None,
+            collisions: Collisions::default(),
+            pending_collisions: Collisions::default(),
+            instructions: [
+                Self::raw,
+                Self::cls,
+                Self::ret,
+                Self::sys,
+                Self::jp,
+                Self::call,
+                Self::se,
+                Self::sne,
+                Self::ld,
+                Self::add,
+                Self::or,
+                Self::and,
+                Self::xor,
+                Self::sub,
+                Self::shr,
+                Self::subn,
+                Self::shl,
+                Self::rnd,
+                Self::drw,
+                Self::skp,
+                Self::sknp,
+                Self::scd,
+                Self::scr,
+                Self::scl,
+                Self::exit,
+                Self::low,
+                Self::high,
+            ],
+            overrides: Vec::new(),
+            self_modified: BTreeSet::new(),
+            reserved_writes: BTreeSet::new(),
+            breakpoints: BTreeSet::new(),
+            breakpoint_hit: None,
+            watched_registers: BTreeSet::new(),
+            listeners: Vec::new(),
+            finished: false,
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        };
+        emulator.init_registers();
+
+        emulator
+    }
+
+    /// Creates a new instance of the Chip-8 emulator from arbitrary bytes,
+    /// such as the input of a fuzzer, loading as many of them as fit in
+    /// memory as the ROM
+    ///
+    /// Unlike `from_rom` this can't fail, so every input reaches the
+    /// interpreter.
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The bytes to load as the ROM
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut emulator = Self::without_rom(InitPolicy::Zeroed);
+        let rom = &bytes[..bytes.len().min(memory::MAX_ROM_SIZE)];
+        if !rom.is_empty() {
+            let loaded = emulator.memory.load_rom(rom);
+            loaded.unwrap_or_else(|_| unreachable!("the ROM was cut to fit in memory"));
+        }
+
+        emulator
+    }
+
+    /// Executes up to a number of instructions, ticking the timers after
+    /// every `STEPS_PER_TICK` of them, until the ROM exits or an instruction
+    /// reports a breakpoint or watchpoint
+    ///
+    /// Returns the number of instructions executed. Whatever the ROM, this
+    /// returns or errors rather than panics, which makes it the entry point
+    /// for fuzzing the core.
+    ///
+    /// # Params
+    ///
+    /// - `max_steps` - The most instructions to execute
+    ///
+    /// # Errors
+    ///
+    /// If an instruction errors, see `step`
+    pub fn run_steps(&mut self, max_steps: usize) -> Result<usize> {
+        for executed in 0..max_steps {
+            match self.step()? {
+                StepOutcome::Executed => {}
+                StepOutcome::Watchpoint(_) => return Ok(executed + 1),
+                StepOutcome::Breakpoint(_) | StepOutcome::Halted => return Ok(executed),
+            }
+
+            if (executed + 1) % STEPS_PER_TICK == 0 {
+                self.tick_timers();
+            }
+        }
+
+        Ok(max_steps)
+    }
+
+    /// Emulates a frame, executing a batch of instructions and then counting
+    /// the timers down once, which is to be called at 60Hz
+    ///
+    /// The frame ends early when the ROM exits, an instruction raises an
+    /// error or reports a breakpoint or watchpoint, see `step`. The timers
+    /// aren't counted down after an error, breakpoint or watchpoint, so the
+    /// rest of the frame can be stepped through by a debugger.
+    ///
+    /// # Params
+    ///
+    /// - `instructions_per_frame` - The number of instructions executed per
+    ///   frame, being the CPU speed divided by 60
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// // `CLS`, then `JP 0x202` to loop forever
+    /// let mut emulator = keet8_core::Emulator::from_rom(&[0x00, 0xE0, 0x12, 0x02]).unwrap();
+    ///
+    /// let summary = emulator.step_frame(10);
+    /// assert_eq!(summary.executed, 10);
+    /// assert!(summary.error.is_none());
+    /// ```
+    pub fn step_frame(&mut self, instructions_per_frame: u32) -> FrameSummary {
+        let video_buffer = self.video_buffer;
+        let resolution = self.resolution;
+
+        let mut executed = 0;
+        let mut outcome = StepOutcome::Executed;
+        let mut error = None;
+        while executed < instructions_per_frame {
+            match self.step() {
+                Ok(StepOutcome::Executed) => executed += 1,
+                Ok(StepOutcome::Watchpoint(hit)) => {
+                    executed += 1;
+                    outcome = StepOutcome::Watchpoint(hit);
+                    break;
+                }
+                Ok(stop) => {
+                    outcome = stop;
+                    break;
+                }
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let paused = matches!(outcome, StepOutcome::Breakpoint(_) | StepOutcome::Watchpoint(_));
+        if error.is_none() && !paused {
+            self.tick_timers();
+        }
+
+        FrameSummary {
+            executed,
+            drawn: self.resolution != resolution || self.video_buffer != video_buffer,
+            sound: self.sound_timer > 0,
+            outcome,
+            error,
+        }
+    }
+
+    /// Emulates one CPU cycle by stepping one single instruction
+    ///
+    /// Nothing is executed once the ROM has exited the interpreter. When the
+    /// program counter reaches a breakpoint, the step stops before the
+    /// instruction and reports the breakpoint instead, and the next step
+    /// executes it. An instruction accessing a watched location is executed
+    /// and reports the first such access. The timers aren't affected, see
+    /// `tick_timers`
+    ///
+    /// # Errors
+    ///
+    /// The errors raised by the instruction are wrapped in
+    /// `Keet8Error::InstructionFailed`, along with its address and opcode:
+    ///
+    /// - If the call stack overflowed or was popped whilst empty
+    /// - If an invalid address mode was encountered
+    /// - If memory was accessed past its end or the interpreter area was
+    ///   written with the strict memory quirk
+    /// - If a registered opcode handler returned an error
+    ///
+    /// And on its own, as it holds them already:
+    ///
+    /// - If an unknown opcode was executed with the emulator being strict
+    pub fn step(&mut self) -> Result<StepOutcome> {
+        if self.halted {
+            return Ok(StepOutcome::Halted);
+        }
+
+        let pc = self.program_counter;
+        if self.breakpoint_hit.take() != Some(pc) && self.breakpoints.contains(&pc) {
+            self.breakpoint_hit = Some(pc);
+            return Ok(StepOutcome::Breakpoint(pc));
+        }
+
+        let raw = self.fetch(self.program_counter);
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back((pc, raw));
+        self.memory.count_execute(pc);
+
+        if self.memory.is_written(self.program_counter)
+            || self.memory.is_written(self.program_counter.wrapping_add(1))
+        {
+            self.self_modified.insert(self.program_counter);
+        }
+
+        self.program_counter += 2;
+
+        let registers = self.registers;
+        self.memory.take_hit();
+
+        if !self.execute_override(raw).map_err(|e| e.at_instruction(pc, raw))? {
+            let opcode = match self.platform {
+                Some(platform) if !platform.supports(raw) => OpCode::raw(raw),
+                _ => OpCode::from(raw),
+            };
+            self.instructions[opcode.instr as usize](self, opcode)
+                .map_err(|e| e.at_instruction(pc, raw))?;
+        }
+
+        if let Some(hit) = self.memory.take_hit() {
+            return Ok(StepOutcome::Watchpoint(hit));
+        }
+
+        let changed = self.watched_registers.iter().find(|&&x| registers[x] != self.registers[x]);
+        if let Some(&x) = changed {
+            return Ok(StepOutcome::Watchpoint(WatchHit::Register(x)));
+        }
+
+        Ok(StepOutcome::Executed)
+    }
+
+    /// Restarts the ROM as if it was just loaded
+    ///
+    /// The registers, timers, audio pattern, call stack, screen and keypad
+    /// are cleared and the memory is restored to the image of the ROM, with
+    /// the registers and the rest of the memory filled as the `InitPolicy`
+    /// the emulator was created with dictates, and the access counts start
+    /// over. The quirks, random source, opcode handlers, event callbacks,
+    /// breakpoints, watchpoints, display, audio sink and call stack limit
+    /// are kept, as are the RPL flags, which SCHIP persists across runs, and
+    /// the save RAM.
+    pub fn reset(&mut self) {
+        self.init_registers();
+        self.program_counter = self.entry_point();
+        self.delay_timer = 0;
+        self.update_sound_timer(0);
+        self.audio_pattern = None;
+        self.pitch = DEFAULT_PITCH;
+        self.update_audio_sink();
+        self.stack.clear();
+        self.memory.reset();
+        self.video_buffer.clear();
+        self.resolution = self.base_resolution();
+        self.halted = false;
+        self.vblank = true;
+        self.screen_updated = true;
+        self.dirty_rows = ALL_ROWS;
+        self.keypad = [0; NUM_KEYS];
+        self.presented = None;
+        self.pending_collisions = Collisions::default();
+        self.collisions = Collisions::default();
+        self.breakpoint_hit = None;
+        self.finished = false;
+        self.history.clear();
+    }
+
+    /// Replaces the ROM with another one and restarts it, as `reset` does
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    ///
+    /// # Errors
+    ///
+    /// If the ROM doesn't fit in memory, in which case the emulator carries
+    /// on with the ROM it had
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<()> {
+        self.memory.load_rom(rom)?;
+        self.reset();
+
+        Ok(())
+    }
+
+    /// Counts the delay and sound timers down by one, which is to be called
+    /// at 60Hz regardless of how many instructions are executed per second
+    ///
+    /// This also marks the start of a new frame for the display wait quirk
+    /// and for the collisions
+    pub fn tick_timers(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.update_sound_timer(self.sound_timer.saturating_sub(1));
+
+        self.vblank = true;
+        self.collisions = core::mem::take(&mut self.pending_collisions);
+        self.present_frame();
+
+        let drawn = core::mem::take(&mut self.screen_updated);
+        self.emit(Event::FrameCompleted { drawn });
+    }
+
+    /// Registers a handler for all raw opcodes where `raw & mask == pattern`
+    ///
+    /// The handler runs instead of the decoded instruction, after the program
+    /// counter has been advanced past the opcode. Handlers registered later
+    /// take precedence over earlier ones.
+    ///
+    /// # Params
+    ///
+    /// - `mask` - The bits of the raw opcode to compare
+    /// - `pattern` - The value the masked raw opcode has to equal
+    /// - `handler` - The handler to execute on a match
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// if let Ok(mut emulator) = keet8_core::Emulator::new("game.ch8") {
+    ///     // Treat `01NN` as a host call, storing `NN` in `V0`
+    ///     emulator.register_opcode_handler(0xFF00, 0x0100, |emu, raw| {
+    ///         emu.set_register(0x00, (raw & 0x00FF) as u8);
+    ///         Ok(())
+    ///     });
+    /// }
+    /// ```
+    pub fn register_opcode_handler<F>(&mut self, mask: u16, pattern: u16, handler: F)
+    where
+        F: FnMut(&mut Emulator, u16) -> Result<()> + Send + 'static,
+    {
+        self.overrides
+            .push(OpcodeOverride::new(mask, pattern, Box::new(handler)));
+    }
+
+    /// Enables the keet-8 host-call extension
+    ///
+    /// Once enabled, the otherwise unused `01NN` opcodes let the ROM talk to
+    /// the host, where `NN` selects the service:
+    ///
+    /// - `00` - Print the NUL-terminated string at `I`
+    /// - `01` - Dump the `V` registers
+    /// - `02` - Store the host's UNIX time in seconds into `V0`-`V3`
+    /// - `10` - Signal that a test has passed and exit
+    /// - `11` - Signal that a test has failed with the code in `V0` and exit
+    ///
+    /// # Params
+    ///
+    /// - `callback` - Called with every request the ROM makes to the host
+    pub fn enable_host_calls<F>(&mut self, mut callback: F)
+    where
+        F: FnMut(HostCall) + Send + 'static,
+    {
+        self.register_opcode_handler(
+            hostcall::HOST_CALL_MASK,
+            hostcall::HOST_CALL_PATTERN,
+            move |emu, raw| {
+                if let Some(call) = hostcall::execute(emu, raw) {
+                    callback(call);
+                }
+
+                Ok(())
+            },
+        );
+    }
+
+    /// Registers a callback notified of the events of the emulator, such as
+    /// the screen being cleared or the ROM finishing, see `Event`
+    ///
+    /// The callbacks are called in the order they were registered, whilst
+    /// the instruction (or `tick_timers`) causing the event runs, and are
+    /// kept across `reset`.
+    ///
+    /// # Params
+    ///
+    /// - `callback` - Called with every event
+    pub fn on_event<F>(&mut self, callback: F)
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        self.listeners.push(Box::new(callback));
+    }
+
+    /// Removes all registered opcode handlers
+    pub fn clear_opcode_handlers(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// Attaches a display the frames are pushed to from `tick_timers`
+    ///
+    /// # Params
+    ///
+    /// - `display` - The display to push the frames to
+    pub fn set_display<D>(&mut self, display: D)
+    where
+        D: Display + 'static,
+    {
+        self.display = Some(Box::new(display));
+        self.presented = None;
+    }
+
+    /// Attaches a sink the buzzer is driven through as the sound timer, audio
+    /// pattern and pitch change
+    ///
+    /// The sink is told about the current state of the buzzer right away, so
+    /// it can be attached whilst the buzzer sounds.
+    ///
+    /// # Params
+    ///
+    /// - `sink` - The sink to drive the buzzer through
+    pub fn set_audio_sink<A>(&mut self, sink: A)
+    where
+        A: AudioSink + 'static,
+    {
+        self.audio_sink = Some(Box::new(sink));
+        self.sink_sounding = false;
+        self.sink_pattern = None;
+        self.update_audio_sink();
+    }
+
+    /// Replaces the source of the random bytes used by the `RND` instruction
+    ///
+    /// By default a [Lcg] is used, seeded from the OS when the `frontend`
+    /// feature is enabled
+    ///
+    /// # Params
+    ///
+    /// - `source` - The new source of random bytes
+    pub fn set_random_source<R>(&mut self, source: R)
+    where
+        R: RandomSource + 'static,
+    {
+        self.rng = Box::new(source);
+    }
+
+    /// Adds a breakpoint, at which `step` stops before executing the
+    /// instruction
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the instruction to stop at
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Removes a breakpoint, returning whether there was one at the address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the breakpoint
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Gets the addresses of the breakpoints, in order of address
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Watches a memory address, so that `step` reports the instructions
+    /// reading or writing it
+    ///
+    /// Only accesses made by the instructions count, not fetching them.
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to watch
+    pub fn add_watchpoint(&mut self, addr: u16) {
+        self.memory.watch(addr);
+    }
+
+    /// Stops watching a memory address, returning whether it was watched
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to stop watching
+    pub fn remove_watchpoint(&mut self, addr: u16) -> bool {
+        self.memory.unwatch(addr)
+    }
+
+    /// Gets the watched memory addresses, in order of address
+    pub fn watchpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.memory.watched()
+    }
+
+    /// Watches a `V` register, so that `step` reports the instructions
+    /// changing its value
+    ///
+    /// # Params
+    ///
+    /// - `x` - The index of the register (`0x0` to `0xF`)
+    pub fn add_register_watchpoint(&mut self, x: usize) {
+        self.watched_registers.insert(x);
+    }
+
+    /// Stops watching a `V` register, returning whether it was watched
+    ///
+    /// # Params
+    ///
+    /// - `x` - The index of the register (`0x0` to `0xF`)
+    pub fn remove_register_watchpoint(&mut self, x: usize) -> bool {
+        self.watched_registers.remove(&x)
+    }
+
+    /// Gets the watched `V` registers, in order of index
+    pub fn register_watchpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.watched_registers.iter().copied()
+    }
+
+    /// Writes a raw opcode into memory, for trying out changes to the running
+    /// ROM
+    ///
+    /// The patched instruction doesn't count as self-modifying code, and a
+    /// reset restores the original ROM.
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to write the opcode to
+    /// - `opcode` - The raw opcode to write
+    pub fn patch(&mut self, addr: u16, opcode: u16) {
+        let [high, low] = opcode.to_be_bytes();
+        self.memory.patch(addr, high);
+        self.memory.patch(addr.wrapping_add(1), low);
+    }
+
+    /// Writes a single byte into memory, such as sprite data, the same way
+    /// `patch` writes an opcode
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to write the byte to
+    /// - `val` - The byte to write
+    pub fn poke(&mut self, addr: u16, val: u8) {
+        self.memory.patch(addr, val);
+    }
+
+    /// Gets the value of the `V` register
+    ///
+    /// # Params
+    ///
+    /// - `x` - The index of the register (`0x0` to `0xF`)
+    pub fn register(&self, x: usize) -> u8 {
+        self.registers[x]
+    }
+
+    /// Assigns a value to the `V` register
+    ///
+    /// # Params
+    ///
+    /// - `x` - The index of the register (`0x0` to `0xF`)
+    /// - `val` - The value to assign to the register
+    pub fn set_register(&mut self, x: usize, val: u8) {
+        self.registers[x] = val;
+    }
+
+    /// Gets the current value of the program counter
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    /// Reads the raw opcode stored at an address in memory
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the opcode
+    pub fn fetch(&self, addr: u16) -> u16 {
+        ((self.memory[addr] as u16) << 8) | (self.memory[addr.wrapping_add(1)] as u16)
+    }
+
+    /// Starts counting the reads, writes and executions of every address of
+    /// memory from zero, or stops counting them and discards the counts
+    ///
+    /// Counting is off by default, as it costs a little on every access.
+    ///
+    /// # Params
+    ///
+    /// - `enabled` - Whether the accesses are counted
+    pub fn set_access_counting(&mut self, enabled: bool) {
+        self.memory.set_counting(enabled);
+    }
+
+    /// Gets the reads, writes and executions of every address of memory since
+    /// the ROM started, if they are counted
+    pub fn access_counts(&self) -> Option<&AccessCounts> {
+        self.memory.counts()
+    }
+
+    /// Gets the whole memory space, indexed by address, for inspecting it
+    /// without fetching byte by byte
+    ///
+    /// This is 4KB unless the memory was resized, see `set_memory_size`.
+    pub fn memory(&self) -> &[u8] {
+        self.memory.as_slice()
+    }
+
+    /// Gets the number of bytes of memory, past which addresses wrap around
+    pub fn memory_size(&self) -> usize {
+        self.memory.size()
+    }
+
+    /// Resizes the memory and restarts the ROM, as `reset` does, such as to
+    /// the 64KB of XO-CHIP
+    ///
+    /// The memory is `MEMORY_SIZE` bytes until then. Watchpoints and save RAM
+    /// past the end of the new memory are dropped.
+    ///
+    /// # Params
+    ///
+    /// - `size` - The number of bytes of memory, being a power of two from
+    ///   `MEMORY_SIZE` to `MAX_MEMORY_SIZE`
+    ///
+    /// # Errors
+    ///
+    /// - If the size isn't a power of two from `MEMORY_SIZE` to
+    ///   `MAX_MEMORY_SIZE`
+    /// - If the ROM doesn't fit in the new memory
+    ///
+    /// In both cases the emulator carries on as it was.
+    pub fn set_memory_size(&mut self, size: usize) -> Result<()> {
+        self.memory.set_size(size)?;
+        self.reset();
+
+        Ok(())
+    }
+
+    /// Gets the current value of the index register
+    pub fn index(&self) -> u16 {
+        self.idx
+    }
+
+    /// Gets the current value of the delay timer
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    /// Gets the current value of the sound timer
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    /// Gets the audio pattern (XO-CHIP) loaded by `F002`, if any, being 128
+    /// bits played from the most significant bit of the first byte whilst the
+    /// sound timer is non-zero
+    pub fn audio_pattern(&self) -> Option<&[u8; AUDIO_PATTERN_SIZE]> {
+        self.audio_pattern.as_ref()
+    }
+
+    /// Gets the pitch (XO-CHIP) set by `FX3A`, giving the playback rate of the
+    /// audio pattern as `4000 * 2^((pitch - 64) / 48)` bits per second
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Gets the quirks the instructions are executed with
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Assigns the quirks to execute the instructions with
+    ///
+    /// # Params
+    ///
+    /// - `quirks` - The quirks to execute the instructions with
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Sets whether unknown opcodes raise an error instead of being skipped,
+    /// surfacing malformed ROMs and gaps in the decoder
+    ///
+    /// # Params
+    ///
+    /// - `strict` - Whether unknown opcodes raise an error
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Gets the platform the opcodes are restricted to, if any
+    pub fn platform(&self) -> Option<Platform> {
+        self.platform
+    }
+
+    /// Restricts the opcodes to those of a platform, treating the opcodes of
+    /// later platforms as unknown opcodes, or lifts the restriction
+    ///
+    /// The call stack takes the limit of the platform, see
+    /// `Platform::stack_limit`, or `STACK_SIZE` when the restriction is
+    /// lifted. The memory takes the size of the platform, see
+    /// `Platform::memory_size`, or `MEMORY_SIZE` when the restriction is
+    /// lifted, restarting the ROM if the size changes. A ROM which doesn't
+    /// fit in the memory of the platform keeps the memory it has. The quirks
+    /// aren't changed, see `Platform::quirks`. Registered opcode handlers
+    /// still run for any opcode.
+    ///
+    /// # Params
+    ///
+    /// - `platform` - The platform, or `None` for every opcode
+    pub fn set_platform(&mut self, platform: Option<Platform>) {
+        self.platform = platform;
+        self.stack.set_limit(platform.map_or(STACK_SIZE, Platform::stack_limit));
+
+        let size = platform.map_or(MEMORY_SIZE, Platform::memory_size);
+        if size != self.memory.size() {
+            self.set_memory_size(size).ok();
+        }
+    }
+
+    /// Gets the address the ROM is loaded and starts at
+    pub fn load_address(&self) -> u16 {
+        self.memory.load_address()
+    }
+
+    /// Loads the ROM at another address and restarts it from there, as
+    /// `reset` does, such as `ETI660_PROG_ADDR` for the ROMs of the ETI-660
+    ///
+    /// The ROM is loaded at `PROG_ADDR` until then.
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to load the ROM at
+    ///
+    /// # Errors
+    ///
+    /// If the ROM doesn't fit in memory from the address, in which case the
+    /// emulator carries on as it was
+    pub fn set_load_address(&mut self, addr: u16) -> Result<()> {
+        self.memory.set_load_address(addr)?;
+        self.reset();
+
+        Ok(())
+    }
+
+    /// Checks whether the ROM runs in the two-page mode (hi-res Chip-8)
+    pub fn is_two_page(&self) -> bool {
+        self.two_page
+    }
+
+    /// Sets whether the ROM runs in the two-page mode (hi-res Chip-8) and
+    /// restarts it, as `reset` does
+    ///
+    /// In the two-page mode the screen is 64x64 pixels and `0230` clears it.
+    /// A ROM starting with `JP 0x260`, see `is_two_page_rom`, starts at
+    /// `TWO_PAGE_ENTRY` instead, skipping the patch of the interpreter it
+    /// carries.
+    ///
+    /// # Params
+    ///
+    /// - `enabled` - Whether the ROM runs in the two-page mode
+    pub fn set_two_page(&mut self, enabled: bool) {
+        self.two_page = enabled;
+        self.reset();
+    }
+
+    /// Gets the hash of the loaded ROM, which identifies the ROM across
+    /// sessions
+    pub fn rom_hash(&self) -> u64 {
+        self.memory.rom_hash()
+    }
+
+    /// Gets the RPL user flags (SCHIP), which `FX75` stores the registers in
+    pub fn rpl_flags(&self) -> &[u8] {
+        &self.rpl
+    }
+
+    /// Assigns the RPL user flags (SCHIP), such as the ones persisted by a
+    /// previous run, ignoring the values past the last flag
+    ///
+    /// # Params
+    ///
+    /// - `flags` - The values of the flags, from the first one
+    pub fn set_rpl_flags(&mut self, flags: &[u8]) {
+        let len = flags.len().min(NUM_RPL_FLAGS);
+        self.rpl[..len].copy_from_slice(&flags[..len]);
+    }
+
+    /// Sets the addresses of the battery-backed save RAM, which keeps its
+    /// contents across `reset` and `load_rom` so a frontend can persist it
+    /// across runs, such as for the high scores of homebrew ROMs
+    ///
+    /// # Params
+    ///
+    /// - `range` - The addresses of the save RAM, cut off at the end of
+    ///   memory, or `None` for no save RAM
+    pub fn set_save_ram(&mut self, range: Option<RangeInclusive<u16>>) {
+        self.memory.set_save_ram(range);
+    }
+
+    /// Gets the contents of the save RAM, which is empty without one
+    pub fn save_ram(&self) -> &[u8] {
+        self.memory.save_ram()
+    }
+
+    /// Restores the contents of the save RAM, such as the ones persisted by a
+    /// previous run, ignoring the bytes past its end
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The contents of the save RAM, from its first address
+    pub fn restore_save_ram(&mut self, bytes: &[u8]) {
+        self.memory.restore_save_ram(bytes);
+    }
+
+    /// Gets the return addresses on the call stack, from the outermost call to
+    /// the innermost one
+    pub fn call_stack(&self) -> &[u16] {
+        self.stack.as_slice()
+    }
+
+    /// Gets the number of return addresses the call stack holds before
+    /// `CALL` raises `CallStackFull`
+    pub fn stack_limit(&self) -> usize {
+        self.stack.limit()
+    }
+
+    /// Sets the number of return addresses the call stack holds before
+    /// `CALL` raises `CallStackFull`, as the interpreter being emulated did
+    ///
+    /// # Params
+    ///
+    /// - `limit` - The number of return addresses, capped at `STACK_SIZE`
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.stack.set_limit(limit);
+    }
+
+    /// Gets the address and raw opcode of the last instructions executed
+    /// since the ROM was loaded or reset, oldest first
+    ///
+    /// The last one is the instruction which raised the error when `step`
+    /// fails, which with the registers tells how the ROM got there.
+    pub fn history(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.history.iter().copied()
+    }
+
+    /// Gets the addresses of the instructions the ROM executed after writing
+    /// to them (self-modifying code), in order of address
+    ///
+    /// Only writes made by the ROM itself count, not the loading of the ROM
+    /// or of a save state
+    pub fn self_modified(&self) -> impl Iterator<Item = u16> + '_ {
+        self.self_modified.iter().copied()
+    }
+
+    /// Gets the addresses of the `FX33`/`FX55` instructions which wrote into
+    /// the interpreter area (`0x000` to `0x1FF`), holding the fonts
+    ///
+    /// Such writes usually point at a bug in the ROM, such as a bad `I`,
+    /// which wrapping memory accesses would otherwise hide. With the strict
+    /// memory quirk enabled they raise an error instead.
+    pub fn reserved_writes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.reserved_writes.iter().copied()
+    }
+
+    /// Checks whether the ROM has exited the interpreter with `EXIT`
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Gets a copy of the pixels of the screen in the current resolution mode
+    /// as bytes
+    ///
+    /// The pixels are copied row by row, `screen_width` pixels per row, with
+    /// every non-zero pixel being on. The screen is `LORES_WIDTH` by
+    /// `LORES_HEIGHT` pixels, `HIRES_WIDTH` by `HIRES_HEIGHT` in the high
+    /// resolution mode (SCHIP), or `LORES_WIDTH` by `TWO_PAGE_HEIGHT` in the
+    /// two-page mode (hi-res Chip-8). `pixels` reads them without copying.
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.video_buffer.to_bytes(self.screen_width(), self.screen_height())
+    }
+
+    /// Gets the pixels of the screen packed as bits, of which the ones within
+    /// `screen_width` and `screen_height` are in use
+    pub fn pixels(&self) -> &Pixels {
+        &self.video_buffer
+    }
+
+    /// Gets the screen in the current resolution mode, to be presented by a
+    /// `Renderer`, with the rows changed since `clear_dirty_rows`
+    pub fn frame(&self) -> FrameBuffer<'_> {
+        FrameBuffer::new(&self.video_buffer, self.screen_width(), self.screen_height())
+            .with_dirty_rows(self.dirty_rows)
+    }
+
+    /// Gets the rows of the screen changed since `clear_dirty_rows`, with bit
+    /// `Y` set for row `Y`
+    ///
+    /// Every row counts as changed after clearing the screen, scrolling it or
+    /// switching the resolution mode, and a sprite only marks the rows whose
+    /// pixels it flipped.
+    pub fn dirty_rows(&self) -> u64 {
+        self.dirty_rows
+    }
+
+    /// Forgets the rows changed so far, to be called once the screen has been
+    /// presented so that the next frame only redraws what changed since
+    pub fn clear_dirty_rows(&mut self) {
+        self.dirty_rows = 0;
+    }
+
+    /// Gets the FNV-1a hash of the screen, which is the same on every machine
+    /// emulating the same frame
+    ///
+    /// Only whether the pixels are on counts, and the resolution mode does as
+    /// well, so two emulators showing the same picture hash the same.
+    pub fn framebuffer_hash(&self) -> u64 {
+        let pixels = self
+            .framebuffer()
+            .iter()
+            .map(|&pixel| (pixel > 0) as u8)
+            .collect::<Vec<u8>>();
+
+        hash::fnv1a(&pixels)
+    }
+
+    /// Gets the collisions of the sprites drawn in the last frame, being the
+    /// time between the last two calls to `tick_timers`
+    pub fn collisions(&self) -> &Collisions {
+        &self.collisions
+    }
+
+    /// Gets the screen packed into 32 rows of 64 pixels, as pushed to a
+    /// `Display`
+    pub fn packed_rows(&self) -> [u64; FRAME_ROWS] {
+        display::pack_rows(&self.video_buffer, self.screen_width(), self.screen_height())
+    }
+
+    /// Gets the current resolution mode of the screen
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Gets the width in pixels of the screen in the current resolution mode
+    pub fn screen_width(&self) -> usize {
+        self.resolution.width()
+    }
+
+    /// Gets the height in pixels of the screen in the current resolution mode
+    pub fn screen_height(&self) -> usize {
+        self.resolution.height()
+    }
+
+    /// Notifies the registered callbacks of an event
+    ///
+    /// # Params
+    ///
+    /// - `event` - The event which happened
+    fn emit(&mut self, event: Event) {
+        for listener in &mut self.listeners {
+            listener(event);
+        }
+    }
+
+    /// Fills the `V` registers and `I` as the `InitPolicy` of the memory
+    /// dictates, `I` only holding addresses within memory
+    fn init_registers(&mut self) {
+        let mut garbage = [0; NUM_REGISTERS + 2];
+        self.memory.init().fill(&mut garbage, REGISTER_STREAM);
+
+        let (registers, idx) = garbage.split_at(NUM_REGISTERS);
+        self.registers.copy_from_slice(registers);
+        self.idx = u16::from_be_bytes([idx[0], idx[1]]) & 0xFFF;
+    }
+
+    /// Assigns the sound timer, reporting when the buzzer starts or stops
+    ///
+    /// # Params
+    ///
+    /// - `val` - The new value of the sound timer
+    fn update_sound_timer(&mut self, val: u8) {
+        let was_sounding = self.sound_timer > 0;
+        self.sound_timer = val;
+
+        match (was_sounding, val > 0) {
+            (false, true) => self.emit(Event::SoundStarted),
+            (true, false) => self.emit(Event::SoundStopped),
+            _ => {}
+        }
+
+        self.update_audio_sink();
+    }
+
+    /// Tells the audio sink, if any, about the changes of the buzzer since it
+    /// was last told about it
+    fn update_audio_sink(&mut self) {
+        let Some(sink) = &mut self.audio_sink else {
+            return;
+        };
+
+        let pattern = self.audio_pattern.map(|pattern| (pattern, self.pitch));
+        if pattern != self.sink_pattern {
+            sink.pattern(pattern.as_ref().map(|(pattern, _)| pattern), self.pitch);
+            self.sink_pattern = pattern;
+        }
+
+        let sounding = self.sound_timer > 0;
+        if sounding != self.sink_sounding {
+            if sounding {
+                sink.beep_start();
+            } else {
+                sink.beep_stop();
+            }
+            self.sink_sounding = sounding;
+        }
+    }
+
+    /// Resolves the address at an offset from the index register, honoring
+    /// the strict and clamp memory quirks
+    ///
+    /// # Params
+    ///
+    /// - `offset` - The offset from the index register
+    ///
+    /// # Errors
+    ///
+    /// If the address lies past the end of memory with the strict memory
+    /// quirk enabled
+    fn index_address(&self, offset: usize) -> Result<u16> {
+        memory::offset_address(self.idx, offset, self.quirks, self.memory.size())
+    }
+
+    /// Pushes the screen to the display, if one is attached and the screen
+    /// changed since the last frame
+    fn present_frame(&mut self) {
+        if self.display.is_none() {
+            return;
+        }
+
+        let rows = self.packed_rows();
+        if self.presented == Some(rows) {
+            return;
+        }
+
+        if let Some(display) = &mut self.display {
+            display.on_frame(&rows);
+        }
+
+        self.presented = Some(rows);
+    }
+
+    /// Notes a write by the current instruction into the interpreter area,
+    /// which is rejected with the strict memory quirk enabled
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address written to
+    ///
+    /// # Errors
+    ///
+    /// If the address lies in the interpreter area with the strict memory
+    /// quirk enabled
+    fn check_reserved_write(&mut self, addr: u16) -> Result<()> {
+        if addr >= memory::PROG_ADDR {
+            return Ok(());
+        }
+
+        if self.quirks.strict_memory {
+            return Err(Keet8Error::ReservedAreaWrite(addr));
+        }
+
+        // The program counter has already moved past the instruction
+        self.reserved_writes.insert(self.program_counter.wrapping_sub(2));
+        Ok(())
+    }
+
+    /// Switches to another resolution mode, which also clears the screen
+    ///
+    /// # Params
+    ///
+    /// - `resolution` - The resolution mode to switch to
+    fn set_resolution(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        self.video_buffer.clear();
+        self.screen_updated = true;
+        self.dirty_rows = ALL_ROWS;
+
+        // The pixels of the collisions lie elsewhere in the other mode
+        self.pending_collisions = Collisions::default();
+        self.collisions = Collisions::default();
+    }
+
+    /// Gets the resolution mode the ROM starts in, which `LOW` returns to
+    fn base_resolution(&self) -> Resolution {
+        if self.two_page {
+            Resolution::TwoPage
+        } else {
+            Resolution::Low
+        }
+    }
+
+    /// Gets the address the ROM starts at, being its load address, or past
+    /// the patch of the interpreter for two-page ROMs
+    fn entry_point(&self) -> u16 {
+        let start = self.memory.load_address();
+        if self.two_page && is_two_page_rom(&self.memory.as_slice()[start as usize..]) {
+            TWO_PAGE_ENTRY
+        } else {
+            start
+        }
+    }
+
+    /// Sets whether a key on the keypad is held down
+    ///
+    /// # Params
+    ///
+    /// - `key` - The key on the keypad
+    /// - `pressed` - Whether the key is held down
+    pub fn set_key(&mut self, key: Key, pressed: bool) {
+        self.keypad[key.index()] = pressed as u8;
+    }
+
+    /// Holds a key on the keypad down
+    ///
+    /// # Params
+    ///
+    /// - `key` - The key on the keypad
+    pub fn press(&mut self, key: Key) {
+        self.set_key(key, true);
+    }
+
+    /// Lets go of a key on the keypad
+    ///
+    /// # Params
+    ///
+    /// - `key` - The key on the keypad
+    pub fn release(&mut self, key: Key) {
+        self.set_key(key, false);
+    }
+
+    /// Sets which keys of the keypad are held down, all at once
+    ///
+    /// # Params
+    ///
+    /// - `keys` - The keys held down, with bit `K` set for key `K`
+    pub fn set_all(&mut self, keys: u16) {
+        for key in Key::ALL {
+            self.set_key(key, keys >> key.index() & 1 != 0);
+        }
+    }
+
+    /// Executes the most recently registered handler matching the raw opcode
+    ///
+    /// Returns `true` if a handler was executed, `false` otherwise
+    ///
+    /// # Params
+    ///
+    /// - `raw` - The raw opcode fetched from memory
+    ///
+    /// # Errors
+    ///
+    /// If the handler returned an error
+    fn execute_override(&mut self, raw: u16) -> Result<bool> {
+        if self.overrides.is_empty() {
+            return Ok(false);
+        }
+
+        // The handlers need mutable access to the emulator, so they are moved
+        // out for the duration of the call
+        let mut overrides = core::mem::take(&mut self.overrides);
+        let result = match overrides.iter_mut().rev().find(|o| o.matches(raw)) {
+            Some(o) => (o.handler)(self, raw).map(|_| true),
+            None => Ok(false),
+        };
+
+        // Keep any handlers registered from within the handler itself
+        overrides.append(&mut self.overrides);
+        self.overrides = overrides;
+
+        result
+    }
+
+    /// Executes the `RAW` instruction, being an opcode which doesn't decode
+    /// to any instruction
+    ///
+    /// This only reports `Event::UnknownOpcode`, unless the emulator is
+    /// strict. In the two-page mode, `0230` calls the routine of the patched
+    /// interpreter which clears the screen.
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing execution context
+    ///
+    /// # Errors
+    ///
+    /// If the emulator is strict
+    fn raw(&mut self, opcode: OpCode) -> Result<()> {
+        let AddressMode::OpCode { opcode } = opcode.address_mode else {
+            return Ok(());
+        };
+        if opcode == TWO_PAGE_CLS && self.resolution == Resolution::TwoPage {
+            return self.cls(OpCode::raw(opcode));
+        }
+
+        let addr = self.program_counter.wrapping_sub(2);
+        self.emit(Event::UnknownOpcode { opcode, addr });
+        if self.strict {
+            return Err(Keet8Error::UnknownOpcode(opcode, addr));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `CLS` instruction
+    ///
+    /// This clears the video buffer, essentially clearing the screen
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of [Executor]
+    fn cls(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        self.video_buffer.clear();
+        self.screen_updated = true;
+        self.dirty_rows = ALL_ROWS;
+        self.emit(Event::ScreenCleared);
+        Ok(())
+    }
+
+    /// Executes the `RET` instruction
+    ///
+    /// This returns from the current function the program counter is in
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If the call stack was empty when attempting to pop the previous
+    /// address off of
+    fn ret(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        if let Some(addr) = self.stack.pop() {
+            self.program_counter = addr;
+        } else {
+            return Err(Keet8Error::CallStackEmpty);
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SYS` instruction
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of `Executor`
+    fn sys(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        Ok(())
+    }
+
+    /// Executes the `JP` instruction
+    ///
+    /// This sets the program counter to an address to jump to
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn jp(&mut self, opcode: OpCode) -> Result<()> {
+        match opcode.address_mode {
+            AddressMode::Addr { address } => {
+                // A jump to itself is how most ROMs end
+                if address == self.program_counter.wrapping_sub(2) && !self.finished {
+                    self.finished = true;
+                    self.emit(Event::Finished { addr: address });
+                }
+
+                self.program_counter = address;
+            }
+            AddressMode::V0Addr { address } => {
+                let x = if self.quirks.jump_vx {
+                    ((address & 0x0F00) >> 8) as usize
+                } else {
+                    0x00
+                };
+
+                self.program_counter = self.registers[x] as u16 + address
+            }
+            _ => return Err(Keet8Error::InvalidAddressMode(opcode.address_mode)),
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `CALL` instruction
+    ///
+    /// This does a function call by means of pushing the current value of the
+    /// program counter and then jumping to the address of the called function
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// - If an invalid address mode was provided
+    /// - If the call stack limit has been reached
+    fn call(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::Addr { address } = opcode.address_mode {
+            self.stack.push(self.program_counter)?;
+            self.program_counter = address;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SE` instruction
+    ///
+    /// Skips the instruction if two values are equal
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn se(&mut self, opcode: OpCode) -> Result<()> {
+        match opcode.address_mode {
+            AddressMode::VxByte { x, byte } => {
+                if self.registers[x] == byte {
+                    self.program_counter += 2;
+                }
+            }
+            AddressMode::VxVy { x, y } => {
+                if self.registers[x] == self.registers[y] {
+                    self.program_counter += 2;
+                }
+            }
+            _ => return Err(Keet8Error::InvalidAddressMode(opcode.address_mode)),
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SNE` instruction
+    ///
+    /// Skips the instruction if two values are not equal
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn sne(&mut self, opcode: OpCode) -> Result<()> {
+        match opcode.address_mode {
+            AddressMode::VxByte { x, byte } => {
+                if self.registers[x] != byte {
+                    self.program_counter += 2;
+                }
+            }
+            AddressMode::VxVy { x, y } => {
+                if self.registers[x] != self.registers[y] {
+                    self.program_counter += 2;
+                }
+            }
+            _ => return Err(Keet8Error::InvalidAddressMode(opcode.address_mode)),
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `LD` instruction
+    ///
+    /// Loads a value into the specified register
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn ld(&mut self, opcode: OpCode) -> Result<()> {
+        match opcode.address_mode {
+            AddressMode::VxByte { x, byte } => {
+                self.registers[x] = byte;
+            }
+            AddressMode::VxVy { x, y } => {
+                self.registers[x] = self.registers[y];
+            }
+            AddressMode::IAddr { address } => {
+                self.idx = address;
+            }
+            AddressMode::VxDt { x } => {
+                self.registers[x] = self.delay_timer;
+            }
+            AddressMode::VxKey { x } => {
+                let mut found = false;
+                for i in 0..NUM_KEYS {
+                    if self.keypad[i] > 0 {
+                        found = true;
+                        self.registers[x] = i as u8;
+                        break;
+                    }
+                }
+
+                if !found {
+                    self.program_counter -= 2;
+                }
+            }
+            AddressMode::DtVx { x } => {
+                self.delay_timer = self.registers[x];
+            }
+            AddressMode::StVx { x } => {
+                self.update_sound_timer(self.registers[x]);
+            }
+            AddressMode::FontVx { x } => {
+                let digit = self.registers[x];
+                self.idx = memory::FONT_ADDR + (5 * digit as u16);
+            }
+            AddressMode::HFontVx { x } => {
+                let digit = self.registers[x] & 0x0F;
+                self.idx = memory::BIG_FONT_ADDR + (10 * digit as u16);
+            }
+            AddressMode::BcdVx { x } => {
+                let value = self.registers[x];
+                let digits = [value / 100, (value / 10) % 10, value % 10];
+
+                for (i, digit) in digits.into_iter().enumerate() {
+                    let addr = self.index_address(i)?;
+                    self.check_reserved_write(addr)?;
+                    self.memory.write(addr, digit);
+                }
+            }
+            AddressMode::AddrIVx { x } => {
+                for i in 0..=x {
+                    let addr = self.index_address(i)?;
+                    self.check_reserved_write(addr)?;
+                    self.memory.write(addr, self.registers[i]);
+                }
+                if self.quirks.load_store_increment {
+                    self.idx = self.idx.wrapping_add(x as u16 + 1);
+                }
+            }
+            AddressMode::VxAddrI { x } => {
+                for i in 0..=x {
+                    let addr = self.index_address(i)?;
+                    self.registers[i] = self.memory.read(addr);
+                }
+                if self.quirks.load_store_increment {
+                    self.idx = self.idx.wrapping_add(x as u16 + 1);
+                }
+            }
+            AddressMode::RplVx { x } => {
+                let x = x.min(NUM_RPL_FLAGS - 1);
+                self.rpl[..=x].copy_from_slice(&self.registers[..=x]);
+            }
+            AddressMode::VxRpl { x } => {
+                let x = x.min(NUM_RPL_FLAGS - 1);
+                self.registers[..=x].copy_from_slice(&self.rpl[..=x]);
+            }
+            AddressMode::AudioI => {
+                let mut pattern = [0; AUDIO_PATTERN_SIZE];
+                for (i, byte) in pattern.iter_mut().enumerate() {
+                    *byte = self.memory.read(self.index_address(i)?);
+                }
+                self.audio_pattern = Some(pattern);
+                self.update_audio_sink();
+            }
+            AddressMode::PitchVx { x } => {
+                self.pitch = self.registers[x];
+                self.update_audio_sink();
+            }
+            _ => return Err(Keet8Error::InvalidAddressMode(opcode.address_mode)),
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `ADD` instruction
+    ///
+    /// Adds a value to the specified register and sets the overflow flag
+    /// if an overflow has occured
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn add(&mut self, opcode: OpCode) -> Result<()> {
+        match opcode.address_mode {
+            AddressMode::VxByte { x, byte } => {
+                self.registers[x] = self.registers[x].overflowing_add(byte).0;
+            }
+            AddressMode::VxVy { x, y } => {
+                let sum = self.registers[x] as u16 + self.registers[y] as u16;
+
+                // The flag is written last, so it wins when `VX` is `VF`
+                self.registers[x] = (sum & 0x00FF) as u8;
+                self.registers[0x0F] = (sum > 0x00FF) as u8;
+            }
+            AddressMode::IVx { x } => {
+                let sum = self.idx as u32 + self.registers[x] as u32;
+                if self.quirks.index_overflow {
+                    self.registers[0x0F] = (sum > 0x0FFF) as u8;
+                }
+
+                self.idx = sum as u16;
+            }
+            _ => return Err(Keet8Error::InvalidAddressMode(opcode.address_mode)),
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `OR` instruction
+    ///
+    /// Performs a bitwise "or" on with the specified registers
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn or(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            self.registers[x] |= self.registers[y];
+            if self.quirks.vf_reset {
+                self.registers[0x0F] = 0;
+            }
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `AND` instruction
+    ///
+    /// Performs a bitwise "and" on with the specified registers
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn and(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            self.registers[x] &= self.registers[y];
+            if self.quirks.vf_reset {
+                self.registers[0x0F] = 0;
+            }
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `XOR` instruction
+    ///
+    /// Performs a bitwise "xor" on with the specified registers
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn xor(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            self.registers[x] ^= self.registers[y];
+            if self.quirks.vf_reset {
+                self.registers[0x0F] = 0;
+            }
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SUB` instruction
+    ///
+    /// Subtracts the specified registers from one another and sets the
+    /// overflow flag if an overflow has occured
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn sub(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            let (vx, vy) = (self.registers[x], self.registers[y]);
+
+            self.registers[x] = vx.wrapping_sub(vy);
+            self.registers[0x0F] = (vx >= vy) as u8;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SHR` instruction
+    ///
+    /// Shifts the value register to the right by one and sets the overflow
+    /// flag if an overflow has occured
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn shr(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            let src = if self.quirks.shift_vy { y } else { x };
+            let value = self.registers[src];
+
+            self.registers[x] = value >> 1;
+            self.registers[0x0F] = value & 0x01;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SUBN` instruction
+    ///
+    /// Sets the `VX` to the value of `VY` minus `VX` and sets the overflow
+    /// flag if an overflow has occured
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn subn(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            let (vx, vy) = (self.registers[x], self.registers[y]);
+
+            self.registers[x] = vy.wrapping_sub(vx);
+            self.registers[0x0F] = (vy >= vx) as u8;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SHL` instruction
+    ///
+    /// Shifts the value register to the left by one and sets the overflow
+    /// flag if an overflow has occured
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn shl(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVy { x, y } = opcode.address_mode {
+            let src = if self.quirks.shift_vy { y } else { x };
+            let value = self.registers[src];
+
+            self.registers[x] = value << 1;
+            self.registers[0x0F] = (value & 0x80) >> 7;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `RND` instruction
+    ///
+    /// Generates a random value between 0 and 255 and masks it by the
+    /// specified byte value
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn rnd(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxByte { x, byte } = opcode.address_mode {
+            self.registers[x] = self.rng.next_u8() & byte;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `DRW` instruction
+    ///
+    /// Display `N`-byte sprite starting at memory location `I` at (`VX`, `VY`)
+    /// Each set bit of xored with what's already drawn. `VF` is set to `1` if
+    /// a collision occurs. `0` otherwise
+    ///
+    /// When `N` is `0`, a 16x16 sprite of 32 bytes is drawn instead (SCHIP).
+    /// Sprites are clipped at the edges of the screen, unless the sprite
+    /// wrapping quirk is enabled
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn drw(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::VxVyN { x, y, nibble } = opcode.address_mode {
+            // Retry this instruction on the next step until a new frame has
+            // started
+            if self.quirks.display_wait && !self.vblank {
+                self.program_counter -= 2;
+                return Ok(());
+            }
+
+            self.vblank = false;
+            self.screen_updated = true;
+
+            let width = self.screen_width();
+            let height = self.screen_height();
+            let (sprite_width, sprite_height) = match nibble {
+                0 => (16, 16),
+                n => (8, n as usize),
+            };
+
+            let xp = self.registers[x] as usize % width;
+            let yp = self.registers[y] as usize % height;
+
+            let (rows, cols) = if self.quirks.wrap_sprites {
+                (sprite_height, sprite_width)
+            } else {
+                (sprite_height.min(height - yp), sprite_width.min(width - xp))
+            };
+
+            self.registers[0x0F] = 0;
+            for r in 0..rows {
+                let offset = r * sprite_width / 8;
+                let sprite = if sprite_width == 16 {
+                    let high = self.memory.read(self.index_address(offset)?) as u16;
+                    (high << 8) | (self.memory.read(self.index_address(offset + 1)?) as u16)
+                } else {
+                    (self.memory.read(self.index_address(offset)?) as u16) << 8
+                };
+
+                let py = (yp + r) % height;
+                for c in (0..cols).filter(|c| sprite & (0x8000 >> c) != 0) {
+                    let px = (xp + c) % width;
+                    if self.video_buffer.toggle(px, py) {
+                        self.registers[0x0F] = 1;
+                        self.pending_collisions.add_pixel(py * width + px);
+                    }
+                }
+                if sprite != 0 {
+                    self.dirty_rows |= 1 << py;
+                }
+            }
+
+            if self.registers[0x0F] == 1 {
+                self.pending_collisions.add_draw();
+            }
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SKP` instruction
+    ///
+    /// Skips the next instruction if the specified key is pressed, the key
+    /// being the low nibble of the register as on the COSMAC VIP
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn skp(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::Vx { x } = opcode.address_mode {
+            let key = self.registers[x] as usize & 0x0F;
+            if self.keypad[key] > 0 {
+                self.program_counter += 2;
+            }
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SKNP` instruction
+    ///
+    /// Skips the next instruction of the specified key is not pressed, the
+    /// key being the low nibble of the register as on the COSMAC VIP
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn sknp(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::Vx { x } = opcode.address_mode {
+            let key = self.registers[x] as usize & 0x0F;
+            if self.keypad[key] == 0 {
+                self.program_counter += 2;
+            }
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SCD` instruction
+    ///
+    /// Scrolls the screen down by `N` pixels (SCHIP)
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// If an invalid address mode was provided
+    fn scd(&mut self, opcode: OpCode) -> Result<()> {
+        if let AddressMode::N { nibble } = opcode.address_mode {
+            self.video_buffer.scroll_down(nibble as usize, self.screen_height());
+            self.screen_updated = true;
+            self.dirty_rows = ALL_ROWS;
+        } else {
+            return Err(Keet8Error::InvalidAddressMode(opcode.address_mode));
+        }
+
+        Ok(())
+    }
+
+    /// Executes the `SCR` instruction
+    ///
+    /// Scrolls the screen right by 4 pixels (SCHIP)
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of [Executor]
+    fn scr(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        self.video_buffer.scroll_right(4, self.screen_width());
+        self.screen_updated = true;
+        self.dirty_rows = ALL_ROWS;
+
+        Ok(())
+    }
+
+    /// Executes the `SCL` instruction
+    ///
+    /// Scrolls the screen left by 4 pixels (SCHIP)
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of [Executor]
+    fn scl(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        self.video_buffer.scroll_left(4);
+        self.screen_updated = true;
+        self.dirty_rows = ALL_ROWS;
+
+        Ok(())
+    }
+
+    /// Executes the `EXIT` instruction
+    ///
+    /// Exits the interpreter, after which no more instructions are executed
+    /// (SCHIP)
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of [Executor]
+    fn exit(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        self.halted = true;
+        Ok(())
+    }
+
+    /// Executes the `LOW` instruction
+    ///
+    /// Switches to the 64x32 low resolution mode (SCHIP), or back to the
+    /// two-page mode for two-page ROMs
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of [Executor]
+    fn low(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        self.set_resolution(self.base_resolution());
+        Ok(())
+    }
+
+    /// Executes the `HIGH` instruction
+    ///
+    /// Switches to the 128x64 high resolution mode (SCHIP)
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode containing the execution context
+    ///
+    /// # Errors
+    ///
+    /// This function doesn't error, but has to return a result due to the
+    /// definition of [Executor]
+    fn high(&mut self, #[allow(unused)] opcode: OpCode) -> Result<()> {
+        self.set_resolution(Resolution::High);
+        Ok(())
+    }
+}
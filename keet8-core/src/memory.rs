@@ -0,0 +1,587 @@
+use super::access::AccessCounts;
+use super::init::{InitPolicy, MEMORY_STREAM};
+use super::state::{Snapshot, StateReader, StateWriter};
+use super::{Quirks, WatchHit};
+
+use crate::hash;
+use crate::prelude::*;
+
+use alloc::collections::BTreeSet;
+use core::ops::{Index, RangeInclusive};
+
+// --- constants --------------------------------------------------------------
+
+/// Represents the starting address of our actual ROM program
+pub const PROG_ADDR: u16 = 0x0200;
+/// Represents the starting address of the ROM program on the ETI-660, whose
+/// interpreter takes up more memory than the one of the COSMAC VIP
+pub const ETI660_PROG_ADDR: u16 = 0x0600;
+/// Represents the starting address of the font data
+pub const FONT_ADDR: u16 = 0x0050;
+/// Represents the starting address of the big (SCHIP) font data
+pub(crate) const BIG_FONT_ADDR: u16 = FONT_ADDR + FONTSET_SIZE as u16;
+/// Represents the address right past the end of the font data
+pub const FONT_END: u16 = BIG_FONT_ADDR + BIG_FONTSET_SIZE as u16;
+
+/// Represents the memory available to Chip-8 and SCHIP, which the emulator
+/// starts with
+pub const MEMORY_SIZE: usize = 4 * 1024;
+/// Represents the memory available to XO-CHIP, being the largest memory the
+/// emulator can have
+pub const MAX_MEMORY_SIZE: usize = 64 * 1024;
+/// Represents the size of the largest ROM which fits in the memory of Chip-8
+pub(crate) const MAX_ROM_SIZE: usize = MEMORY_SIZE - PROG_ADDR as usize;
+/// Represents the size of the `FONTSET` buffer
+const FONTSET_SIZE: usize = 80;
+/// Represents the size of the `BIG_FONTSET` buffer
+const BIG_FONTSET_SIZE: usize = 160;
+
+// --- memory definition ------------------------------------------------------
+
+pub(crate) struct Memory {
+    /// This is the physical memory space of Chip-8, whose size is a power of
+    /// two from `MEMORY_SIZE` to `MAX_MEMORY_SIZE`
+    space: Vec<u8>,
+    /// This is the hash of the loaded ROM, identifying it across sessions
+    rom_hash: u64,
+    /// Flags indicating which addresses the ROM has written to since it was
+    /// loaded
+    written: Vec<bool>,
+    /// This is the image of the loaded ROM, restored on a reset
+    rom: Vec<u8>,
+    /// This is the address the ROM is loaded at
+    load_addr: u16,
+    /// These are the addresses whose reads and writes by the ROM are reported
+    watched: BTreeSet<u16>,
+    /// This is the first access of a watched address since the last call to
+    /// `take_hit`
+    hit: Option<WatchHit>,
+    /// These are the addresses of the battery-backed save RAM, kept when the
+    /// memory is reset, if any
+    save_ram: Option<RangeInclusive<usize>>,
+    /// This is how the memory outside the fonts and the ROM is filled when
+    /// the ROM is loaded or reset
+    init: InitPolicy,
+    /// These are the reads, writes and executions of every address since
+    /// the ROM started, if counted
+    counts: Option<Box<AccessCounts>>,
+}
+
+impl Memory {
+    /// Creates and initializes the memory for the emulator without a ROM,
+    /// which is loaded afterwards with `load_rom` unless the whole memory is
+    /// restored from a save state
+    ///
+    /// # Params
+    ///
+    /// - `init` - How the memory outside the fonts is filled
+    /// - `load_addr` - The address a ROM is to be loaded at
+    pub fn without_rom(init: InitPolicy, load_addr: u16) -> Self {
+        let mut memory = Self {
+            space: vec![0; MEMORY_SIZE],
+            rom_hash: hash::fnv1a(&[]),
+            written: vec![false; MEMORY_SIZE],
+            rom: Vec::new(),
+            load_addr,
+            watched: BTreeSet::new(),
+            hit: None,
+            save_ram: None,
+            init,
+            counts: None,
+        };
+        memory.reset();
+
+        memory
+    }
+
+    /// Restores the memory to the state it was in right after loading the
+    /// ROM, discarding everything the ROM wrote outside of the save RAM
+    pub fn reset(&mut self) {
+        let save_ram = self.save_ram().to_vec();
+        self.init.fill(&mut self.space, MEMORY_STREAM);
+
+        let start = self.load_addr as usize;
+        self.space[start..start + self.rom.len()].copy_from_slice(&self.rom);
+        load_font(&mut self.space);
+        load_big_font(&mut self.space);
+        self.restore_save_ram(&save_ram);
+
+        self.written.fill(false);
+        self.hit = None;
+        if let Some(counts) = &mut self.counts {
+            **counts = AccessCounts::new(self.space.len());
+        }
+    }
+
+    /// Replaces the loaded ROM with another one, restoring the memory to the
+    /// state right after loading it
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM to load into memory
+    ///
+    /// # Errors
+    ///
+    /// - If the ROM is empty or doesn't fit in memory, in which case the
+    ///   loaded ROM is kept
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<()> {
+        self.rom_hash = load_rom(rom, &mut vec![0; self.space.len()], self.load_addr)?;
+        self.rom = rom.to_vec();
+        self.reset();
+
+        Ok(())
+    }
+
+    /// Gets the address the ROM is loaded at
+    #[inline(always)]
+    pub const fn load_address(&self) -> u16 {
+        self.load_addr
+    }
+
+    /// Moves the loaded ROM to another address, restoring the memory to the
+    /// state right after loading it there
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to load the ROM at
+    ///
+    /// # Errors
+    ///
+    /// - If the ROM doesn't fit in memory from the address, in which case
+    ///   the ROM stays where it was
+    pub fn set_load_address(&mut self, addr: u16) -> Result<()> {
+        check_fits(self.rom.len(), addr, self.space.len())?;
+        self.load_addr = addr;
+        self.reset();
+
+        Ok(())
+    }
+
+    /// Gets the number of bytes of memory
+    #[inline(always)]
+    pub fn size(&self) -> usize {
+        self.space.len()
+    }
+
+    /// Resizes the memory, restoring it to the state right after loading the
+    /// ROM
+    ///
+    /// The watched addresses and the save RAM past the end of the new memory
+    /// are dropped.
+    ///
+    /// # Params
+    ///
+    /// - `size` - The number of bytes of memory
+    ///
+    /// # Errors
+    ///
+    /// - If the size isn't a power of two from `MEMORY_SIZE` to
+    ///   `MAX_MEMORY_SIZE`
+    /// - If the ROM doesn't fit in the new memory, in which case the memory
+    ///   keeps its size
+    pub fn set_size(&mut self, size: usize) -> Result<()> {
+        if !size.is_power_of_two() || !(MEMORY_SIZE..=MAX_MEMORY_SIZE).contains(&size) {
+            return Err(Keet8Error::InvalidMemorySize(size));
+        }
+        check_fits(self.rom.len(), self.load_addr, size)?;
+
+        self.resize(size);
+        self.reset();
+        Ok(())
+    }
+
+    /// Gets how the memory is filled when the ROM is loaded or reset
+    #[inline(always)]
+    pub const fn init(&self) -> InitPolicy {
+        self.init
+    }
+
+    /// Gets the hash of the loaded ROM
+    #[inline(always)]
+    pub const fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Sets the hash of the loaded ROM, such as to that of the ROM a save
+    /// state was captured with
+    ///
+    /// # Params
+    ///
+    /// - `rom_hash` - The hash of the ROM
+    #[inline(always)]
+    pub fn set_rom_hash(&mut self, rom_hash: u64) {
+        self.rom_hash = rom_hash;
+    }
+
+    /// Sets the addresses of the battery-backed save RAM, which is kept when
+    /// the memory is reset
+    ///
+    /// # Params
+    ///
+    /// - `range` - The addresses of the save RAM, cut off at the end of
+    ///   memory, or `None` for no save RAM
+    pub fn set_save_ram(&mut self, range: Option<RangeInclusive<u16>>) {
+        self.save_ram = range
+            .map(|range| *range.start() as usize..=(*range.end() as usize).min(self.size() - 1))
+            .filter(|range| !range.is_empty());
+    }
+
+    /// Gets the contents of the save RAM, which is empty without one
+    pub fn save_ram(&self) -> &[u8] {
+        self.save_ram.clone().map_or(&[], |range| &self.space[range])
+    }
+
+    /// Restores the contents of the save RAM, such as the ones of a previous
+    /// run, ignoring the bytes past its end
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The contents of the save RAM, from its first address
+    pub fn restore_save_ram(&mut self, bytes: &[u8]) {
+        let Some(range) = self.save_ram.clone() else {
+            return;
+        };
+
+        let save_ram = &mut self.space[range];
+        let len = bytes.len().min(save_ram.len());
+        save_ram[..len].copy_from_slice(&bytes[..len]);
+    }
+
+    /// Gets the whole memory space, indexed by address
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.space
+    }
+
+    /// Checks whether the ROM has written to the address since it was loaded
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to check
+    #[inline(always)]
+    pub fn is_written(&self, addr: u16) -> bool {
+        self.written[self.wrap(addr)]
+    }
+
+    /// Reads a byte on behalf of the ROM, noting the access if the address is
+    /// watched
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to read
+    pub fn read(&mut self, addr: u16) -> u8 {
+        let addr = self.wrap(addr) as u16;
+        if self.hit.is_none() && self.watched.contains(&addr) {
+            self.hit = Some(WatchHit::Read(addr));
+        }
+        if let Some(counts) = &mut self.counts {
+            counts.count_read(addr);
+        }
+
+        self.space[addr as usize]
+    }
+
+    /// Writes a byte on behalf of the ROM, marking the address as written and
+    /// noting the access if the address is watched
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to write to
+    /// - `val` - The byte to write
+    pub fn write(&mut self, addr: u16, val: u8) {
+        let addr = self.wrap(addr) as u16;
+        if self.hit.is_none() && self.watched.contains(&addr) {
+            self.hit = Some(WatchHit::Write(addr));
+        }
+        if let Some(counts) = &mut self.counts {
+            counts.count_write(addr);
+        }
+
+        self.written[addr as usize] = true;
+        self.space[addr as usize] = val;
+    }
+
+    /// Watches an address, so that reads and writes of it by the ROM are
+    /// reported
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to watch
+    pub fn watch(&mut self, addr: u16) {
+        self.watched.insert(self.wrap(addr) as u16);
+    }
+
+    /// Stops watching an address, returning whether it was watched
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to stop watching
+    pub fn unwatch(&mut self, addr: u16) -> bool {
+        self.watched.remove(&(self.wrap(addr) as u16))
+    }
+
+    /// Gets the watched addresses, in order of address
+    pub fn watched(&self) -> impl Iterator<Item = u16> + '_ {
+        self.watched.iter().copied()
+    }
+
+    /// Counts an instruction executed from an address, if counting
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the instruction
+    pub fn count_execute(&mut self, addr: u16) {
+        if let Some(counts) = &mut self.counts {
+            counts.count_execute(addr);
+        }
+    }
+
+    /// Starts counting the accesses of every address from zero, or stops
+    /// counting them
+    ///
+    /// # Params
+    ///
+    /// - `enabled` - Whether the accesses are counted
+    pub fn set_counting(&mut self, enabled: bool) {
+        self.counts = enabled.then(|| Box::new(AccessCounts::new(self.space.len())));
+    }
+
+    /// Gets the accesses of every address since the ROM started, if counted
+    pub fn counts(&self) -> Option<&AccessCounts> {
+        self.counts.as_deref()
+    }
+
+    /// Takes the first access of a watched address since the last call, if
+    /// there was one
+    pub fn take_hit(&mut self) -> Option<WatchHit> {
+        self.hit.take()
+    }
+
+    /// Writes a byte from outside the ROM, such as from the debugger, which
+    /// doesn't mark the address as written by the ROM
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to write to
+    /// - `val` - The byte to write
+    pub fn patch(&mut self, addr: u16, val: u8) {
+        let addr = self.wrap(addr);
+        self.space[addr] = val;
+    }
+
+    /// Wraps an address around to within memory
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address
+    #[inline(always)]
+    fn wrap(&self, addr: u16) -> usize {
+        addr as usize & (self.space.len() - 1)
+    }
+
+    /// Resizes the memory without restoring it, dropping the watched
+    /// addresses and the save RAM past its end
+    ///
+    /// # Params
+    ///
+    /// - `size` - The number of bytes of memory, being a power of two
+    fn resize(&mut self, size: usize) {
+        self.space.resize(size, 0);
+        self.written.resize(size, false);
+        self.watched.retain(|&addr| (addr as usize) < size);
+        self.hit = None;
+        if let Some(counts) = &mut self.counts {
+            **counts = AccessCounts::new(size);
+        }
+
+        self.save_ram = self
+            .save_ram
+            .take()
+            .map(|range| *range.start()..=(*range.end()).min(size - 1))
+            .filter(|range| !range.is_empty());
+    }
+}
+
+impl Index<u16> for Memory {
+    type Output = u8;
+
+    /// Gets the value at the specified 16-bit address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to read
+    fn index(&self, addr: u16) -> &Self::Output {
+        &self.space[self.wrap(addr)]
+    }
+}
+
+impl Snapshot for Memory {
+    /// Writes the size of memory and the whole memory space
+    fn save(&self, writer: &mut StateWriter) {
+        writer.u32(self.space.len() as u32);
+        writer.bytes(&self.space);
+    }
+
+    /// Restores the size of memory and the whole memory space
+    fn load(&mut self, reader: &mut StateReader) -> Result<()> {
+        let size = reader.u32()? as usize;
+        if !size.is_power_of_two() || !(MEMORY_SIZE..=MAX_MEMORY_SIZE).contains(&size) {
+            return Err(Keet8Error::InvalidSaveState);
+        }
+
+        let space = reader.bytes(size)?;
+        self.resize(size);
+        self.space.copy_from_slice(space);
+        Ok(())
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Reads the contents of the provided ROM file
+///
+/// # Params
+///
+/// - `filepath` - The path to the ROM file
+///
+/// # Errors
+///
+/// If an error occured whilst attempting to read from the ROM file
+#[cfg(feature = "std")]
+pub fn read_rom(filepath: &str) -> Result<Vec<u8>> {
+    std::fs::read(filepath).map_err(|e| Keet8Error::RomUnreadable(filepath.to_string(), e))
+}
+
+/// Resolves the address at an offset from a base address, such as `I`
+///
+/// Addresses past the end of memory wrap around to the start, unless they are
+/// to be rejected with the strict memory quirk or held at the last address
+/// with the clamp memory quirk
+///
+/// # Params
+///
+/// - `base` - The base address
+/// - `offset` - The offset from the base address
+/// - `quirks` - The quirks deciding what happens past the end of memory
+/// - `size` - The number of bytes of memory
+///
+/// # Errors
+///
+/// If the address lies past the end of memory with the strict memory quirk
+/// enabled
+pub(crate) fn offset_address(base: u16, offset: usize, quirks: Quirks, size: usize) -> Result<u16> {
+    let addr = base as usize + offset;
+    if addr < size {
+        return Ok(addr as u16);
+    }
+
+    if quirks.strict_memory {
+        Err(Keet8Error::AddressOutOfRange(addr))
+    } else if quirks.clamp_memory {
+        Ok((size - 1) as u16)
+    } else {
+        Ok((addr % size) as u16)
+    }
+}
+
+/// Loads the ROM into `buffer` starting at the load address
+/// 
+/// Returns the hash of the ROM
+/// 
+/// # Params
+/// 
+/// - `bytes` - The bytes of the ROM
+/// - `buffer` - The memory buffer to load the ROM into
+/// - `load_addr` - The address to load the ROM at
+/// 
+/// # Errors
+/// 
+/// - If the ROM is empty
+/// - If the ROM doesn't fit in the buffer
+fn load_rom(bytes: &[u8], buffer: &mut [u8], load_addr: u16) -> Result<u64> {
+    if bytes.is_empty() {
+        return Err(Keet8Error::EmptyROM);
+    }
+    check_fits(bytes.len(), load_addr, buffer.len())?;
+
+    let start = load_addr as usize;
+    buffer[start..start + bytes.len()].copy_from_slice(bytes);
+    Ok(hash::fnv1a(bytes))
+}
+
+/// Checks that a ROM fits in memory from the load address
+///
+/// # Params
+///
+/// - `size` - The size of the ROM in bytes
+/// - `load_addr` - The address to load the ROM at
+/// - `memory_size` - The number of bytes of memory
+///
+/// # Errors
+///
+/// If the ROM doesn't fit in memory, or the load address lies past its end
+fn check_fits(size: usize, load_addr: u16, memory_size: usize) -> Result<()> {
+    let start = load_addr as usize;
+    let max = memory_size.saturating_sub(start);
+    if start > memory_size || size > max {
+        return Err(Keet8Error::ROMTooLarge { size, max });
+    }
+
+    Ok(())
+}
+
+/// Loads the font data of Chip-8 into the given buffer
+///
+/// # Params
+///
+/// - `buffer` - The buffer to load the font into
+fn load_font(buffer: &mut [u8]) {
+    const FONTSET: [u8; FONTSET_SIZE] = [
+        0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+        0x20, 0x60, 0x20, 0x20, 0x70, // 1
+        0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+        0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+        0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+        0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+        0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+        0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+        0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+        0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+        0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+        0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+        0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+        0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+        0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+        0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+    ];
+
+    (0..FONTSET_SIZE).for_each(|i| buffer[FONT_ADDR as usize + i] = FONTSET[i]);
+}
+
+/// Loads the big 8x10 font data of SCHIP into the given buffer
+///
+/// # Params
+///
+/// - `buffer` - The buffer to load the font into
+fn load_big_font(buffer: &mut [u8]) {
+    const BIG_FONTSET: [u8; BIG_FONTSET_SIZE] = [
+        0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+        0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+        0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+        0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+        0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+        0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+        0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+        0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+        0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+        0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+        0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+        0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+    ];
+
+    (0..BIG_FONTSET_SIZE).for_each(|i| buffer[BIG_FONT_ADDR as usize + i] = BIG_FONTSET[i]);
+}
@@ -0,0 +1,442 @@
+use core::fmt::Display;
+
+// --- macros -----------------------------------------------------------------
+
+/// Retrieves the first nibble of the raw opcode
+macro_rules! instr {
+    ($raw:expr) => {
+        (($raw) & 0xF000)
+    };
+}
+
+/// Retrieves the value of `VX` from the raw opcode
+macro_rules! x {
+    ($raw:expr) => {
+        ((($raw) & 0x0F00) >> 8) as usize
+    };
+}
+
+/// Retrieves the value of `VY` from the raw opcode
+macro_rules! y {
+    ($raw:expr) => {
+        ((($raw) & 0x00F0) >> 4) as usize
+    };
+}
+
+/// Retrieves the nibble (`N`) from the raw opcode as a byte
+macro_rules! n {
+    ($raw:expr) => {
+        (($raw) & 0x000F) as u8
+    };
+}
+
+/// Retrieves the byte (`KK`) from the raw opcode as a byte
+macro_rules! kk {
+    ($raw:expr) => {
+        (($raw) & 0x00FF) as u8
+    };
+}
+
+/// Retrieves the address (`NNN`) from the raw opcode as a 16-bit unsigned
+/// integer
+macro_rules! nnn {
+    ($raw:expr) => {
+        (($raw) & 0x0FFF) as u16
+    };
+}
+
+// --- instruction definition -------------------------------------------------
+
+/// The instructions an opcode decodes to, named after their mnemonics
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Instruction {
+    /// `raw` instruction (used for when an unknown raw opcode was encountered)
+    RAW,
+    /// `cls` instruction to clear the screen buffer
+    CLS,
+    /// `ret` intruction for returning from functions
+    RET,
+    /// `sys` instruction (unused)
+    #[allow(unused)]
+    SYS,
+    /// `jp` instruction for jumping to different memory addresses
+    JP,
+    /// `call` instruction for calling functions
+    CALL,
+    /// `se` instruction for skipping the next instruction if two values are
+    /// equal
+    SE,
+    /// `sne` instruction for skipping the next instruction if two value are
+    /// not equal
+    SNE,
+    /// `ld` instruction for loading a value into a register
+    LD,
+    /// `add` instruction for performing a mathematical add operation
+    ADD,
+    /// `or` instruction for performing a bitwise "or" operation
+    OR,
+    /// `and` instruction for performing a bitwise "and" operation
+    AND,
+    /// `xor` instruction for performing a bitwise "xor" operation
+    XOR,
+    /// `sub` instruction for performing a mathematical subtract operation
+    SUB,
+    /// `shr` instruction for performing a bitwise shift to the right operation
+    SHR,
+    /// `subn` instruction for performing a negative mathematical subtract
+    /// operation
+    SUBN,
+    /// `shl` instruction for performing a bitwise shift to the left operation
+    SHL,
+    /// `rnd` instruction for generating a random unsigned 8-bit integer 
+    RND,
+    /// `drw` instruction for drawing to the screen buffer
+    DRW,
+    /// `skp` instruction for skipping the next instruction if a specific key
+    /// is pressed
+    SKP,
+    /// `sknp` instruction fro skipping the next instruction if a specific key
+    /// is not pressed
+    SKNP,
+    /// `scd` instruction for scrolling the screen down (SCHIP)
+    SCD,
+    /// `scr` instruction for scrolling the screen right by 4 pixels (SCHIP)
+    SCR,
+    /// `scl` instruction for scrolling the screen left by 4 pixels (SCHIP)
+    SCL,
+    /// `exit` instruction for exiting the interpreter (SCHIP)
+    EXIT,
+    /// `low` instruction for switching to low resolution mode (SCHIP)
+    LOW,
+    /// `high` instruction for switching to high resolution mode (SCHIP)
+    HIGH,
+}
+
+impl Display for Instruction {
+    /// Writes the instruction to the output stream
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const INSTRUCTION_STRINGS: [&str; 27] = [
+            "raw", "cls", "ret", "sys", "jp", "call", "se", "sne", "ld", "add", "or", "and", "xor",
+            "sub", "shr", "subn", "shl", "rnd", "drw", "skp", "sknp", "scd", "scr", "scl", "exit",
+            "low", "high",
+        ];
+
+        write!(f, "{}", INSTRUCTION_STRINGS[*self as usize])
+    }
+}
+
+// --- address mode definition ------------------------------------------------
+
+/// The operands of an instruction, decoded from the opcode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressMode {
+    /// Used for instructions that require no address mode
+    None,
+    /// Used for the `raw` instruction
+    OpCode { opcode: u16 },
+    /// Used for instructions requiring a memory address
+    Addr { address: u16 },
+    /// Used for instructions operating on a register with a byte
+    VxByte { x: usize, byte: u8 },
+    /// Used for instructions operating on two registers
+    VxVy { x: usize, y: usize },
+    /// Used for instruction requiring a memory address aswell as the index
+    /// register
+    IAddr { address: u16 },
+    /// Used for instructions using the `V0` register and a memory address
+    V0Addr { address: u16 },
+    /// Used for the `drw` instruction
+    VxVyN { x: usize, y: usize, nibble: u8 },
+    /// Used for instructions operating on a single register
+    Vx { x: usize },
+    /// Used for instructions operating on a single register as the destination
+    /// and the delay timer
+    VxDt { x: usize },
+    /// Used for instructions operating on a single register and making use of
+    /// a key
+    VxKey { x: usize },
+    /// Used for instructions operating on the delay timer as the destination
+    /// and a single register
+    DtVx { x: usize },
+    /// Used for instructions operating on the sound timer as the destination
+    /// and a single register
+    StVx { x: usize },
+    /// Used for instructions operating on the index register as the
+    /// destination and a single register
+    IVx { x: usize },
+    /// Used for instructions operating on the font and a single register
+    FontVx { x: usize },
+    /// Used for instructions operating on a single register with a weird
+    /// operation
+    BcdVx { x: usize },
+    /// Used for instructions operating on a memory address and the index
+    /// register as the destination and a single other register
+    AddrIVx { x: usize },
+    /// Used for instructions operating on a single register as the destination
+    /// and a memory address with the index register
+    VxAddrI { x: usize },
+    /// Used for instructions operating on a single nibble (`scd`)
+    N { nibble: u8 },
+    /// Used for instructions operating on the big font and a single register
+    /// (SCHIP)
+    HFontVx { x: usize },
+    /// Used for instructions operating on the RPL flags as the destination and
+    /// a range of registers (SCHIP)
+    RplVx { x: usize },
+    /// Used for instructions operating on a range of registers as the
+    /// destination and the RPL flags (SCHIP)
+    VxRpl { x: usize },
+    /// Used for instructions operating on the audio pattern as the destination
+    /// and the memory pointed to by the index register (XO-CHIP)
+    AudioI,
+    /// Used for instructions operating on the pitch of the audio pattern as
+    /// the destination and a single register (XO-CHIP)
+    PitchVx { x: usize },
+}
+
+impl Display for AddressMode {
+    /// Writes the address mode as it will appear in assembly to the output
+    /// stream
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AddressMode::None => write!(f, ""),
+            AddressMode::OpCode { opcode } => write!(f, "0x{:04x}", opcode),
+            AddressMode::Addr { address } => write!(f, "0x{:04x}", address),
+            AddressMode::VxByte { x, byte } => write!(f, "v{x} {byte}"),
+            AddressMode::VxVy { x, y } => write!(f, "v{x} v{y}"),
+            AddressMode::IAddr { address } => write!(f, "I 0x{:04x}", address),
+            AddressMode::V0Addr { address } => write!(f, "v0 0x{:04x}", address),
+            AddressMode::VxVyN { x, y, nibble } => write!(f, "v{x} v{y} {nibble}"),
+            AddressMode::Vx { x } => write!(f, "v{x}"),
+            AddressMode::VxDt { x } => write!(f, "v{x} dt"),
+            AddressMode::VxKey { x } => write!(f, "v{x} [key]"),
+            AddressMode::DtVx { x } => write!(f, "dt v{x}"),
+            AddressMode::StVx { x } => write!(f, "st v{x}"),
+            AddressMode::IVx { x } => write!(f, "I v{x}"),
+            AddressMode::FontVx { x } => write!(f, "v{x}"),
+            AddressMode::BcdVx { x } => write!(f, "v{x}"),
+            AddressMode::AddrIVx { x } => write!(f, "v{x}"),
+            AddressMode::VxAddrI { x } => write!(f, "v{x}"),
+            AddressMode::N { nibble } => write!(f, "{nibble}"),
+            AddressMode::HFontVx { x } => write!(f, "hf v{x}"),
+            AddressMode::RplVx { x } => write!(f, "r v{x}"),
+            AddressMode::VxRpl { x } => write!(f, "v{x} r"),
+            AddressMode::AudioI => write!(f, "audio [i]"),
+            AddressMode::PitchVx { x } => write!(f, "pitch v{x}"),
+        }
+    }
+}
+
+// --- opcode definition ------------------------------------------------------
+
+/// A decoded instruction, created from a raw opcode with `OpCode::from`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpCode {
+    /// The specified instruction
+    pub instr: Instruction,
+    /// The address mode to treat the instruction with
+    pub address_mode: AddressMode,
+}
+
+impl OpCode {
+    /// Creates an opcode from a raw opcode found in the ROM binary
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The raw binary opcode in the ROM file
+    #[inline(always)]
+    pub(crate) const fn raw(opcode: u16) -> Self {
+        Self {
+            instr: Instruction::RAW,
+            address_mode: AddressMode::OpCode { opcode },
+        }
+    }
+}
+
+impl From<u16> for OpCode {
+    /// Creates an opcode struct from the raw binary opcode found in the ROM
+    /// file, being the first entry of the decoding table matching it
+    ///
+    /// # Params
+    ///
+    /// - `raw` - The raw binary opcode
+    fn from(raw: u16) -> Self {
+        DECODING[instr!(raw) as usize >> 12]
+            .iter()
+            .find(|decoding| raw & decoding.mask == decoding.pattern)
+            .map_or(Self::raw(raw), |decoding| Self {
+                instr: decoding.instr,
+                address_mode: (decoding.address_mode)(raw),
+            })
+    }
+}
+
+impl Display for OpCode {
+    /// Writes the opcode out as it will appear in assembly to the
+    /// output stream
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} {}", self.instr, self.address_mode)
+    }
+}
+
+// --- disassembly interface -------------------------------------------------
+
+/// Decodes a run of bytes as opcodes, two bytes each in big-endian order, for
+/// tools reusing the decoder without an `Emulator`
+///
+/// Every pair of bytes is decoded, whether it holds an instruction or data,
+/// the opcodes which don't decode to any instruction being `RAW`. A trailing
+/// odd byte is left out.
+///
+/// # Params
+///
+/// - `bytes` - The bytes to decode, such as a ROM
+/// - `base` - The address of the first byte, such as `PROG_ADDR` for a ROM
+///
+/// # Examples
+///
+/// ```rust
+/// use keet8_core::opcode::{disassemble, Instruction};
+///
+/// // CLS, then JP 0x200
+/// let opcodes = disassemble(&[0x00, 0xE0, 0x12, 0x00], 0x200).collect::<Vec<_>>();
+///
+/// assert_eq!(opcodes[0].0, 0x200);
+/// assert_eq!(opcodes[1].1.instr, Instruction::JP);
+/// assert_eq!(opcodes[1].1.to_string(), "jp 0x0200");
+/// ```
+pub fn disassemble(bytes: &[u8], base: u16) -> impl Iterator<Item = (u16, OpCode)> + '_ {
+    bytes.chunks_exact(2).enumerate().map(move |(i, pair)| {
+        let addr = base.wrapping_add(2 * i as u16);
+        (addr, OpCode::from(u16::from_be_bytes([pair[0], pair[1]])))
+    })
+}
+
+// --- decoding table definition ---------------------------------------------
+
+/// An entry of the decoding table, matching the raw opcodes whose bits under
+/// `mask` equal `pattern`
+struct Decoding {
+    /// The bits of the raw opcode which are compared
+    mask: u16,
+    /// The value of the compared bits
+    pattern: u16,
+    /// The instruction of the matching opcodes
+    instr: Instruction,
+    /// Extracts the operands from a matching raw opcode
+    address_mode: fn(u16) -> AddressMode,
+}
+
+/// Creates an entry of the decoding table
+macro_rules! decoding {
+    ($mask:expr, $pattern:expr, $instr:ident, $address_mode:expr) => {
+        Decoding {
+            mask: $mask,
+            pattern: $pattern,
+            instr: Instruction::$instr,
+            address_mode: $address_mode,
+        }
+    };
+}
+
+/// The decoding table, indexed by the first nibble of the raw opcode, an
+/// opcode decoding as the first entry matching it or as `RAW` if none does
+///
+/// An extension opcode is added as an entry of its first nibble, ahead of
+/// any broader entry it would otherwise fall under.
+const DECODING: [&[Decoding]; 16] = [
+    &[
+        decoding!(0xF0FF, 0x00E0, CLS, none),
+        decoding!(0xF0FF, 0x00EE, RET, none),
+        decoding!(0xF0F0, 0x00C0, SCD, |raw| AddressMode::N { nibble: n!(raw) }),
+        decoding!(0xF0FF, 0x00FB, SCR, none),
+        decoding!(0xF0FF, 0x00FC, SCL, none),
+        decoding!(0xF0FF, 0x00FD, EXIT, none),
+        decoding!(0xF0FF, 0x00FE, LOW, none),
+        decoding!(0xF0FF, 0x00FF, HIGH, none),
+    ],
+    &[decoding!(0xF000, 0x1000, JP, |raw| AddressMode::Addr { address: nnn!(raw) })],
+    &[decoding!(0xF000, 0x2000, CALL, |raw| AddressMode::Addr { address: nnn!(raw) })],
+    &[decoding!(0xF000, 0x3000, SE, vx_byte)],
+    &[decoding!(0xF000, 0x4000, SNE, vx_byte)],
+    &[decoding!(0xF000, 0x5000, SE, vx_vy)],
+    &[decoding!(0xF000, 0x6000, LD, vx_byte)],
+    &[decoding!(0xF000, 0x7000, ADD, vx_byte)],
+    &[
+        decoding!(0xF00F, 0x8000, LD, vx_vy),
+        decoding!(0xF00F, 0x8001, OR, vx_vy),
+        decoding!(0xF00F, 0x8002, AND, vx_vy),
+        decoding!(0xF00F, 0x8003, XOR, vx_vy),
+        decoding!(0xF00F, 0x8004, ADD, vx_vy),
+        decoding!(0xF00F, 0x8005, SUB, vx_vy),
+        decoding!(0xF00F, 0x8006, SHR, vx_vy),
+        decoding!(0xF00F, 0x8007, SUBN, vx_vy),
+        decoding!(0xF00F, 0x800E, SHL, vx_vy),
+    ],
+    &[decoding!(0xF000, 0x9000, SNE, vx_vy)],
+    &[decoding!(0xF000, 0xA000, LD, |raw| AddressMode::IAddr { address: nnn!(raw) })],
+    &[decoding!(0xF000, 0xB000, JP, |raw| AddressMode::V0Addr { address: nnn!(raw) })],
+    &[decoding!(0xF000, 0xC000, RND, vx_byte)],
+    &[decoding!(0xF000, 0xD000, DRW, |raw| AddressMode::VxVyN {
+        x: x!(raw),
+        y: y!(raw),
+        nibble: n!(raw),
+    })],
+    &[
+        decoding!(0xF0FF, 0xE09E, SKP, |raw| AddressMode::Vx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xE0A1, SKNP, |raw| AddressMode::Vx { x: x!(raw) }),
+    ],
+    &[
+        decoding!(0xFFFF, 0xF002, LD, |_| AddressMode::AudioI),
+        decoding!(0xF0FF, 0xF007, LD, |raw| AddressMode::VxDt { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF00A, LD, |raw| AddressMode::VxKey { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF015, LD, |raw| AddressMode::DtVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF018, LD, |raw| AddressMode::StVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF01E, ADD, |raw| AddressMode::IVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF029, LD, |raw| AddressMode::FontVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF030, LD, |raw| AddressMode::HFontVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF03A, LD, |raw| AddressMode::PitchVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF033, LD, |raw| AddressMode::BcdVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF055, LD, |raw| AddressMode::AddrIVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF065, LD, |raw| AddressMode::VxAddrI { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF075, LD, |raw| AddressMode::RplVx { x: x!(raw) }),
+        decoding!(0xF0FF, 0xF085, LD, |raw| AddressMode::VxRpl { x: x!(raw) }),
+    ],
+];
+
+// --- utility functions -----------------------------------------------------
+
+/// Decodes an opcode without operands
+///
+/// # Params
+///
+/// - `_raw` - The raw opcode
+fn none(_raw: u16) -> AddressMode {
+    AddressMode::None
+}
+
+/// Decodes the `VX` and `KK` operands of an opcode
+///
+/// # Params
+///
+/// - `raw` - The raw opcode
+fn vx_byte(raw: u16) -> AddressMode {
+    AddressMode::VxByte {
+        x: x!(raw),
+        byte: kk!(raw),
+    }
+}
+
+/// Decodes the `VX` and `VY` operands of an opcode
+///
+/// # Params
+///
+/// - `raw` - The raw opcode
+fn vx_vy(raw: u16) -> AddressMode {
+    AddressMode::VxVy {
+        x: x!(raw),
+        y: y!(raw),
+    }
+}
@@ -0,0 +1,176 @@
+use super::state::{Snapshot, StateReader, StateWriter};
+use super::{HIRES_HEIGHT, HIRES_WIDTH};
+
+use crate::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// Represents the leftmost pixel of a row, being its most significant bit
+const LEFTMOST: u128 = 1 << (HIRES_WIDTH - 1);
+
+/// Represents the number of bytes a row takes in a save state
+const ROW_BYTES: usize = HIRES_WIDTH / 8;
+
+// --- pixels definition ------------------------------------------------------
+
+/// The pixels of the screen packed as bits, large enough for every
+/// resolution mode
+///
+/// Every row is a `u128` with the leftmost pixel in the most significant
+/// bit, so the 64 pixels of a row in the low resolution mode are its upper
+/// half. The pixels outside the current resolution mode are always off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pixels {
+    /// The rows from top to bottom
+    rows: [u128; HIRES_HEIGHT],
+}
+
+impl Default for Pixels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pixels {
+    /// Creates a screen with every pixel off
+    pub const fn new() -> Self {
+        Self {
+            rows: [0; HIRES_HEIGHT],
+        }
+    }
+
+    /// Checks whether a pixel is on, with pixels outside the screen being off
+    ///
+    /// # Params
+    ///
+    /// - `x` - The column of the pixel, from the left
+    /// - `y` - The row of the pixel, from the top
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < HIRES_WIDTH && self.row(y) & (LEFTMOST >> x) != 0
+    }
+
+    /// Turns a pixel on or off, ignoring pixels outside the screen
+    ///
+    /// # Params
+    ///
+    /// - `x` - The column of the pixel, from the left
+    /// - `y` - The row of the pixel, from the top
+    /// - `on` - Whether the pixel is on
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        if let (true, Some(row)) = (x < HIRES_WIDTH, self.rows.get_mut(y)) {
+            if on {
+                *row |= LEFTMOST >> x;
+            } else {
+                *row &= !(LEFTMOST >> x);
+            }
+        }
+    }
+
+    /// Flips a pixel, as a sprite drawn over it does, returning whether it was
+    /// on, which is a collision
+    ///
+    /// # Params
+    ///
+    /// - `x` - The column of the pixel, from the left
+    /// - `y` - The row of the pixel, from the top
+    pub fn toggle(&mut self, x: usize, y: usize) -> bool {
+        let was_on = self.get(x, y);
+        self.set(x, y, !was_on);
+        was_on
+    }
+
+    /// Gets a row, with the leftmost pixel in the most significant bit and
+    /// the rows outside the screen being off
+    ///
+    /// # Params
+    ///
+    /// - `y` - The row, from the top
+    pub fn row(&self, y: usize) -> u128 {
+        self.rows.get(y).copied().unwrap_or(0)
+    }
+
+    /// Gets the rows from top to bottom, with the leftmost pixel of each in
+    /// its most significant bit
+    ///
+    /// # Params
+    ///
+    /// - `height` - The number of rows, being the height of the screen in the
+    ///   current resolution mode
+    pub fn rows(&self, height: usize) -> impl Iterator<Item = u128> + '_ {
+        self.rows.iter().take(height).copied()
+    }
+
+    /// Gets the number of pixels which are on
+    pub fn count_on(&self) -> usize {
+        self.rows.iter().map(|row| row.count_ones() as usize).sum()
+    }
+
+    /// Turns every pixel off
+    pub fn clear(&mut self) {
+        self.rows = [0; HIRES_HEIGHT];
+    }
+
+    /// Copies the pixels out as bytes, row by row, with `0xFF` for a pixel
+    /// which is on and `0` for one which is off
+    ///
+    /// # Params
+    ///
+    /// - `width` - The width of the screen in the current resolution mode
+    /// - `height` - The height of the screen in the current resolution mode
+    pub(crate) fn to_bytes(self, width: usize, height: usize) -> Vec<u8> {
+        self.rows(height)
+            .flat_map(|row| (0..width).map(move |x| row & (LEFTMOST >> x)))
+            .map(|pixel| if pixel != 0 { 0xFF } else { 0 })
+            .collect()
+    }
+
+    /// Scrolls the screen down, the rows at the top turning off
+    ///
+    /// # Params
+    ///
+    /// - `n` - The number of rows to scroll by
+    /// - `height` - The height of the screen in the current resolution mode
+    pub(crate) fn scroll_down(&mut self, n: usize, height: usize) {
+        let n = n.min(height);
+        self.rows.copy_within(0..height - n, n);
+        self.rows[..n].fill(0);
+    }
+
+    /// Scrolls the screen right, the columns on the left turning off
+    ///
+    /// # Params
+    ///
+    /// - `n` - The number of columns to scroll by
+    /// - `width` - The width of the screen in the current resolution mode
+    pub(crate) fn scroll_right(&mut self, n: usize, width: usize) {
+        let visible = !u128::MAX.checked_shr(width as u32).unwrap_or(0);
+        self.rows.iter_mut().for_each(|row| *row = (*row >> n) & visible);
+    }
+
+    /// Scrolls the screen left, the columns on the right turning off
+    ///
+    /// # Params
+    ///
+    /// - `n` - The number of columns to scroll by
+    pub(crate) fn scroll_left(&mut self, n: usize) {
+        self.rows.iter_mut().for_each(|row| *row <<= n);
+    }
+}
+
+impl Snapshot for Pixels {
+    /// Writes every row, big endian
+    fn save(&self, writer: &mut StateWriter) {
+        self.rows.iter().for_each(|row| writer.bytes(&row.to_be_bytes()));
+    }
+
+    /// Restores every row
+    fn load(&mut self, reader: &mut StateReader) -> Result<()> {
+        for row in self.rows.iter_mut() {
+            let mut bytes = [0; ROW_BYTES];
+            bytes.copy_from_slice(reader.bytes(ROW_BYTES)?);
+            *row = u128::from_be_bytes(bytes);
+        }
+
+        Ok(())
+    }
+}
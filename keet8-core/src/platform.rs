@@ -0,0 +1,130 @@
+use super::memory::{MAX_MEMORY_SIZE, MEMORY_SIZE};
+use super::opcode::{AddressMode, Instruction, OpCode};
+use super::Quirks;
+
+// --- platform definition ----------------------------------------------------
+
+/// A platform Chip-8 ROMs are written for, each adding opcodes to the one
+/// before it, as the community databases describe the compatibility of ROMs
+///
+/// Restricting the `Emulator` to a platform with
+/// [Emulator::set_platform](crate::Emulator::set_platform) treats the opcodes
+/// of later platforms as unknown opcodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Platform {
+    /// The original Chip-8 interpreter on the COSMAC VIP
+    Chip8,
+    /// SCHIP 1.1 on the HP-48 calculators, adding the high resolution mode,
+    /// scrolling, big sprites and the RPL user flags
+    Schip,
+    /// XO-CHIP, adding the audio pattern and its pitch to SCHIP
+    XoChip,
+}
+
+impl Platform {
+    /// Every platform, in the order they extend each other
+    pub const ALL: [Platform; 3] = [Platform::Chip8, Platform::Schip, Platform::XoChip];
+
+    /// Gets the name of the platform, as shown to the user
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Chip8 => "Chip-8",
+            Platform::Schip => "SCHIP",
+            Platform::XoChip => "XO-CHIP",
+        }
+    }
+
+    /// Gets the quirks the interpreters of the platform are known for
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Platform::Chip8 => Quirks {
+                shift_vy: true,
+                load_store_increment: true,
+                vf_reset: true,
+                display_wait: true,
+                ..Quirks::default()
+            },
+            Platform::Schip => Quirks {
+                jump_vx: true,
+                ..Quirks::default()
+            },
+            Platform::XoChip => Quirks {
+                shift_vy: true,
+                load_store_increment: true,
+                wrap_sprites: true,
+                ..Quirks::default()
+            },
+        }
+    }
+
+    /// Gets the number of return addresses the call stack of the
+    /// interpreters of the platform holds
+    pub fn stack_limit(self) -> usize {
+        match self {
+            Platform::Chip8 => 12,
+            Platform::Schip | Platform::XoChip => 16,
+        }
+    }
+
+    /// Gets the number of bytes of memory of the interpreters of the
+    /// platform
+    pub fn memory_size(self) -> usize {
+        match self {
+            Platform::Chip8 | Platform::Schip => MEMORY_SIZE,
+            Platform::XoChip => MAX_MEMORY_SIZE,
+        }
+    }
+
+    /// Gets the first platform with a raw opcode, being Chip-8 for the
+    /// opcodes unknown to every platform
+    ///
+    /// Most of the opcodes of XO-CHIP aren't supported by the interpreter, so
+    /// they are matched on the raw opcode rather than the decoded one
+    ///
+    /// # Params
+    ///
+    /// - `raw` - The raw opcode
+    pub fn of_opcode(raw: u16) -> Platform {
+        let is_xochip = matches!(raw & 0xFFF0, 0x00D0) // scroll up
+            || matches!(raw & 0xF00F, 0x5002 | 0x5003) // save/load register range
+            || raw == 0xF000 // load long index
+            || raw == 0xF002 // load audio pattern
+            || matches!(raw & 0xF0FF, 0xF001 | 0xF03A); // select plane, set pitch
+        if is_xochip {
+            return Platform::XoChip;
+        }
+
+        let opcode = OpCode::from(raw);
+        let is_schip = match opcode.instr {
+            Instruction::SCD
+            | Instruction::SCR
+            | Instruction::SCL
+            | Instruction::EXIT
+            | Instruction::LOW
+            | Instruction::HIGH => true,
+            Instruction::DRW => {
+                matches!(opcode.address_mode, AddressMode::VxVyN { nibble: 0, .. })
+            }
+            Instruction::LD => matches!(
+                opcode.address_mode,
+                AddressMode::HFontVx { .. } | AddressMode::RplVx { .. } | AddressMode::VxRpl { .. }
+            ),
+            _ => false,
+        };
+
+        if is_schip {
+            Platform::Schip
+        } else {
+            Platform::Chip8
+        }
+    }
+
+    /// Checks whether the platform has a raw opcode
+    ///
+    /// # Params
+    ///
+    /// - `raw` - The raw opcode
+    pub fn supports(self, raw: u16) -> bool {
+        Platform::of_opcode(raw) <= self
+    }
+}
@@ -0,0 +1,11 @@
+pub use crate::error::Keet8Error;
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec;
+pub(crate) use alloc::vec::Vec;
+
+// --- custom result type definition ------------------------------------------
+
+/// This is the `Result` type to be used in our library for the emulator
+pub type Result<T> = core::result::Result<T, Keet8Error>;
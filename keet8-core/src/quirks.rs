@@ -0,0 +1,36 @@
+// --- quirks definition ------------------------------------------------------
+
+/// The behaviors in which the different Chip-8 interpreters disagree
+///
+/// The default matches the interpretation keet-8 has always used. Different
+/// ROMs were written against different interpreters and may require some of
+/// these to be toggled.
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift `VY` and store the result in `VX`, instead of
+    /// shifting `VX` in place
+    pub shift_vy: bool,
+    /// `FX55`/`FX65` leave `I` incremented past the copied range
+    pub load_store_increment: bool,
+    /// `8XY1`/`8XY2`/`8XY3` reset `VF` to `0`
+    pub vf_reset: bool,
+    /// `BNNN` jumps to `XNN + VX`, instead of `NNN + V0`
+    pub jump_vx: bool,
+    /// Sprites wrap around the edges of the screen, instead of being clipped
+    pub wrap_sprites: bool,
+    /// `DXYN` waits for the next frame before drawing, allowing at most one
+    /// sprite to be drawn per frame
+    pub display_wait: bool,
+    /// `FX1E` sets `VF` to `1` when `I + VX` goes past `0xFFF`, and to `0`
+    /// otherwise (as on the Amiga interpreter)
+    pub index_overflow: bool,
+    /// Reads and writes through `I` past the end of memory raise an error,
+    /// instead of wrapping around to `0x000`, as do writes with `FX33`/`FX55` into the
+    /// interpreter area below `0x200`
+    pub strict_memory: bool,
+    /// Reads and writes through `I` past the end of memory all go to its last
+    /// address, instead of wrapping around to `0x000`, unless `strict_memory`
+    /// rejects them
+    pub clamp_memory: bool,
+}
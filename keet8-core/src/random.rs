@@ -0,0 +1,202 @@
+use super::state::{Snapshot, StateReader, StateWriter};
+
+use crate::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The seed used when no source of OS randomness is available
+#[cfg(not(feature = "rand"))]
+const DEFAULT_SEED: u32 = 0x2545_F491;
+
+/// The multiplier of the linear congruential generator (Numerical Recipes)
+const LCG_MULTIPLIER: u32 = 1_664_525;
+/// The increment of the linear congruential generator (Numerical Recipes)
+const LCG_INCREMENT: u32 = 1_013_904_223;
+
+// --- random source definition -----------------------------------------------
+
+/// A source of the random bytes used by the `RND` instruction
+///
+/// The source is injected into the `Emulator` with
+/// [Emulator::set_random_source](crate::Emulator::set_random_source), so that
+/// replays, fuzzing and differential tests can each provide their own.
+pub trait RandomSource: Send {
+    /// Generates the next random byte
+    fn next_u8(&mut self) -> u8;
+
+    /// Captures the state of the source, to be included in save states
+    ///
+    /// Sources without any reproducible state capture nothing
+    fn save_state(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Restores the state of the source from a save state
+    ///
+    /// # Params
+    ///
+    /// - `state` - The state as captured by `save_state`
+    ///
+    /// # Errors
+    ///
+    /// If the state is invalid for this source
+    fn load_state(&mut self, state: &[u8]) -> Result<()> {
+        let _ = state;
+        Ok(())
+    }
+}
+
+impl Snapshot for Box<dyn RandomSource> {
+    /// Writes the length-prefixed state of the source
+    fn save(&self, writer: &mut StateWriter) {
+        let state = self.save_state();
+        writer.u32(state.len() as u32);
+        writer.bytes(&state);
+    }
+
+    /// Restores the length-prefixed state of the source
+    fn load(&mut self, reader: &mut StateReader) -> Result<()> {
+        let len = reader.u32()? as usize;
+        self.load_state(reader.bytes(len)?)
+    }
+}
+
+// --- lcg definition ---------------------------------------------------------
+
+/// A seeded linear congruential generator, producing the same sequence for
+/// the same seed
+pub struct Lcg {
+    /// The current state of the generator
+    state: u32,
+}
+
+impl Lcg {
+    /// Creates a new generator from the given seed
+    ///
+    /// # Params
+    ///
+    /// - `seed` - The initial state of the generator
+    pub const fn new(seed: u32) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl Default for Lcg {
+    /// Creates a new generator, seeded from the OS when the `rand` feature
+    /// is enabled and from a fixed seed otherwise
+    fn default() -> Self {
+        #[cfg(feature = "rand")]
+        let seed = rand::random::<u32>();
+        #[cfg(not(feature = "rand"))]
+        let seed = DEFAULT_SEED;
+
+        Self::new(seed)
+    }
+}
+
+impl RandomSource for Lcg {
+    /// Generates the next random byte from the high bits of the state, as the
+    /// low bits of an LCG have short periods
+    fn next_u8(&mut self) -> u8 {
+        self.state = self
+            .state
+            .wrapping_mul(LCG_MULTIPLIER)
+            .wrapping_add(LCG_INCREMENT);
+
+        (self.state >> 24) as u8
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.state.to_be_bytes().to_vec()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<()> {
+        let state = state.try_into().map_err(|_| Keet8Error::InvalidSaveState)?;
+        self.state = u32::from_be_bytes(state);
+        Ok(())
+    }
+}
+
+// --- scripted random definition ---------------------------------------------
+
+/// A source replaying a fixed sequence of bytes, starting over once the end
+/// of the sequence has been reached
+pub struct ScriptedRandom {
+    /// The sequence of bytes to replay
+    sequence: Vec<u8>,
+    /// The position of the next byte in the sequence
+    pos: usize,
+}
+
+impl ScriptedRandom {
+    /// Creates a source replaying the given sequence
+    ///
+    /// An empty sequence always produces zero
+    ///
+    /// # Params
+    ///
+    /// - `sequence` - The sequence of bytes to replay
+    pub fn new(sequence: Vec<u8>) -> Self {
+        Self { sequence, pos: 0 }
+    }
+
+    /// Creates a source replaying the raw bytes of a file
+    ///
+    /// # Params
+    ///
+    /// - `file` - The filepath to the sequence of bytes
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read
+    #[cfg(feature = "std")]
+    pub fn load(file: &str) -> Result<Self> {
+        let sequence = std::fs::read(file)
+            .map_err(|_| Keet8Error::FailedToLoadRandomScript(file.to_string()))?;
+
+        Ok(Self::new(sequence))
+    }
+}
+
+impl RandomSource for ScriptedRandom {
+    fn next_u8(&mut self) -> u8 {
+        let Some(&byte) = self.sequence.get(self.pos) else {
+            return 0;
+        };
+
+        self.pos = (self.pos + 1) % self.sequence.len();
+        byte
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        (self.pos as u32).to_be_bytes().to_vec()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<()> {
+        let pos = state
+            .try_into()
+            .map(|state| u32::from_be_bytes(state) as usize)
+            .map_err(|_| Keet8Error::InvalidSaveState)?;
+
+        if pos > 0 && pos >= self.sequence.len() {
+            return Err(Keet8Error::InvalidSaveState);
+        }
+
+        self.pos = pos;
+        Ok(())
+    }
+}
+
+// --- os random definition ---------------------------------------------------
+
+/// A source drawing every byte from the OS, which can't be reproduced
+#[cfg(feature = "rand")]
+#[derive(Default)]
+pub struct OsRandom;
+
+#[cfg(feature = "rand")]
+impl RandomSource for OsRandom {
+    fn next_u8(&mut self) -> u8 {
+        rand::random()
+    }
+}
@@ -0,0 +1,133 @@
+use super::Pixels;
+
+// --- constants --------------------------------------------------------------
+
+/// Represents every row of the screen being changed, in the bits of the
+/// dirty rows
+pub(crate) const ALL_ROWS: u64 = u64::MAX;
+
+// --- frame buffer definition ------------------------------------------------
+
+/// The screen of the `Emulator` at full resolution, borrowed from it by
+/// [Emulator::frame](crate::Emulator::frame) to be presented
+#[derive(Clone, Copy)]
+pub struct FrameBuffer<'a> {
+    /// The pixels of the screen, of which the ones within `width` and
+    /// `height` are in use
+    pixels: &'a Pixels,
+    /// The width in pixels of the screen in the current resolution mode
+    width: usize,
+    /// The height in pixels of the screen in the current resolution mode
+    height: usize,
+    /// The rows changed since the screen was last presented, with bit `Y`
+    /// set for row `Y`
+    dirty_rows: u64,
+}
+
+impl<'a> FrameBuffer<'a> {
+    /// Wraps the pixels of the screen, every row counting as changed
+    ///
+    /// # Params
+    ///
+    /// - `pixels` - The pixels of the screen
+    /// - `width` - The width in pixels of the screen
+    /// - `height` - The height in pixels of the screen
+    pub fn new(pixels: &'a Pixels, width: usize, height: usize) -> Self {
+        Self {
+            pixels,
+            width,
+            height,
+            dirty_rows: ALL_ROWS,
+        }
+    }
+
+    /// Marks the rows changed since the screen was last presented, so that a
+    /// renderer can leave the other rows as it drew them
+    ///
+    /// # Params
+    ///
+    /// - `dirty_rows` - The rows changed, with bit `Y` set for row `Y`
+    pub fn with_dirty_rows(mut self, dirty_rows: u64) -> Self {
+        self.dirty_rows = dirty_rows;
+        self
+    }
+
+    /// Gets the rows changed since the screen was last presented, with bit
+    /// `Y` set for row `Y`
+    pub fn dirty_rows(&self) -> u64 {
+        self.dirty_rows
+    }
+
+    /// Checks whether a row changed since the screen was last presented
+    ///
+    /// # Params
+    ///
+    /// - `y` - The row, from the top
+    pub fn is_row_dirty(&self, y: usize) -> bool {
+        y < u64::BITS as usize && self.dirty_rows >> y & 1 != 0
+    }
+
+    /// Gets the pixels of the screen, of which the ones within `width` and
+    /// `height` are in use
+    pub fn pixels(&self) -> &'a Pixels {
+        self.pixels
+    }
+
+    /// Gets the width in pixels of the screen, being `LORES_WIDTH` or
+    /// `HIRES_WIDTH` (SCHIP)
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height in pixels of the screen, being `LORES_HEIGHT`,
+    /// `TWO_PAGE_HEIGHT` (hi-res Chip-8) or `HIRES_HEIGHT` (SCHIP)
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Checks whether a pixel is on, with pixels outside the screen being off
+    ///
+    /// # Params
+    ///
+    /// - `x` - The column of the pixel, from the left
+    /// - `y` - The row of the pixel, from the top
+    pub fn is_on(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels.get(x, y)
+    }
+
+    /// Gets every pixel of the screen as its column, row and whether it is on,
+    /// row by row from the top-left corner
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, bool)> + 'a {
+        let (pixels, width) = (self.pixels, self.width);
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y, pixels.get(x, y))))
+    }
+
+    /// Gets the positions of the pixels which are on, row by row
+    pub fn lit_pixels(&self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.iter().filter(|&(_, _, on)| on).map(|(x, y, _)| (x, y))
+    }
+}
+
+// --- renderer definition ----------------------------------------------------
+
+/// A backend presenting the screen of the `Emulator`, such as a window, a
+/// terminal or a canvas
+///
+/// The frontend calls `present` whenever it draws a frame, with the screen
+/// taken from [Emulator::frame](crate::Emulator::frame), so the core never
+/// depends on the backend. Unlike a `Display`, which has packed low
+/// resolution frames pushed to it from `tick_timers`, a renderer pulls the
+/// screen at its full resolution at its own pace.
+///
+/// # Examples
+///
+/// A backend drawing the screen in a terminal with block characters is
+/// found in `examples/terminal.rs`.
+pub trait Renderer {
+    /// Presents the screen
+    ///
+    /// # Params
+    ///
+    /// - `fb` - The screen to present
+    fn present(&mut self, fb: &FrameBuffer);
+}
@@ -0,0 +1,90 @@
+use super::{HIRES_HEIGHT, HIRES_WIDTH, LORES_HEIGHT, LORES_WIDTH};
+
+// --- constants --------------------------------------------------------------
+
+/// Represents the height of the screen buffer in the two-page mode (hi-res
+/// Chip-8), which is as wide as the low resolution mode
+pub const TWO_PAGE_HEIGHT: usize = 64;
+
+/// Represents the address the program of a two-page ROM starts at, past the
+/// patch of the interpreter it carries
+pub const TWO_PAGE_ENTRY: u16 = 0x02C0;
+
+/// Represents the first instruction of a two-page ROM, `JP 0x260` into the
+/// patch of the interpreter
+const TWO_PAGE_HEADER: [u8; 2] = [0x12, 0x60];
+
+/// Represents the opcode clearing the screen in the two-page mode, calling
+/// the routine of the patched interpreter
+pub(crate) const TWO_PAGE_CLS: u16 = 0x0230;
+
+// --- resolution definition --------------------------------------------------
+
+/// The resolution modes of the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Resolution {
+    /// The 64x32 screen of Chip-8
+    #[default]
+    Low,
+    /// The 64x64 screen of the hi-res Chip-8 variant, which patched the
+    /// interpreter of the COSMAC VIP to show two pages of video memory
+    TwoPage,
+    /// The 128x64 high resolution mode of SCHIP
+    High,
+}
+
+impl Resolution {
+    /// Gets the width in pixels of the screen
+    pub const fn width(self) -> usize {
+        match self {
+            Resolution::Low | Resolution::TwoPage => LORES_WIDTH,
+            Resolution::High => HIRES_WIDTH,
+        }
+    }
+
+    /// Gets the height in pixels of the screen
+    pub const fn height(self) -> usize {
+        match self {
+            Resolution::Low => LORES_HEIGHT,
+            Resolution::TwoPage => TWO_PAGE_HEIGHT,
+            Resolution::High => HIRES_HEIGHT,
+        }
+    }
+
+    /// Gets the resolution mode from its index in a save state
+    ///
+    /// # Params
+    ///
+    /// - `index` - The index of the resolution mode
+    pub(crate) const fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(Resolution::Low),
+            1 => Some(Resolution::High),
+            2 => Some(Resolution::TwoPage),
+            _ => None,
+        }
+    }
+
+    /// Gets the index of the resolution mode in a save state, which is the
+    /// flag of the high resolution mode older save states stored
+    pub(crate) const fn index(self) -> u8 {
+        match self {
+            Resolution::Low => 0,
+            Resolution::High => 1,
+            Resolution::TwoPage => 2,
+        }
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Checks whether a ROM is written for the two-page mode (hi-res Chip-8),
+/// which such ROMs announce by jumping into the patch of the interpreter
+/// they carry
+///
+/// # Params
+///
+/// - `rom` - The bytes of the ROM
+pub fn is_two_page_rom(rom: &[u8]) -> bool {
+    rom.starts_with(&TWO_PAGE_HEADER)
+}
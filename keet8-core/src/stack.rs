@@ -0,0 +1,125 @@
+use super::state::{Snapshot, StateReader, StateWriter};
+
+use crate::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// This represents the size limit of the call stack, being the largest limit
+/// the emulator can be given and the one it starts with
+pub const STACK_SIZE: usize = 32;
+
+// --- stack definition -------------------------------------------------------
+
+pub(crate) struct CallStack {
+    /// The underlying array holding the data of the stack
+    data: [u16; STACK_SIZE],
+    /// The stack pointer pointing to the next open slot in `data`
+    ptr: usize,
+    /// The number of addresses the stack holds before it is full
+    limit: usize,
+}
+
+impl CallStack {
+    /// Creates a new instance of the address stack
+    /// 
+    /// This will initialize the buffer and the stack pointer to `0`
+    /// indicating that the stack is empty
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            data: [0; STACK_SIZE],
+            ptr: 0,
+            limit: STACK_SIZE,
+        }
+    }
+
+    /// Gets the number of addresses the stack holds before it is full
+    #[inline(always)]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Sets the number of addresses the stack holds before it is full
+    ///
+    /// The addresses already on the stack are kept, even if there are more
+    /// than the limit.
+    ///
+    /// # Params
+    ///
+    /// - `limit` - The number of addresses, capped at `STACK_SIZE`
+    #[inline(always)]
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.min(STACK_SIZE);
+    }
+
+    /// Empties the stack, keeping its limit
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.data = [0; STACK_SIZE];
+        self.ptr = 0;
+    }
+
+    /// Pushes an address onto the stack
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to push onto the stack
+    /// 
+    /// # Errors
+    /// 
+    /// If the stack limit has been reached
+    #[inline(always)]
+    pub const fn push(&mut self, addr: u16) -> Result<()> {
+        if self.ptr >= self.limit {
+            return Err(Keet8Error::CallStackFull);
+        }
+
+        self.data[self.ptr] = addr;
+        self.ptr += 1;
+
+        Ok(())
+    }
+
+    /// Pops an address from the stack
+    ///
+    /// Returns [Some] if the stack has a value
+    /// on the stack. Returns [None] otherwise
+    #[inline(always)]
+    pub const fn pop(&mut self) -> Option<u16> {
+        if self.ptr == 0 {
+            return None;
+        }
+
+        self.ptr -= 1;
+        Some(self.data[self.ptr])
+    }
+
+    /// Gets the addresses currently on the stack, from the bottom to the top
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[u16] {
+        &self.data[..self.ptr]
+    }
+}
+
+impl Snapshot for CallStack {
+    /// Writes the stack pointer and the whole underlying array
+    fn save(&self, writer: &mut StateWriter) {
+        writer.u8(self.ptr as u8);
+        self.data.iter().for_each(|&addr| writer.u16(addr));
+    }
+
+    /// Restores the stack pointer and the whole underlying array
+    fn load(&mut self, reader: &mut StateReader) -> Result<()> {
+        let ptr = reader.u8()? as usize;
+        if ptr > STACK_SIZE {
+            return Err(Keet8Error::InvalidSaveState);
+        }
+
+        for addr in self.data.iter_mut() {
+            *addr = reader.u16()?;
+        }
+
+        self.ptr = ptr;
+        Ok(())
+    }
+}
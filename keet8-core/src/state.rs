@@ -0,0 +1,411 @@
+use super::{Emulator, InitPolicy, Resolution, ALL_ROWS, AUDIO_PATTERN_SIZE};
+
+use crate::hash::crc32;
+use crate::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The bytes every save state starts with, identifying the format
+const STATE_MAGIC: &[u8; 4] = b"K8ST";
+/// The bytes the save states of the formats before the versioned header
+/// started with, followed by the version as an ASCII digit
+const LEGACY_MAGIC: &[u8; 3] = b"K8S";
+/// The version of the layout of the save states, bumped whenever the state of
+/// a subsystem changes, following the versions of the legacy formats
+pub(crate) const STATE_VERSION: u16 = 6;
+/// The number of bytes of the header, being the magic, the version and the
+/// hash of the ROM
+const HEADER_SIZE: usize = STATE_MAGIC.len() + 2 + 8;
+/// The number of bytes of the CRC-32 ending a save state
+const CRC_SIZE: usize = 4;
+
+// --- snapshot definition ----------------------------------------------------
+
+/// Implemented by the subsystems of the emulator whose state is part of a
+/// save state
+pub(crate) trait Snapshot {
+    /// Writes the state of the subsystem
+    ///
+    /// # Params
+    ///
+    /// - `writer` - The writer of the save state
+    fn save(&self, writer: &mut StateWriter);
+
+    /// Restores the state of the subsystem
+    ///
+    /// # Params
+    ///
+    /// - `reader` - The reader of the save state
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely or contains invalid values
+    fn load(&mut self, reader: &mut StateReader) -> Result<()>;
+}
+
+// --- state writer definition ------------------------------------------------
+
+/// Writes the values of a save state in a fixed, platform independent layout
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    /// The bytes written so far
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    /// Writes a byte
+    ///
+    /// # Params
+    ///
+    /// - `val` - The byte to write
+    pub fn u8(&mut self, val: u8) {
+        self.bytes.push(val);
+    }
+
+    /// Writes a 16-bit unsigned integer (big endian)
+    ///
+    /// # Params
+    ///
+    /// - `val` - The integer to write
+    pub fn u16(&mut self, val: u16) {
+        self.bytes.extend_from_slice(&val.to_be_bytes());
+    }
+
+    /// Writes a 32-bit unsigned integer (big endian)
+    ///
+    /// # Params
+    ///
+    /// - `val` - The integer to write
+    pub fn u32(&mut self, val: u32) {
+        self.bytes.extend_from_slice(&val.to_be_bytes());
+    }
+
+    /// Writes a 64-bit unsigned integer (big endian)
+    ///
+    /// # Params
+    ///
+    /// - `val` - The integer to write
+    pub fn u64(&mut self, val: u64) {
+        self.bytes.extend_from_slice(&val.to_be_bytes());
+    }
+
+    /// Writes a flag
+    ///
+    /// # Params
+    ///
+    /// - `val` - The flag to write
+    pub fn bool(&mut self, val: bool) {
+        self.u8(val as u8);
+    }
+
+    /// Writes a slice of bytes as is
+    ///
+    /// # Params
+    ///
+    /// - `vals` - The bytes to write
+    pub fn bytes(&mut self, vals: &[u8]) {
+        self.bytes.extend_from_slice(vals);
+    }
+
+    /// Finishes writing and returns the save state
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+// --- state reader definition ------------------------------------------------
+
+/// Reads the values of a save state written by a [StateWriter]
+pub(crate) struct StateReader<'a> {
+    /// The bytes of the save state
+    bytes: &'a [u8],
+    /// The position of the next byte to read
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    /// Creates a reader over the bytes of a save state
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The bytes of the save state
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Reads a byte
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Reads a 16-bit unsigned integer (big endian)
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely
+    pub fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Reads a 32-bit unsigned integer (big endian)
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely
+    pub fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a 64-bit unsigned integer (big endian)
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely
+    pub fn u64(&mut self) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    /// Reads a flag
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    /// Reads a slice of bytes
+    ///
+    /// # Params
+    ///
+    /// - `len` - The number of bytes to read
+    ///
+    /// # Errors
+    ///
+    /// If the save state ended prematurely
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(Keet8Error::InvalidSaveState)?;
+
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Checks that the whole save state has been read
+    ///
+    /// # Errors
+    ///
+    /// If there are bytes left over
+    pub fn finish(self) -> Result<()> {
+        if self.pos != self.bytes.len() {
+            return Err(Keet8Error::InvalidSaveState);
+        }
+
+        Ok(())
+    }
+}
+
+// --- emulator state ---------------------------------------------------------
+
+impl Emulator {
+    /// Captures the full state of the emulator as a save state
+    ///
+    /// This includes the registers, timers, call stack, memory, video buffer
+    /// and keypad. The quirks and registered opcode handlers are part of the
+    /// configuration rather than the state, so they are not included.
+    ///
+    /// The state is preceded by a header with the version of the format and
+    /// the hash of the ROM, and followed by a CRC-32 of everything before it,
+    /// so that loading it into another ROM, another version of the emulator
+    /// or from a damaged file fails rather than corrupting the emulation.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::default();
+        writer.bytes(STATE_MAGIC);
+        writer.u16(STATE_VERSION);
+        writer.u64(self.rom_hash());
+        self.save(&mut writer);
+
+        let mut bytes = writer.finish();
+        let crc = crc32(&bytes);
+        bytes.extend_from_slice(&crc.to_be_bytes());
+        bytes
+    }
+
+    /// Restores the state of the emulator from a save state
+    ///
+    /// The emulator is left untouched if the save state can't be loaded
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The save state, as captured by [Emulator::save_state]
+    ///
+    /// # Errors
+    ///
+    /// - If the save state is truncated or corrupted
+    /// - If the save state was written in another version of the format
+    /// - If the save state was captured with another ROM
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<()> {
+        let (rom_hash, payload) = open_state(bytes)?;
+        if rom_hash != self.rom_hash() {
+            return Err(Keet8Error::SaveStateRomMismatch(rom_hash));
+        }
+
+        let mut backup = StateWriter::default();
+        self.save(&mut backup);
+        let result = self.restore(payload);
+
+        if result.is_err() {
+            self.restore(&backup.finish())?;
+        }
+
+        self.update_audio_sink();
+        result
+    }
+
+    /// Creates an emulator from a save state alone, without the ROM it was
+    /// captured with, such as for inspecting the state
+    ///
+    /// The emulator takes on the hash of the ROM of the save state, but
+    /// [Emulator::reset] restarts it without a ROM
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The save state, as captured by [Emulator::save_state]
+    ///
+    /// # Errors
+    ///
+    /// - If the save state is truncated or corrupted
+    /// - If the save state was written in another version of the format
+    pub fn from_state(bytes: &[u8]) -> Result<Self> {
+        let (rom_hash, payload) = open_state(bytes)?;
+
+        let mut emulator = Self::without_rom(InitPolicy::Zeroed);
+        emulator.restore(payload)?;
+        emulator.memory.set_rom_hash(rom_hash);
+        Ok(emulator)
+    }
+
+    /// Restores the state of the emulator from the state of its subsystems,
+    /// which may leave the emulator partially restored if it is invalid
+    ///
+    /// # Params
+    ///
+    /// - `payload` - The state of the subsystems, without the header and
+    ///   checksum
+    ///
+    /// # Errors
+    ///
+    /// If the state is truncated or holds invalid values
+    fn restore(&mut self, payload: &[u8]) -> Result<()> {
+        let mut reader = StateReader::new(payload);
+        self.load(&mut reader)?;
+        reader.finish()
+    }
+}
+
+impl Snapshot for Emulator {
+    /// Writes the state of the emulator and all of its subsystems
+    fn save(&self, writer: &mut StateWriter) {
+        writer.bytes(&self.registers);
+        writer.u16(self.idx);
+        writer.u16(self.program_counter);
+        writer.u8(self.delay_timer);
+        writer.u8(self.sound_timer);
+        self.stack.save(writer);
+        self.memory.save(writer);
+        self.video_buffer.save(writer);
+        writer.u8(self.resolution.index());
+        writer.bool(self.halted);
+        writer.bytes(&self.rpl);
+        writer.bool(self.audio_pattern.is_some());
+        writer.bytes(&self.audio_pattern.unwrap_or_default());
+        writer.u8(self.pitch);
+        writer.bool(self.vblank);
+        writer.bytes(&self.keypad);
+        self.rng.save(writer);
+    }
+
+    /// Restores the state of the emulator and all of its subsystems
+    fn load(&mut self, reader: &mut StateReader) -> Result<()> {
+        let len = self.registers.len();
+        self.registers.copy_from_slice(reader.bytes(len)?);
+        self.idx = reader.u16()?;
+        self.program_counter = reader.u16()?;
+        self.delay_timer = reader.u8()?;
+        self.sound_timer = reader.u8()?;
+        self.finished = false;
+        self.stack.load(reader)?;
+        self.memory.load(reader)?;
+        self.video_buffer.load(reader)?;
+        self.resolution =
+            Resolution::from_index(reader.u8()?).ok_or(Keet8Error::InvalidSaveState)?;
+        self.screen_updated = true;
+        self.dirty_rows = ALL_ROWS;
+        self.halted = reader.bool()?;
+        let len = self.rpl.len();
+        self.rpl.copy_from_slice(reader.bytes(len)?);
+        let has_pattern = reader.bool()?;
+        let mut pattern = [0; AUDIO_PATTERN_SIZE];
+        pattern.copy_from_slice(reader.bytes(AUDIO_PATTERN_SIZE)?);
+        self.audio_pattern = has_pattern.then_some(pattern);
+        self.pitch = reader.u8()?;
+        self.vblank = reader.bool()?;
+        let len = self.keypad.len();
+        self.keypad.copy_from_slice(reader.bytes(len)?);
+        self.rng.load(reader)?;
+
+        Ok(())
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Checks the header and checksum of a save state, returning the hash of its
+/// ROM and the state of the subsystems
+///
+/// # Params
+///
+/// - `bytes` - The save state
+///
+/// # Errors
+///
+/// - If the save state is truncated or its checksum doesn't match
+/// - If the save state was written in another version of the format
+fn open_state(bytes: &[u8]) -> Result<(u64, &[u8])> {
+    let mut reader = StateReader::new(bytes);
+    let magic = reader.bytes(STATE_MAGIC.len())?;
+    if magic != STATE_MAGIC {
+        return match magic.split_last() {
+            Some((&digit, legacy)) if legacy == LEGACY_MAGIC && digit.is_ascii_digit() => {
+                Err(Keet8Error::UnsupportedSaveStateVersion((digit - b'0') as u16))
+            }
+            _ => Err(Keet8Error::InvalidSaveState),
+        };
+    }
+
+    let version = reader.u16()?;
+    if version != STATE_VERSION {
+        return Err(Keet8Error::UnsupportedSaveStateVersion(version));
+    }
+    let rom_hash = reader.u64()?;
+
+    let (contents, crc) = bytes
+        .split_at_checked(bytes.len().saturating_sub(CRC_SIZE))
+        .filter(|(contents, _)| contents.len() >= HEADER_SIZE)
+        .ok_or(Keet8Error::InvalidSaveState)?;
+    if crc32(contents).to_be_bytes() != crc {
+        return Err(Keet8Error::InvalidSaveState);
+    }
+
+    Ok((rom_hash, &contents[HEADER_SIZE..]))
+}
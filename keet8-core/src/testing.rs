@@ -0,0 +1,82 @@
+use super::opcode::OpCode;
+use super::Emulator;
+
+use crate::prelude::*;
+
+// --- testing interface ------------------------------------------------------
+
+impl Emulator {
+    /// Executes a single decoded instruction, as if it was fetched from the
+    /// program counter
+    ///
+    /// The program counter is advanced past the instruction before it is
+    /// executed, just like `step` does, but the timers aren't ticked and no
+    /// opcode handlers are consulted
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The instruction to execute
+    ///
+    /// # Errors
+    ///
+    /// If the instruction returned an error
+    pub fn execute(&mut self, opcode: OpCode) -> Result<()> {
+        self.program_counter = self.program_counter.wrapping_add(2);
+        self.instructions[opcode.instr as usize](self, opcode)
+    }
+
+    /// Sets the program counter
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to continue execution at
+    pub fn set_program_counter(&mut self, addr: u16) {
+        self.program_counter = addr;
+    }
+
+    /// Sets the index register
+    ///
+    /// # Params
+    ///
+    /// - `val` - The value to assign to the index register
+    pub fn set_index(&mut self, val: u16) {
+        self.idx = val;
+    }
+
+    /// Sets the delay timer
+    ///
+    /// # Params
+    ///
+    /// - `val` - The value to assign to the delay timer
+    pub fn set_delay_timer(&mut self, val: u8) {
+        self.delay_timer = val;
+    }
+
+    /// Sets the sound timer
+    ///
+    /// # Params
+    ///
+    /// - `val` - The value to assign to the sound timer
+    pub fn set_sound_timer(&mut self, val: u8) {
+        self.sound_timer = val;
+    }
+
+    /// Reads a byte from memory
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to read
+    pub fn read_memory(&self, addr: u16) -> u8 {
+        self.memory[addr]
+    }
+
+    /// Writes a byte to memory
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The memory address to write
+    /// - `val` - The value to write
+    pub fn write_memory(&mut self, addr: u16, val: u8) {
+        self.memory.write(addr, val);
+    }
+}
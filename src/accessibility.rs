@@ -0,0 +1,58 @@
+use raylib::prelude::*;
+use serde::Deserialize;
+
+// --- constants --------------------------------------------------------------
+
+/// The width in pixels of the frame drawn around the window whilst the buzzer
+/// sounds
+const BELL_FRAME: f32 = 12.0;
+
+/// Gets the flag of an option within the accessibility options
+type OptionFlag = fn(&mut Accessibility) -> &mut bool;
+
+/// The accessibility options, by their names in the `[accessibility]` table
+/// of the configuration file, with their labels in the settings menu and the
+/// flag of each
+pub(crate) const OPTIONS: [(&str, &str, OptionFlag); 3] = [
+    ("pixel_grid", "Pixel grid", |options| &mut options.pixel_grid),
+    ("large_text", "Large text", |options| &mut options.large_text),
+    ("visual_bell", "Visual bell", |options| &mut options.visual_bell),
+];
+
+// --- accessibility definition -----------------------------------------------
+
+/// The options making the emulator easier to see and follow, set in the
+/// `[accessibility]` table of the configuration file or from the settings
+/// menu
+///
+/// They go along with the high-contrast built-in palettes.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Accessibility {
+    /// Flag indicating whether lines are drawn between the pixels of the
+    /// display, telling apart the pixels of large sprites
+    pub pixel_grid: bool,
+    /// Flag indicating whether the messages of the on-screen display are
+    /// drawn twice as large
+    pub large_text: bool,
+    /// Flag indicating whether a frame is drawn around the window whilst the
+    /// sound timer runs, showing the buzzer even when it is muted or there is
+    /// no audio device
+    pub visual_bell: bool,
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Draws the frame of the visual bell along the edges of the window
+///
+/// The frame stays on for as long as the buzzer sounds rather than blinking,
+/// as the short beeps of most ROMs already make it flash.
+///
+/// # Params
+///
+/// - `d` - The handle to draw with
+/// - `color` - The color of the frame, being that of the pixels which are on
+pub(crate) fn draw_visual_bell(d: &mut RaylibDrawHandle, color: Color) {
+    let (width, height) = (d.get_screen_width() as f32, d.get_screen_height() as f32);
+    d.draw_rectangle_lines_ex(Rectangle::new(0.0, 0.0, width, height), BELL_FRAME, color);
+}
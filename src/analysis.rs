@@ -0,0 +1,337 @@
+use keet8_core::opcode::{AddressMode, Instruction, OpCode};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+// --- byte kind definition ---------------------------------------------------
+
+/// What a byte of a ROM is used as
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteKind {
+    /// Part of an instruction reachable from the entry point
+    Code,
+    /// Part of data referenced through the index register
+    Data,
+    /// Neither reachable as code nor referenced as data
+    Unreferenced,
+}
+
+impl ByteKind {
+    /// Gets the name of the kind as used in reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::Data => "data",
+            Self::Unreferenced => "unreferenced",
+        }
+    }
+}
+
+// --- reference definition ---------------------------------------------------
+
+/// How an instruction refers to an address of the ROM
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RefKind {
+    /// The address is the subroutine of a `CALL`
+    Call,
+    /// The address is the target of a `JP`
+    Jump,
+    /// The address is loaded into the index register
+    Load,
+}
+
+impl RefKind {
+    /// Gets the comment the references of the kind are listed after in the
+    /// disassembly
+    pub fn comment(self) -> &'static str {
+        match self {
+            Self::Call => "called from",
+            Self::Jump => "jumped to from",
+            Self::Load => "referenced from",
+        }
+    }
+}
+
+// --- analysis definition ----------------------------------------------------
+
+/// The static analysis of a ROM, classifying each of its bytes as code, data
+/// or unreferenced
+///
+/// Code is found by following the control flow from the load address through
+/// jumps, calls and skips. Computed jumps (`JP V0, addr`) can't be followed,
+/// so code only reachable through them is reported as unreferenced. Data
+/// starts at the addresses loaded into the index register and extends up to
+/// the next code or referenced address, as the length of the data isn't
+/// known. Along the way, the addresses the instructions call, jump to and
+/// load into the index register are noted as cross-references.
+pub(crate) struct Analysis {
+    /// The address the ROM is loaded and starts at
+    load_addr: u16,
+    /// The kind of each byte of the ROM, indexed by its offset in the ROM
+    kinds: Vec<ByteKind>,
+    /// The instructions referring to each address, with how they refer to
+    /// it, in order of address
+    refs: BTreeMap<u16, BTreeSet<(RefKind, u16)>>,
+}
+
+impl Analysis {
+    /// Analyzes a ROM
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    /// - `load_addr` - The address the ROM is loaded and starts at
+    pub fn analyze(rom: &[u8], load_addr: u16) -> Self {
+        let mut kinds = vec![ByteKind::Unreferenced; rom.len()];
+        let mut data_refs = Vec::new();
+        let mut refs: BTreeMap<u16, BTreeSet<(RefKind, u16)>> = BTreeMap::new();
+
+        let offset_of = |addr: u16| (addr as usize).checked_sub(load_addr as usize);
+        let mut pending = vec![load_addr];
+
+        while let Some(addr) = pending.pop() {
+            let Some(offset) = offset_of(addr).filter(|&offset| offset + 1 < rom.len()) else {
+                continue;
+            };
+
+            if kinds[offset] == ByteKind::Code {
+                continue;
+            }
+
+            kinds[offset] = ByteKind::Code;
+            kinds[offset + 1] = ByteKind::Code;
+
+            let opcode = OpCode::from(((rom[offset] as u16) << 8) | (rom[offset + 1] as u16));
+            match (opcode.instr, opcode.address_mode) {
+                (Instruction::CALL, AddressMode::Addr { address }) => {
+                    refs.entry(address).or_default().insert((RefKind::Call, addr));
+                    pending.push(address);
+                }
+                (Instruction::JP, AddressMode::Addr { address }) => {
+                    refs.entry(address).or_default().insert((RefKind::Jump, addr));
+                }
+                (Instruction::LD, AddressMode::IAddr { address }) => {
+                    refs.entry(address).or_default().insert((RefKind::Load, addr));
+                    data_refs.extend(offset_of(address))
+                }
+                _ => {}
+            }
+
+            pending.extend(successors(&opcode, addr));
+        }
+
+        for offset in data_refs {
+            for kind in kinds.iter_mut().skip(offset) {
+                if *kind != ByteKind::Unreferenced {
+                    break;
+                }
+
+                *kind = ByteKind::Data;
+            }
+        }
+
+        Self { load_addr, kinds, refs }
+    }
+
+    /// Gets the address the ROM is loaded and starts at
+    pub fn load_address(&self) -> u16 {
+        self.load_addr
+    }
+
+    /// Gets the kind of the byte at an address, if the address lies within
+    /// the ROM
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the byte
+    pub fn kind(&self, addr: u16) -> Option<ByteKind> {
+        (addr as usize)
+            .checked_sub(self.load_addr as usize)
+            .and_then(|offset| self.kinds.get(offset))
+            .copied()
+    }
+
+    /// Gets the instructions referring to an address, with how they refer to
+    /// it, ordered by kind and then by address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address referred to
+    pub fn references(&self, addr: u16) -> impl Iterator<Item = (RefKind, u16)> + '_ {
+        self.refs.get(&addr).into_iter().flatten().copied()
+    }
+
+    /// Gets the consecutive ranges of addresses with the same kind, in order
+    pub fn ranges(&self) -> Vec<(Range<u16>, ByteKind)> {
+        let mut ranges: Vec<(Range<u16>, ByteKind)> = Vec::new();
+
+        for (offset, &kind) in self.kinds.iter().enumerate() {
+            let addr = self.load_addr + offset as u16;
+            match ranges.last_mut() {
+                Some((range, last)) if *last == kind => range.end = addr + 1,
+                _ => ranges.push((addr..addr + 1, kind)),
+            }
+        }
+
+        ranges
+    }
+}
+
+// --- call graph definition --------------------------------------------------
+
+/// The static call graph of a ROM, built from its `CALL` and `RET`
+/// instructions
+///
+/// The entry point is treated as a subroutine of its own, so the call depth
+/// of a subroutine is the number of return addresses on the stack while it
+/// runs. Like the analysis, calls only reachable through computed jumps are
+/// missed.
+pub(crate) struct CallGraph {
+    /// The address the ROM is loaded and starts at
+    entry: u16,
+    /// The subroutines called by each subroutine, keyed by their addresses
+    calls: BTreeMap<u16, BTreeSet<u16>>,
+}
+
+impl CallGraph {
+    /// Builds the call graph of a ROM
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    /// - `load_addr` - The address the ROM is loaded and starts at
+    pub fn build(rom: &[u8], load_addr: u16) -> Self {
+        let mut calls = BTreeMap::new();
+        let mut subroutines = vec![load_addr];
+
+        while let Some(subroutine) = subroutines.pop() {
+            if calls.contains_key(&subroutine) {
+                continue;
+            }
+
+            // The body of the subroutine is everything reachable from its
+            // start without following calls
+            let mut callees = BTreeSet::new();
+            let mut visited = BTreeSet::new();
+            let mut pending = vec![subroutine];
+
+            while let Some(addr) = pending.pop() {
+                let Some(opcode) = decode(rom, load_addr, addr) else {
+                    continue;
+                };
+
+                if !visited.insert(addr) {
+                    continue;
+                }
+
+                if let (Instruction::CALL, AddressMode::Addr { address }) =
+                    (opcode.instr, opcode.address_mode)
+                {
+                    callees.insert(address);
+                    subroutines.push(address);
+                }
+
+                pending.extend(successors(&opcode, addr));
+            }
+
+            calls.insert(subroutine, callees);
+        }
+
+        Self { entry: load_addr, calls }
+    }
+
+    /// Gets the subroutines with the subroutines each of them calls, in order
+    /// of address
+    pub fn subroutines(&self) -> impl Iterator<Item = (u16, &BTreeSet<u16>)> {
+        self.calls.iter().map(|(&addr, callees)| (addr, callees))
+    }
+
+    /// Gets the worst-case number of return addresses on the call stack, or
+    /// `None` if a subroutine can (indirectly) call itself, which makes the
+    /// depth unbounded
+    pub fn max_depth(&self) -> Option<usize> {
+        let mut depths = BTreeMap::new();
+        self.depth(self.entry, &mut depths, &mut BTreeSet::new())
+    }
+
+    /// Checks whether the worst case could overflow the call stack of the
+    /// emulator, raising `CallStackFull` at runtime
+    ///
+    /// # Params
+    ///
+    /// - `limit` - The number of return addresses the call stack holds
+    pub fn may_overflow(&self, limit: usize) -> bool {
+        self.max_depth().is_none_or(|depth| depth > limit)
+    }
+
+    /// Gets the worst-case number of return addresses pushed by a subroutine
+    /// and the subroutines it calls
+    ///
+    /// # Params
+    ///
+    /// - `subroutine` - The address of the subroutine
+    /// - `depths` - The depths of the subroutines computed so far
+    /// - `active` - The subroutines currently being computed, to detect
+    ///   recursion
+    fn depth(
+        &self,
+        subroutine: u16,
+        depths: &mut BTreeMap<u16, usize>,
+        active: &mut BTreeSet<u16>,
+    ) -> Option<usize> {
+        if let Some(&depth) = depths.get(&subroutine) {
+            return Some(depth);
+        }
+
+        if !active.insert(subroutine) {
+            return None;
+        }
+
+        let mut depth = 0;
+        for &callee in self.calls.get(&subroutine).into_iter().flatten() {
+            depth = depth.max(1 + self.depth(callee, depths, active)?);
+        }
+
+        active.remove(&subroutine);
+        depths.insert(subroutine, depth);
+
+        Some(depth)
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Decodes the instruction at an address, if it lies within the ROM
+///
+/// # Params
+///
+/// - `rom` - The bytes of the ROM
+/// - `load_addr` - The address the ROM is loaded at
+/// - `addr` - The address of the instruction
+fn decode(rom: &[u8], load_addr: u16, addr: u16) -> Option<OpCode> {
+    let offset = (addr as usize).checked_sub(load_addr as usize)?;
+    let bytes = rom.get(offset..offset + 2)?;
+
+    Some(OpCode::from(((bytes[0] as u16) << 8) | (bytes[1] as u16)))
+}
+
+/// Gets the addresses execution can continue at after an instruction within
+/// the same subroutine, where calls continue at the next instruction once
+/// they return
+///
+/// # Params
+///
+/// - `opcode` - The decoded instruction
+/// - `addr` - The address of the instruction
+fn successors(opcode: &OpCode, addr: u16) -> Vec<u16> {
+    let next = addr.wrapping_add(2);
+
+    match (opcode.instr, opcode.address_mode) {
+        (Instruction::JP, AddressMode::Addr { address }) => vec![address],
+        (Instruction::JP, _) | (Instruction::RET, _) | (Instruction::EXIT, _) => Vec::new(),
+        (Instruction::SE | Instruction::SNE | Instruction::SKP | Instruction::SKNP, _) => {
+            vec![next, next.wrapping_add(2)]
+        }
+        _ => vec![next],
+    }
+}
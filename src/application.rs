@@ -0,0 +1,2784 @@
+use crate::accessibility::{self, Accessibility};
+use crate::analysis::CallGraph;
+use crate::audio::{step_volume, AudioDump, Buzzer, BuzzerSink};
+use crate::assembler::assemble;
+use crate::bug_report::BugReport;
+use crate::cheats::Cheats;
+use crate::config::{config_file, save_settings, Config, Setting, DEFAULT_CONFIG_FILE};
+use crate::crash_report::crash_report;
+use crate::console::{print_memory, print_registers, Command, Console};
+use crate::debugger::{
+    draw_disassembly, draw_heatmap, DebugSetup, EditTarget, ExecutionControl, FrameGraph,
+    GdbAction, GdbStub, HexView, RegisterView, SpritePreview, ValueEditor,
+};
+#[cfg(feature = "scripting")]
+use crate::input::InputSource;
+use crate::input::{poll_all, Gamepad};
+use crate::input_log::{Frame, InputLog};
+use crate::input_script::InputScript;
+use crate::instance::Instance;
+use crate::keymap::{Keymap, Layout, NUM_KEYPAD_KEYS};
+use crate::netplay::Netplay;
+use crate::options::{Options, WatchTarget};
+use crate::osd::Osd;
+use crate::palette::PALETTES;
+use crate::pause_menu::{PauseAction, PauseMenu};
+use crate::overlay::Overlay;
+use crate::prelude::*;
+use crate::profiler::{call_path, Profiler};
+use crate::recent::{RecentRoms, MAX_RECENT_ROMS};
+use crate::recorder::Recorder;
+use crate::remote::{RemoteAction, RemoteServer};
+use crate::rom_chooser::{Pick, RomChooser};
+use crate::roms::{archived_roms, builtin_name, file_on_disk, read_rom};
+use crate::scheduler::{is_idle, Scheduler};
+use crate::settings::{Choice, SettingsMenu};
+#[cfg(feature = "scripting")]
+use crate::scripting::Script;
+use crate::screen::{framebuffer_image, Screen};
+use crate::sidecar::Sidecar;
+use crate::state_diff::{load_state_file, print_diff};
+use crate::state_dump::state_dump;
+use crate::symbols::Symbols;
+use crate::trace_log::TraceLog;
+use crate::unknown_opcodes::UnknownOpcodes;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::{
+    Emulator, Event, HostCall, InitPolicy, Lcg, Platform, Renderer, ScriptedRandom, STACK_SIZE,
+    StepOutcome, WatchHit,
+};
+use log::{debug, error, info, warn};
+use raylib::prelude::*;
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// --- constants --------------------------------------------------------------
+
+/// Represents the title of the emulator
+const TITLE: &str = "Keet-8";
+/// Represents the current version of the emulator
+const VERSION: &str = "v1.0.0";
+
+/// The number of save state slots available per ROM
+const NUM_STATE_SLOTS: u8 = 10;
+
+/// The number of instructions per second the speed hotkeys change the CPU
+/// speed by
+pub(crate) const CPU_SPEED_STEP: u32 = 60;
+/// The highest CPU speed the speed hotkeys go up to
+pub(crate) const MAX_CPU_SPEED: u32 = 6000;
+/// The gamepad read as input, being the first one connected
+const GAMEPAD: i32 = 0;
+/// The largest fraction of the monitor the window takes up when it is scaled
+/// up for a high-DPI display
+const MAX_MONITOR_FILL: f32 = 0.9;
+/// The thickness of the frame around the half of the window running the
+/// other quirks once the screens diverge
+const DIVERGENCE_FRAME: f32 = 4.0;
+/// The number of rows Page Up and Page Down scroll the hex view by
+const HEX_VIEW_PAGE: i32 = 8;
+/// The digit keys starting the numbered recent ROMs, from `1` to `9`
+const DIGIT_KEYS: [KeyboardKey; MAX_RECENT_ROMS] = [
+    KeyboardKey::KEY_ONE,
+    KeyboardKey::KEY_TWO,
+    KeyboardKey::KEY_THREE,
+    KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_FIVE,
+    KeyboardKey::KEY_SIX,
+    KeyboardKey::KEY_SEVEN,
+    KeyboardKey::KEY_EIGHT,
+    KeyboardKey::KEY_NINE,
+];
+/// The name of the sidecar section holding the RPL user flags
+const RPL_SECTION: &str = "rpl";
+/// The name of the file in the sidecar holding the contents of the save RAM
+const SAVE_RAM_FILE: &str = "save_ram.bin";
+/// The name of the file in the sidecar holding the state of the ROM when it
+/// was last quit, to resume from on the next launch
+const RESUME_FILE: &str = "resume.k8s";
+
+// --- application definition -------------------------------------------------
+
+pub(crate) struct Application {
+    /// The handle to the raylib context
+    rl: RaylibHandle,
+    /// The thread on which raylib is running on
+    thread: RaylibThread,
+    /// Flag indicating whether the application is still running
+    is_running: bool,
+    /// Flag indicating whether debug information is to be drawn on the window
+    debug: bool,
+    /// The actual Chip-8 emulator
+    emulator: Emulator,
+    /// The spreading of the instructions and timer ticks over the frames
+    scheduler: Scheduler,
+    /// The color of the pixels which are on
+    foreground: Color,
+    /// The color of the pixels which are off
+    background: Color,
+    /// The built-in palette the colors were taken from, if any, as an index
+    /// into `PALETTES`
+    palette: Option<usize>,
+    /// The accessibility options, changed from the settings menu
+    accessibility: Accessibility,
+    /// The keyboard keys of the keypad, rebindable from within the
+    /// application
+    keymap: Keymap,
+    /// The keyboard keys of the second player on the same keypad, indexed by
+    /// the key on the keypad
+    player2_keys: [Option<KeyboardKey>; NUM_KEYPAD_KEYS],
+    /// The gamepad whose buttons are mapped to the keypad
+    gamepad: Gamepad,
+    /// The receiving end of the host calls made by the ROM, if the host-call
+    /// extension is enabled
+    host_calls: Option<Receiver<HostCall>>,
+    /// The receiving end of the events of the emulator
+    events: Receiver<Event>,
+    /// The debugger console reading commands from stdin, if enabled
+    console: Option<Console>,
+    /// The save state taken before the instruction last single-stepped or
+    /// the frame last run, with a description of what ran, which the
+    /// `changes` command of the console compares with the current state
+    before: Option<(String, Vec<u8>)>,
+    /// The code of the failed test reported by the ROM, if any
+    failed_test: Option<u8>,
+    /// The symbols of the loaded ROM, used to render addresses as labels
+    symbols: Symbols,
+    /// The data persisted for the loaded ROM across sessions
+    sidecar: Sidecar,
+    /// The debugging setup of the session, restored from the sidecar
+    debug_setup: DebugSetup,
+    /// The graph of the recent frame times, drawn in the debug overlay
+    frame_graph: FrameGraph,
+    /// The hex dump of the memory, drawn in the debug overlay
+    hex_view: HexView,
+    /// The registers, drawn in the debug overlay
+    register_view: RegisterView,
+    /// The memory shown as sprites, drawn in the debug overlay
+    sprite_preview: SpritePreview,
+    /// The editor of the byte of memory or register being edited in the
+    /// debug overlay, if any
+    editor: Option<ValueEditor>,
+    /// The control over the execution of the emulator by the debugger
+    control: ExecutionControl,
+    /// The save state slot used by the save and load hotkeys
+    state_slot: u8,
+    /// The on-screen display giving feedback on the hotkeys
+    osd: Osd,
+    /// The batched rectangles and text drawn on top of the emulator display
+    overlay: Overlay,
+    /// The emulator display, optionally drawn through the CRT filter
+    screen: Screen,
+    /// The buzzer, if sound is enabled and there is an audio device
+    buzzer: Option<Buzzer>,
+    /// The WAV file the sound is rendered to, if dumping it
+    audio_dump: Option<AudioDump>,
+    /// The profiler of the instructions executed, if profiling
+    profiler: Option<Profiler>,
+    /// Flag indicating whether the hash of the screen is shown in the window
+    /// title
+    frame_hash: bool,
+    /// The title last given to the window, so that it is only set again when
+    /// it changes
+    title: String,
+    /// Flag indicating whether the pixels erased by sprite collisions flash
+    /// for a frame
+    flash_collisions: bool,
+    /// The number of sprite collisions since the ROM was (re)started
+    total_collisions: u64,
+    /// The unknown opcodes executed since the ROM was loaded, reported when
+    /// the emulator closes
+    unknown_opcodes: UnknownOpcodes,
+    /// The frames captured whilst recording, if recording
+    recorder: Option<Recorder>,
+    /// The filepath to the ROM file, which screenshots and recordings are
+    /// saved next to
+    rom_file: PathBuf,
+    /// The time the ROM file was last modified, if the ROM is reloaded
+    /// whenever it changes
+    rom_modified: Option<SystemTime>,
+    /// Flag indicating whether the emulator runs as a development loop, which
+    /// starts even if the ROM fails to load and stays open once it exits
+    dev: bool,
+    /// Flag indicating whether the rest of a frame is skipped once the ROM
+    /// waits in an idle loop, which traces and profiles leave out to see
+    /// every instruction
+    idle_skip: bool,
+    /// The error which stopped the ROM, shown in the window until the ROM is
+    /// restarted or replaced
+    fault: Option<Fault>,
+    /// The ROMs of a ZIP archive or the recent ROMs to pick one from, shown
+    /// instead of running until one is picked
+    chooser: Option<RomChooser>,
+    /// The ROM files opened most recently, listed with `Ctrl+O`
+    recent: RecentRoms,
+    /// The settings menu, shown instead of running whilst it is open
+    settings: Option<SettingsMenu>,
+    /// The menu of the common actions, shown whilst the ROM is paused with
+    /// `P`
+    pause_menu: Option<PauseMenu>,
+    /// The configuration file the settings menu writes its changes to
+    config_file: String,
+    /// Flag indicating whether a ROM was loaded, so that the list of ROMs to
+    /// pick from can be closed to carry on with it
+    has_rom: bool,
+    /// The size of the square of a pixel in screenshots
+    screenshot_scale: u32,
+    /// The bug report written when the run ends, if requested
+    bug_report: Option<BugReport>,
+    /// The file each executed instruction is appended to, if tracing
+    trace_log: Option<TraceLog>,
+    /// The input of the current frame, recorded at its end, whose keys are
+    /// the ones held down
+    frame: Frame,
+    /// The filepath to and the log of the input being recorded, if recording
+    recording: Option<(String, InputLog)>,
+    /// The log of the input being replayed, which takes the place of the
+    /// keyboard and the timing of the frames, if replaying
+    replay: Option<InputLog>,
+    /// The connection to the other player, whose keys are combined with the
+    /// ones of this player every frame, if playing over the network
+    netplay: Option<Netplay>,
+    /// The stub external debuggers attach to, if enabled
+    gdb: Option<GdbStub>,
+    /// The server external tools drive the emulator through, if enabled
+    remote: Option<RemoteServer>,
+    /// Flag indicating whether the RPL user flags and the save RAM are
+    /// persisted in the sidecar, which replays and netplay leave out to stay
+    /// deterministic
+    persist: bool,
+    /// Flag indicating whether the state of the ROM is saved on exit and
+    /// offered to resume from on the next launch
+    auto_resume: bool,
+    /// The state of the ROM when it was last quit, offered to resume from
+    /// before the ROM starts, until taken or declined
+    resume: Option<Vec<u8>>,
+    /// The cheats written to memory every frame and after every instruction
+    cheats: Cheats,
+    /// The emulator running the ROM side by side with this one, in the right
+    /// half of the window, if any
+    side: Option<Instance>,
+    /// The comparison of the screens of the two emulators, if the ROM runs
+    /// side by side with the quirks of another platform
+    comparison: Option<Comparison>,
+    /// The script run alongside the ROM, if any
+    #[cfg(feature = "scripting")]
+    script: Option<Script>,
+}
+
+impl Application {
+    /// Creates an instance of the application and initializes raylib
+    ///
+    /// # Params
+    ///
+    /// - `options` - The options parsed from the command-line arguments
+    /// - `config` - The configuration, with the options already applied
+    ///
+    /// # Errors
+    ///
+    /// - If an error occured when loading the ROM file
+    /// - If an error occured when loading the symbol file
+    /// - If an error occured when loading the overlay font
+    /// - If the texture of the emulator display could not be created
+    /// - If an error occured when loading the random script
+    /// - If the trace file could not be opened
+    /// - If the input log to replay could not be loaded
+    /// - If the input script could not be loaded
+    /// - If the netplay session could not be set up
+    /// - If the GDB stub could not listen on its port
+    /// - If the cheats file could not be loaded
+    /// - If the script could not be loaded
+    /// - If the ROM run side by side could not be loaded
+    pub fn new(options: &Options, config: &Config) -> Result<Self> {
+        let symbols = match &options.symbol_file {
+            Some(symbol_file) => Symbols::load(symbol_file)?,
+            None => Symbols::default(),
+        };
+
+        // An archive of several ROMs starts without a ROM until one is
+        // picked, as does the window without a ROM file until one of the
+        // recent ROMs is, unless the input belongs to a ROM given up front
+        let interactive = options.replay_file.is_none()
+            && options.input_script_file.is_none()
+            && options.record_file.is_none()
+            && options.host_port.is_none()
+            && options.connect_addr.is_none();
+        let mut recent = RecentRoms::load();
+        let chooser = if options.rom_file.is_empty() {
+            if !interactive {
+                return Err(Keet8Error::NoROMFile);
+            }
+            Some(RomChooser::launcher(recent.roms()))
+        } else {
+            match archived_roms(&options.rom_file)? {
+                Some(roms) if roms.len() > 1 && interactive => {
+                    Some(RomChooser::new(&options.rom_file, roms))
+                }
+                _ => None,
+            }
+        };
+
+        let (mut rl, thread) = if cfg!(debug_assertions) {
+            raylib::init()
+                .size(config.window_width, config.window_height)
+                .title(&window_title())
+                .vsync()
+                .msaa_4x()
+                .resizable()
+                .build()
+
+        // We don't want logging for release builds
+        } else {
+            raylib::init()
+                .size(config.window_width, config.window_height)
+                .title(&window_title())
+                .vsync()
+                .msaa_4x()
+                .resizable()
+                .log_level(TraceLogLevel::LOG_NONE)
+                .build()
+        };
+
+        // Escape opens the settings menu rather than closing the window
+        rl.set_exit_key(None);
+
+        // The overlay text grows with the window on high-DPI displays
+        let scale = fit_window(&mut rl, config.window_width, config.window_height);
+        let mut theme = options.overlay_theme.clone();
+        theme.font_size = (theme.font_size as f32 * scale).round() as i32;
+        let overlay = Overlay::new(&mut rl, &thread, theme)?;
+        let mut screen = Screen::new(
+            &mut rl,
+            &thread,
+            config.crt,
+            config.integer_scaling,
+            config.phosphor,
+        )?;
+        screen.set_pixel_grid(config.accessibility.pixel_grid);
+        let mut osd = Osd::default();
+        osd.set_large_text(config.accessibility.large_text);
+        let sink = BuzzerSink::default();
+        let buzzer = config
+            .audio
+            .then(|| Buzzer::open(config.volume, config.tone, sink.clone()))
+            .flatten();
+        let audio_dump = options
+            .audio_dump_file
+            .as_deref()
+            .map(|path| AudioDump::create(path, config.tone, sink.clone()))
+            .transpose()?;
+
+        // The development loop starts without a ROM until the file is fixed
+        let mut fault = None;
+        let rom = match read_rom(&options.rom_file) {
+            Ok(rom) => rom,
+            Err(_) if chooser.is_some() => Vec::new(),
+            Err(error) if options.dev => {
+                fault = Some(Fault { error, pc: None });
+                Vec::new()
+            }
+            Err(e) => return Err(e),
+        };
+        let has_rom = chooser.is_none() && fault.is_none();
+        if has_rom {
+            recent.push(&options.rom_file);
+            if let Err(e) = recent.save() {
+                warn!("{}", e.message());
+            }
+        }
+        let stack_limit = config.platform.map_or(STACK_SIZE, Platform::stack_limit);
+        if CallGraph::build(&rom, config.load_addr).may_overflow(stack_limit) {
+            warn!("The call stack of {} may overflow", options.rom_file);
+        }
+
+        // The ROM is loaded once the memory has the size of the platform, and
+        // without a ROM the emulator waits for one to be picked or fixed
+        let create = |init| -> Result<Emulator> {
+            let mut emulator = Emulator::without_rom(init);
+            emulator.set_platform(config.platform);
+            emulator.set_load_address(config.load_addr)?;
+            if !rom.is_empty() {
+                emulator.load_rom(&rom)?;
+            }
+
+            Ok(emulator)
+        };
+        let mut emulator = create(InitPolicy::Zeroed)?;
+        info!("Loaded {} ({} bytes)", options.rom_file, rom.len());
+
+        let replay = options.replay_file.as_deref().map(InputLog::load).transpose()?;
+        let input_script = options.input_script_file.as_deref().map(InputScript::load).transpose()?;
+        if replay.as_ref().is_some_and(|replay| replay.rom_hash() != emulator.rom_hash()) {
+            warn!("The input log was recorded with another ROM");
+        }
+
+        // The seed is picked here rather than by the emulator, so that it can
+        // be recorded along with the input and shared with the other player
+        let seed = replay
+            .as_ref()
+            .map_or(options.seed, |replay| Some(replay.seed()))
+            .unwrap_or_else(rand::random);
+
+        let steps_per_frame = (config.cpu_speed / 60).max(1);
+
+        // An input script is played back as an input log of its own, unless
+        // a recorded one is replayed
+        let rom_hash = emulator.rom_hash();
+        let replay = replay.or_else(|| {
+            input_script.map(|script| script.into_input_log(seed, rom_hash, steps_per_frame))
+        });
+
+        let netplay = match (options.host_port, &options.connect_addr) {
+            (Some(port), _) => Some(Netplay::host(port, emulator.rom_hash(), seed, steps_per_frame)?),
+            (None, Some(addr)) => Some(Netplay::connect(addr, emulator.rom_hash())?),
+            (None, None) => None,
+        };
+        let seed = netplay.as_ref().map_or(seed, Netplay::seed);
+        debug!("Seeded RND with {seed}, running {steps_per_frame} instructions per frame");
+
+        // The garbage is generated from the seed as well, so that replays and
+        // netplay start from the same memory, which is why the emulator is
+        // only created with it once the seed is known
+        let init = if options.random_init {
+            InitPolicy::Random(seed)
+        } else {
+            InitPolicy::Zeroed
+        };
+        if options.random_init {
+            emulator = create(init)?;
+        }
+        emulator.set_quirks(config.quirks);
+        emulator.set_two_page(config.two_page);
+        if config.two_page {
+            info!("Running {} in the two-page mode (hi-res Chip-8)", options.rom_file);
+        }
+        emulator.set_strict(options.strict);
+        if buzzer.is_some() || audio_dump.is_some() {
+            emulator.set_audio_sink(sink);
+        }
+
+        if let Some(random_script) = &options.random_script {
+            emulator.set_random_source(ScriptedRandom::load(random_script)?);
+        } else {
+            emulator.set_random_source(Lcg::new(seed));
+        }
+
+        // Comparing quirks runs the ROM itself on the right
+        let side_rom_file = match options.compare {
+            Some(_) => Some(options.rom_file.as_str()),
+            None => options.side_rom_file.as_deref(),
+        };
+        let mut side = side_rom_file
+            .map(|rom_file| {
+                Instance::new(&mut rl, &thread, rom_file, config, options.strict, seed, init)
+            })
+            .transpose()?;
+        if let (Some(side), Some(platform)) = (&mut side, options.compare) {
+            side.compare_with(platform);
+        }
+
+        let recording = options
+            .record_file
+            .as_ref()
+            .map(|path| (path.clone(), InputLog::new(seed, emulator.rom_hash())));
+
+        let sidecar = Sidecar::open(emulator.rom_hash());
+        let persist = replay.is_none() && netplay.is_none() && recording.is_none();
+
+        // Resuming needs a ROM which loaded, and would throw the input of
+        // replays, recordings and netplay out of step
+        let auto_resume = persist && !options.no_resume;
+        let resume = (auto_resume && chooser.is_none() && fault.is_none())
+            .then(|| resume_state(&sidecar))
+            .flatten();
+        emulator.set_save_ram(options.save_ram.clone());
+        if persist {
+            emulator.set_rpl_flags(&parse_rpl_flags(sidecar.section(RPL_SECTION)));
+
+            let save_ram = sidecar
+                .file_path(SAVE_RAM_FILE)
+                .and_then(|path| std::fs::read(path).ok());
+            emulator.restore_save_ram(&save_ram.unwrap_or_default());
+        }
+
+        let mut cheats = Cheats::default();
+        if let Some(cheats_file) = &options.cheats_file {
+            cheats.load(cheats_file)?;
+        }
+        for &cheat in &options.cheats {
+            cheats.add(cheat, false);
+        }
+        for &cheat in &options.freezes {
+            cheats.add(cheat, true);
+        }
+
+        // Cheats aren't part of the input, so they would make the run drift
+        // from its log or from the other player
+        if !persist && !cheats.is_empty() {
+            warn!("Cheats are ignored when recording, replaying or in netplay");
+            cheats = Cheats::default();
+        }
+
+        // Scripts change memory and press keys outside of the input too
+        #[cfg(feature = "scripting")]
+        let script = match &options.script_file {
+            Some(_) if !persist => {
+                warn!("The script is ignored when recording, replaying or in netplay");
+                None
+            }
+            Some(script_file) => Some(Script::load(script_file, &mut emulator)?),
+            None => None,
+        };
+
+        let keymap = Keymap::load(&sidecar, config.keymap);
+        let mut debug_setup = DebugSetup::load(&sidecar);
+        for tracepoint in &options.tracepoints {
+            debug_setup.add_tracepoint(tracepoint.clone());
+        }
+        for &addr in &options.breakpoints {
+            debug_setup.add_breakpoint(addr);
+        }
+        for condition in &options.conditions {
+            debug_setup.add_condition(condition.clone());
+        }
+        for &target in &options.watchpoints {
+            debug_setup.add_watchpoint(target);
+        }
+        for &addr in &debug_setup.breakpoints {
+            emulator.add_breakpoint(addr);
+        }
+        for &addr in &debug_setup.watchpoints {
+            emulator.add_watchpoint(addr);
+        }
+        for &x in &debug_setup.register_watchpoints {
+            emulator.add_register_watchpoint(x);
+        }
+
+        let bug_report = options.bug_report.as_ref().map(|path| {
+            BugReport::new(
+                path,
+                &options.rom_file,
+                config_file(options),
+                config.cpu_speed,
+                config.quirks,
+            )
+        });
+
+        let trace_log = options
+            .trace_file
+            .as_deref()
+            .map(|path| TraceLog::open(path, options.trace_format))
+            .transpose()?;
+
+        // Built-in ROMs have no file, so their captures are saved in the
+        // working directory
+        let builtin = builtin_name(&options.rom_file);
+
+        let (sender, events) = mpsc::channel();
+        emulator.on_event(move |event| {
+            let _ = sender.send(event);
+        });
+
+        let host_calls = if options.host_calls {
+            let (sender, receiver) = mpsc::channel();
+            emulator.enable_host_calls(move |call| {
+                let _ = sender.send(call);
+            });
+
+            Some(receiver)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rl,
+            thread,
+            is_running: true,
+            debug: false,
+            emulator,
+            scheduler: Scheduler::new(config.cpu_speed),
+            foreground: config.foreground,
+            background: config.background,
+            palette: config.palette,
+            accessibility: config.accessibility,
+            keymap,
+            player2_keys: config.player2_keymap,
+            gamepad: Gamepad::new(GAMEPAD, config.gamepad_map),
+            host_calls,
+            events,
+            console: options.console.then(Console::open),
+            before: None,
+            failed_test: None,
+            symbols,
+            sidecar,
+            debug_setup,
+            frame_graph: FrameGraph::default(),
+            hex_view: HexView::default(),
+            sprite_preview: SpritePreview::default(),
+            register_view: RegisterView::default(),
+            editor: None,
+            control: ExecutionControl::default(),
+            state_slot: 0,
+            osd,
+            overlay,
+            screen,
+            buzzer,
+            audio_dump,
+            profiler: options.profile_file.as_deref().map(Profiler::new),
+            frame_hash: options.frame_hash,
+            title: String::new(),
+            flash_collisions: options.flash_collisions,
+            total_collisions: 0,
+            unknown_opcodes: UnknownOpcodes::default(),
+            recorder: None,
+            rom_file: PathBuf::from(builtin.unwrap_or(&options.rom_file)),
+            rom_modified: (options.reload && builtin.is_none())
+                .then(|| modified_time(&options.rom_file).unwrap_or(UNIX_EPOCH)),
+            dev: options.dev,
+            idle_skip: !options.no_idle_skip
+                && options.trace_file.is_none()
+                && options.profile_file.is_none(),
+            fault,
+            chooser,
+            recent,
+            has_rom,
+            settings: None,
+            pause_menu: None,
+            config_file: config_file(options).unwrap_or(DEFAULT_CONFIG_FILE).to_string(),
+            screenshot_scale: config.screenshot_scale,
+            bug_report,
+            trace_log,
+            frame: Frame::default(),
+            recording,
+            replay,
+            netplay,
+            gdb: options.gdb_port.map(GdbStub::open).transpose()?,
+            remote: options.remote_port.map(RemoteServer::open).transpose()?,
+            persist,
+            auto_resume,
+            resume,
+            cheats,
+            side,
+            comparison: options.compare.map(Comparison::new),
+            #[cfg(feature = "scripting")]
+            script,
+        })
+    }
+
+    /// Runs the application
+    ///
+    /// An error of the emulator doesn't end the run, but stops the ROM and is
+    /// shown in the window until the ROM is restarted or replaced.
+    ///
+    /// # Errors
+    ///
+    /// - If an error of the emulator was still shown when the window closed
+    /// - If the ROM reported a failed test through the host-call extension
+    pub fn run(&mut self) -> Result<()> {
+        while self.is_running {
+            if let Err(error) = self.on_update() {
+                self.write_crash_report(&error);
+
+                let pc = match error {
+                    Keet8Error::InstructionFailed(pc, ..) | Keet8Error::UnknownOpcode(_, pc) => {
+                        Some(pc)
+                    }
+                    _ => None,
+                };
+                self.fault = Some(Fault { error, pc });
+            }
+
+            self.on_render();
+        }
+
+        self.on_exit();
+        self.write_bug_report(self.fault.as_ref().map(|fault| &fault.error));
+
+        if let Some(fault) = self.fault.take() {
+            return Err(fault.error);
+        }
+        match self.failed_test {
+            Some(code) => Err(Keet8Error::TestFailed(code)),
+            None => Ok(()),
+        }
+    }
+
+    /// Called once per frame to update the logic of the application
+    ///
+    /// # Errors
+    ///
+    /// If an error has occured during runtime of the emulator
+    fn on_update(&mut self) -> Result<()> {
+        let mut step_time = 0.0;
+
+        // Execute as many instructions as the CPU speed allows in the time
+        // since the last frame
+        self.scheduler.advance(self.rl.get_frame_time());
+
+        // Replays and netplay run whole frames of their own instead
+        // A ROM stopped by an error doesn't run at all
+        // Neither does one yet to be picked from an archive, nor one which
+        // may be resumed
+        let live = self.replay.is_none()
+            && self.netplay.is_none()
+            && self.fault.is_none()
+            && self.chooser.is_none()
+            && self.settings.is_none()
+            && self.resume.is_none();
+
+        // The script runs before the input is read, so the keys it holds are
+        // pressed in this frame
+        #[cfg(feature = "scripting")]
+        if !self.control.is_paused() {
+            self.run_script(Script::frame_start);
+        }
+
+        // The keys are read every frame, whether or not an instruction is
+        // due, so the keypad never lags behind the keyboard at low speeds
+        if live {
+            self.process_input();
+        }
+
+        // Only taken with the console open, as saving the state every frame
+        // is wasted otherwise
+        if live && self.console.is_some() && !self.control.is_paused() {
+            self.before = Some(("frame".to_string(), self.emulator.save_state()));
+        }
+
+        while live && self.scheduler.next_step() {
+            if !self.control.may_step() {
+                self.scheduler.skip_steps();
+                break;
+            }
+
+            let start = Instant::now();
+            let outcome = self.execute_step()?;
+            step_time += start.elapsed().as_secs_f32();
+
+            if let StepOutcome::Breakpoint(addr) = outcome {
+                self.control.pause();
+                self.osd.show(format!("Breakpoint at {}", self.symbols.symbolicate(addr)));
+                self.scheduler.skip_steps();
+                break;
+            }
+
+            // The program counter has already moved past the instruction
+            if let StepOutcome::Watchpoint(hit) = outcome {
+                let pc = self.emulator.program_counter().wrapping_sub(2);
+                let access = match hit {
+                    WatchHit::Read(addr) => format!("{addr:#05X} read"),
+                    WatchHit::Write(addr) => format!("{addr:#05X} written"),
+                    WatchHit::Register(x) => format!("V{x:X} changed"),
+                };
+
+                self.control.pause();
+                self.osd.show(format!("Watchpoint: {access} at {}", self.symbols.symbolicate(pc)));
+                self.scheduler.skip_steps();
+                break;
+            }
+
+            if let Some(condition) = self.check_conditions() {
+                let pc = self.emulator.program_counter().wrapping_sub(2);
+                self.control.pause();
+                self.osd.show(format!("Break: {condition} at {}", self.symbols.symbolicate(pc)));
+                self.scheduler.skip_steps();
+                break;
+            }
+
+            self.process_host_calls();
+            if self.emulator.is_halted() {
+                break;
+            }
+
+            // Spinning in an idle loop would only burn the host CPU until the
+            // timers tick
+            if self.idle_skip && is_idle(&self.emulator) {
+                self.scheduler.skip_steps();
+                break;
+            }
+        }
+
+        // The timers count down at 60Hz, independent of the CPU speed, and
+        // stand still whilst paused, except in netplay which has to keep in
+        // lockstep with the other player
+        if self.netplay.is_some() {
+            self.scheduler.skip_ticks();
+            self.netplay_frame()?;
+        } else if self.control.is_paused() || !live {
+            self.scheduler.skip_ticks();
+        } else if self.replay.is_some() {
+            self.scheduler.skip_ticks();
+            self.replay_frame()?;
+        } else {
+            while self.scheduler.next_tick() {
+                self.tick_timers();
+            }
+
+            #[cfg(feature = "scripting")]
+            self.run_script(Script::frame_end);
+        }
+
+        if let Some(side) = &mut self.side {
+            side.update(&self.rl, self.control.is_paused(), self.frame.keys);
+        }
+        self.compare_screens();
+
+        if let Some(buzzer) = &mut self.buzzer {
+            buzzer.update(self.control.is_paused());
+        }
+
+        self.process_events();
+        self.process_commands();
+        self.process_gdb();
+        self.process_remote();
+
+        // Close the application if the window has been closed or the ROM
+        // has exited the interpreter, which the development loop waits out,
+        // along with the ROM run side by side
+        let halted = self.emulator.is_halted() && self.side.iter().all(Instance::is_halted);
+        if self.rl.window_should_close() || (halted && !self.dev) {
+            self.is_running = false;
+        }
+
+        // Show debugging information when F3 has been pressed (like Minecraft)
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F3) {
+            self.debug = !self.debug;
+            self.emulator.set_access_counting(self.debug);
+        }
+
+        // The hex view of the debug overlay scrolls with the mouse wheel and
+        // Page Up/Page Down, and Home jumps to the program counter, whilst
+        // its bytes and the registers are edited by clicking them when paused.
+        // Right-clicking a byte shows the sprites from there
+        let editing = self.editor.is_some();
+        if self.debug {
+            self.process_hex_view_input();
+            self.process_edit_input();
+        } else {
+            self.editor = None;
+        }
+
+        // Debugger: F8 pauses and resumes, F10 executes a single instruction
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F8) {
+            self.control.toggle();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F10) {
+            self.control.request_step();
+        }
+
+        // P pauses and resumes like F8 with the pause menu shown, unless it
+        // is mapped to the keypad, and Ctrl+R restarts the ROM
+        if self.rl.is_key_pressed(KeyboardKey::KEY_P)
+            && !self.keymap.keys().contains(&KeyboardKey::KEY_P)
+            && !self.player2_keys.contains(&Some(KeyboardKey::KEY_P))
+        {
+            self.toggle_pause_menu();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_R)
+            && (self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL))
+        {
+            self.reset();
+        }
+
+        // Ctrl+O lists the ROMs opened recently, and closes the list again
+        if self.rl.is_key_pressed(KeyboardKey::KEY_O)
+            && (self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL))
+        {
+            self.toggle_recent_roms();
+        }
+
+        // Reloading the ROM would throw replays and netplay out of step
+        if self.replay.is_none() && self.netplay.is_none() {
+            self.reload_rom();
+        }
+
+        // The listed ROMs are picked from with the arrow keys and Enter,
+        // unless the settings menu was opened over them
+        let in_settings = self.settings.is_some();
+        if self.chooser.is_some() && !in_settings {
+            self.process_chooser_input();
+        }
+
+        // Enter resumes the ROM where it was quit, and Backspace starts over
+        if self.resume.is_some() {
+            self.process_resume_input();
+        }
+
+        // A ROM dropped onto the window replaces the running one
+        if self.rl.is_file_dropped() {
+            let dropped = self.rl.load_dropped_files();
+            if let Some(path) = dropped.paths().first() {
+                self.open_rom(path);
+            }
+        }
+
+        // The input of the frame is recorded once it is complete, leaving out
+        // the frames in which nothing happened
+        let keys = self.frame.keys;
+        let frame = std::mem::replace(&mut self.frame, Frame { keys, ..Frame::default() });
+        let executed = frame.steps;
+        if let Some((_, recording)) = &mut self.recording {
+            if frame.steps > 0 || frame.ticks > 0 || frame.reset {
+                recording.push(frame);
+            }
+        }
+
+        // Save states: F5 saves, F9 loads and F6/F7 cycle through the slots
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F5) {
+            self.save_state();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F9) {
+            self.load_state();
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F6) {
+            self.state_slot = (self.state_slot + NUM_STATE_SLOTS - 1) % NUM_STATE_SLOTS;
+            self.osd.show(format!("Slot {}", self.state_slot));
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F7) {
+            self.state_slot = (self.state_slot + 1) % NUM_STATE_SLOTS;
+            self.osd.show(format!("Slot {}", self.state_slot));
+        }
+
+        // Escape and F2 open and close the settings menu, which binds the
+        // keypad keys one by one, Backspace stopping and Delete restoring the
+        // configured keys
+        if self.keymap.is_binding() {
+            self.process_binding();
+        } else if editing {
+            // Escape and the arrow keys went to the byte being edited
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_F2)
+        {
+            self.toggle_settings();
+        } else if in_settings && self.settings.is_some() {
+            self.process_settings_input();
+        } else if self.pause_menu.is_some() && self.chooser.is_none() && self.resume.is_none() {
+            self.process_pause_menu_input();
+        }
+
+        // The pause menu goes away once the ROM is resumed some other way
+        if !self.control.is_paused() {
+            self.pause_menu = None;
+        }
+
+        // Print Screen saves a screenshot next to the ROM (F12 is taken by
+        // raylib, which saves its own screenshot of the whole window), and
+        // Shift+Print Screen starts and stops recording
+        if self.rl.is_key_pressed(KeyboardKey::KEY_PRINT_SCREEN) {
+            if self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+                || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)
+            {
+                self.toggle_recording();
+            } else {
+                self.save_screenshot();
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.capture(&self.emulator, self.rl.get_frame_time());
+        }
+
+        // F1 toggles the CRT filter
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F1) {
+            let enabled = self.screen.toggle_crt();
+            if let Some(side) = &mut self.side {
+                side.screen().toggle_crt();
+            }
+            self.osd.show(if enabled { "CRT filter on" } else { "CRT filter off" });
+        }
+
+        // Ctrl+I switches between integer scaling and stretching to fit
+        if self.rl.is_key_pressed(KeyboardKey::KEY_I)
+            && (self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL))
+        {
+            let enabled = self.screen.toggle_integer_scaling();
+            if let Some(side) = &mut self.side {
+                side.screen().toggle_integer_scaling();
+            }
+            self.osd.show(if enabled { "Integer scaling" } else { "Stretch to fit" });
+        }
+
+        // Ctrl+D writes a JSON dump of the machine state next to the ROM
+        if self.rl.is_key_pressed(KeyboardKey::KEY_D)
+            && (self.rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL))
+        {
+            let path = self.capture_path("json");
+            match self.save_state_dump(&path) {
+                Ok(_) => self.osd.show("Saved state dump"),
+                Err(e) => {
+                    error!("{}", e.message());
+                    self.osd.show("Failed to save state dump");
+                }
+            }
+        }
+
+        // F4 cycles through the built-in palettes
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F4) {
+            self.set_palette(self.palette.map_or(0, |index| (index + 1) % PALETTES.len()));
+        }
+
+        // CPU speed: + speeds up and - slows down, or with Shift raise and
+        // lower the volume, leaving the keys of the numpad to the ROM run side
+        // by side if they are mapped to its keypad
+        let side_keys = self.side.as_ref().map_or(&[][..], |side| &side.keymap()[..]);
+        let speed_key = |key| self.rl.is_key_pressed(key) && !side_keys.contains(&key);
+        let step = if speed_key(KeyboardKey::KEY_EQUAL) || speed_key(KeyboardKey::KEY_KP_ADD) {
+            1
+        } else if speed_key(KeyboardKey::KEY_MINUS) || speed_key(KeyboardKey::KEY_KP_SUBTRACT) {
+            -1
+        } else {
+            0
+        };
+        let shift = self.rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+            || self.rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        if step != 0 && shift {
+            self.change_volume(step);
+        } else if step > 0 {
+            self.set_cpu_speed((self.scheduler.cpu_speed() + CPU_SPEED_STEP).min(MAX_CPU_SPEED));
+        } else if step < 0 {
+            self.set_cpu_speed(self.scheduler.cpu_speed().saturating_sub(CPU_SPEED_STEP).max(1));
+        }
+
+        // M mutes and unmutes the buzzer, unless it is mapped to the keypad
+        if self.rl.is_key_pressed(KeyboardKey::KEY_M)
+            && !self.keymap.keys().contains(&KeyboardKey::KEY_M)
+            && !self.player2_keys.contains(&Some(KeyboardKey::KEY_M))
+        {
+            match self.buzzer.as_mut().map(|buzzer| (buzzer.toggle_mute(), buzzer.volume())) {
+                Some((true, _)) => self.osd.show("Muted"),
+                Some((false, volume)) => self.osd.show(format!("Volume {:.0}%", volume * 100.0)),
+                None => self.osd.show("No audio device"),
+            }
+        }
+
+        self.update_title();
+
+        self.osd.update(self.rl.get_frame_time());
+        self.frame_graph.record(self.rl.get_frame_time(), step_time, executed);
+
+        // Make the window fullsreen when F11 is pressed
+        if self.rl.is_key_pressed(KeyboardKey::KEY_F11) {
+            if self.rl.is_window_fullscreen() {
+                self.rl.toggle_fullscreen();
+            } else {
+                let monitor = raylib::window::get_current_monitor();
+                let width = raylib::window::get_monitor_width(monitor);
+                let height = raylib::window::get_monitor_height(monitor);
+
+                self.rl.set_window_size(width, height);
+                self.rl.toggle_fullscreen();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Called once per frame to draw everything to the window
+    fn on_render(&mut self) {
+        let start = Instant::now();
+        let mut d = self.rl.begin_drawing(&self.thread);
+        // The bars around the display, when the window doesn't have its
+        // aspect ratio, are left black
+        d.clear_background(Color::BLACK);
+
+        // Running side by side, the window is split between the two displays
+        let width = d.get_screen_width() as f32 / 2.0;
+        let height = d.get_screen_height() as f32;
+        let halves = self.side.is_some().then(|| {
+            (Rectangle::new(0.0, 0.0, width, height), Rectangle::new(width, 0.0, width, height))
+        });
+
+        self.screen.set_viewport(halves.map(|(left, _)| left));
+        self.screen
+            .renderer(&mut d, &self.thread, self.foreground, self.background)
+            .present(&self.emulator.frame());
+        self.emulator.clear_dirty_rows();
+        if let (Some(side), Some((_, right))) = (&mut self.side, halves) {
+            let (foreground, background) = (self.foreground, self.background);
+            side.draw(&mut d, &self.thread, &mut self.overlay, right, foreground, background);
+
+            // The half running the other quirks is framed once it diverges
+            if self.comparison.as_ref().is_some_and(Comparison::has_diverged) {
+                d.draw_rectangle_lines_ex(right, DIVERGENCE_FRAME, Color::RED);
+            }
+        }
+        if self.flash_collisions {
+            self.screen.draw_collisions(&mut d, &self.emulator);
+        }
+        let sounding = self.emulator.sound_timer() > 0 && !self.control.is_paused();
+        if self.accessibility.visual_bell && sounding {
+            accessibility::draw_visual_bell(&mut d, self.foreground);
+        }
+
+        if self.debug {
+            d.draw_fps(5, 5);
+            let cpu_speed = self.scheduler.cpu_speed();
+            let registers_y = 30 + self.frame_graph.draw(&mut self.overlay, 5, 30, cpu_speed) + 10;
+            let height = self.register_view.draw(
+                &mut self.overlay,
+                &self.emulator,
+                &self.symbols,
+                self.editor.as_ref(),
+                5,
+                registers_y,
+            );
+
+            // Collisions are how games detect hits, which helps tuning hitboxes
+            let collisions = self.emulator.collisions().count();
+            let text = format!("collisions {collisions} ({} total)", self.total_collisions);
+            let size = self.overlay.font_size(10);
+            let color = if collisions > 0 { Color::RED } else { Color::RAYWHITE };
+            self.overlay.text(&text, 5, registers_y + height + 4, size, color);
+            let mut y = registers_y + height + size + 12;
+
+            // The unknown opcodes explain a blank screen at a glance
+            if let Some(summary) = self.unknown_opcodes.summary() {
+                self.overlay.text(&summary, 5, y - 4, size, Color::ORANGE);
+                y += size + 4;
+            }
+
+            // Drawn below the collisions, following the program counter
+            draw_disassembly(&mut self.overlay, &self.emulator, &self.symbols, 5, y);
+
+            // Drawn to the right of the FPS counter
+            let text = format!("frame {}", short_hash(self.emulator.framebuffer_hash()));
+            self.overlay.text(&text, 100, 5, self.overlay.font_size(20), Color::RAYWHITE);
+
+            // Drawn against the right edge, below the debugger status, with
+            // the heatmap below the hex view and the sprites to its left
+            let right = d.get_screen_width() - 5;
+            let y = self.overlay.font_size(20) + 25;
+            let editor = self.editor.as_ref();
+            let height = self.hex_view.draw(&mut self.overlay, &self.emulator, editor, right, y);
+            let y = y + height + 5;
+            let heatmap = draw_heatmap(&mut self.overlay, &self.emulator, right, y);
+            let right = if heatmap > 0 { right - heatmap - 5 } else { right };
+            self.sprite_preview.draw(&mut self.overlay, &self.emulator, right, y);
+        }
+
+        if self.control.is_paused() {
+            let pc = self.emulator.program_counter();
+            let opcode = OpCode::from(self.emulator.fetch(pc));
+            let status = format!("PAUSED {}: {opcode}", self.symbols.symbolicate(pc));
+
+            // Drawn in the top-right corner to stay clear of the FPS counter
+            let size = self.overlay.font_size(20);
+            let width = d.get_screen_width();
+            let x = width - self.overlay.measure(&status, size) - 10;
+
+            let background = self.overlay.background();
+            self.overlay.rect(x - 10, 0, width - x + 10, size + 20, background);
+            self.overlay.text(&status, x, 10, size, Color::YELLOW);
+        }
+
+        let resume_prompt = self
+            .resume
+            .as_ref()
+            .map(|_| "Resume where you left off? Enter resumes, Backspace starts over".to_string());
+        if let Some(prompt) = self.keymap.prompt().or(resume_prompt) {
+            // Drawn centered at the top, the other corners being taken
+            let size = self.overlay.font_size(20);
+            let width = self.overlay.measure(&prompt, size);
+            let x = (d.get_screen_width() - width) / 2;
+
+            let background = self.overlay.background();
+            self.overlay.rect(x - 10, 0, width + 20, size + 20, background);
+            self.overlay.text(&prompt, x, 10, size, Color::YELLOW);
+        }
+
+        if let Some(fault) = &self.fault {
+            let mut lines = vec![(fault.error.message(), Color::RED)];
+            if let Some(pc) = fault.pc {
+                let raw = self.emulator.fetch(pc);
+                let opcode = OpCode::from(raw);
+                let at = format!("at {}: {raw:04X} {opcode}", self.symbols.symbolicate(pc));
+                lines.push((at, Color::RAYWHITE));
+            }
+            lines.push(("Ctrl+R restarts, or drop a ROM onto the window".to_string(), Color::GRAY));
+
+            // Drawn across the middle, as the ROM doesn't run behind it
+            let size = self.overlay.font_size(20);
+            let line_height = size + 10;
+            let width = lines
+                .iter()
+                .map(|(text, _)| self.overlay.measure(text, size))
+                .max()
+                .unwrap_or_default();
+            let x = (d.get_screen_width() - width) / 2;
+            let y = (d.get_screen_height() - line_height * lines.len() as i32) / 2;
+
+            let background = self.overlay.background();
+            let height = line_height * lines.len() as i32;
+            self.overlay.rect(x - 10, y - 10, width + 20, height + 10, background);
+            for (i, (text, color)) in lines.iter().enumerate() {
+                self.overlay.text(text, x, y + line_height * i as i32, size, *color);
+            }
+        }
+
+        if let Some(chooser) = &self.chooser {
+            chooser.draw(&mut self.overlay, d.get_screen_width(), d.get_screen_height());
+        }
+        if let Some(settings) = &self.settings {
+            settings.draw(&mut self.overlay, d.get_screen_width(), d.get_screen_height());
+        } else if let (Some(pause_menu), None) = (&self.pause_menu, &self.chooser) {
+            pause_menu.draw(&mut self.overlay, d.get_screen_width(), d.get_screen_height());
+        }
+
+        self.osd.draw(&mut self.overlay, d.get_screen_height());
+        self.overlay.flush(&mut d);
+
+        // Presenting the frame, when drawing ends, is left out as it waits
+        // for the display
+        self.frame_graph.record_render(start.elapsed().as_secs_f32());
+    }
+
+    /// Called once when the application stops, to persist the session
+    fn on_exit(&mut self) {
+        self.close_settings();
+        self.print_self_modified();
+        self.print_reserved_writes();
+        self.debug_setup.breakpoints = self.emulator.breakpoints().collect();
+        self.debug_setup.watchpoints = self.emulator.watchpoints().collect();
+        self.debug_setup.register_watchpoints = self.emulator.register_watchpoints().collect();
+        self.debug_setup.store(&mut self.sidecar);
+        self.keymap.store(&mut self.sidecar);
+        if self.persist {
+            let flags = self.emulator.rpl_flags();
+            let lines = if flags.iter().any(|&flag| flag != 0) {
+                let hex = flags.iter().map(|flag| format!("{flag:02x}")).collect::<Vec<String>>();
+                vec![hex.join(" ")]
+            } else {
+                Vec::new()
+            };
+
+            self.sidecar.set_section(RPL_SECTION, lines);
+        }
+        // A state still offered was never taken up, so it is kept, whilst a
+        // ROM which exited or failed has nothing to resume
+        if self.auto_resume && self.resume.is_none() && self.chooser.is_none() {
+            let finished = self.emulator.is_halted() || self.fault.is_some();
+            let resumable = self.sidecar.file_path(RESUME_FILE).is_some_and(|path| {
+                if finished {
+                    !path.exists() || std::fs::remove_file(path).is_ok()
+                } else {
+                    std::fs::write(path, self.emulator.save_state()).is_ok()
+                }
+            });
+
+            if !resumable {
+                error!("Failed to save the state to resume the ROM from");
+            }
+        }
+        if self.persist && !self.emulator.save_ram().is_empty() {
+            let written = self
+                .sidecar
+                .file_path(SAVE_RAM_FILE)
+                .is_some_and(|path| std::fs::write(path, self.emulator.save_ram()).is_ok());
+
+            if !written {
+                error!("Failed to save the save RAM of the ROM");
+            }
+        }
+
+        // Failing to persist the session shouldn't fail the whole run
+        if let Err(e) = self.sidecar.save() {
+            error!("{}", e.message());
+        }
+
+        // A recording still running is saved rather than lost
+        if self.recorder.is_some() {
+            self.toggle_recording();
+        }
+
+        if let Some((path, recording)) = &self.recording {
+            match recording.save(path) {
+                Ok(_) => info!("Saved input log to {path}"),
+                Err(e) => error!("{}", e.message()),
+            }
+        }
+
+        if let Some(audio_dump) = self.audio_dump.take() {
+            let path = audio_dump.path().to_string();
+            match audio_dump.finish() {
+                Ok(_) => info!("Saved audio to {path}"),
+                Err(e) => error!("{}", e.message()),
+            }
+        }
+
+        if let Some(profiler) = &self.profiler {
+            match profiler.save(&self.symbols) {
+                Ok(_) => info!("Saved profile to {}", profiler.path()),
+                Err(e) => error!("{}", e.message()),
+            }
+        }
+
+        if let Some(report) = self.unknown_opcodes.report(self.emulator.platform()) {
+            println!("{report}");
+        }
+    }
+
+    /// Writes the state of the emulator to the current slot, reporting the
+    /// outcome on the on-screen display
+    fn save_state(&mut self) {
+        let result = self.state_path().and_then(|path| {
+            std::fs::write(&path, self.emulator.save_state())
+                .map_err(|_| Keet8Error::FailedToSaveState(path.display().to_string()))
+        });
+
+        match result {
+            Ok(_) => {
+                info!("Saved state to slot {}", self.state_slot);
+                self.osd.show(format!("Saved state to slot {}", self.state_slot));
+            }
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show(format!("Failed to save state to slot {}", self.state_slot));
+            }
+        }
+    }
+
+    /// Saves the screen to a timestamped PNG next to the ROM, reporting the
+    /// outcome on the on-screen display
+    fn save_screenshot(&mut self) {
+        let path = self.capture_path("png");
+
+        let image = framebuffer_image(
+            &self.emulator.frame(),
+            self.foreground,
+            self.background,
+            self.screenshot_scale,
+        );
+
+        if image.export_image(&path) {
+            self.osd.show("Saved screenshot");
+        } else {
+            error!("{}", Keet8Error::FailedToSaveScreenshot(path).message());
+            self.osd.show("Failed to save screenshot");
+        }
+    }
+
+    /// Executes the next instruction, tracing it and counting it towards the
+    /// input of the frame
+    ///
+    /// # Errors
+    ///
+    /// If an error has occured during runtime of the emulator
+    fn execute_step(&mut self) -> Result<StepOutcome> {
+        self.process_tracepoints();
+        if let Some(bug_report) = &mut self.bug_report {
+            let pc = self.emulator.program_counter();
+            bug_report.record(pc, self.emulator.fetch(pc));
+        }
+        if let Some(trace_log) = &mut self.trace_log {
+            trace_log.begin(&self.emulator);
+        }
+
+        let call_path = self.profiler.as_ref().map(|_| call_path(&self.emulator));
+
+        let stepping = self.control.is_paused();
+        if stepping && self.console.is_some() {
+            let pc = self.emulator.program_counter();
+            let opcode = OpCode::from(self.emulator.fetch(pc));
+            let step = format!("{}: {opcode}", self.symbols.symbolicate(pc));
+            self.before = Some((step, self.emulator.save_state()));
+        }
+
+        // A single step onto a breakpoint executes the instruction rather
+        // than stopping at it
+        let start = Instant::now();
+        let mut outcome = self.emulator.step()?;
+        if stepping && matches!(outcome, StepOutcome::Breakpoint(_)) {
+            outcome = self.emulator.step()?;
+        }
+        let elapsed = start.elapsed();
+        self.cheats.apply_freezes(&mut self.emulator);
+        #[cfg(feature = "scripting")]
+        self.run_script(Script::after_step);
+
+        if !matches!(outcome, StepOutcome::Breakpoint(_) | StepOutcome::Halted) {
+            self.frame.steps += 1;
+            self.write_trace();
+
+            if let (Some(profiler), Some(call_path)) = (&mut self.profiler, call_path) {
+                profiler.record(call_path, elapsed);
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Counts the timers of the emulator down, counting the tick towards the
+    /// input of the frame
+    fn tick_timers(&mut self) {
+        self.emulator.tick_timers();
+        self.cheats.apply_frame(&mut self.emulator);
+        self.total_collisions += self.emulator.collisions().count() as u64;
+        self.frame.ticks += 1;
+
+        if let Some(audio_dump) = &mut self.audio_dump {
+            if let Err(e) = audio_dump.tick() {
+                error!("{}", e.message());
+                self.audio_dump = None;
+            }
+        }
+    }
+
+    /// Restarts the ROM, noting the restart in the input of the frame
+    fn reset(&mut self) {
+        self.emulator.reset();
+        if let Some(side) = &mut self.side {
+            side.reset();
+        }
+        if let Some(comparison) = &mut self.comparison {
+            comparison.restart();
+        }
+        self.fault = None;
+        self.total_collisions = 0;
+        self.before = None;
+        self.frame.reset = true;
+        info!("Reset the ROM");
+        self.osd.show("Reset");
+    }
+
+    /// Sets the window title to the name of the ROM and the state of the
+    /// emulator: the CPU speed, whether it is paused, and the hash of the
+    /// screen when asked to, for comparing the emulation across machines
+    fn update_title(&mut self) {
+        let rom = self.rom_file.file_name().unwrap_or_default().to_string_lossy();
+        let mut title = format!("{} - {rom} ({} IPS)", window_title(), self.scheduler.cpu_speed());
+
+        if self.control.is_paused() {
+            title.push_str(" (paused)");
+        }
+        if self.frame_hash {
+            title.push_str(&format!(" [{}]", short_hash(self.emulator.framebuffer_hash())));
+        }
+
+        if title != self.title {
+            self.rl.set_window_title(&self.thread, &title);
+            self.title = title;
+        }
+    }
+
+    /// Reloads the ROM and restarts it if its file changed since it was last
+    /// loaded, when the ROM is reloaded whenever it changes
+    ///
+    /// A ROM which could not be reloaded is reported, and the emulator
+    /// carries on with the one it had.
+    fn reload_rom(&mut self) {
+        let Some(modified) = self.rom_modified else {
+            return;
+        };
+
+        // The file may briefly be missing whilst an editor replaces it
+        let rom_file = self.rom_file.display().to_string();
+        let Some(latest) = modified_time(&rom_file).filter(|&latest| latest != modified) else {
+            return;
+        };
+
+        self.rom_modified = Some(latest);
+        match read_rom(&rom_file).and_then(|rom| self.emulator.load_rom(&rom)) {
+            Ok(()) => {
+                self.total_collisions = 0;
+                self.unknown_opcodes.clear();
+                self.before = None;
+                self.fault = None;
+                info!("Reloaded {rom_file}");
+                self.osd.show("ROM reloaded");
+            }
+            Err(error) if self.dev => self.fault = Some(Fault { error, pc: None }),
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show("Failed to reload the ROM");
+            }
+        }
+    }
+
+    /// Replaces the running ROM with another ROM file and starts it, such as
+    /// one dropped onto the window
+    ///
+    /// A ROM which could not be loaded is reported, and the emulator carries
+    /// on with the one it had. Replays, recordings and netplay keep their ROM,
+    /// as their input belongs to it. A ZIP archive of several ROMs lists them
+    /// to pick one from instead.
+    ///
+    /// # Params
+    ///
+    /// - `rom_file` - The filepath to the ROM file
+    fn open_rom(&mut self, rom_file: &str) {
+        if !self.persist {
+            self.osd.show("Can't replace the ROM whilst recording, replaying or in netplay");
+            return;
+        }
+
+        match archived_roms(rom_file) {
+            Ok(Some(roms)) if roms.len() > 1 => {
+                self.chooser = Some(RomChooser::new(rom_file, roms));
+                return;
+            }
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show("Failed to open the archive");
+                return;
+            }
+            _ => (),
+        }
+
+        let loaded = read_rom(rom_file).and_then(|rom| {
+            self.emulator.load_rom(&rom)?;
+            Ok(rom)
+        });
+        let rom = match loaded {
+            Ok(rom) => rom,
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show("Failed to load the ROM");
+                return;
+            }
+        };
+
+        // Comparing quirks, the new ROM runs on both sides
+        if let (Some(side), Some(comparison)) = (&mut self.side, &mut self.comparison) {
+            side.load_rom(rom_file, &rom);
+            comparison.restart();
+        }
+
+        // The data of the new ROM lives in its own sidecar, along with the
+        // state it was last quit in and the keys bound for it, whilst the
+        // keys bound for the old ROM stay in the old sidecar
+        self.keymap.store(&mut self.sidecar);
+        if let Err(e) = self.sidecar.save() {
+            error!("{}", e.message());
+        }
+        self.sidecar = Sidecar::open(self.emulator.rom_hash());
+        self.keymap.reload(&self.sidecar);
+        self.resume = self.auto_resume.then(|| resume_state(&self.sidecar)).flatten();
+        self.rom_file = PathBuf::from(rom_file);
+        if self.rom_modified.is_some() {
+            self.rom_modified = Some(modified_time(rom_file).unwrap_or(UNIX_EPOCH));
+        }
+
+        self.recent.push(rom_file);
+        if let Err(e) = self.recent.save() {
+            warn!("{}", e.message());
+        }
+
+        self.fault = None;
+        self.chooser = None;
+        self.has_rom = true;
+        self.total_collisions = 0;
+        self.unknown_opcodes.clear();
+        self.before = None;
+        let name = self.rom_file.file_name().unwrap_or_default().to_string_lossy();
+        info!("Loaded {rom_file}");
+        self.osd.show(format!("Loaded {name}"));
+    }
+
+    /// Pauses the ROM with the pause menu shown, or closes the menu and
+    /// resumes the ROM
+    fn toggle_pause_menu(&mut self) {
+        if self.pause_menu.take().is_some() {
+            self.control.resume();
+        } else {
+            self.control.pause();
+            self.pause_menu = Some(PauseMenu::new());
+        }
+    }
+
+    /// Moves through the pause menu with the arrow keys, and carries out the
+    /// selected action with Enter
+    fn process_pause_menu_input(&mut self) {
+        let Some(pause_menu) = &mut self.pause_menu else {
+            return;
+        };
+
+        if self.rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            pause_menu.move_selection(-1);
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            pause_menu.move_selection(1);
+        }
+        if !self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return;
+        }
+
+        match pause_menu.selected() {
+            PauseAction::Resume => self.toggle_pause_menu(),
+            PauseAction::Reset => {
+                self.reset();
+                self.toggle_pause_menu();
+            }
+            PauseAction::LoadRom => {
+                self.toggle_pause_menu();
+                self.toggle_recent_roms();
+            }
+            PauseAction::SaveState => self.save_state(),
+            PauseAction::LoadState => self.load_state(),
+            PauseAction::Settings => self.toggle_settings(),
+            PauseAction::Quit => self.is_running = false,
+        }
+    }
+
+    /// Opens the settings menu with the current settings, or closes it
+    fn toggle_settings(&mut self) {
+        if self.settings.is_some() {
+            self.close_settings();
+            return;
+        }
+
+        self.settings = Some(SettingsMenu::new(
+            self.palette,
+            self.scheduler.cpu_speed(),
+            self.buzzer.as_ref().map(Buzzer::volume),
+            self.emulator.quirks(),
+            self.accessibility,
+            Layout::matching(self.keymap.keys()),
+        ));
+    }
+
+    /// Closes the settings menu if it is open, writing the settings changed
+    /// in it back to the configuration file
+    fn close_settings(&mut self) {
+        let Some(settings) = self.settings.take() else {
+            return;
+        };
+
+        if settings.changes().is_empty() {
+            return;
+        }
+
+        match save_settings(&self.config_file, settings.changes()) {
+            Ok(()) => self.osd.show(format!("Settings saved to {}", self.config_file)),
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show("Failed to save the settings");
+            }
+        }
+    }
+
+    /// Moves through the settings menu with the arrow keys, changing the
+    /// selected setting with Left/Right and picking it with Enter
+    fn process_settings_input(&mut self) {
+        let Some(settings) = &mut self.settings else {
+            return;
+        };
+
+        let pressed = |key| self.rl.is_key_pressed(key) || self.rl.is_key_pressed_repeat(key);
+        if pressed(KeyboardKey::KEY_UP) {
+            settings.move_selection(-1);
+        }
+        if pressed(KeyboardKey::KEY_DOWN) {
+            settings.move_selection(1);
+        }
+
+        let choice = if pressed(KeyboardKey::KEY_LEFT) {
+            settings.adjust(-1).map(Choice::Change)
+        } else if pressed(KeyboardKey::KEY_RIGHT) {
+            settings.adjust(1).map(Choice::Change)
+        } else if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            settings.activate()
+        } else {
+            None
+        };
+
+        match choice {
+            Some(Choice::Change(Setting::Palette(index))) => self.set_palette(index),
+            Some(Choice::Change(Setting::CpuSpeed(speed))) => self.set_cpu_speed(speed),
+            Some(Choice::Change(Setting::Volume(volume))) => {
+                if let Some(buzzer) = &mut self.buzzer {
+                    buzzer.set_volume(volume);
+                }
+                self.osd.show(format!("Volume {:.0}%", volume * 100.0));
+            }
+            Some(Choice::Change(Setting::Quirk(..))) => {
+                if let Some(settings) = &self.settings {
+                    self.emulator.set_quirks(settings.quirks());
+                }
+            }
+            Some(Choice::Change(Setting::Accessibility(..))) => {
+                if let Some(settings) = &self.settings {
+                    self.set_accessibility(settings.accessibility());
+                }
+            }
+            Some(Choice::Change(Setting::Layout(layout))) => {
+                self.keymap.set_layout(layout);
+                self.osd.show(format!("Layout {}", layout.name()));
+            }
+            Some(Choice::Change(Setting::Keys(_))) | None => (),
+            Some(Choice::BindKeys) => {
+                self.close_settings();
+                self.keymap.start_binding();
+            }
+            Some(Choice::ForgetKeys) => {
+                self.keymap.reset();
+                self.osd.show("Keys reset to the default");
+            }
+            Some(Choice::SaveKeys) => {
+                if let Some(settings) = &mut self.settings {
+                    settings.record(Setting::Keys(*self.keymap.keys()));
+                }
+                self.osd.show("Keys saved as the default");
+            }
+            Some(Choice::Quit) => self.is_running = false,
+        }
+    }
+
+    /// Takes the colors from a built-in palette
+    ///
+    /// # Params
+    ///
+    /// - `index` - The index of the palette in `PALETTES`
+    fn set_palette(&mut self, index: usize) {
+        self.foreground = PALETTES[index].foreground;
+        self.background = PALETTES[index].background;
+        self.palette = Some(index);
+
+        self.osd.show(format!("Palette {}", PALETTES[index].name));
+    }
+
+    /// Applies the accessibility options, to the display of the ROM run side
+    /// by side as well
+    ///
+    /// # Params
+    ///
+    /// - `accessibility` - The accessibility options
+    fn set_accessibility(&mut self, accessibility: Accessibility) {
+        self.accessibility = accessibility;
+        self.screen.set_pixel_grid(accessibility.pixel_grid);
+        if let Some(side) = &mut self.side {
+            side.screen().set_pixel_grid(accessibility.pixel_grid);
+        }
+        self.osd.set_large_text(accessibility.large_text);
+    }
+
+    /// Sets the number of instructions executed per second, for the ROM run
+    /// side by side as well
+    ///
+    /// # Params
+    ///
+    /// - `speed` - The number of instructions executed per second
+    fn set_cpu_speed(&mut self, speed: u32) {
+        self.scheduler.set_cpu_speed(speed);
+        if let Some(side) = &mut self.side {
+            side.set_cpu_speed(speed);
+        }
+        self.osd.show(format!("Speed {speed} IPS"));
+    }
+
+    /// Raises or lowers the volume of the buzzer by a number of steps,
+    /// showing the new volume
+    ///
+    /// # Params
+    ///
+    /// - `steps` - The number of steps to change the volume by, going down
+    ///   if negative
+    fn change_volume(&mut self, steps: i32) {
+        let Some(buzzer) = &mut self.buzzer else {
+            self.osd.show("No audio device");
+            return;
+        };
+
+        let volume = step_volume(buzzer.volume(), steps);
+        buzzer.set_volume(volume);
+        self.osd.show(format!("Volume {:.0}%", volume * 100.0));
+    }
+
+    /// Lists the ROMs opened recently to pick one from, or closes the list of
+    /// ROMs to carry on with the running ROM
+    fn toggle_recent_roms(&mut self) {
+        if self.chooser.is_some() {
+            if self.has_rom {
+                self.chooser = None;
+            }
+            return;
+        }
+
+        if !self.persist {
+            self.osd.show("Can't replace the ROM whilst recording, replaying or in netplay");
+        } else if self.recent.roms().is_empty() {
+            self.osd.show("No recent ROMs");
+        } else {
+            self.chooser = Some(RomChooser::recent(self.recent.roms()));
+        }
+    }
+
+    /// Compares the screens of the two emulators after a frame when
+    /// comparing quirks, pausing on the first frame in which they differ
+    fn compare_screens(&mut self) {
+        let (Some(side), Some(comparison)) = (&self.side, &mut self.comparison) else {
+            return;
+        };
+        if self.control.is_paused() || comparison.has_diverged() {
+            return;
+        }
+
+        comparison.frames += 1;
+        if self.emulator.framebuffer_hash() != side.framebuffer_hash() {
+            comparison.diverged_at = Some(comparison.frames);
+            self.control.pause();
+
+            let platform = comparison.platform.name();
+            info!("The screens diverge from {platform} at frame {}", comparison.frames);
+            self.osd.show(format!("Diverged from {platform} at frame {}", comparison.frames));
+        }
+    }
+
+    /// Sets the keys of the keypad held down
+    ///
+    /// # Params
+    ///
+    /// - `keys` - The keys held down, with bit `K` set for key `K`
+    fn set_keys(&mut self, keys: u16) {
+        self.emulator.set_all(keys);
+        self.frame.keys = keys;
+    }
+
+    /// Replays the next frame of the input log, or hands control back to the
+    /// keyboard once the log is over
+    ///
+    /// # Errors
+    ///
+    /// If an error has occured during runtime of the emulator
+    fn replay_frame(&mut self) -> Result<()> {
+        let Some(frame) = self.replay.as_mut().and_then(InputLog::next_frame) else {
+            self.replay = None;
+            self.osd.show("Replay finished");
+            return Ok(());
+        };
+
+        self.run_frame(frame)
+    }
+
+    /// Runs the next frame of the netplay session with the keys of both
+    /// players combined, or goes on alone if the other player is gone
+    ///
+    /// # Errors
+    ///
+    /// If an error has occured during runtime of the emulator
+    fn netplay_frame(&mut self) -> Result<()> {
+        let keys = self.read_keys();
+        let Some(netplay) = &mut self.netplay else {
+            return Ok(());
+        };
+
+        let steps = netplay.steps_per_frame();
+        let other_keys = match netplay.exchange(keys) {
+            Ok(other_keys) => other_keys,
+            Err(e) => {
+                error!("{}", e.message());
+                self.netplay = None;
+                self.osd.show("The other player disconnected");
+                return Ok(());
+            }
+        };
+
+        self.run_frame(Frame {
+            keys: keys | other_keys,
+            steps,
+            ticks: 1,
+            reset: false,
+        })
+    }
+
+    /// Runs a frame given from elsewhere than the keyboard and the timing of
+    /// the frames, executing its instructions and timer ticks with its keys
+    /// held down
+    ///
+    /// Breakpoints and watchpoints don't pause the frame, as that would split
+    /// it.
+    ///
+    /// # Params
+    ///
+    /// - `frame` - The input of the frame
+    ///
+    /// # Errors
+    ///
+    /// If an error has occured during runtime of the emulator
+    fn run_frame(&mut self, frame: Frame) -> Result<()> {
+        self.set_keys(frame.keys);
+
+        // A breakpoint stops a step before the instruction, which the next
+        // step then executes
+        let mut executed = 0;
+        while executed < frame.steps && !self.emulator.is_halted() {
+            if !matches!(self.execute_step()?, StepOutcome::Breakpoint(_)) {
+                executed += 1;
+            }
+
+            self.process_host_calls();
+        }
+
+        for _ in 0..frame.ticks {
+            self.tick_timers();
+        }
+        if frame.reset {
+            self.reset();
+        }
+
+        Ok(())
+    }
+
+    /// Appends the instruction just executed to the trace, if tracing, and
+    /// stops tracing if the trace could not be written rather than failing
+    /// the run
+    fn write_trace(&mut self) {
+        let Some(trace_log) = &mut self.trace_log else {
+            return;
+        };
+
+        if let Err(e) = trace_log.finish(&self.emulator) {
+            error!("{}", e.message());
+            self.trace_log = None;
+        }
+    }
+
+    /// Writes the bug report if one was requested, reporting the outcome on
+    /// stdout as the run is over
+    ///
+    /// # Params
+    ///
+    /// - `error` - The error the run ended with, if any
+    fn write_bug_report(&self, error: Option<&Keet8Error>) {
+        let Some(bug_report) = &self.bug_report else {
+            return;
+        };
+
+        match bug_report.write(&self.emulator, self.foreground, self.background, error) {
+            Ok(_) => info!("Saved bug report to {}", bug_report.path()),
+            Err(e) => error!("{}", e.message()),
+        }
+    }
+
+    /// Starts recording, or stops and saves the recording to a timestamped
+    /// GIF next to the ROM, reporting the outcome on the on-screen display
+    fn toggle_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            self.recorder = Some(Recorder::default());
+            self.osd.show("Recording");
+            return;
+        };
+
+        let path = self.capture_path("gif");
+        match recorder.save(&path, self.foreground, self.background) {
+            Ok(_) => self.osd.show("Saved recording"),
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show("Failed to save recording");
+            }
+        }
+    }
+
+    /// Writes a JSON dump of the machine state to a file
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath of the dump
+    ///
+    /// # Errors
+    ///
+    /// If the dump could not be written
+    fn save_state_dump(&self, path: &str) -> Result<()> {
+        let dump = state_dump(&self.emulator, self.frame.keys);
+        let text = serde_json::to_string_pretty(&dump).unwrap_or_default();
+
+        std::fs::write(path, text + "\n")
+            .map_err(|_| Keet8Error::FailedToSaveStateDump(path.to_string()))
+    }
+
+    /// Gets the filepath of a screenshot or recording taken now, being next
+    /// to the ROM and named after it and the time
+    ///
+    /// # Params
+    ///
+    /// - `extension` - The file extension of the capture
+    fn capture_path(&self, extension: &str) -> String {
+        let stem = self.rom_file.file_stem().unwrap_or_default().to_string_lossy();
+        let name = format!("{stem}-{}.{extension}", timestamp());
+
+        self.rom_file.with_file_name(name).display().to_string()
+    }
+
+    /// Restores the state of the emulator from the current slot, reporting
+    /// the outcome on the on-screen display
+    fn load_state(&mut self) {
+        let result = self.state_path().and_then(|path| {
+            let bytes = std::fs::read(&path)
+                .map_err(|_| Keet8Error::FailedToLoadState(path.display().to_string()))?;
+
+            self.emulator.load_state(&bytes)
+        });
+
+        match result {
+            Ok(_) => {
+                self.fault = None;
+                self.before = None;
+                info!("Loaded state from slot {}", self.state_slot);
+                self.osd.show(format!("Loaded state from slot {}", self.state_slot));
+            }
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show(format!("Failed to load state from slot {}", self.state_slot));
+            }
+        }
+    }
+
+    /// Gets the path of the file backing the current save state slot
+    ///
+    /// # Errors
+    ///
+    /// If no data directory could be determined for the ROM
+    fn state_path(&self) -> Result<std::path::PathBuf> {
+        self.slot_path(self.state_slot)
+    }
+
+    /// Gets the path of the file backing a save state slot
+    ///
+    /// # Params
+    ///
+    /// - `slot` - The save state slot
+    ///
+    /// # Errors
+    ///
+    /// If no data directory could be determined for the ROM
+    fn slot_path(&self, slot: u8) -> Result<std::path::PathBuf> {
+        let name = format!("state{slot}.k8s");
+        self.sidecar
+            .file_path(&name)
+            .ok_or(Keet8Error::FailedToSaveState(name))
+    }
+
+    /// Writes the locations of the self-modifying code the ROM executed to
+    /// stderr, if there were any
+    fn print_self_modified(&self) {
+        let mut addresses = self.emulator.self_modified().peekable();
+        if addresses.peek().is_none() {
+            return;
+        }
+
+        eprintln!("Self-modifying code executed at:");
+        for addr in addresses {
+            let opcode = OpCode::from(self.emulator.fetch(addr));
+            eprintln!("    {}: {opcode}", self.symbols.symbolicate(addr));
+        }
+    }
+
+    /// Writes the locations of the instructions which wrote into the
+    /// interpreter area to stderr, if any
+    fn print_reserved_writes(&self) {
+        let mut addresses = self.emulator.reserved_writes().peekable();
+        if addresses.peek().is_none() {
+            return;
+        }
+
+        eprintln!("Writes into the interpreter area (0x000-0x1FF) made at:");
+        for addr in addresses {
+            let opcode = OpCode::from(self.emulator.fetch(addr));
+            eprintln!("    {}: {opcode}", self.symbols.symbolicate(addr));
+        }
+    }
+
+    /// Writes the report of an error which stopped the ROM to stderr and to
+    /// a `.crash` file next to the ROM
+    ///
+    /// # Params
+    ///
+    /// - `error` - The error which stopped the ROM
+    fn write_crash_report(&self, error: &Keet8Error) {
+        let report = crash_report(&self.emulator, &self.symbols, error);
+        eprint!("{report}");
+
+        let path = self.capture_path("crash");
+        match std::fs::write(&path, report) {
+            Ok(()) => info!("Saved crash report to {path}"),
+            Err(_) => error!("{}", Keet8Error::FailedToSaveCrashReport(path).message()),
+        }
+    }
+
+    /// Checks the break conditions after an instruction, returning the first
+    /// one which fired, if any
+    ///
+    /// Every condition is checked, so they all follow the values of the
+    /// registers.
+    fn check_conditions(&mut self) -> Option<String> {
+        let mut fired = None;
+        for condition in &mut self.debug_setup.conditions {
+            if condition.check(&self.emulator) && fired.is_none() {
+                fired = Some(condition.to_string());
+            }
+        }
+
+        fired
+    }
+
+    /// Logs the messages of the tracepoints at the instruction about to be
+    /// executed to stderr
+    fn process_tracepoints(&self) {
+        let pc = self.emulator.program_counter();
+        for tracepoint in self.debug_setup.tracepoints.iter().filter(|t| t.addr == pc) {
+            if let Some(message) = tracepoint.trace(&self.emulator) {
+                eprintln!("[TRACE] {}: {message}", self.symbols.symbolicate(pc));
+            }
+        }
+    }
+
+    /// Writes the host calls made by the ROM since the last step to stdout
+    fn process_host_calls(&mut self) {
+        let Some(host_calls) = &self.host_calls else {
+            return;
+        };
+
+        for call in host_calls.try_iter() {
+            match call {
+                HostCall::Print(text) => println!("{text}"),
+                HostCall::Registers(registers) => {
+                    let dump = registers
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| format!("v{i:x}={v:02x}"))
+                        .collect::<Vec<String>>();
+
+                    println!("{}", dump.join(" "));
+                }
+                HostCall::TestResult { passed, code } => {
+                    if passed {
+                        println!("Test passed");
+                    } else {
+                        println!("Test failed with code {code}");
+                        self.failed_test = Some(code);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports the events of the emulator worth noticing since the last frame
+    /// on the on-screen display
+    fn process_events(&mut self) {
+        for event in self.events.try_iter() {
+            match event {
+                Event::UnknownOpcode { opcode, addr } => {
+                    self.unknown_opcodes.record(opcode);
+                    let addr = self.symbols.symbolicate(addr);
+                    info!("Unknown opcode {opcode:04X} at {addr}");
+                    self.osd.show(format!("Unknown opcode {opcode:04X} at {addr}"));
+                }
+                Event::Finished { addr } => {
+                    let addr = self.symbols.symbolicate(addr);
+                    info!("ROM finished, jumping to itself at {addr}");
+                    self.osd.show(format!("ROM finished, jumping to itself at {addr}"));
+                }
+                Event::ScreenCleared
+                | Event::SoundStarted
+                | Event::SoundStopped
+                | Event::FrameCompleted { .. } => {}
+            }
+        }
+    }
+
+    /// Resumes the ROM from the state it was last quit in with Enter, or
+    /// starts it over with Backspace
+    fn process_resume_input(&mut self) {
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            self.resume = None;
+            self.osd.show("Starting over");
+        }
+
+        if !self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return;
+        }
+        let Some(state) = self.resume.take() else {
+            return;
+        };
+
+        match self.emulator.load_state(&state) {
+            Ok(()) => {
+                info!("Resumed the ROM where it was quit");
+                self.osd.show("Resumed");
+            }
+            Err(e) => {
+                error!("{}", e.message());
+                self.osd.show("Failed to resume, starting over");
+            }
+        }
+    }
+
+    /// Binds the keys pressed this frame to the keypad, reporting the
+    /// outcome on the on-screen display
+    fn process_binding(&mut self) {
+        while let Some(key) = self.rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_BACKSPACE => {
+                    self.keymap.stop_binding();
+                    self.osd.show("Binding stopped");
+                }
+                KeyboardKey::KEY_DELETE => {
+                    self.keymap.reset();
+                    self.osd.show("Keys reset");
+                }
+                _ => {
+                    if self.keymap.bind(key) && !self.keymap.is_binding() {
+                        self.osd.show("Keys bound");
+                    }
+                }
+            }
+
+            if !self.keymap.is_binding() {
+                break;
+            }
+        }
+    }
+
+    /// Reports to the attached debugger that execution stopped, and carries
+    /// out what it asks of the execution
+    fn process_gdb(&mut self) {
+        let Some(gdb) = &mut self.gdb else {
+            return;
+        };
+
+        // A step or resume is over once execution is paused again
+        if gdb.is_waiting() && self.control.is_paused() {
+            gdb.report_stop();
+        }
+
+        for action in gdb.poll(&mut self.emulator) {
+            match action {
+                GdbAction::Pause => self.control.pause(),
+                GdbAction::Step => self.control.request_step(),
+                GdbAction::Resume => self.control.resume(),
+            }
+        }
+    }
+
+    /// Carries out what the clients of the remote control server ask of the
+    /// application, and streams the screen to the ones subscribed to it
+    fn process_remote(&mut self) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+
+        for action in remote.poll(&mut self.emulator) {
+            match action {
+                RemoteAction::Load(rom_file) => self.open_rom(&rom_file),
+                RemoteAction::Pause => self.control.pause(),
+                RemoteAction::Resume => self.control.resume(),
+                RemoteAction::Step => self.control.request_step(),
+                RemoteAction::Reset => self.reset(),
+            }
+        }
+
+        if let Some(remote) = &mut self.remote {
+            remote.send_frame(&self.emulator);
+        }
+    }
+
+    /// Executes the commands typed into the debugger console, writing their
+    /// outcome to stdout and any errors to stderr
+    fn process_commands(&mut self) {
+        let Some(console) = &self.console else {
+            return;
+        };
+
+        // Collected first, as the commands need the whole application
+        let commands = console.commands().collect::<Vec<Result<Command>>>();
+        for command in commands {
+            let result = command.and_then(|command| match command {
+                Command::Patch { addr, instruction } => {
+                    let raw = assemble(&instruction)?;
+                    self.emulator.patch(addr, raw);
+
+                    let label = self.symbols.symbolicate(addr);
+                    println!("{label}: {} ({raw:04X})", OpCode::from(raw));
+                    Ok(())
+                }
+                Command::Break { addr } => {
+                    let label = self.symbols.symbolicate(addr);
+                    if self.emulator.remove_breakpoint(addr) {
+                        println!("Breakpoint removed at {label}");
+                    } else {
+                        self.emulator.add_breakpoint(addr);
+                        println!("Breakpoint set at {label}");
+                    }
+
+                    Ok(())
+                }
+                Command::BreakIf { condition } => {
+                    if self.debug_setup.remove_condition(&condition) {
+                        println!("Break condition removed: {condition}");
+                    } else {
+                        println!("Break condition set: {condition}");
+                        self.debug_setup.add_condition(condition);
+                    }
+
+                    Ok(())
+                }
+                Command::Watch { target } => {
+                    let (label, removed) = match target {
+                        WatchTarget::Memory(addr) => {
+                            let removed = self.emulator.remove_watchpoint(addr);
+                            if !removed {
+                                self.emulator.add_watchpoint(addr);
+                            }
+
+                            (format!("{addr:#05X}"), removed)
+                        }
+                        WatchTarget::Register(x) => {
+                            let removed = self.emulator.remove_register_watchpoint(x);
+                            if !removed {
+                                self.emulator.add_register_watchpoint(x);
+                            }
+
+                            (format!("V{x:X}"), removed)
+                        }
+                    };
+
+                    if removed {
+                        println!("Watchpoint removed at {label}");
+                    } else {
+                        println!("Watchpoint set at {label}");
+                    }
+
+                    Ok(())
+                }
+                Command::Diff { from, to } => {
+                    let path_a = self.slot_path(from)?.display().to_string();
+                    let path_b = self.slot_path(to)?.display().to_string();
+
+                    println!("--- slot {from}");
+                    println!("+++ slot {to}");
+                    print_diff(&load_state_file(&path_a)?, &load_state_file(&path_b)?);
+                    Ok(())
+                }
+                Command::Changes => {
+                    let Some((ran, state)) = &self.before else {
+                        println!("Nothing has run yet");
+                        return Ok(());
+                    };
+
+                    println!("--- before {ran}");
+                    println!("+++ after");
+                    print_diff(&Emulator::from_state(state)?, &self.emulator);
+                    Ok(())
+                }
+                Command::Step => {
+                    // The instruction is executed with the next frame
+                    let pc = self.emulator.program_counter();
+                    let raw = self.emulator.fetch(pc);
+                    println!("{}: {} ({raw:04X})", self.symbols.symbolicate(pc), OpCode::from(raw));
+
+                    self.control.request_step();
+                    Ok(())
+                }
+                Command::Continue => {
+                    self.control.resume();
+                    println!("Running");
+                    Ok(())
+                }
+                Command::Pause => {
+                    self.control.pause();
+                    let pc = self.emulator.program_counter();
+                    println!("Paused at {}", self.symbols.symbolicate(pc));
+                    Ok(())
+                }
+                Command::Regs => {
+                    print_registers(&self.emulator);
+                    Ok(())
+                }
+                Command::Mem { addr, len } => {
+                    print_memory(&self.emulator, addr, len);
+                    Ok(())
+                }
+                Command::Poke { addr, value } => {
+                    self.emulator.poke(addr, value);
+                    println!("{}: {value:02X}", self.symbols.symbolicate(addr));
+                    Ok(())
+                }
+                Command::Dump { path } => {
+                    let path = path.unwrap_or_else(|| self.capture_path("json"));
+                    self.save_state_dump(&path)?;
+                    println!("Saved state dump to {path}");
+                    Ok(())
+                }
+            });
+
+            if let Err(e) = result {
+                error!("{}", e.message());
+            }
+        }
+    }
+
+    /// Scrolls the hex view of the debug overlay with the mouse wheel and the
+    /// page keys, or to the program counter with Home, and picks the memory
+    /// shown as sprites by right-clicking it, with `[` and `]` changing their
+    /// height
+    fn process_hex_view_input(&mut self) {
+        let wheel = self.rl.get_mouse_wheel_move();
+        if wheel != 0.0 {
+            self.hex_view.scroll(-wheel.round() as i32);
+        }
+
+        if self.rl.is_key_pressed_repeat(KeyboardKey::KEY_PAGE_UP)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_PAGE_UP)
+        {
+            self.hex_view.scroll(-HEX_VIEW_PAGE);
+        }
+        if self.rl.is_key_pressed_repeat(KeyboardKey::KEY_PAGE_DOWN)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_PAGE_DOWN)
+        {
+            self.hex_view.scroll(HEX_VIEW_PAGE);
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_HOME) {
+            self.hex_view.show(self.emulator.program_counter());
+        }
+
+        // Right-clicking anywhere but a byte follows I again
+        if self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+            let position = self.rl.get_mouse_position();
+            let addr = self.hex_view.address_at(position.x as i32, position.y as i32);
+            self.sprite_preview.pin(addr);
+        }
+        if self.rl.is_key_pressed_repeat(KeyboardKey::KEY_LEFT_BRACKET)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET)
+        {
+            self.sprite_preview.resize(-1);
+        }
+        if self.rl.is_key_pressed_repeat(KeyboardKey::KEY_RIGHT_BRACKET)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_RIGHT_BRACKET)
+        {
+            self.sprite_preview.resize(1);
+        }
+    }
+
+    /// Edits the bytes of memory and the `V` registers in the debug overlay
+    /// whilst paused: clicking a byte starts editing it, typing two
+    /// hexadecimal digits writes it and moves on to the next byte, the arrow
+    /// keys move between the bytes and Enter or Escape stops editing
+    fn process_edit_input(&mut self) {
+        if !self.control.is_paused() {
+            self.editor = None;
+            return;
+        }
+
+        if self.rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            let position = self.rl.get_mouse_position();
+            let (x, y) = (position.x as i32, position.y as i32);
+            let target = self
+                .hex_view
+                .address_at(x, y)
+                .map(EditTarget::Memory)
+                .or_else(|| self.register_view.register_at(x, y).map(EditTarget::Register));
+
+            self.editor = target.map(ValueEditor::new);
+        }
+
+        let Some(editor) = &mut self.editor else {
+            return;
+        };
+
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_ESCAPE)
+        {
+            self.editor = None;
+            return;
+        }
+
+        let row_len = editor.row_len();
+        let moves = [
+            (KeyboardKey::KEY_LEFT, -1),
+            (KeyboardKey::KEY_RIGHT, 1),
+            (KeyboardKey::KEY_UP, -row_len),
+            (KeyboardKey::KEY_DOWN, row_len),
+        ];
+        for (key, offset) in moves {
+            if self.rl.is_key_pressed(key) || self.rl.is_key_pressed_repeat(key) {
+                editor.advance(offset, self.emulator.memory_size());
+            }
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            editor.erase();
+        }
+
+        while let Some(c) = self.rl.get_char_pressed() {
+            let Some(value) = editor.type_char(c) else {
+                continue;
+            };
+
+            match editor.target() {
+                EditTarget::Memory(addr) => self.emulator.poke(addr, value),
+                EditTarget::Register(x) => self.emulator.set_register(x, value),
+            }
+            editor.advance(1, self.emulator.memory_size());
+        }
+
+        if let EditTarget::Memory(addr) = editor.target() {
+            self.hex_view.reveal(addr);
+        }
+    }
+
+    /// Moves through the listed ROMs with the arrow keys, and starts the
+    /// selected one with Enter, or a numbered one with its digit key, Enter
+    /// on a directory listing it and on the settings entry opening the menu
+    fn process_chooser_input(&mut self) {
+        let Some(chooser) = &mut self.chooser else {
+            return;
+        };
+
+        if self.rl.is_key_pressed_repeat(KeyboardKey::KEY_UP)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_UP)
+        {
+            chooser.move_selection(-1);
+        }
+        if self.rl.is_key_pressed_repeat(KeyboardKey::KEY_DOWN)
+            || self.rl.is_key_pressed(KeyboardKey::KEY_DOWN)
+        {
+            chooser.move_selection(1);
+        }
+        if self.rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            match chooser.selected().cloned() {
+                Some(Pick::Rom(rom_file)) => self.open_rom(&rom_file),
+                Some(Pick::Browse(dir)) => self.chooser = Some(RomChooser::browse(&dir)),
+                Some(Pick::Settings) => self.toggle_settings(),
+                None => (),
+            }
+            return;
+        }
+
+        let numbered = DIGIT_KEYS
+            .iter()
+            .position(|&key| self.rl.is_key_pressed(key))
+            .and_then(|i| chooser.numbered(i + 1));
+        if let Some(rom_file) = numbered.map(str::to_string) {
+            self.open_rom(&rom_file);
+        }
+    }
+
+    /// Sets the keypad to the keys held down on the keyboard or gamepad, and
+    /// by the script if any
+    fn process_input(&mut self) {
+        let keys = self.read_keys();
+
+        // The keys held by the script are pressed on top of the keyboard
+        #[cfg(feature = "scripting")]
+        let keys = match &mut self.script {
+            Some(script) => {
+                script.set_keys(keys);
+                keys | script.poll(&self.rl)
+            }
+            None => keys,
+        };
+
+        self.set_keys(keys);
+    }
+
+    /// Calls a hook of the script, stopping the script if it fails
+    ///
+    /// # Params
+    ///
+    /// - `hook` - The hook to call
+    #[cfg(feature = "scripting")]
+    fn run_script(&mut self, hook: fn(&mut Script, &mut Emulator) -> Result<()>) {
+        let Some(script) = &mut self.script else {
+            return;
+        };
+
+        if let Err(e) = hook(script, &mut self.emulator) {
+            error!("{}", e.message());
+            self.script = None;
+            self.osd.show("Script stopped");
+        }
+    }
+
+    /// Reads the keys of the keypad held down on the keyboard or gamepad,
+    /// with bit `K` set for key `K`, keeping the keypad released whilst
+    /// binding so the keys being bound don't reach the ROM
+    fn read_keys(&mut self) -> u16 {
+        if self.keymap.is_binding() {
+            return 0;
+        }
+
+        poll_all(&self.rl, &mut [&mut self.keymap, &mut self.player2_keys, &mut self.gamepad])
+    }
+}
+
+// --- fault definition -------------------------------------------------------
+
+/// An error which stopped the ROM
+struct Fault {
+    /// The error
+    error: Keet8Error,
+    /// The address of the faulting instruction, if the error was raised by
+    /// one rather than by loading the ROM
+    pc: Option<u16>,
+}
+
+// --- comparison definition --------------------------------------------------
+
+/// The comparison of the screens of the ROM run with the configured quirks
+/// and with the quirks of another platform side by side (`--compare`)
+struct Comparison {
+    /// The platform the ROM runs as on the right
+    platform: Platform,
+    /// The number of frames compared since the ROM started
+    frames: u32,
+    /// The first frame in which the screens differed, once they did
+    diverged_at: Option<u32>,
+}
+
+impl Comparison {
+    /// Starts comparing the screens
+    ///
+    /// # Params
+    ///
+    /// - `platform` - The platform the ROM runs as on the right
+    fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            frames: 0,
+            diverged_at: None,
+        }
+    }
+
+    /// Checks whether the screens differed in a frame since the ROM started
+    fn has_diverged(&self) -> bool {
+        self.diverged_at.is_some()
+    }
+
+    /// Compares the screens anew, as the ROM restarted
+    fn restart(&mut self) {
+        self.frames = 0;
+        self.diverged_at = None;
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Gets the title of the window, marking debug builds
+pub(crate) fn window_title() -> String {
+    if cfg!(debug_assertions) {
+        format!("{TITLE} - {VERSION} (debug)")
+    } else {
+        format!("{TITLE} - {VERSION}")
+    }
+}
+
+/// Scales the window up by the scale factor of its monitor, so that it comes
+/// out the same size on high-DPI displays as on regular ones, and centers it
+/// on the monitor, returning the scale applied
+///
+/// The window is kept within `MAX_MONITOR_FILL` of the monitor, and its size
+/// becomes its minimum size. macOS sizes windows in points, which already
+/// follow the scale factor, so the window isn't scaled there.
+///
+/// # Params
+///
+/// - `rl` - The handle to the raylib context
+/// - `width` - The width of the window before scaling
+/// - `height` - The height of the window before scaling
+pub(crate) fn fit_window(rl: &mut RaylibHandle, width: i32, height: i32) -> f32 {
+    let dpi = rl.get_window_scale_dpi();
+    let mut scale = if cfg!(target_os = "macos") { 1.0 } else { dpi.x.max(dpi.y).max(1.0) };
+
+    // A monitor whose size can't be read doesn't limit the window
+    let monitor = raylib::window::get_current_monitor();
+    let monitor_width = raylib::window::get_monitor_width(monitor);
+    let monitor_height = raylib::window::get_monitor_height(monitor);
+    if monitor_width > 0 && monitor_height > 0 {
+        let fit_width = MAX_MONITOR_FILL * monitor_width as f32 / width as f32;
+        let fit_height = MAX_MONITOR_FILL * monitor_height as f32 / height as f32;
+        scale = scale.min(fit_width).min(fit_height).max(1.0);
+    }
+
+    let scaled_width = (width as f32 * scale).round() as i32;
+    let scaled_height = (height as f32 * scale).round() as i32;
+    if scale > 1.0 {
+        rl.set_window_size(scaled_width, scaled_height);
+        rl.set_window_position(
+            (monitor_width - scaled_width).max(0) / 2,
+            (monitor_height - scaled_height).max(0) / 2,
+        );
+    }
+    rl.set_window_min_size(scaled_width, scaled_height);
+
+    scale
+}
+
+/// Shortens a hash of the screen to the 8 hexadecimal digits shown to the
+/// user, which is plenty to tell frames apart by eye
+///
+/// # Params
+///
+/// - `hash` - The hash to shorten
+fn short_hash(hash: u64) -> String {
+    format!("{:08x}", hash >> 32)
+}
+
+/// Parses the RPL user flags stored in the sidecar, written as hexadecimal
+/// bytes separated by spaces, up to the first one which is invalid
+///
+/// # Params
+///
+/// - `lines` - The lines of the sidecar section
+fn parse_rpl_flags(lines: &[String]) -> Vec<u8> {
+    lines
+        .first()
+        .map(|line| {
+            line.split_whitespace()
+                .map_while(|hex| u8::from_str_radix(hex, 16).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads the state a ROM was last quit in from its sidecar, if there is one
+///
+/// # Params
+///
+/// - `sidecar` - The sidecar of the ROM
+fn resume_state(sidecar: &Sidecar) -> Option<Vec<u8>> {
+    sidecar
+        .file_path(RESUME_FILE)
+        .and_then(|path| std::fs::read(path).ok())
+}
+
+/// Gets the time a file was last modified, if it can be read
+///
+/// # Params
+///
+/// - `path` - The filepath to the file
+fn modified_time(path: &str) -> Option<SystemTime> {
+    // A ROM in an archive changes along with the archive
+    std::fs::metadata(file_on_disk(path)).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Formats the current time (UTC) as `YYYYMMDD-HHMMSS`, for naming files
+fn timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    // Converts the days since the epoch to a civil date, taking years as
+    // starting in March so the leap day comes last
+    let days = secs / 86400;
+    let era = (days + 719468) / 146097;
+    let day_of_era = days + 719468 - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+
+    let time = secs % 86400;
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
@@ -0,0 +1,228 @@
+use crate::hash;
+use crate::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The signature of the end of central directory record
+const END_SIGNATURE: u32 = 0x06054B50;
+/// The signature of an entry of the central directory
+const ENTRY_SIGNATURE: u32 = 0x02014B50;
+/// The signature of the local header in front of a file
+const LOCAL_SIGNATURE: u32 = 0x04034B50;
+
+/// The size of the end of central directory record, without its comment
+const END_SIZE: usize = 22;
+/// The size of an entry of the central directory, without its name, extra
+/// field and comment
+const ENTRY_SIZE: usize = 46;
+/// The size of a local header, without its name and extra field
+const LOCAL_SIZE: usize = 30;
+
+/// The compression method of files stored as they are
+const METHOD_STORED: u16 = 0;
+/// The compression method of files compressed with Deflate
+const METHOD_DEFLATED: u16 = 8;
+
+// --- zip archive definition -------------------------------------------------
+
+/// A ZIP archive read into memory, such as a pack of ROMs
+///
+/// Files stored as they are and files compressed with Deflate can be read,
+/// which covers what common tools write. Encrypted files and ZIP64 archives
+/// aren't supported.
+pub(crate) struct ZipArchive {
+    /// The filepath to the archive
+    path: String,
+    /// The whole archive
+    bytes: Vec<u8>,
+    /// The files in the archive, from the central directory
+    entries: Vec<ZipEntry>,
+}
+
+/// A file in a ZIP archive, as listed in the central directory
+struct ZipEntry {
+    /// The name of the file, including the folders it is in
+    name: String,
+    /// The compression method of the file
+    method: u16,
+    /// Flag indicating whether the file is encrypted
+    encrypted: bool,
+    /// The CRC-32 of the contents of the file
+    crc: u32,
+    /// The size of the file in the archive
+    compressed_size: usize,
+    /// The size of the contents of the file
+    size: usize,
+    /// The offset of the local header of the file
+    offset: usize,
+}
+
+impl ZipArchive {
+    /// Reads an archive and its central directory
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the archive
+    ///
+    /// # Errors
+    ///
+    /// - If the archive could not be read
+    /// - If the archive is invalid
+    pub fn open(path: &str) -> Result<Self> {
+        let bytes = keet8_core::read_rom(path)?;
+        let entries = read_entries(&bytes).ok_or_else(|| invalid(path, "not a ZIP archive"))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            bytes,
+            entries,
+        })
+    }
+
+    /// Gets the names of the files in the archive, leaving out the folders
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .filter(|name| !name.ends_with('/'))
+    }
+
+    /// Reads the contents of a file in the archive
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the file, including the folders it is in
+    ///
+    /// # Errors
+    ///
+    /// - If there is no file by that name
+    /// - If the file is encrypted or compressed with an unsupported method
+    /// - If the file is corrupted
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| invalid(&self.path, &format!("no file named {name}")))?;
+
+        if entry.encrypted {
+            return Err(invalid(&self.path, &format!("{name} is encrypted")));
+        }
+
+        let corrupted = || invalid(&self.path, &format!("{name} is corrupted"));
+        let data = local_data(&self.bytes, entry).ok_or_else(corrupted)?;
+        let contents = match entry.method {
+            METHOD_STORED => data.to_vec(),
+            METHOD_DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(data, entry.size)
+                .map_err(|_| corrupted())?,
+            method => {
+                return Err(invalid(
+                    &self.path,
+                    &format!("{name} is compressed with unsupported method {method}"),
+                ))
+            }
+        };
+
+        if contents.len() != entry.size || hash::crc32(&contents) != entry.crc {
+            return Err(corrupted());
+        }
+
+        Ok(contents)
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Reads the entries of the central directory of an archive, found through
+/// the end of central directory record at the end of the archive
+///
+/// # Params
+///
+/// - `bytes` - The whole archive
+fn read_entries(bytes: &[u8]) -> Option<Vec<ZipEntry>> {
+    // The record is followed by a comment of up to 64K, so it is searched
+    // for backwards from the end
+    let last = bytes.len().checked_sub(END_SIZE)?;
+    let end = (last.saturating_sub(u16::MAX as usize)..=last)
+        .rev()
+        .find(|&at| read_u32(bytes, at) == Some(END_SIGNATURE))?;
+
+    let num_entries = read_u16(bytes, end + 10)?;
+    let mut at = read_u32(bytes, end + 16)? as usize;
+    let mut entries = Vec::with_capacity(num_entries as usize);
+
+    for _ in 0..num_entries {
+        if read_u32(bytes, at)? != ENTRY_SIGNATURE {
+            return None;
+        }
+
+        let name_len = read_u16(bytes, at + 28)? as usize;
+        let extra_len = read_u16(bytes, at + 30)? as usize;
+        let comment_len = read_u16(bytes, at + 32)? as usize;
+        let name = bytes.get(at + ENTRY_SIZE..at + ENTRY_SIZE + name_len)?;
+
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: read_u16(bytes, at + 10)?,
+            encrypted: read_u16(bytes, at + 8)? & 1 != 0,
+            crc: read_u32(bytes, at + 16)?,
+            compressed_size: read_u32(bytes, at + 20)? as usize,
+            size: read_u32(bytes, at + 24)? as usize,
+            offset: read_u32(bytes, at + 42)? as usize,
+        });
+
+        at += ENTRY_SIZE + name_len + extra_len + comment_len;
+    }
+
+    Some(entries)
+}
+
+/// Gets the data of a file in an archive, found after its local header
+///
+/// # Params
+///
+/// - `bytes` - The whole archive
+/// - `entry` - The entry of the file in the central directory
+fn local_data<'a>(bytes: &'a [u8], entry: &ZipEntry) -> Option<&'a [u8]> {
+    if read_u32(bytes, entry.offset)? != LOCAL_SIGNATURE {
+        return None;
+    }
+
+    // The local header has its own extra field, which may differ in length
+    // from the one in the central directory
+    let name_len = read_u16(bytes, entry.offset + 26)? as usize;
+    let extra_len = read_u16(bytes, entry.offset + 28)? as usize;
+    let start = entry.offset + LOCAL_SIZE + name_len + extra_len;
+
+    bytes.get(start..start + entry.compressed_size)
+}
+
+/// Reads a little-endian `u16`, if it lies within the bytes
+///
+/// # Params
+///
+/// - `bytes` - The bytes to read from
+/// - `at` - The offset of the value
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+/// Reads a little-endian `u32`, if it lies within the bytes
+///
+/// # Params
+///
+/// - `bytes` - The bytes to read from
+/// - `at` - The offset of the value
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Creates the error for an invalid archive
+///
+/// # Params
+///
+/// - `path` - The filepath to the archive
+/// - `reason` - The reason it is invalid
+fn invalid(path: &str, reason: &str) -> Keet8Error {
+    Keet8Error::InvalidArchive(path.to_string(), reason.to_string())
+}
@@ -0,0 +1,522 @@
+use crate::prelude::*;
+
+use keet8_core::PROG_ADDR;
+
+use std::collections::HashMap;
+
+// --- constants --------------------------------------------------------------
+
+/// The deepest macros may be expanded within one another, which stops a
+/// macro expanding itself forever
+const MAX_MACRO_DEPTH: usize = 16;
+
+// --- assembler interface ----------------------------------------------------
+
+/// Assembles a single instruction into its raw opcode
+///
+/// The syntax is the one of the disassembly, being the mnemonic followed by
+/// its operands separated by spaces or commas, with the usual names for the
+/// operands it leaves ambiguous: `ld f vX` and `ld hf vX` for the font
+/// sprites, `ld b vX` for BCD and `ld [i] vX` and `ld vX [i]` for storing and
+/// loading registers, and `ld audio [i]` and `ld pitch vX` for the audio
+/// pattern (XO-CHIP). Numbers are decimal, hexadecimal with `0x` or binary
+/// with `0b`.
+///
+/// # Params
+///
+/// - `text` - The instruction to assemble, such as `jp 0x200`
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(keet_8::assemble("ld v0 5").ok(), Some(0x6005));
+/// assert_eq!(keet_8::assemble("drw v1, v2, 15").ok(), Some(0xD12F));
+/// ```
+///
+/// # Errors
+///
+/// If the instruction is unknown or its operands are invalid
+pub fn assemble(text: &str) -> Result<u16> {
+    let invalid = || Keet8Error::InvalidAssembly(text.trim().to_string());
+
+    let lower = text.to_ascii_lowercase();
+    let mut tokens = lower
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty());
+
+    let mnemonic = tokens.next().ok_or_else(invalid)?;
+    let operands = tokens
+        .map(Operand::parse)
+        .collect::<Option<Vec<Operand>>>()
+        .ok_or_else(invalid)?;
+
+    use Operand::*;
+    let raw = match (mnemonic, operands.as_slice()) {
+        ("cls", []) => 0x00E0,
+        ("ret", []) => 0x00EE,
+        ("scd", &[Num(n)]) if n <= 0xF => 0x00C0 | n,
+        ("scr", []) => 0x00FB,
+        ("scl", []) => 0x00FC,
+        ("exit", []) => 0x00FD,
+        ("low", []) => 0x00FE,
+        ("high", []) => 0x00FF,
+        ("raw", &[Num(raw)]) => raw,
+        ("sys", &[Num(addr)]) if addr <= 0xFFF => addr,
+        ("jp", &[Num(addr)]) if addr <= 0xFFF => 0x1000 | addr,
+        ("jp", &[V(0), Num(addr)]) if addr <= 0xFFF => 0xB000 | addr,
+        ("call", &[Num(addr)]) if addr <= 0xFFF => 0x2000 | addr,
+        ("se", &[V(x), Num(kk)]) if kk <= 0xFF => 0x3000 | x << 8 | kk,
+        ("sne", &[V(x), Num(kk)]) if kk <= 0xFF => 0x4000 | x << 8 | kk,
+        ("se", &[V(x), V(y)]) => 0x5000 | x << 8 | y << 4,
+        ("sne", &[V(x), V(y)]) => 0x9000 | x << 8 | y << 4,
+        ("ld", &[V(x), Num(kk)]) if kk <= 0xFF => 0x6000 | x << 8 | kk,
+        ("add", &[V(x), Num(kk)]) if kk <= 0xFF => 0x7000 | x << 8 | kk,
+        ("ld", &[V(x), V(y)]) => 0x8000 | x << 8 | y << 4,
+        ("or", &[V(x), V(y)]) => 0x8001 | x << 8 | y << 4,
+        ("and", &[V(x), V(y)]) => 0x8002 | x << 8 | y << 4,
+        ("xor", &[V(x), V(y)]) => 0x8003 | x << 8 | y << 4,
+        ("add", &[V(x), V(y)]) => 0x8004 | x << 8 | y << 4,
+        ("sub", &[V(x), V(y)]) => 0x8005 | x << 8 | y << 4,
+        ("shr", &[V(x), V(y)]) => 0x8006 | x << 8 | y << 4,
+        ("shr", &[V(x)]) => 0x8006 | x << 8 | x << 4,
+        ("subn", &[V(x), V(y)]) => 0x8007 | x << 8 | y << 4,
+        ("shl", &[V(x), V(y)]) => 0x800E | x << 8 | y << 4,
+        ("shl", &[V(x)]) => 0x800E | x << 8 | x << 4,
+        ("ld", &[I, Num(addr)]) if addr <= 0xFFF => 0xA000 | addr,
+        ("rnd", &[V(x), Num(kk)]) if kk <= 0xFF => 0xC000 | x << 8 | kk,
+        ("drw", &[V(x), V(y), Num(n)]) if n <= 0xF => 0xD000 | x << 8 | y << 4 | n,
+        ("skp", &[V(x)]) => 0xE09E | x << 8,
+        ("sknp", &[V(x)]) => 0xE0A1 | x << 8,
+        ("ld", &[V(x), Dt]) => 0xF007 | x << 8,
+        ("ld", &[V(x), Key]) => 0xF00A | x << 8,
+        ("ld", &[Dt, V(x)]) => 0xF015 | x << 8,
+        ("ld", &[St, V(x)]) => 0xF018 | x << 8,
+        ("add", &[I, V(x)]) => 0xF01E | x << 8,
+        ("ld", &[F, V(x)]) => 0xF029 | x << 8,
+        ("ld", &[Hf, V(x)]) => 0xF030 | x << 8,
+        ("ld", &[B, V(x)]) => 0xF033 | x << 8,
+        ("ld", &[AtI, V(x)]) => 0xF055 | x << 8,
+        ("ld", &[V(x), AtI]) => 0xF065 | x << 8,
+        ("ld", &[R, V(x)]) => 0xF075 | x << 8,
+        ("ld", &[V(x), R]) => 0xF085 | x << 8,
+        ("ld", &[Audio, AtI]) => 0xF002,
+        ("ld", &[Pitch, V(x)]) => 0xF03A | x << 8,
+        _ => return Err(invalid()),
+    };
+
+    Ok(raw)
+}
+
+/// Assembles a whole program into the bytes of a ROM, loaded at `0x200`
+///
+/// Every line holds an instruction in the syntax of `assemble`, a directive
+/// or nothing. A line may start with a label ending in `:`, which stands for
+/// the address of what follows it wherever an operand is expected, even
+/// before it is defined, and `;` starts a comment:
+///
+/// ```text
+/// speed equ 2
+///
+/// macro move reg
+///     add reg speed
+/// endm
+///
+/// loop:  ; Draws the sprite forever
+///     ld i sprite
+///     drw v0 v1 2
+///     move v0
+///     jp loop
+/// sprite:
+///     sprite #......# ########
+/// ```
+///
+/// The directives are:
+///
+/// - `name equ value` - Defines a constant, which stands for its value
+///   wherever an operand is expected after it
+/// - `db` - Writes the bytes following it, such as sprite data
+/// - `dw` - Writes the 16-bit words following it, most significant byte first
+/// - `sprite` - Writes a byte for each row following it, drawn with `#` for
+///   the pixels which are on and `.` for the ones which are off
+/// - `macro name params` - Starts the definition of a macro, which ends with
+///   `endm`, and whose lines replace `name args` with the parameters replaced
+///   by the arguments
+///
+/// Numbers are decimal, hexadecimal with `0x` or binary with `0b`.
+///
+/// # Params
+///
+/// - `source` - The source of the program
+///
+/// # Examples
+///
+/// ```rust
+/// let rom = keet_8::assemble_program("start: jp start").ok();
+/// assert_eq!(rom, Some(vec![0x12, 0x00]));
+/// ```
+///
+/// # Errors
+///
+/// If a line couldn't be assembled, with the error holding its line number
+pub fn assemble_program(source: &str) -> Result<Vec<u8>> {
+    let lines = expand_macros(source)?;
+
+    // The labels and constants are resolved before assembling, as labels may
+    // be used before they are defined
+    let mut symbols = HashMap::new();
+    let mut addr = PROG_ADDR as usize;
+    for (n, label, text) in &lines {
+        let duplicate = |name: &str| {
+            Keet8Error::InvalidAssembly(format!("line {n}: duplicate {name}"))
+        };
+
+        if let Some(label) = label {
+            if symbols.insert(label.to_ascii_lowercase(), addr as u16).is_some() {
+                return Err(duplicate(label));
+            }
+        }
+
+        let tokens = tokenize(text).collect::<Vec<&str>>();
+        addr += match (directive(&tokens).as_deref(), tokens.as_slice()) {
+            (Some("equ"), &[name, _, value]) => {
+                let value = parse_number(value)
+                    .or_else(|| symbols.get(&value.to_ascii_lowercase()).copied())
+                    .filter(|_| is_label(name))
+                    .ok_or_else(|| Keet8Error::InvalidAssembly(format!("line {n}: {text}")))?;
+                if symbols.insert(name.to_ascii_lowercase(), value).is_some() {
+                    return Err(duplicate(name));
+                }
+
+                0
+            }
+            (Some("db" | "sprite"), _) => tokens.len() - 1,
+            (Some("dw"), _) => 2 * (tokens.len() - 1),
+            (_, []) => 0,
+            _ => 2,
+        };
+    }
+
+    let mut rom = Vec::new();
+    for (n, _, text) in lines {
+        let invalid = || Keet8Error::InvalidAssembly(format!("line {n}: {text}"));
+        let tokens = tokenize(&text).collect::<Vec<&str>>();
+        let resolved = tokens
+            .iter()
+            .map(|token| match symbols.get(&token.to_ascii_lowercase()) {
+                Some(value) => format!("{value:#05x}"),
+                None => token.to_string(),
+            })
+            .collect::<Vec<String>>();
+        let operands = resolved.iter().skip(1).map(String::as_str);
+
+        match directive(&tokens).as_deref() {
+            Some("equ") => {}
+            Some("db") => {
+                for byte in operands {
+                    let byte = parse_number(byte).filter(|&byte| byte <= 0xFF);
+                    rom.push(byte.ok_or_else(invalid)? as u8);
+                }
+            }
+            Some("dw") => {
+                for word in operands {
+                    let word = parse_number(word).ok_or_else(invalid)?;
+                    rom.extend_from_slice(&word.to_be_bytes());
+                }
+            }
+            Some("sprite") => {
+                for row in operands {
+                    rom.push(sprite_row(row).ok_or_else(invalid)?);
+                }
+            }
+            _ if resolved.is_empty() => {}
+            _ => {
+                let raw = assemble(&resolved.join(" ")).map_err(|_| invalid())?;
+                rom.extend_from_slice(&raw.to_be_bytes());
+            }
+        }
+    }
+
+    Ok(rom)
+}
+
+// --- macro definition -------------------------------------------------------
+
+/// A macro of a program, whose lines replace its name wherever it is used as
+/// an instruction
+struct Macro {
+    /// The names of the parameters, replaced by the arguments
+    params: Vec<String>,
+    /// The lines of the macro, without their comments
+    body: Vec<String>,
+}
+
+// --- operand definition -----------------------------------------------------
+
+/// An operand of an instruction
+#[derive(Clone, Copy)]
+enum Operand {
+    /// A `V` register (`v0` to `vf`, or `v10` to `v15` as disassembled)
+    V(u16),
+    /// A number, being an address, byte or nibble
+    Num(u16),
+    /// The index register (`i`)
+    I,
+    /// The memory pointed to by the index register (`[i]`)
+    AtI,
+    /// The delay timer (`dt`)
+    Dt,
+    /// The sound timer (`st`)
+    St,
+    /// A key press (`k` or `[key]`)
+    Key,
+    /// The font sprite of a digit (`f`)
+    F,
+    /// The big font sprite of a digit (`hf`)
+    Hf,
+    /// The BCD representation of a register (`b`)
+    B,
+    /// The RPL flags (`r`)
+    R,
+    /// The audio pattern (`audio`)
+    Audio,
+    /// The pitch of the audio pattern (`pitch`)
+    Pitch,
+}
+
+impl Operand {
+    /// Parses an operand from its lowercase textual form
+    ///
+    /// # Params
+    ///
+    /// - `text` - The textual form of the operand
+    fn parse(text: &str) -> Option<Self> {
+        let operand = match text {
+            "i" => Self::I,
+            "[i]" => Self::AtI,
+            "dt" => Self::Dt,
+            "st" => Self::St,
+            "k" | "[key]" => Self::Key,
+            "f" => Self::F,
+            "hf" => Self::Hf,
+            "b" => Self::B,
+            "r" => Self::R,
+            "audio" => Self::Audio,
+            "pitch" => Self::Pitch,
+            _ => match text.strip_prefix('v') {
+                Some(x) if x.len() == 1 => Self::V(u16::from_str_radix(x, 16).ok()?),
+                Some(x) if x.starts_with('1') => Self::V(x.parse().ok().filter(|&x| x < 16)?),
+                _ => Self::Num(parse_number(text)?),
+            },
+        };
+
+        Some(operand)
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Strips the comments and macro definitions off the source and expands the
+/// macros, returning the number, label and rest of each line
+///
+/// The lines of an expanded macro take the number of the line using it, so
+/// errors point at it.
+///
+/// # Params
+///
+/// - `source` - The source of the program
+///
+/// # Errors
+///
+/// - If a macro is defined without a valid name or `endm`
+/// - If a macro is given the wrong number of arguments, or expands itself
+fn expand_macros(source: &str) -> Result<Vec<(usize, Option<String>, String)>> {
+    let mut macros = HashMap::new();
+    let mut lines = Vec::new();
+
+    let mut definition: Option<(usize, String, Macro)> = None;
+    for (n, line) in source.lines().enumerate() {
+        let n = n + 1;
+        let line = line.split(';').next().unwrap_or_default();
+
+        if let Some((_, name, mut body)) = definition.take() {
+            if line.trim().eq_ignore_ascii_case("endm") {
+                macros.insert(name, body);
+            } else {
+                body.body.push(line.to_string());
+                definition = Some((n, name, body));
+            }
+
+            continue;
+        }
+
+        let tokens = tokenize(line).collect::<Vec<&str>>();
+        if let Some((keyword, rest)) = tokens.split_first() {
+            if keyword.eq_ignore_ascii_case("macro") {
+                let (name, params) = rest
+                    .split_first()
+                    .filter(|(name, _)| is_label(name))
+                    .ok_or_else(|| Keet8Error::InvalidAssembly(format!("line {n}: {line}")))?;
+                let body = Macro {
+                    params: params.iter().map(|p| p.to_ascii_lowercase()).collect(),
+                    body: Vec::new(),
+                };
+
+                definition = Some((n, name.to_ascii_lowercase(), body));
+                continue;
+            }
+        }
+
+        let (label, text) = split_label(line);
+        expand_line(n, label, text, &macros, 0, &mut lines)?;
+    }
+
+    match definition {
+        Some((n, name, _)) => Err(Keet8Error::InvalidAssembly(format!(
+            "line {n}: macro {name} without endm"
+        ))),
+        None => Ok(lines),
+    }
+}
+
+/// Adds a line of source to the lines of the program, expanding it if it
+/// uses a macro
+///
+/// # Params
+///
+/// - `n` - The number of the line
+/// - `label` - The label of the line, if any
+/// - `text` - The rest of the line, without its comment
+/// - `macros` - The macros defined so far, keyed by their lowercase names
+/// - `depth` - The number of macros the line is expanded from
+/// - `lines` - The lines of the program to add to
+///
+/// # Errors
+///
+/// If the macro is given the wrong number of arguments, or expands itself
+fn expand_line(
+    n: usize,
+    label: Option<&str>,
+    text: &str,
+    macros: &HashMap<String, Macro>,
+    depth: usize,
+    lines: &mut Vec<(usize, Option<String>, String)>,
+) -> Result<()> {
+    let invalid = || Keet8Error::InvalidAssembly(format!("line {n}: {text}"));
+
+    let tokens = tokenize(text).collect::<Vec<&str>>();
+    let used = tokens
+        .split_first()
+        .and_then(|(name, args)| Some((macros.get(&name.to_ascii_lowercase())?, args)));
+    let Some((used, args)) = used else {
+        lines.push((n, label.map(str::to_string), text.to_string()));
+        return Ok(());
+    };
+
+    if args.len() != used.params.len() || depth >= MAX_MACRO_DEPTH {
+        return Err(invalid());
+    }
+
+    // The label of the line stands for the address of the expanded lines
+    if label.is_some() {
+        lines.push((n, label.map(str::to_string), String::new()));
+    }
+
+    for line in &used.body {
+        let (label, text) = split_label(line);
+        let text = tokenize(text)
+            .map(|token| {
+                match used.params.iter().position(|p| token.eq_ignore_ascii_case(p)) {
+                    Some(i) => args[i],
+                    None => token,
+                }
+            })
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        expand_line(n, label, &text, macros, depth + 1, lines)?;
+    }
+
+    Ok(())
+}
+
+/// Splits a line of source into its tokens, separated by spaces or commas
+///
+/// # Params
+///
+/// - `text` - The line of source, without its label and comment
+fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+}
+
+/// Gets the lowercase directive of a line of source, if it is one
+///
+/// # Params
+///
+/// - `tokens` - The tokens of the line
+fn directive(tokens: &[&str]) -> Option<String> {
+    match tokens {
+        [_, equ, _] if equ.eq_ignore_ascii_case("equ") => Some("equ".to_string()),
+        [first, ..] => Some(first.to_ascii_lowercase())
+            .filter(|first| matches!(first.as_str(), "db" | "dw" | "sprite")),
+        [] => None,
+    }
+}
+
+/// Parses a row of a sprite literal, drawn with `#` for the pixels which are
+/// on and `.` for the ones which are off, from the leftmost pixel
+///
+/// # Params
+///
+/// - `row` - The row of up to 8 pixels
+fn sprite_row(row: &str) -> Option<u8> {
+    if row.len() > 8 {
+        return None;
+    }
+
+    row.chars().enumerate().try_fold(0, |byte, (i, c)| match c {
+        '#' => Some(byte | 0x80 >> i),
+        '.' => Some(byte),
+        _ => None,
+    })
+}
+
+/// Splits the label off the start of a line of source, if any, returning the
+/// label and the rest of the line, both trimmed
+///
+/// # Params
+///
+/// - `line` - The line of source, without its comment
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(':') {
+        Some((label, rest)) if is_label(label.trim()) => (Some(label.trim()), rest.trim()),
+        _ => (None, line.trim()),
+    }
+}
+
+/// Checks whether a name can be a label, starting with a letter or `_` and
+/// holding only letters, digits and `_`
+///
+/// # Params
+///
+/// - `name` - The name to check
+fn is_label(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses a number written in decimal, in hexadecimal with `0x` or in binary
+/// with `0b`
+///
+/// # Params
+///
+/// - `text` - The textual form of the number
+fn parse_number(text: &str) -> Option<u16> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = text.strip_prefix("0b") {
+        u16::from_str_radix(binary, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
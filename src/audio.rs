@@ -0,0 +1,517 @@
+use crate::prelude::*;
+
+use keet8_core::{AudioSink, AUDIO_PATTERN_SIZE};
+use log::warn;
+use raylib::prelude::*;
+
+use std::f32::consts::TAU;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of samples per second of the tone
+pub(crate) const SAMPLE_RATE: u32 = 44100;
+/// The amount the volume changes by per step, from 0 to 1
+const VOLUME_STEP: f32 = 0.1;
+/// The default frequency in Hz of the tone, as the buzzer has no pitch of
+/// its own
+const DEFAULT_FREQUENCY: f32 = 440.0;
+/// The amplitude of the samples, leaving headroom below the loudest sample
+const AMPLITUDE: f32 = i16::MAX as f32 / 4.0;
+/// The default fraction of a period the square wave is high for
+const DEFAULT_DUTY_CYCLE: f32 = 0.5;
+/// The names of the waveforms, as given in the configuration file
+const WAVEFORM_NAMES: [(&str, Waveform); 3] = [
+    ("square", Waveform::Square),
+    ("sine", Waveform::Sine),
+    ("triangle", Waveform::Triangle),
+];
+/// The playback rate in bits per second of an audio pattern (XO-CHIP) at
+/// the default pitch of 64
+const PATTERN_RATE: f32 = 4000.0;
+/// The number of bits in an audio pattern (XO-CHIP)
+const PATTERN_BITS: f32 = 128.0;
+/// The number of samples written to the stream whenever it has played the
+/// previous ones
+const SAMPLES_PER_UPDATE: usize = 1024;
+/// The number of samples in a tick of the timers (60Hz)
+const SAMPLES_PER_TICK: usize = SAMPLE_RATE as usize / 60;
+/// The size in bytes of the header of a WAV file
+const WAV_HEADER_SIZE: u32 = 44;
+
+// --- tone definition --------------------------------------------------------
+
+/// The shape of a period of the tone
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Waveform {
+    /// High for the duty cycle of the period and low for the rest of it
+    Square,
+    /// A sine wave, the softest of the three
+    Sine,
+    /// Rising and falling linearly, softer than the square wave
+    Triangle,
+}
+
+impl Waveform {
+    /// Finds a waveform by its name, ignoring case
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the waveform, such as `sine`
+    pub fn parse(name: &str) -> Option<Self> {
+        WAVEFORM_NAMES
+            .iter()
+            .find(|(waveform_name, _)| waveform_name.eq_ignore_ascii_case(name))
+            .map(|&(_, waveform)| waveform)
+    }
+
+    /// Gets the level of the waveform at a position within its period, from
+    /// -1 up to 1
+    ///
+    /// # Params
+    ///
+    /// - `phase` - The position within the period, from 0 up to 1
+    /// - `duty_cycle` - The fraction of the period a square wave is high for
+    fn level(self, phase: f32, duty_cycle: f32) -> f32 {
+        match self {
+            Waveform::Square => {
+                if phase < duty_cycle {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Sine => (TAU * phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+/// The tone the buzzer sounds whilst the sound timer is non-zero
+#[derive(Clone, Copy)]
+pub(crate) struct Tone {
+    /// The shape of a period of the tone
+    pub waveform: Waveform,
+    /// The frequency of the tone in Hz
+    pub frequency: f32,
+    /// The fraction of a period the square wave is high for, from 0 to 1
+    pub duty_cycle: f32,
+}
+
+impl Default for Tone {
+    /// Creates the tone of the buzzer when the configuration doesn't set one,
+    /// being a 440Hz square wave
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Square,
+            frequency: DEFAULT_FREQUENCY,
+            duty_cycle: DEFAULT_DUTY_CYCLE,
+        }
+    }
+}
+
+// --- buzzer sink definition -------------------------------------------------
+
+/// The state of the buzzer, as the emulator last set it
+#[derive(Clone, Copy, Default)]
+struct BuzzerState {
+    /// Flag indicating whether the buzzer is to sound
+    on: bool,
+    /// The audio pattern (XO-CHIP) to play instead of the tone, if the ROM
+    /// loaded one
+    pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    /// The pitch the audio pattern is played at (XO-CHIP)
+    pitch: u8,
+}
+
+/// The audio sink of the emulator, setting the state of the buzzer for the
+/// `Buzzer` to play on the next frame
+///
+/// The raylib audio stream can't be moved to the emulator, so the state is
+/// shared with the buzzer instead, and with the audio dump if there is one.
+#[derive(Clone, Default)]
+pub(crate) struct BuzzerSink(Arc<Mutex<BuzzerState>>);
+
+impl BuzzerSink {
+    /// Gets the state of the buzzer
+    fn state(&self) -> BuzzerState {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Updates the state of the buzzer
+    ///
+    /// # Params
+    ///
+    /// - `update` - The change to make to the state
+    fn update(&self, update: impl FnOnce(&mut BuzzerState)) {
+        // The state is plain data, left whole even by a panicking thread
+        update(&mut self.0.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+}
+
+impl AudioSink for BuzzerSink {
+    fn beep_start(&mut self) {
+        self.update(|state| state.on = true);
+    }
+
+    fn beep_stop(&mut self) {
+        self.update(|state| state.on = false);
+    }
+
+    fn pattern(&mut self, pattern: Option<&[u8; AUDIO_PATTERN_SIZE]>, pitch: u8) {
+        self.update(|state| {
+            state.pattern = pattern.copied();
+            state.pitch = pitch;
+        });
+    }
+}
+
+// --- buzzer definition ------------------------------------------------------
+
+/// The buzzer, sounding a tone whilst the sound timer is non-zero
+///
+/// Once a ROM loaded an audio pattern (XO-CHIP), its 128 bits are played in
+/// a loop instead, each bit being a sample at the rate the pitch gives.
+///
+/// The audio device is the default output device of the system, as raylib
+/// doesn't offer a choice, so another device is selected in the sound
+/// settings of the system (or with `PULSE_SINK` under PulseAudio).
+pub(crate) struct Buzzer {
+    /// The audio device the stream plays on
+    audio: &'static RaylibAudio,
+    /// The volume of the buzzer, from 0 to 1
+    volume: f32,
+    /// Flag indicating whether the buzzer is silenced, keeping its volume
+    muted: bool,
+    /// The stream the samples are written to
+    stream: AudioStream<'static>,
+    /// The synthesizer of the samples
+    synth: Synth,
+    /// The samples written to the stream, kept to not allocate every update
+    samples: Vec<i16>,
+    /// The state of the buzzer, set by the emulator through its sink
+    sink: BuzzerSink,
+}
+
+impl Buzzer {
+    /// Opens the audio device and creates the buzzer on it
+    ///
+    /// Having no audio device isn't an error, as on headless servers and in
+    /// CI: a warning is printed and the emulator runs without sound.
+    ///
+    /// # Params
+    ///
+    /// - `volume` - The volume of the buzzer, from 0 to 1
+    /// - `tone` - The tone to sound when there is no audio pattern
+    /// - `sink` - The audio sink the emulator drives the buzzer through
+    pub fn open(volume: f32, tone: Tone, sink: BuzzerSink) -> Option<Self> {
+        let audio = match RaylibAudio::init_audio_device() {
+            Ok(audio) => audio,
+            Err(e) => {
+                warn!("No audio device, running without sound: {e}");
+                return None;
+            }
+        };
+
+        // The device stays open for the rest of the process, which lets the
+        // stream borrow it without the buzzer borrowing itself
+        let audio: &'static RaylibAudio = Box::leak(Box::new(audio));
+        audio.set_master_volume(volume);
+
+        Some(Self {
+            audio,
+            volume,
+            muted: false,
+            stream: audio.new_audio_stream(SAMPLE_RATE, 16, 1),
+            synth: Synth::new(tone),
+            samples: vec![0; SAMPLES_PER_UPDATE],
+            sink,
+        })
+    }
+
+    /// Gets the volume of the buzzer, from 0 to 1
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Sets the volume of the buzzer, unmuting it
+    ///
+    /// # Params
+    ///
+    /// - `volume` - The volume of the buzzer, from 0 to 1
+    pub fn set_volume(&mut self, volume: f32) {
+        self.audio.set_master_volume(volume);
+        self.volume = volume;
+        self.muted = false;
+    }
+
+    /// Silences the buzzer, or restores its volume if it was silenced,
+    /// returning whether it is now muted
+    pub fn toggle_mute(&mut self) -> bool {
+        self.muted = !self.muted;
+        self.audio.set_master_volume(if self.muted { 0.0 } else { self.volume });
+        self.muted
+    }
+
+    /// Sounds or silences the buzzer as the emulator last set it, to be
+    /// called once per frame
+    ///
+    /// # Params
+    ///
+    /// - `paused` - Whether the emulator is paused, which silences the buzzer
+    pub fn update(&mut self, paused: bool) {
+        let state = self.sink.state();
+        if !state.on || paused {
+            if self.stream.is_playing() {
+                self.stream.pause();
+            }
+
+            return;
+        }
+
+        if self.stream.is_processed() {
+            self.synth.fill(&mut self.samples, &state);
+            self.stream.update(&self.samples);
+        }
+
+        if !self.stream.is_playing() {
+            self.stream.play();
+        }
+    }
+}
+
+// --- audio dump definition --------------------------------------------------
+
+/// Renders the sound of the emulator to a WAV file, whether or not it is
+/// played as well
+///
+/// The samples follow the emulated time rather than the time of the host, a
+/// tick of the timers at a time, so the file is the same however fast the
+/// host runs and leaves out the time spent paused. They are written as
+/// 16-bit mono PCM at the sample rate of the buzzer, and the sizes in the
+/// header are filled in once the dump is finished.
+pub(crate) struct AudioDump {
+    /// The filepath to the WAV file
+    path: String,
+    /// The WAV file, buffered as samples are written every tick
+    writer: BufWriter<File>,
+    /// The synthesizer of the samples
+    synth: Synth,
+    /// The samples of a tick, kept to not allocate every tick
+    samples: Vec<i16>,
+    /// The number of samples written so far
+    len: u32,
+    /// The state of the buzzer, set by the emulator through its sink
+    sink: BuzzerSink,
+}
+
+impl AudioDump {
+    /// Creates the WAV file, replacing it if it already exists
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the WAV file
+    /// - `tone` - The tone to render when there is no audio pattern
+    /// - `sink` - The audio sink the emulator drives the buzzer through
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be created
+    pub fn create(path: &str, tone: Tone, sink: BuzzerSink) -> Result<Self> {
+        let failed = |_| Keet8Error::FailedToSaveAudio(path.to_string());
+
+        let mut writer = BufWriter::new(File::create(path).map_err(failed)?);
+        writer.write_all(&wav_header(0)).map_err(failed)?;
+
+        Ok(Self {
+            path: path.to_string(),
+            writer,
+            synth: Synth::new(tone),
+            samples: vec![0; SAMPLES_PER_TICK],
+            len: 0,
+            sink,
+        })
+    }
+
+    /// Gets the filepath to the WAV file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Writes the samples of a tick of the timers, being silence unless the
+    /// buzzer sounds
+    ///
+    /// # Errors
+    ///
+    /// If the samples could not be written
+    pub fn tick(&mut self) -> Result<()> {
+        let state = self.sink.state();
+        if state.on {
+            self.synth.fill(&mut self.samples, &state);
+        } else {
+            self.samples.fill(0);
+        }
+
+        let bytes = self
+            .samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<u8>>();
+        self.writer
+            .write_all(&bytes)
+            .map_err(|_| Keet8Error::FailedToSaveAudio(self.path.clone()))?;
+        self.len += SAMPLES_PER_TICK as u32;
+
+        Ok(())
+    }
+
+    /// Fills in the sizes in the header and flushes the file
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be written
+    pub fn finish(mut self) -> Result<()> {
+        let header = wav_header(self.len * 2);
+        self.writer
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.writer.write_all(&header))
+            .and_then(|_| self.writer.flush())
+            .map_err(|_| Keet8Error::FailedToSaveAudio(self.path.clone()))
+    }
+}
+
+// --- synth definition -------------------------------------------------------
+
+/// Synthesizes the samples of the buzzer, carrying on from where the last
+/// samples ended so that consecutive ones join up
+struct Synth {
+    /// The tone sounded when there is no audio pattern
+    tone: Tone,
+    /// The position within the current period of the tone, from 0 up to 1
+    phase: f32,
+    /// The position within the audio pattern, from 0 up to the number of
+    /// bits in the pattern
+    bit: f32,
+}
+
+impl Synth {
+    /// Creates the synthesizer at the start of the tone
+    ///
+    /// # Params
+    ///
+    /// - `tone` - The tone to sound when there is no audio pattern
+    fn new(tone: Tone) -> Self {
+        Self {
+            tone,
+            phase: 0.0,
+            bit: 0.0,
+        }
+    }
+
+    /// Writes the samples of the buzzer as the emulator set it, being the
+    /// audio pattern if the ROM loaded one and the tone otherwise
+    ///
+    /// # Params
+    ///
+    /// - `samples` - The samples to write
+    /// - `state` - The state of the buzzer
+    fn fill(&mut self, samples: &mut [i16], state: &BuzzerState) {
+        match &state.pattern {
+            Some(pattern) => self.write_pattern(samples, pattern, state.pitch),
+            None => self.write_tone(samples),
+        }
+    }
+
+    /// Writes the samples of the tone, synthesized from its waveform
+    ///
+    /// # Params
+    ///
+    /// - `samples` - The samples to write
+    fn write_tone(&mut self, samples: &mut [i16]) {
+        let Tone {
+            waveform,
+            frequency,
+            duty_cycle,
+        } = self.tone;
+
+        let step = frequency / SAMPLE_RATE as f32;
+        for sample in samples {
+            *sample = (waveform.level(self.phase, duty_cycle) * AMPLITUDE) as i16;
+            self.phase = (self.phase + step).fract();
+        }
+    }
+
+    /// Writes the samples of the audio pattern (XO-CHIP), resampled from its
+    /// playback rate to the one of the stream
+    ///
+    /// # Params
+    ///
+    /// - `samples` - The samples to write
+    /// - `pattern` - The audio pattern, played from the most significant bit
+    ///   of the first byte
+    /// - `pitch` - The pitch the audio pattern is played at
+    fn write_pattern(
+        &mut self,
+        samples: &mut [i16],
+        pattern: &[u8; AUDIO_PATTERN_SIZE],
+        pitch: u8,
+    ) {
+        let rate = PATTERN_RATE * 2f32.powf((pitch as f32 - 64.0) / 48.0);
+        let step = rate / SAMPLE_RATE as f32;
+        for sample in samples {
+            let bit = self.bit as usize;
+            let high = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+            *sample = (if high { AMPLITUDE } else { -AMPLITUDE }) as i16;
+            self.bit = (self.bit + step) % PATTERN_BITS;
+        }
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Creates the header of a WAV file of 16-bit mono PCM samples
+///
+/// # Params
+///
+/// - `data_len` - The size in bytes of the samples
+fn wav_header(data_len: u32) -> [u8; WAV_HEADER_SIZE as usize] {
+    let mut header = [0; WAV_HEADER_SIZE as usize];
+    let fields: [&[u8]; 13] = [
+        b"RIFF",
+        &(WAV_HEADER_SIZE - 8 + data_len).to_le_bytes(),
+        b"WAVE",
+        b"fmt ",
+        &16u32.to_le_bytes(), // size of the format chunk
+        &1u16.to_le_bytes(),  // PCM
+        &1u16.to_le_bytes(),  // mono
+        &SAMPLE_RATE.to_le_bytes(),
+        &(SAMPLE_RATE * 2).to_le_bytes(), // bytes per second
+        &2u16.to_le_bytes(),              // bytes per sample
+        &16u16.to_le_bytes(),             // bits per sample
+        b"data",
+        &data_len.to_le_bytes(),
+    ];
+
+    let mut offset = 0;
+    for field in fields {
+        header[offset..offset + field.len()].copy_from_slice(field);
+        offset += field.len();
+    }
+
+    header
+}
+
+/// Changes a volume by a number of steps, keeping it on a step between 0 and
+/// 1
+///
+/// # Params
+///
+/// - `volume` - The volume, from 0 to 1
+/// - `steps` - The number of steps to change the volume by, going down if
+///   negative
+pub(crate) fn step_volume(volume: f32, steps: i32) -> f32 {
+    let volume = volume + steps as f32 * VOLUME_STEP;
+    ((volume / VOLUME_STEP).round() * VOLUME_STEP).clamp(0.0, 1.0)
+}
@@ -0,0 +1,117 @@
+use crate::config::Config;
+use crate::prelude::*;
+use crate::roms::read_rom;
+
+use keet8_core::{Emulator, InitPolicy, Lcg, StepOutcome};
+
+use std::time::{Duration, Instant};
+
+// --- bench interface --------------------------------------------------------
+
+/// Runs the ROM for a number of frames without a window, as fast as the core
+/// goes, and writes to stdout how many instructions it executed per second
+/// along with the time the frames took
+///
+/// A frame executes the instructions the CPU speed gives per 60Hz tick and
+/// then ticks the timers, as in the window, so the figures are comparable
+/// across changes to the core. Breakpoints and host calls are left out.
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+/// - `config` - The configuration, with the options already applied
+/// - `frames` - The number of frames to run
+/// - `seed` - The seed of the random numbers, if not the default one
+/// - `strict` - Whether unknown opcodes raise an error
+///
+/// # Errors
+///
+/// - If an error occured when loading the ROM file
+/// - If an error occured whilst running the ROM
+pub(crate) fn print_bench(
+    rom_file: &str,
+    config: &Config,
+    frames: u32,
+    seed: Option<u32>,
+    strict: bool,
+) -> Result<()> {
+    // The ROM is loaded once the memory has the size of the platform
+    let mut emulator = Emulator::without_rom(InitPolicy::Zeroed);
+    emulator.set_quirks(config.quirks);
+    emulator.set_platform(config.platform);
+    emulator.set_two_page(config.two_page);
+    emulator.set_load_address(config.load_addr)?;
+    emulator.load_rom(&read_rom(rom_file)?)?;
+    emulator.set_strict(strict);
+    if let Some(seed) = seed {
+        emulator.set_random_source(Lcg::new(seed));
+    }
+
+    let steps_per_frame = (config.cpu_speed / 60).max(1);
+    let mut frame_times = Vec::with_capacity(frames as usize);
+    let mut steps = 0u64;
+
+    let start = Instant::now();
+    while frame_times.len() < frames as usize && !emulator.is_halted() {
+        let frame_start = Instant::now();
+
+        for _ in 0..steps_per_frame {
+            if let StepOutcome::Halted = emulator.step()? {
+                break;
+            }
+
+            steps += 1;
+        }
+
+        emulator.tick_timers();
+        frame_times.push(frame_start.elapsed());
+    }
+    let elapsed = start.elapsed();
+
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    let emulated = frame_times.len() as f64 / 60.0;
+
+    println!("ROM:          {rom_file}");
+    println!("Frames:       {} of {frames}, {steps_per_frame} instructions each", frame_times.len());
+    if emulator.is_halted() {
+        println!("              (the ROM exited early)");
+    }
+    println!("Instructions: {steps} in {:.3}s", elapsed.as_secs_f64());
+    println!("Speed:        {:.0} instructions per second", steps as f64 / secs);
+    println!(
+        "              {:.0} frames per second, {:.1}x real time",
+        frame_times.len() as f64 / secs,
+        emulated / secs,
+    );
+
+    if frame_times.is_empty() {
+        return Ok(());
+    }
+
+    let mean = frame_times.iter().sum::<Duration>() / frame_times.len() as u32;
+    frame_times.sort_unstable();
+    let percentile = |p: usize| frame_times[(frame_times.len() - 1) * p / 100];
+
+    println!();
+    println!("Frame time    min       mean      p99       max");
+    println!(
+        "              {:<9} {:<9} {:<9} {}",
+        micros(frame_times[0]),
+        micros(mean),
+        micros(percentile(99)),
+        micros(frame_times[frame_times.len() - 1]),
+    );
+
+    Ok(())
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Formats a duration in microseconds, such as `12.3µs`
+///
+/// # Params
+///
+/// - `duration` - The duration to format
+fn micros(duration: Duration) -> String {
+    format!("{:.1}µs", duration.as_secs_f64() * 1e6)
+}
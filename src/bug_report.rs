@@ -0,0 +1,268 @@
+use crate::hash;
+use crate::prelude::*;
+use crate::roms::read_rom_bytes;
+use crate::screen::framebuffer_image;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::{Emulator, Quirks};
+use raylib::prelude::*;
+
+use std::collections::VecDeque;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of instructions last executed kept in the trace of the report
+const TRACE_LENGTH: usize = 256;
+/// The size of the square of a pixel in the screenshot of the report
+const SCREENSHOT_SCALE: u32 = 8;
+/// The DOS date stored for the files of the archive, being 1980-01-01 so the
+/// archive only depends on its contents
+const DOS_DATE: u16 = 1 << 5 | 1;
+
+// --- bug report definition --------------------------------------------------
+
+/// Gathers what is needed to reproduce a run of a ROM, to be packaged into a
+/// ZIP archive when the run ends: a summary with the hashes of the ROM and
+/// the quirks it ran with, the configuration file, the instructions last
+/// executed, a save state and a screenshot
+pub(crate) struct BugReport {
+    /// The filepath to the archive
+    path: String,
+    /// The filepath to the ROM file
+    rom_file: String,
+    /// The contents of the configuration file, if there is one
+    config: Option<String>,
+    /// The number of instructions executed per second
+    cpu_speed: u32,
+    /// The quirks the ROM ran with
+    quirks: Quirks,
+    /// The address and raw opcode of the instructions last executed, oldest
+    /// first
+    trace: VecDeque<(u16, u16)>,
+}
+
+impl BugReport {
+    /// Creates a bug report to be written to an archive
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the archive
+    /// - `rom_file` - The filepath to the ROM file
+    /// - `config_file` - The filepath to the configuration file, if any
+    /// - `cpu_speed` - The number of instructions executed per second
+    /// - `quirks` - The quirks the ROM runs with
+    pub fn new(
+        path: &str,
+        rom_file: &str,
+        config_file: Option<&str>,
+        cpu_speed: u32,
+        quirks: Quirks,
+    ) -> Self {
+        Self {
+            path: path.to_string(),
+            rom_file: rom_file.to_string(),
+            config: config_file.and_then(|file| std::fs::read_to_string(file).ok()),
+            cpu_speed,
+            quirks,
+            trace: VecDeque::with_capacity(TRACE_LENGTH),
+        }
+    }
+
+    /// Gets the filepath to the archive
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Notes an instruction about to be executed, forgetting the oldest one
+    /// once the trace is full
+    ///
+    /// # Params
+    ///
+    /// - `pc` - The address of the instruction
+    /// - `raw` - The raw opcode of the instruction
+    pub fn record(&mut self, pc: u16, raw: u16) {
+        if self.trace.len() == TRACE_LENGTH {
+            self.trace.pop_front();
+        }
+
+        self.trace.push_back((pc, raw));
+    }
+
+    /// Writes the archive with the current state of the emulator
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator which ran the ROM
+    /// - `foreground` - The color of the pixels which are on
+    /// - `background` - The color of the pixels which are off
+    /// - `error` - The error the run ended with, if any
+    ///
+    /// # Errors
+    ///
+    /// If the archive could not be written
+    pub fn write(
+        &self,
+        emulator: &Emulator,
+        foreground: Color,
+        background: Color,
+        error: Option<&Keet8Error>,
+    ) -> Result<()> {
+        let failed = || Keet8Error::FailedToSaveBugReport(self.path.clone());
+
+        let mut zip = ZipWriter::default();
+        zip.add("report.txt", self.summary(emulator, error).as_bytes());
+        if let Some(config) = &self.config {
+            zip.add("config.toml", config.as_bytes());
+        }
+        zip.add("trace.txt", self.trace_text().as_bytes());
+        zip.add("state.k8s", &emulator.save_state());
+
+        // raylib only exports images to files, so the screenshot takes a
+        // detour through a temporary one
+        let image = framebuffer_image(&emulator.frame(), foreground, background, SCREENSHOT_SCALE);
+        let temp_file = std::env::temp_dir().join(format!("keet8-{}.png", std::process::id()));
+        let temp_path = temp_file.display().to_string();
+        if image.export_image(&temp_path) {
+            let screenshot = std::fs::read(&temp_file);
+            let _ = std::fs::remove_file(&temp_file);
+            zip.add("screenshot.png", &screenshot.map_err(|_| failed())?);
+        }
+
+        std::fs::write(&self.path, zip.finish()).map_err(|_| failed())
+    }
+
+    /// Formats the summary of the run: the versions, the ROM with its hashes,
+    /// the speed and quirks it ran with and how it ended
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator which ran the ROM
+    /// - `error` - The error the run ended with, if any
+    fn summary(&self, emulator: &Emulator, error: Option<&Keet8Error>) -> String {
+        let mut lines = vec![
+            format!("keet-8:   {}", env!("CARGO_PKG_VERSION")),
+            format!("Platform: {} {}", std::env::consts::OS, std::env::consts::ARCH),
+            format!("ROM:      {}", self.rom_file),
+        ];
+
+        if let Ok(bytes) = read_rom_bytes(&self.rom_file) {
+            let sha1 = hash::sha1_hex(&bytes);
+
+            lines.push(format!("SHA-1:    {sha1}"));
+            lines.push(format!("FNV-1a:   {:016x}", hash::fnv1a(&bytes)));
+        }
+
+        let quirks = self.quirks;
+        let enabled = [
+            ("shift_vy", quirks.shift_vy),
+            ("load_store_increment", quirks.load_store_increment),
+            ("vf_reset", quirks.vf_reset),
+            ("jump_vx", quirks.jump_vx),
+            ("wrap_sprites", quirks.wrap_sprites),
+            ("display_wait", quirks.display_wait),
+            ("index_overflow", quirks.index_overflow),
+            ("strict_memory", quirks.strict_memory),
+            ("clamp_memory", quirks.clamp_memory),
+        ]
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>();
+
+        lines.push(format!("Speed:    {} instructions per second", self.cpu_speed));
+        if enabled.is_empty() {
+            lines.push("Quirks:   none".to_string());
+        } else {
+            lines.push(format!("Quirks:   {}", enabled.join(", ")));
+        }
+        lines.push(format!("PC:       {:#05X}", emulator.program_counter()));
+
+        // The error is written without the colors meant for the terminal
+        if let Some(error) = error {
+            colored::control::set_override(false);
+            lines.push(format!("Error:    {error}"));
+            colored::control::unset_override();
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Formats the trace, one disassembled instruction per line
+    fn trace_text(&self) -> String {
+        self.trace
+            .iter()
+            .map(|&(pc, raw)| format!("{pc:#06X}: {raw:04X}  {}\n", OpCode::from(raw)))
+            .collect()
+    }
+}
+
+// --- zip writer definition --------------------------------------------------
+
+/// Writes a ZIP archive whose files are stored without compression, which
+/// every unzipping tool reads
+#[derive(Default)]
+struct ZipWriter {
+    /// The archive written so far, being the local headers and the files
+    bytes: Vec<u8>,
+    /// The central directory, with an entry per file
+    directory: Vec<u8>,
+    /// The number of files in the archive
+    num_files: u16,
+}
+
+impl ZipWriter {
+    /// Adds a file to the archive
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the file within the archive
+    /// - `contents` - The contents of the file
+    fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.bytes.len() as u32;
+
+        // The fields the local header shares with the central directory,
+        // from the version needed to the length of the extra field
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&hash::crc32(contents).to_le_bytes());
+        common.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        self.bytes.extend_from_slice(&0x04034B50u32.to_le_bytes());
+        self.bytes.extend_from_slice(&common);
+        self.bytes.extend_from_slice(name.as_bytes());
+        self.bytes.extend_from_slice(contents);
+
+        // The central directory additionally holds the version made by, the
+        // comment, disk and attributes, and where the local header is
+        self.directory.extend_from_slice(&0x02014B50u32.to_le_bytes());
+        self.directory.extend_from_slice(&20u16.to_le_bytes());
+        self.directory.extend_from_slice(&common);
+        self.directory.extend_from_slice(&[0; 10]);
+        self.directory.extend_from_slice(&offset.to_le_bytes());
+        self.directory.extend_from_slice(name.as_bytes());
+
+        self.num_files += 1;
+    }
+
+    /// Finishes the archive with the central directory
+    fn finish(mut self) -> Vec<u8> {
+        let offset = self.bytes.len() as u32;
+        self.bytes.extend_from_slice(&self.directory);
+
+        self.bytes.extend_from_slice(&0x06054B50u32.to_le_bytes());
+        self.bytes.extend_from_slice(&[0; 4]);
+        self.bytes.extend_from_slice(&self.num_files.to_le_bytes());
+        self.bytes.extend_from_slice(&self.num_files.to_le_bytes());
+        self.bytes.extend_from_slice(&(self.directory.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(&offset.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes());
+        self.bytes
+    }
+}
@@ -0,0 +1,144 @@
+use crate::options::parse_address;
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+
+// --- cheats definition ------------------------------------------------------
+
+/// The cheats applied to the memory of the running ROM
+///
+/// A cheat writes a byte to an address once per frame, which suits values the
+/// ROM only checks now and then, such as the number of lives. A frozen cheat
+/// writes the byte after every instruction instead, pinning the address to
+/// the value whatever the ROM writes to it.
+///
+/// Cheats are given as `addr:value`, both in hexadecimal, on the command line
+/// or one per line in a cheats file, where `freeze` in front of a cheat
+/// freezes it and `#` starts a comment:
+///
+/// ```text
+/// # Infinite lives
+/// freeze 0x2f0:09
+/// 0x2f4:ff
+/// ```
+#[derive(Default)]
+pub(crate) struct Cheats {
+    /// The cheats written once per frame
+    pokes: Vec<Cheat>,
+    /// The cheats written after every instruction
+    freezes: Vec<Cheat>,
+}
+
+/// A byte written to an address
+#[derive(Clone, Copy)]
+pub(crate) struct Cheat {
+    /// The address written to
+    pub addr: u16,
+    /// The byte written
+    pub value: u8,
+}
+
+impl Cheats {
+    /// Adds a cheat
+    ///
+    /// # Params
+    ///
+    /// - `cheat` - The cheat to add
+    /// - `freeze` - Whether the cheat is written after every instruction
+    ///   rather than once per frame
+    pub fn add(&mut self, cheat: Cheat, freeze: bool) {
+        if freeze {
+            self.freezes.push(cheat);
+        } else {
+            self.pokes.push(cheat);
+        }
+    }
+
+    /// Adds the cheats of a cheats file
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the cheats file
+    ///
+    /// # Errors
+    ///
+    /// If the cheats file could not be read or a cheat is invalid
+    pub fn load(&mut self, path: &str) -> Result<()> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|_| Keet8Error::FailedToLoadCheats(path.to_string()))?;
+
+        let lines = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty());
+
+        for line in lines {
+            let (text, freeze) = match line.strip_prefix("freeze") {
+                Some(cheat) => (cheat.trim(), true),
+                None => (line, false),
+            };
+
+            let cheat =
+                Cheat::parse(text).ok_or_else(|| Keet8Error::InvalidCheat(line.to_string()))?;
+            self.add(cheat, freeze);
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether there are no cheats
+    pub fn is_empty(&self) -> bool {
+        self.pokes.is_empty() && self.freezes.is_empty()
+    }
+
+    /// Writes the cheats applied once per frame, along with the frozen ones
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to write the cheats to
+    pub fn apply_frame(&self, emulator: &mut Emulator) {
+        write_cheats(&self.pokes, emulator);
+        self.apply_freezes(emulator);
+    }
+
+    /// Writes the frozen cheats, to be called after every instruction
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to write the cheats to
+    pub fn apply_freezes(&self, emulator: &mut Emulator) {
+        write_cheats(&self.freezes, emulator);
+    }
+}
+
+impl Cheat {
+    /// Parses a cheat from its textual form, `addr:value` in hexadecimal,
+    /// such as `0x2f0:09`
+    ///
+    /// # Params
+    ///
+    /// - `text` - The textual form of the cheat
+    pub fn parse(text: &str) -> Option<Self> {
+        let (addr, value) = text.split_once(':')?;
+        let value = value.trim();
+
+        Some(Self {
+            addr: parse_address(addr.trim())?,
+            value: u8::from_str_radix(value.strip_prefix("0x").unwrap_or(value), 16).ok()?,
+        })
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Writes cheats to the memory of the emulator
+///
+/// # Params
+///
+/// - `cheats` - The cheats to write
+/// - `emulator` - The emulator to write the cheats to
+fn write_cheats(cheats: &[Cheat], emulator: &mut Emulator) {
+    for cheat in cheats {
+        emulator.poke(cheat.addr, cheat.value);
+    }
+}
@@ -0,0 +1,73 @@
+//! The entry points and shims kept for users of earlier releases.
+//!
+//! The public API follows semantic versioning: within a major version, items
+//! are only added, never removed or changed in a breaking way. An item that
+//! is superseded is marked `#[deprecated]` with the release it was deprecated
+//! in and its replacement, moved here as a thin wrapper over the replacement,
+//! and kept until the next major version. Every release lists its additions,
+//! deprecations and removals in `CHANGELOG.md`.
+
+use crate::options::Options;
+use crate::prelude::*;
+use crate::screen::draw_framebuffer;
+
+use keet8_core::Emulator;
+use raylib::prelude::*;
+
+// --- entry points -----------------------------------------------------------
+
+/// Runs the application
+///
+/// This is the entry point since 1.0.0, and parses the arguments into
+/// `Options` before handing them to `start`
+///
+/// # Params
+///
+/// - `args` - The command-line arguments
+///
+/// # Examples
+///
+/// ```rust
+/// let args = std::env::args()
+///     .collect();
+///
+/// if let Err(e) = keet_8::run(args) {
+///     eprintln!("{e}");
+/// }
+/// ```
+///
+/// # Errors
+///
+/// - If no ROM file was provided
+/// - If an unknown command-line argument was provided
+/// - Any of the errors of `start`
+pub fn run(args: Vec<String>) -> Result<()> {
+    crate::start(&Options::parse(&args)?)
+}
+
+// --- deprecated shims -------------------------------------------------------
+
+/// The drawing of the emulator to the window, which left the emulator when
+/// the core moved to the `keet8-core` crate
+///
+/// The trait has to be imported for `Emulator::draw_buffer` to be found.
+pub trait DrawBuffer {
+    /// Draws the video buffer data to the window
+    ///
+    /// # Params
+    ///
+    /// - `d` - The draw handle provided by raylib
+    /// - `color` - The color of the pixels which are on
+    #[deprecated(
+        since = "1.1.0",
+        note = "the core no longer draws itself, draw the pixels of `framebuffer` instead"
+    )]
+    fn draw_buffer(&mut self, d: &mut RaylibDrawHandle, color: Color);
+}
+
+impl DrawBuffer for Emulator {
+    fn draw_buffer(&mut self, d: &mut RaylibDrawHandle, color: Color) {
+        let scale = d.get_screen_width() as f32 / self.screen_width() as f32;
+        draw_framebuffer(d, &self.frame(), color, scale);
+    }
+}
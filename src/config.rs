@@ -0,0 +1,867 @@
+use crate::accessibility::Accessibility;
+use crate::audio::{Tone, Waveform, SAMPLE_RATE};
+use crate::hash;
+use crate::keymap::{key_name, parse_button, parse_key, Layout, NUM_KEYPAD_KEYS};
+use crate::options::Options;
+use crate::palette::{find_palette, PALETTES};
+use crate::prelude::*;
+use crate::rom_db::{self, RomProfile};
+use crate::roms::read_rom_bytes;
+
+use keet8_core::{is_two_page_rom, Platform, Quirks, PROG_ADDR};
+use log::info;
+use raylib::prelude::*;
+use serde::Deserialize;
+use toml_edit::{value, DocumentMut, TableLike};
+
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// --- macros -----------------------------------------------------------------
+
+/// Creates a `Color` from a single byte, where the bits represent the strength
+/// in the individual colors.
+/// 
+/// # Bit Representation of color
+/// 
+/// - The byte is split up as follows `00|00|00|00`
+/// - The red bits are represented as `0xC0` (most significant 2 bits)
+/// - The green bits are represented as `0x30`
+/// - The blue bits are represented as `0x0C`
+/// - The alpha bits are represented as `0x03` (least significant 2 bits)
+/// 
+/// # Params
+/// 
+/// - `byte` - The byte to create the color from
+macro_rules! color8 {
+    ($byte:expr) => {{
+        Color {
+            r: 85 * ((($byte) & 0xC0) >> 6),
+            g: 85 * ((($byte) & 0x30) >> 4),
+            b: 85 * ((($byte) & 0x0C) >> 2),
+            a: 85 * ((($byte) & 0x03) >> 0),
+        }
+    }};
+}
+
+// --- constants --------------------------------------------------------------
+
+/// The configuration file loaded from the working directory when no other
+/// file is given with `--config`, and created there by the settings menu
+pub(crate) const DEFAULT_CONFIG_FILE: &str = "keet8.toml";
+
+/// The default width of the window
+const DEFAULT_WINDOW_WIDTH: i32 = 1024;
+/// The default height of the window
+const DEFAULT_WINDOW_HEIGHT: i32 = 512;
+
+/// Represents the default color of a single pixel on the screen buffer
+/// 
+/// This a green color close to that of the one provided by the raylib library
+/// but this one is entirely 8-bit
+const DEFAULT_PIXEL_COLOR: Color = color8!(0b00110111);
+
+/// The default size of the square of a pixel in screenshots, giving the size
+/// of the default window
+const DEFAULT_SCREENSHOT_SCALE: u32 = 8;
+
+/// The default volume of the buzzer, from 0 to 1
+const DEFAULT_VOLUME: f32 = 0.5;
+
+/// The default number of instructions executed per second
+const DEFAULT_CPU_SPEED: u32 = 60;
+
+/// The key of the `[keys]` table naming the layout of the keypad, which the
+/// other keys of the table are bound on top of
+const LAYOUT_KEY: &str = "layout";
+
+/// The default keyboard keys of the keypad of the ROM run side by side,
+/// indexed by the key on the keypad: the digits on the digits of the numpad
+/// and `A` to `F` on the keys around them
+const DEFAULT_SIDE_KEYMAP: [KeyboardKey; NUM_KEYPAD_KEYS] = [
+    KeyboardKey::KEY_KP_0,
+    KeyboardKey::KEY_KP_1,
+    KeyboardKey::KEY_KP_2,
+    KeyboardKey::KEY_KP_3,
+    KeyboardKey::KEY_KP_4,
+    KeyboardKey::KEY_KP_5,
+    KeyboardKey::KEY_KP_6,
+    KeyboardKey::KEY_KP_7,
+    KeyboardKey::KEY_KP_8,
+    KeyboardKey::KEY_KP_9,
+    KeyboardKey::KEY_KP_DECIMAL,
+    KeyboardKey::KEY_KP_ENTER,
+    KeyboardKey::KEY_KP_DIVIDE,
+    KeyboardKey::KEY_KP_MULTIPLY,
+    KeyboardKey::KEY_KP_SUBTRACT,
+    KeyboardKey::KEY_KP_ADD,
+];
+
+/// The default gamepad buttons of the keypad, indexed by the key on the
+/// keypad: the d-pad on the `2`/`4`/`6`/`8` directions most ROMs use and `A`
+/// on `5`, usually the action key
+const DEFAULT_GAMEPAD_MAP: [Option<GamepadButton>; NUM_KEYPAD_KEYS] = [
+    None,
+    None,
+    Some(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP),
+    None,
+    Some(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+    Some(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+    Some(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+    None,
+    Some(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+];
+
+// --- config definition ------------------------------------------------------
+
+/// The configuration of the application, loaded from a TOML file with the
+/// command-line flags taking precedence
+pub(crate) struct Config {
+    /// The initial (and minimum) width of the window
+    pub window_width: i32,
+    /// The initial (and minimum) height of the window
+    pub window_height: i32,
+    /// The color of the pixels which are on
+    pub foreground: Color,
+    /// The color of the pixels which are off
+    pub background: Color,
+    /// The built-in palette the colors were taken from, if any, as an index
+    /// into `PALETTES`
+    pub palette: Option<usize>,
+    /// Flag indicating whether the CRT filter is initially applied
+    pub crt: bool,
+    /// Flag indicating whether the display is initially scaled by whole
+    /// numbers only
+    pub integer_scaling: bool,
+    /// The number of 60Hz frames a pixel takes to fade out after turning off,
+    /// with 0 turning it off instantly
+    pub phosphor: u32,
+    /// The size of the square of a pixel in screenshots, with 1 being the
+    /// native resolution
+    pub screenshot_scale: u32,
+    /// The keyboard keys of the keypad, indexed by the key on the keypad
+    pub keymap: [KeyboardKey; NUM_KEYPAD_KEYS],
+    /// The keyboard keys of the keypad of the ROM run side by side, indexed
+    /// by the key on the keypad
+    pub side_keymap: [KeyboardKey; NUM_KEYPAD_KEYS],
+    /// The keyboard keys of the second player on the same keypad, indexed by
+    /// the key on the keypad
+    pub player2_keymap: [Option<KeyboardKey>; NUM_KEYPAD_KEYS],
+    /// The gamepad buttons of the keypad, indexed by the key on the keypad
+    pub gamepad_map: [Option<GamepadButton>; NUM_KEYPAD_KEYS],
+    /// Flag indicating whether the buzzer sounds, if there is an audio device
+    pub audio: bool,
+    /// The volume of the buzzer, from 0 to 1
+    pub volume: f32,
+    /// The tone the buzzer sounds
+    pub tone: Tone,
+    /// The number of instructions executed per second
+    pub cpu_speed: u32,
+    /// The quirks to run the ROM with
+    pub quirks: Quirks,
+    /// The platform the opcodes are restricted to, if any
+    pub platform: Option<Platform>,
+    /// Flag indicating whether the ROM runs in the two-page mode (hi-res
+    /// Chip-8)
+    pub two_page: bool,
+    /// The address the ROM is loaded and starts at
+    pub load_addr: u16,
+    /// The accessibility options
+    pub accessibility: Accessibility,
+    /// The display settings of single ROMs, keyed by the SHA-1 of the ROM
+    /// or the file name of the ROM file
+    pub themes: BTreeMap<String, Theme>,
+}
+
+impl Default for Config {
+    /// Creates the configuration used when there is no configuration file
+    fn default() -> Self {
+        Self {
+            window_width: DEFAULT_WINDOW_WIDTH,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+            foreground: DEFAULT_PIXEL_COLOR,
+            background: Color::BLACK,
+            palette: None,
+            crt: false,
+            integer_scaling: false,
+            phosphor: 0,
+            screenshot_scale: DEFAULT_SCREENSHOT_SCALE,
+            keymap: Layout::default().keys(),
+            side_keymap: DEFAULT_SIDE_KEYMAP,
+            player2_keymap: [None; NUM_KEYPAD_KEYS],
+            gamepad_map: DEFAULT_GAMEPAD_MAP,
+            audio: true,
+            volume: DEFAULT_VOLUME,
+            tone: Tone::default(),
+            cpu_speed: DEFAULT_CPU_SPEED,
+            quirks: Quirks::default(),
+            platform: None,
+            two_page: false,
+            load_addr: PROG_ADDR,
+            accessibility: Accessibility::default(),
+            themes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration for the options, with the options taking
+    /// precedence over the ROM database, which takes precedence over the
+    /// configuration file
+    ///
+    /// The configuration file is the one given with `--config`, otherwise
+    /// `keet8.toml` in the working directory if it exists. Anything missing
+    /// from the file keeps its default. Unless `--no-rom-db` is given, a ROM
+    /// found in the ROM database runs with the quirks of its platform and the
+    /// speed and colors it was made for.
+    ///
+    /// # Params
+    ///
+    /// - `options` - The options parsed from the command-line arguments
+    ///
+    /// # Errors
+    ///
+    /// - If the configuration file could not be read
+    /// - If the configuration file is invalid
+    /// - If the ROM database could not be read or is invalid
+    pub fn load(options: &Options) -> Result<Self> {
+        let mut config = match config_file(options) {
+            Some(file) => {
+                let text = std::fs::read_to_string(file)
+                    .map_err(|_| Keet8Error::FailedToLoadConfig(file.to_string()))?;
+
+                Self::parse(&text)?
+            }
+            None => Self::default(),
+        };
+
+        // A ROM which can't be read is reported when the ROM is loaded
+        let rom = read_rom_bytes(&options.rom_file).ok();
+        if let Some(rom) = rom.as_ref().filter(|_| !options.no_rom_database) {
+            if let Some(profile) = rom_db::lookup(rom, options.rom_database.as_deref())? {
+                config.apply_profile(&profile);
+            }
+        }
+
+        // The theme of the ROM takes precedence over the colors of the ROM
+        // database, whether it is keyed by the hash or the file name
+        let file_name = Path::new(&options.rom_file).file_name().and_then(|name| name.to_str());
+        let theme = rom
+            .as_ref()
+            .and_then(|rom| config.themes.get(&hash::sha1_hex(rom)))
+            .or_else(|| config.themes.get(file_name?))
+            .copied();
+        if let Some(theme) = theme {
+            config.apply_theme(&theme);
+        }
+
+        if let Some(speed) = options.cpu_speed {
+            config.cpu_speed = speed;
+        }
+        if let Some(layout) = options.layout {
+            config.keymap = layout.keys();
+        }
+
+        // The players share the keypad, so a keyboard key pressed by one of
+        // them can't stand for another keypad key of the other
+        let shared = config.player2_keymap.iter().flatten().find(|key| config.keymap.contains(key));
+        if let Some(&key) = shared {
+            return Err(Keet8Error::InvalidConfig(format!(
+                "keyboard key {} is bound for both players",
+                key_name(key).unwrap_or("?")
+            )));
+        }
+
+        config.crt |= options.crt;
+        config.integer_scaling |= options.integer_scaling;
+        if let Some(phosphor) = options.phosphor {
+            config.phosphor = phosphor;
+        }
+        config.audio &= !options.mute;
+
+        let invalid_value = |flag: &str| Keet8Error::InvalidArgumentValue(flag.to_string());
+        if let Some(name) = &options.palette {
+            config.set_palette(find_palette(name).ok_or_else(|| invalid_value("--palette"))?);
+        }
+        if let Some(foreground) = &options.foreground {
+            config.foreground = parse_color(foreground).map_err(|_| invalid_value("--foreground"))?;
+        }
+        if let Some(background) = &options.background {
+            config.background = parse_color(background).map_err(|_| invalid_value("--background"))?;
+        }
+
+        // The platform replaces the quirks of the file and the ROM database
+        if let Some(platform) = options.platform {
+            config.quirks = platform.quirks();
+            config.platform = Some(platform);
+        }
+
+        // The quirk flags can only enable quirks, so they are combined with
+        // the ones enabled in the file, by the ROM database or the platform
+        let quirks = &mut config.quirks;
+        quirks.shift_vy |= options.quirks.shift_vy;
+        quirks.load_store_increment |= options.quirks.load_store_increment;
+        quirks.vf_reset |= options.quirks.vf_reset;
+        quirks.jump_vx |= options.quirks.jump_vx;
+        quirks.wrap_sprites |= options.quirks.wrap_sprites;
+        quirks.display_wait |= options.quirks.display_wait;
+        quirks.index_overflow |= options.quirks.index_overflow;
+        quirks.strict_memory |= options.quirks.strict_memory;
+        quirks.clamp_memory |= options.quirks.clamp_memory;
+
+        // Two-page ROMs are recognized by their first instruction, unless
+        // they run as a later platform, which has no use for the mode
+        let chip8 = config.platform.is_none_or(|platform| platform == Platform::Chip8);
+        let two_page_rom = rom.is_some_and(|rom| is_two_page_rom(&rom));
+        config.two_page = options.two_page || (chip8 && two_page_rom);
+        if let Some(addr) = options.load_addr {
+            config.load_addr = addr;
+        }
+
+        Ok(config)
+    }
+
+    /// Parses the configuration from the contents of a configuration file
+    ///
+    /// # Params
+    ///
+    /// - `text` - The contents of the configuration file
+    ///
+    /// # Errors
+    ///
+    /// If the configuration is invalid
+    fn parse(text: &str) -> Result<Self> {
+        let file: ConfigFile =
+            toml::from_str(text).map_err(|e| Keet8Error::InvalidConfig(e.message().to_string()))?;
+
+        let mut config = Self::default();
+
+        if let Some(width) = file.window.width {
+            config.window_width = width;
+        }
+        if let Some(height) = file.window.height {
+            config.window_height = height;
+        }
+        if let Some(crt) = file.window.crt {
+            config.crt = crt;
+        }
+        if let Some(integer_scaling) = file.window.integer_scaling {
+            config.integer_scaling = integer_scaling;
+        }
+        if let Some(phosphor) = file.window.phosphor {
+            config.phosphor = phosphor;
+        }
+        if let Some(scale) = file.window.screenshot_scale {
+            if scale == 0 {
+                return Err(Keet8Error::InvalidConfig(
+                    "screenshot scale must be positive".to_string(),
+                ));
+            }
+
+            config.screenshot_scale = scale;
+        }
+
+        if let Some(name) = &file.colors.palette {
+            config.set_palette(
+                find_palette(name)
+                    .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown palette: {name}")))?,
+            );
+        }
+        if let Some(foreground) = &file.colors.foreground {
+            config.foreground = parse_color(foreground)?;
+        }
+        if let Some(background) = &file.colors.background {
+            config.background = parse_color(background)?;
+        }
+
+        // The keys of the table are bound on top of the layout
+        if let Some(name) = file.keys.get(LAYOUT_KEY) {
+            config.keymap = Layout::parse(name)
+                .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown layout: {name}")))?
+                .keys();
+        }
+        for (key, name) in file.keys.iter().filter(|(key, _)| *key != LAYOUT_KEY) {
+            config.keymap[parse_keypad_key(key)?] = parse_key(name)
+                .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown keyboard key: {name}")))?;
+        }
+
+        for (key, name) in &file.side_keys {
+            config.side_keymap[parse_keypad_key(key)?] = parse_key(name)
+                .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown keyboard key: {name}")))?;
+        }
+
+        for (key, name) in &file.player2_keys {
+            config.player2_keymap[parse_keypad_key(key)?] = Some(parse_key(name).ok_or_else(
+                || Keet8Error::InvalidConfig(format!("unknown keyboard key: {name}")),
+            )?);
+        }
+
+        for (key, name) in &file.gamepad {
+            config.gamepad_map[parse_keypad_key(key)?] = match name.to_ascii_uppercase().as_str() {
+                "NONE" => None,
+                _ => Some(parse_button(name).ok_or_else(|| {
+                    Keet8Error::InvalidConfig(format!("unknown gamepad button: {name}"))
+                })?),
+            };
+        }
+
+        if let Some(enabled) = file.audio.enabled {
+            config.audio = enabled;
+        }
+        if let Some(volume) = file.audio.volume {
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(Keet8Error::InvalidConfig(
+                    "audio volume must be between 0 and 1".to_string(),
+                ));
+            }
+
+            config.volume = volume;
+        }
+        if let Some(name) = &file.audio.waveform {
+            config.tone.waveform = Waveform::parse(name)
+                .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown waveform: {name}")))?;
+        }
+        if let Some(frequency) = file.audio.frequency {
+            // Above half the sample rate the tone would alias into another one
+            let max = SAMPLE_RATE as f32 / 2.0;
+            if !(frequency > 0.0 && frequency < max) {
+                return Err(Keet8Error::InvalidConfig(format!(
+                    "audio frequency must be between 0 and {max}Hz"
+                )));
+            }
+
+            config.tone.frequency = frequency;
+        }
+        if let Some(duty_cycle) = file.audio.duty_cycle {
+            if !(duty_cycle > 0.0 && duty_cycle < 1.0) {
+                return Err(Keet8Error::InvalidConfig(
+                    "audio duty cycle must be between 0 and 1".to_string(),
+                ));
+            }
+
+            config.tone.duty_cycle = duty_cycle;
+        }
+
+        if let Some(speed) = file.cpu.speed {
+            if speed == 0 {
+                return Err(Keet8Error::InvalidConfig("cpu speed must be positive".to_string()));
+            }
+
+            config.cpu_speed = speed;
+        }
+
+        for (key, theme) in &file.themes {
+            config.themes.insert(key.clone(), theme.parse()?);
+        }
+
+        config.quirks = file.quirks;
+        config.accessibility = file.accessibility;
+        Ok(config)
+    }
+
+    /// Takes the quirks, speed and colors of a ROM found in the ROM database,
+    /// keeping the configured speed and colors where the ROM has none
+    ///
+    /// # Params
+    ///
+    /// - `profile` - The settings of the ROM
+    fn apply_profile(&mut self, profile: &RomProfile) {
+        info!("Found {} ({}) in the ROM database", profile.title, profile.platform);
+
+        self.quirks = profile.quirks;
+        if let Some(speed) = profile.cpu_speed.filter(|&speed| speed > 0) {
+            self.cpu_speed = speed;
+        }
+
+        // Colors the frontend can't draw are left out rather than failing
+        let colors = profile.colors.as_ref().and_then(|(off, on)| {
+            Some((parse_color(off).ok()?, parse_color(on).ok()?))
+        });
+        if let Some((background, foreground)) = colors {
+            self.background = background;
+            self.foreground = foreground;
+            self.palette = None;
+        }
+    }
+
+    /// Takes the display settings of a ROM, keeping the configured ones where
+    /// the theme has none
+    ///
+    /// # Params
+    ///
+    /// - `theme` - The display settings of the ROM
+    fn apply_theme(&mut self, theme: &Theme) {
+        if let Some(index) = theme.palette {
+            self.set_palette(index);
+        }
+        if let Some(foreground) = theme.foreground {
+            self.foreground = foreground;
+            self.palette = None;
+        }
+        if let Some(background) = theme.background {
+            self.background = background;
+            self.palette = None;
+        }
+        if let Some(crt) = theme.crt {
+            self.crt = crt;
+        }
+        if let Some(integer_scaling) = theme.integer_scaling {
+            self.integer_scaling = integer_scaling;
+        }
+        if let Some(phosphor) = theme.phosphor {
+            self.phosphor = phosphor;
+        }
+    }
+
+    /// Takes the colors from a built-in palette
+    ///
+    /// # Params
+    ///
+    /// - `index` - The index of the palette in `PALETTES`
+    fn set_palette(&mut self, index: usize) {
+        self.foreground = PALETTES[index].foreground;
+        self.background = PALETTES[index].background;
+        self.palette = Some(index);
+    }
+}
+
+// --- theme definition -------------------------------------------------------
+
+/// The display settings of a single ROM, taken from the `[themes]` table of
+/// the configuration file, with `None` keeping the configured setting
+#[derive(Clone, Copy)]
+pub(crate) struct Theme {
+    /// The built-in palette, as an index into `PALETTES`
+    pub palette: Option<usize>,
+    /// The color of the pixels which are on
+    pub foreground: Option<Color>,
+    /// The color of the pixels which are off
+    pub background: Option<Color>,
+    /// Whether the CRT filter is initially applied
+    pub crt: Option<bool>,
+    /// Whether the display is initially scaled by whole numbers only
+    pub integer_scaling: Option<bool>,
+    /// The number of 60Hz frames a pixel takes to fade out
+    pub phosphor: Option<u32>,
+}
+
+// --- setting definition -----------------------------------------------------
+
+/// A setting changed from within the application, to be written back to the
+/// configuration file
+#[derive(Clone, Copy)]
+pub(crate) enum Setting {
+    /// The built-in palette, as an index into `PALETTES`
+    Palette(usize),
+    /// The number of instructions executed per second
+    CpuSpeed(u32),
+    /// The volume of the buzzer, from 0 to 1
+    Volume(f32),
+    /// A quirk, by its name in the `[quirks]` table, and whether it is
+    /// enabled
+    Quirk(&'static str, bool),
+    /// An accessibility option, by its name in the `[accessibility]` table,
+    /// and whether it is enabled
+    Accessibility(&'static str, bool),
+    /// The keyboard keys of the keypad, indexed by the key on the keypad
+    Keys([KeyboardKey; NUM_KEYPAD_KEYS]),
+    /// The layout of the keyboard keys of the keypad
+    Layout(Layout),
+}
+
+impl Setting {
+    /// Checks whether two changes are of the same setting, so that the later
+    /// one replaces the earlier one
+    ///
+    /// # Params
+    ///
+    /// - `other` - The other change
+    pub fn is_same_as(&self, other: &Setting) -> bool {
+        match (self, other) {
+            (Setting::Quirk(name, _), Setting::Quirk(other, _)) => name == other,
+            (Setting::Accessibility(name, _), Setting::Accessibility(other, _)) => name == other,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+// --- config file definition -------------------------------------------------
+
+/// The layout of the configuration file, before validation
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    /// The `[window]` table
+    window: WindowSection,
+    /// The `[colors]` table
+    colors: ColorsSection,
+    /// The `[keys]` table, mapping keypad keys (`0` to `f`) to key names,
+    /// along with the name of the layout they are bound on top of
+    keys: BTreeMap<String, String>,
+    /// The `[side_keys]` table, mapping the keypad keys of the ROM run side
+    /// by side to key names
+    side_keys: BTreeMap<String, String>,
+    /// The `[player2_keys]` table, mapping the keypad keys of the second
+    /// player to key names
+    player2_keys: BTreeMap<String, String>,
+    /// The `[gamepad]` table, mapping keypad keys (`0` to `f`) to button names
+    gamepad: BTreeMap<String, String>,
+    /// The `[audio]` table
+    audio: AudioSection,
+    /// The `[cpu]` table
+    cpu: CpuSection,
+    /// The `[quirks]` table
+    quirks: Quirks,
+    /// The `[accessibility]` table
+    accessibility: Accessibility,
+    /// The `[themes]` table, mapping the SHA-1 of a ROM or the file name of
+    /// the ROM file to its display settings
+    themes: BTreeMap<String, ThemeSection>,
+}
+
+/// The `[window]` table of the configuration file
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WindowSection {
+    /// The width of the window
+    width: Option<i32>,
+    /// The height of the window
+    height: Option<i32>,
+    /// Whether the CRT filter is initially applied
+    crt: Option<bool>,
+    /// Whether the display is initially scaled by whole numbers only
+    integer_scaling: Option<bool>,
+    /// The number of frames the pixels take to fade out
+    phosphor: Option<u32>,
+    /// The size of the square of a pixel in screenshots
+    screenshot_scale: Option<u32>,
+}
+
+/// The `[colors]` table of the configuration file
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ColorsSection {
+    /// The name of the built-in palette, overridden by the colors below
+    palette: Option<String>,
+    /// The color of the pixels which are on, as `#RRGGBB`
+    foreground: Option<String>,
+    /// The color of the pixels which are off, as `#RRGGBB`
+    background: Option<String>,
+}
+
+/// A table of the `[themes]` table of the configuration file, such as
+/// `[themes."invaders.ch8"]`
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeSection {
+    /// The name of the built-in palette, overridden by the colors below
+    palette: Option<String>,
+    /// The color of the pixels which are on, as `#RRGGBB`
+    foreground: Option<String>,
+    /// The color of the pixels which are off, as `#RRGGBB`
+    background: Option<String>,
+    /// Whether the CRT filter is initially applied
+    crt: Option<bool>,
+    /// Whether the display is initially scaled by whole numbers only
+    integer_scaling: Option<bool>,
+    /// The number of frames the pixels take to fade out
+    phosphor: Option<u32>,
+}
+
+impl ThemeSection {
+    /// Parses the display settings of the table
+    ///
+    /// # Errors
+    ///
+    /// If the palette is unknown or a color is invalid
+    fn parse(&self) -> Result<Theme> {
+        let palette = self
+            .palette
+            .as_ref()
+            .map(|name| {
+                find_palette(name)
+                    .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown palette: {name}")))
+            })
+            .transpose()?;
+
+        Ok(Theme {
+            palette,
+            foreground: self.foreground.as_deref().map(parse_color).transpose()?,
+            background: self.background.as_deref().map(parse_color).transpose()?,
+            crt: self.crt,
+            integer_scaling: self.integer_scaling,
+            phosphor: self.phosphor,
+        })
+    }
+}
+
+/// The `[audio]` table of the configuration file
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AudioSection {
+    /// Whether the buzzer sounds
+    enabled: Option<bool>,
+    /// The volume of the buzzer, from 0 to 1
+    volume: Option<f32>,
+    /// The shape of the tone, being `square`, `sine` or `triangle`
+    waveform: Option<String>,
+    /// The frequency of the tone in Hz
+    frequency: Option<f32>,
+    /// The fraction of a period the square wave is high for
+    duty_cycle: Option<f32>,
+}
+
+/// The `[cpu]` table of the configuration file
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CpuSection {
+    /// The number of instructions executed per second
+    speed: Option<u32>,
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Writes settings changed from within the application back to a
+/// configuration file, creating the file if it doesn't exist yet
+///
+/// The rest of the file is kept as it was, along with its comments. A
+/// palette replaces the colors of the file, which would take precedence
+/// over it.
+///
+/// # Params
+///
+/// - `file` - The filepath to the configuration file
+/// - `settings` - The changed settings
+///
+/// # Errors
+///
+/// - If the configuration file could not be read or written
+/// - If the configuration file is invalid
+pub(crate) fn save_settings(file: &str, settings: &[Setting]) -> Result<()> {
+    let failed = || Keet8Error::FailedToSaveConfig(file.to_string());
+    let text = match std::fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(_) => return Err(failed()),
+    };
+    let mut doc: DocumentMut =
+        text.parse().map_err(|e: toml_edit::TomlError| Keet8Error::InvalidConfig(e.to_string()))?;
+
+    for setting in settings {
+        match *setting {
+            Setting::Palette(index) => {
+                let colors = config_table(&mut doc, "colors")?;
+                colors.insert("palette", value(PALETTES[index].name));
+                colors.remove("foreground");
+                colors.remove("background");
+            }
+            Setting::CpuSpeed(speed) => {
+                config_table(&mut doc, "cpu")?.insert("speed", value(i64::from(speed)));
+            }
+            Setting::Volume(volume) => {
+                // The volume goes in steps, which `f32` doesn't hit exactly
+                let volume = (f64::from(volume) * 100.0).round() / 100.0;
+                config_table(&mut doc, "audio")?.insert("volume", value(volume));
+            }
+            Setting::Quirk(name, enabled) => {
+                config_table(&mut doc, "quirks")?.insert(name, value(enabled));
+            }
+            Setting::Accessibility(name, enabled) => {
+                config_table(&mut doc, "accessibility")?.insert(name, value(enabled));
+            }
+            Setting::Layout(layout) => {
+                // The keys bound on top would no longer follow the layout
+                let table = config_table(&mut doc, "keys")?;
+                table.clear();
+                table.insert(LAYOUT_KEY, value(layout.name()));
+            }
+            Setting::Keys(keys) => {
+                let table = config_table(&mut doc, "keys")?;
+                for (index, &key) in keys.iter().enumerate() {
+                    if let Some(name) = key_name(key) {
+                        table.insert(&format!("{index:x}"), value(name));
+                    }
+                }
+            }
+        }
+    }
+
+    std::fs::write(file, doc.to_string()).map_err(|_| failed())
+}
+
+/// Gets a table of a configuration file being edited, adding it if the file
+/// doesn't have it yet
+///
+/// # Params
+///
+/// - `doc` - The contents of the configuration file
+/// - `name` - The name of the table
+///
+/// # Errors
+///
+/// If the file has something other than a table under that name
+fn config_table<'a>(doc: &'a mut DocumentMut, name: &str) -> Result<&'a mut dyn TableLike> {
+    doc.entry(name)
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| Keet8Error::InvalidConfig(format!("{name} is not a table")))
+}
+
+/// Gets the filepath to the configuration file for the options, being the one
+/// given with `--config`, otherwise `keet8.toml` in the working directory if
+/// it exists
+///
+/// # Params
+///
+/// - `options` - The options parsed from the command-line arguments
+pub(crate) fn config_file(options: &Options) -> Option<&str> {
+    match &options.config_file {
+        Some(file) => Some(file.as_str()),
+        None => Path::new(DEFAULT_CONFIG_FILE)
+            .exists()
+            .then_some(DEFAULT_CONFIG_FILE),
+    }
+}
+
+/// Parses a color written as `#RRGGBB`, or as `RRGGBB` which needs no
+/// quoting on the command line
+///
+/// # Params
+///
+/// - `text` - The textual form of the color
+///
+/// # Errors
+///
+/// If the color isn't written as `#RRGGBB` or `RRGGBB`
+fn parse_color(text: &str) -> Result<Color> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    Some(hex)
+        .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(|rgb| Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255))
+        .ok_or_else(|| Keet8Error::InvalidConfig(format!("invalid color: {text}")))
+}
+
+/// Parses a keypad key written as a hexadecimal digit (`0` to `f`)
+///
+/// # Params
+///
+/// - `key` - The textual form of the keypad key
+///
+/// # Errors
+///
+/// If the key isn't on the keypad
+fn parse_keypad_key(key: &str) -> Result<usize> {
+    usize::from_str_radix(key, 16)
+        .ok()
+        .filter(|&index| index < NUM_KEYPAD_KEYS)
+        .ok_or_else(|| Keet8Error::InvalidConfig(format!("unknown keypad key: {key}")))
+}
@@ -0,0 +1,262 @@
+use crate::debugger::BreakCondition;
+use crate::options::{parse_address, WatchTarget};
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of bytes `mem` writes when given no length
+const DEFAULT_MEM_LEN: u16 = 16;
+/// The number of bytes `mem` writes per line
+const BYTES_PER_LINE: usize = 16;
+
+// --- command definition -----------------------------------------------------
+
+/// A command typed into the console
+pub(crate) enum Command {
+    /// Assembles an instruction and writes it into memory
+    /// (`patch <addr> "<instruction>"`)
+    Patch {
+        /// The address to write the instruction to
+        addr: u16,
+        /// The instruction to assemble
+        instruction: String,
+    },
+    /// Sets a breakpoint, or removes the one already set (`break <addr>`)
+    Break {
+        /// The address of the breakpoint
+        addr: u16,
+    },
+    /// Sets a break condition, or removes the identical one already set
+    /// (`break-if <condition>`)
+    BreakIf {
+        /// The break condition
+        condition: BreakCondition,
+    },
+    /// Watches a memory address or `V` register, or stops watching it
+    /// (`watch <addr>` or `watch vX`)
+    Watch {
+        /// The location to watch
+        target: WatchTarget,
+    },
+    /// Compares two save state slots (`diff <slot> <slot>`)
+    Diff {
+        /// The slot compared from
+        from: u8,
+        /// The slot compared to
+        to: u8,
+    },
+    /// Writes what the instruction last stepped, or the frame last run,
+    /// changed (`changes`)
+    Changes,
+    /// Executes a single instruction, pausing the ROM if it runs (`step`)
+    Step,
+    /// Resumes the ROM (`continue`)
+    Continue,
+    /// Pauses the ROM (`pause`)
+    Pause,
+    /// Writes the registers, timers and call stack depth (`regs`)
+    Regs,
+    /// Writes the bytes of a range of memory (`mem <addr> [<len>]`)
+    Mem {
+        /// The address of the first byte
+        addr: u16,
+        /// The number of bytes
+        len: u16,
+    },
+    /// Writes a byte into memory (`poke <addr> <byte>`)
+    Poke {
+        /// The address of the byte
+        addr: u16,
+        /// The byte to write
+        value: u8,
+    },
+    /// Writes a JSON dump of the machine state (`dump [<file>]`)
+    Dump {
+        /// The filepath of the dump, next to the ROM if not given
+        path: Option<String>,
+    },
+}
+
+impl Command {
+    /// Parses a command from a line typed into the console
+    ///
+    /// # Params
+    ///
+    /// - `line` - The line to parse
+    ///
+    /// # Errors
+    ///
+    /// If the command is unknown or its arguments are invalid
+    pub fn parse(line: &str) -> Result<Self> {
+        let invalid = || Keet8Error::InvalidCommand(line.trim().to_string());
+
+        let line = line.trim();
+        let (name, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match name {
+            "step" | "s" if args.is_empty() => Ok(Self::Step),
+            "continue" | "c" if args.is_empty() => Ok(Self::Continue),
+            "pause" if args.is_empty() => Ok(Self::Pause),
+            "regs" if args.is_empty() => Ok(Self::Regs),
+            "changes" if args.is_empty() => Ok(Self::Changes),
+            "mem" => {
+                let mut args = args.split_whitespace();
+                let addr = args.next().and_then(parse_address).ok_or_else(invalid)?;
+                let len = match args.next() {
+                    Some(len) => len.parse().map_err(|_| invalid())?,
+                    None => DEFAULT_MEM_LEN,
+                };
+
+                match args.next() {
+                    Some(_) => Err(invalid()),
+                    None => Ok(Self::Mem { addr, len }),
+                }
+            }
+            "poke" => {
+                let (addr, value) =
+                    args.trim().split_once(char::is_whitespace).ok_or_else(invalid)?;
+                let addr = parse_address(addr).ok_or_else(invalid)?;
+                let value = parse_byte(value.trim()).ok_or_else(invalid)?;
+
+                Ok(Self::Poke { addr, value })
+            }
+            "patch" => {
+                let (addr, instruction) =
+                    args.trim().split_once(char::is_whitespace).ok_or_else(invalid)?;
+                let addr = parse_address(addr).ok_or_else(invalid)?;
+
+                let instruction = instruction.trim().trim_matches('"').to_string();
+                Ok(Self::Patch { addr, instruction })
+            }
+            "dump" => {
+                let path = Some(args.trim()).filter(|path| !path.is_empty());
+                Ok(Self::Dump { path: path.map(str::to_string) })
+            }
+            "break" => {
+                let addr = parse_address(args.trim()).ok_or_else(invalid)?;
+                Ok(Self::Break { addr })
+            }
+            "break-if" => {
+                let condition = BreakCondition::parse(args.trim()).ok_or_else(invalid)?;
+                Ok(Self::BreakIf { condition })
+            }
+            "watch" => {
+                let target = WatchTarget::parse(args.trim()).ok_or_else(invalid)?;
+                Ok(Self::Watch { target })
+            }
+            "diff" => {
+                let slots = args
+                    .split_whitespace()
+                    .map(|slot| slot.parse().ok())
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(invalid)?;
+
+                match slots.as_slice() {
+                    &[from, to] => Ok(Self::Diff { from, to }),
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// --- console definition -----------------------------------------------------
+
+/// The debugger console, reading commands from stdin whilst the emulator runs
+pub(crate) struct Console {
+    /// The receiving end of the lines read from stdin
+    lines: Receiver<String>,
+}
+
+impl Console {
+    /// Starts reading lines from stdin on a thread of its own, so the window
+    /// keeps running whilst waiting for input
+    pub fn open() -> Self {
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { lines }
+    }
+
+    /// Gets the commands typed since the last call, skipping empty lines
+    pub fn commands(&self) -> impl Iterator<Item = Result<Command>> + '_ {
+        self.lines
+            .try_iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Command::parse(&line))
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Writes the registers, timers and call stack depth of the emulator to
+/// stdout, for `regs`
+///
+/// # Params
+///
+/// - `emulator` - The emulator
+pub(crate) fn print_registers(emulator: &Emulator) {
+    println!(
+        "PC={:04X} I={:04X} SP={}/{} DT={:02X} ST={:02X}",
+        emulator.program_counter(),
+        emulator.index(),
+        emulator.call_stack().len(),
+        emulator.stack_limit(),
+        emulator.delay_timer(),
+        emulator.sound_timer(),
+    );
+
+    let registers = (0..16)
+        .map(|x| format!("V{x:X}={:02X}", emulator.register(x)))
+        .collect::<Vec<String>>();
+    println!("{}", registers[..8].join(" "));
+    println!("{}", registers[8..].join(" "));
+}
+
+/// Writes a range of memory to stdout as rows of bytes, for `mem`
+///
+/// The address wraps around past the end of memory, and the range is cut off
+/// there
+///
+/// # Params
+///
+/// - `emulator` - The emulator
+/// - `addr` - The address of the first byte
+/// - `len` - The number of bytes
+pub(crate) fn print_memory(emulator: &Emulator, addr: u16, len: u16) {
+    let memory = emulator.memory();
+    let start = addr as usize % memory.len();
+    let end = (start + len as usize).min(memory.len());
+
+    for (row, bytes) in memory[start..end].chunks(BYTES_PER_LINE).enumerate() {
+        let bytes = bytes.iter().map(|byte| format!("{byte:02X}")).collect::<Vec<String>>();
+        println!("{:#06X}: {}", start + row * BYTES_PER_LINE, bytes.join(" "));
+    }
+}
+
+/// Parses a byte written in hexadecimal with a `0x` prefix, or in decimal
+///
+/// # Params
+///
+/// - `text` - The textual form of the byte
+fn parse_byte(text: &str) -> Option<u8> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
@@ -0,0 +1,65 @@
+use crate::prelude::*;
+use crate::symbols::Symbols;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::Emulator;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of `V` registers dumped per line
+const REGISTERS_PER_LINE: usize = 8;
+
+// --- crash report interface -------------------------------------------------
+
+/// Formats the report of an error which stopped the ROM: the error, a dump of
+/// the registers, the call stack and the instructions last executed, with the
+/// addresses rendered as labels when symbols are loaded
+///
+/// # Params
+///
+/// - `emulator` - The emulator the error was raised by
+/// - `symbols` - The symbols of the ROM
+/// - `error` - The error which stopped the ROM
+pub(crate) fn crash_report(emulator: &Emulator, symbols: &Symbols, error: &Keet8Error) -> String {
+    let mut lines = vec![format!("Error: {}", error.message()), "Registers:".to_string()];
+
+    for row in (0..16).step_by(REGISTERS_PER_LINE) {
+        let registers = (row..row + REGISTERS_PER_LINE)
+            .map(|x| format!("V{x:X}={:02X}", emulator.register(x)))
+            .collect::<Vec<String>>();
+
+        lines.push(format!("    {}", registers.join(" ")));
+    }
+    lines.push(format!(
+        "    I={:03X} PC={:03X} SP={}/{} DT={:02X} ST={:02X}",
+        emulator.index(),
+        emulator.program_counter(),
+        emulator.call_stack().len(),
+        emulator.stack_limit(),
+        emulator.delay_timer(),
+        emulator.sound_timer()
+    ));
+
+    // The last instruction executed is the one which raised the error, as
+    // the program counter has already moved past it
+    let pc = emulator
+        .history()
+        .last()
+        .map_or(emulator.program_counter().wrapping_sub(2), |(pc, _)| pc);
+    lines.push("Stack trace (most recent call first):".to_string());
+    lines.push(format!("    at {}", symbols.symbolicate(pc)));
+
+    // The call stack holds return addresses, so the calls were made from the
+    // instructions right before them
+    for &addr in emulator.call_stack().iter().rev() {
+        lines.push(format!("    called from {}", symbols.symbolicate(addr.wrapping_sub(2))));
+    }
+
+    lines.push("Last instructions (oldest first):".to_string());
+    for (pc, raw) in emulator.history() {
+        let line = format!("    {}: {raw:04X}  {}", symbols.symbolicate(pc), OpCode::from(raw));
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,250 @@
+use keet8_core::Emulator;
+
+use std::fmt::Display;
+
+// --- condition definition ---------------------------------------------------
+
+/// A comparison between a part of the emulator state and a constant
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Condition {
+    /// The part of the emulator state to compare
+    operand: Operand,
+    /// The comparison to perform (`==`, `!=`, `<`, `<=`, `>` or `>=`)
+    op: &'static str,
+    /// The constant to compare against
+    value: u16,
+}
+
+impl Condition {
+    /// The supported comparisons, with the longer ones first so that they
+    /// take precedence when splitting
+    const OPS: [&'static str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+    /// Parses a condition such as `v0 == 0x03`
+    ///
+    /// # Params
+    ///
+    /// - `text` - The textual form of the condition
+    pub fn parse(text: &str) -> Option<Self> {
+        let op = Self::OPS.into_iter().find(|op| text.contains(op))?;
+        let (operand, value) = text.split_once(op)?;
+
+        Some(Self {
+            operand: Operand::parse(operand.trim())?,
+            op,
+            value: parse_number(value.trim())?,
+        })
+    }
+
+    /// Checks whether the condition holds for the current state of the
+    /// emulator
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to check the condition against
+    pub fn holds(&self, emulator: &Emulator) -> bool {
+        self.compares(self.operand.value(emulator))
+    }
+
+    /// Checks whether the condition holds for a value of its operand
+    ///
+    /// # Params
+    ///
+    /// - `lhs` - The value of the operand
+    fn compares(&self, lhs: u16) -> bool {
+        match self.op {
+            "==" => lhs == self.value,
+            "!=" => lhs != self.value,
+            "<=" => lhs <= self.value,
+            ">=" => lhs >= self.value,
+            "<" => lhs < self.value,
+            _ => lhs > self.value,
+        }
+    }
+}
+
+impl Display for Condition {
+    /// Writes the condition in its textual form
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} 0x{:x}", self.operand, self.op, self.value)
+    }
+}
+
+// --- break condition definition ---------------------------------------------
+
+/// A condition checked after every instruction, pausing execution when it
+/// fires
+///
+/// Break conditions are written as `<operand> <op> <value>`, e.g.
+/// `v5 == 0x20`, which fires when the comparison starts to hold, or as
+/// `<operand> crosses <value>`, e.g. `i crosses 0x300`, which fires when the
+/// operand moves from below the value to it or above, or back.
+#[derive(Clone)]
+pub(crate) struct BreakCondition {
+    /// What makes the condition fire
+    kind: BreakKind,
+    /// The value of the operand when the condition was last checked, if it
+    /// was checked yet
+    last: Option<u16>,
+}
+
+/// What makes a break condition fire
+#[derive(Clone)]
+enum BreakKind {
+    /// The comparison starts to hold
+    Holds(Condition),
+    /// The operand moves across the value
+    Crosses(Operand, u16),
+}
+
+impl BreakCondition {
+    /// Parses a break condition from its textual form
+    ///
+    /// # Params
+    ///
+    /// - `text` - The textual form of the break condition
+    pub fn parse(text: &str) -> Option<Self> {
+        let kind = match text.split_once(" crosses ") {
+            Some((operand, value)) => BreakKind::Crosses(
+                Operand::parse(operand.trim())?,
+                parse_number(value.trim())?,
+            ),
+            None => BreakKind::Holds(Condition::parse(text)?),
+        };
+
+        Some(Self { kind, last: None })
+    }
+
+    /// Checks the condition against the state of the emulator after an
+    /// instruction, returning whether it fired
+    ///
+    /// A comparison which already holds when first checked fires right away,
+    /// whereas crossing needs a value to cross from.
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator which executed the instruction
+    pub fn check(&mut self, emulator: &Emulator) -> bool {
+        let operand = match &self.kind {
+            BreakKind::Holds(condition) => condition.operand,
+            BreakKind::Crosses(operand, _) => *operand,
+        };
+
+        let value = operand.value(emulator);
+        let last = self.last.replace(value);
+        match &self.kind {
+            BreakKind::Holds(condition) => {
+                condition.compares(value) && !last.is_some_and(|last| condition.compares(last))
+            }
+            BreakKind::Crosses(_, threshold) => {
+                last.is_some_and(|last| (last < *threshold) != (value < *threshold))
+            }
+        }
+    }
+}
+
+impl Display for BreakCondition {
+    /// Writes the break condition in its textual form
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            BreakKind::Holds(condition) => write!(f, "{condition}"),
+            BreakKind::Crosses(operand, value) => write!(f, "{operand} crosses 0x{value:x}"),
+        }
+    }
+}
+
+// --- operand definition -----------------------------------------------------
+
+/// A part of the emulator state which can be referred to by a tracepoint or
+/// a break condition
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operand {
+    /// A `V` register
+    V(usize),
+    /// The index register
+    Index,
+    /// The program counter
+    ProgramCounter,
+    /// The delay timer
+    DelayTimer,
+    /// The sound timer
+    SoundTimer,
+}
+
+impl Operand {
+    /// Parses an operand such as `v3` or `pc`
+    ///
+    /// # Params
+    ///
+    /// - `text` - The name of the operand
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_ascii_lowercase().as_str() {
+            "i" => Some(Self::Index),
+            "pc" => Some(Self::ProgramCounter),
+            "dt" => Some(Self::DelayTimer),
+            "st" => Some(Self::SoundTimer),
+            name => {
+                let x = name.strip_prefix('v')?;
+                (x.len() == 1)
+                    .then(|| usize::from_str_radix(x, 16).ok())
+                    .flatten()
+                    .map(Self::V)
+            }
+        }
+    }
+
+    /// Gets the current value of the operand
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to read the value from
+    fn value(self, emulator: &Emulator) -> u16 {
+        match self {
+            Self::V(x) => emulator.register(x) as u16,
+            Self::Index => emulator.index(),
+            Self::ProgramCounter => emulator.program_counter(),
+            Self::DelayTimer => emulator.delay_timer() as u16,
+            Self::SoundTimer => emulator.sound_timer() as u16,
+        }
+    }
+
+    /// Formats the current value of the operand in hexadecimal
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to read the value from
+    pub fn format(self, emulator: &Emulator) -> String {
+        match self {
+            Self::Index | Self::ProgramCounter => format!("{:04x}", self.value(emulator)),
+            _ => format!("{:02x}", self.value(emulator)),
+        }
+    }
+}
+
+impl Display for Operand {
+    /// Writes the name of the operand
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V(x) => write!(f, "v{x:x}"),
+            Self::Index => write!(f, "i"),
+            Self::ProgramCounter => write!(f, "pc"),
+            Self::DelayTimer => write!(f, "dt"),
+            Self::SoundTimer => write!(f, "st"),
+        }
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Parses a number, which is hexadecimal when prefixed with `0x` and decimal
+/// otherwise
+///
+/// # Params
+///
+/// - `text` - The textual form of the number
+pub(crate) fn parse_number(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
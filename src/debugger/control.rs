@@ -0,0 +1,55 @@
+// --- execution control definition -------------------------------------------
+
+/// Controls whether the emulator is allowed to execute instructions, allowing
+/// it to be paused, single-stepped and resumed
+#[derive(Default)]
+pub(crate) struct ExecutionControl {
+    /// Flag indicating whether execution is paused
+    paused: bool,
+    /// Flag indicating whether a single instruction is to be executed while
+    /// paused
+    step_requested: bool,
+}
+
+impl ExecutionControl {
+    /// Checks whether execution is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Pauses execution, or resumes it if it was already paused
+    pub fn toggle(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Pauses execution, such as when a breakpoint is hit
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes execution, such as when an attached debugger continues
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.step_requested = false;
+    }
+
+    /// Requests a single instruction to be executed, pausing execution if it
+    /// wasn't paused yet
+    pub fn request_step(&mut self) {
+        if self.paused {
+            self.step_requested = true;
+        } else {
+            self.paused = true;
+        }
+    }
+
+    /// Decides whether the next instruction may be executed, which whilst
+    /// paused is only when a single step was requested
+    pub fn may_step(&mut self) -> bool {
+        if self.paused {
+            return std::mem::take(&mut self.step_requested);
+        }
+
+        true
+    }
+}
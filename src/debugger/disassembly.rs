@@ -0,0 +1,93 @@
+use crate::overlay::Overlay;
+use crate::symbols::Symbols;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::Emulator;
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of instructions shown before the one at `PC`
+const INSTRUCTIONS_BEFORE: u16 = 4;
+/// The number of instructions shown after the one at `PC`
+const INSTRUCTIONS_AFTER: u16 = 8;
+/// The space in pixels around the text of the view
+const PADDING: i32 = 4;
+
+/// The color of the instruction at `PC`
+const PC_COLOR: Color = Color::YELLOW;
+/// The color of the instructions with a breakpoint
+const BREAKPOINT_COLOR: Color = Color::RED;
+/// The color of the labels of the symbols
+const LABEL_COLOR: Color = Color::GRAY;
+
+// --- disassembly view interface ---------------------------------------------
+
+/// Draws the instructions around `PC` with its top-left corner at the given
+/// position, decoded from memory as it is now so that self-modifying code
+/// shows what will actually run
+///
+/// The view follows `PC` as the ROM runs, with the instruction at it marked
+/// and highlighted, the instructions with a breakpoint marked in red and the
+/// labels of the symbols above the instructions they name.
+///
+/// # Params
+///
+/// - `overlay` - The overlay to draw the instructions on
+/// - `emulator` - The emulator to disassemble the memory of
+/// - `symbols` - The symbols to label the instructions with
+/// - `x` - The x-coordinate of the view
+/// - `y` - The y-coordinate of the view
+pub(crate) fn draw_disassembly(
+    overlay: &mut Overlay,
+    emulator: &Emulator,
+    symbols: &Symbols,
+    x: i32,
+    y: i32,
+) {
+    let pc = (emulator.program_counter() as usize % emulator.memory_size()) as u16;
+    let last_addr = (emulator.memory_size() - 2) as u16;
+    let breakpoints = emulator.breakpoints().collect::<Vec<u16>>();
+
+    // Instructions are two bytes long, so the ones before `PC` are decoded
+    // from two bytes apart as well
+    let first = pc.saturating_sub(2 * INSTRUCTIONS_BEFORE);
+    let last = pc.saturating_add(2 * INSTRUCTIONS_AFTER).min(last_addr);
+
+    let mut lines = Vec::new();
+    for addr in (first..=last).step_by(2) {
+        if let Some(label) = symbols.label(addr) {
+            lines.push((format!("{label}:"), LABEL_COLOR));
+        }
+
+        let raw = emulator.fetch(addr);
+        let marker = if addr == pc { '>' } else { ' ' };
+        let line = format!("{marker} {addr:#05X}: {raw:04X}  {}", OpCode::from(raw));
+        let color = if addr == pc {
+            PC_COLOR
+        } else if breakpoints.contains(&addr) {
+            BREAKPOINT_COLOR
+        } else {
+            Color::RAYWHITE
+        };
+
+        lines.push((line.trim_end().to_string(), color));
+    }
+
+    let size = overlay.font_size(10);
+    let line_height = size + PADDING;
+    let width = lines
+        .iter()
+        .map(|(line, _)| overlay.measure(line, size))
+        .max()
+        .unwrap_or(0);
+
+    let background = overlay.background();
+    let height = lines.len() as i32 * line_height + PADDING;
+    overlay.rect(x, y, width + 2 * PADDING, height, background);
+
+    for (i, (line, color)) in lines.iter().enumerate() {
+        let line_y = y + PADDING + i as i32 * line_height;
+        overlay.text(line, x + PADDING, line_y, size, *color);
+    }
+}
@@ -0,0 +1,113 @@
+// --- constants --------------------------------------------------------------
+
+/// The number of bytes per row of the hex view
+const BYTES_PER_ROW: i32 = 16;
+/// The number of `V` registers
+const NUM_REGISTERS: i32 = 16;
+/// The number of `V` registers per line of the register view
+const REGISTERS_PER_LINE: i32 = 8;
+
+// --- value editor definition ------------------------------------------------
+
+/// A byte edited in the debug overlay
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EditTarget {
+    /// A byte of memory in the hex view
+    Memory(u16),
+    /// A `V` register in the register view
+    Register(usize),
+}
+
+/// Edits the bytes of memory and the `V` registers in the debug overlay
+/// whilst the ROM is paused, as typed in hexadecimal
+///
+/// A byte is written once both of its digits are typed, and the editor moves
+/// on to the next one, as in a hex editor.
+pub(crate) struct ValueEditor {
+    /// The byte being edited
+    target: EditTarget,
+    /// The high digit typed so far, if any
+    digit: Option<u8>,
+}
+
+impl ValueEditor {
+    /// Starts editing a byte, with none of its digits typed yet
+    ///
+    /// # Params
+    ///
+    /// - `target` - The byte to edit
+    pub fn new(target: EditTarget) -> Self {
+        Self { target, digit: None }
+    }
+
+    /// Gets the byte being edited
+    pub fn target(&self) -> EditTarget {
+        self.target
+    }
+
+    /// Types a hexadecimal digit, returning the byte to write if it is the
+    /// second digit of the byte
+    ///
+    /// Other characters are ignored.
+    ///
+    /// # Params
+    ///
+    /// - `c` - The character typed
+    pub fn type_char(&mut self, c: char) -> Option<u8> {
+        let digit = c.to_digit(16)? as u8;
+
+        match self.digit.take() {
+            Some(high) => Some(high << 4 | digit),
+            None => {
+                self.digit = Some(digit);
+                None
+            }
+        }
+    }
+
+    /// Forgets the digit typed so far
+    pub fn erase(&mut self) {
+        self.digit = None;
+    }
+
+    /// Moves on to another byte, wrapping around the memory space or the
+    /// registers and forgetting the digit typed so far
+    ///
+    /// # Params
+    ///
+    /// - `offset` - The number of bytes to move forward, or back if negative
+    /// - `memory_size` - The number of bytes of memory
+    pub fn advance(&mut self, offset: i32, memory_size: usize) {
+        self.digit = None;
+        self.target = match self.target {
+            EditTarget::Memory(addr) => {
+                let addr = (addr as i32 + offset).rem_euclid(memory_size as i32);
+                EditTarget::Memory(addr as u16)
+            }
+            EditTarget::Register(x) => {
+                EditTarget::Register((x as i32 + offset).rem_euclid(NUM_REGISTERS) as usize)
+            }
+        };
+    }
+
+    /// Gets the number of bytes between a byte and the one shown below it
+    pub fn row_len(&self) -> i32 {
+        match self.target {
+            EditTarget::Memory(_) => BYTES_PER_ROW,
+            EditTarget::Register(_) => REGISTERS_PER_LINE,
+        }
+    }
+
+    /// Gets the text shown in place of the byte being edited, being the digit
+    /// typed so far followed by an underscore, or the byte itself
+    ///
+    /// # Params
+    ///
+    /// - `value` - The current value of the byte
+    pub fn text(&self, value: u8) -> String {
+        match self.digit {
+            Some(digit) => format!("{digit:X}_"),
+            None => format!("{value:02X}"),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::overlay::{Overlay, OVERLAY_BUDGET};
+
+use raylib::prelude::*;
+
+use std::collections::VecDeque;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of frames shown in the graph
+const NUM_SAMPLES: usize = 120;
+
+/// The width in pixels of a single frame in the graph
+const SAMPLE_WIDTH: i32 = 2;
+/// The height in pixels of the graph
+const GRAPH_HEIGHT: i32 = 80;
+/// The frame time in seconds at the top of the graph (two 60Hz frames)
+const GRAPH_MAX_TIME: f32 = 2.0 / 60.0;
+/// The frame time in seconds of the reference line (one 60Hz frame)
+const TARGET_FRAME_TIME: f32 = 1.0 / 60.0;
+/// The time in seconds the instructions per second are measured over
+const IPS_WINDOW: f32 = 1.0;
+/// The space in pixels between the lines of text below the graph
+const LINE_SPACING: i32 = 4;
+
+// --- frame graph definition -------------------------------------------------
+
+/// A scrolling graph of the most recent host frame times, split into the
+/// time spent stepping the emulator, drawing, and waiting for the display,
+/// used to correlate stutters with their cause
+#[derive(Default)]
+pub(crate) struct FrameGraph {
+    /// The times in seconds of the most recent frames, oldest first
+    frame_times: VecDeque<f32>,
+    /// The times in seconds spent stepping the emulator in those frames
+    step_times: VecDeque<f32>,
+    /// The times in seconds spent drawing those frames
+    render_times: VecDeque<f32>,
+    /// The number of instructions executed in those frames
+    steps: VecDeque<u32>,
+    /// The time in seconds spent drawing the last frame, recorded along with
+    /// the next frame
+    render_time: f32,
+}
+
+impl FrameGraph {
+    /// Records the times of a frame, dropping the oldest frame once the graph
+    /// is full
+    ///
+    /// # Params
+    ///
+    /// - `frame_time` - The time in seconds the whole frame took
+    /// - `step_time` - The time in seconds spent stepping the emulator
+    /// - `steps` - The number of instructions executed
+    pub fn record(&mut self, frame_time: f32, step_time: f32, steps: u32) {
+        if self.frame_times.len() == NUM_SAMPLES {
+            self.frame_times.pop_front();
+            self.step_times.pop_front();
+            self.render_times.pop_front();
+            self.steps.pop_front();
+        }
+
+        self.frame_times.push_back(frame_time);
+        self.step_times.push_back(step_time);
+        self.render_times.push_back(self.render_time);
+        self.steps.push_back(steps);
+    }
+
+    /// Records the time spent drawing a frame, which doesn't include
+    /// presenting it and waiting for the display
+    ///
+    /// # Params
+    ///
+    /// - `render_time` - The time in seconds spent drawing the frame
+    pub fn record_render(&mut self, render_time: f32) {
+        self.render_time = render_time;
+    }
+
+    /// Gets the number of instructions executed per second over the most
+    /// recent frames
+    fn instructions_per_second(&self) -> f32 {
+        let mut time = 0.0;
+        let mut steps = 0;
+        for (&frame_time, &frame_steps) in self.frame_times.iter().zip(&self.steps).rev() {
+            if time >= IPS_WINDOW {
+                break;
+            }
+
+            time += frame_time;
+            steps += frame_steps;
+        }
+
+        if time > 0.0 {
+            steps as f32 / time
+        } else {
+            0.0
+        }
+    }
+
+    /// Draws the graph with its top-left corner at the given position
+    ///
+    /// Frame times are drawn in green, with step times in red and the render
+    /// times in blue on top of them, along with a reference line at the
+    /// duration of a 60Hz frame. Below the graph are written the breakdown of
+    /// the last frame, with the rest of it spent waiting for the display, the
+    /// instructions executed per second against the CPU speed, and the time
+    /// the overlay took to draw in the previous frame, in red when it
+    /// exceeded its budget.
+    ///
+    /// Returns the height in pixels of the graph and its text, for drawing
+    /// below it
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the graph on
+    /// - `x` - The x-coordinate of the graph
+    /// - `y` - The y-coordinate of the graph
+    /// - `cpu_speed` - The number of instructions to execute per second
+    pub fn draw(&self, overlay: &mut Overlay, x: i32, y: i32, cpu_speed: u32) -> i32 {
+        let width = NUM_SAMPLES as i32 * SAMPLE_WIDTH;
+        let bottom = y + GRAPH_HEIGHT;
+        let height_of = |time: f32| {
+            ((time / GRAPH_MAX_TIME).min(1.0) * GRAPH_HEIGHT as f32) as i32
+        };
+
+        let background = overlay.background();
+        overlay.rect(x, y, width, GRAPH_HEIGHT, background);
+
+        let samples = self.frame_times.iter().zip(&self.step_times).zip(&self.render_times);
+        for (i, ((&frame_time, &step_time), &render_time)) in samples.enumerate() {
+            let sample_x = x + i as i32 * SAMPLE_WIDTH;
+
+            let frame_height = height_of(frame_time);
+            overlay.rect(sample_x, bottom - frame_height, SAMPLE_WIDTH, frame_height, Color::GREEN);
+
+            let step_height = height_of(step_time);
+            overlay.rect(sample_x, bottom - step_height, SAMPLE_WIDTH, step_height, Color::RED);
+
+            let render_height = height_of(step_time + render_time) - step_height;
+            let render_y = bottom - step_height - render_height;
+            overlay.rect(sample_x, render_y, SAMPLE_WIDTH, render_height, Color::SKYBLUE);
+        }
+
+        let target_y = bottom - height_of(TARGET_FRAME_TIME);
+        overlay.rect(x, target_y, width, 1, Color::LIGHTGRAY);
+        overlay.rect_lines(x, y, width, GRAPH_HEIGHT, Color::GRAY);
+
+        let size = overlay.font_size(10);
+        if let Some(frame_time) = self.frame_times.back() {
+            let text = format!("{:.2}ms", frame_time * 1000.0);
+            overlay.text(&text, x + 4, y + 4, size, Color::RAYWHITE);
+        }
+
+        let mut text_y = bottom + LINE_SPACING;
+        let last = (self.frame_times.back(), self.step_times.back(), self.render_times.back());
+        if let (Some(&frame_time), Some(&step_time), Some(&render_time)) = last {
+            let wait_time = (frame_time - step_time - render_time).max(0.0);
+            let text = format!(
+                "emu {:.2}ms render {:.2}ms wait {:.2}ms",
+                step_time * 1000.0,
+                render_time * 1000.0,
+                wait_time * 1000.0,
+            );
+            overlay.text(&text, x, text_y, size, Color::RAYWHITE);
+            text_y += size + LINE_SPACING;
+        }
+
+        // Running below speed shows the host can't keep up with the CPU
+        let ips = self.instructions_per_second();
+        let text = format!("{ips:.0} IPS ({:.2}x)", ips / cpu_speed.max(1) as f32);
+        overlay.text(&text, x, text_y, size, Color::RAYWHITE);
+        text_y += size + LINE_SPACING;
+
+        let flush_time = overlay.flush_time();
+        let color = if flush_time > OVERLAY_BUDGET {
+            Color::RED
+        } else {
+            Color::RAYWHITE
+        };
+
+        let text = format!("overlay {:.3}ms", flush_time * 1000.0);
+        overlay.text(&text, x, text_y, size, color);
+
+        text_y + size - y
+    }
+}
@@ -0,0 +1,434 @@
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+use log::info;
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// --- constants --------------------------------------------------------------
+
+/// The largest packet accepted from the debugger, as announced to it
+const PACKET_SIZE: usize = 0x1000;
+/// The byte a debugger sends to interrupt the running target
+const INTERRUPT: u8 = 0x03;
+/// The signal reported when the target stops at a breakpoint or after a step
+const SIGTRAP: u8 = 5;
+/// The signal reported when the debugger interrupted the target
+const SIGINT: u8 = 2;
+
+/// The number of the `I` register, following `V0` to `VF`
+const REG_I: usize = 16;
+/// The number of the program counter
+const REG_PC: usize = 17;
+/// The number of the delay timer
+const REG_DT: usize = 18;
+/// The number of the sound timer
+const REG_ST: usize = 19;
+
+// --- gdb action definition --------------------------------------------------
+
+/// What the debugger asked the execution of the emulator to do
+pub(crate) enum GdbAction {
+    /// Pauses execution, when the debugger attaches or interrupts
+    Pause,
+    /// Executes a single instruction
+    Step,
+    /// Resumes execution until a breakpoint or watchpoint is hit, or the
+    /// debugger detached
+    Resume,
+}
+
+// --- gdb stub definition ----------------------------------------------------
+
+/// A stub speaking the GDB remote serial protocol on a TCP port, so that
+/// debuggers and other tools can attach to the emulator
+///
+/// The registers are numbered `V0` to `VF` (0 to 15), `I` (16), `PC` (17),
+/// `DT` (18) and `ST` (19), with `I` and `PC` in big-endian like the memory.
+/// The stub is polled every frame, so it never blocks the window.
+pub(crate) struct GdbStub {
+    /// The socket accepting the debugger
+    listener: TcpListener,
+    /// The connection to the debugger, if one is attached
+    client: Option<TcpStream>,
+    /// The bytes received which don't form a whole packet yet
+    received: Vec<u8>,
+    /// Flag indicating whether the debugger waits for the emulator to stop
+    /// after a step or while resumed
+    waiting: bool,
+    /// Flag indicating whether the debugger interrupted the emulator
+    interrupted: bool,
+}
+
+impl GdbStub {
+    /// Listens for a debugger on a TCP port
+    ///
+    /// # Params
+    ///
+    /// - `port` - The TCP port to listen on
+    ///
+    /// # Errors
+    ///
+    /// If the port could not be listened on
+    pub fn open(port: u16) -> Result<Self> {
+        let failed = |_| Keet8Error::FailedToOpenGdbStub(port);
+
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(failed)?;
+        listener.set_nonblocking(true).map_err(failed)?;
+
+        Ok(Self {
+            listener,
+            client: None,
+            received: Vec::new(),
+            waiting: false,
+            interrupted: false,
+        })
+    }
+
+    /// Checks whether the debugger waits for the emulator to stop
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Tells the debugger the emulator stopped, after a step, a breakpoint,
+    /// a watchpoint or an interrupt
+    pub fn report_stop(&mut self) {
+        let signal = if self.interrupted { SIGINT } else { SIGTRAP };
+        self.waiting = false;
+        self.interrupted = false;
+        self.send(&format!("S{signal:02x}"));
+    }
+
+    /// Accepts a debugger and handles the packets it sent since the last
+    /// call, answering the ones which inspect or change the emulator
+    ///
+    /// Returns what the debugger asked the execution to do, in order
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator being debugged
+    pub fn poll(&mut self, emulator: &mut Emulator) -> Vec<GdbAction> {
+        let mut actions = Vec::new();
+
+        if self.client.is_none() {
+            let Ok((stream, peer)) = self.listener.accept() else {
+                return actions;
+            };
+            if stream.set_nonblocking(true).is_err() {
+                return actions;
+            }
+
+            info!("Debugger attached from {peer}");
+            self.client = Some(stream);
+            self.received.clear();
+            actions.push(GdbAction::Pause);
+        }
+
+        if !self.receive() {
+            info!("Debugger detached");
+            self.detach();
+            actions.push(GdbAction::Resume);
+            return actions;
+        }
+
+        while let Some(packet) = self.next_packet() {
+            match packet {
+                Packet::Interrupt => {
+                    self.interrupted = self.waiting;
+                    actions.push(GdbAction::Pause);
+                }
+                Packet::Data(data) => {
+                    if let Some(action) = self.handle(&data, emulator) {
+                        actions.push(action);
+                    }
+                }
+            }
+        }
+
+        actions
+    }
+
+    /// Reads the bytes the debugger sent, returning `false` if it
+    /// disconnected
+    fn receive(&mut self) -> bool {
+        let Some(client) = &mut self.client else {
+            return true;
+        };
+
+        let mut buffer = [0; 512];
+        loop {
+            match client.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(len) => self.received.extend_from_slice(&buffer[..len]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(_) => return false,
+            }
+        }
+    }
+
+    /// Takes the next whole packet out of the bytes received, acknowledging
+    /// it, and skips the acknowledgements sent by the debugger
+    fn next_packet(&mut self) -> Option<Packet> {
+        loop {
+            match *self.received.first()? {
+                INTERRUPT => {
+                    self.received.remove(0);
+                    return Some(Packet::Interrupt);
+                }
+                b'$' => break,
+                _ => {
+                    self.received.remove(0);
+                }
+            }
+        }
+
+        // A packet is `$<data>#<checksum>`, with the checksum being two hex
+        // digits
+        let end = self.received.iter().position(|&b| b == b'#')?;
+        if self.received.len() < end + 3 {
+            return None;
+        }
+
+        let packet = self.received.drain(..end + 3).collect::<Vec<u8>>();
+        let data = &packet[1..end];
+        let checksum = std::str::from_utf8(&packet[end + 1..])
+            .ok()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        if checksum != Some(checksum_of(data)) {
+            self.write(b"-");
+            return self.next_packet();
+        }
+
+        self.write(b"+");
+        Some(Packet::Data(String::from_utf8_lossy(data).into_owned()))
+    }
+
+    /// Handles a packet, answering it unless it resumes execution
+    ///
+    /// # Params
+    ///
+    /// - `data` - The data of the packet
+    /// - `emulator` - The emulator being debugged
+    fn handle(&mut self, data: &str, emulator: &mut Emulator) -> Option<GdbAction> {
+        let mut chars = data.chars();
+        let command = chars.next();
+        let args = chars.as_str();
+
+        let reply = match command {
+            Some('?') => format!("S{SIGTRAP:02x}"),
+            Some('g') => (0..=REG_ST).map(|n| read_register(emulator, n)).collect(),
+            Some('p') => match usize::from_str_radix(args, 16) {
+                Ok(n) if n <= REG_ST => read_register(emulator, n),
+                _ => "E01".to_string(),
+            },
+            Some('P') => write_register(emulator, args).unwrap_or_else(|| "E01".to_string()),
+            Some('m') => read_memory(emulator, args).unwrap_or_else(|| "E01".to_string()),
+            Some('M') => write_memory(emulator, args).unwrap_or_else(|| "E01".to_string()),
+            Some('Z') => set_point(emulator, true, args).unwrap_or_default(),
+            Some('z') => set_point(emulator, false, args).unwrap_or_default(),
+            Some('s') => {
+                self.waiting = true;
+                return Some(GdbAction::Step);
+            }
+            Some('c') => {
+                self.waiting = true;
+                return Some(GdbAction::Resume);
+            }
+            Some('D') => {
+                self.send("OK");
+                info!("Debugger detached");
+                self.detach();
+                return Some(GdbAction::Resume);
+            }
+            Some('k') => {
+                self.detach();
+                return Some(GdbAction::Resume);
+            }
+            Some('H' | 'T') => "OK".to_string(),
+            Some('q') if args.starts_with("Supported") => format!("PacketSize={PACKET_SIZE:x}"),
+            Some('q') if args.starts_with("Attached") => "1".to_string(),
+            // Anything else is unsupported, which an empty reply tells
+            _ => String::new(),
+        };
+
+        self.send(&reply);
+        None
+    }
+
+    /// Sends a packet to the debugger
+    ///
+    /// # Params
+    ///
+    /// - `data` - The data of the packet
+    fn send(&mut self, data: &str) {
+        let packet = format!("${data}#{:02x}", checksum_of(data.as_bytes()));
+        self.write(packet.as_bytes());
+    }
+
+    /// Writes bytes to the debugger, dropping it if the connection broke
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The bytes to write
+    fn write(&mut self, bytes: &[u8]) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+
+        if client.write_all(bytes).is_err() {
+            self.detach();
+        }
+    }
+
+    /// Forgets the debugger
+    fn detach(&mut self) {
+        self.client = None;
+        self.received.clear();
+        self.waiting = false;
+        self.interrupted = false;
+    }
+}
+
+/// A packet received from the debugger
+enum Packet {
+    /// The debugger interrupted the running target
+    Interrupt,
+    /// A command, with the framing and checksum removed
+    Data(String),
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Computes the checksum of the data of a packet, being the sum of its bytes
+///
+/// # Params
+///
+/// - `data` - The data of the packet
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Formats a register in hexadecimal, as sent to the debugger
+///
+/// # Params
+///
+/// - `emulator` - The emulator being debugged
+/// - `n` - The number of the register
+fn read_register(emulator: &Emulator, n: usize) -> String {
+    match n {
+        REG_I => format!("{:04x}", emulator.index()),
+        REG_PC => format!("{:04x}", emulator.program_counter()),
+        REG_DT => format!("{:02x}", emulator.delay_timer()),
+        REG_ST => format!("{:02x}", emulator.sound_timer()),
+        x => format!("{:02x}", emulator.register(x)),
+    }
+}
+
+/// Handles `P<n>=<value>`, writing a `V` register, the only ones the
+/// emulator lets be changed
+///
+/// # Params
+///
+/// - `emulator` - The emulator being debugged
+/// - `args` - The arguments of the packet
+fn write_register(emulator: &mut Emulator, args: &str) -> Option<String> {
+    let (n, value) = args.split_once('=')?;
+    let x = usize::from_str_radix(n, 16).ok().filter(|&x| x < REG_I)?;
+    let value = u8::from_str_radix(value, 16).ok()?;
+
+    emulator.set_register(x, value);
+    Some("OK".to_string())
+}
+
+/// Handles `m<addr>,<len>`, reading memory up to its end and as much as a
+/// packet holds
+///
+/// # Params
+///
+/// - `emulator` - The emulator being debugged
+/// - `args` - The arguments of the packet
+fn read_memory(emulator: &Emulator, args: &str) -> Option<String> {
+    let memory = emulator.memory();
+    let (addr, len) = parse_range(args, memory.len())?;
+    let end = (addr + len.min(PACKET_SIZE / 2)).min(memory.len());
+
+    Some(memory[addr..end].iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Handles `M<addr>,<len>:<bytes>`, writing memory like a patch
+///
+/// # Params
+///
+/// - `emulator` - The emulator being debugged
+/// - `args` - The arguments of the packet
+fn write_memory(emulator: &mut Emulator, args: &str) -> Option<String> {
+    let (range, hex) = args.split_once(':')?;
+    let (addr, len) = parse_range(range, emulator.memory_size())?;
+    if hex.len() != len * 2 || addr + len > emulator.memory().len() {
+        return None;
+    }
+
+    let bytes = (0..len)
+        .map(|i| u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        emulator.poke((addr + i) as u16, byte);
+    }
+
+    Some("OK".to_string())
+}
+
+/// Handles `Z<type>,<addr>,<kind>` and `z<type>,<addr>,<kind>`, setting or
+/// removing a breakpoint (types 0 and 1) or a watchpoint on the memory it
+/// covers (types 2 to 4, which all watch both reads and writes)
+///
+/// Returns `None` for the types which aren't supported
+///
+/// # Params
+///
+/// - `emulator` - The emulator being debugged
+/// - `set` - Flag indicating whether the point is set rather than removed
+/// - `args` - The arguments of the packet
+fn set_point(emulator: &mut Emulator, set: bool, args: &str) -> Option<String> {
+    let (kind, range) = args.split_once(',')?;
+    let (addr, len) = parse_range(range, emulator.memory_size())?;
+    let addr = addr as u16;
+
+    match (kind, set) {
+        ("0" | "1", true) => emulator.add_breakpoint(addr),
+        ("0" | "1", false) => {
+            emulator.remove_breakpoint(addr);
+        }
+        ("2" | "3" | "4", true) => {
+            for offset in 0..len.max(1) as u16 {
+                emulator.add_watchpoint(addr.wrapping_add(offset));
+            }
+        }
+        ("2" | "3" | "4", false) => {
+            for offset in 0..len.max(1) as u16 {
+                emulator.remove_watchpoint(addr.wrapping_add(offset));
+            }
+        }
+        _ => return None,
+    }
+
+    Some("OK".to_string())
+}
+
+/// Parses `<addr>,<len>` in hexadecimal, with the address and length within
+/// memory
+///
+/// # Params
+///
+/// - `args` - The range to parse
+/// - `memory_size` - The number of bytes of memory
+fn parse_range(args: &str, memory_size: usize) -> Option<(usize, usize)> {
+    let (addr, len) = args.split_once(',')?;
+    let addr = usize::from_str_radix(addr, 16).ok().filter(|&addr| addr < memory_size)?;
+    let len = usize::from_str_radix(len, 16).ok().filter(|&len| len <= memory_size)?;
+
+    Some((addr, len))
+}
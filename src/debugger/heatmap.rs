@@ -0,0 +1,109 @@
+use crate::overlay::Overlay;
+
+use keet8_core::{AccessCounts, Emulator};
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of cells per row, making the 4KB memory space square
+const CELLS_PER_ROW: usize = 64;
+/// The number of cells, each being an address of the 4KB memory space or a
+/// block of addresses of a larger one
+const NUM_CELLS: usize = CELLS_PER_ROW * CELLS_PER_ROW;
+/// The size in pixels of the cell of an address
+const CELL_SIZE: i32 = 3;
+/// The space in pixels around the heatmap
+const PADDING: i32 = 4;
+/// The color of the addresses which were never accessed
+const UNTOUCHED_COLOR: Color = Color::new(24, 24, 24, 255);
+
+// --- heatmap interface ------------------------------------------------------
+
+/// Draws the accesses of every address of memory since the ROM started as a
+/// square of cells, with its top-right corner at the given position
+///
+/// The addresses run left to right and top to bottom, 64 to a row. A memory
+/// larger than 4KB is shown as blocks of addresses, such as 16 to a cell for
+/// the 64KB of XO-CHIP. The writes of a cell tint it red, the reads green and
+/// the executions blue, each brighter the more often it happened relative to
+/// the busiest cell, so code shows up blue, sprites green and variables
+/// yellow or red.
+///
+/// Returns the height in pixels of the heatmap, for drawing below it
+///
+/// # Params
+///
+/// - `overlay` - The overlay to draw the heatmap on
+/// - `emulator` - The emulator to show the accesses of, drawing nothing if it
+///   doesn't count them
+/// - `right` - The x-coordinate of the right edge of the heatmap
+/// - `y` - The y-coordinate of the heatmap
+pub(crate) fn draw_heatmap(overlay: &mut Overlay, emulator: &Emulator, right: i32, y: i32) -> i32 {
+    let Some(counts) = emulator.access_counts() else {
+        return 0;
+    };
+
+    let size = CELLS_PER_ROW as i32 * CELL_SIZE + 2 * PADDING;
+    let x = right - size;
+    let background = overlay.background();
+    overlay.rect(x, y, size, size, background);
+
+    let per_cell = (emulator.memory_size() / NUM_CELLS).max(1);
+    let cell_counts = |count: fn(&AccessCounts, u16) -> u32| {
+        (0..NUM_CELLS)
+            .map(|cell| {
+                let addrs = cell * per_cell..(cell + 1) * per_cell;
+                addrs.map(|addr| count(counts, addr as u16)).fold(0, u32::saturating_add)
+            })
+            .collect::<Vec<u32>>()
+    };
+    let (writes, reads, executes) = (
+        cell_counts(AccessCounts::writes),
+        cell_counts(AccessCounts::reads),
+        cell_counts(AccessCounts::executes),
+    );
+    let max = |counts: &[u32]| counts.iter().copied().max().unwrap_or(0);
+    let (max_writes, max_reads, max_executes) = (max(&writes), max(&reads), max(&executes));
+
+    for cell in 0..NUM_CELLS {
+        let (writes, reads, executes) = (writes[cell], reads[cell], executes[cell]);
+
+        let color = if writes == 0 && reads == 0 && executes == 0 {
+            UNTOUCHED_COLOR
+        } else {
+            Color::new(
+                intensity(writes, max_writes),
+                intensity(reads, max_reads),
+                intensity(executes, max_executes),
+                255,
+            )
+        };
+
+        let col = (cell % CELLS_PER_ROW) as i32;
+        let row = (cell / CELLS_PER_ROW) as i32;
+        let cell_x = x + PADDING + col * CELL_SIZE;
+        let cell_y = y + PADDING + row * CELL_SIZE;
+        overlay.rect(cell_x, cell_y, CELL_SIZE, CELL_SIZE, color);
+    }
+
+    size
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Scales an access count to the brightness of a color channel, on a
+/// logarithmic scale so that addresses accessed a few times still show
+///
+/// # Params
+///
+/// - `count` - The number of accesses of the address
+/// - `max` - The largest number of accesses of any address
+fn intensity(count: u32, max: u32) -> u8 {
+    if count == 0 {
+        return 0;
+    }
+
+    // Anything accessed at all is kept visible against the background
+    let scale = (count as f32).ln_1p() / (max as f32).ln_1p();
+    (64.0 + scale * 191.0) as u8
+}
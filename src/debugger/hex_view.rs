@@ -0,0 +1,214 @@
+use crate::debugger::editor::{EditTarget, ValueEditor};
+use crate::overlay::Overlay;
+
+use keet8_core::{Emulator, FONT_ADDR, FONT_END, MEMORY_SIZE};
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of bytes shown per row
+const BYTES_PER_ROW: usize = 16;
+/// The number of rows shown at once
+const VISIBLE_ROWS: usize = 16;
+/// The space in pixels around the text of the view
+const PADDING: i32 = 4;
+
+/// The color of the bytes of the instruction at `PC`
+const PC_COLOR: Color = Color::YELLOW;
+/// The color of the byte at `I`
+const INDEX_COLOR: Color = Color::SKYBLUE;
+/// The color of the bytes of the fonts
+const FONT_COLOR: Color = Color::GRAY;
+/// The color behind the byte being edited
+pub(crate) const EDIT_COLOR: Color = Color::ORANGE;
+
+// --- hex view definition ----------------------------------------------------
+
+/// A scrollable hex dump of the memory of the emulator, highlighting the
+/// instruction at `PC`, the byte at `I` and the fonts
+pub(crate) struct HexView {
+    /// The first row shown, each row holding `BYTES_PER_ROW` bytes
+    top: usize,
+    /// The number of rows in the whole memory space, as of the last draw
+    num_rows: usize,
+    /// Where the bytes were last drawn, for finding the byte clicked on
+    grid: Option<Grid>,
+}
+
+/// Where the bytes of the view are drawn
+#[derive(Clone, Copy)]
+struct Grid {
+    /// The x-coordinate of the first byte of a row
+    x: i32,
+    /// The y-coordinate of the first row
+    y: i32,
+    /// The width of the cell of a byte
+    cell_width: i32,
+    /// The height of a row
+    line_height: i32,
+}
+
+impl Default for HexView {
+    /// Creates the view scrolled to the start of memory
+    fn default() -> Self {
+        Self {
+            top: 0,
+            num_rows: MEMORY_SIZE / BYTES_PER_ROW,
+            grid: None,
+        }
+    }
+}
+
+impl HexView {
+    /// Scrolls the view by a number of rows, stopping at either end of the
+    /// memory space
+    ///
+    /// # Params
+    ///
+    /// - `rows` - The number of rows to scroll down, or up if negative
+    pub fn scroll(&mut self, rows: i32) {
+        let top = self.top as i32 + rows;
+        self.top = top.clamp(0, (self.num_rows - VISIBLE_ROWS) as i32) as usize;
+    }
+
+    /// Scrolls the view so that an address is shown in the top row
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to show
+    pub fn show(&mut self, addr: u16) {
+        self.top = 0;
+        self.scroll(self.row_of(addr) as i32);
+    }
+
+    /// Scrolls the view as little as needed for an address to be shown
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to show
+    pub fn reveal(&mut self, addr: u16) {
+        let row = self.row_of(addr);
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + VISIBLE_ROWS {
+            self.top = row + 1 - VISIBLE_ROWS;
+        }
+    }
+
+    /// Gets the address of the byte drawn at a position, if any
+    ///
+    /// # Params
+    ///
+    /// - `x` - The x-coordinate of the position
+    /// - `y` - The y-coordinate of the position
+    pub fn address_at(&self, x: i32, y: i32) -> Option<u16> {
+        let grid = self.grid?;
+        if x < grid.x || y < grid.y {
+            return None;
+        }
+
+        let col = ((x - grid.x) / grid.cell_width) as usize;
+        let row = ((y - grid.y) / grid.line_height) as usize;
+        if col >= BYTES_PER_ROW || row >= VISIBLE_ROWS {
+            return None;
+        }
+
+        Some(((self.top + row) * BYTES_PER_ROW + col) as u16)
+    }
+
+    /// Draws the view with its top-right corner at the given position, so it
+    /// can be placed against the right edge of the window
+    ///
+    /// Returns the height in pixels of the view, for drawing below it
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the view on
+    /// - `emulator` - The emulator to show the memory of
+    /// - `editor` - The editor of the byte being edited, if any
+    /// - `right` - The x-coordinate of the right edge of the view
+    /// - `y` - The y-coordinate of the view
+    pub fn draw(
+        &mut self,
+        overlay: &mut Overlay,
+        emulator: &Emulator,
+        editor: Option<&ValueEditor>,
+        right: i32,
+        y: i32,
+    ) -> i32 {
+        let memory = emulator.memory();
+        let pc = emulator.program_counter() as usize % memory.len();
+        let index = emulator.index() as usize % memory.len();
+        let fonts = FONT_ADDR as usize..FONT_END as usize;
+
+        // The memory may have been resized since the last draw
+        self.num_rows = memory.len() / BYTES_PER_ROW;
+        self.scroll(0);
+
+        let size = overlay.font_size(10);
+        let line_height = size + PADDING;
+
+        // The default font isn't monospaced, so every byte gets a cell as
+        // wide as the widest one
+        let digits = if memory.len() > MEMORY_SIZE { 4 } else { 3 };
+        let label_width = overlay.measure(&format!("0x{}: ", "0".repeat(digits)), size);
+        let cell_width = overlay.measure("DD ", size);
+        let width = label_width + BYTES_PER_ROW as i32 * cell_width + PADDING;
+        let height = VISIBLE_ROWS as i32 * line_height + PADDING;
+
+        let x = right - width;
+        let background = overlay.background();
+        overlay.rect(x, y, width, height, background);
+
+        self.grid = Some(Grid {
+            x: x + PADDING + label_width,
+            y: y + PADDING,
+            cell_width,
+            line_height,
+        });
+        let edited = editor.and_then(|editor| match editor.target() {
+            EditTarget::Memory(addr) => Some((addr as usize, editor)),
+            EditTarget::Register(_) => None,
+        });
+
+        for row in 0..VISIBLE_ROWS {
+            let start = (self.top + row) * BYTES_PER_ROW;
+            let row_y = y + PADDING + row as i32 * line_height;
+            let label = format!("{start:#0width$X}:", width = digits + 2);
+            overlay.text(&label, x + PADDING, row_y, size, Color::RAYWHITE);
+
+            for (i, byte) in memory[start..start + BYTES_PER_ROW].iter().enumerate() {
+                let addr = start + i;
+                let color = if addr == pc || addr == pc + 1 {
+                    PC_COLOR
+                } else if addr == index {
+                    INDEX_COLOR
+                } else if fonts.contains(&addr) {
+                    FONT_COLOR
+                } else {
+                    Color::RAYWHITE
+                };
+
+                let byte_x = x + PADDING + label_width + i as i32 * cell_width;
+                match edited {
+                    Some((edited, editor)) if edited == addr => {
+                        overlay.rect(byte_x - 1, row_y - 1, cell_width - 2, size + 2, EDIT_COLOR);
+                        overlay.text(&editor.text(*byte), byte_x, row_y, size, Color::BLACK);
+                    }
+                    _ => overlay.text(&format!("{byte:02X}"), byte_x, row_y, size, color),
+                }
+            }
+        }
+
+        height
+    }
+
+    /// Gets the row an address is shown in
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address
+    fn row_of(&self, addr: u16) -> usize {
+        addr as usize % (self.num_rows * BYTES_PER_ROW) / BYTES_PER_ROW
+    }
+}
@@ -0,0 +1,36 @@
+//! This module, `debugger`, contains the debugging tools of the frontend.
+//!
+//! This includes the control over the execution of the emulator (pausing,
+//! single-stepping and resuming), the frame-time graph, register view,
+//! disassembly view, memory hex view, memory access heatmap and sprite preview
+//! of the debug overlay, the editing of memory and registers in those views and the
+//! debugging setup of a session (breakpoints, watchpoints, break conditions,
+//! tracepoints and watch expressions), which is persisted per ROM in its
+//! sidecar. External debuggers attach through a stub speaking the GDB remote
+//! serial protocol.
+
+mod condition;
+mod control;
+mod disassembly;
+mod editor;
+mod frame_graph;
+mod gdb;
+mod heatmap;
+mod hex_view;
+mod registers;
+mod setup;
+mod sprite_preview;
+mod tracepoint;
+
+pub(crate) use condition::BreakCondition;
+pub(crate) use control::ExecutionControl;
+pub(crate) use disassembly::draw_disassembly;
+pub(crate) use editor::{EditTarget, ValueEditor};
+pub(crate) use frame_graph::FrameGraph;
+pub(crate) use gdb::{GdbAction, GdbStub};
+pub(crate) use heatmap::draw_heatmap;
+pub(crate) use hex_view::HexView;
+pub(crate) use registers::RegisterView;
+pub(crate) use setup::DebugSetup;
+pub(crate) use sprite_preview::SpritePreview;
+pub(crate) use tracepoint::Tracepoint;
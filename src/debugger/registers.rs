@@ -0,0 +1,136 @@
+use crate::debugger::editor::{EditTarget, ValueEditor};
+use crate::debugger::hex_view::EDIT_COLOR;
+use crate::overlay::Overlay;
+use crate::symbols::Symbols;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::Emulator;
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of `V` registers shown per line
+const REGISTERS_PER_LINE: usize = 8;
+/// The space in pixels around the text of the view
+const PADDING: i32 = 4;
+
+/// The number of lines above the `V` registers
+const HEADER_LINES: usize = 2;
+
+// --- register view definition -----------------------------------------------
+
+/// A view of the registers of the emulator: `PC` with the decoded instruction
+/// at it, `I`, the timers, the depth of the call stack out of its limit and
+/// the `V` registers, in hexadecimal
+#[derive(Default)]
+pub(crate) struct RegisterView {
+    /// Where the values of the `V` registers were last drawn, as the
+    /// x-coordinate, y-coordinate and width of each, for finding the
+    /// register clicked on
+    cells: Vec<(i32, i32, i32)>,
+    /// The height of a line
+    line_height: i32,
+}
+
+impl RegisterView {
+    /// Gets the `V` register whose value is drawn at a position, if any
+    ///
+    /// # Params
+    ///
+    /// - `x` - The x-coordinate of the position
+    /// - `y` - The y-coordinate of the position
+    pub fn register_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.cells.iter().position(|&(cell_x, cell_y, width)| {
+            (cell_x..cell_x + width).contains(&x)
+                && (cell_y..cell_y + self.line_height).contains(&y)
+        })
+    }
+
+    /// Draws the registers with the top-left corner of the view at the given
+    /// position
+    ///
+    /// Returns the height in pixels of the view, for drawing below it
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the registers on
+    /// - `emulator` - The emulator to show the registers of
+    /// - `symbols` - The symbols to render the program counter with
+    /// - `editor` - The editor of the byte being edited, if any
+    /// - `x` - The x-coordinate of the view
+    /// - `y` - The y-coordinate of the view
+    pub fn draw(
+        &mut self,
+        overlay: &mut Overlay,
+        emulator: &Emulator,
+        symbols: &Symbols,
+        editor: Option<&ValueEditor>,
+        x: i32,
+        y: i32,
+    ) -> i32 {
+        let pc = emulator.program_counter();
+        let opcode = OpCode::from(emulator.fetch(pc));
+
+        let mut lines = vec![
+            format!("PC {}: {opcode}", symbols.symbolicate(pc)),
+            format!(
+                "I  {:#05X}  DT {:02X}  ST {:02X}  SP {}/{}",
+                emulator.index(),
+                emulator.delay_timer(),
+                emulator.sound_timer(),
+                emulator.call_stack().len(),
+                emulator.stack_limit(),
+            ),
+        ];
+
+        for first in (0..16).step_by(REGISTERS_PER_LINE) {
+            let registers = (first..first + REGISTERS_PER_LINE)
+                .map(|x| format!("V{x:X} {:02X}", emulator.register(x)))
+                .collect::<Vec<String>>();
+
+            lines.push(registers.join("  "));
+        }
+
+        let size = overlay.font_size(10);
+        let line_height = size + PADDING;
+        let width = lines
+            .iter()
+            .map(|line| overlay.measure(line, size))
+            .max()
+            .unwrap_or(0);
+
+        let background = overlay.background();
+        let height = lines.len() as i32 * line_height + PADDING;
+        overlay.rect(x, y, width + 2 * PADDING, height, background);
+
+        for (i, line) in lines.iter().enumerate() {
+            let line_y = y + PADDING + i as i32 * line_height;
+            overlay.text(line, x + PADDING, line_y, size, Color::RAYWHITE);
+        }
+
+        // The values are found by measuring the text before them, as the
+        // default font isn't monospaced
+        self.line_height = line_height;
+        self.cells = (0..16)
+            .map(|reg| {
+                let line = HEADER_LINES + reg / REGISTERS_PER_LINE;
+                let start = (reg % REGISTERS_PER_LINE) * "VX 00  ".len() + "VX ".len();
+                let cell_x = x + PADDING + overlay.measure(&lines[line][..start], size);
+                let cell_y = y + PADDING + line as i32 * line_height;
+
+                (cell_x, cell_y, overlay.measure(&lines[line][start..start + 2], size))
+            })
+            .collect();
+
+        if let Some(editor) = editor {
+            if let EditTarget::Register(reg) = editor.target() {
+                let (cell_x, cell_y, width) = self.cells[reg];
+                let text = editor.text(emulator.register(reg));
+                overlay.rect(cell_x - 1, cell_y - 1, width + 2, size + 2, EDIT_COLOR);
+                overlay.text(&text, cell_x, cell_y, size, Color::BLACK);
+            }
+        }
+
+        height
+    }
+}
@@ -0,0 +1,181 @@
+use super::{BreakCondition, Tracepoint};
+
+use crate::options::{parse_register, WatchTarget};
+use crate::sidecar::Sidecar;
+
+// --- constants --------------------------------------------------------------
+
+/// The sidecar section holding the breakpoints
+const BREAKPOINTS_SECTION: &str = "breakpoints";
+/// The sidecar section holding the watchpoints
+const WATCHPOINTS_SECTION: &str = "watchpoints";
+/// The sidecar section holding the tracepoints
+const TRACEPOINTS_SECTION: &str = "tracepoints";
+/// The sidecar section holding the watch expressions
+const WATCHES_SECTION: &str = "watches";
+/// The sidecar section holding the break conditions
+const CONDITIONS_SECTION: &str = "conditions";
+
+// --- debug setup definition -------------------------------------------------
+
+/// The debugging setup of a session, persisted per ROM so that it survives
+/// restarting the emulator
+#[derive(Default)]
+pub(crate) struct DebugSetup {
+    /// The addresses at which execution is paused
+    pub breakpoints: Vec<u16>,
+    /// The memory addresses at which execution is paused when accessed
+    pub watchpoints: Vec<u16>,
+    /// The `V` registers at which execution is paused when changed, stored
+    /// along with the watched memory addresses as `vX`
+    pub register_watchpoints: Vec<usize>,
+    /// The addresses at which a message is logged without pausing
+    pub tracepoints: Vec<Tracepoint>,
+    /// The expressions whose values are watched
+    pub watches: Vec<String>,
+    /// The conditions at which execution is paused once they fire
+    pub conditions: Vec<BreakCondition>,
+}
+
+impl DebugSetup {
+    /// Loads the debugging setup from the sidecar of the ROM
+    ///
+    /// Addresses, registers, tracepoints and break conditions that can't be
+    /// parsed are skipped
+    ///
+    /// # Params
+    ///
+    /// - `sidecar` - The sidecar of the ROM
+    pub fn load(sidecar: &Sidecar) -> Self {
+        Self {
+            breakpoints: parse_addresses(sidecar.section(BREAKPOINTS_SECTION)),
+            watchpoints: parse_addresses(sidecar.section(WATCHPOINTS_SECTION)),
+            register_watchpoints: sidecar
+                .section(WATCHPOINTS_SECTION)
+                .iter()
+                .filter_map(|line| parse_register(line))
+                .collect(),
+            tracepoints: sidecar
+                .section(TRACEPOINTS_SECTION)
+                .iter()
+                .filter_map(|line| Tracepoint::parse(line))
+                .collect(),
+            watches: sidecar.section(WATCHES_SECTION).to_vec(),
+            conditions: sidecar
+                .section(CONDITIONS_SECTION)
+                .iter()
+                .filter_map(|line| BreakCondition::parse(line))
+                .collect(),
+        }
+    }
+
+    /// Stores the debugging setup in the sidecar of the ROM
+    ///
+    /// # Params
+    ///
+    /// - `sidecar` - The sidecar of the ROM
+    pub fn store(&self, sidecar: &mut Sidecar) {
+        sidecar.set_section(BREAKPOINTS_SECTION, format_addresses(&self.breakpoints));
+        let mut watchpoints = format_addresses(&self.watchpoints);
+        watchpoints.extend(self.register_watchpoints.iter().map(|x| format!("v{x:x}")));
+        sidecar.set_section(WATCHPOINTS_SECTION, watchpoints);
+        sidecar.set_section(
+            TRACEPOINTS_SECTION,
+            self.tracepoints.iter().map(Tracepoint::to_string).collect(),
+        );
+        sidecar.set_section(WATCHES_SECTION, self.watches.clone());
+        sidecar.set_section(
+            CONDITIONS_SECTION,
+            self.conditions.iter().map(BreakCondition::to_string).collect(),
+        );
+    }
+
+    /// Adds a breakpoint, unless one is already present at the address
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the breakpoint
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        if !self.breakpoints.contains(&addr) {
+            self.breakpoints.push(addr);
+        }
+    }
+
+    /// Adds a watchpoint, unless the location is already watched
+    ///
+    /// # Params
+    ///
+    /// - `target` - The memory address or `V` register to watch
+    pub fn add_watchpoint(&mut self, target: WatchTarget) {
+        match target {
+            WatchTarget::Memory(addr) if !self.watchpoints.contains(&addr) => {
+                self.watchpoints.push(addr);
+            }
+            WatchTarget::Register(x) if !self.register_watchpoints.contains(&x) => {
+                self.register_watchpoints.push(x);
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds a tracepoint, unless an identical one is already present
+    ///
+    /// # Params
+    ///
+    /// - `tracepoint` - The tracepoint to add
+    pub fn add_tracepoint(&mut self, tracepoint: Tracepoint) {
+        if !self.tracepoints.contains(&tracepoint) {
+            self.tracepoints.push(tracepoint);
+        }
+    }
+
+    /// Adds a break condition, unless an identical one is already present
+    ///
+    /// # Params
+    ///
+    /// - `condition` - The break condition to add
+    pub fn add_condition(&mut self, condition: BreakCondition) {
+        let text = condition.to_string();
+        if !self.conditions.iter().any(|other| other.to_string() == text) {
+            self.conditions.push(condition);
+        }
+    }
+
+    /// Removes a break condition, returning whether an identical one was
+    /// present
+    ///
+    /// # Params
+    ///
+    /// - `condition` - The break condition to remove
+    pub fn remove_condition(&mut self, condition: &BreakCondition) -> bool {
+        let text = condition.to_string();
+        let len = self.conditions.len();
+        self.conditions.retain(|other| other.to_string() != text);
+
+        self.conditions.len() != len
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Parses the hexadecimal addresses from the lines of a sidecar section
+///
+/// # Params
+///
+/// - `lines` - The lines of the section
+fn parse_addresses(lines: &[String]) -> Vec<u16> {
+    lines
+        .iter()
+        .filter(|line| !line.starts_with('v'))
+        .filter_map(|line| u16::from_str_radix(line.trim_start_matches("0x"), 16).ok())
+        .collect()
+}
+
+/// Formats the addresses as the lines of a sidecar section
+///
+/// # Params
+///
+/// - `addrs` - The addresses to format
+fn format_addresses(addrs: &[u16]) -> Vec<String> {
+    addrs.iter().map(|addr| format!("0x{addr:04x}")).collect()
+}
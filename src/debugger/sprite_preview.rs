@@ -0,0 +1,135 @@
+use crate::overlay::Overlay;
+
+use keet8_core::Emulator;
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of sprites shown at once
+const NUM_SPRITES: u16 = 16;
+/// The number of sprites shown per row
+const SPRITES_PER_ROW: u16 = 4;
+/// The number of rows a sprite has until another is chosen, a common size of
+/// the tiles of games
+const DEFAULT_HEIGHT: u8 = 8;
+/// The largest number of rows of a sprite, being the most `DRW` draws
+const MAX_HEIGHT: u8 = 15;
+/// The size in pixels of a pixel of a sprite
+const PIXEL_SIZE: i32 = 2;
+/// The space in pixels around the preview and between the sprites
+const PADDING: i32 = 4;
+
+/// The color of the pixels which are on
+const PIXEL_COLOR: Color = Color::RAYWHITE;
+/// The color behind a sprite
+const SPRITE_BACKGROUND: Color = Color::new(24, 24, 24, 255);
+/// The color of the outline of the sprite at `I`
+const INDEX_COLOR: Color = Color::SKYBLUE;
+
+// --- sprite preview definition ----------------------------------------------
+
+/// Shows a range of memory as 8-pixel wide sprites, for finding and
+/// inspecting the graphics of a ROM
+///
+/// The range starts at `I` so that the sprites about to be drawn are shown,
+/// unless it was pinned to an address of the hex view. Every sprite has the
+/// same number of rows, and follows the previous one in memory.
+pub(crate) struct SpritePreview {
+    /// The address of the first sprite, following `I` if not pinned
+    start: Option<u16>,
+    /// The number of rows of every sprite, being a byte each
+    height: u8,
+}
+
+impl Default for SpritePreview {
+    fn default() -> Self {
+        Self {
+            start: None,
+            height: DEFAULT_HEIGHT,
+        }
+    }
+}
+
+impl SpritePreview {
+    /// Starts the range at an address, or back at `I` if not given
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the first sprite, if any
+    pub fn pin(&mut self, addr: Option<u16>) {
+        self.start = addr;
+    }
+
+    /// Changes the number of rows of every sprite, staying between 1 and the
+    /// 15 rows `DRW` draws at most
+    ///
+    /// # Params
+    ///
+    /// - `rows` - The number of rows to add, or remove if negative
+    pub fn resize(&mut self, rows: i32) {
+        self.height = (self.height as i32 + rows).clamp(1, MAX_HEIGHT as i32) as u8;
+    }
+
+    /// Draws the preview with its top-right corner at the given position
+    ///
+    /// Returns the height in pixels of the preview, for drawing below it
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the preview on
+    /// - `emulator` - The emulator to show the memory of
+    /// - `right` - The x-coordinate of the right edge of the preview
+    /// - `y` - The y-coordinate of the preview
+    pub fn draw(&self, overlay: &mut Overlay, emulator: &Emulator, right: i32, y: i32) -> i32 {
+        let memory = emulator.memory();
+        let mask = (memory.len() - 1) as u16;
+        let index = emulator.index() & mask;
+        let start = self.start.map_or(index, |start| start & mask);
+        let height = self.height as u16;
+
+        let size = overlay.font_size(10);
+        let sprite_width = 8 * PIXEL_SIZE;
+        let sprite_height = height as i32 * PIXEL_SIZE;
+        let num_rows = (NUM_SPRITES / SPRITES_PER_ROW) as i32;
+        let width = SPRITES_PER_ROW as i32 * (sprite_width + PADDING) + PADDING;
+        let total_height = size + PADDING + num_rows * (sprite_height + PADDING) + PADDING;
+
+        let x = right - width;
+        let background = overlay.background();
+        overlay.rect(x, y, width, total_height, background);
+
+        let end = start.wrapping_add(NUM_SPRITES * height - 1) & mask;
+        let title = format!("{start:#05X}-{end:#05X} 8x{height}");
+        overlay.text(&title, x + PADDING, y + PADDING, size, Color::RAYWHITE);
+
+        for sprite in 0..NUM_SPRITES {
+            let addr = start.wrapping_add(sprite * height) & mask;
+            let col = (sprite % SPRITES_PER_ROW) as i32;
+            let row = (sprite / SPRITES_PER_ROW) as i32;
+            let sprite_x = x + PADDING + col * (sprite_width + PADDING);
+            let sprite_y = y + size + 2 * PADDING + row * (sprite_height + PADDING);
+            overlay.rect(sprite_x, sprite_y, sprite_width, sprite_height, SPRITE_BACKGROUND);
+
+            for r in 0..height {
+                let byte = memory[(addr.wrapping_add(r) & mask) as usize];
+                for c in (0..8).filter(|c| byte & (0x80 >> c) != 0) {
+                    let pixel_x = sprite_x + c * PIXEL_SIZE;
+                    let pixel_y = sprite_y + r as i32 * PIXEL_SIZE;
+                    overlay.rect(pixel_x, pixel_y, PIXEL_SIZE, PIXEL_SIZE, PIXEL_COLOR);
+                }
+            }
+
+            if addr == index {
+                overlay.rect_lines(
+                    sprite_x - 1,
+                    sprite_y - 1,
+                    sprite_width + 2,
+                    sprite_height + 2,
+                    INDEX_COLOR,
+                );
+            }
+        }
+
+        total_height
+    }
+}
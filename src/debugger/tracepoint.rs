@@ -0,0 +1,104 @@
+use super::condition::{parse_number, Condition, Operand};
+
+use keet8_core::Emulator;
+
+use std::fmt::Display;
+
+// --- tracepoint definition --------------------------------------------------
+
+/// An address which logs a message when executed, without pausing execution
+///
+/// Tracepoints are written as `<addr> [if <operand> <op> <value>:] <message>`,
+/// e.g. `0x0240 if v0 == 0x03: x={v0} y={v1}`. The message may refer to the
+/// state of the emulator with the placeholders `{v0}` to `{vf}`, `{i}`,
+/// `{pc}`, `{dt}` and `{st}`, which are substituted with hexadecimal values.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Tracepoint {
+    /// The address at which the message is logged
+    pub addr: u16,
+    /// The condition under which the message is logged, if any
+    condition: Option<Condition>,
+    /// The message to log, which may contain placeholders
+    message: String,
+}
+
+impl Tracepoint {
+    /// Parses a tracepoint from its textual form
+    ///
+    /// # Params
+    ///
+    /// - `text` - The textual form of the tracepoint
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (addr, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let addr = parse_number(addr)?;
+
+        let rest = rest.trim_start();
+        let (condition, message) = match rest.strip_prefix("if ") {
+            Some(rest) => {
+                let (condition, message) = rest.split_once(':')?;
+                (Some(Condition::parse(condition)?), message.trim_start())
+            }
+            None => (None, rest),
+        };
+
+        Some(Self {
+            addr,
+            condition,
+            message: message.to_string(),
+        })
+    }
+
+    /// Formats the message of the tracepoint with the current state of the
+    /// emulator, if its condition holds
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator about to execute the traced address
+    pub fn trace(&self, emulator: &Emulator) -> Option<String> {
+        if let Some(condition) = &self.condition {
+            if !condition.holds(emulator) {
+                return None;
+            }
+        }
+
+        let mut message = String::new();
+        let mut rest = self.message.as_str();
+
+        while let Some(start) = rest.find('{') {
+            message.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let substituted = rest.find('}').and_then(|end| {
+                let operand = Operand::parse(&rest[1..end])?;
+                Some((operand.format(emulator), end + 1))
+            });
+
+            match substituted {
+                Some((value, len)) => {
+                    message.push_str(&value);
+                    rest = &rest[len..];
+                }
+                None => {
+                    message.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        message.push_str(rest);
+        Some(message)
+    }
+}
+
+impl Display for Tracepoint {
+    /// Writes the tracepoint in its textual form
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:04x}", self.addr)?;
+        if let Some(condition) = &self.condition {
+            write!(f, " if {condition}:")?;
+        }
+
+        write!(f, " {}", self.message)
+    }
+}
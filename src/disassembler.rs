@@ -0,0 +1,461 @@
+use crate::analysis::{Analysis, ByteKind, RefKind};
+use crate::assembler::assemble;
+use crate::octo::assemble_octo;
+use crate::options::DisassemblyFormat;
+use crate::prelude::*;
+use crate::roms::read_rom;
+use crate::symbols::Symbols;
+
+use keet8_core::opcode::OpCode;
+use serde_json::json;
+
+use std::collections::BTreeMap;
+
+// --- constants --------------------------------------------------------------
+
+/// The maximum number of data bytes written on a single line
+const DATA_BYTES_PER_LINE: usize = 8;
+/// The width the instructions and data are padded to, lining up the comments
+/// holding their addresses
+const TEXT_WIDTH: usize = 24;
+
+// --- syntax definition ------------------------------------------------------
+
+/// The syntax a listing is written in
+#[derive(Clone, Copy)]
+enum Syntax {
+    /// The syntax of `assemble_program`
+    Assembler,
+    /// The dialect of Octo, as assembled by `assemble_octo`
+    Octo,
+}
+
+impl Syntax {
+    /// Gets the marker starting a comment
+    fn comment(self) -> &'static str {
+        match self {
+            Self::Assembler => ";",
+            Self::Octo => "#",
+        }
+    }
+
+    /// Renders the definition of a label
+    ///
+    /// # Params
+    ///
+    /// - `label` - The name of the label
+    fn label(self, label: &str) -> String {
+        match self {
+            Self::Assembler => format!("{label}:"),
+            Self::Octo => format!(": {label}"),
+        }
+    }
+
+    /// Renders a run of data bytes
+    ///
+    /// # Params
+    ///
+    /// - `bytes` - The data bytes
+    fn data(self, bytes: &[u8]) -> String {
+        let data = bytes.iter().map(|b| format!("0x{b:02x}"));
+        match self {
+            Self::Assembler => format!("db {}", data.collect::<Vec<String>>().join(", ")),
+            Self::Octo => data.collect::<Vec<String>>().join(" "),
+        }
+    }
+
+    /// Renders an instruction, with the address it refers to replaced by its
+    /// label if it has one
+    ///
+    /// # Params
+    ///
+    /// - `raw` - The raw opcode of the instruction
+    /// - `labels` - The labels of the ROM, keyed by their addresses
+    fn instruction(self, raw: u16, labels: &BTreeMap<u16, String>) -> String {
+        match self {
+            Self::Assembler => instruction_text(raw, labels),
+            Self::Octo => octo_instruction_text(raw, labels),
+        }
+    }
+}
+
+// --- disassembler interface -------------------------------------------------
+
+/// Disassembles a whole ROM and writes the listing to stdout
+///
+/// The listing is in the syntax of `assemble_program`, so it can be edited
+/// and assembled back into the ROM. Code is written one instruction per line,
+/// while data and unreferenced bytes are written as `db` lines, each followed
+/// by a comment with its address (and raw opcode). The addresses which are
+/// called, jumped to or loaded into the index register get a label, being the
+/// one from the symbols or else one generated from how they are referred to
+/// (`sub_0240`, `loop_0212`, `label_0230` or `data_0300`), which the
+/// instructions use instead of the raw address. Each label is written on its
+/// own line, after the cross-references to the address as comments, such as
+/// `; called from 0x0234`.
+///
+/// The listing can be written in the dialect of Octo instead, to be assembled
+/// by `assemble_octo`, with `: main` at the start of the ROM, skips written as
+/// `if ... then` and `#` comments, or as JSON for external tooling.
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM to disassemble
+/// - `symbols` - The symbols of the ROM, which may be empty
+/// - `format` - The format the listing is written in
+/// - `load_addr` - The address the ROM is loaded and starts at
+///
+/// # Errors
+///
+/// If there was an error when loading the ROM file
+pub(crate) fn disassemble(
+    rom_file: &str,
+    symbols: &Symbols,
+    format: DisassemblyFormat,
+    load_addr: u16,
+) -> Result<()> {
+    let bytes = read_rom(rom_file)?;
+    let analysis = Analysis::analyze(&bytes, load_addr);
+    let lines = split_lines(&bytes, &analysis, symbols);
+
+    // Only the addresses lines start at can be labelled, which leaves out
+    // the targets in the middle of an instruction
+    let labels = lines
+        .iter()
+        .filter_map(|&(addr, _)| Some((addr, label(&analysis, symbols, addr)?)))
+        .collect::<BTreeMap<u16, String>>();
+
+    match format {
+        DisassemblyFormat::Text => {
+            print_listing(Syntax::Assembler, &bytes, &analysis, &lines, &labels)
+        }
+        DisassemblyFormat::Octo => print_listing(Syntax::Octo, &bytes, &analysis, &lines, &labels),
+        DisassemblyFormat::Json => print_json(&bytes, &analysis, &lines, &labels),
+    }
+
+    Ok(())
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Writes the listing of a ROM to stdout, one line per instruction or run of
+/// data, with the labels and cross-references before the lines they are for
+///
+/// # Params
+///
+/// - `syntax` - The syntax the listing is written in
+/// - `bytes` - The bytes of the ROM
+/// - `analysis` - The analysis of the ROM
+/// - `lines` - The address and length of each line
+/// - `labels` - The labels of the ROM, keyed by their addresses
+fn print_listing(
+    syntax: Syntax,
+    bytes: &[u8],
+    analysis: &Analysis,
+    lines: &[(u16, usize)],
+    labels: &BTreeMap<u16, String>,
+) {
+    let comment = syntax.comment();
+    for &(addr, len) in lines {
+        print_references(analysis, addr, comment);
+
+        // Octo starts running the ROM from `main`, jumping there if it isn't
+        // at the start of the ROM
+        let label = labels.get(&addr).map(String::as_str);
+        let entry = addr == analysis.load_address();
+        if matches!(syntax, Syntax::Octo) && entry && label != Some("main") {
+            println!("{}", syntax.label("main"));
+        }
+        if let Some(label) = label {
+            println!("{}", syntax.label(label));
+        }
+
+        let offset = (addr - analysis.load_address()) as usize;
+        let line_bytes = &bytes[offset..offset + len];
+
+        if analysis.kind(addr) == Some(ByteKind::Code) {
+            let raw = ((line_bytes[0] as u16) << 8) | (line_bytes[1] as u16);
+            let text = syntax.instruction(raw, labels);
+            println!("    {text:<TEXT_WIDTH$}  {comment} 0x{addr:04x}  {raw:04x}");
+
+            continue;
+        }
+
+        let text = syntax.data(line_bytes);
+        match analysis.kind(addr) {
+            Some(ByteKind::Unreferenced) => {
+                println!("    {text:<TEXT_WIDTH$}  {comment} 0x{addr:04x}  unreferenced");
+            }
+            _ => println!("    {text:<TEXT_WIDTH$}  {comment} 0x{addr:04x}"),
+        }
+    }
+}
+
+/// Writes the listing of a ROM to stdout as a JSON array, with an object per
+/// instruction or run of data
+///
+/// Every object holds the `address`, `kind` (`code`, `data` or
+/// `unreferenced`) and `label` (or `null`) of the line. Instructions add the
+/// `raw` opcode, with the `mnemonic` and `operands` in the syntax of
+/// `assemble`, and data adds its `bytes`.
+///
+/// # Params
+///
+/// - `bytes` - The bytes of the ROM
+/// - `analysis` - The analysis of the ROM
+/// - `lines` - The address and length of each line
+/// - `labels` - The labels of the ROM, keyed by their addresses
+fn print_json(
+    bytes: &[u8],
+    analysis: &Analysis,
+    lines: &[(u16, usize)],
+    labels: &BTreeMap<u16, String>,
+) {
+    let entries = lines
+        .iter()
+        .map(|&(addr, len)| {
+            let offset = (addr - analysis.load_address()) as usize;
+            let line_bytes = &bytes[offset..offset + len];
+            let kind = analysis.kind(addr).unwrap_or(ByteKind::Unreferenced);
+            let label = labels.get(&addr);
+
+            if kind != ByteKind::Code {
+                return json!({
+                    "address": addr,
+                    "kind": kind.name(),
+                    "label": label,
+                    "bytes": line_bytes,
+                });
+            }
+
+            // The operands are left as the raw addresses rather than labels
+            let raw = ((line_bytes[0] as u16) << 8) | (line_bytes[1] as u16);
+            let text = instruction_text(raw, &BTreeMap::new());
+            let mut tokens = text.split_whitespace();
+            let mnemonic = tokens.next().unwrap_or_default();
+
+            json!({
+                "address": addr,
+                "kind": kind.name(),
+                "label": label,
+                "raw": raw,
+                "mnemonic": mnemonic,
+                "operands": tokens.collect::<Vec<&str>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    println!("{}", serde_json::Value::Array(entries));
+}
+
+/// Splits a ROM into the lines of its listing, being the address and length
+/// of each instruction and run of data
+///
+/// # Params
+///
+/// - `bytes` - The bytes of the ROM
+/// - `analysis` - The analysis of the ROM
+/// - `symbols` - The symbols of the ROM, which start lines of their own
+fn split_lines(bytes: &[u8], analysis: &Analysis, symbols: &Symbols) -> Vec<(u16, usize)> {
+    let mut lines = Vec::new();
+
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let addr = analysis.load_address() + offset as u16;
+        if analysis.kind(addr) == Some(ByteKind::Code) {
+            lines.push((addr, 2));
+
+            offset += 2;
+            continue;
+        }
+
+        // Data runs until the kind changes, a label starts or the line is full
+        let kind = analysis.kind(addr);
+        let len = (1..DATA_BYTES_PER_LINE)
+            .take_while(|&i| {
+                let addr = addr + i as u16;
+                offset + i < bytes.len()
+                    && analysis.kind(addr) == kind
+                    && symbols.label(addr).is_none()
+                    && analysis.references(addr).next().is_none()
+            })
+            .count()
+            + 1;
+
+        lines.push((addr, len));
+        offset += len;
+    }
+
+    lines
+}
+
+/// Gets the label of an address, being the one from the symbols or else one
+/// generated from how the address is referred to, if it is referred to
+///
+/// Subroutines are named `sub_`, the targets of jumps backwards (usually
+/// loops) `loop_` and of other jumps `label_`, and data `data_`, followed by
+/// the address.
+///
+/// # Params
+///
+/// - `analysis` - The analysis of the ROM
+/// - `symbols` - The symbols of the ROM
+/// - `addr` - The address to label
+fn label(analysis: &Analysis, symbols: &Symbols, addr: u16) -> Option<String> {
+    if let Some(label) = symbols.label(addr) {
+        return Some(label.to_string());
+    }
+
+    // The references are ordered by kind, so calls take precedence
+    let references = analysis.references(addr).collect::<Vec<(RefKind, u16)>>();
+    let backwards = references
+        .iter()
+        .any(|&(kind, from)| kind == RefKind::Jump && from >= addr);
+    let prefix = match references.first()? {
+        (RefKind::Call, _) => "sub",
+        (RefKind::Jump, _) if backwards => "loop",
+        (RefKind::Jump, _) => "label",
+        (RefKind::Load, _) => "data",
+    };
+
+    Some(format!("{prefix}_{addr:04x}"))
+}
+
+/// Renders an instruction in the syntax of `assemble`, with the address it
+/// refers to replaced by its label if it has one
+///
+/// The disassembly of a few instructions leaves out an operand, so those are
+/// given the names `assemble` knows them by. Opcodes with bits the decoder
+/// ignores, such as `5XY1`, would assemble to another opcode, so they are
+/// written as `raw` instead.
+///
+/// # Params
+///
+/// - `raw` - The raw opcode of the instruction
+/// - `labels` - The labels of the ROM, keyed by their addresses
+fn instruction_text(raw: u16, labels: &BTreeMap<u16, String>) -> String {
+    let x = (raw >> 8) & 0xF;
+    let text = match raw & 0xF0FF {
+        0xE09E => format!("skp v{x}"),
+        0xE0A1 => format!("sknp v{x}"),
+        0xF029 => format!("ld f v{x}"),
+        0xF030 => format!("ld hf v{x}"),
+        0xF033 => format!("ld b v{x}"),
+        0xF055 => format!("ld [i] v{x}"),
+        0xF065 => format!("ld v{x} [i]"),
+        _ => OpCode::from(raw).to_string().trim_end().to_string(),
+    };
+
+    if assemble(&text).ok() != Some(raw) {
+        return format!("raw 0x{raw:04x}");
+    }
+
+    match (raw & 0xF000, labels.get(&(raw & 0x0FFF))) {
+        (0x1000, Some(label)) => format!("jp {label}"),
+        (0x2000, Some(label)) => format!("call {label}"),
+        (0xA000, Some(label)) => format!("ld i {label}"),
+        (0xB000, Some(label)) => format!("jp v0 {label}"),
+        _ => text,
+    }
+}
+
+/// Renders an instruction in the dialect of Octo, with the address it refers
+/// to replaced by its label if it has one
+///
+/// The skips are written as `if ... then` on the opposite condition, as the
+/// statement after `then` runs only if it holds. Opcodes Octo has no
+/// statement for, or which would assemble to another opcode, are written as
+/// their two bytes instead.
+///
+/// # Params
+///
+/// - `raw` - The raw opcode of the instruction
+/// - `labels` - The labels of the ROM, keyed by their addresses
+fn octo_instruction_text(raw: u16, labels: &BTreeMap<u16, String>) -> String {
+    let x = (raw >> 8) & 0xF;
+    let y = (raw >> 4) & 0xF;
+    let n = raw & 0xF;
+    let nn = raw & 0xFF;
+    let nnn = raw & 0xFFF;
+
+    let text = match (raw >> 12, x, y, n) {
+        (0x0, 0x0, 0xE, 0x0) => "clear".to_string(),
+        (0x0, 0x0, 0xE, 0xE) => "return".to_string(),
+        (0x0, 0x0, 0xC, _) => format!("scroll-down {n}"),
+        (0x0, 0x0, 0xF, 0xB) => "scroll-right".to_string(),
+        (0x0, 0x0, 0xF, 0xC) => "scroll-left".to_string(),
+        (0x0, 0x0, 0xF, 0xD) => "exit".to_string(),
+        (0x0, 0x0, 0xF, 0xE) => "lores".to_string(),
+        (0x0, 0x0, 0xF, 0xF) => "hires".to_string(),
+        (0x1, ..) => format!("jump 0x{nnn:03x}"),
+        (0x2, ..) => format!(":call 0x{nnn:03x}"),
+        (0x3, ..) => format!("if v{x:x} != 0x{nn:02x} then"),
+        (0x4, ..) => format!("if v{x:x} == 0x{nn:02x} then"),
+        (0x5, _, _, 0x0) => format!("if v{x:x} != v{y:x} then"),
+        (0x6, ..) => format!("v{x:x} := 0x{nn:02x}"),
+        (0x7, ..) => format!("v{x:x} += 0x{nn:02x}"),
+        (0x8, _, _, 0x0) => format!("v{x:x} := v{y:x}"),
+        (0x8, _, _, 0x1) => format!("v{x:x} |= v{y:x}"),
+        (0x8, _, _, 0x2) => format!("v{x:x} &= v{y:x}"),
+        (0x8, _, _, 0x3) => format!("v{x:x} ^= v{y:x}"),
+        (0x8, _, _, 0x4) => format!("v{x:x} += v{y:x}"),
+        (0x8, _, _, 0x5) => format!("v{x:x} -= v{y:x}"),
+        (0x8, _, _, 0x6) => format!("v{x:x} >>= v{y:x}"),
+        (0x8, _, _, 0x7) => format!("v{x:x} =- v{y:x}"),
+        (0x8, _, _, 0xE) => format!("v{x:x} <<= v{y:x}"),
+        (0x9, _, _, 0x0) => format!("if v{x:x} == v{y:x} then"),
+        (0xA, ..) => format!("i := 0x{nnn:03x}"),
+        (0xB, ..) => format!("jump0 0x{nnn:03x}"),
+        (0xC, ..) => format!("v{x:x} := random 0x{nn:02x}"),
+        (0xD, ..) => format!("sprite v{x:x} v{y:x} {n}"),
+        (0xE, _, 0x9, 0xE) => format!("if v{x:x} -key then"),
+        (0xE, _, 0xA, 0x1) => format!("if v{x:x} key then"),
+        (0xF, 0x0, 0x0, 0x2) => "audio".to_string(),
+        (0xF, _, 0x0, 0x7) => format!("v{x:x} := delay"),
+        (0xF, _, 0x0, 0xA) => format!("v{x:x} := key"),
+        (0xF, _, 0x1, 0x5) => format!("delay := v{x:x}"),
+        (0xF, _, 0x1, 0x8) => format!("buzzer := v{x:x}"),
+        (0xF, _, 0x1, 0xE) => format!("i += v{x:x}"),
+        (0xF, _, 0x2, 0x9) => format!("i := hex v{x:x}"),
+        (0xF, _, 0x3, 0x0) => format!("i := bighex v{x:x}"),
+        (0xF, _, 0x3, 0x3) => format!("bcd v{x:x}"),
+        (0xF, _, 0x3, 0xA) => format!("pitch := v{x:x}"),
+        (0xF, _, 0x5, 0x5) => format!("save v{x:x}"),
+        (0xF, _, 0x6, 0x5) => format!("load v{x:x}"),
+        (0xF, _, 0x7, 0x5) => format!("saveflags v{x:x}"),
+        (0xF, _, 0x8, 0x5) => format!("loadflags v{x:x}"),
+        _ => String::new(),
+    };
+
+    if assemble_octo(&text).ok().as_deref() != Some(&raw.to_be_bytes()[..]) {
+        return Syntax::Octo.data(&raw.to_be_bytes());
+    }
+
+    match (raw & 0xF000, labels.get(&nnn)) {
+        (0x1000, Some(label)) => format!("jump {label}"),
+        (0x2000, Some(label)) => label.to_string(),
+        (0xA000, Some(label)) => format!("i := {label}"),
+        (0xB000, Some(label)) => format!("jump0 {label}"),
+        _ => text,
+    }
+}
+
+/// Writes the cross-references to an address as comments, one line per kind
+/// of reference
+///
+/// # Params
+///
+/// - `analysis` - The analysis of the ROM
+/// - `addr` - The address referred to
+/// - `comment` - The marker starting a comment
+fn print_references(analysis: &Analysis, addr: u16, comment: &str) {
+    let mut references = analysis.references(addr).peekable();
+    while let Some((kind, from)) = references.next() {
+        let mut sources = vec![format!("0x{from:04x}")];
+        while let Some((_, from)) = references.next_if(|&(next, _)| next == kind) {
+            sources.push(format!("0x{from:04x}"));
+        }
+
+        println!("{comment} {} {}", kind.comment(), sources.join(", "));
+    }
+}
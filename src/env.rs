@@ -0,0 +1,129 @@
+use crate::prelude::*;
+
+use keet8_core::{Emulator, FRAME_ROWS};
+
+// --- constants --------------------------------------------------------------
+
+/// The default number of frames emulated per step of the environment
+const DEFAULT_FRAME_SKIP: usize = 4;
+/// The default number of instructions executed per frame (600 per second)
+const DEFAULT_STEPS_PER_FRAME: usize = 10;
+
+// --- env definition ---------------------------------------------------------
+
+/// The screen as seen by an agent: 32 rows of 64 pixels, packed as pushed to
+/// a [Display](crate::Display)
+pub type Observation = [u64; FRAME_ROWS];
+
+/// A reinforcement learning environment around a ROM, in the style of Gym
+///
+/// Every `step` holds down the keys of an action for a number of frames
+/// (the frame skip), running a fixed number of instructions and one tick of
+/// the timers per frame, and returns the screen along with whether the ROM
+/// has exited. Rewards are game specific, so they are left to the agent,
+/// which can read the registers and memory through `emulator`.
+///
+/// # Examples
+///
+/// ```rust
+/// // DRW V0, V0, 5 (the font sprite of 0), JP 0x0202
+/// let rom = [0xD0, 0x05, 0x12, 0x02];
+///
+/// if let Ok(mut env) = keet_8::Env::new(&rom) {
+///     let mut observation = env.reset();
+///     for _ in 0..100 {
+///         // Hold down key 5, one bit per key of the keypad
+///         match env.step(1 << 0x5) {
+///             Ok((next, false)) => observation = next,
+///             _ => break,
+///         }
+///     }
+///
+///     println!("{} rows lit", observation.iter().filter(|&&row| row != 0).count());
+/// }
+/// ```
+pub struct Env {
+    /// The emulator running the ROM
+    emulator: Emulator,
+    /// The number of frames emulated per step
+    frame_skip: usize,
+    /// The number of instructions executed per frame
+    steps_per_frame: usize,
+}
+
+impl Env {
+    /// Creates an environment around the bytes of a ROM
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    ///
+    /// # Errors
+    ///
+    /// If the ROM doesn't fit in memory
+    pub fn new(rom: &[u8]) -> Result<Self> {
+        Ok(Self {
+            emulator: Emulator::from_rom(rom)?,
+            frame_skip: DEFAULT_FRAME_SKIP,
+            steps_per_frame: DEFAULT_STEPS_PER_FRAME,
+        })
+    }
+
+    /// Sets the number of frames emulated per step, during which the action
+    /// is held down
+    ///
+    /// # Params
+    ///
+    /// - `frame_skip` - The number of frames per step (at least `1`)
+    pub fn set_frame_skip(&mut self, frame_skip: usize) {
+        self.frame_skip = frame_skip.max(1);
+    }
+
+    /// Sets the number of instructions executed per frame, being the CPU
+    /// speed divided by 60
+    ///
+    /// # Params
+    ///
+    /// - `steps_per_frame` - The number of instructions per frame
+    pub fn set_steps_per_frame(&mut self, steps_per_frame: usize) {
+        self.steps_per_frame = steps_per_frame;
+    }
+
+    /// Gets the emulator running the ROM, to set its quirks or random source
+    /// or to read the state a reward is computed from
+    pub fn emulator(&mut self) -> &mut Emulator {
+        &mut self.emulator
+    }
+
+    /// Restarts the ROM, returning the first observation
+    pub fn reset(&mut self) -> Observation {
+        self.emulator.reset();
+        self.emulator.packed_rows()
+    }
+
+    /// Holds down the keys of an action for the frame skip, returning the
+    /// observation after the last frame and whether the ROM has exited
+    ///
+    /// # Params
+    ///
+    /// - `action` - The keys held down, bit `n` being key `n` of the keypad
+    ///
+    /// # Errors
+    ///
+    /// If an error occured whilst executing an instruction
+    pub fn step(&mut self, action: u16) -> Result<(Observation, bool)> {
+        self.emulator.set_all(action);
+
+        for _ in 0..self.frame_skip {
+            if let Some(e) = self.emulator.step_frame(self.steps_per_frame as u32).error {
+                return Err(e);
+            }
+
+            if self.emulator.is_halted() {
+                break;
+            }
+        }
+
+        Ok((self.emulator.packed_rows(), self.emulator.is_halted()))
+    }
+}
@@ -0,0 +1,79 @@
+pub use keet8_core::{crc32, fnv1a};
+
+// --- constants --------------------------------------------------------------
+
+/// The initial state of the SHA-1 hash
+const SHA1_INIT: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+
+// --- hashing functions ------------------------------------------------------
+
+/// Hashes the bytes with SHA-1
+///
+/// SHA-1 is what the CHIP-8 community database identifies ROMs by, so it is
+/// used to match ROMs against it rather than for any security purpose
+///
+/// # Params
+///
+/// - `bytes` - The bytes to hash
+pub(crate) fn sha1(bytes: &[u8]) -> [u8; 20] {
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0x00);
+    }
+    message.extend_from_slice(&((bytes.len() as u64) * 8).to_be_bytes());
+
+    let mut state = SHA1_INIT;
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (chunk, s) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&s.to_be_bytes());
+    }
+
+    digest
+}
+
+/// Hashes the bytes with SHA-1, written in lowercase hexadecimal as the
+/// CHIP-8 community database lists ROMs
+///
+/// # Params
+///
+/// - `bytes` - The bytes to hash
+pub(crate) fn sha1_hex(bytes: &[u8]) -> String {
+    sha1(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
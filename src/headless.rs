@@ -0,0 +1,105 @@
+use crate::config::Config;
+use crate::hash::fnv1a;
+use crate::input_script::InputScript;
+use crate::prelude::*;
+use crate::roms::read_rom;
+
+use keet8_core::{Emulator, InitPolicy, Lcg, StepOutcome};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of frames run when `--frames` isn't given, being 10 seconds
+pub(crate) const DEFAULT_HEADLESS_FRAMES: u32 = 600;
+
+// --- headless interface -----------------------------------------------------
+
+/// Runs the ROM for a number of frames without a window and writes a hash of
+/// the final screen and registers to stdout, for checking the behavior of the
+/// emulator on machines without a display, such as in CI
+///
+/// A frame executes the instructions the CPU speed gives per 60Hz tick and
+/// then ticks the timers, as in the window, with the keys held down by the
+/// input script, if any, or none at all. The random numbers come from the
+/// seed, or the default one, so the hash is the same on every machine and
+/// every run.
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+/// - `config` - The configuration, with the options already applied
+/// - `frames` - The number of frames to run
+/// - `seed` - The seed of the random numbers, if not the default one
+/// - `strict` - Whether unknown opcodes raise an error
+/// - `input_script` - The filepath to the input script to play, if any
+///
+/// # Errors
+///
+/// - If an error occured when loading the ROM file
+/// - If the input script could not be loaded
+/// - If an error occured whilst running the ROM
+pub(crate) fn print_headless(
+    rom_file: &str,
+    config: &Config,
+    frames: u32,
+    seed: Option<u32>,
+    strict: bool,
+    input_script: Option<&str>,
+) -> Result<()> {
+    let input_script = input_script.map(InputScript::load).transpose()?;
+
+    // The ROM is loaded once the memory has the size of the platform
+    let mut emulator = Emulator::without_rom(InitPolicy::Zeroed);
+    emulator.set_quirks(config.quirks);
+    emulator.set_platform(config.platform);
+    emulator.set_two_page(config.two_page);
+    emulator.set_load_address(config.load_addr)?;
+    emulator.load_rom(&read_rom(rom_file)?)?;
+    emulator.set_strict(strict);
+    if let Some(seed) = seed {
+        emulator.set_random_source(Lcg::new(seed));
+    }
+
+    let steps_per_frame = (config.cpu_speed / 60).max(1);
+    for frame in 0..frames as usize {
+        if let Some(input_script) = &input_script {
+            emulator.set_all(input_script.keys(frame));
+        }
+
+        for _ in 0..steps_per_frame {
+            if let StepOutcome::Halted = emulator.step()? {
+                break;
+            }
+        }
+
+        emulator.tick_timers();
+    }
+
+    println!("{:016x}", state_hash(&emulator));
+    Ok(())
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Hashes the screen along with the `V` registers, `I`, the program counter,
+/// the timers and the call stack
+///
+/// # Params
+///
+/// - `emulator` - The emulator to hash the state of
+fn state_hash(emulator: &Emulator) -> u64 {
+    let mut bytes = emulator
+        .framebuffer()
+        .iter()
+        .map(|&pixel| (pixel > 0) as u8)
+        .collect::<Vec<u8>>();
+
+    bytes.extend((0..16).map(|x| emulator.register(x)));
+    bytes.extend_from_slice(&emulator.index().to_be_bytes());
+    bytes.extend_from_slice(&emulator.program_counter().to_be_bytes());
+    bytes.extend([emulator.delay_timer(), emulator.sound_timer()]);
+    for addr in emulator.call_stack() {
+        bytes.extend_from_slice(&addr.to_be_bytes());
+    }
+
+    fnv1a(&bytes)
+}
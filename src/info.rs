@@ -0,0 +1,124 @@
+use crate::analysis::{Analysis, CallGraph};
+use crate::hash;
+use crate::prelude::*;
+use crate::rom_db;
+use crate::roms::read_rom;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::{is_two_page_rom, Platform, MEMORY_SIZE};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of instructions disassembled from the start of the ROM
+const NUM_FIRST_INSTRUCTIONS: usize = 8;
+
+// --- info interface ---------------------------------------------------------
+
+/// Writes a summary of a ROM to stdout, to triage a ROM before running it
+///
+/// The summary contains the size and hashes of the ROM, a hint at the
+/// platform it targets (or the title and platform found in the ROM
+/// database), the counts of SCHIP and XO-CHIP opcodes, the ranges of code,
+/// data and unreferenced bytes, the call graph with its worst-case stack depth
+/// and the first few instructions. The opcodes are counted with a linear
+/// sweep over the whole ROM, so sprite data may be counted as well.
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM
+/// - `rom_database` - The filepath to the ROM database, if not the bundled one
+/// - `load_addr` - The address the ROM is loaded and starts at
+///
+/// # Errors
+///
+/// - If there was an error when loading the ROM file
+/// - If the ROM database could not be read or is invalid
+pub(crate) fn print_info(
+    rom_file: &str,
+    rom_database: Option<&str>,
+    load_addr: u16,
+) -> Result<()> {
+    let bytes = read_rom(rom_file)?;
+
+    let raws = bytes
+        .chunks_exact(2)
+        .map(|pair| ((pair[0] as u16) << 8) | (pair[1] as u16))
+        .collect::<Vec<u16>>();
+
+    let platforms = raws.iter().map(|&raw| Platform::of_opcode(raw));
+    let num_schip = platforms.clone().filter(|&p| p == Platform::Schip).count();
+    let num_xochip = platforms.clone().filter(|&p| p == Platform::XoChip).count();
+
+    // The largest ROM which fits in the 4K memory of Chip-8 and SCHIP
+    let max_chip8_size = MEMORY_SIZE.saturating_sub(load_addr as usize);
+    let platform = if bytes.len() > max_chip8_size {
+        Platform::XoChip
+    } else {
+        platforms.max().unwrap_or(Platform::Chip8)
+    };
+
+    let sha1 = hash::sha1_hex(&bytes);
+
+    println!("File:     {rom_file}");
+    println!("Size:     {} bytes", bytes.len());
+    println!("SHA-1:    {sha1}");
+    println!("FNV-1a:   {:016x}", hash::fnv1a(&bytes));
+    match rom_db::lookup(&bytes, rom_database)? {
+        Some(profile) => {
+            println!("Title:    {}", profile.title);
+            println!("Platform: {}", profile.platform);
+        }
+        None => println!("Platform: {} (hint)", platform.name()),
+    }
+    if is_two_page_rom(&bytes) {
+        println!("Mode:     two-page, 64x64 (hi-res Chip-8)");
+    }
+    println!("Opcodes:  {} total, {num_schip} SCHIP, {num_xochip} XO-CHIP", raws.len());
+
+    if bytes.len() > max_chip8_size {
+        println!("Warning:  the ROM doesn't fit in the 4K memory of Chip-8");
+    }
+
+    println!();
+    println!("Layout:");
+    for (range, kind) in Analysis::analyze(&bytes, load_addr).ranges() {
+        let len = range.end - range.start;
+        println!("    0x{:04x}-0x{:04x}  {:<12}  {len} bytes", range.start, range.end - 1, kind.name());
+    }
+
+    let call_graph = CallGraph::build(&bytes, load_addr);
+
+    println!();
+    println!("Call graph:");
+    for (subroutine, callees) in call_graph.subroutines() {
+        let callees = callees
+            .iter()
+            .map(|callee| format!("0x{callee:04x}"))
+            .collect::<Vec<String>>();
+
+        if callees.is_empty() {
+            println!("    0x{subroutine:04x}");
+        } else {
+            println!("    0x{subroutine:04x} -> {}", callees.join(", "));
+        }
+    }
+
+    let stack_limit = platform.stack_limit();
+    match call_graph.max_depth() {
+        Some(depth) => println!("Stack:    {depth} of {stack_limit} entries (worst case)"),
+        None => println!("Stack:    unbounded (recursive calls)"),
+    }
+
+    if call_graph.may_overflow(stack_limit) {
+        println!("Warning:  the call stack may overflow at runtime");
+    }
+
+    println!();
+    println!("First instructions:");
+    for (i, &raw) in raws.iter().take(NUM_FIRST_INSTRUCTIONS).enumerate() {
+        let addr = load_addr as usize + 2 * i;
+        println!("    0x{addr:04x}  {raw:04x}  {}", OpCode::from(raw));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::keymap::NUM_KEYPAD_KEYS;
+
+use raylib::prelude::*;
+
+// --- input source definition ------------------------------------------------
+
+/// A source of the keys of the keypad held down each frame, such as the
+/// keyboard, a gamepad or a script
+///
+/// The application polls all of its sources once per frame, a key of the
+/// keypad being held down if any of them holds it.
+pub(crate) trait InputSource {
+    /// Reads the keys of the keypad held down, with bit `K` set for key `K`
+    ///
+    /// # Params
+    ///
+    /// - `rl` - The handle to the raylib context
+    fn poll(&mut self, rl: &RaylibHandle) -> u16;
+}
+
+impl InputSource for [KeyboardKey; NUM_KEYPAD_KEYS] {
+    /// Reads the keypad from the keyboard keys, indexed by the key on the
+    /// keypad
+    fn poll(&mut self, rl: &RaylibHandle) -> u16 {
+        self.iter()
+            .enumerate()
+            .fold(0, |keys, (k, &key)| keys | (rl.is_key_down(key) as u16) << k)
+    }
+}
+
+impl InputSource for [Option<KeyboardKey>; NUM_KEYPAD_KEYS] {
+    /// Reads the keypad from the keyboard keys mapped to some of its keys,
+    /// indexed by the key on the keypad
+    fn poll(&mut self, rl: &RaylibHandle) -> u16 {
+        self.iter().enumerate().fold(0, |keys, (k, &key)| {
+            keys | (key.is_some_and(|key| rl.is_key_down(key)) as u16) << k
+        })
+    }
+}
+
+// --- gamepad definition -----------------------------------------------------
+
+/// A gamepad with some of its buttons mapped to the keypad
+pub(crate) struct Gamepad {
+    /// The index of the gamepad, in the order they were connected
+    index: i32,
+    /// The buttons of the keypad, indexed by the key on the keypad
+    buttons: [Option<GamepadButton>; NUM_KEYPAD_KEYS],
+}
+
+impl Gamepad {
+    /// Creates a gamepad read as input
+    ///
+    /// # Params
+    ///
+    /// - `index` - The index of the gamepad, in the order they were connected
+    /// - `buttons` - The buttons of the keypad, indexed by the key on the
+    ///   keypad
+    pub fn new(index: i32, buttons: [Option<GamepadButton>; NUM_KEYPAD_KEYS]) -> Self {
+        Self { index, buttons }
+    }
+}
+
+impl InputSource for Gamepad {
+    /// Reads the keypad from the buttons, holding no key whilst the gamepad
+    /// is disconnected
+    fn poll(&mut self, rl: &RaylibHandle) -> u16 {
+        if !rl.is_gamepad_available(self.index) {
+            return 0;
+        }
+
+        self.buttons.iter().enumerate().fold(0, |keys, (k, &button)| {
+            let down = button.is_some_and(|button| rl.is_gamepad_button_down(self.index, button));
+            keys | (down as u16) << k
+        })
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Polls several sources, combining the keys held down by any of them
+///
+/// # Params
+///
+/// - `rl` - The handle to the raylib context
+/// - `sources` - The sources to poll
+pub(crate) fn poll_all(rl: &RaylibHandle, sources: &mut [&mut dyn InputSource]) -> u16 {
+    sources.iter_mut().fold(0, |keys, source| keys | source.poll(rl))
+}
@@ -0,0 +1,185 @@
+use crate::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The first line of an input log, identifying the format
+const HEADER: &str = "# keet-8 input log";
+
+// --- input log definition ---------------------------------------------------
+
+/// The input of a run, frame by frame, for replaying it deterministically
+///
+/// A frame holds the keys held down, the number of instructions executed and
+/// of timer ticks, and whether the ROM was restarted at its end. Together
+/// with the seed of the random numbers, this makes the emulator end up in
+/// the exact same state whatever the frame rate of the replay.
+///
+/// The log is stored as text, with the seed and the hash of the ROM followed
+/// by a line per frame:
+///
+/// ```text
+/// # keet-8 input log
+/// seed 2545f491
+/// rom 8b2e0c7f1d9a3c44
+/// 0000 11 1
+/// 0010 12 1 reset
+/// ```
+pub(crate) struct InputLog {
+    /// The seed of the random numbers of the run
+    seed: u32,
+    /// The hash of the ROM which was run
+    rom_hash: u64,
+    /// The frames of the run, in order
+    frames: Vec<Frame>,
+    /// The position of the next frame to replay
+    pos: usize,
+}
+
+/// The input of a single frame
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Frame {
+    /// The keys held down, with bit `K` set for key `K`
+    pub keys: u16,
+    /// The number of instructions executed
+    pub steps: u32,
+    /// The number of times the timers ticked
+    pub ticks: u32,
+    /// Flag indicating whether the ROM was restarted after the frame
+    pub reset: bool,
+}
+
+impl InputLog {
+    /// Creates an empty log to record a run into
+    ///
+    /// # Params
+    ///
+    /// - `seed` - The seed of the random numbers of the run
+    /// - `rom_hash` - The hash of the ROM being run
+    pub fn new(seed: u32, rom_hash: u64) -> Self {
+        Self {
+            seed,
+            rom_hash,
+            frames: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Loads a recorded log to replay
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the log
+    ///
+    /// # Errors
+    ///
+    /// If the log could not be read or is invalid
+    pub fn load(path: &str) -> Result<Self> {
+        let invalid = || Keet8Error::FailedToLoadInputLog(path.to_string());
+
+        let text = std::fs::read_to_string(path).map_err(|_| invalid())?;
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err(invalid());
+        }
+
+        let mut field = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .map(str::trim)
+                .map(str::to_string)
+                .ok_or_else(invalid)
+        };
+
+        let seed = u32::from_str_radix(&field("seed")?, 16).map_err(|_| invalid())?;
+        let rom_hash = u64::from_str_radix(&field("rom")?, 16).map_err(|_| invalid())?;
+
+        let frames = lines
+            .map(parse_frame)
+            .collect::<Option<Vec<Frame>>>()
+            .ok_or_else(invalid)?;
+
+        Ok(Self {
+            seed,
+            rom_hash,
+            frames,
+            pos: 0,
+        })
+    }
+
+    /// Writes the log to a file
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the log
+    ///
+    /// # Errors
+    ///
+    /// If the log could not be written
+    pub fn save(&self, path: &str) -> Result<()> {
+        let mut lines = vec![
+            HEADER.to_string(),
+            format!("seed {:08x}", self.seed),
+            format!("rom {:016x}", self.rom_hash),
+        ];
+
+        for frame in &self.frames {
+            let reset = if frame.reset { " reset" } else { "" };
+            lines.push(format!("{:04x} {} {}{reset}", frame.keys, frame.steps, frame.ticks));
+        }
+
+        std::fs::write(path, lines.join("\n") + "\n")
+            .map_err(|_| Keet8Error::FailedToSaveInputLog(path.to_string()))
+    }
+
+    /// Gets the seed of the random numbers of the run
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Gets the hash of the ROM which was run
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Appends a frame to the log
+    ///
+    /// # Params
+    ///
+    /// - `frame` - The input of the frame
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    /// Takes the next frame to replay, if the replay isn't over
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        let frame = self.frames.get(self.pos).copied()?;
+        self.pos += 1;
+        Some(frame)
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Parses the line of a frame, being the keys in hexadecimal, the number of
+/// instructions, the number of timer ticks and optionally `reset`
+///
+/// # Params
+///
+/// - `line` - The line of the frame
+fn parse_frame(line: &str) -> Option<Frame> {
+    let mut tokens = line.split_whitespace();
+
+    let frame = Frame {
+        keys: u16::from_str_radix(tokens.next()?, 16).ok()?,
+        steps: tokens.next()?.parse().ok()?,
+        ticks: tokens.next()?.parse().ok()?,
+        reset: match tokens.next() {
+            Some("reset") => true,
+            Some(_) => return None,
+            None => false,
+        },
+    };
+
+    tokens.next().is_none().then_some(frame)
+}
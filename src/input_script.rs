@@ -0,0 +1,138 @@
+use crate::input_log::{Frame, InputLog};
+use crate::prelude::*;
+
+// --- input script definition ------------------------------------------------
+
+/// The keys pressed and released at given frames, written by hand for
+/// recording demos and reproducing bug reports
+///
+/// A script is a list of statements, separated by newlines or semicolons,
+/// each pressing or releasing a key of the keypad at the start of a frame,
+/// counted from 0. Keys stay held down until released, and `#` starts a
+/// comment:
+///
+/// ```text
+/// # Start the game, then jump
+/// frame 120: press 5; frame 130: release 5
+/// frame 200: press A
+/// frame 204: release A
+/// ```
+pub(crate) struct InputScript {
+    /// The keys held down in every frame up to the last one the script
+    /// changes, with bit `K` set for key `K`
+    frames: Vec<u16>,
+}
+
+impl InputScript {
+    /// Loads a script from a file
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the script
+    ///
+    /// # Errors
+    ///
+    /// - If the script could not be read
+    /// - If a statement of the script is invalid
+    pub fn load(path: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|_| Keet8Error::InvalidInputScript(path.to_string(), String::new()))?;
+
+        Self::parse(&text)
+            .map_err(|statement| Keet8Error::InvalidInputScript(path.to_string(), statement))
+    }
+
+    /// Parses the statements of a script, returning the first invalid one
+    /// on failure
+    ///
+    /// # Params
+    ///
+    /// - `text` - The text of the script
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut changes = text
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap_or_default().split(';'))
+            .map(str::trim)
+            .filter(|statement| !statement.is_empty())
+            .map(|statement| parse_statement(statement).ok_or_else(|| statement.to_string()))
+            .collect::<std::result::Result<Vec<(usize, u16, bool)>, String>>()?;
+
+        // The statements may come in any order, those of a frame being
+        // applied in the order they are written
+        changes.sort_by_key(|&(frame, _, _)| frame);
+
+        let len = changes.last().map_or(0, |&(frame, _, _)| frame + 1);
+        let mut frames = Vec::with_capacity(len);
+        let mut keys = 0;
+        let mut changes = changes.into_iter().peekable();
+        for frame in 0..len {
+            while let Some((_, key, pressed)) = changes.next_if(|&(at, _, _)| at == frame) {
+                keys = if pressed { keys | key } else { keys & !key };
+            }
+            frames.push(keys);
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// Gets the keys held down in a frame, being the keys of the last frame
+    /// the script changes for the frames after it
+    ///
+    /// # Params
+    ///
+    /// - `frame` - The frame, counted from 0
+    pub fn keys(&self, frame: usize) -> u16 {
+        self.frames.get(frame).or(self.frames.last()).copied().unwrap_or(0)
+    }
+
+    /// Turns the script into an input log running a fixed number of
+    /// instructions and a single timer tick per frame, so that it is played
+    /// back as deterministically as a recorded log
+    ///
+    /// # Params
+    ///
+    /// - `seed` - The seed of the random numbers of the run
+    /// - `rom_hash` - The hash of the ROM being run
+    /// - `steps_per_frame` - The number of instructions executed per frame
+    pub fn into_input_log(self, seed: u32, rom_hash: u64, steps_per_frame: u32) -> InputLog {
+        let mut log = InputLog::new(seed, rom_hash);
+        for keys in self.frames {
+            log.push(Frame {
+                keys,
+                steps: steps_per_frame,
+                ticks: 1,
+                reset: false,
+            });
+        }
+
+        log
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Parses a statement as `frame <n>: press <key>` or `frame <n>: release
+/// <key>`, returning the frame, the bit of the key and whether it is pressed
+///
+/// # Params
+///
+/// - `statement` - The statement, without its separator
+fn parse_statement(statement: &str) -> Option<(usize, u16, bool)> {
+    let (frame, action) = statement.strip_prefix("frame")?.split_once(':')?;
+    let frame = frame.trim().parse().ok()?;
+
+    let mut tokens = action.split_whitespace();
+    let pressed = match tokens.next()? {
+        "press" => true,
+        "release" => false,
+        _ => return None,
+    };
+
+    let key = tokens.next()?;
+    if key.len() != 1 || tokens.next().is_some() {
+        return None;
+    }
+
+    let key = u16::from_str_radix(key, 16).ok()?;
+    Some((frame, 1 << key, pressed))
+}
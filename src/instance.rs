@@ -0,0 +1,255 @@
+use crate::config::Config;
+use crate::input::{poll_all, Gamepad};
+use crate::keymap::NUM_KEYPAD_KEYS;
+use crate::overlay::Overlay;
+use crate::prelude::*;
+use crate::roms::read_rom;
+use crate::scheduler::Scheduler;
+use crate::screen::Screen;
+
+use keet8_core::{Emulator, InitPolicy, Lcg, Platform, Renderer};
+use log::{error, info};
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The gamepad read as the input of the instance, being the second one
+/// connected as the first one plays the ROM of the application
+const GAMEPAD: i32 = 1;
+
+// --- instance definition ----------------------------------------------------
+
+/// An emulator run side by side with the one of the application, with its
+/// own ROM, keys and half of the window, to compare two revisions of a ROM or
+/// race another player locally
+///
+/// The instance only runs its ROM, leaving the debugger, save states,
+/// recordings and the rest of the tooling to the emulator of the application.
+/// An error of its ROM stops it, and is shown over its half of the window.
+///
+/// When comparing quirks, the instance runs the ROM of the application with
+/// the quirks of another platform instead, pressing the keys of the
+/// application so both emulators see the same input.
+pub(crate) struct Instance {
+    /// The filepath to the ROM file
+    rom_file: String,
+    /// The emulator running the ROM
+    emulator: Emulator,
+    /// The scheduler of the instructions and timer ticks of the emulator
+    scheduler: Scheduler,
+    /// The display of the emulator
+    screen: Screen,
+    /// The keyboard keys of the keypad, indexed by the key on the keypad
+    keymap: [KeyboardKey; NUM_KEYPAD_KEYS],
+    /// The gamepad whose buttons are mapped to the keypad
+    gamepad: Gamepad,
+    /// The error which stopped the ROM, if any
+    fault: Option<Keet8Error>,
+    /// Flag indicating whether the keys of the application are pressed
+    /// rather than the keys of the instance, when comparing quirks
+    mirror: bool,
+}
+
+impl Instance {
+    /// Loads the ROM into its own emulator, configured like the one of the
+    /// application but with the keys of the side instance
+    ///
+    /// # Params
+    ///
+    /// - `rl` - The handle to the raylib context
+    /// - `thread` - The thread on which raylib is running
+    /// - `rom_file` - The filepath to the ROM file
+    /// - `config` - The configuration, with the options already applied
+    /// - `strict` - Whether unknown opcodes raise an error
+    /// - `seed` - The seed of the random number generator, shared with the
+    ///   emulator of the application so both ROMs roll the same numbers
+    /// - `init` - How the memory and registers are filled, as for the
+    ///   emulator of the application
+    ///
+    /// # Errors
+    ///
+    /// - If an error occured when loading the ROM file
+    /// - If the texture of the display could not be created
+    pub fn new(
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        rom_file: &str,
+        config: &Config,
+        strict: bool,
+        seed: u32,
+        init: InitPolicy,
+    ) -> Result<Self> {
+        // The ROM is loaded once the memory has the size of the platform
+        let mut emulator = Emulator::without_rom(init);
+        emulator.set_quirks(config.quirks);
+        emulator.set_platform(config.platform);
+        emulator.set_two_page(config.two_page);
+        emulator.set_load_address(config.load_addr)?;
+        emulator.load_rom(&read_rom(rom_file)?)?;
+        emulator.set_strict(strict);
+        emulator.set_random_source(Lcg::new(seed));
+        info!("Loaded {rom_file} side by side");
+
+        let mut screen =
+            Screen::new(rl, thread, config.crt, config.integer_scaling, config.phosphor)?;
+        screen.set_pixel_grid(config.accessibility.pixel_grid);
+
+        Ok(Self {
+            rom_file: rom_file.to_string(),
+            emulator,
+            scheduler: Scheduler::new(config.cpu_speed),
+            screen,
+            keymap: config.side_keymap,
+            gamepad: Gamepad::new(GAMEPAD, config.gamepad_map),
+            fault: None,
+            mirror: false,
+        })
+    }
+
+    /// Runs the ROM with the quirks and opcodes of a platform and the keys of
+    /// the application, to compare it against the application
+    ///
+    /// # Params
+    ///
+    /// - `platform` - The platform to run the ROM as
+    pub fn compare_with(&mut self, platform: Platform) {
+        self.emulator.set_quirks(platform.quirks());
+        self.emulator.set_platform(Some(platform));
+        self.mirror = true;
+        info!("Comparing {} with the quirks of {}", self.rom_file, platform.name());
+    }
+
+    /// Replaces the ROM with the bytes of another one and starts it, keeping
+    /// the ROM it had if the new one doesn't fit in memory
+    ///
+    /// # Params
+    ///
+    /// - `rom_file` - The filepath to the ROM file
+    /// - `rom` - The bytes of the ROM
+    pub fn load_rom(&mut self, rom_file: &str, rom: &[u8]) {
+        match self.emulator.load_rom(rom) {
+            Ok(()) => {
+                self.rom_file = rom_file.to_string();
+                self.fault = None;
+            }
+            Err(e) => {
+                error!("{rom_file}: {}", e.message());
+                self.fault = Some(e);
+            }
+        }
+    }
+
+    /// Gets the keyboard keys of the keypad, indexed by the key on the keypad
+    pub fn keymap(&self) -> &[KeyboardKey; NUM_KEYPAD_KEYS] {
+        &self.keymap
+    }
+
+    /// Gets the display of the emulator, to toggle its filters along with the
+    /// one of the application
+    pub fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
+    /// Checks whether the ROM has exited the interpreter
+    pub fn is_halted(&self) -> bool {
+        self.emulator.is_halted()
+    }
+
+    /// Gets the FNV-1a hash of the screen, to compare it against the screen
+    /// of the application
+    pub fn framebuffer_hash(&self) -> u64 {
+        self.emulator.framebuffer_hash()
+    }
+
+    /// Sets the number of instructions executed per second
+    ///
+    /// # Params
+    ///
+    /// - `cpu_speed` - The number of instructions executed per second
+    pub fn set_cpu_speed(&mut self, cpu_speed: u32) {
+        self.scheduler.set_cpu_speed(cpu_speed);
+    }
+
+    /// Restarts the ROM from its original image
+    pub fn reset(&mut self) {
+        self.emulator.reset();
+        self.fault = None;
+    }
+
+    /// Runs the instructions and timer ticks due in the time since the last
+    /// frame, with the keys held down
+    ///
+    /// # Params
+    ///
+    /// - `rl` - The handle to the raylib context
+    /// - `paused` - Whether the application is paused, which stops the
+    ///   instance as well
+    /// - `keys` - The keys held down in the application, pressed instead of
+    ///   the keys of the instance when comparing quirks
+    pub fn update(&mut self, rl: &RaylibHandle, paused: bool, keys: u16) {
+        self.scheduler.advance(rl.get_frame_time());
+        if paused || self.fault.is_some() {
+            self.scheduler.skip_steps();
+            self.scheduler.skip_ticks();
+            return;
+        }
+
+        let keys = if self.mirror {
+            keys
+        } else {
+            poll_all(rl, &mut [&mut self.keymap, &mut self.gamepad])
+        };
+        self.emulator.set_all(keys);
+
+        while self.scheduler.next_step() {
+            if let Err(e) = self.emulator.step() {
+                error!("{}: {}", self.rom_file, e.message());
+                self.fault = Some(e);
+                return;
+            }
+        }
+
+        while self.scheduler.next_tick() {
+            self.emulator.tick_timers();
+        }
+    }
+
+    /// Draws the display of the emulator in its part of the window, with the
+    /// error which stopped the ROM over it if any
+    ///
+    /// # Params
+    ///
+    /// - `d` - The draw handle provided by raylib
+    /// - `thread` - The thread on which raylib is running
+    /// - `overlay` - The overlay to draw the error on
+    /// - `viewport` - The part of the window the instance is drawn in
+    /// - `foreground` - The color of the pixels which are on
+    /// - `background` - The color of the pixels which are off
+    pub fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        thread: &RaylibThread,
+        overlay: &mut Overlay,
+        viewport: Rectangle,
+        foreground: Color,
+        background: Color,
+    ) {
+        self.screen.set_viewport(Some(viewport));
+        self.screen
+            .renderer(d, thread, foreground, background)
+            .present(&self.emulator.frame());
+        self.emulator.clear_dirty_rows();
+
+        if let Some(fault) = &self.fault {
+            let message = fault.message();
+            let size = overlay.font_size(20);
+            let width = overlay.measure(&message, size);
+            let x = viewport.x as i32 + (viewport.width as i32 - width) / 2;
+            let y = viewport.y as i32 + (viewport.height as i32 - size) / 2;
+
+            let background = overlay.background();
+            overlay.rect(x - 10, y - 10, width + 20, size + 20, background);
+            overlay.text(&message, x, y, size, Color::RED);
+        }
+    }
+}
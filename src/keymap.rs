@@ -0,0 +1,389 @@
+use crate::input::InputSource;
+use crate::sidecar::Sidecar;
+
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of keys on the Chip-8 keypad
+pub(crate) const NUM_KEYPAD_KEYS: usize = 16;
+
+/// The sidecar section holding the keys bound in the application
+const KEYS_SECTION: &str = "keys";
+
+/// The names of the keyboard keys which can be mapped to the keypad
+const KEY_NAMES: [(&str, KeyboardKey); 59] = [
+    ("0", KeyboardKey::KEY_ZERO),
+    ("1", KeyboardKey::KEY_ONE),
+    ("2", KeyboardKey::KEY_TWO),
+    ("3", KeyboardKey::KEY_THREE),
+    ("4", KeyboardKey::KEY_FOUR),
+    ("5", KeyboardKey::KEY_FIVE),
+    ("6", KeyboardKey::KEY_SIX),
+    ("7", KeyboardKey::KEY_SEVEN),
+    ("8", KeyboardKey::KEY_EIGHT),
+    ("9", KeyboardKey::KEY_NINE),
+    ("A", KeyboardKey::KEY_A),
+    ("B", KeyboardKey::KEY_B),
+    ("C", KeyboardKey::KEY_C),
+    ("D", KeyboardKey::KEY_D),
+    ("E", KeyboardKey::KEY_E),
+    ("F", KeyboardKey::KEY_F),
+    ("G", KeyboardKey::KEY_G),
+    ("H", KeyboardKey::KEY_H),
+    ("I", KeyboardKey::KEY_I),
+    ("J", KeyboardKey::KEY_J),
+    ("K", KeyboardKey::KEY_K),
+    ("L", KeyboardKey::KEY_L),
+    ("M", KeyboardKey::KEY_M),
+    ("N", KeyboardKey::KEY_N),
+    ("O", KeyboardKey::KEY_O),
+    ("P", KeyboardKey::KEY_P),
+    ("Q", KeyboardKey::KEY_Q),
+    ("R", KeyboardKey::KEY_R),
+    ("S", KeyboardKey::KEY_S),
+    ("T", KeyboardKey::KEY_T),
+    ("U", KeyboardKey::KEY_U),
+    ("V", KeyboardKey::KEY_V),
+    ("W", KeyboardKey::KEY_W),
+    ("X", KeyboardKey::KEY_X),
+    ("Y", KeyboardKey::KEY_Y),
+    ("Z", KeyboardKey::KEY_Z),
+    ("KP_0", KeyboardKey::KEY_KP_0),
+    ("KP_1", KeyboardKey::KEY_KP_1),
+    ("KP_2", KeyboardKey::KEY_KP_2),
+    ("KP_3", KeyboardKey::KEY_KP_3),
+    ("KP_4", KeyboardKey::KEY_KP_4),
+    ("KP_5", KeyboardKey::KEY_KP_5),
+    ("KP_6", KeyboardKey::KEY_KP_6),
+    ("KP_7", KeyboardKey::KEY_KP_7),
+    ("KP_8", KeyboardKey::KEY_KP_8),
+    ("KP_9", KeyboardKey::KEY_KP_9),
+    ("KP_DECIMAL", KeyboardKey::KEY_KP_DECIMAL),
+    ("KP_DIVIDE", KeyboardKey::KEY_KP_DIVIDE),
+    ("KP_MULTIPLY", KeyboardKey::KEY_KP_MULTIPLY),
+    ("KP_SUBTRACT", KeyboardKey::KEY_KP_SUBTRACT),
+    ("KP_ADD", KeyboardKey::KEY_KP_ADD),
+    ("KP_ENTER", KeyboardKey::KEY_KP_ENTER),
+    ("SPACE", KeyboardKey::KEY_SPACE),
+    ("ENTER", KeyboardKey::KEY_ENTER),
+    ("TAB", KeyboardKey::KEY_TAB),
+    ("UP", KeyboardKey::KEY_UP),
+    ("DOWN", KeyboardKey::KEY_DOWN),
+    ("LEFT", KeyboardKey::KEY_LEFT),
+    ("RIGHT", KeyboardKey::KEY_RIGHT),
+];
+
+/// The names of the gamepad buttons which can be mapped to the keypad, with
+/// the face buttons named after their position on an Xbox controller
+const BUTTON_NAMES: [(&str, GamepadButton); 17] = [
+    ("UP", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP),
+    ("DOWN", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+    ("LEFT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+    ("RIGHT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+    ("A", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+    ("B", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+    ("X", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+    ("Y", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP),
+    ("LB", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1),
+    ("LT", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2),
+    ("RB", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+    ("RT", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2),
+    ("BACK", GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT),
+    ("GUIDE", GamepadButton::GAMEPAD_BUTTON_MIDDLE),
+    ("START", GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT),
+    ("LS", GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB),
+    ("RS", GamepadButton::GAMEPAD_BUTTON_RIGHT_THUMB),
+];
+
+/// The keyboard keys of the hex layout, indexed by the key on the keypad:
+/// each key of the keypad on the key of the same digit or letter
+const HEX_LAYOUT: [KeyboardKey; NUM_KEYPAD_KEYS] = [
+    KeyboardKey::KEY_ZERO,
+    KeyboardKey::KEY_ONE,
+    KeyboardKey::KEY_TWO,
+    KeyboardKey::KEY_THREE,
+    KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_FIVE,
+    KeyboardKey::KEY_SIX,
+    KeyboardKey::KEY_SEVEN,
+    KeyboardKey::KEY_EIGHT,
+    KeyboardKey::KEY_NINE,
+    KeyboardKey::KEY_A,
+    KeyboardKey::KEY_B,
+    KeyboardKey::KEY_C,
+    KeyboardKey::KEY_D,
+    KeyboardKey::KEY_E,
+    KeyboardKey::KEY_F,
+];
+
+/// The keyboard keys of the QWERTY layout, indexed by the key on the keypad:
+/// the 4x4 grid of the COSMAC VIP keypad on `1234`/`QWER`/`ASDF`/`ZXCV`
+const QWERTY_LAYOUT: [KeyboardKey; NUM_KEYPAD_KEYS] = [
+    KeyboardKey::KEY_X,
+    KeyboardKey::KEY_ONE,
+    KeyboardKey::KEY_TWO,
+    KeyboardKey::KEY_THREE,
+    KeyboardKey::KEY_Q,
+    KeyboardKey::KEY_W,
+    KeyboardKey::KEY_E,
+    KeyboardKey::KEY_A,
+    KeyboardKey::KEY_S,
+    KeyboardKey::KEY_D,
+    KeyboardKey::KEY_Z,
+    KeyboardKey::KEY_C,
+    KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_R,
+    KeyboardKey::KEY_F,
+    KeyboardKey::KEY_V,
+];
+
+// --- layout definition ------------------------------------------------------
+
+/// A built-in layout of the keypad on the keyboard
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Layout {
+    /// Each key of the keypad on the key of the same digit or letter
+    #[default]
+    Hex,
+    /// The grid of the keypad on the left of a QWERTY keyboard, as most other
+    /// emulators map it
+    Qwerty,
+}
+
+impl Layout {
+    /// All the layouts, in the order the settings menu cycles through them
+    pub const ALL: [Layout; 2] = [Layout::Hex, Layout::Qwerty];
+
+    /// Parses a layout from its name, being `hex` or `qwerty`
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the layout (case insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Gets the name of the layout
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Hex => "hex",
+            Layout::Qwerty => "qwerty",
+        }
+    }
+
+    /// Gets the keyboard keys of the keypad, indexed by the key on the keypad
+    pub fn keys(self) -> [KeyboardKey; NUM_KEYPAD_KEYS] {
+        match self {
+            Layout::Hex => HEX_LAYOUT,
+            Layout::Qwerty => QWERTY_LAYOUT,
+        }
+    }
+
+    /// Finds the layout the keyboard keys of the keypad follow, if any
+    ///
+    /// # Params
+    ///
+    /// - `keys` - The keyboard keys, indexed by the key on the keypad
+    pub fn matching(keys: &[KeyboardKey; NUM_KEYPAD_KEYS]) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.keys() == *keys)
+    }
+}
+
+// --- keymap definition ------------------------------------------------------
+
+/// The keyboard keys of the keypad, which can be rebound from within the
+/// application
+///
+/// Keys bound in the application are persisted in the sidecar of the ROM and
+/// take precedence over the keys of the configuration, as different ROMs use
+/// different parts of the keypad.
+pub(crate) struct Keymap {
+    /// The keyboard keys of the keypad, indexed by the key on the keypad
+    keys: [KeyboardKey; NUM_KEYPAD_KEYS],
+    /// The keyboard keys of the configuration, restored on a reset
+    defaults: [KeyboardKey; NUM_KEYPAD_KEYS],
+    /// Flag indicating whether the keys were bound for this ROM in particular
+    per_rom: bool,
+    /// The keypad key waiting to be bound, if binding is in progress
+    binding: Option<usize>,
+}
+
+impl Keymap {
+    /// Loads the keys bound for the ROM from its sidecar, falling back to the
+    /// keys of the configuration
+    ///
+    /// Lines that can't be parsed are skipped
+    ///
+    /// # Params
+    ///
+    /// - `sidecar` - The sidecar of the ROM
+    /// - `defaults` - The keyboard keys of the configuration
+    pub fn load(sidecar: &Sidecar, defaults: [KeyboardKey; NUM_KEYPAD_KEYS]) -> Self {
+        let mut keys = defaults;
+        let lines = sidecar.section(KEYS_SECTION);
+
+        for line in lines {
+            let Some((index, name)) = line.split_once('=') else {
+                continue;
+            };
+
+            let index = usize::from_str_radix(index.trim(), 16).ok();
+            if let (Some(index), Some(key)) = (index, parse_key(name.trim())) {
+                if index < NUM_KEYPAD_KEYS {
+                    keys[index] = key;
+                }
+            }
+        }
+
+        Self {
+            keys,
+            defaults,
+            per_rom: !lines.is_empty(),
+            binding: None,
+        }
+    }
+
+    /// Takes the keys bound for another ROM from its sidecar, falling back to
+    /// the keys of the configuration, such as when the ROM is replaced
+    ///
+    /// # Params
+    ///
+    /// - `sidecar` - The sidecar of the other ROM
+    pub fn reload(&mut self, sidecar: &Sidecar) {
+        *self = Self::load(sidecar, self.defaults);
+    }
+
+    /// Stores the keys in the sidecar of the ROM, if they were bound for it
+    ///
+    /// # Params
+    ///
+    /// - `sidecar` - The sidecar of the ROM
+    pub fn store(&self, sidecar: &mut Sidecar) {
+        let lines = if self.per_rom {
+            self.keys
+                .iter()
+                .enumerate()
+                .filter_map(|(index, &key)| key_name(key).map(|name| format!("{index:x} = {name}")))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        sidecar.set_section(KEYS_SECTION, lines);
+    }
+
+    /// Gets the keyboard keys of the keypad, indexed by the key on the keypad
+    pub fn keys(&self) -> &[KeyboardKey; NUM_KEYPAD_KEYS] {
+        &self.keys
+    }
+
+    /// Checks whether binding is in progress
+    pub fn is_binding(&self) -> bool {
+        self.binding.is_some()
+    }
+
+    /// Starts binding the keypad keys one by one, starting at `0`
+    pub fn start_binding(&mut self) {
+        self.binding = Some(0);
+    }
+
+    /// Stops binding, keeping the keys bound so far
+    pub fn stop_binding(&mut self) {
+        self.binding = None;
+    }
+
+    /// Replaces the keys of the configuration with the ones of a layout, and
+    /// the keys in use with them, including the keys bound for the ROM
+    ///
+    /// # Params
+    ///
+    /// - `layout` - The layout of the keys
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.defaults = layout.keys();
+        self.reset();
+    }
+
+    /// Restores the keys of the configuration, which stops binding as well
+    pub fn reset(&mut self) {
+        self.keys = self.defaults;
+        self.per_rom = false;
+        self.binding = None;
+    }
+
+    /// Binds a keyboard key to the keypad key waiting to be bound, moving on
+    /// to the next keypad key
+    ///
+    /// Returns `true` if the key was bound, `false` if no binding is in
+    /// progress or the key can't be mapped to the keypad
+    ///
+    /// # Params
+    ///
+    /// - `key` - The keyboard key which was pressed
+    pub fn bind(&mut self, key: KeyboardKey) -> bool {
+        let Some(index) = self.binding.filter(|_| key_name(key).is_some()) else {
+            return false;
+        };
+
+        self.keys[index] = key;
+        self.per_rom = true;
+        self.binding = Some(index + 1).filter(|&next| next < NUM_KEYPAD_KEYS);
+
+        true
+    }
+
+    /// Gets the prompt asking for the next key, if binding is in progress
+    pub fn prompt(&self) -> Option<String> {
+        self.binding.map(|index| {
+            let current = key_name(self.keys[index]).unwrap_or("?");
+            format!("Press the key for {index:X} (now {current})")
+        })
+    }
+}
+
+impl InputSource for Keymap {
+    /// Reads the keypad from the keyboard keys bound to it
+    fn poll(&mut self, rl: &RaylibHandle) -> u16 {
+        self.keys.poll(rl)
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Parses the name of a keyboard key, such as `X`, `7`, `KP_4` or `SPACE`
+///
+/// # Params
+///
+/// - `name` - The name of the key (case insensitive)
+pub(crate) fn parse_key(name: &str) -> Option<KeyboardKey> {
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
+
+/// Gets the name of a keyboard key, if it can be mapped to the keypad
+///
+/// # Params
+///
+/// - `key` - The keyboard key
+pub(crate) fn key_name(key: KeyboardKey) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|&&(_, named)| named == key)
+        .map(|&(name, _)| name)
+}
+
+/// Parses the name of a gamepad button, such as `UP`, `A` or `START`
+///
+/// # Params
+///
+/// - `name` - The name of the button (case insensitive)
+pub(crate) fn parse_button(name: &str) -> Option<GamepadButton> {
+    BUTTON_NAMES
+        .iter()
+        .find(|(button_name, _)| button_name.eq_ignore_ascii_case(name))
+        .map(|&(_, button)| button)
+}
@@ -1,227 +1,275 @@
 //! This module, `keet_8`, contains the actual implementation of our
 //! application.
-//! 
+//!
 //! This is done purposely seperately from the main binary crate to have a form
 //! of abstraction from the main routine.
-//! 
-//! This module only exposes one function, that being the `run` function, which
-//! is to be called from `main`.
+//!
+//! The interpreter core (the `Emulator`) lives in the `keet8-core` crate and
+//! is re-exported here. It is always available and has no native
+//! dependencies, so it can be embedded in other frontends. The raylib
+//! frontend, along with the `run` function which is to be called from `main`,
+//! is only compiled with the `frontend` feature (enabled by default). The
+//! `wasm` feature adds JavaScript bindings of the core for running in a
+//! browser.
+//!
+//! Entry points and deprecated shims kept for earlier releases live in the
+//! `compat` module, which also describes the versioning policy.
 
-mod emulator;
-mod error;
+#[cfg(feature = "frontend")]
+mod accessibility;
+#[cfg(feature = "frontend")]
+mod analysis;
+#[cfg(feature = "frontend")]
+mod archive;
+#[cfg(feature = "frontend")]
+mod application;
+mod assembler;
+#[cfg(feature = "frontend")]
+mod audio;
+#[cfg(feature = "frontend")]
+mod bench;
+#[cfg(feature = "frontend")]
+mod bug_report;
+#[cfg(feature = "frontend")]
+mod cheats;
+#[cfg(feature = "frontend")]
+pub mod compat;
+#[cfg(feature = "frontend")]
+mod config;
+#[cfg(feature = "frontend")]
+mod crash_report;
+#[cfg(feature = "frontend")]
+mod console;
+#[cfg(feature = "frontend")]
+mod debugger;
+#[cfg(feature = "frontend")]
+mod disassembler;
+mod env;
+#[cfg(feature = "frontend")]
+mod hash;
+#[cfg(feature = "frontend")]
+mod headless;
+mod octo;
+#[cfg(feature = "frontend")]
+mod info;
+#[cfg(feature = "frontend")]
+mod input;
+#[cfg(feature = "frontend")]
+mod input_log;
+#[cfg(feature = "frontend")]
+mod input_script;
+#[cfg(feature = "frontend")]
+mod instance;
+#[cfg(feature = "frontend")]
+mod keymap;
+#[cfg(feature = "frontend")]
+mod lockstep;
+#[cfg(feature = "frontend")]
+mod netplay;
+#[cfg(feature = "frontend")]
+mod options;
+#[cfg(feature = "frontend")]
+mod osd;
+#[cfg(feature = "frontend")]
+mod overlay;
+#[cfg(feature = "frontend")]
+mod palette;
+#[cfg(feature = "frontend")]
+mod pause_menu;
 mod prelude;
+#[cfg(feature = "frontend")]
+mod profiler;
+#[cfg(feature = "frontend")]
+mod recent;
+#[cfg(feature = "frontend")]
+mod recorder;
+#[cfg(feature = "frontend")]
+mod remote;
+#[cfg(feature = "frontend")]
+mod rom_chooser;
+#[cfg(feature = "frontend")]
+mod rom_db;
+#[cfg(feature = "frontend")]
+mod roms;
+#[cfg(feature = "frontend")]
+mod scheduler;
+#[cfg(feature = "frontend")]
+mod screen;
+#[cfg(feature = "scripting")]
+mod scripting;
+#[cfg(feature = "frontend")]
+mod settings;
+#[cfg(feature = "frontend")]
+mod sidecar;
+#[cfg(feature = "frontend")]
+mod state_diff;
+#[cfg(feature = "frontend")]
+mod state_dump;
+mod symbols;
+#[cfg(feature = "frontend")]
+mod timing;
+#[cfg(feature = "frontend")]
+mod trace_log;
+#[cfg(feature = "frontend")]
+mod unknown_opcodes;
+#[cfg(feature = "frontend")]
+mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "frontend")]
+mod worker;
 
-use emulator::Emulator;
-use prelude::*;
+pub use assembler::{assemble, assemble_program};
+#[cfg(feature = "frontend")]
+pub use compat::run;
+pub use env::{Env, Observation};
+#[cfg(feature = "frontend")]
+pub use keet8_core::OsRandom;
+pub use keet8_core::opcode::{disassemble, AddressMode, Instruction, OpCode};
+pub use keet8_core::{
+    is_two_page_rom, AccessCounts, AudioSink, Collisions, Display, Emulator, Event, FrameBuffer,
+    FrameSummary, HostCall, InitPolicy, Key, Lcg, Pixels, Platform, Quirks, RandomSource,
+    Renderer, Resolution, ScriptedRandom, StepOutcome, WatchHit, AUDIO_PATTERN_SIZE, FRAME_ROWS,
+    HIRES_HEIGHT, HIRES_WIDTH, LORES_HEIGHT, LORES_WIDTH, MAX_MEMORY_SIZE, MEMORY_SIZE, STACK_SIZE,
+    TWO_PAGE_ENTRY, TWO_PAGE_HEIGHT,
+};
+pub use octo::assemble_octo;
+#[cfg(feature = "frontend")]
+pub use options::Options;
+pub use prelude::{Keet8Error, Result};
+pub use symbols::Symbols;
+#[cfg(feature = "wasm")]
+pub use wasm::WebEmulator;
 
-use raylib::prelude::*;
+#[cfg(feature = "frontend")]
+use application::Application;
+#[cfg(feature = "frontend")]
+use config::Config;
+#[cfg(feature = "frontend")]
+use options::Mode;
 
 // --- keet-8 interface -------------------------------------------------------
 
-/// Runs the application
+/// Starts the application with parsed options, running, disassembling or
+/// summarizing the ROM, or running one of the other subcommands
 ///
 /// # Params
 ///
-/// - `args` - The command-line arguments
-/// 
+/// - `options` - The options of the application
+///
 /// # Examples
-/// 
+///
 /// ```rust
-/// let args = std::env::args()
+/// let args: Vec<String> = std::env::args()
 ///     .collect();
-/// 
-/// if let Err(e) = keet_8::run(args) {
+///
+/// let result = keet_8::Options::parse(&args)
+///     .and_then(|options| keet_8::start(&options));
+///
+/// if let Err(e) = result {
 ///     eprintln!("{e}");
 /// }
 /// ```
 ///
 /// # Errors
 ///
-/// - If no ROM file was provided
 /// - If there was an error when loading the ROM
 /// - If there was an error during runtime
-pub fn run(args: Vec<String>) -> Result<()> {
-    if args.len() < 2 {
-        return Err(Keet8Error::NoROMFile);
-    }
-
-    let mut app = Application::new(&args[1])?;
-    app.run()
-}
-
-// --- constants --------------------------------------------------------------
-
-/// Represents the title of the emulator
-const TITLE: &'static str = "Keet-8";
-/// Represents the current version of the emulator
-const VERSION: &'static str = "v1.0.0";
-
-/// Represents the width of the window
-const WINDOW_WIDTH: i32 = 1024;
-/// Represents the height of the window
-const WINDOW_HEIGHT: i32 = 512;
-
-/// The delay in seconds between CPU cycles for the emulator (60FPS or 16.67ms)
-const EMU_STEP_DELAY: f32 = 1.0 / 60.0;
-
-// --- application definition -------------------------------------------------
-
-struct Application {
-    /// The handle to the raylib context
-    rl: RaylibHandle,
-    /// The thread on which raylib is running on
-    thread: RaylibThread,
-    /// Flag indicating whether the application is still running
-    is_running: bool,
-    /// Flag indicating whether debug information is to be drawn on the window
-    debug: bool,
-    /// The actual Chip-8 emulator
-    emulator: Emulator,
-    /// The current time in seconds for the CPU ticks
-    curr_time: f32,
-}
-
-impl Application {
-    /// Creates an instance of the application and initializes raylib
-    ///
-    /// # Params
-    ///
-    /// - `rom_file` - The filepath to the ROM file
-    ///
-    /// # Errors
-    ///
-    /// If an error occured when loading the ROM file
-    pub fn new(rom_file: &str) -> Result<Self> {
-        let (mut rl, thread) = if cfg!(debug_assertions) {
-            let window_title = format!("{TITLE} - {VERSION} (debug)");
-            raylib::init()
-                .size(WINDOW_WIDTH, WINDOW_HEIGHT)
-                .title(&window_title)
-                .vsync()
-                .msaa_4x()
-                .resizable()
-                .build()
-
-        // We don't want logging for release builds
-        } else {
-            let window_title = format!("{TITLE} - {VERSION}");
-            raylib::init()
-                .size(WINDOW_WIDTH, WINDOW_HEIGHT)
-                .title(&window_title)
-                .vsync()
-                .msaa_4x()
-                .resizable()
-                .log_level(TraceLogLevel::LOG_NONE)
-                .build()
-        };
-
-        rl.set_window_min_size(WINDOW_WIDTH, WINDOW_HEIGHT);
-
-        Ok(Self {
-            rl,
-            thread,
-            is_running: true,
-            debug: false,
-            emulator: Emulator::new(rom_file)?,
-            curr_time: 0.0
-        })
-    }
+/// - If the ROM reported a failed test through the host-call extension
+/// - If there was an error when loading the symbol file
+/// - If the configuration file could not be read or is invalid
+/// - If the ROM database could not be read or is invalid
+#[cfg(feature = "frontend")]
+pub fn start(options: &Options) -> Result<()> {
+    init_logger(options.verbosity);
 
-    /// Runs the application
-    ///
-    /// # Errors
-    ///
-    /// If an error occured during runtime of the emulator
-    pub fn run(&mut self) -> Result<()> {
-        while self.is_running {
-            self.on_update()?;
-            self.on_render();
+    match options.mode {
+        Mode::Run if options.threaded => worker::run_threaded(options, &Config::load(options)?),
+        Mode::Run => {
+            let config = Config::load(options)?;
+            let mut app = Application::new(options, &config)?;
+            app.run()
         }
+        Mode::Disassemble => {
+            let symbols = match &options.symbol_file {
+                Some(symbol_file) => Symbols::load(symbol_file)?,
+                None => Symbols::default(),
+            };
 
-        Ok(())
-    }
-
-    /// Called once per frame to update the logic of the application
-    ///
-    /// # Errors
-    ///
-    /// If an error has occured during runtime of the emulator
-    fn on_update(&mut self) -> Result<()> {
-        // Step the emulator if timer has met the delay time 
-        if self.curr_time >= EMU_STEP_DELAY {
-            self.process_input();
-            self.emulator.step()?;
-
-            self.curr_time -= EMU_STEP_DELAY;
-
-        // Otherwise accumelate the timer
-        } else {
-            self.curr_time += self.rl.get_frame_time();
+            disassembler::disassemble(
+                &options.rom_file,
+                &symbols,
+                options.disassembly_format,
+                Config::load(options)?.load_addr,
+            )
         }
-
-        // Close the application if the escape key has been pressed
-        if self.rl.window_should_close() {
-            self.is_running = false;
+        Mode::Info => info::print_info(
+            &options.rom_file,
+            options.rom_database.as_deref(),
+            Config::load(options)?.load_addr,
+        ),
+        Mode::TimingCheck => timing::print_timing_check(Config::load(options)?.cpu_speed),
+        Mode::Builtins => {
+            roms::print_builtin_roms();
+            Ok(())
         }
-
-        // Show debugging information when F3 has been pressed (like Minecraft)
-        if self.rl.is_key_pressed(KeyboardKey::KEY_F3) {
-            self.debug = !self.debug;
+        Mode::Verify => verify::print_verify(),
+        Mode::Bench(frames) => bench::print_bench(
+            &options.rom_file,
+            &Config::load(options)?,
+            frames,
+            options.seed,
+            options.strict,
+        ),
+        Mode::Headless => headless::print_headless(
+            &options.rom_file,
+            &Config::load(options)?,
+            options.frames.unwrap_or(headless::DEFAULT_HEADLESS_FRAMES),
+            options.seed,
+            options.strict,
+            options.input_script_file.as_deref(),
+        ),
+        Mode::Lockstep => lockstep::print_lockstep(
+            &options.rom_file,
+            &Config::load(options)?,
+            options.reference_trace.as_deref().unwrap_or_default(),
+            options.seed,
+            options.strict,
+        ),
+        Mode::DiffStates => {
+            state_diff::print_state_diff(&options.state_files[0], &options.state_files[1])
         }
-
-        // Make the window fullsreen when F11 is pressed
-        if self.rl.is_key_pressed(KeyboardKey::KEY_F11) {
-            if self.rl.is_window_fullscreen() {
-                self.rl.toggle_fullscreen();
-            } else {
-                let monitor = raylib::window::get_current_monitor();
-                let width = raylib::window::get_monitor_width(monitor);
-                let height = raylib::window::get_monitor_height(monitor);
-
-                self.rl.set_window_size(width, height);
-                self.rl.toggle_fullscreen();
-            }
+        Mode::DiffTraces => {
+            trace_log::print_trace_diff(&options.trace_files[0], &options.trace_files[1])
         }
-
-        Ok(())
     }
+}
 
-    /// Called once per frame to draw everything to the window
-    fn on_render(&mut self) {
-        let mut d = self.rl.begin_drawing(&self.thread);
-        d.clear_background(Color::BLACK);
+// --- utility functions ------------------------------------------------------
 
-        self.emulator.draw_buffer(&mut d);
-        if self.debug {
-            d.draw_fps(5, 5);
-        }
-    }
-
-    /// Processes the keyboard input
-    fn process_input(&mut self) {
-        const NUM_KEYS: usize = 16;
-        const KEYBOARD_KEY: [KeyboardKey; NUM_KEYS] = [
-            KeyboardKey::KEY_ZERO,
-            KeyboardKey::KEY_ONE,
-            KeyboardKey::KEY_TWO,
-            KeyboardKey::KEY_THREE,
-            KeyboardKey::KEY_FOUR,
-            KeyboardKey::KEY_FIVE,
-            KeyboardKey::KEY_SIX,
-            KeyboardKey::KEY_SEVEN,
-            KeyboardKey::KEY_EIGHT,
-            KeyboardKey::KEY_NINE,
-            KeyboardKey::KEY_A,
-            KeyboardKey::KEY_B,
-            KeyboardKey::KEY_C,
-            KeyboardKey::KEY_D,
-            KeyboardKey::KEY_E,
-            KeyboardKey::KEY_F,
-        ];
+/// Sends the logs of the emulator to stderr, showing the warnings and errors
+/// and, with `-v` and `-vv`, the info and debug messages too
+///
+/// `RUST_LOG` takes precedence over the verbosity, such as
+/// `RUST_LOG=keet_8::netplay=debug` to only see the debug messages of netplay.
+/// The logs of raylib are controlled separately.
+///
+/// # Params
+///
+/// - `verbosity` - The number of times `-v` was given
+#[cfg(feature = "frontend")]
+fn init_logger(verbosity: u8) {
+    let level = match verbosity {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Info,
+        _ => log::LevelFilter::Debug,
+    };
 
-        (0..NUM_KEYS).for_each(|k| {
-            self.emulator
-                .set_key(k, self.rl.is_key_down(KEYBOARD_KEY[k]) as u8)
-        });
-    }
+    // The logger may already be set when started more than once
+    let _ = env_logger::Builder::new()
+        .filter_level(level)
+        .format_timestamp(None)
+        .format_target(false)
+        .parse_default_env()
+        .try_init();
 }
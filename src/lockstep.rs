@@ -0,0 +1,129 @@
+use crate::config::Config;
+use crate::prelude::*;
+use crate::roms::read_rom;
+use crate::trace_log::{read_trace, Snapshot};
+
+use keet8_core::{Emulator, InitPolicy, Lcg, StepOutcome};
+
+// --- lockstep interface -----------------------------------------------------
+
+/// Runs the ROM without a window in lockstep with a reference trace in the
+/// Octo format, stopping at the first instruction whose state differs and
+/// writing both states to stdout
+///
+/// Every line of the reference holds the state before an instruction, which
+/// is compared against the emulator before it executes the same instruction.
+/// Only the fields the reference has are compared, so traces of interpreters
+/// leaving out the timers or the stack still line up. The memory and the
+/// screen are compared through the `mem` and `fb` hashes of the state format,
+/// when the reference has them. The timers are ticked every 60th of the CPU
+/// speed, as in the window.
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+/// - `config` - The configuration, with the options already applied
+/// - `trace_file` - The filepath to the reference trace
+/// - `seed` - The seed of the random numbers, if not the default one
+/// - `strict` - Whether unknown opcodes raise an error
+///
+/// # Errors
+///
+/// - If an error occured when loading the ROM file or the reference trace
+/// - If an error occured whilst running the ROM
+/// - If the emulator diverges from the reference
+pub(crate) fn print_lockstep(
+    rom_file: &str,
+    config: &Config,
+    trace_file: &str,
+    seed: Option<u32>,
+    strict: bool,
+) -> Result<()> {
+    let reference = read_trace(trace_file)?;
+    let hashes = reference.iter().any(|line| line.contains("mem=") || line.contains("fb="));
+
+    // The ROM is loaded once the memory has the size of the platform
+    let mut emulator = Emulator::without_rom(InitPolicy::Zeroed);
+    emulator.set_quirks(config.quirks);
+    emulator.set_platform(config.platform);
+    emulator.set_two_page(config.two_page);
+    emulator.set_load_address(config.load_addr)?;
+    emulator.load_rom(&read_rom(rom_file)?)?;
+    emulator.set_strict(strict);
+    if let Some(seed) = seed {
+        emulator.set_random_source(Lcg::new(seed));
+    }
+
+    let steps_per_tick = (config.cpu_speed / 60).max(1) as usize;
+    for (step, expected) in reference.iter().enumerate() {
+        let actual = if emulator.is_halted() {
+            "(halted)".to_string()
+        } else if hashes {
+            Snapshot::capture(&emulator).with_hashes(&emulator).octo_line()
+        } else {
+            Snapshot::capture(&emulator).octo_line()
+        };
+
+        let differences = differences(expected, &actual);
+        if !differences.is_empty() {
+            if step > 0 {
+                println!("  {:>8}  {}", step, reference[step - 1]);
+            }
+            println!("- {:>8}  {expected}", step + 1);
+            println!("+ {:>8}  {actual}", step + 1);
+            println!("Differs in: {}", differences.join(", "));
+
+            return Err(Keet8Error::TracesDiverged(step + 1));
+        }
+
+        if let StepOutcome::Halted = emulator.step()? {
+            continue;
+        }
+        if (step + 1) % steps_per_tick == 0 {
+            emulator.tick_timers();
+        }
+    }
+
+    println!("The emulator matches the reference ({} steps)", reference.len());
+    Ok(())
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Gets the names of the fields of a reference line which the line of the
+/// emulator doesn't have or has a different value for
+///
+/// The values are compared as hexadecimal numbers where they are ones, so
+/// that differences in padding don't count.
+///
+/// # Params
+///
+/// - `expected` - The line of the reference
+/// - `actual` - The line of the emulator
+fn differences(expected: &str, actual: &str) -> Vec<String> {
+    let actual = fields(actual);
+
+    fields(expected)
+        .into_iter()
+        .filter(|(name, value)| {
+            let other = actual.iter().find(|(other, _)| other == name).map(|(_, value)| value);
+            other.is_none_or(|other| {
+                match (u64::from_str_radix(value, 16), u64::from_str_radix(other, 16)) {
+                    (Ok(a), Ok(b)) => a != b,
+                    _ => !value.eq_ignore_ascii_case(other),
+                }
+            })
+        })
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Splits a line of the Octo format into its `name=value` fields, skipping
+/// anything else
+///
+/// # Params
+///
+/// - `line` - The line
+fn fields(line: &str) -> Vec<(&str, &str)> {
+    line.split_whitespace().filter_map(|field| field.split_once('=')).collect()
+}
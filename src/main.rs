@@ -6,6 +6,17 @@
 //! # Examples
 //! 
 //! `cargo run path/to/rom`
+//! 
+//! `cargo run -- --hostcall path/to/rom`
+//! 
+//! `cargo run -- --disassemble path/to/rom`
+//! 
+//! `cargo run -- info path/to/rom`
+//! 
+//! `cargo run -- --builtin ibm-logo`
+
+use colored::Colorize;
+use keet_8::Keet8Error;
 
 use std::process::ExitCode;
 
@@ -17,8 +28,13 @@ fn main() -> ExitCode {
         .collect();
 
     if let Err(e) = keet_8::run(args) {
-        eprintln!("{e}");
-        return ExitCode::FAILURE;
+        eprintln!("{} {}", "[ERROR]:".bold().red(), e.message());
+
+        // Tests failed through the host-call extension report their own code
+        return match e {
+            Keet8Error::TestFailed(code) => ExitCode::from(code.max(1)),
+            _ => ExitCode::FAILURE,
+        };
     }
 
     ExitCode::SUCCESS
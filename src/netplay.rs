@@ -0,0 +1,185 @@
+use crate::prelude::*;
+
+use log::info;
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+// --- constants --------------------------------------------------------------
+
+/// The bytes starting the handshake, identifying the protocol and its version
+const MAGIC: &[u8; 5] = b"K8NP1";
+/// The time to wait for the keys of the other player before giving up
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// --- netplay definition -----------------------------------------------------
+
+/// A connection to another instance running the same ROM, exchanging the
+/// keys of both players every frame in lockstep
+///
+/// The host picks the seed of the random numbers and the number of
+/// instructions per frame, so both emulators run the exact same frames with
+/// the keys of both players combined. A frame only runs once the keys of the
+/// other player arrived, so the slower instance sets the pace.
+pub(crate) struct Netplay {
+    /// The connection to the other player
+    stream: TcpStream,
+    /// The seed of the random numbers, picked by the host
+    seed: u32,
+    /// The number of instructions executed per frame, picked by the host
+    steps_per_frame: u32,
+}
+
+impl Netplay {
+    /// Waits for the other player to connect, sending them the seed and the
+    /// number of instructions per frame
+    ///
+    /// # Params
+    ///
+    /// - `port` - The TCP port to listen on
+    /// - `rom_hash` - The hash of the ROM, which both players have to run
+    /// - `seed` - The seed of the random numbers
+    /// - `steps_per_frame` - The number of instructions executed per frame
+    ///
+    /// # Errors
+    ///
+    /// - If the port could not be listened on
+    /// - If the other player runs another ROM or the connection failed
+    pub fn host(port: u16, rom_hash: u64, seed: u32, steps_per_frame: u32) -> Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(failed)?;
+
+        println!("Waiting for the other player on port {port}");
+        let (stream, peer) = listener.accept().map_err(failed)?;
+        info!("{peer} connected");
+
+        let mut netplay = Self::open(stream, seed, steps_per_frame)?;
+        let mut handshake = Vec::from(*MAGIC);
+        handshake.extend_from_slice(&rom_hash.to_be_bytes());
+        handshake.extend_from_slice(&seed.to_be_bytes());
+        handshake.extend_from_slice(&steps_per_frame.to_be_bytes());
+        netplay.stream.write_all(&handshake).map_err(failed)?;
+
+        netplay.check_handshake(rom_hash)?;
+        Ok(netplay)
+    }
+
+    /// Connects to the host, taking the seed and the number of instructions
+    /// per frame from them
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address of the host, such as `192.168.1.2:8642`
+    /// - `rom_hash` - The hash of the ROM, which both players have to run
+    ///
+    /// # Errors
+    ///
+    /// - If the host could not be reached
+    /// - If the host runs another ROM or the connection failed
+    pub fn connect(addr: &str, rom_hash: u64) -> Result<Self> {
+        let stream = TcpStream::connect(addr).map_err(failed)?;
+        let mut netplay = Self::open(stream, 0, 0)?;
+
+        // Both players send their handshake before checking the other one,
+        // so that both can tell what went wrong
+        let mut handshake = Vec::from(*MAGIC);
+        handshake.extend_from_slice(&rom_hash.to_be_bytes());
+        netplay.stream.write_all(&handshake).map_err(failed)?;
+        netplay.check_handshake(rom_hash)?;
+
+        let mut settings = [0; 4];
+        netplay.stream.read_exact(&mut settings).map_err(failed)?;
+        netplay.seed = u32::from_be_bytes(settings);
+        netplay.stream.read_exact(&mut settings).map_err(failed)?;
+        netplay.steps_per_frame = u32::from_be_bytes(settings);
+
+        info!("Connected to {addr}");
+        Ok(netplay)
+    }
+
+    /// Gets the seed of the random numbers, picked by the host
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Gets the number of instructions executed per frame, picked by the host
+    pub fn steps_per_frame(&self) -> u32 {
+        self.steps_per_frame
+    }
+
+    /// Sends the keys of this player and waits for the ones of the other
+    /// player
+    ///
+    /// # Params
+    ///
+    /// - `keys` - The keys held down, with bit `K` set for key `K`
+    ///
+    /// # Errors
+    ///
+    /// If the other player disconnected or took too long to answer
+    pub fn exchange(&mut self, keys: u16) -> Result<u16> {
+        self.stream.write_all(&keys.to_be_bytes()).map_err(failed)?;
+
+        let mut other = [0; 2];
+        self.stream.read_exact(&mut other).map_err(failed)?;
+        Ok(u16::from_be_bytes(other))
+    }
+
+    /// Sets up the connection for the small messages sent every frame
+    ///
+    /// # Params
+    ///
+    /// - `stream` - The connection to the other player
+    /// - `seed` - The seed of the random numbers
+    /// - `steps_per_frame` - The number of instructions executed per frame
+    ///
+    /// # Errors
+    ///
+    /// If the connection could not be set up
+    fn open(stream: TcpStream, seed: u32, steps_per_frame: u32) -> Result<Self> {
+        stream.set_nodelay(true).map_err(failed)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(failed)?;
+
+        Ok(Self {
+            stream,
+            seed,
+            steps_per_frame,
+        })
+    }
+
+    /// Reads the start of the handshake of the other player, checking that
+    /// they speak the same protocol and run the same ROM
+    ///
+    /// # Params
+    ///
+    /// - `rom_hash` - The hash of the ROM of this player
+    ///
+    /// # Errors
+    ///
+    /// If the other player runs another ROM or the connection failed
+    fn check_handshake(&mut self, rom_hash: u64) -> Result<()> {
+        let mut handshake = [0; MAGIC.len() + 8];
+        self.stream.read_exact(&mut handshake).map_err(failed)?;
+
+        let (magic, hash) = handshake.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(Keet8Error::NetplayFailed("the other player isn't keet-8".to_string()));
+        }
+        if hash != rom_hash.to_be_bytes() {
+            return Err(Keet8Error::NetplayFailed("the other player runs another ROM".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Wraps an I/O error of the connection
+///
+/// # Params
+///
+/// - `e` - The I/O error
+fn failed(e: std::io::Error) -> Keet8Error {
+    Keet8Error::NetplayFailed(e.to_string())
+}
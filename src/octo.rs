@@ -0,0 +1,728 @@
+use crate::prelude::*;
+
+use keet8_core::PROG_ADDR;
+
+use std::collections::HashMap;
+
+// --- constants --------------------------------------------------------------
+
+/// The label execution starts at, jumped to from `0x200` unless it is there
+const MAIN_LABEL: &str = "main";
+
+/// The raw opcode of `jump`, with the address to be filled in
+const JUMP: u16 = 0x1000;
+/// The raw opcode of a call, with the address to be filled in
+const CALL: u16 = 0x2000;
+
+// --- octo interface ---------------------------------------------------------
+
+/// Assembles a program written in the dialect of Octo, the most popular
+/// modern CHIP-8 toolchain, into the bytes of a ROM, loaded at `0x200`
+///
+/// The statements are separated by whitespace rather than lines, with `#`
+/// starting a comment. Labels are defined with `: name` (or `:name`), and
+/// execution starts at `main`, jumped to from `0x200` unless it is there.
+/// Besides the instructions (`v0 += 5`, `i := sprite`, `sprite v0 v1 5`,
+/// `if v0 == 3 then ...`), the structured `if ... begin ... else ... end` and
+/// `loop ... while ... again`, `:const`, `:alias`, `:org`, `:byte` and
+/// `:call` are supported, a bare label calls it and a bare number is written
+/// as a byte. Macros, `:calc` and `:unpack` aren't supported.
+///
+/// # Params
+///
+/// - `source` - The source of the program
+///
+/// # Examples
+///
+/// ```rust
+/// let rom = keet_8::assemble_octo(": main v0 += 5 loop again").ok();
+/// assert_eq!(rom, Some(vec![0x70, 0x05, 0x12, 0x02]));
+/// ```
+///
+/// # Errors
+///
+/// If a statement couldn't be assembled, with the error holding its line
+/// number
+pub fn assemble_octo(source: &str) -> Result<Vec<u8>> {
+    let tokens = tokenize(source);
+    let (rom, main) = OctoAssembler::new(&tokens, false).run()?;
+
+    // The jump to `main` moves everything after it, so the program is
+    // assembled again behind it
+    match main {
+        Some(addr) if addr != PROG_ADDR => Ok(OctoAssembler::new(&tokens, true).run()?.0),
+        _ => Ok(rom),
+    }
+}
+
+// --- token definition -------------------------------------------------------
+
+/// A token of the source, with the line it is on for reporting errors
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    /// The text of the token
+    text: &'a str,
+    /// The number of the line the token is on, from `1`
+    line: usize,
+}
+
+// --- condition definition ---------------------------------------------------
+
+/// The condition of an `if` or a `while`
+#[derive(Clone, Copy)]
+enum Condition {
+    /// `vX == NN`
+    EqualsByte(u16, u16),
+    /// `vX != NN`
+    DiffersByte(u16, u16),
+    /// `vX == vY`
+    Equals(u16, u16),
+    /// `vX != vY`
+    Differs(u16, u16),
+    /// `vX key`, the key in `vX` being held down
+    Key(u16),
+    /// `vX -key`, the key in `vX` not being held down
+    NoKey(u16),
+}
+
+impl Condition {
+    /// Gets the opposite condition
+    fn negate(self) -> Self {
+        match self {
+            Self::EqualsByte(x, nn) => Self::DiffersByte(x, nn),
+            Self::DiffersByte(x, nn) => Self::EqualsByte(x, nn),
+            Self::Equals(x, y) => Self::Differs(x, y),
+            Self::Differs(x, y) => Self::Equals(x, y),
+            Self::Key(x) => Self::NoKey(x),
+            Self::NoKey(x) => Self::Key(x),
+        }
+    }
+
+    /// Gets the skip instruction which skips the next instruction unless the
+    /// condition holds
+    fn skip_unless(self) -> u16 {
+        match self {
+            Self::EqualsByte(x, nn) => 0x4000 | x << 8 | nn,
+            Self::DiffersByte(x, nn) => 0x3000 | x << 8 | nn,
+            Self::Equals(x, y) => 0x9000 | x << 8 | y << 4,
+            Self::Differs(x, y) => 0x5000 | x << 8 | y << 4,
+            Self::Key(x) => 0xE0A1 | x << 8,
+            Self::NoKey(x) => 0xE09E | x << 8,
+        }
+    }
+}
+
+// --- block definition -------------------------------------------------------
+
+/// A structured block being assembled, whose jumps are filled in once its
+/// end is known
+enum Block {
+    /// `if ... begin`, jumping past the block (or to `else`) when the
+    /// condition doesn't hold
+    If(usize),
+    /// `else`, jumping past the block from the end of the `if` branch
+    Else(usize),
+    /// `loop`, with the address it starts at and the jumps out of the loop
+    /// made by `while`
+    Loop(u16, Vec<usize>),
+}
+
+// --- octo assembler definition ----------------------------------------------
+
+/// The state of assembling a program in the dialect of Octo
+struct OctoAssembler<'a> {
+    /// The tokens of the source
+    tokens: &'a [Token<'a>],
+    /// The index of the next token
+    pos: usize,
+    /// The bytes of the ROM assembled so far
+    rom: Vec<u8>,
+    /// The addresses of the labels
+    labels: HashMap<&'a str, u16>,
+    /// The values of the constants defined with `:const`
+    constants: HashMap<&'a str, u16>,
+    /// The registers named with `:alias`
+    aliases: HashMap<&'a str, u16>,
+    /// The offsets of the opcodes whose address is a label, with the token
+    /// of the label, filled in once every label is known
+    fixups: Vec<(usize, Token<'a>)>,
+    /// The structured blocks being assembled, innermost last
+    blocks: Vec<Block>,
+}
+
+impl<'a> OctoAssembler<'a> {
+    /// Creates the state for assembling the tokens
+    ///
+    /// # Params
+    ///
+    /// - `tokens` - The tokens of the source
+    /// - `jump_to_main` - Whether the ROM starts with a jump to `main`
+    fn new(tokens: &'a [Token<'a>], jump_to_main: bool) -> Self {
+        let mut assembler = Self {
+            tokens,
+            pos: 0,
+            rom: Vec::new(),
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+        };
+
+        if jump_to_main {
+            let main = Token {
+                text: MAIN_LABEL,
+                line: 1,
+            };
+            assembler.fixups.push((0, main));
+            assembler.emit(JUMP);
+        }
+
+        assembler
+    }
+
+    /// Assembles every statement, returning the ROM and the address of
+    /// `main` if it is defined
+    ///
+    /// # Errors
+    ///
+    /// If a statement couldn't be assembled
+    fn run(mut self) -> Result<(Vec<u8>, Option<u16>)> {
+        while self.pos < self.tokens.len() {
+            self.statement()?;
+        }
+
+        if let Some(block) = self.blocks.last() {
+            let unclosed = match block {
+                Block::If(_) | Block::Else(_) => "begin without end",
+                Block::Loop(..) => "loop without again",
+            };
+            return Err(Keet8Error::InvalidAssembly(unclosed.to_string()));
+        }
+
+        for (at, token) in std::mem::take(&mut self.fixups) {
+            let addr = *self.labels.get(token.text).ok_or_else(|| invalid(token))?;
+            self.patch(at, addr);
+        }
+
+        let main = self.labels.get(MAIN_LABEL).copied();
+        Ok((self.rom, main))
+    }
+
+    /// Assembles the next statement
+    ///
+    /// # Errors
+    ///
+    /// If the statement couldn't be assembled
+    fn statement(&mut self) -> Result<()> {
+        let token = self.next()?;
+
+        match token.text {
+            ":" => {
+                let name = self.next()?;
+                self.define_label(name)?;
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.number()?;
+                self.constants.insert(name.text, value);
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let x = self.register()?;
+                self.aliases.insert(name.text, x);
+            }
+            ":org" => {
+                let addr = self.number()?;
+                let len = (addr as usize)
+                    .checked_sub(PROG_ADDR as usize)
+                    .filter(|&len| len >= self.rom.len())
+                    .ok_or_else(|| invalid(token))?;
+                self.rom.resize(len, 0);
+            }
+            ":byte" => {
+                let byte = self.byte()?;
+                self.rom.push(byte as u8);
+            }
+            ":call" => self.emit_address(CALL)?,
+            "clear" => self.emit(0x00E0),
+            "return" | ";" => self.emit(0x00EE),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(0x00C0 | n);
+            }
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-left" => self.emit(0x00FC),
+            "exit" => self.emit(0x00FD),
+            "lores" => self.emit(0x00FE),
+            "hires" => self.emit(0x00FF),
+            "audio" => self.emit(0xF002),
+            "jump" => self.emit_address(JUMP)?,
+            "jump0" => self.emit_address(0xB000)?,
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(0xD000 | x << 8 | y << 4 | n);
+            }
+            "bcd" => self.emit_register(0xF033)?,
+            "save" => self.emit_register(0xF055)?,
+            "load" => self.emit_register(0xF065)?,
+            "saveflags" => self.emit_register(0xF075)?,
+            "loadflags" => self.emit_register(0xF085)?,
+            "delay" => self.assign_from_register(0xF015)?,
+            "buzzer" => self.assign_from_register(0xF018)?,
+            "pitch" => self.assign_from_register(0xF03A)?,
+            "i" => self.index_statement()?,
+            "if" => self.if_statement()?,
+            "else" => match self.blocks.pop() {
+                Some(Block::If(at)) => {
+                    let end = self.emit_placeholder();
+                    self.patch(at, self.here());
+                    self.blocks.push(Block::Else(end));
+                }
+                _ => return Err(invalid(token)),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If(at) | Block::Else(at)) => self.patch(at, self.here()),
+                _ => return Err(invalid(token)),
+            },
+            "loop" => self.blocks.push(Block::Loop(self.here(), Vec::new())),
+            "while" => {
+                // The jump out of the loop is skipped while the condition holds
+                let condition = self.condition()?;
+                self.emit(condition.negate().skip_unless());
+                let exit = self.emit_placeholder();
+
+                match self.blocks.iter_mut().rev().find(|b| matches!(b, Block::Loop(..))) {
+                    Some(Block::Loop(_, exits)) => exits.push(exit),
+                    _ => return Err(invalid(token)),
+                }
+            }
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop(start, exits)) => {
+                    self.emit(JUMP | start);
+                    for exit in exits {
+                        self.patch(exit, self.here());
+                    }
+                }
+                _ => return Err(invalid(token)),
+            },
+            _ => {
+                if let Some(name) = token.text.strip_prefix(':').filter(|name| !name.is_empty()) {
+                    return self.define_label(Token { text: name, ..token });
+                }
+
+                if let Some(x) = self.try_register(token) {
+                    return self.register_statement(x);
+                }
+
+                match self.value(token) {
+                    Some(byte) if byte <= 0xFF => self.rom.push(byte as u8),
+                    Some(_) => return Err(invalid(token)),
+                    None if is_name(token.text) => {
+                        // A bare label calls the subroutine it names
+                        self.fixups.push((self.rom.len(), token));
+                        self.emit(CALL);
+                    }
+                    None => return Err(invalid(token)),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assembles the rest of a statement on the index register
+    ///
+    /// # Errors
+    ///
+    /// If the statement couldn't be assembled
+    fn index_statement(&mut self) -> Result<()> {
+        let op = self.next()?;
+
+        match op.text {
+            "+=" => self.emit_register(0xF01E),
+            ":=" => match self.peek().map(|token| token.text) {
+                Some("hex") => {
+                    self.pos += 1;
+                    self.emit_register(0xF029)
+                }
+                Some("bighex") => {
+                    self.pos += 1;
+                    self.emit_register(0xF030)
+                }
+                _ => self.emit_address(0xA000),
+            },
+            _ => Err(invalid(op)),
+        }
+    }
+
+    /// Assembles the rest of an `if` statement, followed by `then` for a
+    /// single statement or `begin` for a block
+    ///
+    /// # Errors
+    ///
+    /// If the statement couldn't be assembled
+    fn if_statement(&mut self) -> Result<()> {
+        let condition = self.condition()?;
+        let keyword = self.next()?;
+
+        match keyword.text {
+            "then" => self.emit(condition.skip_unless()),
+            "begin" => {
+                // The jump past the block is skipped when the condition holds
+                self.emit(condition.negate().skip_unless());
+                let at = self.emit_placeholder();
+                self.blocks.push(Block::If(at));
+            }
+            _ => return Err(invalid(keyword)),
+        }
+
+        Ok(())
+    }
+
+    /// Assembles the rest of a statement on a `V` register, such as `+= 5`
+    ///
+    /// # Params
+    ///
+    /// - `x` - The register
+    ///
+    /// # Errors
+    ///
+    /// If the statement couldn't be assembled
+    fn register_statement(&mut self, x: u16) -> Result<()> {
+        let op = self.next()?;
+        let rhs = self.next()?;
+        let x8 = x << 8;
+
+        if let Some(y) = self.try_register(rhs) {
+            let y4 = y << 4;
+            let opcode = match op.text {
+                ":=" => 0x8000,
+                "|=" => 0x8001,
+                "&=" => 0x8002,
+                "^=" => 0x8003,
+                "+=" => 0x8004,
+                "-=" => 0x8005,
+                ">>=" => 0x8006,
+                "=-" => 0x8007,
+                "<<=" => 0x800E,
+                _ => return Err(invalid(op)),
+            };
+
+            self.emit(opcode | x8 | y4);
+            return Ok(());
+        }
+
+        let opcode = match (op.text, rhs.text) {
+            (":=", "key") => 0xF00A | x8,
+            (":=", "delay") => 0xF007 | x8,
+            (":=", "random") => 0xC000 | x8 | self.byte()?,
+            (":=", _) => 0x6000 | x8 | self.byte_value(rhs)?,
+            ("+=", _) => 0x7000 | x8 | self.byte_value(rhs)?,
+            ("-=", _) => 0x7000 | x8 | (self.byte_value(rhs)?.wrapping_neg() & 0xFF),
+            _ => return Err(invalid(op)),
+        };
+
+        self.emit(opcode);
+        Ok(())
+    }
+
+    /// Parses the condition of an `if` or a `while`
+    ///
+    /// # Errors
+    ///
+    /// If the condition is invalid
+    fn condition(&mut self) -> Result<Condition> {
+        let x = self.register()?;
+        let op = self.next()?;
+
+        let condition = match op.text {
+            "key" => Condition::Key(x),
+            "-key" => Condition::NoKey(x),
+            "==" | "!=" => {
+                let rhs = self.next()?;
+                match (op.text, self.try_register(rhs)) {
+                    ("==", Some(y)) => Condition::Equals(x, y),
+                    (_, Some(y)) => Condition::Differs(x, y),
+                    ("==", None) => Condition::EqualsByte(x, self.byte_value(rhs)?),
+                    (_, None) => Condition::DiffersByte(x, self.byte_value(rhs)?),
+                }
+            }
+            _ => return Err(invalid(op)),
+        };
+
+        Ok(condition)
+    }
+
+    /// Defines a label at the current address
+    ///
+    /// # Params
+    ///
+    /// - `name` - The token of the name of the label
+    ///
+    /// # Errors
+    ///
+    /// If the name is invalid or already taken
+    fn define_label(&mut self, name: Token<'a>) -> Result<()> {
+        let here = self.here();
+        if !is_name(name.text) || self.labels.insert(name.text, here).is_some() {
+            return Err(invalid(name));
+        }
+
+        Ok(())
+    }
+
+    /// Writes an instruction whose address is the next token, being a number,
+    /// a constant or a label
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode without its address
+    ///
+    /// # Errors
+    ///
+    /// If the address is invalid
+    fn emit_address(&mut self, opcode: u16) -> Result<()> {
+        let token = self.next()?;
+
+        match self.value(token) {
+            Some(addr) if addr <= 0xFFF => self.emit(opcode | addr),
+            Some(_) => return Err(invalid(token)),
+            None if is_name(token.text) => {
+                self.fixups.push((self.rom.len(), token));
+                self.emit(opcode);
+            }
+            None => return Err(invalid(token)),
+        }
+
+        Ok(())
+    }
+
+    /// Writes an instruction on the register in the next token
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode without its register
+    ///
+    /// # Errors
+    ///
+    /// If the next token isn't a register
+    fn emit_register(&mut self, opcode: u16) -> Result<()> {
+        let x = self.register()?;
+        self.emit(opcode | x << 8);
+        Ok(())
+    }
+
+    /// Writes an instruction assigning a register to a timer or the pitch,
+    /// such as `delay := v0`
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The opcode without its register
+    ///
+    /// # Errors
+    ///
+    /// If the statement isn't an assignment of a register
+    fn assign_from_register(&mut self, opcode: u16) -> Result<()> {
+        let op = self.next()?;
+        if op.text != ":=" {
+            return Err(invalid(op));
+        }
+
+        self.emit_register(opcode)
+    }
+
+    /// Writes a jump whose address is filled in later, returning its offset
+    fn emit_placeholder(&mut self) -> usize {
+        let at = self.rom.len();
+        self.emit(JUMP);
+        at
+    }
+
+    /// Writes a raw opcode
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The raw opcode
+    fn emit(&mut self, opcode: u16) {
+        self.rom.extend_from_slice(&opcode.to_be_bytes());
+    }
+
+    /// Fills in the address of an instruction written before
+    ///
+    /// # Params
+    ///
+    /// - `at` - The offset of the instruction in the ROM
+    /// - `addr` - The address to fill in
+    fn patch(&mut self, at: usize, addr: u16) {
+        let opcode = u16::from_be_bytes([self.rom[at], self.rom[at + 1]]) | (addr & 0x0FFF);
+        self.rom[at..at + 2].copy_from_slice(&opcode.to_be_bytes());
+    }
+
+    /// Gets the address of what is written next
+    fn here(&self) -> u16 {
+        PROG_ADDR + self.rom.len() as u16
+    }
+
+    /// Gets the next token without consuming it
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    /// Consumes the next token
+    ///
+    /// # Errors
+    ///
+    /// If the source ended
+    fn next(&mut self) -> Result<Token<'a>> {
+        let token = self.peek().ok_or_else(|| {
+            Keet8Error::InvalidAssembly("unexpected end of source".to_string())
+        })?;
+
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Consumes a register
+    ///
+    /// # Errors
+    ///
+    /// If the next token isn't a register
+    fn register(&mut self) -> Result<u16> {
+        let token = self.next()?;
+        self.try_register(token).ok_or_else(|| invalid(token))
+    }
+
+    /// Consumes a number or constant
+    ///
+    /// # Errors
+    ///
+    /// If the next token isn't a number or constant
+    fn number(&mut self) -> Result<u16> {
+        let token = self.next()?;
+        self.value(token).ok_or_else(|| invalid(token))
+    }
+
+    /// Consumes a byte, which may be negative
+    ///
+    /// # Errors
+    ///
+    /// If the next token isn't a byte
+    fn byte(&mut self) -> Result<u16> {
+        let token = self.next()?;
+        self.byte_value(token)
+    }
+
+    /// Consumes a nibble
+    ///
+    /// # Errors
+    ///
+    /// If the next token isn't a nibble
+    fn nibble(&mut self) -> Result<u16> {
+        let token = self.next()?;
+        self.value(token)
+            .filter(|&n| n <= 0xF)
+            .ok_or_else(|| invalid(token))
+    }
+
+    /// Gets the byte a token stands for, negative numbers being written in
+    /// two's complement
+    ///
+    /// # Params
+    ///
+    /// - `token` - The token
+    ///
+    /// # Errors
+    ///
+    /// If the token isn't a byte
+    fn byte_value(&self, token: Token<'a>) -> Result<u16> {
+        let byte = match token.text.strip_prefix('-') {
+            Some(text) => parse_number(text)
+                .filter(|&n| n <= 0x80)
+                .map(|n| n.wrapping_neg() & 0xFF),
+            None => self.value(token).filter(|&n| n <= 0xFF),
+        };
+
+        byte.ok_or_else(|| invalid(token))
+    }
+
+    /// Gets the value a token stands for, being a number or a constant
+    ///
+    /// # Params
+    ///
+    /// - `token` - The token
+    fn value(&self, token: Token<'a>) -> Option<u16> {
+        parse_number(token.text).or_else(|| self.constants.get(token.text).copied())
+    }
+
+    /// Gets the register a token stands for, being `v0` to `vf` or an alias
+    ///
+    /// # Params
+    ///
+    /// - `token` - The token
+    fn try_register(&self, token: Token<'a>) -> Option<u16> {
+        let register = token
+            .text
+            .strip_prefix(['v', 'V'])
+            .filter(|x| x.len() == 1)
+            .and_then(|x| u16::from_str_radix(x, 16).ok());
+
+        register.or_else(|| self.aliases.get(token.text).copied())
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Splits the source into tokens, leaving out the comments
+///
+/// # Params
+///
+/// - `source` - The source of the program
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    source
+        .lines()
+        .enumerate()
+        .flat_map(|(n, line)| {
+            let code = line.split('#').next().unwrap_or_default();
+            code.split_whitespace().map(move |text| Token { text, line: n + 1 })
+        })
+        .collect()
+}
+
+/// Checks whether a name can be a label, constant or alias, starting with a
+/// letter or `_` and holding only letters, digits, `_` and `-`
+///
+/// # Params
+///
+/// - `name` - The name to check
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Parses a number written in decimal, in hexadecimal with `0x` or in binary
+/// with `0b`
+///
+/// # Params
+///
+/// - `text` - The textual form of the number
+fn parse_number(text: &str) -> Option<u16> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = text.strip_prefix("0b") {
+        u16::from_str_radix(binary, 2).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+/// Creates the error for a token which couldn't be assembled
+///
+/// # Params
+///
+/// - `token` - The token
+fn invalid(token: Token) -> Keet8Error {
+    Keet8Error::InvalidAssembly(format!("line {}: {}", token.line, token.text))
+}
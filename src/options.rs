@@ -0,0 +1,718 @@
+use crate::cheats::Cheat;
+use crate::debugger::{BreakCondition, Tracepoint};
+use crate::keymap::Layout;
+use crate::overlay::OverlayTheme;
+use crate::prelude::*;
+use crate::roms::builtin_rom_file;
+
+use keet8_core::{Platform, Quirks, ETI660_PROG_ADDR, PROG_ADDR};
+
+use std::ops::RangeInclusive;
+
+// --- mode definition --------------------------------------------------------
+
+/// What the application does with the ROM
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Mode {
+    /// Runs the ROM in a window
+    #[default]
+    Run,
+    /// Disassembles the ROM to stdout (`--disassemble`)
+    Disassemble,
+    /// Prints a summary of the ROM to stdout (`info` subcommand)
+    Info,
+    /// Checks the instructions executed per timer tick with the built-in
+    /// timing test ROM (`timing-check` subcommand)
+    TimingCheck,
+    /// Runs the ROM for the number of frames without a window as fast as
+    /// possible and prints how fast it went (`--bench`)
+    Bench(u32),
+    /// Prints the differences between two save states to stdout
+    /// (`diff-states` subcommand)
+    DiffStates,
+    /// Lists the ROMs bundled with the emulator (`builtins` subcommand)
+    Builtins,
+    /// Prints the first step at which two instruction traces diverge to
+    /// stdout (`diff-traces` subcommand)
+    DiffTraces,
+    /// Runs the community test ROMs without a window and prints whether each
+    /// one passed (`--verify`)
+    Verify,
+    /// Runs the ROM for a number of frames without a window and prints a
+    /// hash of the final state (`--headless`)
+    Headless,
+    /// Runs the ROM without a window in lockstep with a reference trace and
+    /// prints the first step at which they diverge (`--compare-trace`)
+    Lockstep,
+}
+
+// --- watch target definition ------------------------------------------------
+
+/// A location to watch, given as `0x300` for a memory address or `v3` for a
+/// `V` register
+#[derive(Clone, Copy)]
+pub(crate) enum WatchTarget {
+    /// A memory address, whose reads and writes pause execution
+    Memory(u16),
+    /// A `V` register, whose changes pause execution
+    Register(usize),
+}
+
+impl WatchTarget {
+    /// Parses a watch target from its textual form
+    ///
+    /// # Params
+    ///
+    /// - `text` - The textual form of the target
+    pub fn parse(text: &str) -> Option<Self> {
+        parse_register(text)
+            .map(Self::Register)
+            .or_else(|| parse_address(text).map(Self::Memory))
+    }
+}
+
+// --- disassembly format definition ------------------------------------------
+
+/// The format a disassembly is written in, given with `--format`
+#[derive(Clone, Copy, Default)]
+pub(crate) enum DisassemblyFormat {
+    /// A listing in the syntax of the assembler (`text`)
+    #[default]
+    Text,
+    /// A listing in the dialect of Octo (`octo`)
+    Octo,
+    /// A JSON array with an object per instruction or run of data (`json`)
+    Json,
+}
+
+impl DisassemblyFormat {
+    /// Parses a disassembly format from its name
+    ///
+    /// # Params
+    ///
+    /// - `text` - The name of the format
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "text" => Some(Self::Text),
+            "octo" => Some(Self::Octo),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+// --- trace format definition ------------------------------------------------
+
+/// The format an instruction trace is written in, given with `--trace-format`
+#[derive(Clone, Copy, Default)]
+pub(crate) enum TraceFormat {
+    /// The address, disassembly and registers changed by each instruction
+    /// (`changes`)
+    #[default]
+    Changes,
+    /// The address, raw opcode and all of the registers before each
+    /// instruction, for diffing against reference interpreters (`octo`)
+    Octo,
+    /// The Octo format followed by hashes of the memory and of the screen,
+    /// for diffing against other builds of the emulator (`state`)
+    State,
+}
+
+impl TraceFormat {
+    /// Parses a trace format from its name
+    ///
+    /// # Params
+    ///
+    /// - `text` - The name of the format
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "changes" => Some(Self::Changes),
+            "octo" => Some(Self::Octo),
+            "state" => Some(Self::State),
+            _ => None,
+        }
+    }
+}
+
+// --- options definition -----------------------------------------------------
+
+/// The options of the application, parsed from the command-line arguments
+///
+/// The options are opaque, and only created by `parse` (or `default`) to be
+/// handed to `start`
+#[derive(Default)]
+pub struct Options {
+    /// The filepath to the ROM file, which is empty for the window to list
+    /// the recent ROMs instead
+    pub(crate) rom_file: String,
+    /// The filepath to the ROM file run side by side with the ROM in its own
+    /// emulator, if any
+    pub(crate) side_rom_file: Option<String>,
+    /// The platform whose quirks the ROM runs with side by side with the
+    /// configured ones, comparing the two screens, if any
+    pub(crate) compare: Option<Platform>,
+    /// What to do with the ROM
+    pub(crate) mode: Mode,
+    /// The filepaths to the two save states to compare, with the
+    /// `diff-states` subcommand
+    pub(crate) state_files: Vec<String>,
+    /// The filepaths to the two instruction traces to compare, with the
+    /// `diff-traces` subcommand
+    pub(crate) trace_files: Vec<String>,
+    /// The number of frames run with `--headless`, if not the default
+    pub(crate) frames: Option<u32>,
+    /// The filepath to the reference trace the ROM runs in lockstep with,
+    /// with `--compare-trace`
+    pub(crate) reference_trace: Option<String>,
+    /// Flag indicating whether the keet-8 host-call extension is enabled
+    pub(crate) host_calls: bool,
+    /// The filepath to the configuration file, if not the default one
+    pub(crate) config_file: Option<String>,
+    /// The filepath to the ROM database known ROMs are looked up in, if not
+    /// the bundled one
+    pub(crate) rom_database: Option<String>,
+    /// Flag indicating whether the ROM isn't looked up in the ROM database,
+    /// running it with the configured settings only
+    pub(crate) no_rom_database: bool,
+    /// The quirks enabled with flags, in addition to the ones enabled in the
+    /// configuration file
+    pub(crate) quirks: Quirks,
+    /// The platform whose quirks and opcodes the ROM runs with, if any
+    pub(crate) platform: Option<Platform>,
+    /// Flag indicating whether the ROM runs in the two-page mode (hi-res
+    /// Chip-8), which the ROMs starting with `JP 0x260` run in anyway
+    pub(crate) two_page: bool,
+    /// The address the ROM is loaded and starts at, if not `PROG_ADDR`
+    pub(crate) load_addr: Option<u16>,
+    /// The seed of the random number generator, if it shouldn't be seeded
+    /// from the OS
+    pub(crate) seed: Option<u32>,
+    /// The filepath to a sequence of bytes to replay as random numbers, if
+    /// any, which takes precedence over the seed
+    pub(crate) random_script: Option<String>,
+    /// The filepath to the symbol file of the ROM, if any
+    pub(crate) symbol_file: Option<String>,
+    /// The format the disassembly is written in
+    pub(crate) disassembly_format: DisassemblyFormat,
+    /// The tracepoints to add to the debugging setup of the ROM
+    pub(crate) tracepoints: Vec<Tracepoint>,
+    /// The addresses of the breakpoints to add to the debugging setup of the
+    /// ROM
+    pub(crate) breakpoints: Vec<u16>,
+    /// The conditions to pause at, added to the debugging setup of the ROM
+    pub(crate) conditions: Vec<BreakCondition>,
+    /// The memory addresses and `V` registers to watch, added to the
+    /// debugging setup of the ROM
+    pub(crate) watchpoints: Vec<WatchTarget>,
+    /// The look of the overlay drawn on top of the emulator display
+    pub(crate) overlay_theme: OverlayTheme,
+    /// The number of instructions executed per second, if not the one from
+    /// the configuration file
+    pub(crate) cpu_speed: Option<u32>,
+    /// The name of the built-in palette, if not the one from the
+    /// configuration file
+    pub(crate) palette: Option<String>,
+    /// The layout of the keypad on the keyboard, if not the keys from the
+    /// configuration file
+    pub(crate) layout: Option<Layout>,
+    /// The color of the pixels which are on as `#RRGGBB` or `RRGGBB`, taking
+    /// precedence over the palette
+    pub(crate) foreground: Option<String>,
+    /// The color of the pixels which are off as `#RRGGBB` or `RRGGBB`, taking
+    /// precedence over the palette
+    pub(crate) background: Option<String>,
+    /// Flag indicating whether the CRT filter is initially applied
+    pub(crate) crt: bool,
+    /// Flag indicating whether the display is initially scaled by whole
+    /// numbers only
+    pub(crate) integer_scaling: bool,
+    /// The number of frames the pixels take to fade out, if not the one from
+    /// the configuration file
+    pub(crate) phosphor: Option<u32>,
+    /// Flag indicating whether the hash of the screen is shown in the window
+    /// title
+    pub(crate) frame_hash: bool,
+    /// Flag indicating whether the pixels erased by sprite collisions flash
+    /// for a frame
+    pub(crate) flash_collisions: bool,
+    /// Flag indicating whether debugger commands are read from stdin
+    pub(crate) console: bool,
+    /// Flag indicating whether the emulator runs without sound
+    pub(crate) mute: bool,
+    /// The filepath to the ZIP archive a bug report is written to when the
+    /// run ends, if any
+    pub(crate) bug_report: Option<String>,
+    /// The filepath to the file each executed instruction is appended to, if
+    /// any
+    pub(crate) trace_file: Option<String>,
+    /// The filepath to the WAV file the sound of the emulator is rendered to,
+    /// if any
+    pub(crate) audio_dump_file: Option<String>,
+    /// The filepath to the profile of the instructions executed, written
+    /// when the emulator closes, if any
+    pub(crate) profile_file: Option<String>,
+    /// The format the instruction trace is written in
+    pub(crate) trace_format: TraceFormat,
+    /// The filepath to the file the input is recorded to, if any
+    pub(crate) record_file: Option<String>,
+    /// The filepath to the recorded input to replay, if any
+    pub(crate) replay_file: Option<String>,
+    /// The filepath to the timed input script to play, if any
+    pub(crate) input_script_file: Option<String>,
+    /// The TCP port to wait on for the other player of a netplay session, if
+    /// hosting one
+    pub(crate) host_port: Option<u16>,
+    /// The address of the host of a netplay session to join, if any
+    pub(crate) connect_addr: Option<String>,
+    /// The TCP port the GDB stub listens on for debuggers, if enabled
+    pub(crate) gdb_port: Option<u16>,
+    /// The TCP port the remote control server listens on for clients, if
+    /// enabled
+    pub(crate) remote_port: Option<u16>,
+    /// Flag indicating whether the ROM is reloaded whenever its file changes
+    pub(crate) reload: bool,
+    /// Flag indicating whether the emulator runs as a development loop, with
+    /// the ROM reloaded whenever it changes and its errors shown in the
+    /// window rather than ending the run
+    pub(crate) dev: bool,
+    /// The number of times `-v` was given, raising the level of the logs from
+    /// warnings to info (`-v`) and debug messages (`-vv`)
+    pub(crate) verbosity: u8,
+    /// Flag indicating whether unknown opcodes raise an error
+    pub(crate) strict: bool,
+    /// Flag indicating whether the emulator runs on a worker thread of its
+    /// own, without the debugger and the rest of the tooling
+    pub(crate) threaded: bool,
+    /// Flag indicating whether idle loops run every instruction rather than
+    /// skipping to the next timer tick
+    pub(crate) no_idle_skip: bool,
+    /// Flag indicating whether the memory and registers start with random
+    /// garbage instead of zeros
+    pub(crate) random_init: bool,
+    /// Flag indicating whether the state of the ROM is neither saved on exit
+    /// nor offered to resume from on the next launch
+    pub(crate) no_resume: bool,
+    /// The addresses of the battery-backed save RAM persisted across runs, if
+    /// any
+    pub(crate) save_ram: Option<RangeInclusive<u16>>,
+    /// The cheats written to memory once per frame
+    pub(crate) cheats: Vec<Cheat>,
+    /// The cheats written to memory after every instruction
+    pub(crate) freezes: Vec<Cheat>,
+    /// The filepath to the file more cheats are read from, if any
+    pub(crate) cheats_file: Option<String>,
+    /// The filepath to the Rhai script run alongside the ROM, if any
+    #[cfg(feature = "scripting")]
+    pub(crate) script_file: Option<String>,
+}
+
+impl Options {
+    /// Parses the options from the command-line arguments
+    ///
+    /// The first argument not starting with `--` is taken as the ROM file,
+    /// unless it is the `info` subcommand preceding the ROM file, and
+    /// `--builtin` takes the place of the ROM file. A second ROM file is run
+    /// side by side with the first. The `timing-check` and
+    /// `builtins` subcommands take no ROM file, and the `diff-states` and
+    /// `diff-traces` subcommands take two save state or trace files instead.
+    ///
+    /// # Params
+    ///
+    /// - `args` - The command-line arguments, including the program name
+    ///
+    /// # Errors
+    ///
+    /// - If no ROM file was provided, other than to the window which lists the
+    ///   recent ROMs instead
+    /// - If not exactly two save state files were provided to `diff-states`
+    /// - If not exactly two trace files were provided to `diff-traces`
+    /// - If an unknown argument was provided
+    /// - If an argument requiring a value was provided without one
+    /// - If a tracepoint could not be parsed
+    /// - If a break condition could not be parsed
+    /// - If a cheat could not be parsed
+    /// - If an argument was provided with an invalid value
+    /// - If there is no built-in ROM by the name given with `--builtin`
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Self::default();
+        let mut rom_file = None;
+        let mut args = args.iter().skip(1).peekable();
+
+        if args.next_if(|arg| *arg == "info").is_some() {
+            options.mode = Mode::Info;
+        } else if args.next_if(|arg| *arg == "timing-check").is_some() {
+            options.mode = Mode::TimingCheck;
+        } else if args.next_if(|arg| *arg == "diff-states").is_some() {
+            options.mode = Mode::DiffStates;
+        } else if args.next_if(|arg| *arg == "diff-traces").is_some() {
+            options.mode = Mode::DiffTraces;
+        } else if args.next_if(|arg| *arg == "builtins").is_some() {
+            options.mode = Mode::Builtins;
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--disassemble" => options.mode = Mode::Disassemble,
+                "--verify" => options.mode = Mode::Verify,
+                "--format" => {
+                    options.disassembly_format =
+                        DisassemblyFormat::parse(&next_value(&mut args, arg)?)
+                            .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+                }
+                "--bench" => {
+                    let frames = next_value(&mut args, arg)?
+                        .parse()
+                        .ok()
+                        .filter(|&frames| frames > 0)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.mode = Mode::Bench(frames);
+                }
+                "--headless" => options.mode = Mode::Headless,
+                "--frames" => {
+                    let frames = next_value(&mut args, arg)?
+                        .parse()
+                        .ok()
+                        .filter(|&frames| frames > 0)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.frames = Some(frames);
+                }
+                "--config" => options.config_file = Some(next_value(&mut args, arg)?),
+                "--rom-db" => options.rom_database = Some(next_value(&mut args, arg)?),
+                "--no-rom-db" => options.no_rom_database = true,
+                "--hostcall" => options.host_calls = true,
+                "--quirk-shift-vy" => options.quirks.shift_vy = true,
+                "--quirk-load-store" => options.quirks.load_store_increment = true,
+                "--quirk-vf-reset" => options.quirks.vf_reset = true,
+                "--quirk-jump-vx" => options.quirks.jump_vx = true,
+                "--quirk-wrap" => options.quirks.wrap_sprites = true,
+                "--quirk-display-wait" => options.quirks.display_wait = true,
+                "--quirk-index-overflow" => options.quirks.index_overflow = true,
+                "--quirk-strict-memory" => options.quirks.strict_memory = true,
+                "--quirk-clamp-memory" => options.quirks.clamp_memory = true,
+                "--compare" => {
+                    let platform = parse_platform(&next_value(&mut args, arg)?)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.compare = Some(platform);
+                }
+                "--compare-trace" => {
+                    options.mode = Mode::Lockstep;
+                    options.reference_trace = Some(next_value(&mut args, arg)?);
+                }
+                "--platform" => {
+                    let platform = parse_platform(&next_value(&mut args, arg)?)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.platform = Some(platform);
+                }
+                "--two-page" => options.two_page = true,
+                "--load-addr" => {
+                    let addr = parse_load_address(&next_value(&mut args, arg)?)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.load_addr = Some(addr);
+                }
+                "--builtin" => {
+                    let builtin = builtin_rom_file(&next_value(&mut args, arg)?)?;
+                    if let Some(other) = rom_file.replace(builtin) {
+                        return Err(Keet8Error::UnknownArgument(other));
+                    }
+                }
+                "--symbols" => options.symbol_file = Some(next_value(&mut args, arg)?),
+                "--overlay-font" => {
+                    options.overlay_theme.font_file = Some(next_value(&mut args, arg)?);
+                }
+                "--overlay-font-size" => {
+                    options.overlay_theme.font_size = next_value(&mut args, arg)?
+                        .parse()
+                        .ok()
+                        .filter(|&size| size > 0)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+                }
+                "--overlay-opacity" => {
+                    options.overlay_theme.opacity = next_value(&mut args, arg)?
+                        .parse()
+                        .ok()
+                        .filter(|opacity| (0.0..=1.0).contains(opacity))
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+                }
+                "--seed" => {
+                    let seed = next_value(&mut args, arg)?;
+                    let seed = match seed.strip_prefix("0x") {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => seed.parse().ok(),
+                    };
+
+                    options.seed =
+                        Some(seed.ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?);
+                }
+                "--speed" => {
+                    options.cpu_speed = Some(
+                        next_value(&mut args, arg)?
+                            .parse()
+                            .ok()
+                            .filter(|&speed| speed > 0)
+                            .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--palette" => options.palette = Some(next_value(&mut args, arg)?),
+                "--layout" => {
+                    let layout = Layout::parse(&next_value(&mut args, arg)?)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.layout = Some(layout);
+                }
+                "--fg" | "--foreground" => options.foreground = Some(next_value(&mut args, arg)?),
+                "--bg" | "--background" => options.background = Some(next_value(&mut args, arg)?),
+                "--crt" => options.crt = true,
+                "--integer-scaling" => options.integer_scaling = true,
+                "--phosphor" => {
+                    options.phosphor = Some(
+                        next_value(&mut args, arg)?
+                            .parse()
+                            .map_err(|_| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--frame-hash" => options.frame_hash = true,
+                "--flash-collisions" => options.flash_collisions = true,
+                "--console" | "--repl" => options.console = true,
+                "--mute" => options.mute = true,
+                "-v" | "--verbose" => options.verbosity += 1,
+                "-vv" => options.verbosity += 2,
+                "--reload" => options.reload = true,
+                "--dev" => {
+                    options.dev = true;
+                    options.reload = true;
+                }
+                "--strict" => options.strict = true,
+                "--no-idle-skip" => options.no_idle_skip = true,
+                "--threaded" => options.threaded = true,
+                "--random-init" => options.random_init = true,
+                "--no-resume" => options.no_resume = true,
+                "--random-script" => options.random_script = Some(next_value(&mut args, arg)?),
+                "--bug-report" => options.bug_report = Some(next_value(&mut args, arg)?),
+                "--trace" => options.trace_file = Some(next_value(&mut args, arg)?),
+                "--dump-audio" => options.audio_dump_file = Some(next_value(&mut args, arg)?),
+                "--profile" => options.profile_file = Some(next_value(&mut args, arg)?),
+                "--trace-format" => {
+                    options.trace_format = TraceFormat::parse(&next_value(&mut args, arg)?)
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+                }
+                "--record" => options.record_file = Some(next_value(&mut args, arg)?),
+                "--replay" => options.replay_file = Some(next_value(&mut args, arg)?),
+                "--input-script" => {
+                    options.input_script_file = Some(next_value(&mut args, arg)?);
+                }
+                "--host" => {
+                    options.host_port = Some(
+                        next_value(&mut args, arg)?
+                            .parse()
+                            .map_err(|_| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--connect" => options.connect_addr = Some(next_value(&mut args, arg)?),
+                "--gdb" => {
+                    options.gdb_port = Some(
+                        next_value(&mut args, arg)?
+                            .parse()
+                            .map_err(|_| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--remote" => {
+                    options.remote_port = Some(
+                        next_value(&mut args, arg)?
+                            .parse()
+                            .map_err(|_| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--break" => {
+                    let addr = next_value(&mut args, arg)?;
+                    options.breakpoints.push(
+                        parse_address(&addr)
+                            .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--save-ram" => {
+                    let range = next_value(&mut args, arg)?;
+                    let range = range
+                        .split_once('-')
+                        .and_then(|(start, end)| Some(parse_address(start)?..=parse_address(end)?))
+                        .filter(|range| !range.is_empty())
+                        .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?;
+
+                    options.save_ram = Some(range);
+                }
+                "--cheat" | "--freeze" => {
+                    let text = next_value(&mut args, arg)?;
+                    let cheat = Cheat::parse(&text).ok_or(Keet8Error::InvalidCheat(text))?;
+
+                    if arg == "--freeze" {
+                        options.freezes.push(cheat);
+                    } else {
+                        options.cheats.push(cheat);
+                    }
+                }
+                "--cheats" => options.cheats_file = Some(next_value(&mut args, arg)?),
+                #[cfg(feature = "scripting")]
+                "--script" => options.script_file = Some(next_value(&mut args, arg)?),
+                "--watch" => {
+                    let target = next_value(&mut args, arg)?;
+                    options.watchpoints.push(
+                        WatchTarget::parse(&target)
+                            .ok_or_else(|| Keet8Error::InvalidArgumentValue(arg.to_string()))?,
+                    );
+                }
+                "--tracepoint" => {
+                    let text = next_value(&mut args, arg)?;
+                    let tracepoint = Tracepoint::parse(&text)
+                        .ok_or(Keet8Error::InvalidTracepoint(text))?;
+
+                    options.tracepoints.push(tracepoint);
+                }
+                "--break-if" => {
+                    let text = next_value(&mut args, arg)?;
+                    let condition = BreakCondition::parse(&text)
+                        .ok_or(Keet8Error::InvalidBreakCondition(text))?;
+
+                    options.conditions.push(condition);
+                }
+                flag if flag.starts_with("--") => {
+                    return Err(Keet8Error::UnknownArgument(flag.to_string()));
+                }
+                file if options.mode == Mode::DiffStates && options.state_files.len() < 2 => {
+                    options.state_files.push(file.to_string());
+                }
+                file if options.mode == Mode::DiffTraces && options.trace_files.len() < 2 => {
+                    options.trace_files.push(file.to_string());
+                }
+                rom if rom_file.is_none() => rom_file = Some(rom.to_string()),
+                rom if options.mode == Mode::Run && options.side_rom_file.is_none() => {
+                    options.side_rom_file = Some(rom.to_string());
+                }
+                other => return Err(Keet8Error::UnknownArgument(other.to_string())),
+            }
+        }
+
+        // The states hold the whole memory, so no ROM is needed to compare them
+        if options.mode == Mode::DiffStates {
+            if let Some(other) = rom_file {
+                return Err(Keet8Error::UnknownArgument(other));
+            }
+            if options.state_files.len() != 2 {
+                return Err(Keet8Error::NoStateFiles);
+            }
+
+            return Ok(options);
+        }
+
+        // Traces are compared as text, whichever ROM they were written with
+        if options.mode == Mode::DiffTraces {
+            if let Some(other) = rom_file {
+                return Err(Keet8Error::UnknownArgument(other));
+            }
+            if options.trace_files.len() != 2 {
+                return Err(Keet8Error::NoTraceFiles);
+            }
+
+            return Ok(options);
+        }
+
+        // The timing check and verification run their own ROMs, and the
+        // built-in ROMs are only listed
+        if matches!(options.mode, Mode::TimingCheck | Mode::Builtins | Mode::Verify) {
+            if let Some(rom) = rom_file {
+                return Err(Keet8Error::UnknownArgument(rom));
+            }
+
+            return Ok(options);
+        }
+
+        // Only the window runs a second ROM side by side, and comparing
+        // quirks runs the same ROM on both sides
+        if options.mode != Mode::Run || options.compare.is_some() {
+            if let Some(rom) = options.side_rom_file.take() {
+                return Err(Keet8Error::UnknownArgument(rom));
+            }
+        }
+
+        // The window lists the recent ROMs to pick from without a ROM file
+        options.rom_file = match rom_file {
+            Some(rom) => rom,
+            None if options.mode == Mode::Run && options.compare.is_none() => String::new(),
+            None => return Err(Keet8Error::NoROMFile),
+        };
+        Ok(options)
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Takes the value following an argument
+///
+/// # Params
+///
+/// - `args` - The remaining command-line arguments
+/// - `arg` - The argument requiring the value
+///
+/// # Errors
+///
+/// If there are no arguments left
+fn next_value<'a>(args: &mut impl Iterator<Item = &'a String>, arg: &str) -> Result<String> {
+    args.next()
+        .cloned()
+        .ok_or_else(|| Keet8Error::MissingArgumentValue(arg.to_string()))
+}
+
+/// Parses the name of a platform, being `chip8`, `schip` or `xochip`
+///
+/// # Params
+///
+/// - `text` - The name of the platform
+fn parse_platform(text: &str) -> Option<Platform> {
+    match text {
+        "chip8" => Some(Platform::Chip8),
+        "schip" => Some(Platform::Schip),
+        "xochip" => Some(Platform::XoChip),
+        _ => None,
+    }
+}
+
+/// Parses the address a ROM is loaded at, written in hexadecimal with `0x`
+/// from `0x200` on, or `eti660` for the address of the ETI-660
+///
+/// # Params
+///
+/// - `text` - The textual form of the address
+fn parse_load_address(text: &str) -> Option<u16> {
+    match text {
+        "eti660" => Some(ETI660_PROG_ADDR),
+        _ => parse_address(text).filter(|&addr| addr >= PROG_ADDR),
+    }
+}
+
+/// Parses a `V` register written as `v0` to `vf`
+///
+/// # Params
+///
+/// - `text` - The textual form of the register
+pub(crate) fn parse_register(text: &str) -> Option<usize> {
+    text.strip_prefix(['v', 'V'])
+        .filter(|x| x.len() == 1)
+        .and_then(|x| usize::from_str_radix(x, 16).ok())
+}
+
+/// Parses an address written in hexadecimal with `0x`, such as `0x230`, up
+/// to the end of the 64KB memory of XO-CHIP
+///
+/// Past the end of a smaller memory the address wraps around, as the
+/// addresses the ROM accesses do.
+///
+/// # Params
+///
+/// - `text` - The textual form of the address
+pub(crate) fn parse_address(text: &str) -> Option<u16> {
+    text.strip_prefix("0x")
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+}
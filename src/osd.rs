@@ -0,0 +1,81 @@
+use crate::overlay::Overlay;
+
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The time in seconds a message stays on screen
+const MESSAGE_DURATION: f32 = 2.0;
+/// The font size of the messages, before scaling with the overlay theme
+const FONT_SIZE: i32 = 20;
+/// The font size of the messages with the large text accessibility option
+const LARGE_FONT_SIZE: i32 = 40;
+/// The distance in pixels between the message and the edges of the window
+const MARGIN: i32 = 10;
+
+// --- on-screen display definition -------------------------------------------
+
+/// Shows short-lived messages on top of the emulator display, used to give
+/// feedback on actions triggered by hotkeys
+#[derive(Default)]
+pub(crate) struct Osd {
+    /// The message currently shown, if any
+    message: Option<String>,
+    /// The time in seconds until the current message disappears
+    remaining: f32,
+    /// Flag indicating whether the messages are drawn twice as large
+    large_text: bool,
+}
+
+impl Osd {
+    /// Shows a message, replacing the current one
+    ///
+    /// # Params
+    ///
+    /// - `message` - The message to show
+    pub fn show(&mut self, message: impl Into<String>) {
+        self.message = Some(message.into());
+        self.remaining = MESSAGE_DURATION;
+    }
+
+    /// Draws the messages twice as large, or back at their usual size
+    ///
+    /// # Params
+    ///
+    /// - `large_text` - Whether the messages are drawn twice as large
+    pub fn set_large_text(&mut self, large_text: bool) {
+        self.large_text = large_text;
+    }
+
+    /// Advances the time of the current message, hiding it once it expired
+    ///
+    /// # Params
+    ///
+    /// - `dt` - The time in seconds since the last update
+    pub fn update(&mut self, dt: f32) {
+        self.remaining -= dt;
+        if self.remaining <= 0.0 {
+            self.message = None;
+        }
+    }
+
+    /// Draws the current message in the bottom-left corner of the window
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the message on
+    /// - `screen_height` - The height of the window
+    pub fn draw(&self, overlay: &mut Overlay, screen_height: i32) {
+        let Some(message) = &self.message else {
+            return;
+        };
+
+        let size = overlay.font_size(if self.large_text { LARGE_FONT_SIZE } else { FONT_SIZE });
+        let y = screen_height - size - MARGIN;
+        let width = overlay.measure(message, size);
+
+        let background = overlay.background();
+        overlay.rect(MARGIN - 4, y - 4, width + 8, size + 8, background);
+        overlay.text(message, MARGIN, y, size, Color::RAYWHITE);
+    }
+}
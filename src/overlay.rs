@@ -0,0 +1,324 @@
+use crate::prelude::*;
+
+use raylib::prelude::*;
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+// --- constants --------------------------------------------------------------
+
+/// The time in seconds the overlay may take to draw per frame
+pub(crate) const OVERLAY_BUDGET: f32 = 0.001;
+
+/// The font size the sizes passed to the overlay are relative to
+const DEFAULT_FONT_SIZE: i32 = 20;
+/// The default opacity of the overlay backgrounds
+const DEFAULT_OPACITY: f32 = 0.6;
+/// The space in pixels between the lines of a menu
+const MENU_LINE_SPACING: i32 = 6;
+/// The space in pixels around a menu
+const MENU_PADDING: i32 = 10;
+
+/// The number of cached text widths after which the cache is cleared, so that
+/// frequently changing strings can't grow it without bounds
+const MAX_CACHED_WIDTHS: usize = 256;
+
+// --- overlay theme definition -----------------------------------------------
+
+/// The look of the overlay, as chosen by the user
+#[derive(Clone)]
+pub(crate) struct OverlayTheme {
+    /// The filepath to the TTF font to draw text with, if not the default
+    /// raylib font
+    pub font_file: Option<String>,
+    /// The font size of regular text, which all other text is scaled with
+    pub font_size: i32,
+    /// The opacity of the backgrounds behind the overlay elements (`0.0` to
+    /// `1.0`)
+    pub opacity: f32,
+}
+
+impl Default for OverlayTheme {
+    /// Creates the default theme, using the default raylib font
+    fn default() -> Self {
+        Self {
+            font_file: None,
+            font_size: DEFAULT_FONT_SIZE,
+            opacity: DEFAULT_OPACITY,
+        }
+    }
+}
+
+// --- overlay definition -----------------------------------------------------
+
+/// A rectangle queued to be drawn
+struct RectCommand {
+    /// The x-coordinate of the rectangle
+    x: i32,
+    /// The y-coordinate of the rectangle
+    y: i32,
+    /// The width of the rectangle
+    width: i32,
+    /// The height of the rectangle
+    height: i32,
+    /// The color of the rectangle
+    color: Color,
+}
+
+/// A piece of text queued to be drawn
+struct TextCommand {
+    /// The index of the string holding the text in the pool
+    index: usize,
+    /// The x-coordinate of the text
+    x: i32,
+    /// The y-coordinate of the text
+    y: i32,
+    /// The font size of the text
+    size: i32,
+    /// The color of the text
+    color: Color,
+}
+
+/// Batches the rectangles and text drawn on top of the emulator display
+///
+/// Everything is queued during the frame and drawn at once by `flush`, with
+/// all rectangles before all text so that raylib doesn't have to switch
+/// between the shape and font textures for every element. The strings are
+/// pooled across frames and the widths of measured strings are cached, which
+/// keeps the overlay free of per-frame allocations once it has warmed up.
+pub(crate) struct Overlay {
+    /// The look of the overlay
+    theme: OverlayTheme,
+    /// The font loaded from the theme, if any
+    font: Option<Font>,
+    /// The rectangles queued this frame
+    rects: Vec<RectCommand>,
+    /// The text queued this frame
+    texts: Vec<TextCommand>,
+    /// The strings reused for the queued text across frames
+    pool: Vec<String>,
+    /// The cached widths of measured strings, keyed by font size
+    widths: HashMap<i32, HashMap<String, i32>>,
+    /// The number of cached widths across all font sizes
+    num_widths: usize,
+    /// The time in seconds the last flush took
+    flush_time: f32,
+}
+
+impl Overlay {
+    /// Creates the overlay, loading the font of the theme
+    ///
+    /// # Params
+    ///
+    /// - `rl` - The handle to the raylib context
+    /// - `thread` - The thread on which raylib is running on
+    /// - `theme` - The look of the overlay
+    ///
+    /// # Errors
+    ///
+    /// If the font of the theme could not be loaded
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread, theme: OverlayTheme) -> Result<Self> {
+        let font = match &theme.font_file {
+            Some(font_file) => Some(
+                rl.load_font_ex(thread, font_file, theme.font_size, None)
+                    .map_err(|_| Keet8Error::FailedToLoadFont(font_file.clone()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            theme,
+            font,
+            rects: Vec::new(),
+            texts: Vec::new(),
+            pool: Vec::new(),
+            widths: HashMap::new(),
+            num_widths: 0,
+            flush_time: 0.0,
+        })
+    }
+
+    /// Scales a font size with the font size of the theme
+    ///
+    /// # Params
+    ///
+    /// - `size` - The font size relative to regular text of 20 pixels
+    pub fn font_size(&self, size: i32) -> i32 {
+        (size * self.theme.font_size / DEFAULT_FONT_SIZE).max(1)
+    }
+
+    /// Gets the color of the backgrounds behind the overlay elements
+    pub fn background(&self) -> Color {
+        Color::BLACK.fade(self.theme.opacity)
+    }
+
+    /// Queues a filled rectangle
+    ///
+    /// # Params
+    ///
+    /// - `x` - The x-coordinate of the rectangle
+    /// - `y` - The y-coordinate of the rectangle
+    /// - `width` - The width of the rectangle
+    /// - `height` - The height of the rectangle
+    /// - `color` - The color of the rectangle
+    pub fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        self.rects.push(RectCommand {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    /// Queues the outline of a rectangle, one pixel thick
+    ///
+    /// # Params
+    ///
+    /// - `x` - The x-coordinate of the rectangle
+    /// - `y` - The y-coordinate of the rectangle
+    /// - `width` - The width of the rectangle
+    /// - `height` - The height of the rectangle
+    /// - `color` - The color of the outline
+    pub fn rect_lines(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        self.rect(x, y, width, 1, color);
+        self.rect(x, y + height - 1, width, 1, color);
+        self.rect(x, y, 1, height, color);
+        self.rect(x + width - 1, y, 1, height, color);
+    }
+
+    /// Queues a piece of text
+    ///
+    /// # Params
+    ///
+    /// - `text` - The text to draw
+    /// - `x` - The x-coordinate of the text
+    /// - `y` - The y-coordinate of the text
+    /// - `size` - The font size of the text
+    /// - `color` - The color of the text
+    pub fn text(&mut self, text: &str, x: i32, y: i32, size: i32, color: Color) {
+        let index = self.texts.len();
+        if index == self.pool.len() {
+            self.pool.push(String::new());
+        }
+
+        self.pool[index].clear();
+        self.pool[index].push_str(text);
+
+        self.texts.push(TextCommand {
+            index,
+            x,
+            y,
+            size,
+            color,
+        });
+    }
+
+    /// Queues the lines of a menu, such as a list of ROMs, centered in the
+    /// window on a background
+    ///
+    /// # Params
+    ///
+    /// - `lines` - The lines of the menu, each with its color
+    /// - `screen_width` - The width of the window
+    /// - `screen_height` - The height of the window
+    pub fn menu(&mut self, lines: &[(String, Color)], screen_width: i32, screen_height: i32) {
+        let size = self.font_size(DEFAULT_FONT_SIZE);
+        let line_height = size + MENU_LINE_SPACING;
+        let width = lines
+            .iter()
+            .map(|(text, _)| self.measure(text, size))
+            .max()
+            .unwrap_or_default();
+        let height = line_height * lines.len() as i32;
+        let x = (screen_width - width) / 2;
+        let y = (screen_height - height) / 2;
+
+        let background = self.background();
+        self.rect(
+            x - MENU_PADDING,
+            y - MENU_PADDING,
+            width + 2 * MENU_PADDING,
+            height + MENU_PADDING,
+            background,
+        );
+        for (i, (text, color)) in lines.iter().enumerate() {
+            self.text(text, x, y + line_height * i as i32, size, *color);
+        }
+    }
+
+    /// Measures the width in pixels of a piece of text, using the cached
+    /// width if the text was measured before
+    ///
+    /// # Params
+    ///
+    /// - `text` - The text to measure
+    /// - `size` - The font size of the text
+    pub fn measure(&mut self, text: &str, size: i32) -> i32 {
+        if let Some(&width) = self.widths.get(&size).and_then(|w| w.get(text)) {
+            return width;
+        }
+
+        if self.num_widths == MAX_CACHED_WIDTHS {
+            self.widths.clear();
+            self.num_widths = 0;
+        }
+
+        let width = match &self.font {
+            Some(font) => measure_text_ex(font, text, size as f32, spacing(size)).x as i32,
+            None => measure_text(text, size),
+        };
+
+        self.widths
+            .entry(size)
+            .or_default()
+            .insert(text.to_string(), width);
+        self.num_widths += 1;
+
+        width
+    }
+
+    /// Draws everything queued this frame and clears the queue
+    ///
+    /// # Params
+    ///
+    /// - `d` - The handle used for drawing
+    pub fn flush(&mut self, d: &mut RaylibDrawHandle) {
+        let start = Instant::now();
+
+        for rect in self.rects.drain(..) {
+            d.draw_rectangle(rect.x, rect.y, rect.width, rect.height, rect.color);
+        }
+
+        for text in self.texts.drain(..) {
+            let string = &self.pool[text.index];
+            match &self.font {
+                Some(font) => {
+                    let position = Vector2::new(text.x as f32, text.y as f32);
+                    let size = text.size as f32;
+                    d.draw_text_ex(font, string, position, size, spacing(text.size), text.color);
+                }
+                None => d.draw_text(string, text.x, text.y, text.size, text.color),
+            }
+        }
+
+        self.flush_time = start.elapsed().as_secs_f32();
+    }
+
+    /// Gets the time in seconds the last flush took
+    pub fn flush_time(&self) -> f32 {
+        self.flush_time
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Gets the spacing between characters for a font size, matching the spacing
+/// raylib uses for its default font
+///
+/// # Params
+///
+/// - `size` - The font size of the text
+fn spacing(size: i32) -> f32 {
+    size as f32 / 10.0
+}
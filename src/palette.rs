@@ -0,0 +1,69 @@
+use raylib::prelude::*;
+
+// --- palette definition -----------------------------------------------------
+
+/// A pair of colors the screen is drawn with, after the displays Chip-8 games
+/// were played on
+pub(crate) struct Palette {
+    /// The name of the palette, as used in the configuration and on the
+    /// command-line
+    pub name: &'static str,
+    /// The color of the pixels which are on
+    pub foreground: Color,
+    /// The color of the pixels which are off
+    pub background: Color,
+}
+
+// --- constants --------------------------------------------------------------
+
+/// The built-in palettes, in the order they are cycled through
+pub(crate) const PALETTES: [Palette; 7] = [
+    Palette {
+        name: "green",
+        foreground: Color::new(0x33, 0xFF, 0x66, 0xFF),
+        background: Color::new(0x05, 0x14, 0x0A, 0xFF),
+    },
+    Palette {
+        name: "amber",
+        foreground: Color::new(0xFF, 0xB0, 0x00, 0xFF),
+        background: Color::new(0x1A, 0x0F, 0x00, 0xFF),
+    },
+    Palette {
+        name: "white",
+        foreground: Color::new(0xF0, 0xF0, 0xF0, 0xFF),
+        background: Color::new(0x10, 0x10, 0x10, 0xFF),
+    },
+    Palette {
+        name: "lcd",
+        foreground: Color::new(0x0F, 0x38, 0x0F, 0xFF),
+        background: Color::new(0x9B, 0xBC, 0x0F, 0xFF),
+    },
+    Palette {
+        name: "high-contrast",
+        foreground: Color::new(0xFF, 0xFF, 0xFF, 0xFF),
+        background: Color::new(0x00, 0x00, 0x00, 0xFF),
+    },
+    Palette {
+        name: "inverted",
+        foreground: Color::new(0x00, 0x00, 0x00, 0xFF),
+        background: Color::new(0xFF, 0xFF, 0xFF, 0xFF),
+    },
+    Palette {
+        name: "yellow",
+        foreground: Color::new(0xFF, 0xFF, 0x00, 0xFF),
+        background: Color::new(0x00, 0x00, 0x00, 0xFF),
+    },
+];
+
+// --- utility functions ------------------------------------------------------
+
+/// Finds a built-in palette by its name, returning its index in `PALETTES`
+///
+/// # Params
+///
+/// - `name` - The name of the palette (case insensitive)
+pub(crate) fn find_palette(name: &str) -> Option<usize> {
+    PALETTES
+        .iter()
+        .position(|palette| palette.name.eq_ignore_ascii_case(name))
+}
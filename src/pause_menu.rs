@@ -0,0 +1,92 @@
+use crate::overlay::Overlay;
+
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The color of the selected entry
+const SELECTED_COLOR: Color = Color::YELLOW;
+
+/// The entries of the menu from top to bottom, each with the hotkey doing
+/// the same, so that the hotkeys can be learned from the menu
+const ENTRIES: [(PauseAction, &str); 7] = [
+    (PauseAction::Resume, "Resume (P)"),
+    (PauseAction::Reset, "Reset (Ctrl+R)"),
+    (PauseAction::LoadRom, "Load ROM (Ctrl+O)"),
+    (PauseAction::SaveState, "Save state (F5)"),
+    (PauseAction::LoadState, "Load state (F9)"),
+    (PauseAction::Settings, "Settings (Esc)"),
+    (PauseAction::Quit, "Quit"),
+];
+
+// --- pause menu definition --------------------------------------------------
+
+/// What an entry of the pause menu asks the application to do
+#[derive(Clone, Copy)]
+pub(crate) enum PauseAction {
+    /// Resume the ROM
+    Resume,
+    /// Restart the ROM
+    Reset,
+    /// List the recent ROMs to replace the ROM with
+    LoadRom,
+    /// Save the state of the ROM to the current slot
+    SaveState,
+    /// Load the state of the ROM from the current slot
+    LoadState,
+    /// Open the settings menu
+    Settings,
+    /// Close the application
+    Quit,
+}
+
+/// A menu of the common actions, shown over the window whilst the ROM is
+/// paused so that the emulator can be used without knowing its hotkeys
+pub(crate) struct PauseMenu {
+    /// The index of the selected entry
+    selected: usize,
+}
+
+impl PauseMenu {
+    /// Creates the menu with resuming the ROM selected
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    /// Moves the selection, wrapping around either end of the menu
+    ///
+    /// # Params
+    ///
+    /// - `offset` - The number of entries to move down, or up if negative
+    pub fn move_selection(&mut self, offset: i32) {
+        let len = ENTRIES.len() as i32;
+        self.selected = (self.selected as i32 + offset).rem_euclid(len) as usize;
+    }
+
+    /// Gets the action of the selected entry
+    pub fn selected(&self) -> PauseAction {
+        ENTRIES[self.selected].0
+    }
+
+    /// Draws the menu centered in the window
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the menu on
+    /// - `screen_width` - The width of the window
+    /// - `screen_height` - The height of the window
+    pub fn draw(&self, overlay: &mut Overlay, screen_width: i32, screen_height: i32) {
+        let mut lines = vec![("Paused".to_string(), Color::RAYWHITE)];
+        for (i, (_, label)) in ENTRIES.iter().enumerate() {
+            let (marker, color) = if i == self.selected {
+                ("> ", SELECTED_COLOR)
+            } else {
+                ("  ", Color::RAYWHITE)
+            };
+            lines.push((format!("{marker}{label}"), color));
+        }
+        lines.push(("Up/Down selects, Enter picks".to_string(), Color::GRAY));
+
+        overlay.menu(&lines, screen_width, screen_height);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::prelude::*;
+use crate::symbols::Symbols;
+
+use keet8_core::Emulator;
+use serde_json::json;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// --- constants --------------------------------------------------------------
+
+/// The file extension the profile is written as JSON for, rather than as
+/// collapsed stacks
+const JSON_EXTENSION: &str = ".json";
+
+// --- profiler definition ----------------------------------------------------
+
+/// Counts the instructions executed at every address, and the time they took,
+/// along with the subroutines they were executed in, for profiling ROMs with
+/// standard tools
+///
+/// The profile is written as collapsed stacks, one line per address and call
+/// path such as `main;draw_ball;draw_ball+0x4 1200`, weighted by the number of
+/// instructions executed, which `flamegraph.pl`, inferno and speedscope take
+/// as they are. A filepath ending in `.json` gets the counts and times per
+/// address and per call path as JSON instead.
+pub(crate) struct Profiler {
+    /// The filepath to the profile
+    path: String,
+    /// The counts and times by call path, being the entry points of the
+    /// subroutines the instructions were executed in followed by their
+    /// address
+    samples: BTreeMap<Vec<u16>, Sample>,
+}
+
+/// The instructions executed at an address along a call path
+#[derive(Clone, Copy, Default)]
+struct Sample {
+    /// The number of instructions executed
+    count: u64,
+    /// The time the instructions took
+    time: Duration,
+}
+
+impl Profiler {
+    /// Creates a profiler which hasn't counted anything yet
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to write the profile to
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            samples: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the filepath to the profile
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Counts an executed instruction
+    ///
+    /// # Params
+    ///
+    /// - `call_path` - The call path of the instruction, as given by
+    ///   `call_path` before it was executed
+    /// - `time` - The time the instruction took
+    pub fn record(&mut self, call_path: Vec<u16>, time: Duration) {
+        let sample = self.samples.entry(call_path).or_default();
+        sample.count += 1;
+        sample.time += time;
+    }
+
+    /// Writes the profile, with the addresses rendered as labels when
+    /// symbols are loaded
+    ///
+    /// # Params
+    ///
+    /// - `symbols` - The symbols of the ROM
+    ///
+    /// # Errors
+    ///
+    /// If the profile could not be written
+    pub fn save(&self, symbols: &Symbols) -> Result<()> {
+        let text = if self.path.ends_with(JSON_EXTENSION) {
+            self.json(symbols)
+        } else {
+            self.collapsed(symbols)
+        };
+
+        std::fs::write(&self.path, text)
+            .map_err(|_| Keet8Error::FailedToSaveProfile(self.path.clone()))
+    }
+
+    /// Formats the profile as collapsed stacks
+    ///
+    /// # Params
+    ///
+    /// - `symbols` - The symbols of the ROM
+    fn collapsed(&self, symbols: &Symbols) -> String {
+        self.samples
+            .iter()
+            .map(|(call_path, sample)| {
+                let frames = call_path
+                    .iter()
+                    .map(|&addr| symbols.symbolicate(addr))
+                    .collect::<Vec<String>>();
+
+                format!("{} {}\n", frames.join(";"), sample.count)
+            })
+            .collect()
+    }
+
+    /// Formats the profile as JSON, with the addresses taking the most
+    /// instructions first
+    ///
+    /// # Params
+    ///
+    /// - `symbols` - The symbols of the ROM
+    fn json(&self, symbols: &Symbols) -> String {
+        let mut addresses = BTreeMap::<u16, Sample>::new();
+        for (call_path, sample) in &self.samples {
+            let total = addresses.entry(*call_path.last().unwrap_or(&0)).or_default();
+            total.count += sample.count;
+            total.time += sample.time;
+        }
+
+        let mut addresses = addresses.into_iter().collect::<Vec<(u16, Sample)>>();
+        addresses.sort_by_key(|(_, sample)| std::cmp::Reverse(sample.count));
+
+        let addresses = addresses
+            .iter()
+            .map(|(addr, sample)| {
+                json!({
+                    "addr": format!("{addr:#06x}"),
+                    "label": symbols.symbolicate(*addr),
+                    "count": sample.count,
+                    "time_us": sample.time.as_secs_f64() * 1e6,
+                })
+            })
+            .collect::<Vec<_>>();
+        let stacks = self
+            .samples
+            .iter()
+            .map(|(call_path, sample)| {
+                let frames = call_path
+                    .iter()
+                    .map(|&addr| symbols.symbolicate(addr))
+                    .collect::<Vec<String>>();
+
+                json!({
+                    "stack": frames,
+                    "count": sample.count,
+                    "time_us": sample.time.as_secs_f64() * 1e6,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let total = self.samples.values().map(|sample| sample.count).sum::<u64>();
+        let profile = json!({ "instructions": total, "addresses": addresses, "stacks": stacks });
+        serde_json::to_string_pretty(&profile).unwrap_or_default() + "\n"
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Gets the call path of the instruction the emulator executes next, being
+/// the load address of the ROM and the entry point of every subroutine on the
+/// call stack, followed by the address of the instruction
+///
+/// The entry point of a subroutine is the target of the `CALL` right before
+/// its return address, or that `CALL` itself if it was rewritten since.
+///
+/// # Params
+///
+/// - `emulator` - The emulator about to execute the instruction
+pub(crate) fn call_path(emulator: &Emulator) -> Vec<u16> {
+    let mut call_path = vec![emulator.load_address()];
+
+    for &addr in emulator.call_stack() {
+        let caller = addr.wrapping_sub(2);
+        let raw = emulator.fetch(caller);
+
+        call_path.push(if raw & 0xF000 == 0x2000 { raw & 0x0FFF } else { caller });
+    }
+
+    call_path.push(emulator.program_counter());
+    call_path
+}
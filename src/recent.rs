@@ -0,0 +1,109 @@
+use crate::prelude::*;
+use crate::roms::builtin_name;
+use crate::sidecar::data_dir;
+
+use std::path::{Path, PathBuf};
+
+// --- constants --------------------------------------------------------------
+
+/// The name of the file in the data directory listing the recent ROMs
+const RECENT_FILE: &str = "recent.txt";
+/// The number of ROMs remembered, each picked with a single digit key
+pub(crate) const MAX_RECENT_ROMS: usize = 9;
+
+// --- recent roms definition -------------------------------------------------
+
+/// The ROM files opened most recently, persisted across sessions so that one
+/// can be started again without giving its path
+///
+/// The ROM files are stored one per line in `recent.txt` in the data
+/// directory, the most recent first.
+pub(crate) struct RecentRoms {
+    /// The filepath to the list, if a data directory could be determined
+    path: Option<PathBuf>,
+    /// The ROM files, the most recent first
+    roms: Vec<String>,
+}
+
+impl RecentRoms {
+    /// Reads the list, which is empty if no ROM was opened yet
+    pub fn load() -> Self {
+        let path = data_dir().map(|dir| dir.join(RECENT_FILE));
+        let roms = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|text| {
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .take(MAX_RECENT_ROMS)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { path, roms }
+    }
+
+    /// Gets the ROM files, the most recent first
+    pub fn roms(&self) -> &[String] {
+        &self.roms
+    }
+
+    /// Moves a ROM file to the front of the list, forgetting the oldest one
+    /// if the list is full
+    ///
+    /// Relative filepaths are made absolute, so the ROM can be opened again
+    /// from any working directory
+    ///
+    /// # Params
+    ///
+    /// - `rom_file` - The filepath to the ROM file
+    pub fn push(&mut self, rom_file: &str) {
+        let rom_file = match builtin_name(rom_file) {
+            Some(_) => rom_file.to_string(),
+            None => std::path::absolute(rom_file)
+                .map_or_else(|_| rom_file.to_string(), |path| path.display().to_string()),
+        };
+
+        self.roms.retain(|rom| *rom != rom_file);
+        self.roms.insert(0, rom_file);
+        self.roms.truncate(MAX_RECENT_ROMS);
+    }
+
+    /// Writes the list to disk
+    ///
+    /// # Errors
+    ///
+    /// If the list could not be written
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let text: String = self.roms.iter().map(|rom| format!("{rom}\n")).collect();
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, text))
+            .map_err(|_| Keet8Error::FailedToSaveRecentRoms(path.display().to_string()))
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Gets the name a ROM file is listed under, being the name of the file or of
+/// the built-in ROM
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+pub(crate) fn display_name(rom_file: &str) -> String {
+    builtin_name(rom_file).map_or_else(
+        || {
+            Path::new(rom_file)
+                .file_name()
+                .map_or_else(|| rom_file.to_string(), |name| name.to_string_lossy().into_owned())
+        },
+        str::to_string,
+    )
+}
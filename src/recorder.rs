@@ -0,0 +1,268 @@
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+use raylib::prelude::*;
+
+use std::collections::HashMap;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of bits of the codes of the two colors, being the minimum GIF
+/// allows
+const MIN_CODE_SIZE: u8 = 2;
+/// The largest number of bits of an LZW code
+const MAX_CODE_SIZE: u8 = 12;
+/// The shortest delay in centiseconds between two frames, as viewers show
+/// anything shorter much slower instead
+const MIN_DELAY: u32 = 2;
+
+// --- recorder definition ----------------------------------------------------
+
+/// Captures the frames of the emulator at their native resolution, to be
+/// written as an animated GIF
+///
+/// Only frames which differ from the one before are kept, so a recording
+/// takes little memory until it is saved.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    /// The frames captured so far
+    frames: Vec<Frame>,
+}
+
+/// A frame captured from the emulator
+struct Frame {
+    /// The width in pixels of the frame
+    width: usize,
+    /// The pixels of the frame, row by row, with 1 being on
+    pixels: Vec<u8>,
+    /// The time in seconds the frame was shown for
+    duration: f32,
+}
+
+impl Recorder {
+    /// Captures the screen of the emulator, or extends the last frame if the
+    /// screen hasn't changed
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to capture the screen of
+    /// - `frame_time` - The time in seconds the screen is shown for
+    pub fn capture(&mut self, emulator: &Emulator, frame_time: f32) {
+        let width = emulator.screen_width();
+        let pixels = emulator.frame().iter().map(|(_, _, on)| on as u8).collect::<Vec<u8>>();
+
+        if let Some(last) = self.frames.last_mut() {
+            if last.width == width && last.pixels == pixels {
+                last.duration += frame_time;
+                return;
+            }
+        }
+
+        self.frames.push(Frame {
+            width,
+            pixels,
+            duration: frame_time,
+        });
+    }
+
+    /// Writes the captured frames to an animated GIF, which loops forever
+    ///
+    /// The GIF is as large as the largest frame, with the frames of the low
+    /// resolution mode scaled up to it, if the resolution changed whilst
+    /// recording.
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to write the GIF to
+    /// - `foreground` - The color of the pixels which are on
+    /// - `background` - The color of the pixels which are off
+    ///
+    /// # Errors
+    ///
+    /// If the GIF could not be written
+    pub fn save(&self, path: &str, foreground: Color, background: Color) -> Result<()> {
+        let width = self.frames.iter().map(|frame| frame.width).max().unwrap_or(0);
+        let height = self
+            .frames
+            .iter()
+            .map(|frame| frame.pixels.len() / frame.width)
+            .max()
+            .unwrap_or(0);
+
+        let mut gif = Vec::new();
+        gif.extend_from_slice(b"GIF89a");
+        gif.extend_from_slice(&(width as u16).to_le_bytes());
+        gif.extend_from_slice(&(height as u16).to_le_bytes());
+
+        // A global color table of two colors, the background first
+        gif.extend_from_slice(&[0x80, 0x00, 0x00]);
+        gif.extend_from_slice(&[background.r, background.g, background.b]);
+        gif.extend_from_slice(&[foreground.r, foreground.g, foreground.b]);
+
+        // The application extension making the animation loop forever
+        gif.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        gif.extend_from_slice(b"NETSCAPE2.0");
+        gif.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        for (frame, delay) in self.frames.iter().zip(delays(&self.frames)) {
+            let Some(delay) = delay else {
+                continue;
+            };
+
+            let scale = width / frame.width;
+            let pixels = (0..width * height)
+                .map(|i| frame.pixels[(i / width / scale) * frame.width + (i % width) / scale])
+                .collect::<Vec<u8>>();
+
+            // The graphic control extension, holding the delay of the frame
+            gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+            gif.extend_from_slice(&(delay as u16).to_le_bytes());
+            gif.extend_from_slice(&[0x00, 0x00]);
+
+            // The image descriptor, covering the whole GIF
+            gif.push(0x2C);
+            gif.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+            gif.extend_from_slice(&(width as u16).to_le_bytes());
+            gif.extend_from_slice(&(height as u16).to_le_bytes());
+            gif.push(0x00);
+
+            gif.push(MIN_CODE_SIZE);
+            for block in lzw_encode(&pixels).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend_from_slice(block);
+            }
+            gif.push(0x00);
+        }
+
+        gif.push(0x3B);
+        std::fs::write(path, gif).map_err(|_| Keet8Error::FailedToSaveRecording(path.to_string()))
+    }
+}
+
+// --- code writer definition -------------------------------------------------
+
+/// Packs codes of varying sizes into bytes, least significant bit first
+#[derive(Default)]
+struct CodeWriter {
+    /// The bytes packed so far
+    bytes: Vec<u8>,
+    /// The bits not yet making up a whole byte
+    bits: u32,
+    /// The number of bits not yet making up a whole byte
+    num_bits: u8,
+}
+
+impl CodeWriter {
+    /// Appends a code
+    ///
+    /// # Params
+    ///
+    /// - `code` - The code to append
+    /// - `size` - The number of bits of the code
+    fn write(&mut self, code: u16, size: u8) {
+        self.bits |= (code as u32) << self.num_bits;
+        self.num_bits += size;
+
+        while self.num_bits >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.num_bits -= 8;
+        }
+    }
+
+    /// Pads the last byte with zeros, returning the packed bytes
+    fn finish(mut self) -> Vec<u8> {
+        if self.num_bits > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+
+        self.bytes
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Converts the durations of the frames to delays in centiseconds, skipping
+/// frames (with `None`) which would be shown shorter than `MIN_DELAY`
+///
+/// The delays are rounded from the time elapsed since the start, so that the
+/// rounding doesn't add up over a long recording.
+///
+/// # Params
+///
+/// - `frames` - The frames to get the delays of
+fn delays(frames: &[Frame]) -> Vec<Option<u32>> {
+    let mut elapsed = 0.0;
+    let mut shown_until = 0;
+
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            elapsed += frame.duration;
+            let end = (elapsed * 100.0).round() as u32;
+            let delay = end.saturating_sub(shown_until);
+
+            if delay < MIN_DELAY && i + 1 < frames.len() {
+                return None;
+            }
+
+            shown_until = end;
+            Some(delay.max(MIN_DELAY))
+        })
+        .collect()
+}
+
+/// Compresses the color indices of an image with the variable-length LZW
+/// coding of GIF
+///
+/// # Params
+///
+/// - `indices` - The color indices of the pixels (0 or 1)
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut writer = CodeWriter::default();
+    let mut table = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = MIN_CODE_SIZE + 1;
+    writer.write(clear, code_size);
+
+    let mut prefix = None;
+    for &index in indices {
+        let Some(code) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+
+        if let Some(&extended) = table.get(&(code, index)) {
+            prefix = Some(extended);
+            continue;
+        }
+
+        writer.write(code, code_size);
+
+        if next_code == 1 << MAX_CODE_SIZE {
+            // The table is full, so it starts over
+            writer.write(clear, code_size);
+            table.clear();
+            next_code = end + 1;
+            code_size = MIN_CODE_SIZE + 1;
+        } else {
+            table.insert((code, index), next_code);
+            next_code += 1;
+            if next_code > 1 << code_size && code_size < MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        }
+
+        prefix = Some(index as u16);
+    }
+
+    if let Some(code) = prefix {
+        writer.write(code, code_size);
+    }
+    writer.write(end, code_size);
+    writer.finish()
+}
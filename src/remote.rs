@@ -0,0 +1,342 @@
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+use log::info;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of bytes `mem` reads when given no length
+const DEFAULT_MEM_LEN: u16 = 16;
+
+// --- request definition -----------------------------------------------------
+
+/// A request sent by a client, as a JSON object on a line of its own with the
+/// name of the request in `cmd`
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    /// Replaces the ROM (`{"cmd": "load", "rom": "<path>"}`)
+    Load {
+        /// The filepath to the ROM file
+        rom: String,
+    },
+    /// Pauses the ROM
+    Pause,
+    /// Resumes the ROM
+    Resume,
+    /// Executes a single instruction, pausing the ROM if it runs
+    Step,
+    /// Restarts the ROM
+    Reset,
+    /// Reads the registers, timers and call stack depth
+    Regs,
+    /// Reads a range of memory (`{"cmd": "mem", "addr": 512, "len": 16}`)
+    Mem {
+        /// The address of the first byte
+        addr: u16,
+        /// The number of bytes, 16 if not given
+        len: Option<u16>,
+    },
+    /// Writes a byte into memory (`{"cmd": "poke", "addr": 512, "value": 0}`)
+    Poke {
+        /// The address of the byte
+        addr: u16,
+        /// The byte to write
+        value: u8,
+    },
+    /// Streams the screen to the client whenever it changes
+    Subscribe,
+    /// Stops streaming the screen to the client
+    Unsubscribe,
+}
+
+// --- remote action definition -----------------------------------------------
+
+/// What a client asked the application to do
+pub(crate) enum RemoteAction {
+    /// Replace the ROM with the one in the file
+    Load(String),
+    /// Pause the ROM
+    Pause,
+    /// Resume the ROM
+    Resume,
+    /// Execute a single instruction
+    Step,
+    /// Restart the ROM
+    Reset,
+}
+
+// --- remote server definition -----------------------------------------------
+
+/// A server taking requests as lines of JSON on a TCP port, so that external
+/// debugger UIs, bots and dashboards can drive the emulator
+///
+/// Every request is answered with a line of JSON having `ok` set, along with
+/// `error` if it failed. Subscribed clients are also sent the screen as a
+/// `frame` event whenever it changes. The server is polled every frame, so it
+/// never blocks the window.
+pub(crate) struct RemoteServer {
+    /// The socket accepting the clients
+    listener: TcpListener,
+    /// The connected clients
+    clients: Vec<Client>,
+}
+
+impl RemoteServer {
+    /// Listens for clients on a TCP port
+    ///
+    /// # Params
+    ///
+    /// - `port` - The TCP port to listen on
+    ///
+    /// # Errors
+    ///
+    /// If the port could not be listened on
+    pub fn open(port: u16) -> Result<Self> {
+        let failed = |_| Keet8Error::FailedToOpenRemote(port);
+
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(failed)?;
+        listener.set_nonblocking(true).map_err(failed)?;
+
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    /// Accepts new clients and handles the requests they sent since the last
+    /// call, answering the ones which inspect or change the emulator
+    ///
+    /// Returns what the clients asked the application to do, in order
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator being driven
+    pub fn poll(&mut self, emulator: &mut Emulator) -> Vec<RemoteAction> {
+        while let Ok((stream, peer)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                info!("Remote client connected from {peer}");
+                self.clients.push(Client::new(stream));
+            }
+        }
+
+        let mut actions = Vec::new();
+        for client in &mut self.clients {
+            client.receive();
+            while let Some(line) = client.next_line() {
+                let reply = match serde_json::from_str::<Request>(&line) {
+                    Ok(request) => handle(request, client, emulator, &mut actions),
+                    Err(e) => json!({ "ok": false, "error": e.to_string() }),
+                };
+                client.send(&reply);
+            }
+        }
+
+        self.drop_disconnected();
+        actions
+    }
+
+    /// Sends the screen to the subscribed clients which haven't been sent it
+    /// since it last changed
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator being driven
+    pub fn send_frame(&mut self, emulator: &Emulator) {
+        let hash = emulator.framebuffer_hash();
+        let mut frame = None;
+
+        for client in &mut self.clients {
+            if !client.subscribed || client.last_frame == Some(hash) {
+                continue;
+            }
+
+            let frame = frame.get_or_insert_with(|| frame_event(emulator));
+            client.last_frame = Some(hash);
+            client.send(frame);
+        }
+
+        self.drop_disconnected();
+    }
+
+    /// Forgets the clients whose connection closed or broke
+    fn drop_disconnected(&mut self) {
+        self.clients.retain(|client| {
+            if !client.connected {
+                info!("Remote client disconnected");
+            }
+
+            client.connected
+        });
+    }
+}
+
+/// A client connected to the server
+struct Client {
+    /// The connection to the client
+    stream: TcpStream,
+    /// The bytes received which don't form a whole line yet
+    received: Vec<u8>,
+    /// Flag indicating whether the client is sent the screen
+    subscribed: bool,
+    /// The hash of the screen last sent to the client, if any
+    last_frame: Option<u64>,
+    /// Flag indicating whether the connection is still open
+    connected: bool,
+}
+
+impl Client {
+    /// Creates a client which is yet to send anything
+    ///
+    /// # Params
+    ///
+    /// - `stream` - The connection to the client
+    fn new(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            received: Vec::new(),
+            subscribed: false,
+            last_frame: None,
+            connected: true,
+        }
+    }
+
+    /// Reads the bytes the client sent, marking it disconnected if the
+    /// connection closed
+    fn receive(&mut self) {
+        let mut buffer = [0; 512];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => break self.connected = false,
+                Ok(len) => self.received.extend_from_slice(&buffer[..len]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break self.connected = false,
+            }
+        }
+    }
+
+    /// Takes the next whole line out of the bytes received, skipping empty
+    /// ones
+    fn next_line(&mut self) -> Option<String> {
+        loop {
+            let end = self.received.iter().position(|&b| b == b'\n')?;
+            let line = self.received.drain(..=end).collect::<Vec<u8>>();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+
+            if !line.is_empty() {
+                return Some(line);
+            }
+        }
+    }
+
+    /// Sends a message to the client as a line of JSON, marking it
+    /// disconnected if the connection broke
+    ///
+    /// # Params
+    ///
+    /// - `message` - The message to send
+    fn send(&mut self, message: &Value) {
+        if !self.connected {
+            return;
+        }
+
+        let line = format!("{message}\n");
+        if self.stream.write_all(line.as_bytes()).is_err() {
+            self.connected = false;
+        }
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Handles a request, returning the reply to it
+///
+/// The requests which need the whole application are passed on as actions,
+/// and answered as soon as they are accepted.
+///
+/// # Params
+///
+/// - `request` - The request
+/// - `client` - The client which sent the request
+/// - `emulator` - The emulator being driven
+/// - `actions` - The actions to pass on to the application
+fn handle(
+    request: Request,
+    client: &mut Client,
+    emulator: &mut Emulator,
+    actions: &mut Vec<RemoteAction>,
+) -> Value {
+    match request {
+        Request::Load { rom } => actions.push(RemoteAction::Load(rom)),
+        Request::Pause => actions.push(RemoteAction::Pause),
+        Request::Resume => actions.push(RemoteAction::Resume),
+        Request::Step => actions.push(RemoteAction::Step),
+        Request::Reset => actions.push(RemoteAction::Reset),
+        Request::Regs => {
+            return json!({
+                "ok": true,
+                "pc": emulator.program_counter(),
+                "i": emulator.index(),
+                "sp": emulator.call_stack().len(),
+                "dt": emulator.delay_timer(),
+                "st": emulator.sound_timer(),
+                "v": (0..16).map(|x| emulator.register(x)).collect::<Vec<u8>>(),
+            });
+        }
+        Request::Mem { addr, len } => {
+            let memory = emulator.memory();
+            let start = (addr as usize).min(memory.len());
+            let end = (start + len.unwrap_or(DEFAULT_MEM_LEN) as usize).min(memory.len());
+
+            return json!({ "ok": true, "addr": start, "bytes": &memory[start..end] });
+        }
+        Request::Poke { addr, value } => {
+            if addr as usize >= emulator.memory().len() {
+                return json!({ "ok": false, "error": format!("Invalid address: {addr:#05X}") });
+            }
+
+            emulator.poke(addr, value);
+        }
+        Request::Subscribe => {
+            client.subscribed = true;
+            client.last_frame = None;
+        }
+        Request::Unsubscribe => client.subscribed = false,
+    }
+
+    json!({ "ok": true })
+}
+
+/// Creates the `frame` event sent to subscribed clients, with every row of
+/// the screen in hexadecimal, the leftmost pixel being the highest bit
+///
+/// # Params
+///
+/// - `emulator` - The emulator being driven
+fn frame_event(emulator: &Emulator) -> Value {
+    let width = emulator.screen_width();
+    let rows = emulator
+        .framebuffer()
+        .chunks(width)
+        .map(|row| {
+            row.chunks(4)
+                .map(|pixels| {
+                    let nibble = pixels.iter().fold(0, |acc, &pixel| acc << 1 | (pixel > 0) as u32);
+                    format!("{nibble:X}")
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>();
+
+    json!({
+        "event": "frame",
+        "width": width,
+        "height": emulator.screen_height(),
+        "rows": rows,
+    })
+}
@@ -0,0 +1,215 @@
+use crate::overlay::Overlay;
+use crate::recent::display_name;
+use crate::roms::{builtin_rom_file, is_browsed_file, BUILTIN_ROMS};
+
+use raylib::prelude::*;
+
+use std::path::{Path, PathBuf};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of ROMs listed at once, the list scrolling with the selection
+const VISIBLE_ROMS: usize = 12;
+
+/// The color of the selected ROM
+const SELECTED_COLOR: Color = Color::YELLOW;
+
+// --- rom chooser definition -------------------------------------------------
+
+/// What picking an entry of the list does
+#[derive(Clone)]
+pub(crate) enum Pick {
+    /// Starts a ROM, given as a ROM file such as `pack.zip/game.ch8`
+    Rom(String),
+    /// Lists the directories and ROMs in a directory
+    Browse(PathBuf),
+    /// Opens the settings menu
+    Settings,
+}
+
+/// A list of ROMs to pick one from, shown in the window when a ZIP archive
+/// holds several ROMs, to start one of the ROMs opened recently, or as the
+/// launcher when the emulator is started without a ROM file
+pub(crate) struct RomChooser {
+    /// The title of the list
+    title: String,
+    /// The entries, with the names they are listed under
+    entries: Vec<(String, Pick)>,
+    /// The number of entries at the top which the digit keys start, the
+    /// first one being `1`
+    numbered: usize,
+    /// The index of the selected entry
+    selected: usize,
+}
+
+impl RomChooser {
+    /// Creates the list of the ROMs in an archive with the first ROM selected
+    ///
+    /// # Params
+    ///
+    /// - `archive` - The filepath to the archive
+    /// - `roms` - The ROMs in the archive, as ROM files
+    pub fn new(archive: &str, roms: Vec<String>) -> Self {
+        let entries = roms
+            .into_iter()
+            .map(|rom| (rom[archive.len() + 1..].to_string(), Pick::Rom(rom)))
+            .collect();
+
+        Self {
+            title: format!("Pick a ROM from {archive}"),
+            entries,
+            numbered: 0,
+            selected: 0,
+        }
+    }
+
+    /// Creates the list of the ROMs opened recently with the most recent one
+    /// selected, each of them numbered
+    ///
+    /// # Params
+    ///
+    /// - `roms` - The ROM files, the most recent first
+    pub fn recent(roms: &[String]) -> Self {
+        Self {
+            title: "Pick a recent ROM".to_string(),
+            entries: recent_entries(roms),
+            numbered: roms.len(),
+            selected: 0,
+        }
+    }
+
+    /// Creates the launcher shown when the emulator is started without a ROM
+    /// file, listing the ROMs opened recently, numbered, then the built-in
+    /// ROMs, the file browser and the settings menu
+    ///
+    /// # Params
+    ///
+    /// - `roms` - The ROM files opened recently, the most recent first
+    pub fn launcher(roms: &[String]) -> Self {
+        let mut entries = recent_entries(roms);
+        entries.extend(BUILTIN_ROMS.iter().filter_map(|rom| {
+            let rom_file = builtin_rom_file(rom.name).ok()?;
+            Some((format!("{} - {}", rom.name, rom.description), Pick::Rom(rom_file)))
+        }));
+        let cwd = std::env::current_dir().unwrap_or_default();
+        entries.push(("Browse files...".to_string(), Pick::Browse(cwd)));
+        entries.push(("Settings".to_string(), Pick::Settings));
+
+        Self {
+            title: "Pick a ROM".to_string(),
+            entries,
+            numbered: roms.len(),
+            selected: 0,
+        }
+    }
+
+    /// Creates the list of the directories and ROMs in a directory, with its
+    /// parent directory first
+    ///
+    /// A directory which can't be read lists its parent directory only.
+    ///
+    /// # Params
+    ///
+    /// - `dir` - The directory to list
+    pub fn browse(dir: &Path) -> Self {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push((format!("{name}/"), Pick::Browse(path)));
+            } else if is_browsed_file(&name) {
+                files.push((name, Pick::Rom(path.to_string_lossy().into_owned())));
+            }
+        }
+        dirs.sort_by_key(|(name, _)| name.to_lowercase());
+        files.sort_by_key(|(name, _)| name.to_lowercase());
+
+        let parent = dir.parent().map(|parent| ("../".to_string(), Pick::Browse(parent.into())));
+        Self {
+            title: format!("Pick a ROM from {}", dir.display()),
+            entries: parent.into_iter().chain(dirs).chain(files).collect(),
+            numbered: 0,
+            selected: 0,
+        }
+    }
+
+    /// Moves the selection, wrapping around either end of the list
+    ///
+    /// # Params
+    ///
+    /// - `offset` - The number of ROMs to move down, or up if negative
+    pub fn move_selection(&mut self, offset: i32) {
+        let len = self.entries.len() as i32;
+        self.selected = (self.selected as i32 + offset).rem_euclid(len.max(1)) as usize;
+    }
+
+    /// Gets what picking the selected entry does, if there are any entries
+    pub fn selected(&self) -> Option<&Pick> {
+        self.entries.get(self.selected).map(|(_, pick)| pick)
+    }
+
+    /// Gets the ROM numbered by a digit key, if there is one
+    ///
+    /// # Params
+    ///
+    /// - `digit` - The digit, the first ROM being `1`
+    pub fn numbered(&self, digit: usize) -> Option<&str> {
+        let index = digit.checked_sub(1).filter(|&index| index < self.numbered)?;
+        match self.entries.get(index) {
+            Some((_, Pick::Rom(rom_file))) => Some(rom_file),
+            _ => None,
+        }
+    }
+
+    /// Draws the list centered in the window
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the list on
+    /// - `screen_width` - The width of the window
+    /// - `screen_height` - The height of the window
+    pub fn draw(&self, overlay: &mut Overlay, screen_width: i32, screen_height: i32) {
+        // The list scrolls to keep the selection in view
+        let first = self
+            .selected
+            .saturating_sub(VISIBLE_ROMS - 1)
+            .min(self.entries.len().saturating_sub(VISIBLE_ROMS));
+        let entries = self.entries[first..].iter().take(VISIBLE_ROMS);
+        let hint = if self.numbered > 0 {
+            "Up/Down selects, Enter or 1-9 starts"
+        } else {
+            "Up/Down selects, Enter starts"
+        };
+
+        let mut lines = vec![(self.title.clone(), Color::RAYWHITE)];
+        for (i, (name, _)) in entries.enumerate() {
+            let (marker, color) = if first + i == self.selected {
+                ("> ", SELECTED_COLOR)
+            } else {
+                ("  ", Color::RAYWHITE)
+            };
+            let name = if first + i < self.numbered {
+                format!("{}. {name}", first + i + 1)
+            } else {
+                name.clone()
+            };
+            lines.push((format!("{marker}{name}"), color));
+        }
+        lines.push((hint.to_string(), Color::GRAY));
+
+        overlay.menu(&lines, screen_width, screen_height);
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Lists the ROMs opened recently under their names
+///
+/// # Params
+///
+/// - `roms` - The ROM files, the most recent first
+fn recent_entries(roms: &[String]) -> Vec<(String, Pick)> {
+    roms.iter().map(|rom| (display_name(rom), Pick::Rom(rom.clone()))).collect()
+}
@@ -0,0 +1,307 @@
+use crate::hash;
+use crate::prelude::*;
+
+use keet8_core::Quirks;
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+
+// --- constants --------------------------------------------------------------
+
+/// The ROM database bundled with the emulator, in the format of the
+/// `programs.json` file of the CHIP-8 community database
+const BUNDLED_DATABASE: &str = include_str!("../res/programs.json");
+
+/// The number of times the timers tick per second, converting the tick rates
+/// of the database (instructions per frame) to instructions per second
+const FRAMES_PER_SECOND: u32 = 60;
+
+// --- platform definition ----------------------------------------------------
+
+/// A platform of the community database keet-8 can emulate, with the quirks
+/// it is known for
+struct Platform {
+    /// The identifier of the platform in the database
+    id: &'static str,
+    /// The name shown to the user
+    name: &'static str,
+    /// The quirks of the platform
+    quirks: DatabaseQuirks,
+}
+
+/// The platforms of the community database keet-8 can emulate, the others
+/// (such as MEGA-CHIP) being skipped when picking the platform of a ROM
+const PLATFORMS: [Platform; 7] = [
+    Platform {
+        id: "originalChip8",
+        name: "Chip-8 (COSMAC VIP)",
+        quirks: DatabaseQuirks::COSMAC_VIP,
+    },
+    Platform {
+        id: "hybridVIP",
+        name: "Chip-8 (hybrid VIP)",
+        quirks: DatabaseQuirks::COSMAC_VIP,
+    },
+    Platform {
+        id: "modernChip8",
+        name: "Chip-8",
+        quirks: DatabaseQuirks::MODERN,
+    },
+    Platform {
+        id: "chip48",
+        name: "CHIP-48",
+        quirks: DatabaseQuirks::CHIP48,
+    },
+    Platform {
+        id: "superchip1",
+        name: "SCHIP 1.0",
+        quirks: DatabaseQuirks::CHIP48,
+    },
+    Platform {
+        id: "superchip",
+        name: "SCHIP 1.1",
+        quirks: DatabaseQuirks::SUPERCHIP,
+    },
+    Platform {
+        id: "xochip",
+        name: "XO-CHIP",
+        quirks: DatabaseQuirks::XOCHIP,
+    },
+];
+
+// --- rom profile definition -------------------------------------------------
+
+/// The settings a known ROM plays best with, looked up in the ROM database
+pub(crate) struct RomProfile {
+    /// The title of the program
+    pub title: String,
+    /// The name of the platform picked for the ROM
+    pub platform: &'static str,
+    /// The quirks of the platform, with the overrides of the ROM
+    pub quirks: Quirks,
+    /// The number of instructions executed per second, if the ROM needs a
+    /// specific speed
+    pub cpu_speed: Option<u32>,
+    /// The colors of the pixels which are off and on, as `#RRGGBB`, if the
+    /// ROM was made for specific colors
+    pub colors: Option<(String, String)>,
+}
+
+/// Looks a ROM up in a ROM database by the SHA-1 of its bytes
+///
+/// The database is the one bundled with the emulator unless another one is
+/// given, such as the full `programs.json` of the CHIP-8 community database.
+/// The platform is the first one listed for the ROM which keet-8 can
+/// emulate, and ROMs made only for other platforms aren't found.
+///
+/// # Params
+///
+/// - `rom` - The bytes of the ROM
+/// - `database_file` - The filepath to the ROM database, if not the bundled
+///   one
+///
+/// # Errors
+///
+/// - If the ROM database could not be read
+/// - If the ROM database is invalid
+pub(crate) fn lookup(rom: &[u8], database_file: Option<&str>) -> Result<Option<RomProfile>> {
+    let text = match database_file {
+        Some(file) => std::fs::read_to_string(file)
+            .map_err(|_| Keet8Error::FailedToLoadRomDatabase(file.to_string()))?,
+        None => BUNDLED_DATABASE.to_string(),
+    };
+    let programs: Vec<Program> =
+        serde_json::from_str(&text).map_err(|e| Keet8Error::InvalidRomDatabase(e.to_string()))?;
+
+    let sha1 = hash::sha1_hex(rom);
+
+    let found = programs
+        .into_iter()
+        .find_map(|program| Some((program.roms.get(&sha1)?.clone(), program.title)));
+    let Some((entry, title)) = found else {
+        return Ok(None);
+    };
+
+    let Some(platform) = entry
+        .platforms
+        .iter()
+        .find_map(|id| PLATFORMS.iter().find(|platform| platform.id == id))
+    else {
+        return Ok(None);
+    };
+
+    let quirks = entry
+        .quirky_platforms
+        .get(platform.id)
+        .map_or(platform.quirks, |overrides| overrides.apply(platform.quirks));
+
+    Ok(Some(RomProfile {
+        title,
+        platform: platform.name,
+        quirks: quirks.into(),
+        cpu_speed: entry.tickrate.map(|tickrate| tickrate * FRAMES_PER_SECOND),
+        colors: entry.colors.and_then(|colors| match &colors.pixels[..] {
+            [off, on, ..] => Some((off.clone(), on.clone())),
+            _ => None,
+        }),
+    }))
+}
+
+// --- database definition ----------------------------------------------------
+
+/// A program of the ROM database, with the ROMs released for it
+///
+/// The fields of the database keet-8 doesn't use are ignored.
+#[derive(Deserialize)]
+struct Program {
+    /// The title of the program
+    title: String,
+    /// The ROMs of the program, keyed by their SHA-1 in hexadecimal
+    roms: BTreeMap<String, RomEntry>,
+}
+
+/// A ROM of the ROM database
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RomEntry {
+    /// The identifiers of the platforms the ROM runs on, best first
+    #[serde(default)]
+    platforms: Vec<String>,
+    /// The quirks the ROM needs on a platform which differ from those of the
+    /// platform, keyed by the identifier of the platform
+    #[serde(default)]
+    quirky_platforms: BTreeMap<String, QuirkOverrides>,
+    /// The number of instructions executed per frame
+    tickrate: Option<u32>,
+    /// The colors the ROM was made for
+    colors: Option<RomColors>,
+}
+
+/// The colors of a ROM of the ROM database
+#[derive(Clone, Deserialize)]
+struct RomColors {
+    /// The colors of the pixels, with `0` for off and `1` for on
+    #[serde(default)]
+    pixels: Vec<String>,
+}
+
+/// The quirks as the ROM database names them, with `true` being the behavior
+/// of the later interpreters
+#[derive(Clone, Copy)]
+struct DatabaseQuirks {
+    /// `8XY6`/`8XYE` shift `VX` in place
+    shift: bool,
+    /// `FX55`/`FX65` increment `I` by `X` only
+    memory_increment_by_x: bool,
+    /// `FX55`/`FX65` leave `I` unchanged
+    memory_leave_i_unchanged: bool,
+    /// Sprites wrap around the edges of the screen
+    wrap: bool,
+    /// `BNNN` jumps to `XNN + VX`
+    jump: bool,
+    /// `DXYN` waits for the next frame
+    vblank: bool,
+    /// `8XY1`/`8XY2`/`8XY3` reset `VF`
+    logic: bool,
+}
+
+/// The quirks a ROM of the ROM database overrides on a platform
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuirkOverrides {
+    /// Overrides `shift`, if given
+    shift: Option<bool>,
+    /// Overrides `memory_increment_by_x`, if given
+    memory_increment_by_x: Option<bool>,
+    /// Overrides `memory_leave_i_unchanged`, if given
+    memory_leave_i_unchanged: Option<bool>,
+    /// Overrides `wrap`, if given
+    wrap: Option<bool>,
+    /// Overrides `jump`, if given
+    jump: Option<bool>,
+    /// Overrides `vblank`, if given
+    vblank: Option<bool>,
+    /// Overrides `logic`, if given
+    logic: Option<bool>,
+}
+
+impl DatabaseQuirks {
+    /// The quirks of the original interpreter on the COSMAC VIP
+    const COSMAC_VIP: Self = Self {
+        shift: false,
+        memory_increment_by_x: false,
+        memory_leave_i_unchanged: false,
+        wrap: false,
+        jump: false,
+        vblank: true,
+        logic: true,
+    };
+
+    /// The quirks of modern Chip-8 interpreters
+    const MODERN: Self = Self {
+        vblank: false,
+        logic: false,
+        ..Self::COSMAC_VIP
+    };
+
+    /// The quirks of CHIP-48 and SCHIP 1.0 on the HP-48 calculators
+    const CHIP48: Self = Self {
+        shift: true,
+        memory_increment_by_x: true,
+        jump: true,
+        ..Self::MODERN
+    };
+
+    /// The quirks of SCHIP 1.1
+    const SUPERCHIP: Self = Self {
+        shift: true,
+        memory_leave_i_unchanged: true,
+        jump: true,
+        ..Self::MODERN
+    };
+
+    /// The quirks of XO-CHIP
+    const XOCHIP: Self = Self {
+        wrap: true,
+        ..Self::MODERN
+    };
+}
+
+impl QuirkOverrides {
+    /// Applies the overrides to the quirks of a platform
+    ///
+    /// # Params
+    ///
+    /// - `quirks` - The quirks of the platform
+    fn apply(self, quirks: DatabaseQuirks) -> DatabaseQuirks {
+        DatabaseQuirks {
+            shift: self.shift.unwrap_or(quirks.shift),
+            memory_increment_by_x: self
+                .memory_increment_by_x
+                .unwrap_or(quirks.memory_increment_by_x),
+            memory_leave_i_unchanged: self
+                .memory_leave_i_unchanged
+                .unwrap_or(quirks.memory_leave_i_unchanged),
+            wrap: self.wrap.unwrap_or(quirks.wrap),
+            jump: self.jump.unwrap_or(quirks.jump),
+            vblank: self.vblank.unwrap_or(quirks.vblank),
+            logic: self.logic.unwrap_or(quirks.logic),
+        }
+    }
+}
+
+impl From<DatabaseQuirks> for Quirks {
+    /// Converts the quirks of the ROM database to those of keet-8, which
+    /// increments `I` past the copied range whenever it is incremented
+    fn from(quirks: DatabaseQuirks) -> Self {
+        Self {
+            shift_vy: !quirks.shift,
+            load_store_increment: !quirks.memory_leave_i_unchanged,
+            vf_reset: quirks.logic,
+            jump_vx: quirks.jump,
+            wrap_sprites: quirks.wrap,
+            display_wait: quirks.vblank,
+            ..Self::default()
+        }
+    }
+}
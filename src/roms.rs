@@ -0,0 +1,283 @@
+use crate::archive::ZipArchive;
+use crate::assembler::assemble_program;
+use crate::octo::assemble_octo;
+use crate::prelude::*;
+
+use log::warn;
+
+// --- constants --------------------------------------------------------------
+
+/// The prefix of the ROM file marking a built-in ROM, such as
+/// `builtin:ibm-logo`
+const BUILTIN_PREFIX: &str = "builtin:";
+/// The extension of the source files assembled into ROMs when loaded
+const SOURCE_EXTENSION: &str = ".asm";
+/// The extension of the source files in the dialect of Octo, assembled into
+/// ROMs when loaded
+const OCTO_EXTENSION: &str = ".8o";
+/// The extension of the ZIP archives ROMs are read from, as ROM packs are
+/// distributed
+const ARCHIVE_EXTENSION: &str = ".zip";
+/// The extension of the ROMs found in ZIP archives
+const ROM_EXTENSION: &str = ".ch8";
+/// The extensions of the files listed when browsing for a ROM, being ROMs,
+/// source files and archives
+const BROWSED_EXTENSIONS: [&str; 7] =
+    [ROM_EXTENSION, ".c8", ".sc8", ".xo8", SOURCE_EXTENSION, OCTO_EXTENSION, ARCHIVE_EXTENSION];
+
+/// The ROMs bundled with the emulator, so it can be tried out without
+/// hunting for ROM files
+pub(crate) const BUILTIN_ROMS: [BuiltinRom; 10] = [
+    BuiltinRom {
+        name: "chip8-logo",
+        description: "Draws the CHIP-8 logo, the smallest splash screen",
+        bytes: include_bytes!("../tests/1-chip8-logo.ch8"),
+    },
+    BuiltinRom {
+        name: "ibm-logo",
+        description: "Draws the IBM logo with only a few instructions",
+        bytes: include_bytes!("../tests/2-ibm-logo.ch8"),
+    },
+    BuiltinRom {
+        name: "corax",
+        description: "Checks the results of the core instructions",
+        bytes: include_bytes!("../tests/3-corax+.ch8"),
+    },
+    BuiltinRom {
+        name: "flags",
+        description: "Checks VF after the arithmetic instructions",
+        bytes: include_bytes!("../tests/4-flags.ch8"),
+    },
+    BuiltinRom {
+        name: "quirks",
+        description: "Tells which quirks are enabled, after picking a platform",
+        bytes: include_bytes!("../tests/5-quirks.ch8"),
+    },
+    BuiltinRom {
+        name: "keypad",
+        description: "Shows the keys held down and checks FX0A",
+        bytes: include_bytes!("../tests/6-keypad.ch8"),
+    },
+    BuiltinRom {
+        name: "beep",
+        description: "Beeps whilst a key is held down",
+        bytes: include_bytes!("../tests/7-beep.ch8"),
+    },
+    BuiltinRom {
+        name: "scrolling",
+        description: "Checks the SCHIP scrolling instructions",
+        bytes: include_bytes!("../tests/8-scrolling.ch8"),
+    },
+    BuiltinRom {
+        name: "test-opcode",
+        description: "Checks the core instructions, showing OK or NO for each",
+        bytes: include_bytes!("../tests/test_opcode.ch8"),
+    },
+    BuiltinRom {
+        name: "chip8-test",
+        description: "Checks the core instructions, in another way",
+        bytes: include_bytes!("../tests/chip8-test-rom.ch8"),
+    },
+];
+
+// --- builtin rom definition -------------------------------------------------
+
+/// A ROM bundled with the emulator
+pub(crate) struct BuiltinRom {
+    /// The name the ROM is selected by with `--builtin`
+    pub name: &'static str,
+    /// What the ROM does, as listed by the `builtins` subcommand
+    pub description: &'static str,
+    /// The bytes of the ROM
+    pub bytes: &'static [u8],
+}
+
+// --- roms interface ---------------------------------------------------------
+
+/// Gets the ROM file standing for a built-in ROM, which `read_rom` reads
+/// from the emulator itself
+///
+/// # Params
+///
+/// - `name` - The name of the built-in ROM
+///
+/// # Errors
+///
+/// If there is no built-in ROM by that name
+pub(crate) fn builtin_rom_file(name: &str) -> Result<String> {
+    if !BUILTIN_ROMS.iter().any(|rom| rom.name == name) {
+        return Err(Keet8Error::UnknownBuiltinRom(name.to_string()));
+    }
+
+    Ok(format!("{BUILTIN_PREFIX}{name}"))
+}
+
+/// Reads the bytes of a ROM, being a built-in ROM if the ROM file was given
+/// by `builtin_rom_file`, or assembling the ROM file if it is a source file
+/// ending in `.asm`, or `.8o` in the dialect of Octo
+///
+/// A ROM in a ZIP archive is given as `pack.zip/game.ch8`, and an archive
+/// holding a single ROM can be given as `pack.zip` alone.
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+///
+/// # Errors
+///
+/// - If there was an error when reading the ROM file
+/// - If there is no built-in ROM by that name
+/// - If the source file could not be assembled
+/// - If the archive is invalid, or holds no ROM or several ROMs without one
+///   being picked
+/// - If the ROM is empty
+pub(crate) fn read_rom(rom_file: &str) -> Result<Vec<u8>> {
+    let rom = read_rom_bytes(rom_file)?;
+    if rom.is_empty() {
+        return Err(Keet8Error::EmptyROM);
+    }
+
+    // Instructions are two bytes long, so an odd size hints at a truncated
+    // or mistaken file, though some ROMs do end in a byte of sprite data
+    if rom.len() % 2 != 0 {
+        warn!("{rom_file} has an odd size of {} bytes, and may be truncated", rom.len());
+    }
+
+    Ok(rom)
+}
+
+/// Reads the bytes of a ROM from wherever the ROM file points, as
+/// `read_rom` does without checking them, for looking the ROM up or hashing
+/// it without warning about it twice
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+///
+/// # Errors
+///
+/// - If there was an error when reading the ROM file
+/// - If there is no built-in ROM by that name
+/// - If the source file could not be assembled
+/// - If the archive is invalid, or holds no ROM or several ROMs without one
+///   being picked
+pub(crate) fn read_rom_bytes(rom_file: &str) -> Result<Vec<u8>> {
+    if let Some((archive, name)) = split_archive_path(rom_file) {
+        return ZipArchive::open(archive)?.read(name);
+    }
+
+    if let Some(roms) = archived_roms(rom_file)? {
+        return match &roms[..] {
+            [rom] => read_rom_bytes(rom),
+            [] => Err(Keet8Error::InvalidArchive(
+                rom_file.to_string(),
+                format!("no {ROM_EXTENSION} ROM"),
+            )),
+            _ => Err(Keet8Error::SeveralRomsInArchive(
+                rom_file.to_string(),
+                roms.iter().map(|rom| rom[rom_file.len() + 1..].to_string()).collect(),
+            )),
+        };
+    }
+
+    if rom_file.ends_with(SOURCE_EXTENSION) {
+        let source = keet8_core::read_rom(rom_file)?;
+        return assemble_program(&String::from_utf8_lossy(&source));
+    }
+
+    if rom_file.ends_with(OCTO_EXTENSION) {
+        let source = keet8_core::read_rom(rom_file)?;
+        return assemble_octo(&String::from_utf8_lossy(&source));
+    }
+
+    let Some(name) = builtin_name(rom_file) else {
+        return keet8_core::read_rom(rom_file);
+    };
+
+    BUILTIN_ROMS
+        .iter()
+        .find(|rom| rom.name == name)
+        .map(|rom| rom.bytes.to_vec())
+        .ok_or_else(|| Keet8Error::UnknownBuiltinRom(name.to_string()))
+}
+
+/// Lists the ROMs in a ZIP archive as ROM files, such as `pack.zip/game.ch8`,
+/// if the ROM file is an archive
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+///
+/// # Errors
+///
+/// If the ROM file is an archive which could not be read or is invalid
+pub(crate) fn archived_roms(rom_file: &str) -> Result<Option<Vec<String>>> {
+    if !rom_file.to_ascii_lowercase().ends_with(ARCHIVE_EXTENSION) {
+        return Ok(None);
+    }
+
+    let archive = ZipArchive::open(rom_file)?;
+    let roms = archive
+        .names()
+        .filter(|name| name.to_ascii_lowercase().ends_with(ROM_EXTENSION))
+        .map(|name| format!("{rom_file}/{name}"))
+        .collect();
+
+    Ok(Some(roms))
+}
+
+/// Gets the file on disk the ROM file is read from, being the archive for a
+/// ROM in a ZIP archive
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+pub(crate) fn file_on_disk(rom_file: &str) -> &str {
+    split_archive_path(rom_file).map_or(rom_file, |(archive, _)| archive)
+}
+
+/// Checks whether a file is listed when browsing for a ROM, going by its
+/// extension
+///
+/// # Params
+///
+/// - `name` - The name of the file
+pub(crate) fn is_browsed_file(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    BROWSED_EXTENSIONS.iter().any(|extension| name.ends_with(extension))
+}
+
+/// Gets the name of the built-in ROM the ROM file stands for, if it was
+/// given by `builtin_rom_file`
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+pub(crate) fn builtin_name(rom_file: &str) -> Option<&str> {
+    rom_file.strip_prefix(BUILTIN_PREFIX)
+}
+
+/// Splits a ROM file in a ZIP archive, such as `pack.zip/game.ch8`, into the
+/// filepath to the archive and the name of the ROM within it
+///
+/// # Params
+///
+/// - `rom_file` - The filepath to the ROM file
+fn split_archive_path(rom_file: &str) -> Option<(&str, &str)> {
+    // Lowercasing ASCII keeps the offsets of the characters
+    let end = rom_file
+        .to_ascii_lowercase()
+        .find(&format!("{ARCHIVE_EXTENSION}/"))?
+        + ARCHIVE_EXTENSION.len();
+
+    Some((&rom_file[..end], &rom_file[end + 1..]))
+}
+
+/// Writes the names of the built-in ROMs to stdout, along with what they do
+pub(crate) fn print_builtin_roms() {
+    let width = BUILTIN_ROMS.iter().map(|rom| rom.name.len()).max().unwrap_or(0);
+
+    for rom in &BUILTIN_ROMS {
+        println!("{:<width$}  {}", rom.name, rom.description);
+    }
+}
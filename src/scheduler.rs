@@ -0,0 +1,145 @@
+use keet8_core::Emulator;
+
+// --- constants --------------------------------------------------------------
+
+/// The delay in seconds between ticks of the delay and sound timers (60Hz)
+const TIMER_DELAY: f32 = 1.0 / 60.0;
+/// The longest frame time in seconds caught up on, so that a stall (such as
+/// dragging the window) doesn't make the emulator race to catch up
+const MAX_FRAME_TIME: f32 = 0.25;
+
+// --- scheduler definition ---------------------------------------------------
+
+/// Spreads the instructions and timer ticks over the frames, so that the CPU
+/// runs at its speed and the timers at 60Hz whatever the refresh rate
+///
+/// Every frame the frame time is added with `advance`, after which the
+/// instructions due are taken with `next_step` and then the timer ticks due
+/// with `next_tick`.
+pub(crate) struct Scheduler {
+    /// The number of instructions executed per second
+    cpu_speed: u32,
+    /// The time in seconds accumulated for the CPU cycles still to execute
+    step_time: f32,
+    /// The time in seconds accumulated for the timer ticks still to happen
+    tick_time: f32,
+}
+
+impl Scheduler {
+    /// Creates a scheduler with no time accumulated yet
+    ///
+    /// # Params
+    ///
+    /// - `cpu_speed` - The number of instructions executed per second
+    pub fn new(cpu_speed: u32) -> Self {
+        Self {
+            cpu_speed,
+            step_time: 0.0,
+            tick_time: 0.0,
+        }
+    }
+
+    /// Gets the number of instructions executed per second
+    pub fn cpu_speed(&self) -> u32 {
+        self.cpu_speed
+    }
+
+    /// Sets the number of instructions executed per second
+    ///
+    /// # Params
+    ///
+    /// - `cpu_speed` - The number of instructions executed per second
+    pub fn set_cpu_speed(&mut self, cpu_speed: u32) {
+        self.cpu_speed = cpu_speed;
+    }
+
+    /// Accumulates the time of a frame for the instructions and timer ticks
+    ///
+    /// # Params
+    ///
+    /// - `frame_time` - The time in seconds since the last frame
+    pub fn advance(&mut self, frame_time: f32) {
+        self.step_time = (self.step_time + frame_time).min(MAX_FRAME_TIME);
+        self.tick_time = (self.tick_time + frame_time).min(MAX_FRAME_TIME);
+    }
+
+    /// Checks whether an instruction is due, without taking it
+    pub fn is_step_due(&self) -> bool {
+        self.step_time >= self.step_delay()
+    }
+
+    /// Takes the next instruction due, returning whether there was one
+    pub fn next_step(&mut self) -> bool {
+        let due = self.is_step_due();
+        if due {
+            self.step_time -= self.step_delay();
+        }
+
+        due
+    }
+
+    /// Takes the next timer tick due, returning whether there was one
+    pub fn next_tick(&mut self) -> bool {
+        let due = self.tick_time >= TIMER_DELAY;
+        if due {
+            self.tick_time -= TIMER_DELAY;
+        }
+
+        due
+    }
+
+    /// Drops the time accumulated for instructions, so that it doesn't pile
+    /// up whilst the emulator is paused
+    pub fn skip_steps(&mut self) {
+        self.step_time = 0.0;
+    }
+
+    /// Drops the time accumulated for timer ticks, so that the timers stand
+    /// still whilst the emulator is paused
+    pub fn skip_ticks(&mut self) {
+        self.tick_time = 0.0;
+    }
+
+    /// Gets the delay in seconds between two instructions
+    fn step_delay(&self) -> f32 {
+        1.0 / self.cpu_speed as f32
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Checks whether the ROM is waiting in an idle loop which nothing but a timer
+/// tick can end, being a jump to itself, or reading the delay timer until it
+/// runs out:
+///
+/// ```text
+/// loop: LD Vx, DT
+///       SE Vx, 0
+///       JP loop
+/// ```
+///
+/// Running the rest of the instructions of the frame would only spin around
+/// the loop, so they can be skipped. A loop with a breakpoint in it isn't
+/// idle, so that the breakpoint is still hit.
+///
+/// # Params
+///
+/// - `emulator` - The emulator about to execute its next instruction
+pub(crate) fn is_idle(emulator: &Emulator) -> bool {
+    let pc = emulator.program_counter();
+    if emulator.breakpoints().any(|addr| addr.wrapping_sub(pc) < 6) {
+        return false;
+    }
+
+    let fetch = |offset: u16| emulator.fetch(pc.wrapping_add(offset));
+    let jump_back = 0x1000 | (pc & 0x0FFF);
+    if fetch(0) == jump_back {
+        return true;
+    }
+
+    let x = fetch(0) & 0x0F00;
+    fetch(0) & 0xF0FF == 0xF007
+        && fetch(2) == 0x3000 | x
+        && fetch(4) == jump_back
+        && emulator.delay_timer() > 0
+}
@@ -0,0 +1,519 @@
+use crate::prelude::*;
+
+use keet8_core::{Emulator, FrameBuffer, Renderer, HIRES_HEIGHT, HIRES_WIDTH};
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The width in pixels of the texture the emulator display is rendered to,
+/// being the width of the high resolution mode
+const TEXTURE_WIDTH: u32 = HIRES_WIDTH as u32;
+/// The height in pixels of the texture the emulator display is rendered to,
+/// being the height of the high resolution mode
+const TEXTURE_HEIGHT: u32 = HIRES_HEIGHT as u32;
+
+/// The color the pixels erased by sprite collisions flash in
+const COLLISION_COLOR: Color = Color::RED;
+
+/// The smallest size in window pixels of a pixel of the display for the grid
+/// to be drawn, below which the lines would hide the pixels
+const MIN_GRID_CELL: f32 = 4.0;
+
+/// The rate in Hz the phosphor persistence is counted in frames of, as the
+/// timers of the emulator run at
+const PHOSPHOR_RATE: f32 = 60.0;
+
+/// The fragment shader of the CRT filter, giving the display curved glass,
+/// glowing pixels and dark gaps between the scanlines
+const CRT_SHADER: &str = r#"
+#version 330
+
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+
+// The size of the emulated screen in pixels, one scanline per row
+uniform vec2 resolution;
+
+out vec4 finalColor;
+
+const float CURVATURE = 0.06;
+const float GLOW = 0.6;
+const float SCANLINES = 0.45;
+
+void main()
+{
+    // Bend the screen outwards from its center, like the glass of a CRT
+    vec2 centered = fragTexCoord*2.0 - 1.0;
+    centered *= 1.0 + CURVATURE*dot(centered, centered);
+    vec2 uv = centered*0.5 + 0.5;
+
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))))
+    {
+        finalColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    // Let the lit pixels bleed into their neighbours
+    vec2 texel = 1.0/vec2(textureSize(texture0, 0));
+    vec4 color = texture(texture0, uv);
+    vec4 glow = (texture(texture0, uv + vec2(texel.x, 0.0)) + texture(texture0, uv - vec2(texel.x, 0.0))
+        + texture(texture0, uv + vec2(0.0, texel.y)) + texture(texture0, uv - vec2(0.0, texel.y)))*0.25;
+    color = mix(color, max(color, glow), GLOW);
+
+    // Darken the edges of every row of the emulated screen
+    float row = fract(uv.y*resolution.y)*2.0 - 1.0;
+    float scanline = 1.0 - SCANLINES*row*row;
+
+    finalColor = vec4(color.rgb*scanline, 1.0)*colDiffuse*fragColor;
+}
+"#;
+
+// --- screen definition ------------------------------------------------------
+
+/// The emulator display, rendered to a texture which is then scaled onto the
+/// window, optionally through the CRT filter
+pub(crate) struct Screen {
+    /// The texture the framebuffer is rendered to, one texel per pixel in the
+    /// high resolution mode
+    texture: RenderTexture2D,
+    /// The shader of the CRT filter
+    crt: Shader,
+    /// The location of the `resolution` uniform of the CRT filter
+    resolution_loc: i32,
+    /// Flag indicating whether the CRT filter is applied
+    crt_enabled: bool,
+    /// Flag indicating whether the display is scaled by whole numbers only,
+    /// keeping every pixel the same size
+    integer_scaling: bool,
+    /// The number of 60Hz frames a pixel takes to fade out after turning off,
+    /// with 0 turning it off instantly
+    phosphor: u32,
+    /// The brightness of every pixel of the framebuffer, from 0 to 1, kept
+    /// whilst the phosphor fades out
+    brightness: Vec<f32>,
+    /// Flag indicating whether lines are drawn between the pixels, in the
+    /// color of the pixels which are off
+    pixel_grid: bool,
+    /// The part of the window the display is drawn in, if not the whole
+    /// window, such as half of it when running side by side
+    viewport: Option<Rectangle>,
+    /// The width and height of the screen the texture holds, with its
+    /// foreground and background colors, if it was drawn yet
+    drawn: Option<(usize, usize, Color, Color)>,
+}
+
+impl Screen {
+    /// Creates the texture of the display and compiles the CRT filter
+    ///
+    /// # Params
+    ///
+    /// - `rl` - The handle to the raylib context
+    /// - `thread` - The thread on which raylib is running
+    /// - `crt_enabled` - Whether the CRT filter is initially applied
+    /// - `integer_scaling` - Whether the display is initially scaled by whole
+    ///   numbers only
+    /// - `phosphor` - The number of 60Hz frames a pixel takes to fade out
+    ///   after turning off, with 0 turning it off instantly
+    ///
+    /// # Errors
+    ///
+    /// If the texture could not be created
+    pub fn new(
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        crt_enabled: bool,
+        integer_scaling: bool,
+        phosphor: u32,
+    ) -> Result<Self> {
+        let texture = rl
+            .load_render_texture(thread, TEXTURE_WIDTH, TEXTURE_HEIGHT)
+            .map_err(Keet8Error::FailedToCreateTexture)?;
+
+        let crt = rl.load_shader_from_memory(thread, None, Some(CRT_SHADER));
+        let resolution_loc = crt.get_shader_location("resolution");
+
+        Ok(Self {
+            texture,
+            crt,
+            resolution_loc,
+            crt_enabled,
+            integer_scaling,
+            phosphor,
+            brightness: Vec::new(),
+            pixel_grid: false,
+            viewport: None,
+            drawn: None,
+        })
+    }
+
+    /// Sets the part of the window the display is drawn in
+    ///
+    /// # Params
+    ///
+    /// - `viewport` - The part of the window, or `None` for the whole window
+    pub fn set_viewport(&mut self, viewport: Option<Rectangle>) {
+        self.viewport = viewport;
+    }
+
+    /// Draws lines between the pixels, or stops drawing them
+    ///
+    /// # Params
+    ///
+    /// - `enabled` - Whether the lines are drawn
+    pub fn set_pixel_grid(&mut self, enabled: bool) {
+        self.pixel_grid = enabled;
+    }
+
+    /// Turns the CRT filter on or off, returning whether it is now on
+    pub fn toggle_crt(&mut self) -> bool {
+        self.crt_enabled = !self.crt_enabled;
+        self.crt_enabled
+    }
+
+    /// Switches between scaling the display by whole numbers only and
+    /// stretching it to fit the window, returning whether it is now scaled
+    /// by whole numbers
+    pub fn toggle_integer_scaling(&mut self) -> bool {
+        self.integer_scaling = !self.integer_scaling;
+        self.integer_scaling
+    }
+
+    /// Gets the renderer presenting the screen of the emulator on the window
+    /// whilst it is drawn
+    ///
+    /// # Params
+    ///
+    /// - `d` - The draw handle provided by raylib
+    /// - `thread` - The thread on which raylib is running
+    /// - `foreground` - The color of the pixels which are on
+    /// - `background` - The color of the pixels which are off
+    pub fn renderer<'a, 'd>(
+        &'a mut self,
+        d: &'a mut RaylibDrawHandle<'d>,
+        thread: &'a RaylibThread,
+        foreground: Color,
+        background: Color,
+    ) -> WindowRenderer<'a, 'd> {
+        WindowRenderer {
+            screen: self,
+            d,
+            thread,
+            foreground,
+            background,
+        }
+    }
+
+    /// Draws the screen of the emulator, scaled as large as fits in the
+    /// window whilst keeping its aspect ratio, and centered
+    ///
+    /// With integer scaling the scale is rounded down to a whole number of
+    /// window pixels per texel, so that no pixel comes out larger than
+    /// another.
+    ///
+    /// # Params
+    ///
+    /// - `d` - The draw handle provided by raylib
+    /// - `thread` - The thread on which raylib is running
+    /// - `fb` - The screen to draw
+    /// - `foreground` - The color of the pixels which are on
+    /// - `background` - The color of the pixels which are off
+    fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        thread: &RaylibThread,
+        fb: &FrameBuffer,
+        foreground: Color,
+        background: Color,
+    ) {
+        let width = fb.width() as f32;
+        let height = fb.height() as f32;
+
+        if self.phosphor > 0 {
+            self.fade(fb, d.get_frame_time());
+        }
+
+        // Only the rows which changed are drawn to the texture again, unless
+        // the phosphor fades the pixels every frame or the texture holds
+        // another resolution or other colors
+        let drawn = (fb.width(), fb.height(), foreground, background);
+        let redraw = self.phosphor > 0 || self.drawn != Some(drawn);
+        if redraw || fb.dirty_rows() != 0 {
+            let mut t = d.begin_texture_mode(thread, &mut self.texture);
+
+            let scale = TEXTURE_WIDTH as f32 / width;
+            if self.phosphor > 0 {
+                t.clear_background(background);
+                draw_phosphor(&mut t, &self.brightness, fb.width(), foreground, scale);
+            } else if redraw {
+                t.clear_background(background);
+                draw_framebuffer(&mut t, fb, foreground, scale);
+            } else {
+                draw_dirty_rows(&mut t, fb, foreground, background, scale);
+            }
+        }
+        self.drawn = Some(drawn);
+
+        // Render textures are stored upside down, hence the negative height
+        let source = Rectangle::new(0.0, 0.0, TEXTURE_WIDTH as f32, -(TEXTURE_HEIGHT as f32));
+        let dest = display_area(self.viewport(d), width, height, self.integer_scaling);
+
+        let texture = self.texture.texture();
+        let origin = Vector2::zero();
+
+        if self.crt_enabled {
+            let resolution = Vector2::new(width, height);
+            self.crt.set_shader_value(self.resolution_loc, resolution);
+
+            let mut s = d.begin_shader_mode(&self.crt);
+            s.draw_texture_pro(texture, source, dest, origin, 0.0, Color::WHITE);
+        } else {
+            d.draw_texture_pro(texture, source, dest, origin, 0.0, Color::WHITE);
+        }
+
+        if self.pixel_grid {
+            draw_grid(d, dest, fb.width(), fb.height(), background);
+        }
+    }
+
+    /// Flashes the pixels erased by the sprite collisions of the last frame,
+    /// on top of the screen presented by the renderer
+    ///
+    /// # Params
+    ///
+    /// - `d` - The draw handle provided by raylib
+    /// - `emulator` - The emulator to take the collisions of
+    pub fn draw_collisions(&self, d: &mut RaylibDrawHandle, emulator: &Emulator) {
+        let width = emulator.screen_width();
+        let height = emulator.screen_height() as f32;
+        let area = display_area(self.viewport(d), width as f32, height, self.integer_scaling);
+        let scale = area.width / width as f32;
+
+        for &i in emulator.collisions().pixels() {
+            let (x, y) = ((i % width) as f32, (i / width) as f32);
+            let cell = Rectangle::new(area.x + x * scale, area.y + y * scale, scale, scale);
+            d.draw_rectangle_rec(cell, COLLISION_COLOR);
+        }
+    }
+
+    /// Gets the part of the window the display is drawn in
+    ///
+    /// # Params
+    ///
+    /// - `d` - The draw handle provided by raylib
+    fn viewport(&self, d: &RaylibDrawHandle) -> Rectangle {
+        self.viewport.unwrap_or_else(|| {
+            Rectangle::new(0.0, 0.0, d.get_screen_width() as f32, d.get_screen_height() as f32)
+        })
+    }
+
+    /// Lights the pixels which are on and fades out the ones which are off
+    ///
+    /// # Params
+    ///
+    /// - `fb` - The screen to take the pixels which are on from
+    /// - `frame_time` - The time in seconds since the last frame
+    fn fade(&mut self, fb: &FrameBuffer, frame_time: f32) {
+        let num_pixels = fb.width() * fb.height();
+
+        // Switching the resolution moves every pixel, so nothing is kept
+        if self.brightness.len() != num_pixels {
+            self.brightness = vec![0.0; num_pixels];
+        }
+
+        let step = frame_time * PHOSPHOR_RATE / self.phosphor as f32;
+        for (brightness, (_, _, on)) in self.brightness.iter_mut().zip(fb.iter()) {
+            *brightness = if on { 1.0 } else { (*brightness - step).max(0.0) };
+        }
+    }
+}
+
+// --- window renderer definition ---------------------------------------------
+
+/// The raylib backend of the `Renderer`, presenting the screen on the window
+/// through the `Screen` whilst a frame is drawn
+pub(crate) struct WindowRenderer<'a, 'd> {
+    /// The display the screen is drawn with
+    screen: &'a mut Screen,
+    /// The draw handle of the frame being drawn
+    d: &'a mut RaylibDrawHandle<'d>,
+    /// The thread on which raylib is running
+    thread: &'a RaylibThread,
+    /// The color of the pixels which are on
+    foreground: Color,
+    /// The color of the pixels which are off
+    background: Color,
+}
+
+impl Renderer for WindowRenderer<'_, '_> {
+    /// Draws the screen onto the window
+    fn present(&mut self, fb: &FrameBuffer) {
+        self.screen.draw(self.d, self.thread, fb, self.foreground, self.background);
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Gets the area of the viewport the display is drawn in, being as large as
+/// fits whilst keeping the aspect ratio of the display, and centered so the
+/// rest of the viewport is split evenly between the bars on either side
+///
+/// # Params
+///
+/// - `viewport` - The part of the window the display is drawn in
+/// - `width` - The width in pixels of the display
+/// - `height` - The height in pixels of the display
+/// - `integer_scaling` - Whether the texture the display is rendered to is
+///   scaled by a whole number
+fn display_area(viewport: Rectangle, width: f32, height: f32, integer_scaling: bool) -> Rectangle {
+    let (screen_width, screen_height) = (viewport.width, viewport.height);
+    let mut scale = (screen_width / width).min(screen_height / height);
+
+    // The texture holds the high resolution, so the low resolution has two
+    // texels per pixel
+    if integer_scaling {
+        let texels = TEXTURE_WIDTH as f32 / width;
+        scale = (scale / texels).floor().max(1.0) * texels;
+    }
+
+    let (area_width, area_height) = (width * scale, height * scale);
+    Rectangle::new(
+        viewport.x + ((screen_width - area_width) / 2.0).floor(),
+        viewport.y + ((screen_height - area_height) / 2.0).floor(),
+        area_width,
+        area_height,
+    )
+}
+
+/// Creates an image of the framebuffer of the emulator, with a square of
+/// `scale` by `scale` pixels per pixel of the screen
+///
+/// # Params
+///
+/// - `fb` - The screen to take the image of
+/// - `foreground` - The color of the pixels which are on
+/// - `background` - The color of the pixels which are off
+/// - `scale` - The size of the square of a pixel, with 1 being the native
+///   resolution
+pub(crate) fn framebuffer_image(
+    fb: &FrameBuffer,
+    foreground: Color,
+    background: Color,
+    scale: u32,
+) -> Image {
+    let width = fb.width();
+    let height = fb.height();
+
+    let mut image = Image::gen_image_color(width as i32, height as i32, background);
+    for (x, y) in fb.lit_pixels() {
+        image.draw_pixel(x as i32, y as i32, foreground);
+    }
+
+    image.resize_nn((width as u32 * scale) as i32, (height as u32 * scale) as i32);
+    image
+}
+
+/// Draws the screen with a square per lit pixel
+///
+/// # Params
+///
+/// - `d` - The handle to draw with
+/// - `fb` - The screen to draw
+/// - `color` - The color of the pixels which are on
+/// - `scale` - The size of the square of a pixel
+pub(crate) fn draw_framebuffer(
+    d: &mut impl RaylibDraw,
+    fb: &FrameBuffer,
+    color: Color,
+    scale: f32,
+) {
+    for (x, y) in fb.lit_pixels() {
+        let cell = Rectangle::new(x as f32 * scale, y as f32 * scale, scale, scale);
+        d.draw_rectangle_rec(cell, color);
+    }
+}
+
+/// Draws the rows of the screen which changed over what the handle drew
+/// before, leaving the other rows as they are
+///
+/// # Params
+///
+/// - `d` - The handle to draw with
+/// - `fb` - The screen to draw
+/// - `foreground` - The color of the pixels which are on
+/// - `background` - The color of the pixels which are off
+/// - `scale` - The size of the square of a pixel
+fn draw_dirty_rows(
+    d: &mut impl RaylibDraw,
+    fb: &FrameBuffer,
+    foreground: Color,
+    background: Color,
+    scale: f32,
+) {
+    for y in (0..fb.height()).filter(|&y| fb.is_row_dirty(y)) {
+        let row = Rectangle::new(0.0, y as f32 * scale, fb.width() as f32 * scale, scale);
+        d.draw_rectangle_rec(row, background);
+
+        for x in (0..fb.width()).filter(|&x| fb.is_on(x, y)) {
+            let cell = Rectangle::new(x as f32 * scale, y as f32 * scale, scale, scale);
+            d.draw_rectangle_rec(cell, foreground);
+        }
+    }
+}
+
+/// Draws a line between every row and column of pixels of the display, as
+/// long as the pixels are large enough for the lines to leave them visible
+///
+/// # Params
+///
+/// - `d` - The handle to draw with
+/// - `area` - The part of the window the display is drawn in
+/// - `width` - The width in pixels of the screen in the current resolution
+///   mode
+/// - `height` - The height in pixels of the screen in the current resolution
+///   mode
+/// - `color` - The color of the lines
+fn draw_grid(d: &mut impl RaylibDraw, area: Rectangle, width: usize, height: usize, color: Color) {
+    let scale = area.width / width as f32;
+    if scale < MIN_GRID_CELL {
+        return;
+    }
+
+    for x in 1..width {
+        let line = Rectangle::new(area.x + x as f32 * scale, area.y, 1.0, area.height);
+        d.draw_rectangle_rec(line, color);
+    }
+    for y in 1..height {
+        let line = Rectangle::new(area.x, area.y + y as f32 * scale, area.width, 1.0);
+        d.draw_rectangle_rec(line, color);
+    }
+}
+
+/// Draws a square per pixel which is still glowing, blended with the
+/// background by its brightness
+///
+/// # Params
+///
+/// - `d` - The handle to draw with
+/// - `brightness` - The brightness of every pixel, from 0 to 1
+/// - `width` - The width in pixels of the screen in the current resolution
+///   mode
+/// - `color` - The color of the pixels which are on
+/// - `scale` - The size of the square of a pixel
+fn draw_phosphor(
+    d: &mut impl RaylibDraw,
+    brightness: &[f32],
+    width: usize,
+    color: Color,
+    scale: f32,
+) {
+    for (i, &brightness) in brightness.iter().enumerate() {
+        if brightness > 0.0 {
+            let (x, y) = (i % width, i / width);
+            let cell = Rectangle::new(x as f32 * scale, y as f32 * scale, scale, scale);
+            d.draw_rectangle_rec(cell, color.fade(brightness));
+        }
+    }
+}
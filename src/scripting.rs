@@ -0,0 +1,379 @@
+use crate::input::InputSource;
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+use raylib::RaylibHandle;
+use rhai::{CallFnOptions, Dynamic, Engine, Map, Scope, AST, INT};
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of operations a single run of the script may take, so a script
+/// stuck in a loop fails rather than freezing the emulator
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+// --- script definition ------------------------------------------------------
+
+/// A Rhai script run alongside the ROM, such as a bot, an auto-splitter or a
+/// visualization
+///
+/// The script is run once when loaded, and then its hooks are called as the
+/// ROM runs, the ones it doesn't define being skipped:
+///
+/// - `on_start()` - Once after loading
+/// - `on_frame_start()` - Before the instructions of a frame
+/// - `on_frame_end()` - After the timers of a frame have ticked
+/// - `on_memory(addr, old, new)` - After an instruction changed a byte of
+///   memory watched with `watch_memory(addr)`
+/// - `on_register(x, old, new)` - After an instruction changed a `V`
+///   register watched with `watch_register(x)`
+///
+/// Rhai functions can't see the variables of the script, so the hooks are
+/// called as methods of an object map kept between calls, holding the state
+/// of the script as properties of `this`.
+///
+/// Whilst a hook runs, the script reads the emulator through `peek(addr)`,
+/// `reg(x)`, `index()`, `pc()`, `key(k)`, `pixel(x, y)`, `screen_width()`,
+/// `screen_height()` and `frame()`, and changes it through `poke(addr, value)`,
+/// `set_reg(x, value)`, and `hold(k)` and `release(k)` pressing keys of the
+/// keypad on top of the keyboard.
+pub(crate) struct Script {
+    /// The engine running the script, with the emulator functions
+    engine: Engine,
+    /// The compiled script
+    ast: AST,
+    /// The variables of the script
+    scope: Scope<'static>,
+    /// The object map the hooks are called on
+    this: Dynamic,
+    /// The view of the emulator shared with the functions of the engine
+    view: Rc<RefCell<View>>,
+}
+
+/// The state of the emulator the script sees whilst a hook runs, copied from
+/// the emulator before the hook and with the changes of the script written
+/// back after it
+#[derive(Default)]
+struct View {
+    /// The memory
+    memory: Vec<u8>,
+    /// The `V` registers
+    registers: [u8; 16],
+    /// The index register
+    index: u16,
+    /// The program counter
+    pc: u16,
+    /// The keys held down by the player, with bit `K` set for key `K`
+    keys: u16,
+    /// The keys held down by the script, with bit `K` set for key `K`
+    held: u16,
+    /// The pixels of the screen, row by row
+    pixels: Vec<u8>,
+    /// The width in pixels of the screen
+    width: usize,
+    /// The height in pixels of the screen
+    height: usize,
+    /// The number of frames run so far
+    frame: u64,
+    /// The changes made by the script, written back after the hook
+    writes: Vec<Write>,
+    /// The watched addresses with the bytes they held after the last
+    /// instruction
+    watched_memory: Vec<(u16, u8)>,
+    /// The watched `V` registers with the values they held after the last
+    /// instruction
+    watched_registers: Vec<(usize, u8)>,
+}
+
+/// A change made to the emulator by the script
+enum Write {
+    /// A byte written to memory
+    Memory(u16, u8),
+    /// A value written to a `V` register
+    Register(usize, u8),
+}
+
+impl Script {
+    /// Loads and runs the script, then calls its `on_start` hook
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the script
+    /// - `emulator` - The emulator the script runs against
+    ///
+    /// # Errors
+    ///
+    /// If the script could not be read or compiled, or failed whilst running
+    pub fn load(path: &str, emulator: &mut Emulator) -> Result<Self> {
+        let view = Rc::new(RefCell::new(View::default()));
+        let engine = create_engine(&view);
+        let ast = engine
+            .compile_file(PathBuf::from(path))
+            .map_err(|e| Keet8Error::ScriptFailed(format!("{path}: {e}")))?;
+
+        let mut script = Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            this: Dynamic::from_map(Map::new()),
+            view,
+        };
+
+        script.view.borrow_mut().sync(emulator);
+        script
+            .engine
+            .run_ast_with_scope(&mut script.scope, &script.ast)
+            .map_err(|e| Keet8Error::ScriptFailed(e.to_string()))?;
+        script.view.borrow_mut().apply(emulator);
+
+        script.call(emulator, "on_start", &[])?;
+        Ok(script)
+    }
+
+    /// Sets the keys held down by the player, seen by the script through
+    /// `key(k)`
+    ///
+    /// # Params
+    ///
+    /// - `keys` - The keys held down, with bit `K` set for key `K`
+    pub fn set_keys(&mut self, keys: u16) {
+        self.view.borrow_mut().keys = keys;
+    }
+
+    /// Gets the keys held down by the script, with bit `K` set for key `K`
+    pub fn held_keys(&self) -> u16 {
+        self.view.borrow().held
+    }
+
+    /// Calls the `on_frame_start` hook, before the instructions of a frame
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator the script runs against
+    ///
+    /// # Errors
+    ///
+    /// If the hook failed
+    pub fn frame_start(&mut self, emulator: &mut Emulator) -> Result<()> {
+        self.call(emulator, "on_frame_start", &[])
+    }
+
+    /// Calls the `on_frame_end` hook, after the timers of a frame have ticked,
+    /// and counts the frame
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator the script runs against
+    ///
+    /// # Errors
+    ///
+    /// If the hook failed
+    pub fn frame_end(&mut self, emulator: &mut Emulator) -> Result<()> {
+        self.call(emulator, "on_frame_end", &[])?;
+        self.view.borrow_mut().frame += 1;
+        Ok(())
+    }
+
+    /// Calls the `on_memory` and `on_register` hooks for the watched memory
+    /// and registers an instruction changed
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator which executed the instruction
+    ///
+    /// # Errors
+    ///
+    /// If a hook failed
+    pub fn after_step(&mut self, emulator: &mut Emulator) -> Result<()> {
+        let mut changes = Vec::new();
+        let mut view = self.view.borrow_mut();
+
+        for (addr, value) in &mut view.watched_memory {
+            let new = emulator.memory()[*addr as usize];
+            if new != *value {
+                changes.push(("on_memory", *addr as INT, *value as INT, new as INT));
+                *value = new;
+            }
+        }
+        for (x, value) in &mut view.watched_registers {
+            let new = emulator.register(*x);
+            if new != *value {
+                changes.push(("on_register", *x as INT, *value as INT, new as INT));
+                *value = new;
+            }
+        }
+        drop(view);
+
+        for (hook, at, old, new) in changes {
+            self.call(emulator, hook, &[at, old, new])?;
+        }
+
+        Ok(())
+    }
+
+    /// Calls a hook of the script with the current state of the emulator,
+    /// writing the changes of the script back
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator the script runs against
+    /// - `hook` - The name of the hook
+    /// - `args` - The arguments of the hook
+    ///
+    /// # Errors
+    ///
+    /// If the hook failed
+    fn call(&mut self, emulator: &mut Emulator, hook: &str, args: &[INT]) -> Result<()> {
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|f| f.name == hook && f.params.len() == args.len());
+        if !defined {
+            return Ok(());
+        }
+
+        self.view.borrow_mut().sync(emulator);
+
+        // Top-level statements already ran when the script was loaded
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.this);
+        // Whatever the hook returns is ignored
+        let args = args.to_vec();
+        let _ = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut self.scope, &self.ast, hook, args)
+            .map_err(|e| Keet8Error::ScriptFailed(format!("{hook}: {e}")))?;
+
+        self.view.borrow_mut().apply(emulator);
+        Ok(())
+    }
+}
+
+impl InputSource for Script {
+    /// Reads the keys held down by the script through `press(k)`
+    fn poll(&mut self, _rl: &RaylibHandle) -> u16 {
+        self.held_keys()
+    }
+}
+
+impl View {
+    /// Copies the state of the emulator
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to copy the state of
+    fn sync(&mut self, emulator: &Emulator) {
+        self.memory.clear();
+        self.memory.extend_from_slice(emulator.memory());
+        self.registers = std::array::from_fn(|x| emulator.register(x));
+        self.index = emulator.index();
+        self.pc = emulator.program_counter();
+        self.pixels.clear();
+        self.pixels.extend_from_slice(emulator.framebuffer());
+        self.width = emulator.screen_width();
+        self.height = emulator.screen_height();
+    }
+
+    /// Writes the changes made by the script back to the emulator, and takes
+    /// the watched memory and registers as they are now, so the script isn't
+    /// told about its own changes
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to write the changes to
+    fn apply(&mut self, emulator: &mut Emulator) {
+        for write in self.writes.drain(..) {
+            match write {
+                Write::Memory(addr, value) => emulator.poke(addr, value),
+                Write::Register(x, value) => emulator.set_register(x, value),
+            }
+        }
+
+        for (addr, value) in &mut self.watched_memory {
+            *value = emulator.memory()[*addr as usize];
+        }
+        for (x, value) in &mut self.watched_registers {
+            *value = emulator.register(*x);
+        }
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Creates the engine running the script, with the functions reading and
+/// changing the emulator through the view
+///
+/// Addresses wrap around memory, and registers and keys are taken from their
+/// low nibble, so the functions can't fail.
+///
+/// # Params
+///
+/// - `view` - The view of the emulator shared with the functions
+fn create_engine(view: &Rc<RefCell<View>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let v = view.clone();
+    engine.register_fn("peek", move |addr: INT| {
+        let view = v.borrow();
+        view.memory[addr as usize % view.memory.len()] as INT
+    });
+    let v = view.clone();
+    engine.register_fn("poke", move |addr: INT, value: INT| {
+        let mut view = v.borrow_mut();
+        let addr = addr as usize % view.memory.len();
+        view.memory[addr] = value as u8;
+        view.writes.push(Write::Memory(addr as u16, value as u8));
+    });
+    let v = view.clone();
+    engine.register_fn("reg", move |x: INT| v.borrow().registers[x as usize & 0xF] as INT);
+    let v = view.clone();
+    engine.register_fn("set_reg", move |x: INT, value: INT| {
+        let mut view = v.borrow_mut();
+        view.registers[x as usize & 0xF] = value as u8;
+        view.writes.push(Write::Register(x as usize & 0xF, value as u8));
+    });
+    let v = view.clone();
+    engine.register_fn("index", move || v.borrow().index as INT);
+    let v = view.clone();
+    engine.register_fn("pc", move || v.borrow().pc as INT);
+    let v = view.clone();
+    engine.register_fn("frame", move || v.borrow().frame as INT);
+
+    let v = view.clone();
+    engine.register_fn("key", move |k: INT| {
+        let view = v.borrow();
+        (view.keys | view.held) >> (k & 0xF) & 1 == 1
+    });
+    let v = view.clone();
+    engine.register_fn("hold", move |k: INT| v.borrow_mut().held |= 1 << (k & 0xF));
+    let v = view.clone();
+    engine.register_fn("release", move |k: INT| v.borrow_mut().held &= !(1 << (k & 0xF)));
+
+    let v = view.clone();
+    engine.register_fn("pixel", move |x: INT, y: INT| {
+        let view = v.borrow();
+        let on_screen =
+            (0..view.width as INT).contains(&x) && (0..view.height as INT).contains(&y);
+        on_screen && view.pixels[y as usize * view.width + x as usize] > 0
+    });
+    let v = view.clone();
+    engine.register_fn("screen_width", move || v.borrow().width as INT);
+    let v = view.clone();
+    engine.register_fn("screen_height", move || v.borrow().height as INT);
+
+    let v = view.clone();
+    engine.register_fn("watch_memory", move |addr: INT| {
+        let mut view = v.borrow_mut();
+        let addr = addr as usize % view.memory.len();
+        view.watched_memory.push((addr as u16, 0));
+    });
+    let v = view.clone();
+    engine.register_fn("watch_register", move |x: INT| {
+        v.borrow_mut().watched_registers.push((x as usize & 0xF, 0));
+    });
+
+    engine
+}
@@ -0,0 +1,321 @@
+use crate::accessibility::{self, Accessibility};
+use crate::application::{CPU_SPEED_STEP, MAX_CPU_SPEED};
+use crate::audio::step_volume;
+use crate::config::Setting;
+use crate::keymap::Layout;
+use crate::overlay::Overlay;
+use crate::palette::PALETTES;
+
+use keet8_core::Quirks;
+use raylib::prelude::*;
+
+// --- constants --------------------------------------------------------------
+
+/// The color of the selected entry
+const SELECTED_COLOR: Color = Color::YELLOW;
+
+/// Gets the flag of a quirk within the quirks
+type QuirkFlag = fn(&mut Quirks) -> &mut bool;
+
+/// The quirks, by their names in the `[quirks]` table of the configuration
+/// file, with the flag of each
+const QUIRKS: [(&str, QuirkFlag); 9] = [
+    ("shift_vy", |quirks| &mut quirks.shift_vy),
+    ("load_store_increment", |quirks| &mut quirks.load_store_increment),
+    ("vf_reset", |quirks| &mut quirks.vf_reset),
+    ("jump_vx", |quirks| &mut quirks.jump_vx),
+    ("wrap_sprites", |quirks| &mut quirks.wrap_sprites),
+    ("display_wait", |quirks| &mut quirks.display_wait),
+    ("index_overflow", |quirks| &mut quirks.index_overflow),
+    ("strict_memory", |quirks| &mut quirks.strict_memory),
+    ("clamp_memory", |quirks| &mut quirks.clamp_memory),
+];
+
+// --- settings menu definition -----------------------------------------------
+
+/// What an entry of the settings menu asks the application to do when it is
+/// picked
+pub(crate) enum Choice {
+    /// Apply a changed setting
+    Change(Setting),
+    /// Bind the keys of the keypad for the ROM
+    BindKeys,
+    /// Go back to the keys of the configuration for the ROM
+    ForgetKeys,
+    /// Make the keys of the keypad the keys of the configuration
+    SaveKeys,
+    /// Close the application
+    Quit,
+}
+
+/// An entry of the settings menu
+#[derive(Clone, Copy)]
+enum Entry {
+    /// The built-in palette
+    Palette,
+    /// The number of instructions executed per second
+    CpuSpeed,
+    /// The volume of the buzzer
+    Volume,
+    /// A quirk, as an index into `QUIRKS`
+    Quirk(usize),
+    /// An accessibility option, as an index into `accessibility::OPTIONS`
+    Accessibility(usize),
+    /// The layout of the keypad on the keyboard
+    Layout,
+    /// Binding the keys of the keypad
+    BindKeys,
+    /// Forgetting the keys bound for the ROM
+    ForgetKeys,
+    /// Saving the keys of the keypad to the configuration
+    SaveKeys,
+    /// Closing the application
+    Quit,
+}
+
+/// A menu changing the settings of the application whilst it runs, shown
+/// over the window
+///
+/// The changes are applied by the application as they are made, and kept by
+/// the menu to be written back to the configuration file once it closes.
+pub(crate) struct SettingsMenu {
+    /// The built-in palette the colors were taken from, if any
+    palette: Option<usize>,
+    /// The number of instructions executed per second
+    cpu_speed: u32,
+    /// The volume of the buzzer, if there is an audio device
+    volume: Option<f32>,
+    /// The quirks the ROM runs with
+    quirks: Quirks,
+    /// The accessibility options
+    accessibility: Accessibility,
+    /// The layout the keys of the keypad follow, if any
+    layout: Option<Layout>,
+    /// The entries of the menu, from top to bottom
+    entries: Vec<Entry>,
+    /// The index of the selected entry
+    selected: usize,
+    /// The settings changed since the menu was opened
+    changes: Vec<Setting>,
+}
+
+impl SettingsMenu {
+    /// Creates the menu with the current settings and the first entry
+    /// selected
+    ///
+    /// # Params
+    ///
+    /// - `palette` - The built-in palette the colors were taken from, if any
+    /// - `cpu_speed` - The number of instructions executed per second
+    /// - `volume` - The volume of the buzzer, leaving its entry out if there
+    ///   is no audio device
+    /// - `quirks` - The quirks the ROM runs with
+    /// - `accessibility` - The accessibility options
+    /// - `layout` - The layout the keys of the keypad follow, if any
+    pub fn new(
+        palette: Option<usize>,
+        cpu_speed: u32,
+        volume: Option<f32>,
+        quirks: Quirks,
+        accessibility: Accessibility,
+        layout: Option<Layout>,
+    ) -> Self {
+        let mut entries = vec![Entry::Palette, Entry::CpuSpeed];
+        if volume.is_some() {
+            entries.push(Entry::Volume);
+        }
+        entries.extend((0..QUIRKS.len()).map(Entry::Quirk));
+        entries.extend((0..accessibility::OPTIONS.len()).map(Entry::Accessibility));
+        entries.extend([
+            Entry::Layout,
+            Entry::BindKeys,
+            Entry::ForgetKeys,
+            Entry::SaveKeys,
+            Entry::Quit,
+        ]);
+
+        Self {
+            palette,
+            cpu_speed,
+            volume,
+            quirks,
+            accessibility,
+            layout,
+            entries,
+            selected: 0,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Moves the selection, wrapping around either end of the menu
+    ///
+    /// # Params
+    ///
+    /// - `offset` - The number of entries to move down, or up if negative
+    pub fn move_selection(&mut self, offset: i32) {
+        let len = self.entries.len() as i32;
+        self.selected = (self.selected as i32 + offset).rem_euclid(len) as usize;
+    }
+
+    /// Changes the value of the selected entry, returning the changed
+    /// setting if the entry has a value
+    ///
+    /// # Params
+    ///
+    /// - `step` - The number of steps to change the value by, going down if
+    ///   negative
+    pub fn adjust(&mut self, step: i32) -> Option<Setting> {
+        let setting = match self.entries[self.selected] {
+            Entry::Palette => {
+                let len = PALETTES.len() as i32;
+                let index = match self.palette {
+                    Some(index) => (index as i32 + step).rem_euclid(len),
+                    None if step < 0 => len - 1,
+                    None => 0,
+                } as usize;
+
+                self.palette = Some(index);
+                Setting::Palette(index)
+            }
+            Entry::CpuSpeed => {
+                let speed = self.cpu_speed as i64 + i64::from(step) * i64::from(CPU_SPEED_STEP);
+                self.cpu_speed = speed.clamp(1, i64::from(MAX_CPU_SPEED)) as u32;
+                Setting::CpuSpeed(self.cpu_speed)
+            }
+            Entry::Volume => {
+                let volume = step_volume(self.volume?, step);
+                self.volume = Some(volume);
+                Setting::Volume(volume)
+            }
+            Entry::Quirk(index) => {
+                let (name, flag) = QUIRKS[index];
+                let enabled = flag(&mut self.quirks);
+                *enabled = !*enabled;
+                Setting::Quirk(name, *enabled)
+            }
+            Entry::Accessibility(index) => {
+                let (name, _, flag) = accessibility::OPTIONS[index];
+                let enabled = flag(&mut self.accessibility);
+                *enabled = !*enabled;
+                Setting::Accessibility(name, *enabled)
+            }
+            Entry::Layout => {
+                let len = Layout::ALL.len() as i32;
+                let index = match self.layout {
+                    Some(layout) => {
+                        let index = Layout::ALL.iter().position(|&other| other == layout);
+                        (index.unwrap_or_default() as i32 + step).rem_euclid(len)
+                    }
+                    None if step < 0 => len - 1,
+                    None => 0,
+                } as usize;
+
+                self.layout = Some(Layout::ALL[index]);
+                Setting::Layout(Layout::ALL[index])
+            }
+            Entry::BindKeys | Entry::ForgetKeys | Entry::SaveKeys | Entry::Quit => return None,
+        };
+
+        self.record(setting);
+        Some(setting)
+    }
+
+    /// Picks the selected entry, changing its value if it has one
+    pub fn activate(&mut self) -> Option<Choice> {
+        match self.entries[self.selected] {
+            Entry::BindKeys => Some(Choice::BindKeys),
+            Entry::ForgetKeys => Some(Choice::ForgetKeys),
+            Entry::SaveKeys => Some(Choice::SaveKeys),
+            Entry::Quit => Some(Choice::Quit),
+            _ => self.adjust(1).map(Choice::Change),
+        }
+    }
+
+    /// Keeps a changed setting to be written back to the configuration file,
+    /// replacing an earlier change of the same setting
+    ///
+    /// # Params
+    ///
+    /// - `setting` - The changed setting
+    pub fn record(&mut self, setting: Setting) {
+        self.changes.retain(|change| !change.is_same_as(&setting));
+        self.changes.push(setting);
+    }
+
+    /// Gets the settings changed since the menu was opened
+    pub fn changes(&self) -> &[Setting] {
+        &self.changes
+    }
+
+    /// Gets the quirks with the changes made in the menu
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Gets the accessibility options with the changes made in the menu
+    pub fn accessibility(&self) -> Accessibility {
+        self.accessibility
+    }
+
+    /// Draws the menu centered in the window
+    ///
+    /// # Params
+    ///
+    /// - `overlay` - The overlay to draw the menu on
+    /// - `screen_width` - The width of the window
+    /// - `screen_height` - The height of the window
+    pub fn draw(&self, overlay: &mut Overlay, screen_width: i32, screen_height: i32) {
+        let hint = "Up/Down selects, Left/Right changes, Enter picks, Esc closes";
+
+        let mut lines = vec![("Settings".to_string(), Color::RAYWHITE)];
+        for (i, &entry) in self.entries.iter().enumerate() {
+            let (marker, color) = if i == self.selected {
+                ("> ", SELECTED_COLOR)
+            } else {
+                ("  ", Color::RAYWHITE)
+            };
+            lines.push((format!("{marker}{}", self.label(entry)), color));
+        }
+        lines.push((hint.to_string(), Color::GRAY));
+
+        overlay.menu(&lines, screen_width, screen_height);
+    }
+
+    /// Gets the text of an entry, with its current value
+    ///
+    /// # Params
+    ///
+    /// - `entry` - The entry
+    fn label(&self, entry: Entry) -> String {
+        match entry {
+            Entry::Palette => {
+                let name = self.palette.map_or("custom", |index| PALETTES[index].name);
+                format!("Palette: {name}")
+            }
+            Entry::CpuSpeed => format!("CPU speed: {} IPS", self.cpu_speed),
+            Entry::Volume => {
+                format!("Volume: {:.0}%", self.volume.unwrap_or_default() * 100.0)
+            }
+            Entry::Quirk(index) => {
+                let (name, flag) = QUIRKS[index];
+                let mut quirks = self.quirks;
+                let state = if *flag(&mut quirks) { "on" } else { "off" };
+                format!("Quirk {name}: {state}")
+            }
+            Entry::Accessibility(index) => {
+                let (_, label, flag) = accessibility::OPTIONS[index];
+                let mut options = self.accessibility;
+                let state = if *flag(&mut options) { "on" } else { "off" };
+                format!("{label}: {state}")
+            }
+            Entry::Layout => {
+                let name = self.layout.map_or("custom", Layout::name);
+                format!("Keyboard layout: {name}")
+            }
+            Entry::BindKeys => "Bind the keys for this ROM".to_string(),
+            Entry::ForgetKeys => "Use the default keys for this ROM".to_string(),
+            Entry::SaveKeys => "Save the keys as the default".to_string(),
+            Entry::Quit => "Quit".to_string(),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::prelude::*;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// --- constants --------------------------------------------------------------
+
+/// The name of the environment variable overriding the data directory
+const DATA_DIR_VAR: &str = "KEET8_DATA_DIR";
+/// The name of the file holding the sections of a sidecar
+const SIDECAR_FILE: &str = "sidecar.ini";
+
+// --- sidecar definition -----------------------------------------------------
+
+/// Per-ROM data persisted across sessions, keyed by the hash of the ROM
+///
+/// Each ROM gets its own directory in the data directory. The textual data is
+/// stored as named sections of lines in `sidecar.ini`, while other files
+/// (e.g. binary data) can be placed next to it.
+pub(crate) struct Sidecar {
+    /// The directory of the sidecar, if a data directory could be determined
+    dir: Option<PathBuf>,
+    /// The sections of lines stored in the sidecar
+    sections: BTreeMap<String, Vec<String>>,
+    /// Flag indicating whether the sections differ from the ones on disk
+    modified: bool,
+}
+
+impl Sidecar {
+    /// Opens the sidecar of the ROM, which is empty if nothing was persisted
+    /// for it yet
+    ///
+    /// # Params
+    ///
+    /// - `rom_hash` - The hash of the ROM
+    pub fn open(rom_hash: u64) -> Self {
+        let dir = data_dir().map(|dir| dir.join(format!("{rom_hash:016x}")));
+        let sections = dir
+            .as_ref()
+            .and_then(|dir| std::fs::read_to_string(dir.join(SIDECAR_FILE)).ok())
+            .map(|text| parse_sections(&text))
+            .unwrap_or_default();
+
+        Self {
+            dir,
+            sections,
+            modified: false,
+        }
+    }
+
+    /// Gets the lines of a section, which is empty if the section doesn't
+    /// exist
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the section
+    pub fn section(&self, name: &str) -> &[String] {
+        self.sections.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces the lines of a section
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the section
+    /// - `lines` - The new lines of the section
+    pub fn set_section(&mut self, name: &str, lines: Vec<String>) {
+        if self.section(name) == lines.as_slice() {
+            return;
+        }
+
+        if lines.is_empty() {
+            self.sections.remove(name);
+        } else {
+            self.sections.insert(name.to_string(), lines);
+        }
+
+        self.modified = true;
+    }
+
+    /// Gets the path of a file placed next to the sections, if a data
+    /// directory could be determined
+    ///
+    /// The directory of the sidecar is created if it doesn't exist yet
+    ///
+    /// # Params
+    ///
+    /// - `name` - The name of the file
+    pub fn file_path(&self, name: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        std::fs::create_dir_all(dir).ok()?;
+
+        Some(dir.join(name))
+    }
+
+    /// Writes the sections to disk if they were modified
+    ///
+    /// # Errors
+    ///
+    /// If the sidecar could not be written
+    pub fn save(&mut self) -> Result<()> {
+        let Some(dir) = self.dir.as_ref().filter(|_| self.modified) else {
+            return Ok(());
+        };
+
+        let mut text = String::new();
+        for (name, lines) in &self.sections {
+            text.push_str(&format!("[{name}]\n"));
+            lines.iter().for_each(|line| {
+                text.push_str(line);
+                text.push('\n');
+            });
+        }
+
+        std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(dir.join(SIDECAR_FILE), text))
+            .map_err(|_| Keet8Error::FailedToSaveSidecar(dir.display().to_string()))?;
+
+        self.modified = false;
+        Ok(())
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Determines the directory in which keet-8 persists its data
+///
+/// This is `KEET8_DATA_DIR` if set, otherwise the platform's conventional
+/// data directory
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+    if let Some(dir) = var(DATA_DIR_VAR) {
+        return Some(dir);
+    }
+
+    if cfg!(windows) {
+        var("APPDATA").map(|dir| dir.join("keet_8"))
+    } else {
+        var("XDG_DATA_HOME")
+            .or_else(|| var("HOME").map(|home| home.join(".local").join("share")))
+            .map(|dir| dir.join("keet_8"))
+    }
+}
+
+/// Parses the sections of a sidecar file
+///
+/// Lines before the first section header are ignored
+///
+/// # Params
+///
+/// - `text` - The contents of the sidecar file
+fn parse_sections(text: &str) -> BTreeMap<String, Vec<String>> {
+    let mut sections = BTreeMap::new();
+    let mut current = None;
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.to_string());
+        } else if let Some(name) = &current {
+            sections
+                .entry(name.clone())
+                .or_insert_with(Vec::new)
+                .push(line.to_string());
+        }
+    }
+
+    sections
+}
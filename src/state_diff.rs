@@ -0,0 +1,253 @@
+use crate::prelude::*;
+
+use keet8_core::Emulator;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of bytes per row of the hexdump
+const BYTES_PER_ROW: usize = 16;
+/// The largest number of changed pixels listed one by one, beyond which only
+/// their number is given
+const MAX_LISTED_PIXELS: usize = 16;
+
+// --- state diff interface ---------------------------------------------------
+
+/// Writes the differences between two save state files to stdout
+///
+/// # Params
+///
+/// - `file_a` - The filepath to the save state compared from
+/// - `file_b` - The filepath to the save state compared to
+///
+/// # Errors
+///
+/// - If a save state file could not be read
+/// - If a save state is invalid
+pub(crate) fn print_state_diff(file_a: &str, file_b: &str) -> Result<()> {
+    let a = load_state_file(file_a)?;
+    let b = load_state_file(file_b)?;
+
+    println!("--- {file_a}");
+    println!("+++ {file_b}");
+    print_diff(&a, &b);
+    Ok(())
+}
+
+/// Writes the differences between the states of two emulators to stdout: the
+/// registers which changed, the pixels which changed, listed when there are
+/// few of them, and a hexdump of the rows of memory which changed, with the
+/// changed bytes marked
+///
+/// # Params
+///
+/// - `a` - The emulator holding the state compared from
+/// - `b` - The emulator holding the state compared to
+pub(crate) fn print_diff(a: &Emulator, b: &Emulator) {
+    let registers = register_changes(a, b);
+    let screen = screen_change(a, b);
+    let rows = changed_rows(a.memory(), b.memory());
+
+    if registers.is_empty() && screen.is_none() && rows.is_empty() {
+        println!("The states are identical");
+        return;
+    }
+
+    if !registers.is_empty() {
+        println!("Registers:");
+        for change in registers {
+            println!("    {change}");
+        }
+    }
+
+    if let Some(screen) = screen {
+        println!("Screen: {screen}");
+        for change in pixel_changes(a, b) {
+            println!("    {change}");
+        }
+    }
+
+    if rows.is_empty() {
+        return;
+    }
+
+    let num_bytes = a.memory().iter().zip(b.memory()).filter(|(a, b)| a != b).count();
+    println!("Memory: {num_bytes} of {} bytes differ", a.memory().len());
+
+    let mut last_row = None;
+    for row in rows {
+        // Rows which didn't change are left out, marked by a gap
+        if last_row.is_some_and(|last| last + 1 != row) {
+            println!("    ...");
+        }
+        last_row = Some(row);
+
+        let start = row * BYTES_PER_ROW;
+        let row_a = &a.memory()[start..start + BYTES_PER_ROW];
+        let row_b = &b.memory()[start..start + BYTES_PER_ROW];
+
+        let marks = row_a
+            .iter()
+            .zip(row_b)
+            .map(|(a, b)| if a == b { "  " } else { "^^" })
+            .collect::<Vec<&str>>();
+
+        println!("    {start:#05X}  - {}", hex_row(row_a));
+        println!("           + {}", hex_row(row_b));
+        println!("             {}", marks.join(" ").trim_end());
+    }
+}
+
+/// Loads a save state file into an emulator without a ROM, as the state
+/// holds the whole memory
+///
+/// # Params
+///
+/// - `path` - The filepath to the save state
+///
+/// # Errors
+///
+/// - If the save state file could not be read
+/// - If the save state is invalid
+pub(crate) fn load_state_file(path: &str) -> Result<Emulator> {
+    let bytes = std::fs::read(path).map_err(|_| Keet8Error::FailedToLoadState(path.to_string()))?;
+
+    Emulator::from_state(&bytes)
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Describes the registers, timers and call stack which differ between two
+/// emulators, one line per change
+///
+/// # Params
+///
+/// - `a` - The emulator holding the state compared from
+/// - `b` - The emulator holding the state compared to
+fn register_changes(a: &Emulator, b: &Emulator) -> Vec<String> {
+    let mut changes = (0..16)
+        .filter(|&x| a.register(x) != b.register(x))
+        .map(|x| format!("V{x:X}  {:#04X} -> {:#04X}", a.register(x), b.register(x)))
+        .collect::<Vec<String>>();
+
+    if a.index() != b.index() {
+        changes.push(format!("I   {:#06X} -> {:#06X}", a.index(), b.index()));
+    }
+    if a.program_counter() != b.program_counter() {
+        changes.push(format!(
+            "PC  {:#06X} -> {:#06X}",
+            a.program_counter(),
+            b.program_counter()
+        ));
+    }
+    if a.delay_timer() != b.delay_timer() {
+        changes.push(format!("DT  {} -> {}", a.delay_timer(), b.delay_timer()));
+    }
+    if a.sound_timer() != b.sound_timer() {
+        changes.push(format!("ST  {} -> {}", a.sound_timer(), b.sound_timer()));
+    }
+    if a.call_stack() != b.call_stack() {
+        changes.push(format!(
+            "Stack  [{}] -> [{}]",
+            format_addresses(a.call_stack()),
+            format_addresses(b.call_stack())
+        ));
+    }
+
+    changes
+}
+
+/// Describes how the screen differs between two emulators, if it does
+///
+/// # Params
+///
+/// - `a` - The emulator holding the state compared from
+/// - `b` - The emulator holding the state compared to
+fn screen_change(a: &Emulator, b: &Emulator) -> Option<String> {
+    let size_a = (a.screen_width(), a.screen_height());
+    let size_b = (b.screen_width(), b.screen_height());
+    if size_a != size_b {
+        return Some(format!(
+            "resolution {}x{} -> {}x{}",
+            size_a.0, size_a.1, size_b.0, size_b.1
+        ));
+    }
+
+    let num_pixels = a
+        .frame()
+        .iter()
+        .zip(b.frame().iter())
+        .filter(|&((_, _, a), (_, _, b))| a != b)
+        .count();
+
+    let total = size_a.0 * size_a.1;
+    (num_pixels > 0).then(|| format!("{num_pixels} of {total} pixels differ"))
+}
+
+/// Describes the pixels which differ between two emulators of the same
+/// resolution, one line per pixel, unless there are too many to list
+///
+/// # Params
+///
+/// - `a` - The emulator holding the state compared from
+/// - `b` - The emulator holding the state compared to
+fn pixel_changes(a: &Emulator, b: &Emulator) -> Vec<String> {
+    if (a.screen_width(), a.screen_height()) != (b.screen_width(), b.screen_height()) {
+        return Vec::new();
+    }
+
+    let state = |on| if on { "on" } else { "off" };
+    let changes = a
+        .frame()
+        .iter()
+        .zip(b.frame().iter())
+        .filter(|&((_, _, a), (_, _, b))| a != b)
+        .map(|((x, y, a), (_, _, b))| format!("({x}, {y})  {} -> {}", state(a), state(b)))
+        .take(MAX_LISTED_PIXELS + 1)
+        .collect::<Vec<String>>();
+
+    if changes.len() > MAX_LISTED_PIXELS {
+        return Vec::new();
+    }
+    changes
+}
+
+/// Gets the rows of the hexdump in which the memory differs, in order
+///
+/// # Params
+///
+/// - `a` - The memory compared from
+/// - `b` - The memory compared to
+fn changed_rows(a: &[u8], b: &[u8]) -> Vec<usize> {
+    a.chunks(BYTES_PER_ROW)
+        .zip(b.chunks(BYTES_PER_ROW))
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(row, _)| row)
+        .collect()
+}
+
+/// Formats a row of bytes in hexadecimal, separated by spaces
+///
+/// # Params
+///
+/// - `bytes` - The bytes of the row
+fn hex_row(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Formats addresses in hexadecimal, separated by commas
+///
+/// # Params
+///
+/// - `addrs` - The addresses to format
+fn format_addresses(addrs: &[u16]) -> String {
+    addrs
+        .iter()
+        .map(|addr| format!("{addr:#06X}"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -0,0 +1,71 @@
+use keet8_core::Emulator;
+use serde_json::{json, Value};
+
+// --- constants --------------------------------------------------------------
+
+/// The number of bytes of memory dumped per line
+const BYTES_PER_LINE: usize = 16;
+
+// --- state dump interface ---------------------------------------------------
+
+/// Creates a dump of the complete machine state, for inspecting offline and
+/// attaching to bug reports
+///
+/// Every value is kept readable in a text editor: the registers and addresses
+/// are hexadecimal strings, memory is a list of lines of 16 bytes each
+/// prefixed with the address of the first, and every row of the screen is a
+/// string with `#` for a pixel which is on and `.` for one which is off.
+///
+/// # Params
+///
+/// - `emulator` - The emulator to dump the state of
+/// - `keys` - The keys of the keypad held down, with bit `K` set for key `K`
+pub(crate) fn state_dump(emulator: &Emulator, keys: u16) -> Value {
+    let registers = (0..16)
+        .map(|x| (format!("v{x:X}"), json!(format!("{:02X}", emulator.register(x)))))
+        .collect::<serde_json::Map<String, Value>>();
+    let stack = emulator
+        .call_stack()
+        .iter()
+        .map(|addr| format!("{addr:03X}"))
+        .collect::<Vec<String>>();
+    let held = (0..16u16)
+        .filter(|key| keys >> key & 1 != 0)
+        .map(|key| format!("{key:X}"))
+        .collect::<Vec<String>>();
+
+    let memory = emulator
+        .memory()
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(row, bytes)| {
+            let bytes = bytes.iter().map(|byte| format!("{byte:02X}")).collect::<Vec<String>>();
+            format!("{:03X}: {}", row * BYTES_PER_LINE, bytes.join(" "))
+        })
+        .collect::<Vec<String>>();
+    let rows = emulator
+        .framebuffer()
+        .chunks(emulator.screen_width())
+        .map(|row| row.iter().map(|&pixel| if pixel > 0 { '#' } else { '.' }).collect())
+        .collect::<Vec<String>>();
+
+    json!({
+        "platform": emulator.platform().map(|platform| platform.name()),
+        "registers": registers,
+        "pc": format!("{:03X}", emulator.program_counter()),
+        "i": format!("{:03X}", emulator.index()),
+        "timers": {
+            "delay": emulator.delay_timer(),
+            "sound": emulator.sound_timer(),
+        },
+        "stack": stack,
+        "rpl_flags": emulator.rpl_flags(),
+        "keypad": held,
+        "memory": memory,
+        "screen": {
+            "width": emulator.screen_width(),
+            "height": emulator.screen_height(),
+            "rows": rows,
+        },
+    })
+}
@@ -0,0 +1,103 @@
+use crate::prelude::*;
+
+use std::collections::BTreeMap;
+
+// --- symbols definition -----------------------------------------------------
+
+/// A table of labels for addresses in a ROM, used to render addresses as
+/// `label+offset` instead of bare hex
+///
+/// Symbol files contain one symbol per line, written as the address followed
+/// by the label (e.g. `0x0240 draw_sprite`). Empty lines and lines starting
+/// with `#` are ignored.
+#[derive(Default)]
+pub struct Symbols {
+    /// The labels, ordered by their address
+    labels: BTreeMap<u16, String>,
+}
+
+impl Symbols {
+    /// Loads the symbols from a symbol file
+    ///
+    /// # Params
+    ///
+    /// - `filepath` - The path to the symbol file
+    ///
+    /// # Errors
+    ///
+    /// - If the file could not be read
+    /// - If a line in the file is not a valid symbol
+    pub fn load(filepath: &str) -> Result<Self> {
+        let text = std::fs::read_to_string(filepath)
+            .map_err(|_| Keet8Error::FailedToLoadSymbols(filepath.to_string()))?;
+
+        Self::parse(&text)
+            .ok_or_else(|| Keet8Error::FailedToLoadSymbols(filepath.to_string()))
+    }
+
+    /// Parses the symbols from the contents of a symbol file
+    ///
+    /// Returns [None] if any of the lines is not a valid symbol
+    ///
+    /// # Params
+    ///
+    /// - `text` - The contents of the symbol file
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut labels = BTreeMap::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (addr, label) = line.split_once(char::is_whitespace)?;
+            let addr = addr.trim_start_matches("0x").trim_start_matches("0X");
+
+            labels.insert(u16::from_str_radix(addr, 16).ok()?, label.trim().to_string());
+        }
+
+        Some(Self { labels })
+    }
+
+    /// Checks whether no symbols are loaded
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Gets the label defined exactly at the address, if any
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to look up
+    pub fn label(&self, addr: u16) -> Option<&str> {
+        self.labels.get(&addr).map(String::as_str)
+    }
+
+    /// Finds the closest label at or before the address
+    ///
+    /// Returns the label and the offset of the address from it
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to look up
+    pub fn lookup(&self, addr: u16) -> Option<(&str, u16)> {
+        self.labels
+            .range(..=addr)
+            .next_back()
+            .map(|(&base, label)| (label.as_str(), addr - base))
+    }
+
+    /// Renders the address as `label+0xoffset`, or as bare hex when no label
+    /// precedes it
+    ///
+    /// # Params
+    ///
+    /// - `addr` - The address to render
+    pub fn symbolicate(&self, addr: u16) -> String {
+        match self.lookup(addr) {
+            Some((label, 0)) => label.to_string(),
+            Some((label, offset)) => format!("{label}+0x{offset:x}"),
+            None => format!("0x{addr:04x}"),
+        }
+    }
+}
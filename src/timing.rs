@@ -0,0 +1,179 @@
+use crate::prelude::*;
+use crate::scheduler::Scheduler;
+
+use keet8_core::Emulator;
+
+// --- constants --------------------------------------------------------------
+
+/// The timing test ROM, which counts the iterations of a loop until the
+/// delay timer set to `TIMING_TICKS` runs out and then exits
+///
+/// The ROM is generated from this listing, each iteration taking
+/// `LOOP_LENGTH` instructions with the count in `V2:V1`:
+///
+/// ```text
+/// 200: 6078  ld v0 120
+/// 202: F015  ld dt v0
+/// 204: 7101  add v1 1      ; loop
+/// 206: 3100  se v1 0
+/// 208: 120C  jp 0x20C
+/// 20A: 7201  add v2 1
+/// 20C: F307  ld v3 dt
+/// 20E: 3300  se v3 0
+/// 210: 1204  jp 0x204
+/// 212: 00FD  exit
+/// ```
+const TIMING_ROM: &[u8] = include_bytes!("../tests/timing.ch8");
+/// The number of timer ticks the timing test ROM counts for
+const TIMING_TICKS: u32 = 120;
+/// The number of instructions of an iteration of the loop of the timing test
+/// ROM
+const LOOP_LENGTH: u32 = 6;
+
+/// The refresh rates in Hz the frames are simulated at
+const REFRESH_RATES: [u32; 5] = [30, 60, 75, 144, 240];
+/// The deviation from the expected number of instructions allowed, as a
+/// fraction of it, on top of a single iteration of the loop
+const TOLERANCE: f32 = 0.02;
+
+// --- timing interface -------------------------------------------------------
+
+/// Runs the timing test ROM through the scheduler at common refresh rates and
+/// writes to stdout how many instructions executed between timer ticks,
+/// compared to what the CPU speed is expected to give
+///
+/// Frames are simulated rather than drawn, so the check runs without a
+/// window or audio device.
+///
+/// # Params
+///
+/// - `cpu_speed` - The number of instructions executed per second
+///
+/// # Errors
+///
+/// - If the ROM didn't count within the tolerance at some refresh rate
+/// - If an error occured whilst running the ROM
+pub(crate) fn print_timing_check(cpu_speed: u32) -> Result<()> {
+    let per_tick = cpu_speed as f32 / 60.0;
+    let expected = per_tick * TIMING_TICKS as f32;
+    let tolerance = expected * TOLERANCE + LOOP_LENGTH as f32;
+
+    println!("CPU speed: {cpu_speed} IPS, expecting {per_tick:.2} instructions per tick");
+    println!("Counting:  {TIMING_TICKS} ticks, expecting {expected:.0} instructions");
+    println!();
+    println!("Refresh  Per tick (min-max)    Counted  Deviation");
+
+    let mut failed = None;
+    for refresh_rate in REFRESH_RATES {
+        let timing = measure(cpu_speed, refresh_rate)?;
+        let counted = timing.iterations * LOOP_LENGTH;
+
+        let deviation = counted as f32 - expected;
+        let status = if deviation.abs() <= tolerance {
+            "ok"
+        } else {
+            failed.get_or_insert(refresh_rate);
+            "FAILED"
+        };
+
+        println!(
+            "{:<8} {:>8.2} ({:>4}-{:<4})    {counted:>7}  {:>+8.1}%  {status}",
+            format!("{refresh_rate}Hz"),
+            timing.mean_per_tick(),
+            timing.min_per_tick,
+            timing.max_per_tick,
+            100.0 * deviation / expected,
+        );
+    }
+
+    match failed {
+        Some(refresh_rate) => Err(Keet8Error::TimingCheckFailed(refresh_rate)),
+        None => Ok(()),
+    }
+}
+
+// --- timing definition ------------------------------------------------------
+
+/// The instructions executed whilst running the timing test ROM at a refresh
+/// rate
+struct Timing {
+    /// The number of loop iterations counted by the ROM itself
+    iterations: u32,
+    /// The number of instructions executed between the first and last tick
+    steps: u32,
+    /// The number of intervals between two ticks
+    intervals: u32,
+    /// The fewest instructions executed between two ticks
+    min_per_tick: u32,
+    /// The most instructions executed between two ticks
+    max_per_tick: u32,
+}
+
+impl Timing {
+    /// Gets the average number of instructions executed between two ticks
+    fn mean_per_tick(&self) -> f32 {
+        self.steps as f32 / self.intervals.max(1) as f32
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Runs the timing test ROM to its end with the frames of a refresh rate
+///
+/// # Params
+///
+/// - `cpu_speed` - The number of instructions executed per second
+/// - `refresh_rate` - The number of frames per second
+///
+/// # Errors
+///
+/// - If an error occured whilst running the ROM
+/// - If the ROM didn't exit in twice the time it should take
+fn measure(cpu_speed: u32, refresh_rate: u32) -> Result<Timing> {
+    let mut emulator = Emulator::from_rom(TIMING_ROM)?;
+    let mut scheduler = Scheduler::new(cpu_speed);
+    let frame_time = 1.0 / refresh_rate as f32;
+
+    let mut timing = Timing {
+        iterations: 0,
+        steps: 0,
+        intervals: 0,
+        min_per_tick: u32::MAX,
+        max_per_tick: 0,
+    };
+
+    let mut ticked = false;
+    let mut since_tick = 0;
+
+    for _ in 0..2 * TIMING_TICKS * refresh_rate / 60 {
+        scheduler.advance(frame_time);
+
+        while scheduler.next_step() && !emulator.is_halted() {
+            emulator.step()?;
+            since_tick += 1;
+        }
+
+        while scheduler.next_tick() {
+            emulator.tick_timers();
+
+            // The instructions before the first tick and after the exit belong
+            // to no interval
+            if ticked && !emulator.is_halted() {
+                timing.steps += since_tick;
+                timing.intervals += 1;
+                timing.min_per_tick = timing.min_per_tick.min(since_tick);
+                timing.max_per_tick = timing.max_per_tick.max(since_tick);
+            }
+
+            ticked = true;
+            since_tick = 0;
+        }
+
+        if emulator.is_halted() {
+            timing.iterations = ((emulator.register(2) as u32) << 8) | emulator.register(1) as u32;
+            return Ok(timing);
+        }
+    }
+
+    Err(Keet8Error::TimingCheckFailed(refresh_rate))
+}
@@ -0,0 +1,260 @@
+use crate::hash::fnv1a;
+use crate::options::TraceFormat;
+use crate::prelude::*;
+
+use keet8_core::opcode::OpCode;
+use keet8_core::Emulator;
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+// --- trace log definition ---------------------------------------------------
+
+/// Appends a line per executed instruction to a file, holding the address,
+/// the raw and decoded opcode and the registers it changed, such as
+///
+/// `0x0202: 6A05  ld v10 5  VA 00->05`
+///
+/// or, in the Octo format, the address, the raw opcode and a dump of all the
+/// registers before the instruction, such as
+///
+/// `pc=0202 op=6A05 i=0000 v0=00 ... vf=00 dt=00 st=00 sp=0`
+///
+/// which the state format follows with hashes of the memory and the screen,
+/// as `mem=<hash> fb=<hash>`.
+///
+/// The lines are meant to be compared against the traces of other emulators
+/// when a ROM goes wrong, which `print_trace_diff` does line by line.
+pub(crate) struct TraceLog {
+    /// The filepath to the trace
+    path: String,
+    /// The format the lines are written in
+    format: TraceFormat,
+    /// The trace file, buffered as a line is written per instruction
+    writer: BufWriter<File>,
+    /// The state of the emulator before the instruction being executed
+    before: Snapshot,
+}
+
+/// The state of the emulator before an instruction, which the instruction is
+/// compared against
+#[derive(Default)]
+pub(crate) struct Snapshot {
+    /// The address of the instruction
+    pc: u16,
+    /// The raw opcode of the instruction
+    raw: u16,
+    /// The `V` registers
+    registers: [u8; 16],
+    /// The index register
+    index: u16,
+    /// The delay timer
+    delay_timer: u8,
+    /// The sound timer
+    sound_timer: u8,
+    /// The number of return addresses on the call stack
+    depth: usize,
+    /// The FNV-1a hashes of the memory and of the screen, if noted
+    hashes: Option<(u64, u64)>,
+}
+
+impl TraceLog {
+    /// Opens the trace file, appending to it if it already exists
+    ///
+    /// # Params
+    ///
+    /// - `path` - The filepath to the trace
+    /// - `format` - The format the lines are written in
+    ///
+    /// # Errors
+    ///
+    /// If the trace file could not be opened
+    pub fn open(path: &str, format: TraceFormat) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|_| Keet8Error::FailedToWriteTrace(path.to_string()))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            format,
+            writer: BufWriter::new(file),
+            before: Snapshot::default(),
+        })
+    }
+
+    /// Notes the state of the emulator before it executes an instruction
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator about to execute the instruction
+    pub fn begin(&mut self, emulator: &Emulator) {
+        self.before = match self.format {
+            TraceFormat::State => Snapshot::capture(emulator).with_hashes(emulator),
+            _ => Snapshot::capture(emulator),
+        };
+    }
+
+    /// Writes the line of the instruction noted by `begin`, now that the
+    /// emulator has executed it
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator which executed the instruction
+    ///
+    /// # Errors
+    ///
+    /// If the trace file could not be written
+    pub fn finish(&mut self, emulator: &Emulator) -> Result<()> {
+        let line = match self.format {
+            TraceFormat::Changes => self.changes_line(emulator),
+            TraceFormat::Octo | TraceFormat::State => self.before.octo_line(),
+        };
+
+        writeln!(self.writer, "{}", line.trim_end())
+            .map_err(|_| Keet8Error::FailedToWriteTrace(self.path.clone()))
+    }
+
+    /// Formats the line of the instruction with the registers it changed
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator which executed the instruction
+    fn changes_line(&self, emulator: &Emulator) -> String {
+        let before = &self.before;
+
+        let mut changes = (0..16)
+            .filter(|&x| before.registers[x] != emulator.register(x))
+            .map(|x| format!("V{x:X} {:02X}->{:02X}", before.registers[x], emulator.register(x)))
+            .collect::<Vec<String>>();
+
+        if before.index != emulator.index() {
+            changes.push(format!("I {:03X}->{:03X}", before.index, emulator.index()));
+        }
+        if before.delay_timer != emulator.delay_timer() {
+            changes.push(format!("DT {:02X}->{:02X}", before.delay_timer, emulator.delay_timer()));
+        }
+        if before.sound_timer != emulator.sound_timer() {
+            changes.push(format!("ST {:02X}->{:02X}", before.sound_timer, emulator.sound_timer()));
+        }
+
+        let opcode = OpCode::from(before.raw).to_string();
+        format!("{:#06X}: {:04X}  {opcode}  {}", before.pc, before.raw, changes.join(" "))
+    }
+}
+
+impl Snapshot {
+    /// Notes the state of the emulator before it executes the next
+    /// instruction
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator about to execute the instruction
+    pub fn capture(emulator: &Emulator) -> Self {
+        let pc = emulator.program_counter();
+
+        Self {
+            pc,
+            raw: emulator.fetch(pc),
+            registers: std::array::from_fn(|x| emulator.register(x)),
+            index: emulator.index(),
+            delay_timer: emulator.delay_timer(),
+            sound_timer: emulator.sound_timer(),
+            depth: emulator.call_stack().len(),
+            hashes: None,
+        }
+    }
+
+    /// Notes the hashes of the memory and of the screen as well, which the
+    /// Octo line then ends with
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator about to execute the instruction
+    pub fn with_hashes(self, emulator: &Emulator) -> Self {
+        let hashes = (fnv1a(emulator.memory()), emulator.framebuffer_hash());
+        Self { hashes: Some(hashes), ..self }
+    }
+
+    /// Formats the line of the instruction with a dump of all the registers
+    /// before it was executed (Octo format), followed by the hashes if noted
+    pub fn octo_line(&self) -> String {
+        let registers = (0..16)
+            .map(|x| format!("v{x:x}={:02x}", self.registers[x]))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let line = format!(
+            "pc={:04x} op={:04x} i={:04x} {registers} dt={:02x} st={:02x} sp={}",
+            self.pc, self.raw, self.index, self.delay_timer, self.sound_timer, self.depth
+        );
+
+        match self.hashes {
+            Some((memory, screen)) => format!("{line} mem={memory:016x} fb={screen:016x}"),
+            None => line,
+        }
+    }
+}
+
+// --- trace diff interface ---------------------------------------------------
+
+/// Compares two instruction traces line by line and writes the first step at
+/// which they diverge to stdout, along with the step before it
+///
+/// The lines are compared ignoring case and the amount of whitespace, as
+/// traces written by other emulators seldom agree on either.
+///
+/// # Params
+///
+/// - `file_a` - The filepath to the trace compared from
+/// - `file_b` - The filepath to the trace compared to
+///
+/// # Errors
+///
+/// - If a trace file could not be read
+/// - If the traces diverge
+pub(crate) fn print_trace_diff(file_a: &str, file_b: &str) -> Result<()> {
+    let a = read_trace(file_a)?;
+    let b = read_trace(file_b)?;
+
+    let Some(step) = (0..a.len().max(b.len())).find(|&i| a.get(i) != b.get(i)) else {
+        println!("The traces are identical ({} steps)", a.len());
+        return Ok(());
+    };
+
+    let line = |trace: &[String], i: usize| {
+        trace.get(i).map_or("(end of trace)", String::as_str).to_string()
+    };
+
+    if step > 0 {
+        println!("  {:>8}  {}", step, line(&a, step - 1));
+    }
+    println!("- {:>8}  {}", step + 1, line(&a, step));
+    println!("+ {:>8}  {}", step + 1, line(&b, step));
+
+    Err(Keet8Error::TracesDiverged(step + 1))
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Reads the lines of a trace, with their whitespace collapsed and in lower
+/// case, leaving out blank lines
+///
+/// # Params
+///
+/// - `path` - The filepath to the trace
+///
+/// # Errors
+///
+/// If the trace file could not be read
+pub(crate) fn read_trace(path: &str) -> Result<Vec<String>> {
+    let text =
+        std::fs::read_to_string(path).map_err(|_| Keet8Error::FailedToReadTrace(path.to_string()))?;
+
+    Ok(text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
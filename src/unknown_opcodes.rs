@@ -0,0 +1,154 @@
+use keet8_core::Platform;
+
+use std::collections::BTreeMap;
+
+// --- constants --------------------------------------------------------------
+
+/// The number of opcodes listed in the summary of the debug overlay, the most
+/// frequent first
+const SUMMARY_OPCODES: usize = 3;
+
+// --- unknown opcodes definition ---------------------------------------------
+
+/// Counts the raw opcodes which fell back to the `RAW` instruction, along with
+/// the extension of Chip-8 each belongs to, explaining why a ROM shows a blank
+/// screen or misbehaves
+///
+/// An opcode is unknown either because it belongs to a platform later than
+/// the one the emulator is restricted to, because it is an XO-CHIP opcode the
+/// interpreter doesn't support, or because it belongs to no platform at all,
+/// which usually means the ROM jumped into its data.
+#[derive(Default)]
+pub(crate) struct UnknownOpcodes {
+    /// The number of times each raw opcode was executed
+    counts: BTreeMap<u16, u64>,
+}
+
+impl UnknownOpcodes {
+    /// Counts an unknown opcode being executed
+    ///
+    /// # Params
+    ///
+    /// - `opcode` - The raw opcode
+    pub fn record(&mut self, opcode: u16) {
+        *self.counts.entry(opcode).or_default() += 1;
+    }
+
+    /// Forgets every opcode counted, such as when another ROM is loaded
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    /// Gets the opcodes counted with their counts, the most frequent first
+    fn by_count(&self) -> Vec<(u16, u64)> {
+        let mut opcodes =
+            self.counts.iter().map(|(&opcode, &count)| (opcode, count)).collect::<Vec<_>>();
+        opcodes.sort_by_key(|&(opcode, count)| (std::cmp::Reverse(count), opcode));
+        opcodes
+    }
+
+    /// Gets the latest platform any of the opcodes belongs to, being the one
+    /// the ROM most likely needs, if any belongs to a platform after Chip-8
+    fn needed_platform(&self) -> Option<Platform> {
+        self.counts
+            .keys()
+            .map(|&opcode| Platform::of_opcode(opcode))
+            .filter(|&platform| platform != Platform::Chip8)
+            .max()
+    }
+
+    /// Gets a line summing up the most frequent opcodes, for the debug
+    /// overlay, if any were counted
+    pub fn summary(&self) -> Option<String> {
+        let opcodes = self.by_count();
+        if opcodes.is_empty() {
+            return None;
+        }
+
+        let listed = opcodes
+            .iter()
+            .take(SUMMARY_OPCODES)
+            .map(|&(opcode, count)| format!("{opcode:04X} x{count} ({})", short_origin(opcode)))
+            .collect::<Vec<String>>();
+        let more = opcodes.len().saturating_sub(SUMMARY_OPCODES);
+        let more = if more > 0 { format!(" and {more} more") } else { String::new() };
+
+        Some(format!("unknown {}{more}", listed.join(", ")))
+    }
+
+    /// Gets the report of every opcode counted and where it comes from,
+    /// followed by the platform to run the ROM as, if any were counted
+    ///
+    /// # Params
+    ///
+    /// - `platform` - The platform the emulator is restricted to, if any
+    pub fn report(&self, platform: Option<Platform>) -> Option<String> {
+        let opcodes = self.by_count();
+        if opcodes.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec!["Unknown opcodes executed:".to_string()];
+        for (opcode, count) in opcodes {
+            lines.push(format!("  {opcode:04X} {count:>8}x  {}", origin(opcode)));
+        }
+
+        match (self.needed_platform(), platform) {
+            (Some(needed), Some(platform)) if needed > platform => lines.push(format!(
+                "The ROM uses {} opcodes, try running it with --platform {}",
+                needed.name(),
+                platform_flag(needed)
+            )),
+            (Some(needed), _) => lines.push(format!(
+                "The ROM uses {} opcodes which the emulator doesn't support",
+                needed.name()
+            )),
+            (None, _) => {}
+        }
+
+        Some(lines.join("\n"))
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Describes where an unknown opcode comes from
+///
+/// # Params
+///
+/// - `opcode` - The raw opcode
+fn origin(opcode: u16) -> String {
+    match Platform::of_opcode(opcode) {
+        Platform::Chip8 if opcode & 0xF000 == 0 => {
+            "machine code routine of the COSMAC VIP, which isn't emulated".to_string()
+        }
+        Platform::Chip8 => "unknown to every platform, maybe data run as code".to_string(),
+        platform => format!("{} opcode", platform.name()),
+    }
+}
+
+/// Describes where an unknown opcode comes from in a word or two
+///
+/// # Params
+///
+/// - `opcode` - The raw opcode
+fn short_origin(opcode: u16) -> &'static str {
+    match Platform::of_opcode(opcode) {
+        Platform::Chip8 if opcode & 0xF000 == 0 => "machine code",
+        Platform::Chip8 => "no platform",
+        platform => platform.name(),
+    }
+}
+
+/// Gets the value of `--platform` selecting a platform
+///
+/// # Params
+///
+/// - `platform` - The platform
+fn platform_flag(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Chip8 => "chip8",
+        Platform::Schip => "schip",
+        Platform::XoChip => "xochip",
+    }
+}
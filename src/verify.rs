@@ -0,0 +1,226 @@
+use crate::prelude::*;
+use crate::roms::{builtin_rom_file, read_rom};
+
+use keet8_core::{Emulator, FrameBuffer, Key, Platform};
+
+// --- constants --------------------------------------------------------------
+
+/// The address the test ROMs read to pick a test without showing their menu
+const SELECTION_ADDR: u16 = 0x1FF;
+/// The selection of the CHIP-8 platform in the quirks test ROM
+const CHIP8_PLATFORM: u8 = 1;
+/// The selection of the `FX0A` test in the keypad test ROM
+const GETKEY_TEST: u8 = 3;
+
+/// The number of frames each test ROM runs for, enough for all of them to
+/// show their results
+const FRAMES: u32 = 600;
+/// The number of instructions executed per frame
+const STEPS_PER_FRAME: u32 = 20;
+/// The frame at which the key is tapped, once the ROM waits for it
+const TAP_FRAME: u32 = 120;
+/// The key tapped for the tests reading the keypad
+const TAP_KEY: Key = Key::K5;
+
+/// The rows of the checkmark the test ROMs draw next to a passed check, the
+/// most significant of the 3 bits being the leftmost pixel
+const CHECKMARK: [u8; 3] = [0b101, 0b110, 0b100];
+/// The rows of the cross the test ROMs draw next to a failed check
+const CROSS: [u8; 3] = [0b101, 0b010, 0b101];
+
+/// The tests of the suite, all of them community test ROMs built into the
+/// emulator
+const TESTS: [Test; 4] = [
+    Test {
+        rom: "corax",
+        description: "Core instructions",
+        selection: None,
+        original_quirks: false,
+        tap: false,
+        checks: 22,
+    },
+    Test {
+        rom: "flags",
+        description: "VF after arithmetic",
+        selection: None,
+        original_quirks: false,
+        tap: false,
+        checks: 45,
+    },
+    Test {
+        rom: "quirks",
+        description: "CHIP-8 quirks",
+        selection: Some(CHIP8_PLATFORM),
+        original_quirks: true,
+        tap: false,
+        checks: 6,
+    },
+    Test {
+        rom: "keypad",
+        description: "FX0A waits for a key",
+        selection: Some(GETKEY_TEST),
+        original_quirks: false,
+        tap: true,
+        checks: 1,
+    },
+];
+
+// --- test definition --------------------------------------------------------
+
+/// A test ROM of the suite, with how it is run and what it should show
+struct Test {
+    /// The name of the built-in ROM
+    rom: &'static str,
+    /// What the ROM checks, as written in the summary
+    description: &'static str,
+    /// The value written at `SELECTION_ADDR` to skip the menu of the ROM, if
+    /// it has one
+    selection: Option<u8>,
+    /// Flag indicating whether the ROM runs with the quirks of the original
+    /// CHIP-8 interpreter rather than the default ones
+    original_quirks: bool,
+    /// Flag indicating whether a key is tapped while the ROM waits for one
+    tap: bool,
+    /// The number of checkmarks the ROM shows when everything passes
+    checks: usize,
+}
+
+// --- verify interface -------------------------------------------------------
+
+/// Runs the community test ROMs without a window and writes to stdout
+/// whether each one passed
+///
+/// Every ROM runs for a fixed number of frames, after which the checkmarks
+/// and crosses it drew next to its checks are counted on the screen. A ROM
+/// passes when it shows no cross and a checkmark for every check. The quirks
+/// test runs with the quirks of the original CHIP-8 interpreter, and the key
+/// tapped for the keypad test is released as soon as `FX0A` reads it.
+///
+/// # Errors
+///
+/// - If a test ROM failed
+/// - If a test ROM could not be loaded
+pub(crate) fn print_verify() -> Result<()> {
+    println!("ROM      Test                  Result");
+
+    let mut failed = 0;
+    for test in &TESTS {
+        let rom = read_rom(&builtin_rom_file(test.rom)?)?;
+        let result = match run(test, &rom) {
+            Ok(frame) => {
+                let checks = count_glyphs(&frame, CHECKMARK);
+                let crosses = count_glyphs(&frame, CROSS);
+
+                if crosses == 0 && checks == test.checks {
+                    format!("ok ({checks} passed)")
+                } else {
+                    failed += 1;
+                    format!("FAILED ({checks} of {} passed, {crosses} failed)", test.checks)
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                format!("FAILED ({})", e.message())
+            }
+        };
+
+        println!("{:<8} {:<21} {result}", test.rom, test.description);
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(Keet8Error::VerificationFailed(failed)),
+    }
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Runs a test ROM for `FRAMES` frames, returning the pixels of its screen
+///
+/// # Params
+///
+/// - `test` - The test to run
+/// - `rom` - The bytes of the test ROM
+///
+/// # Errors
+///
+/// - If the ROM could not be loaded
+/// - If an error occured whilst running the ROM
+fn run(test: &Test, rom: &[u8]) -> Result<Vec<Vec<bool>>> {
+    let mut emulator = Emulator::from_rom(rom)?;
+    if test.original_quirks {
+        emulator.set_quirks(Platform::Chip8.quirks());
+    }
+    if let Some(selection) = test.selection {
+        emulator.poke(SELECTION_ADDR, selection);
+    }
+
+    let mut tapping = false;
+    for frame in 0..FRAMES {
+        if test.tap && frame == TAP_FRAME {
+            emulator.press(TAP_KEY);
+            tapping = true;
+        }
+
+        for _ in 0..STEPS_PER_FRAME {
+            if emulator.is_halted() {
+                break;
+            }
+
+            let pc = emulator.program_counter();
+            let waits_for_key = emulator.fetch(pc) & 0xF0FF == 0xF00A;
+            emulator.step()?;
+
+            if tapping && waits_for_key && emulator.program_counter() != pc {
+                emulator.release(TAP_KEY);
+                tapping = false;
+            }
+        }
+
+        emulator.tick_timers();
+    }
+
+    Ok(pixels(&emulator.frame()))
+}
+
+/// Copies the pixels of a screen, row by row
+///
+/// # Params
+///
+/// - `frame` - The screen
+fn pixels(frame: &FrameBuffer) -> Vec<Vec<bool>> {
+    (0..frame.height())
+        .map(|y| (0..frame.width()).map(|x| frame.is_on(x, y)).collect())
+        .collect()
+}
+
+/// Counts the places a 3x3 glyph is drawn on the screen, surrounded by
+/// pixels which are off so the letters of the ROMs aren't mistaken for it
+///
+/// # Params
+///
+/// - `pixels` - The pixels of the screen, row by row
+/// - `glyph` - The rows of the glyph
+fn count_glyphs(pixels: &[Vec<bool>], glyph: [u8; 3]) -> usize {
+    let height = pixels.len();
+    let width = pixels.first().map_or(0, Vec::len);
+
+    // The glyph is checked in a 5x5 window, its border being off
+    let is_on = |x: usize, y: usize| pixels[y][x];
+    let matches = |left: usize, top: usize| {
+        (0..5).all(|dy| {
+            (0..5).all(|dx| {
+                let on = match (dx, dy) {
+                    (1..=3, 1..=3) => glyph[dy - 1] & (0b100 >> (dx - 1)) != 0,
+                    _ => false,
+                };
+                is_on(left + dx, top + dy) == on
+            })
+        })
+    };
+
+    (0..height.saturating_sub(4))
+        .flat_map(|top| (0..width.saturating_sub(4)).map(move |left| (left, top)))
+        .filter(|&(left, top)| matches(left, top))
+        .count()
+}
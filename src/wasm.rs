@@ -0,0 +1,106 @@
+use keet8_core::{Emulator, Key, Lcg};
+use wasm_bindgen::prelude::*;
+
+// --- web emulator definition ------------------------------------------------
+
+/// The interpreter core as exposed to JavaScript
+///
+/// The page owns the main loop: it calls `run` and `tickTimers` once per
+/// animation frame, forwards key events to `setKey` and draws `framebuffer`
+/// onto a canvas, `screenWidth` by `screenHeight` pixels.
+#[wasm_bindgen]
+pub struct WebEmulator {
+    /// The wrapped emulator
+    emulator: Emulator,
+}
+
+#[wasm_bindgen]
+impl WebEmulator {
+    /// Creates the emulator from the bytes of a ROM, such as a `Uint8Array`
+    /// over the `ArrayBuffer` of a fetched or uploaded file
+    ///
+    /// # Params
+    ///
+    /// - `rom` - The bytes of the ROM
+    /// - `seed` - The seed of the random numbers of `RND`, as the browser
+    ///   has no entropy source the core could use
+    ///
+    /// # Errors
+    ///
+    /// If the ROM doesn't fit in memory
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8], seed: u32) -> Result<WebEmulator, JsError> {
+        let mut emulator = Emulator::from_rom(rom).map_err(|e| JsError::new(&e.to_string()))?;
+        emulator.set_random_source(Lcg::new(seed));
+
+        Ok(Self { emulator })
+    }
+
+    /// Executes a number of instructions, stopping at the first error
+    ///
+    /// # Params
+    ///
+    /// - `steps` - The number of instructions to execute
+    ///
+    /// # Errors
+    ///
+    /// If an error occured whilst executing an instruction
+    pub fn run(&mut self, steps: u32) -> Result<(), JsError> {
+        for _ in 0..steps {
+            self.emulator.step().map_err(|e| JsError::new(&e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Counts the delay and sound timers down by one, which is to be called
+    /// at 60Hz
+    #[wasm_bindgen(js_name = tickTimers)]
+    pub fn tick_timers(&mut self) {
+        self.emulator.tick_timers();
+    }
+
+    /// Sets whether a key on the keypad is held down
+    ///
+    /// # Params
+    ///
+    /// - `key` - The key on the keypad (`0x0` to `0xF`)
+    /// - `down` - Whether the key is held down
+    #[wasm_bindgen(js_name = setKey)]
+    pub fn set_key(&mut self, key: usize, down: bool) {
+        if let Some(key) = Key::from_index(key) {
+            self.emulator.set_key(key, down);
+        }
+    }
+
+    /// Gets a copy of the pixels of the screen, row by row, with every
+    /// non-zero pixel being on
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.emulator.framebuffer().to_vec()
+    }
+
+    /// Gets the width in pixels of the screen in the current resolution mode
+    #[wasm_bindgen(js_name = screenWidth)]
+    pub fn screen_width(&self) -> usize {
+        self.emulator.screen_width()
+    }
+
+    /// Gets the height in pixels of the screen in the current resolution mode
+    #[wasm_bindgen(js_name = screenHeight)]
+    pub fn screen_height(&self) -> usize {
+        self.emulator.screen_height()
+    }
+
+    /// Gets the value of the sound timer, the buzzer sounding while it is
+    /// non-zero
+    #[wasm_bindgen(js_name = soundTimer)]
+    pub fn sound_timer(&self) -> u8 {
+        self.emulator.sound_timer()
+    }
+
+    /// Checks whether the ROM has exited the interpreter with `EXIT`
+    #[wasm_bindgen(js_name = isHalted)]
+    pub fn is_halted(&self) -> bool {
+        self.emulator.is_halted()
+    }
+}
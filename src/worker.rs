@@ -0,0 +1,337 @@
+use crate::application::{fit_window, window_title};
+use crate::audio::{Buzzer, BuzzerSink};
+use crate::config::Config;
+use crate::input::{poll_all, Gamepad};
+use crate::options::Options;
+use crate::prelude::*;
+use crate::roms::read_rom;
+use crate::scheduler::Scheduler;
+use crate::screen::Screen;
+
+use keet8_core::{Emulator, FrameBuffer, InitPolicy, Lcg, Pixels, Renderer, StepOutcome};
+use log::info;
+use raylib::prelude::*;
+
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// --- constants --------------------------------------------------------------
+
+/// The gamepad read as the input of the ROM, being the first one connected
+const GAMEPAD: i32 = 0;
+/// The time the worker sleeps between checking for instructions due, well
+/// below the 60Hz of the timers
+const WORKER_SLEEP: Duration = Duration::from_millis(1);
+
+// --- worker definition ------------------------------------------------------
+
+/// What the render thread asks the worker to do
+enum Command {
+    /// Hold down the keys of the keypad, with bit `K` set for key `K`
+    Keys(u16),
+    /// Pause or resume the ROM
+    Pause(bool),
+    /// Restart the ROM from its original image
+    Reset,
+    /// Stop running the ROM and end the thread
+    Stop,
+}
+
+/// The screen of the emulator as the worker left it after a timer tick, sent
+/// to the render thread to be presented
+struct Snapshot {
+    /// The pixels of the screen
+    pixels: Pixels,
+    /// The width in pixels of the screen
+    width: usize,
+    /// The height in pixels of the screen
+    height: usize,
+    /// Flag indicating whether the ROM has exited the interpreter
+    halted: bool,
+    /// The error which stopped the ROM, if any
+    fault: Option<Keet8Error>,
+}
+
+impl Snapshot {
+    /// Copies the screen of the emulator
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator to copy the screen of
+    /// - `fault` - The error which stopped the ROM, if any
+    fn capture(emulator: &Emulator, fault: Option<Keet8Error>) -> Self {
+        Self {
+            pixels: *emulator.pixels(),
+            width: emulator.screen_width(),
+            height: emulator.screen_height(),
+            halted: emulator.is_halted(),
+            fault,
+        }
+    }
+
+    /// Gets the screen to be presented
+    fn frame(&self) -> FrameBuffer<'_> {
+        FrameBuffer::new(&self.pixels, self.width, self.height)
+    }
+}
+
+/// Runs an emulator on a thread of its own, so that a high CPU speed never
+/// holds up drawing the window or reading the input
+///
+/// The render thread sends the keys held down and the other commands over a
+/// channel, and takes the snapshots of the screen the worker sends back after
+/// every timer tick. The worker keeps its own time, stepping the emulator at
+/// its speed whatever the refresh rate of the window.
+struct Worker {
+    /// The sending end of the commands to the worker
+    commands: Sender<Command>,
+    /// The receiving end of the snapshots of the screen
+    snapshots: Receiver<Snapshot>,
+    /// The handle to the thread, joined once the worker is dropped
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    /// Moves the emulator onto a new thread and starts running its ROM
+    ///
+    /// # Params
+    ///
+    /// - `emulator` - The emulator with the ROM loaded
+    /// - `cpu_speed` - The number of instructions executed per second
+    fn spawn(emulator: Emulator, cpu_speed: u32) -> Self {
+        let (commands, received) = mpsc::channel();
+        let (sender, snapshots) = mpsc::channel();
+        let scheduler = Scheduler::new(cpu_speed);
+        let handle = thread::spawn(move || run(emulator, scheduler, received, sender));
+
+        Self {
+            commands,
+            snapshots,
+            handle: Some(handle),
+        }
+    }
+
+    /// Sends a command to the worker, which is dropped if the worker has
+    /// already stopped
+    ///
+    /// # Params
+    ///
+    /// - `command` - The command to send
+    fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+
+    /// Gets the latest snapshot sent since the last call, skipping the older
+    /// ones the window was too slow to present, unless one carries an error
+    fn latest(&self) -> Option<Snapshot> {
+        let mut latest = None;
+        for snapshot in self.snapshots.try_iter() {
+            let fault = snapshot.fault.is_some();
+            latest = Some(snapshot);
+            if fault {
+                break;
+            }
+        }
+
+        latest
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.send(Command::Stop);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// --- threaded interface -----------------------------------------------------
+
+/// Runs the ROM in a window with the emulator on a worker thread, which is
+/// enabled with `--threaded`
+///
+/// Only the ROM runs, with its keys, sound and display, leaving the debugger,
+/// save states, recordings and the rest of the tooling to the regular window.
+/// P pauses and resumes the ROM and Ctrl+R restarts it. The window closes
+/// once the ROM exits the interpreter.
+///
+/// # Params
+///
+/// - `options` - The options of the application
+/// - `config` - The configuration, with the options already applied
+///
+/// # Errors
+///
+/// - If an error occured when loading the ROM file
+/// - If the texture of the display could not be created
+/// - If an error occured whilst running the ROM
+pub(crate) fn run_threaded(options: &Options, config: &Config) -> Result<()> {
+    let rom = read_rom(&options.rom_file)?;
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let init = if options.random_init {
+        InitPolicy::Random(seed)
+    } else {
+        InitPolicy::Zeroed
+    };
+
+    // The ROM is loaded once the memory has the size of the platform
+    let mut emulator = Emulator::without_rom(init);
+    emulator.set_quirks(config.quirks);
+    emulator.set_platform(config.platform);
+    emulator.set_two_page(config.two_page);
+    emulator.set_load_address(config.load_addr)?;
+    emulator.load_rom(&rom)?;
+    emulator.set_strict(options.strict);
+    emulator.set_random_source(Lcg::new(seed));
+
+    // We don't want logging for release builds
+    let log_level = if cfg!(debug_assertions) {
+        TraceLogLevel::LOG_INFO
+    } else {
+        TraceLogLevel::LOG_NONE
+    };
+    let (mut rl, thread) = raylib::init()
+        .size(config.window_width, config.window_height)
+        .title(&window_title())
+        .vsync()
+        .msaa_4x()
+        .resizable()
+        .log_level(log_level)
+        .build();
+    fit_window(&mut rl, config.window_width, config.window_height);
+
+    let mut screen =
+        Screen::new(&mut rl, &thread, config.crt, config.integer_scaling, config.phosphor)?;
+    screen.set_pixel_grid(config.accessibility.pixel_grid);
+    let sink = BuzzerSink::default();
+    let mut buzzer = config
+        .audio
+        .then(|| Buzzer::open(config.volume, config.tone, sink.clone()))
+        .flatten();
+    if buzzer.is_some() {
+        emulator.set_audio_sink(sink);
+    }
+
+    let mut keymap = config.keymap;
+    let mut gamepad = Gamepad::new(GAMEPAD, config.gamepad_map);
+    let worker = Worker::spawn(emulator, config.cpu_speed);
+    info!("Running {} on a worker thread", options.rom_file);
+
+    let mut snapshot = None;
+    let mut keys = 0;
+    let mut paused = false;
+    while !rl.window_should_close() {
+        let held = poll_all(&rl, &mut [&mut keymap, &mut gamepad]);
+        if held != keys {
+            keys = held;
+            worker.send(Command::Keys(keys));
+        }
+
+        // P pauses and resumes, unless it is mapped to the keypad, and Ctrl+R
+        // restarts the ROM
+        if rl.is_key_pressed(KeyboardKey::KEY_P) && !keymap.contains(&KeyboardKey::KEY_P) {
+            paused = !paused;
+            worker.send(Command::Pause(paused));
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_R)
+            && (rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL))
+        {
+            worker.send(Command::Reset);
+        }
+
+        if let Some(latest) = worker.latest() {
+            snapshot = Some(latest);
+        }
+        if let Some(fault) = snapshot.as_mut().and_then(|snapshot| snapshot.fault.take()) {
+            return Err(fault);
+        }
+        if snapshot.as_ref().is_some_and(|snapshot| snapshot.halted) {
+            break;
+        }
+
+        if let Some(buzzer) = &mut buzzer {
+            buzzer.update(paused);
+        }
+
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::BLACK);
+        if let Some(snapshot) = &snapshot {
+            screen
+                .renderer(&mut d, &thread, config.foreground, config.background)
+                .present(&snapshot.frame());
+        }
+    }
+
+    Ok(())
+}
+
+// --- utility functions ------------------------------------------------------
+
+/// Runs the ROM on the worker thread until it is asked to stop, the render
+/// thread has gone or the ROM has raised an error
+///
+/// # Params
+///
+/// - `emulator` - The emulator with the ROM loaded
+/// - `scheduler` - The scheduler of the instructions and timer ticks
+/// - `commands` - The receiving end of the commands
+/// - `snapshots` - The sending end of the snapshots of the screen
+fn run(
+    mut emulator: Emulator,
+    mut scheduler: Scheduler,
+    commands: Receiver<Command>,
+    snapshots: Sender<Snapshot>,
+) {
+    let mut paused = false;
+    let mut last = Instant::now();
+
+    loop {
+        loop {
+            match commands.try_recv() {
+                Ok(Command::Keys(keys)) => emulator.set_all(keys),
+                Ok(Command::Pause(pause)) => paused = pause,
+                Ok(Command::Reset) => emulator.reset(),
+                Ok(Command::Stop) | Err(TryRecvError::Disconnected) => return,
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        let now = Instant::now();
+        scheduler.advance((now - last).as_secs_f32());
+        last = now;
+        if paused {
+            scheduler.skip_steps();
+            scheduler.skip_ticks();
+        }
+
+        let mut fault = None;
+        while scheduler.next_step() {
+            match emulator.step() {
+                Ok(StepOutcome::Halted) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    fault = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let mut ticked = false;
+        while scheduler.next_tick() {
+            emulator.tick_timers();
+            ticked = true;
+        }
+
+        if ticked || fault.is_some() {
+            let stopped = fault.is_some();
+            if snapshots.send(Snapshot::capture(&emulator, fault)).is_err() || stopped {
+                return;
+            }
+        }
+
+        thread::sleep(WORKER_SLEEP);
+    }
+}
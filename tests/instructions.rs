@@ -0,0 +1,1644 @@
+//! Tests of every instruction executor, executing single opcodes on a fresh
+//! emulator and checking the registers, memory and flags they leave behind.
+//!
+//! Requires the `testing` feature: `cargo test --features testing`
+
+use keet_8::{
+    assemble, assemble_octo, assemble_program, is_two_page_rom, AudioSink, Emulator, Event, HostCall, FrameBuffer, InitPolicy, Keet8Error, Key, OpCode, Pixels, Platform, Quirks, Renderer, Resolution, ScriptedRandom,
+    StepOutcome, WatchHit, AUDIO_PATTERN_SIZE, HIRES_HEIGHT, HIRES_WIDTH, LORES_WIDTH,
+    MAX_MEMORY_SIZE, MEMORY_SIZE, STACK_SIZE, TWO_PAGE_ENTRY, TWO_PAGE_HEIGHT,
+};
+
+use std::sync::{Arc, Mutex};
+
+// --- constants --------------------------------------------------------------
+
+/// The address the program counter starts at
+const PROG_ADDR: u16 = 0x0200;
+/// The address of the font sprite of `0`
+const FONT_ADDR: u16 = 0x0050;
+/// The address of the big font sprite of `0`
+const BIG_FONT_ADDR: u16 = 0x00A0;
+
+// --- utility functions ------------------------------------------------------
+
+/// Creates an emulator with a ROM of a single zeroed instruction
+fn emulator() -> Emulator {
+    Emulator::from_rom(&[0x00, 0x00]).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Executes a raw opcode, failing the test if the instruction errors
+///
+/// # Params
+///
+/// - `emulator` - The emulator to execute the opcode on
+/// - `raw` - The raw opcode to execute
+fn execute(emulator: &mut Emulator, raw: u16) {
+    if let Err(e) = emulator.execute(OpCode::from(raw)) {
+        panic!("{raw:04X}: {e}");
+    }
+}
+
+/// Counts the pixels which are on
+///
+/// # Params
+///
+/// - `emulator` - The emulator to count the pixels of
+fn lit_pixels(emulator: &Emulator) -> usize {
+    emulator.framebuffer().iter().filter(|&&pixel| pixel > 0).count()
+}
+
+// --- system and flow --------------------------------------------------------
+
+#[test]
+fn raw_does_nothing() {
+    let mut emu = emulator();
+    execute(&mut emu, 0x0123);
+
+    assert_eq!(emu.program_counter(), PROG_ADDR + 2);
+    assert!((0..16).all(|x| emu.register(x) == 0));
+}
+
+#[test]
+fn cls_clears_the_screen() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD005);
+    assert!(lit_pixels(&emu) > 0);
+
+    execute(&mut emu, 0x00E0);
+    assert_eq!(lit_pixels(&emu), 0);
+}
+
+#[test]
+fn jp_jumps_to_the_address() {
+    let mut emu = emulator();
+    execute(&mut emu, 0x1234);
+
+    assert_eq!(emu.program_counter(), 0x0234);
+}
+
+#[test]
+fn jp_v0_jumps_with_offset() {
+    let mut emu = emulator();
+    emu.set_register(0x0, 0x10);
+    emu.set_register(0x3, 0x20);
+    execute(&mut emu, 0xB300);
+
+    assert_eq!(emu.program_counter(), 0x0310);
+}
+
+#[test]
+fn jp_vx_quirk_uses_the_register_of_the_address() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        jump_vx: true,
+        ..Quirks::default()
+    });
+    emu.set_register(0x0, 0x10);
+    emu.set_register(0x3, 0x20);
+    execute(&mut emu, 0xB300);
+
+    assert_eq!(emu.program_counter(), 0x0320);
+}
+
+#[test]
+fn call_and_ret_use_the_call_stack() {
+    let mut emu = emulator();
+    execute(&mut emu, 0x2400);
+
+    assert_eq!(emu.program_counter(), 0x0400);
+    assert_eq!(emu.call_stack(), &[PROG_ADDR + 2]);
+
+    execute(&mut emu, 0x00EE);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 2);
+    assert!(emu.call_stack().is_empty());
+}
+
+#[test]
+fn ret_fails_on_an_empty_call_stack() {
+    let mut emu = emulator();
+
+    assert!(emu.execute(OpCode::from(0x00EE)).is_err());
+}
+
+#[test]
+fn call_fails_on_a_full_call_stack() {
+    let mut emu = emulator();
+    for _ in 0..32 {
+        execute(&mut emu, 0x2400);
+    }
+
+    assert!(emu.execute(OpCode::from(0x2400)).is_err());
+}
+
+#[test]
+fn from_rom_rejects_a_rom_too_large_for_memory() {
+    assert!(Emulator::from_rom(&[0; 3584]).is_ok());
+    let err = Emulator::from_rom(&[0; 3585]).err();
+    assert!(matches!(err, Some(Keet8Error::ROMTooLarge { size: 3585, max: 3584 })));
+}
+
+#[test]
+fn empty_roms_are_rejected() {
+    assert!(matches!(Emulator::from_rom(&[]).err(), Some(Keet8Error::EmptyROM)));
+
+    let mut emu = Emulator::from_rom(&[0x60, 0x42]).unwrap_or_else(|e| panic!("{e}"));
+    assert!(matches!(emu.load_rom(&[]), Err(Keet8Error::EmptyROM)));
+    assert_eq!(&emu.memory()[0x200..0x202], [0x60, 0x42]);
+
+    let mut emu = Emulator::without_rom(InitPolicy::Zeroed);
+    emu.set_load_address(0x600).unwrap_or_else(|e| panic!("{e}"));
+    emu.load_rom(&[0x60, 0x42]).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(&emu.memory()[0x600..0x602], [0x60, 0x42]);
+}
+
+#[test]
+fn roms_load_and_start_at_the_load_address() {
+    // LD V0, 0x42
+    let mut emu = Emulator::from_rom(&[0x60, 0x42]).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_load_address(0x600).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!((emu.load_address(), emu.program_counter()), (0x600, 0x600));
+    assert_eq!(&emu.memory()[0x600..0x602], [0x60, 0x42]);
+    assert_eq!(&emu.memory()[0x200..0x202], [0x00, 0x00]);
+
+    emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.register(0x0), 0x42);
+    emu.reset();
+    assert_eq!(emu.program_counter(), 0x600);
+
+    let mut emu = Emulator::from_rom(&[0; 2561]).unwrap_or_else(|e| panic!("{e}"));
+    let err = emu.set_load_address(0x600).err();
+    assert!(matches!(err, Some(Keet8Error::ROMTooLarge { size: 2561, max: 2560 })));
+    assert_eq!(emu.load_address(), PROG_ADDR);
+
+    let mut emu = Emulator::from_rom(&[0x60, 0x42]).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_load_address(0x600).unwrap_or_else(|e| panic!("{e}"));
+    let err = emu.load_rom(&[0; 2561]).err();
+    assert!(matches!(err, Some(Keet8Error::ROMTooLarge { size: 2561, max: 2560 })));
+    assert_eq!(
+        err.map(|e| e.to_string()).as_deref(),
+        Some("[ERROR]: ROM too large: 2561 bytes, but at most 2560 bytes fit")
+    );
+}
+
+#[test]
+fn reset_restarts_the_rom() {
+    let mut emu = Emulator::from_rom(&[0x12, 0x34]).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_register(0x1, 5);
+    emu.set_delay_timer(10);
+    emu.write_memory(PROG_ADDR, 0xFF);
+    execute(&mut emu, 0x2400);
+    execute(&mut emu, 0x00E0);
+
+    emu.reset();
+    assert_eq!(emu.program_counter(), PROG_ADDR);
+    assert_eq!(emu.register(0x1), 0);
+    assert_eq!(emu.delay_timer(), 0);
+    assert!(emu.call_stack().is_empty());
+    assert_eq!(emu.read_memory(PROG_ADDR), 0x12);
+    assert_eq!(emu.read_memory(FONT_ADDR), 0xF0);
+}
+
+#[test]
+fn random_init_fills_everything_but_the_rom_and_fonts() {
+    let rom = [0x12, 0x34];
+    let mut emu = Emulator::with_init(&rom, InitPolicy::Random(7)).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.program_counter(), PROG_ADDR);
+    assert_eq!(emu.read_memory(PROG_ADDR), 0x12);
+    assert_eq!(emu.read_memory(FONT_ADDR), 0xF0);
+    assert!(emu.index() <= 0xFFF);
+
+    // The garbage only covers what the ROM and fonts leave free
+    let garbage = emu.memory()[PROG_ADDR as usize + rom.len()..].to_vec();
+    assert!(garbage.iter().any(|&byte| byte != 0));
+    assert!((0..16).any(|x| emu.register(x) != 0));
+
+    // The same seed gives the same garbage on a reset
+    let registers: Vec<u8> = (0..16).map(|x| emu.register(x)).collect();
+    emu.write_memory(0xFFF, !garbage[garbage.len() - 1]);
+    emu.reset();
+    assert_eq!(emu.memory()[PROG_ADDR as usize + rom.len()..], garbage[..]);
+    assert_eq!((0..16).map(|x| emu.register(x)).collect::<Vec<_>>(), registers);
+
+    // Zeroed memory is what `from_rom` starts with
+    let emu = Emulator::with_init(&rom, InitPolicy::Zeroed).unwrap_or_else(|e| panic!("{e}"));
+    assert!(emu.memory()[PROG_ADDR as usize + rom.len()..].iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn load_rom_replaces_the_rom_and_restarts() {
+    let mut emu = Emulator::from_rom(&[0x12, 0x34, 0x56]).unwrap_or_else(|e| panic!("{e}"));
+    let old_hash = emu.rom_hash();
+    emu.set_register(0x1, 5);
+    execute(&mut emu, 0x2400);
+
+    emu.load_rom(&[0xAB]).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.program_counter(), PROG_ADDR);
+    assert_eq!(emu.register(0x1), 0);
+    assert_ne!(emu.rom_hash(), old_hash);
+    assert_eq!(emu.read_memory(PROG_ADDR), 0xAB);
+    assert_eq!(emu.read_memory(PROG_ADDR + 1), 0x00);
+
+    // A ROM which doesn't fit leaves the loaded one in place
+    assert!(emu.load_rom(&[0; 0x1000]).is_err());
+    assert_eq!(emu.read_memory(PROG_ADDR), 0xAB);
+
+    emu.reset();
+    assert_eq!(emu.read_memory(PROG_ADDR), 0xAB);
+}
+
+#[test]
+fn exit_halts_the_interpreter() {
+    let mut emu = emulator();
+    execute(&mut emu, 0x00FD);
+
+    assert!(emu.is_halted());
+}
+
+// --- skips ------------------------------------------------------------------
+
+#[test]
+fn se_skips_if_equal() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0x42);
+    emu.set_register(0x2, 0x42);
+
+    execute(&mut emu, 0x3142);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 4);
+
+    execute(&mut emu, 0x3143);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 6);
+
+    execute(&mut emu, 0x5120);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 10);
+}
+
+#[test]
+fn sne_skips_if_not_equal() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0x42);
+    emu.set_register(0x2, 0x43);
+
+    execute(&mut emu, 0x4143);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 4);
+
+    execute(&mut emu, 0x4142);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 6);
+
+    execute(&mut emu, 0x9120);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 10);
+}
+
+#[test]
+fn skp_skips_if_the_key_is_down() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0xA);
+
+    execute(&mut emu, 0xE19E);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 2);
+
+    emu.press(Key::KA);
+    execute(&mut emu, 0xE19E);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 6);
+}
+
+#[test]
+fn sknp_skips_if_the_key_is_up() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0xA);
+
+    execute(&mut emu, 0xE1A1);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 4);
+
+    emu.press(Key::KA);
+    execute(&mut emu, 0xE1A1);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 6);
+}
+
+#[test]
+fn key_skips_use_the_low_nibble_of_the_register() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0xFA);
+    emu.press(Key::KA);
+
+    execute(&mut emu, 0xE1A1);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 2);
+}
+
+// --- loads ------------------------------------------------------------------
+
+#[test]
+fn ld_loads_bytes_and_registers() {
+    let mut emu = emulator();
+    execute(&mut emu, 0x6A42);
+    assert_eq!(emu.register(0xA), 0x42);
+
+    execute(&mut emu, 0x8BA0);
+    assert_eq!(emu.register(0xB), 0x42);
+}
+
+#[test]
+fn ld_loads_the_index() {
+    let mut emu = emulator();
+    execute(&mut emu, 0xA123);
+
+    assert_eq!(emu.index(), 0x0123);
+}
+
+#[test]
+fn ld_loads_the_timers() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 30);
+    execute(&mut emu, 0xF115);
+    execute(&mut emu, 0xF118);
+
+    assert_eq!(emu.delay_timer(), 30);
+    assert_eq!(emu.sound_timer(), 30);
+
+    emu.set_delay_timer(12);
+    execute(&mut emu, 0xF207);
+    assert_eq!(emu.register(0x2), 12);
+}
+
+#[test]
+fn timers_only_tick_on_their_own() {
+    let mut emu = emulator();
+    emu.set_delay_timer(2);
+    emu.set_sound_timer(1);
+    execute(&mut emu, 0x0123);
+    assert_eq!((emu.delay_timer(), emu.sound_timer()), (2, 1));
+
+    emu.tick_timers();
+    emu.tick_timers();
+    assert_eq!((emu.delay_timer(), emu.sound_timer()), (0, 0));
+}
+
+#[test]
+fn timing_rom_counts_the_instructions_per_tick() {
+    let rom = std::fs::read("tests/timing.ch8").unwrap_or_else(|e| panic!("{e}"));
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+
+    // 12 instructions per tick, being 2 iterations of the loop, for the 120
+    // ticks the ROM counts
+    while !emu.is_halted() {
+        for _ in 0..12 {
+            emu.step().unwrap_or_else(|e| panic!("{e}"));
+        }
+        emu.tick_timers();
+    }
+
+    let iterations = ((emu.register(2) as u32) << 8) | emu.register(1) as u32;
+    assert!((239..=241).contains(&iterations), "{iterations} iterations");
+}
+
+#[test]
+fn ld_waits_for_a_key() {
+    let mut emu = emulator();
+    execute(&mut emu, 0xF30A);
+    assert_eq!(emu.program_counter(), PROG_ADDR);
+
+    emu.press(Key::K7);
+    execute(&mut emu, 0xF30A);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 2);
+    assert_eq!(emu.register(0x3), 0x7);
+}
+
+#[test]
+fn keys_are_set_by_value_or_all_at_once() {
+    assert_eq!(Key::from_index(0xA), Some(Key::KA));
+    assert_eq!(Key::from_index(0x10), None);
+    assert_eq!(Key::KF.index(), 0xF);
+
+    let mut emu = emulator();
+    emu.set_register(0x1, 0xA);
+    emu.set_all(1 << 0xA);
+    execute(&mut emu, 0xE1A1);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 2);
+
+    emu.release(Key::KA);
+    execute(&mut emu, 0xE1A1);
+    assert_eq!(emu.program_counter(), PROG_ADDR + 6);
+}
+
+#[test]
+fn ld_points_the_index_at_font_sprites() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0x3);
+
+    execute(&mut emu, 0xF129);
+    assert_eq!(emu.index(), FONT_ADDR + 15);
+
+    execute(&mut emu, 0xF130);
+    assert_eq!(emu.index(), BIG_FONT_ADDR + 30);
+}
+
+#[test]
+fn ld_stores_bcd() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 254);
+    emu.set_index(0x0300);
+    execute(&mut emu, 0xF133);
+
+    assert_eq!(emu.read_memory(0x0300), 2);
+    assert_eq!(emu.read_memory(0x0301), 5);
+    assert_eq!(emu.read_memory(0x0302), 4);
+}
+
+#[test]
+fn memory_is_readable_as_a_slice() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 254);
+    emu.set_index(0x0300);
+    execute(&mut emu, 0xF133);
+
+    assert_eq!(emu.memory().len(), 4096);
+    assert_eq!(emu.memory()[0x0300..0x0303], [2, 5, 4]);
+    assert_eq!(emu.memory()[FONT_ADDR as usize], 0xF0);
+}
+
+#[test]
+fn ld_stores_and_loads_registers() {
+    let mut emu = emulator();
+    (0..4).for_each(|x| emu.set_register(x, x as u8 + 1));
+    emu.set_index(0x0300);
+
+    execute(&mut emu, 0xF255);
+    assert_eq!(
+        (0..4).map(|i| emu.read_memory(0x0300 + i)).collect::<Vec<u8>>(),
+        [1, 2, 3, 0]
+    );
+    assert_eq!(emu.index(), 0x0300);
+
+    (0..4).for_each(|x| emu.set_register(x, 0));
+    emu.write_memory(0x0303, 9);
+    execute(&mut emu, 0xF365);
+    assert_eq!((0..4).map(|x| emu.register(x)).collect::<Vec<u8>>(), [1, 2, 3, 9]);
+}
+
+#[test]
+fn ld_wraps_past_the_end_of_memory() {
+    let mut emu = emulator();
+    (0..2).for_each(|x| emu.set_register(x, x as u8 + 1));
+    emu.set_index(0x0FFF);
+    execute(&mut emu, 0xF155);
+
+    assert_eq!(emu.read_memory(0x0FFF), 1);
+    assert_eq!(emu.read_memory(0x0000), 2);
+}
+
+#[test]
+fn strict_memory_quirk_rejects_addresses_past_the_end() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        strict_memory: true,
+        ..Quirks::default()
+    });
+    emu.set_index(0x0FFF);
+
+    assert!(emu.execute(OpCode::from(0xF055)).is_ok());
+    assert!(emu.execute(OpCode::from(0xF155)).is_err());
+    assert!(emu.execute(OpCode::from(0xF033)).is_err());
+    assert!(emu.execute(OpCode::from(0xD012)).is_err());
+}
+
+#[test]
+fn clamp_memory_quirk_holds_addresses_at_the_end() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        clamp_memory: true,
+        ..Quirks::default()
+    });
+    (0..2).for_each(|x| emu.set_register(x, x as u8 + 1));
+    emu.set_index(0x0FFF);
+    execute(&mut emu, 0xF155);
+
+    assert_eq!(emu.read_memory(0x0FFF), 2);
+    assert_eq!(emu.read_memory(0x0000), 0);
+
+    // Rejecting the addresses takes precedence over clamping them
+    emu.set_quirks(Quirks {
+        clamp_memory: true,
+        strict_memory: true,
+        ..Quirks::default()
+    });
+    assert!(matches!(
+        emu.execute(OpCode::from(0xF155)),
+        Err(Keet8Error::AddressOutOfRange(0x1000))
+    ));
+}
+
+#[test]
+fn strict_emulator_rejects_unknown_opcodes() {
+    // 8XY8 decodes to no instruction
+    let mut emu = Emulator::from_rom(&[0x81, 0x28, 0x81, 0x28]).unwrap_or_else(|e| panic!("{e}"));
+    assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+
+    emu.set_strict(true);
+    assert!(matches!(emu.step(), Err(Keet8Error::UnknownOpcode(0x8128, 0x0202))));
+    execute(&mut emu, 0x6005);
+}
+
+#[test]
+fn step_errors_hold_the_failing_instruction() {
+    // CALL 0x200 recursing until the call stack overflows
+    let mut emu = Emulator::from_rom(&[0x22, 0x00]).unwrap_or_else(|e| panic!("{e}"));
+    let error = loop {
+        if let Err(e) = emu.step() {
+            break e;
+        }
+    };
+
+    assert!(matches!(error, Keet8Error::InstructionFailed(0x200, 0x2200, _)));
+    assert!(matches!(error.root_cause(), Keet8Error::CallStackFull));
+
+    let source = std::error::Error::source(&error).map(ToString::to_string);
+    assert!(source.is_some_and(|source| source.ends_with("Call stack limit reached")));
+}
+
+#[test]
+fn platforms_limit_the_depth_of_the_call_stack() {
+    // CALL 0x200 recursing until the call stack overflows
+    let mut emu = Emulator::from_rom(&[0x22, 0x00]).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_platform(Some(Platform::Chip8));
+    assert_eq!(emu.stack_limit(), 12);
+
+    let error = loop {
+        if let Err(e) = emu.step() {
+            break e;
+        }
+    };
+    assert!(matches!(error.root_cause(), Keet8Error::CallStackFull));
+    assert_eq!(emu.call_stack().len(), 12);
+
+    emu.reset();
+    emu.set_stack_limit(100);
+    assert_eq!(emu.stack_limit(), STACK_SIZE);
+    emu.set_platform(None);
+    assert_eq!(emu.stack_limit(), STACK_SIZE);
+}
+
+#[test]
+fn platforms_set_the_size_of_memory() {
+    let mut emu = emulator();
+    emu.set_platform(Some(Platform::XoChip));
+    assert_eq!(emu.memory_size(), MAX_MEMORY_SIZE);
+    assert_eq!(emu.memory().len(), MAX_MEMORY_SIZE);
+
+    emu.set_platform(Some(Platform::Schip));
+    assert_eq!(emu.memory_size(), MEMORY_SIZE);
+
+    // A ROM too large for 4KB keeps the 64KB it was loaded into
+    let mut emu = emulator();
+    emu.set_memory_size(MAX_MEMORY_SIZE).unwrap_or_else(|e| panic!("{e}"));
+    emu.load_rom(&[0; 0x2000]).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_platform(Some(Platform::Chip8));
+    assert_eq!(emu.memory_size(), MAX_MEMORY_SIZE);
+}
+
+#[test]
+fn resized_memory_is_addressed_through_the_index() {
+    let mut emu = emulator();
+    emu.set_memory_size(MAX_MEMORY_SIZE).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_register(0x0, 0xAB);
+    emu.set_index(0x1234);
+    execute(&mut emu, 0xF055);
+    assert_eq!(emu.read_memory(0x1234), 0xAB);
+    assert_eq!(emu.read_memory(0x0234), 0x00);
+
+    emu.set_index(0xFFFF);
+    execute(&mut emu, 0xF155);
+    assert_eq!(emu.read_memory(0xFFFF), 0xAB);
+    assert_eq!(emu.read_memory(0x0000), 0x00);
+
+    let state = emu.save_state();
+    let inspected = Emulator::from_state(&state).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(inspected.memory_size(), MAX_MEMORY_SIZE);
+    assert_eq!(inspected.memory()[0x1234], 0xAB);
+}
+
+#[test]
+fn memory_sizes_are_checked() {
+    let mut emu = emulator();
+    for size in [0, 2048, 6000, 0x20000] {
+        let err = emu.set_memory_size(size).err();
+        assert!(matches!(err, Some(Keet8Error::InvalidMemorySize(s)) if s == size));
+    }
+    assert_eq!(emu.memory_size(), MEMORY_SIZE);
+
+    emu.set_memory_size(0x4000).unwrap_or_else(|e| panic!("{e}"));
+    emu.load_rom(&[0; 0x2000]).unwrap_or_else(|e| panic!("{e}"));
+    let err = emu.set_memory_size(MEMORY_SIZE).err();
+    assert!(matches!(err, Some(Keet8Error::ROMTooLarge { size: 0x2000, max: 3584 })));
+    assert_eq!(emu.memory_size(), 0x4000);
+}
+
+#[test]
+fn unreadable_roms_keep_the_io_error() {
+    let Err(error) = Emulator::new("tests/missing.ch8") else {
+        panic!("a missing ROM should fail to load");
+    };
+
+    let source = std::error::Error::source(&error).and_then(|e| e.downcast_ref::<std::io::Error>());
+    assert_eq!(source.map(std::io::Error::kind), Some(std::io::ErrorKind::NotFound));
+}
+
+#[test]
+fn events_are_reported_to_the_callbacks() {
+    let rom = [0x00, 0xE0, 0x61, 0x03, 0xF1, 0x18, 0x81, 0x28, 0x12, 0x08];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    emu.on_event(move |event| sink.lock().unwrap().push(event));
+
+    // The jump to itself is only reported the first time round
+    for _ in 0..6 {
+        assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+    }
+    (0..3).for_each(|_| emu.tick_timers());
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            Event::ScreenCleared,
+            Event::SoundStarted,
+            Event::UnknownOpcode {
+                opcode: 0x8128,
+                addr: 0x0206
+            },
+            Event::Finished { addr: 0x0208 },
+            Event::FrameCompleted { drawn: true },
+            Event::FrameCompleted { drawn: false },
+            Event::SoundStopped,
+            Event::FrameCompleted { drawn: false },
+        ]
+    );
+}
+
+#[test]
+fn sprites_only_mark_the_rows_they_change() {
+    // `LD V1, 2`, `DRW V0, V1, 2` with I pointing at `00` and `80`, then `CLS`
+    let rom = [0x61, 0x02, 0xA2, 0x08, 0xD0, 0x12, 0x00, 0xE0, 0x00, 0x80];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.dirty_rows(), u64::MAX);
+
+    emu.clear_dirty_rows();
+    for _ in 0..3 {
+        assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+    }
+    assert_eq!(emu.dirty_rows(), 0b1000);
+    assert!(emu.frame().is_row_dirty(3));
+    assert!(!emu.frame().is_row_dirty(2));
+
+    emu.clear_dirty_rows();
+    assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+    assert_eq!(emu.dirty_rows(), u64::MAX);
+}
+
+#[test]
+fn frames_report_whether_the_screen_was_drawn() {
+    // `DRW V0, V0, 1`, then `JP 0x202` to loop forever
+    let mut emu = Emulator::from_rom(&[0xD0, 0x01, 0x12, 0x02]).unwrap_or_else(|e| panic!("{e}"));
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    emu.on_event(move |event| sink.lock().unwrap().push(event));
+
+    emu.tick_timers();
+    assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+    emu.tick_timers();
+    assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+    emu.tick_timers();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            Event::FrameCompleted { drawn: true },
+            Event::FrameCompleted { drawn: true },
+            Event::Finished { addr: 0x0202 },
+            Event::FrameCompleted { drawn: false },
+        ]
+    );
+}
+
+#[test]
+fn audio_sink_follows_the_buzzer() {
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl AudioSink for Recorder {
+        fn beep_start(&mut self) {
+            self.0.lock().unwrap().push("start".to_string());
+        }
+
+        fn beep_stop(&mut self) {
+            self.0.lock().unwrap().push("stop".to_string());
+        }
+
+        fn pattern(&mut self, pattern: Option<&[u8; AUDIO_PATTERN_SIZE]>, pitch: u8) {
+            let first = pattern.map(|pattern| pattern[0]);
+            self.0.lock().unwrap().push(format!("pattern {first:?} {pitch}"));
+        }
+    }
+
+    let mut emu = emulator();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    emu.set_audio_sink(Recorder(Arc::clone(&calls)));
+
+    // Setting the sound timer again whilst the buzzer sounds isn't repeated
+    emu.set_register(1, 2);
+    execute(&mut emu, 0xF118);
+    execute(&mut emu, 0xF118);
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xF002);
+    emu.set_register(2, 112);
+    execute(&mut emu, 0xF23A);
+    (0..3).for_each(|_| emu.tick_timers());
+    emu.reset();
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            "start",
+            "pattern Some(240) 64",
+            "pattern Some(240) 112",
+            "stop",
+            "pattern None 64",
+        ]
+    );
+}
+
+#[test]
+fn access_counts_follow_the_rom() {
+    // LD I, 0x300; LD [I], V1; LD V1, [I]
+    let rom = [0xA3, 0x00, 0xF1, 0x55, 0xF1, 0x65];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    assert!(emu.access_counts().is_none());
+
+    emu.set_access_counting(true);
+    for _ in 0..3 {
+        assert!(matches!(emu.step(), Ok(StepOutcome::Executed)));
+    }
+
+    let counts = emu.access_counts().unwrap();
+    assert_eq!((counts.executes(PROG_ADDR), counts.executes(PROG_ADDR + 5)), (1, 1));
+    assert_eq!((counts.writes(0x300), counts.writes(0x301), counts.writes(0x302)), (1, 1, 0));
+    assert_eq!((counts.reads(0x300), counts.reads(0x301), counts.reads(0x302)), (1, 1, 0));
+
+    emu.reset();
+    assert_eq!(emu.access_counts().unwrap().executes(PROG_ADDR), 0);
+}
+
+#[test]
+fn ld_notes_writes_into_the_interpreter_area() {
+    let mut emu = emulator();
+    emu.set_index(0x01FE);
+    execute(&mut emu, 0xF155);
+    execute(&mut emu, 0xF133);
+
+    assert_eq!(emu.reserved_writes().collect::<Vec<u16>>(), [PROG_ADDR, PROG_ADDR + 2]);
+
+    emu.set_quirks(Quirks {
+        strict_memory: true,
+        ..Quirks::default()
+    });
+    assert!(emu.execute(OpCode::from(0xF055)).is_err());
+
+    emu.set_index(PROG_ADDR);
+    assert!(emu.execute(OpCode::from(0xF255)).is_ok());
+}
+
+#[test]
+fn load_store_quirk_increments_the_index() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        load_store_increment: true,
+        ..Quirks::default()
+    });
+    emu.set_index(0x0300);
+
+    execute(&mut emu, 0xF255);
+    assert_eq!(emu.index(), 0x0303);
+
+    execute(&mut emu, 0xF165);
+    assert_eq!(emu.index(), 0x0305);
+}
+
+#[test]
+fn load_store_quirk_wraps_the_index_around() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        load_store_increment: true,
+        ..Quirks::default()
+    });
+
+    emu.set_index(0xFFFF);
+    execute(&mut emu, 0xF255);
+    assert_eq!(emu.index(), 0x0002);
+
+    emu.set_index(0xFFFF);
+    execute(&mut emu, 0xF165);
+    assert_eq!(emu.index(), 0x0001);
+}
+
+#[test]
+fn host_call_print_wraps_the_index_around() {
+    // SYS 0x100 (print), with the last byte of memory holding `A`
+    let mut rom = vec![0; 3584];
+    rom[..2].copy_from_slice(&[0x01, 0x00]);
+    rom[3583] = b'A';
+
+    let printed = Arc::new(Mutex::new(Vec::new()));
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    let sink = Arc::clone(&printed);
+    emu.enable_host_calls(move |call| {
+        if let HostCall::Print(text) = call {
+            sink.lock().unwrap().push(text);
+        }
+    });
+
+    emu.set_index(0xFFFF);
+    emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(*printed.lock().unwrap(), ["A"]);
+}
+
+#[test]
+fn ld_saves_and_restores_rpl_flags() {
+    let mut emu = emulator();
+    (0..3).for_each(|x| emu.set_register(x, 0x10 + x as u8));
+    execute(&mut emu, 0xF275);
+
+    (0..3).for_each(|x| emu.set_register(x, 0));
+    execute(&mut emu, 0xF285);
+    assert_eq!((0..3).map(|x| emu.register(x)).collect::<Vec<u8>>(), [0x10, 0x11, 0x12]);
+}
+
+#[test]
+fn rpl_flags_can_be_restored_from_a_previous_run() {
+    let mut emu = emulator();
+    emu.set_rpl_flags(&[0x42, 0x07, 0, 0, 0, 0, 0, 0, 0xFF]);
+    assert_eq!(emu.rpl_flags(), [0x42, 0x07, 0, 0, 0, 0, 0, 0]);
+
+    emu.reset();
+    execute(&mut emu, 0xF185);
+    assert_eq!((emu.register(0), emu.register(1)), (0x42, 0x07));
+}
+
+#[test]
+fn save_ram_survives_a_reset() {
+    let mut emu = emulator();
+    emu.set_save_ram(Some(0x0E00..=0x0E03));
+    emu.restore_save_ram(&[0x12, 0x34]);
+    assert_eq!(emu.save_ram(), [0x12, 0x34, 0, 0]);
+
+    emu.set_index(0x0DFF);
+    emu.set_register(0x0, 0xAA);
+    emu.set_register(0x1, 0xBB);
+    execute(&mut emu, 0xF155);
+    assert_eq!(emu.save_ram(), [0xBB, 0x34, 0, 0]);
+
+    emu.reset();
+    assert_eq!(emu.save_ram(), [0xBB, 0x34, 0, 0]);
+    assert_eq!(emu.memory()[0x0DFF], 0);
+}
+
+#[test]
+fn ld_loads_the_audio_pattern_and_pitch() {
+    let mut emu = emulator();
+    assert!(emu.audio_pattern().is_none());
+    assert_eq!(emu.pitch(), 64);
+
+    (0..16).for_each(|i| emu.poke(0x0300 + i, 0xF0 | i as u8));
+    emu.set_index(0x0300);
+    emu.set_register(0x4, 112);
+    execute(&mut emu, 0xF002);
+    execute(&mut emu, 0xF43A);
+
+    let expected: [u8; 16] = std::array::from_fn(|i| 0xF0 | i as u8);
+    assert_eq!(emu.audio_pattern(), Some(&expected));
+    assert_eq!(emu.pitch(), 112);
+
+    emu.reset();
+    assert!(emu.audio_pattern().is_none());
+    assert_eq!(emu.pitch(), 64);
+}
+
+// --- arithmetic -------------------------------------------------------------
+
+#[test]
+fn add_byte_wraps_without_touching_vf() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0xFF);
+    execute(&mut emu, 0x7102);
+
+    assert_eq!(emu.register(0x1), 0x01);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+#[test]
+fn add_registers_sets_the_carry() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0xF0);
+    emu.set_register(0x2, 0x20);
+    execute(&mut emu, 0x8124);
+
+    assert_eq!(emu.register(0x1), 0x10);
+    assert_eq!(emu.register(0xF), 1);
+
+    execute(&mut emu, 0x8124);
+    assert_eq!(emu.register(0x1), 0x30);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+#[test]
+fn add_index_adds_the_register() {
+    let mut emu = emulator();
+    emu.set_index(0x0300);
+    emu.set_register(0x1, 0x20);
+    execute(&mut emu, 0xF11E);
+
+    assert_eq!(emu.index(), 0x0320);
+}
+
+#[test]
+fn index_overflow_quirk_sets_vf() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        index_overflow: true,
+        ..Quirks::default()
+    });
+    emu.set_index(0x0FF0);
+    emu.set_register(0x1, 0x10);
+    execute(&mut emu, 0xF11E);
+
+    assert_eq!(emu.index(), 0x1000);
+    assert_eq!(emu.register(0xF), 1);
+
+    emu.set_index(0x0300);
+    execute(&mut emu, 0xF11E);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+#[test]
+fn sub_sets_not_borrow() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0x30);
+    emu.set_register(0x2, 0x10);
+    execute(&mut emu, 0x8125);
+
+    assert_eq!(emu.register(0x1), 0x20);
+    assert_eq!(emu.register(0xF), 1);
+
+    emu.set_register(0x1, 0x00);
+    execute(&mut emu, 0x8125);
+    assert_eq!(emu.register(0x1), 0xF0);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+#[test]
+fn subn_sets_not_borrow() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0x10);
+    emu.set_register(0x2, 0x30);
+    execute(&mut emu, 0x8127);
+
+    assert_eq!(emu.register(0x1), 0x20);
+    assert_eq!(emu.register(0xF), 1);
+
+    emu.set_register(0x1, 0x40);
+    execute(&mut emu, 0x8127);
+    assert_eq!(emu.register(0x1), 0xF0);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+#[test]
+fn sub_of_equal_values_does_not_borrow() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0x30);
+    emu.set_register(0x2, 0x30);
+    execute(&mut emu, 0x8125);
+    assert_eq!(emu.register(0x1), 0x00);
+    assert_eq!(emu.register(0xF), 1);
+
+    emu.set_register(0x1, 0x30);
+    execute(&mut emu, 0x8127);
+    assert_eq!(emu.register(0x1), 0x00);
+    assert_eq!(emu.register(0xF), 1);
+}
+
+#[test]
+fn arithmetic_on_vf_keeps_the_flag() {
+    let mut emu = emulator();
+    emu.set_register(0xF, 0xF0);
+    emu.set_register(0x1, 0x20);
+    execute(&mut emu, 0x8F14);
+    assert_eq!(emu.register(0xF), 1);
+
+    emu.set_register(0xF, 0x10);
+    execute(&mut emu, 0x8F15);
+    assert_eq!(emu.register(0xF), 0);
+
+    emu.set_register(0xF, 0x02);
+    execute(&mut emu, 0x8FF6);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+#[test]
+fn shr_shifts_out_the_lowest_bit() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0b0000_0101);
+    emu.set_register(0x2, 0b1000_0000);
+    execute(&mut emu, 0x8126);
+
+    assert_eq!(emu.register(0x1), 0b0000_0010);
+    assert_eq!(emu.register(0xF), 1);
+}
+
+#[test]
+fn shl_shifts_out_the_highest_bit() {
+    let mut emu = emulator();
+    emu.set_register(0x1, 0b1000_0001);
+    emu.set_register(0x2, 0b0000_0001);
+    execute(&mut emu, 0x812E);
+
+    assert_eq!(emu.register(0x1), 0b0000_0010);
+    assert_eq!(emu.register(0xF), 1);
+}
+
+#[test]
+fn shift_quirk_shifts_vy() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        shift_vy: true,
+        ..Quirks::default()
+    });
+    emu.set_register(0x1, 0xFF);
+    emu.set_register(0x2, 0b0000_0100);
+
+    execute(&mut emu, 0x8126);
+    assert_eq!(emu.register(0x1), 0b0000_0010);
+    assert_eq!(emu.register(0xF), 0);
+
+    execute(&mut emu, 0x812E);
+    assert_eq!(emu.register(0x1), 0b0000_1000);
+    assert_eq!(emu.register(0xF), 0);
+}
+
+// --- logic ------------------------------------------------------------------
+
+#[test]
+fn or_and_xor_combine_registers() {
+    let mut emu = emulator();
+    emu.set_register(0x2, 0b1100);
+
+    emu.set_register(0x1, 0b1010);
+    execute(&mut emu, 0x8121);
+    assert_eq!(emu.register(0x1), 0b1110);
+
+    emu.set_register(0x1, 0b1010);
+    execute(&mut emu, 0x8122);
+    assert_eq!(emu.register(0x1), 0b1000);
+
+    emu.set_register(0x1, 0b1010);
+    execute(&mut emu, 0x8123);
+    assert_eq!(emu.register(0x1), 0b0110);
+}
+
+#[test]
+fn vf_reset_quirk_clears_vf() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        vf_reset: true,
+        ..Quirks::default()
+    });
+
+    for raw in [0x8121, 0x8122, 0x8123] {
+        emu.set_register(0xF, 1);
+        execute(&mut emu, raw);
+        assert_eq!(emu.register(0xF), 0, "{raw:04X}");
+    }
+}
+
+#[test]
+fn rnd_masks_the_random_byte() {
+    let mut emu = emulator();
+    emu.set_random_source(ScriptedRandom::new(vec![0xAB]));
+    execute(&mut emu, 0xC10F);
+
+    assert_eq!(emu.register(0x1), 0x0B);
+}
+
+// --- display ----------------------------------------------------------------
+
+#[test]
+fn drw_draws_sprites_and_detects_collisions() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD005);
+
+    // The sprite of `0` has 14 pixels
+    assert_eq!(lit_pixels(&emu), 14);
+    assert_eq!(emu.register(0xF), 0);
+
+    execute(&mut emu, 0xD005);
+    assert_eq!(lit_pixels(&emu), 0);
+    assert_eq!(emu.register(0xF), 1);
+}
+
+#[test]
+fn collisions_are_counted_per_frame() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD005);
+    execute(&mut emu, 0xD005);
+
+    // The collisions only show once the frame has ended
+    assert_eq!(emu.collisions().count(), 0);
+    emu.tick_timers();
+    assert_eq!(emu.collisions().count(), 1);
+    assert_eq!(emu.collisions().pixels().len(), 14);
+
+    emu.tick_timers();
+    assert_eq!(emu.collisions().count(), 0);
+    assert!(emu.collisions().pixels().is_empty());
+}
+
+#[test]
+fn drw_clips_at_the_edges() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    emu.set_register(0x1, 62);
+    execute(&mut emu, 0xD125);
+
+    // Only the two leftmost columns of the sprite of `0` remain
+    assert_eq!(lit_pixels(&emu), 7);
+}
+
+#[test]
+fn wrap_quirk_wraps_sprites() {
+    let mut emu = emulator();
+    emu.set_quirks(Quirks {
+        wrap_sprites: true,
+        ..Quirks::default()
+    });
+    emu.set_index(FONT_ADDR);
+    emu.set_register(0x1, 62);
+    execute(&mut emu, 0xD125);
+
+    assert_eq!(lit_pixels(&emu), 14);
+}
+
+#[test]
+fn high_and_low_switch_the_resolution() {
+    let mut emu = emulator();
+    execute(&mut emu, 0x00FF);
+    assert_eq!((emu.screen_width(), emu.screen_height()), (128, 64));
+
+    execute(&mut emu, 0x00FE);
+    assert_eq!((emu.screen_width(), emu.screen_height()), (64, 32));
+}
+
+#[test]
+fn platforms_leave_out_the_opcodes_of_later_platforms() {
+    assert_eq!(Platform::of_opcode(0x00E0), Platform::Chip8);
+    assert_eq!(Platform::of_opcode(0x00FF), Platform::Schip);
+    assert_eq!(Platform::of_opcode(0xD120), Platform::Schip);
+    assert_eq!(Platform::of_opcode(0xF03A), Platform::XoChip);
+    assert!(Platform::XoChip.supports(0x00FF) && !Platform::Chip8.supports(0x00FF));
+
+    // `HIGH` is an unknown opcode on Chip-8, and raises an error if strict
+    let mut emu = Emulator::from_rom(&[0x00, 0xFF, 0x00, 0xFF]).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_platform(Some(Platform::Chip8));
+    emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.screen_width(), 64);
+
+    emu.set_strict(true);
+    assert!(matches!(emu.step(), Err(Keet8Error::UnknownOpcode(0x00FF, 0x202))));
+
+    emu.reset();
+    emu.set_platform(Some(Platform::Schip));
+    emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.screen_width(), 128);
+}
+
+#[test]
+fn packed_rows_fold_hires_pixels() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD015);
+    assert_eq!(emu.packed_rows()[0], 0xF000_0000_0000_0000);
+
+    execute(&mut emu, 0x00FF);
+    execute(&mut emu, 0xD015);
+    assert_eq!(emu.packed_rows()[0], 0xC000_0000_0000_0000);
+    assert_eq!(emu.packed_rows()[1], 0xC000_0000_0000_0000);
+}
+
+#[test]
+fn two_page_roms_run_on_a_64x64_screen() {
+    // `JP 0x260` into the patch of the interpreter, which starts the program
+    // at 0x2C0
+    let mut rom = vec![0; 0xC0];
+    rom[..2].copy_from_slice(&[0x12, 0x60]);
+    assert!(is_two_page_rom(&rom));
+
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    emu.set_two_page(true);
+    assert_eq!(emu.program_counter(), TWO_PAGE_ENTRY);
+    assert_eq!(emu.resolution(), Resolution::TwoPage);
+    assert_eq!((emu.screen_width(), emu.screen_height()), (LORES_WIDTH, TWO_PAGE_HEIGHT));
+
+    // A sprite drawn on the second page, at y = 40
+    emu.set_index(FONT_ADDR);
+    emu.set_register(0x1, 40);
+    execute(&mut emu, 0xD015);
+    assert_eq!(emu.packed_rows()[20], 0xF000_0000_0000_0000);
+    assert_eq!(lit_pixels(&emu), 14);
+
+    execute(&mut emu, 0x0230);
+    assert_eq!(lit_pixels(&emu), 0);
+
+    execute(&mut emu, 0x00FF);
+    execute(&mut emu, 0x00FE);
+    assert_eq!(emu.resolution(), Resolution::TwoPage);
+
+    emu.set_two_page(false);
+    assert_eq!(emu.program_counter(), PROG_ADDR);
+    assert_eq!(emu.resolution(), Resolution::Low);
+}
+
+#[test]
+fn frame_presents_the_screen_at_full_resolution() {
+    struct Recorder(Vec<(usize, usize)>);
+
+    impl Renderer for Recorder {
+        fn present(&mut self, fb: &FrameBuffer) {
+            self.0 = fb.lit_pixels().collect();
+        }
+    }
+
+    let mut emu = emulator();
+    execute(&mut emu, 0x00FF);
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD011);
+
+    let frame = emu.frame();
+    assert_eq!((frame.width(), frame.height()), (HIRES_WIDTH, HIRES_HEIGHT));
+    assert_eq!(frame.iter().count(), HIRES_WIDTH * HIRES_HEIGHT);
+    assert_eq!(frame.iter().nth(HIRES_WIDTH + 3), Some((3, 1, false)));
+    assert!(frame.is_on(0, 0) && !frame.is_on(4, 0) && !frame.is_on(128, 0));
+
+    let mut recorder = Recorder(Vec::new());
+    recorder.present(&emu.frame());
+    assert_eq!(recorder.0, [(0, 0), (1, 0), (2, 0), (3, 0)]);
+}
+
+#[test]
+fn scd_scrolls_down() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD001);
+    execute(&mut emu, 0x00C2);
+
+    let width = emu.screen_width();
+    assert_eq!(emu.framebuffer()[..2 * width].iter().filter(|&&p| p > 0).count(), 0);
+    assert!(emu.framebuffer()[2 * width] > 0);
+}
+
+#[test]
+fn scr_and_scl_scroll_sideways() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD001);
+
+    execute(&mut emu, 0x00FB);
+    assert_eq!(emu.framebuffer()[..4].iter().filter(|&&p| p > 0).count(), 0);
+    assert!(emu.framebuffer()[4] > 0);
+
+    execute(&mut emu, 0x00FC);
+    assert!(emu.framebuffer()[0] > 0);
+}
+
+#[test]
+fn pixels_are_packed_as_bits() {
+    let mut emu = emulator();
+    emu.set_index(FONT_ADDR);
+    execute(&mut emu, 0xD001);
+
+    let pixels = emu.pixels();
+    assert_eq!(pixels.row(0), 0xF0 << 120);
+    assert!(pixels.get(3, 0) && !pixels.get(4, 0) && !pixels.get(0, 1));
+    assert_eq!(pixels.count_on(), 4);
+
+    let mut copy = *pixels;
+    assert!(copy.toggle(0, 0));
+    assert!(!copy.toggle(200, 0));
+    copy.set(5, 1, true);
+    assert_eq!(copy.rows(2).collect::<Vec<u128>>(), [0x70 << 120, 1 << 122]);
+    assert_eq!(Pixels::default(), Pixels::new());
+
+    // Scrolling right in the low resolution mode drops the pixels past its
+    // right edge rather than keeping them for the high resolution mode
+    execute(&mut emu, 0x00E0);
+    emu.set_register(0, 60);
+    execute(&mut emu, 0xD001);
+    execute(&mut emu, 0x00FB);
+    assert_eq!(emu.pixels().count_on(), 0);
+}
+
+// --- assembler --------------------------------------------------------------
+
+#[test]
+fn assemble_round_trips_the_disassembly() {
+    for raw in [
+        0x00E0, 0x00EE, 0x00C3, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x00FF, 0x1234, 0x2345, 0x3A12,
+        0x4B34, 0x5120, 0x6C56, 0x7D78, 0x8120, 0x8231, 0x8342, 0x8453, 0x8564, 0x8675, 0x8786,
+        0x889E, 0x9AB0, 0xA123, 0xB456, 0xC7FF, 0xD12F, 0xF307, 0xF40A, 0xF515, 0xF618, 0xF71E,
+        0xF175, 0xF285, 0xF002, 0xF43A, 0xE69E, 0xE7A1,
+    ] {
+        let text = OpCode::from(raw).to_string();
+        let assembled = assemble(&text).unwrap_or_else(|e| panic!("{raw:04X}: {e}"));
+        assert_eq!(assembled, raw, "{text}");
+    }
+}
+
+#[test]
+fn assemble_names_the_ambiguous_loads() {
+    let assembled = ["ld f v1", "ld hf v2", "ld b v3", "ld [i] v4", "ld v5 [i]", "skp v6", "sknp v7"]
+        .map(|text| assemble(text).unwrap_or_else(|e| panic!("{text}: {e}")));
+
+    assert_eq!(assembled, [0xF129, 0xF230, 0xF333, 0xF455, 0xF565, 0xE69E, 0xE7A1]);
+}
+
+#[test]
+fn assemble_rejects_invalid_instructions() {
+    for text in ["", "jp", "jp 0x1000", "ld v0 256", "drw v0 v1 16", "ld vg 1", "mov v0 v1"] {
+        assert!(assemble(text).is_err(), "{text}");
+    }
+}
+
+#[test]
+fn assemble_program_resolves_labels_and_data() {
+    let source = "
+        start:  ; Draws the sprite forever
+            ld i sprite
+            DRW v0, v1, 2
+            jp start
+        sprite: db 0xFF 129
+    ";
+
+    let rom = assemble_program(source).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(rom, [0xA2, 0x06, 0xD0, 0x12, 0x12, 0x00, 0xFF, 0x81]);
+}
+
+#[test]
+fn assemble_program_reports_the_failing_line() {
+    let Err(Keet8Error::InvalidAssembly(text)) = assemble_program("cls\njp nowhere") else {
+        panic!("an unknown label should fail");
+    };
+    assert_eq!(text, "line 2: jp nowhere");
+}
+
+#[test]
+fn assemble_program_expands_constants_macros_and_directives() {
+    let source = "
+        speed equ 2
+        macro move reg
+            add reg, speed
+        endm
+
+        loop:
+            ld i sprite
+            drw v0 v1 2
+            move v0
+            jp loop
+        sprite:
+            sprite #......# ########
+            dw 0x1234 speed
+            db 0b1010
+    ";
+
+    let rom = assemble_program(source).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(
+        rom,
+        [0xA2, 0x08, 0xD0, 0x12, 0x70, 0x02, 0x12, 0x00, 0x81, 0xFF, 0x12, 0x34, 0x00, 0x02, 0x0A]
+    );
+}
+
+#[test]
+fn assemble_program_checks_macro_arguments() {
+    let source = "macro move reg\nadd reg 1\nendm\ncls\nmove v0 v1";
+    let Err(Keet8Error::InvalidAssembly(text)) = assemble_program(source) else {
+        panic!("a macro given too many arguments should fail");
+    };
+    assert_eq!(text, "line 5: move v0 v1");
+}
+
+#[test]
+fn assemble_octo_jumps_to_main_and_resolves_blocks() {
+    let source = "
+        : draw  # Draws the sprite
+            i := sprite
+            sprite v0 v1 2
+        ;
+        : main
+            v0 := 3
+            loop
+                v0 -= 1
+                if v0 == 1 then draw
+                while v0 != 0
+            again
+        : sprite 0xFF 0x81
+    ";
+
+    let rom = assemble_octo(source).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(
+        rom,
+        [
+            0x12, 0x08, 0xA2, 0x16, 0xD0, 0x12, 0x00, 0xEE, 0x60, 0x03, 0x70, 0xFF, 0x40, 0x01,
+            0x22, 0x02, 0x40, 0x00, 0x12, 0x16, 0x12, 0x0A, 0xFF, 0x81,
+        ]
+    );
+}
+
+#[test]
+fn assemble_octo_reports_the_failing_line() {
+    let Err(Keet8Error::InvalidAssembly(text)) = assemble_octo(": main\n  v0 *= 2") else {
+        panic!("an unknown operator should fail");
+    };
+    assert_eq!(text, "line 2: *=");
+}
+
+#[test]
+fn patch_writes_the_opcode_without_self_modifying() {
+    let mut emu = Emulator::from_rom(&[0x00, 0xE0]).unwrap_or_else(|e| panic!("{e}"));
+    emu.patch(PROG_ADDR, 0x6042);
+    assert_eq!(emu.fetch(PROG_ADDR), 0x6042);
+
+    emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.register(0), 0x42);
+    assert_eq!(emu.self_modified().count(), 0);
+
+    emu.reset();
+    assert_eq!(emu.fetch(PROG_ADDR), 0x00E0);
+}
+
+// --- breakpoints ------------------------------------------------------------
+
+#[test]
+fn step_stops_at_breakpoints_once() {
+    // LD V0, 1; LD V0, 2; JP 0x0202
+    let rom = [0x60, 0x01, 0x60, 0x02, 0x12, 0x02];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    emu.add_breakpoint(PROG_ADDR + 2);
+
+    let step = |emu: &mut Emulator| emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(step(&mut emu), StepOutcome::Executed);
+    assert_eq!(step(&mut emu), StepOutcome::Breakpoint(PROG_ADDR + 2));
+    assert_eq!(emu.register(0), 1);
+
+    // The breakpoint is executed on the next step, and hit again on the way
+    // back around the loop
+    assert_eq!(step(&mut emu), StepOutcome::Executed);
+    assert_eq!(emu.register(0), 2);
+    assert_eq!(step(&mut emu), StepOutcome::Executed);
+    assert_eq!(step(&mut emu), StepOutcome::Breakpoint(PROG_ADDR + 2));
+
+    assert!(emu.remove_breakpoint(PROG_ADDR + 2));
+    assert_eq!(emu.breakpoints().count(), 0);
+}
+
+#[test]
+fn step_reports_watched_accesses() {
+    // LD I, 0x300; LD B, V0; LD V1, [I]; LD V2, 7
+    let rom = [0xA3, 0x00, 0xF0, 0x33, 0xF1, 0x65, 0x62, 0x07];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    emu.add_watchpoint(0x0301);
+    emu.add_register_watchpoint(0x2);
+
+    let step = |emu: &mut Emulator| emu.step().unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(step(&mut emu), StepOutcome::Executed);
+    assert_eq!(step(&mut emu), StepOutcome::Watchpoint(WatchHit::Write(0x0301)));
+    assert_eq!(step(&mut emu), StepOutcome::Watchpoint(WatchHit::Read(0x0301)));
+    assert_eq!(step(&mut emu), StepOutcome::Watchpoint(WatchHit::Register(0x2)));
+    assert_eq!(emu.register(0x2), 7);
+}
+
+// --- fuzzing ----------------------------------------------------------------
+
+#[test]
+fn history_keeps_the_last_instructions_up_to_the_failing_one() {
+    let mut emu = Emulator::from_rom(&[0x60, 0x01, 0x12, 0x06, 0x00, 0x00, 0x00, 0xEE])
+        .unwrap_or_else(|e| panic!("{e}"));
+    assert!(emu.run_steps(10).is_err());
+
+    let history: Vec<(u16, u16)> = emu.history().collect();
+    assert_eq!(history, [(0x200, 0x6001), (0x202, 0x1206), (0x206, 0x00EE)]);
+
+    // Only the most recent instructions are kept, and a reset forgets them
+    let mut emu = Emulator::from_rom(&[0x12, 0x00]).unwrap_or_else(|e| panic!("{e}"));
+    emu.run_steps(100).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(emu.history().count(), 32);
+    emu.reset();
+    assert_eq!(emu.history().count(), 0);
+}
+
+#[test]
+fn run_steps_loads_any_bytes_and_stops_at_the_limit() {
+    // Bytes past the end of memory are cut off rather than rejected
+    let mut bytes = vec![0x12, 0x00];
+    bytes.resize(8192, 0xFF);
+    let mut emu = Emulator::from_bytes(&bytes);
+
+    let executed = emu.run_steps(25).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(executed, 25);
+    assert_eq!(emu.memory()[0xFFF], 0xFF);
+
+    // EXIT stops the loop early
+    let mut emu = Emulator::from_bytes(&[0x60, 0x01, 0x00, 0xFD, 0x60, 0x02]);
+    let executed = emu.run_steps(25).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(executed, 2);
+    assert_eq!(emu.register(0), 1);
+}
+
+// --- frames -----------------------------------------------------------------
+
+#[test]
+fn step_frame_runs_a_batch_and_ticks_the_timers_once() {
+    // V0 = 5, ST = V0, draw the font sprite of `0`, then loop forever
+    let rom = [0x60, 0x05, 0xF0, 0x18, 0xA0, 0x50, 0xD0, 0x05, 0x12, 0x08];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+
+    let summary = emu.step_frame(10);
+    assert_eq!(summary.executed, 10);
+    assert_eq!(summary.outcome, StepOutcome::Executed);
+    assert!(summary.drawn && summary.sound && summary.error.is_none());
+    assert_eq!(emu.sound_timer(), 4);
+
+    // Only looping leaves the screen as it was
+    let summary = emu.step_frame(10);
+    assert!(!summary.drawn && summary.sound);
+    assert_eq!(emu.sound_timer(), 3);
+}
+
+#[test]
+fn step_frame_stops_early_without_ticking_the_timers() {
+    // ST = V0, draw the font sprite of `0`, then `RET` with an empty stack
+    let rom = [0x60, 0x05, 0xF0, 0x18, 0xA0, 0x50, 0xD0, 0x05, 0x00, 0xEE];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+
+    let summary = emu.step_frame(10);
+    assert_eq!(summary.executed, 4);
+    assert!(summary.drawn);
+    let cause = summary.error.as_ref().map(Keet8Error::root_cause);
+    assert!(matches!(cause, Some(Keet8Error::CallStackEmpty)));
+    assert_eq!(emu.sound_timer(), 5);
+
+    // A breakpoint pauses the frame before the instruction
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    emu.add_breakpoint(0x204);
+    let summary = emu.step_frame(10);
+    assert_eq!(summary.executed, 2);
+    assert_eq!(summary.outcome, StepOutcome::Breakpoint(0x204));
+    assert!(!summary.drawn && summary.error.is_none());
+    assert_eq!(emu.sound_timer(), 5);
+}
+
+// --- save states ------------------------------------------------------------
+
+#[test]
+fn save_states_only_load_into_their_rom_and_version() {
+    let rom = [0x60, 0x2A, 0x12, 0x02];
+    let mut emu = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    emu.run_steps(1).unwrap_or_else(|e| panic!("{e}"));
+    let state = emu.save_state();
+
+    let mut restored = Emulator::from_rom(&rom).unwrap_or_else(|e| panic!("{e}"));
+    restored.load_state(&state).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(restored.register(0), 0x2A);
+
+    // Another ROM, a damaged byte or an older format are refused untouched
+    let mut other = Emulator::from_rom(&[0x12, 0x00]).unwrap_or_else(|e| panic!("{e}"));
+    let result = other.load_state(&state);
+    let hash = emu.rom_hash();
+    assert!(matches!(result, Err(Keet8Error::SaveStateRomMismatch(h)) if h == hash));
+    assert_eq!(other.register(0), 0);
+
+    let mut damaged = state.clone();
+    damaged[0x100] ^= 0x01;
+    assert!(matches!(restored.load_state(&damaged), Err(Keet8Error::InvalidSaveState)));
+    assert!(matches!(
+        restored.load_state(&state[..state.len() - 1]),
+        Err(Keet8Error::InvalidSaveState)
+    ));
+
+    let mut legacy = b"K8S4".to_vec();
+    legacy.extend_from_slice(&state[14..]);
+    let result = restored.load_state(&legacy);
+    assert!(matches!(result, Err(Keet8Error::UnsupportedSaveStateVersion(4))));
+
+    // Without the ROM, the state is loaded as is
+    let inspected = Emulator::from_state(&state).unwrap_or_else(|e| panic!("{e}"));
+    assert_eq!(inspected.register(0), 0x2A);
+    assert_eq!(inspected.rom_hash(), emu.rom_hash());
+}